@@ -1,3 +1,8 @@
+import java.util.List;
+import java.util.Map;
+import java.util.concurrent.CompletableFuture;
+import java.util.function.BiConsumer;
+
 public class TestRedis {
     static {
         // Load the Rust shared library (.so/.dll/.dylib depending on your OS)
@@ -10,6 +15,30 @@ public class TestRedis {
     public native String executeSetCommand(long setCommandPtr, long connPtr);
     public native void freeRedisConnection(long connPtr);
     public native void freeSetCommand(long setCommandPtr);
+    public native long createGetCommand(String key);
+    public native void freeGetCommand(long getCommandPtr);
+
+    // The value of the key, null when it does not exist
+    public native String queryGetCommand(long getCommandPtr, long connPtr);
+
+    // Any command, the reply as a List, Map, Long, Double, BigInteger, String, byte[] or null
+    public native Object executeCommand(long connPtr, String[] arguments);
+
+    // Asynchronous variant, the callback gets either the reply, as executeCommand returns it, or the error from a Rust thread
+    public native void executeSetCommandAsync(long setCommandPtr, long connPtr, BiConsumer<Object, Throwable> callback);
+
+    // Wraps the callback, the command and the connection must not be freed before the future completes
+    public CompletableFuture<Object> executeSetCommandFuture(long setCommandPtr, long connPtr) {
+        CompletableFuture<Object> future = new CompletableFuture<>();
+        executeSetCommandAsync(setCommandPtr, connPtr, (reply, error) -> {
+            if (error != null) {
+                future.completeExceptionally(error);
+            } else {
+                future.complete(reply);
+            }
+        });
+        return future;
+    }
 
     // Example usage
     public static void main(String[] args) {
@@ -25,7 +54,30 @@ public class TestRedis {
         String response = rustRedis.executeSetCommand(setCommand, conn);
         System.out.println("Response from Redis: " + response);
 
-        // Step 4: Free the memory (clean up)
+        // Step 4: Execute it again without blocking, then wait for the reply
+        Object asyncResponse = rustRedis.executeSetCommandFuture(setCommand, conn).join();
+        System.out.println("Async response from Redis: " + asyncResponse);
+
+        // Step 5: Read a key that does not exist, null rather than the raw nil reply
+        long getCommand = rustRedis.createGetCommand("missingkey");
+        String value = rustRedis.queryGetCommand(getCommand, conn);
+        System.out.println("Missing key is null: " + (value == null));
+
+        // Step 6: Any command, with its reply as Java collections and numbers. Its arguments are
+        // sent UPPERCASE like those of the commands above, so it reads the key they set
+        System.out.println("Value of mykey: " + rustRedis.executeCommand(conn, new String[] {"GET", "mykey"}));
+        rustRedis.executeCommand(conn, new String[] {"RPUSH", "mylist", "a", "b"});
+        List<?> list = (List<?>) rustRedis.executeCommand(conn, new String[] {"LRANGE", "mylist", "0", "-1"});
+        Long length = (Long) rustRedis.executeCommand(conn, new String[] {"LLEN", "mylist"});
+        System.out.println("List " + list + " of length " + length);
+        rustRedis.executeCommand(conn, new String[] {"HSET", "myhash", "field", "value"});
+        Object hash = rustRedis.executeCommand(conn, new String[] {"HGETALL", "myhash"});
+        // A Map over RESP3, a flat List of fields and values over RESP2
+        System.out.println("Hash " + (hash instanceof Map ? "map " : "list ") + hash);
+        rustRedis.executeCommand(conn, new String[] {"DEL", "mylist", "myhash"});
+
+        // Step 7: Free the memory (clean up)
+        rustRedis.freeGetCommand(getCommand);
         rustRedis.freeSetCommand(setCommand);
         rustRedis.freeRedisConnection(conn);
     }
@@ -0,0 +1,45 @@
+// benches/encode.rs
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+use resp3string::resp3::commands::eval::EvalCommand;
+use resp3string::resp3::commands::set::SetCommand;
+use resp3string::resp3::utils::command::Command;
+use resp3string::resp3::utils::prepared::PreparedCommand;
+
+fn encode_set(c: &mut Criterion) {
+    let command = SetCommand::new("bench:key".to_string(), "some value".to_string()).ex(60);
+
+    c.bench_function("set/format_resp_command", |b| b.iter(|| black_box(&command).format_resp_command()));
+    c.bench_function("set/format_resp_bytes", |b| b.iter(|| black_box(&command).format_resp_bytes()));
+
+    let mut buffer = Vec::new();
+    c.bench_function("set/encode_into", |b| b.iter(|| {
+        buffer.clear();
+        black_box(&command).encode_into(&mut buffer);
+    }));
+
+    let prepared = PreparedCommand::new("SET").argument().argument().token("EX").token("60");
+    c.bench_function("set/prepared", |b| b.iter(|| {
+        buffer.clear();
+        black_box(&prepared).encode_into(&mut buffer, &[b"bench:key", b"some value"]).unwrap();
+    }));
+}
+
+fn encode_eval(c: &mut Criterion) {
+    let keys = (0..16).map(|index| format!("bench:key:{}", index)).collect();
+    let args = (0..16).map(|index| format!("argument {}", index)).collect();
+    let command = EvalCommand::new("return redis.call('mget', unpack(KEYS))".to_string(), keys, args);
+
+    c.bench_function("eval/format_resp_command", |b| b.iter(|| black_box(&command).format_resp_command()));
+    c.bench_function("eval/format_resp_bytes", |b| b.iter(|| black_box(&command).format_resp_bytes()));
+
+    let mut buffer = Vec::new();
+    c.bench_function("eval/encode_into", |b| b.iter(|| {
+        buffer.clear();
+        black_box(&command).encode_into(&mut buffer);
+    }));
+}
+
+criterion_group!(benches, encode_set, encode_eval);
+criterion_main!(benches);
@@ -0,0 +1,30 @@
+// benches/load.rs
+//
+// Load against a running server, RESP3STRING_BENCH_ADDRESS (127.0.0.1:6379 by default) and
+// RESP3STRING_BENCH_REQUESTS (100000 by default) change the target and the size of the runs
+
+use std::env;
+
+use resp3string::resp3::bench::{LoadGenerator, Workload};
+
+fn main() {
+    let address = env::var("RESP3STRING_BENCH_ADDRESS").unwrap_or_else(|_| "127.0.0.1:6379".to_string());
+    let requests = env::var("RESP3STRING_BENCH_REQUESTS").ok().and_then(|requests| requests.parse().ok()).unwrap_or(100_000);
+
+    for workload in [Workload::Set, Workload::Get, Workload::Incr] {
+        for pipeline in [1, 16] {
+            let generator = LoadGenerator::new(&address, workload).requests(requests).pipeline(pipeline);
+            match generator.run() {
+                Ok(report) => println!("{}", report),
+                Err(err) => {
+                    eprintln!("{} against {} failed: {}", workload.as_str(), address, err);
+                    return;
+                }
+            }
+        }
+    }
+    println!("payload of 1 KiB:");
+    if let Ok(report) = LoadGenerator::new(&address, Workload::Set).requests(requests).payload_size(1024).run() {
+        println!("{}", report);
+    }
+}
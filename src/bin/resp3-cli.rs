@@ -0,0 +1,5 @@
+// src/bin/resp3-cli.rs
+
+fn main() {
+    resp3string::resp3::cli::main();
+}
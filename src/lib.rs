@@ -1,31 +1,195 @@
 // src/lib.rs
 
 pub mod resp3 {
+    pub mod analysis;
     pub mod bindings {
         #[cfg(feature = "java")]
         mod java_21;
         #[cfg(feature = "python")]
         mod python_3;
+        #[cfg(feature = "ruby")]
+        mod ruby_3;
     }
     pub mod commands {
+        pub mod acl;
+        pub mod admin;
+        pub mod bitmap;
+        pub mod client;
+        pub mod cluster;
+        pub mod config;
+        pub mod eval;
+        pub mod function;
+        pub mod geo;
         pub mod get;
+        pub mod hash;
+        pub mod hyperloglog;
+        pub mod incr;
+        pub mod keys;
+        pub mod list;
+        pub mod options;
+        pub mod pubsub;
+        pub mod replication;
+        pub mod server;
         pub mod set;
+        pub mod sets;
+        pub mod sorted_set;
+        pub mod stream;
     }
     pub mod utils {
+        pub mod auto_pipeline;
+        pub mod cached_executor;
+        pub mod codec;
         pub mod command;
         pub mod command_executor;
+        pub mod command_queue;
+        pub mod connection_events;
+        #[cfg(feature="lz4")]
+        pub mod compression;
+        pub mod connection_state;
+        #[cfg(feature="encryption")]
+        pub mod encryption;
+        pub mod journal;
+        pub mod key_prefix;
+        pub mod lint;
+        pub mod prepared;
+        pub mod proxy;
         pub mod redis_connection;
+        pub mod stats;
+        pub mod streaming_pipeline;
+        #[cfg(feature="tls")]
+        pub mod tls;
+        pub mod token;
+        pub mod transport;
+        pub mod validation;
+        pub mod value_guard;
     }
+    pub mod bench;
+    #[cfg(feature="blobs")]
+    pub mod blob_store;
+    pub mod cache;
+    #[cfg(feature="cli")]
+    pub mod cli;
+    pub mod cluster;
+    pub mod dispatcher;
+    pub mod error;
+    #[doc(hidden)]
+    pub mod fuzzing;
+    pub mod large_value;
+    pub mod leaderboard;
+    pub mod lock;
+    pub mod modules {
+        #[cfg(feature="modules-bloom")]
+        pub mod bloom;
+        #[cfg(feature="modules-json")]
+        pub mod json;
+        #[cfg(feature="modules-search")]
+        pub mod search;
+        #[cfg(feature="modules-timeseries")]
+        pub mod timeseries;
+    }
+    pub mod monitor;
+    #[cfg(feature="tokio")]
+    pub mod multiplexed;
+    pub mod notifications;
+    #[cfg(feature="otel")]
+    pub mod otel;
+    pub mod parser;
+    #[cfg(any(feature="deadpool", feature="bb8", feature="r2d2"))]
+    pub mod pool;
     pub mod protocol;
+    pub mod pubsub;
+    pub mod queue;
+    pub mod ratelimit;
+    pub mod scripts;
+    pub mod stream_consumer;
+    pub mod stream_producer;
+    #[cfg(feature="sessions")]
+    pub mod session;
+    pub mod testing;
+    #[cfg(feature="tokio")]
+    pub mod tokio_bridge;
+    pub mod value;
+    #[cfg(feature="wire-debug")]
+    pub mod wire_debug;
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::resp3::commands::get::GetCommand;
-    use crate::resp3::commands::set::SetCommand;
+    use crate::resp3::analysis::{glob_match, MemoryScanner, OTHER_GROUP};
+    use crate::resp3::bench::{LoadGenerator, Workload};
+    use crate::resp3::cache::Cache;
+    use crate::resp3::cluster::{command_slot, hash_tag, key_slot, slot_for_key, ClusterConnections, ScanProgress, SLOT_COUNT};
+    #[cfg(feature="cli")]
+    use crate::resp3::cli::{self, CliOptions};
+    use crate::resp3::commands::acl::{AclCatCommand, AclDelUserCommand, AclGetUserCommand, AclListCommand, AclSetUserCommand, AclWhoAmICommand, CommandRule, KeyPattern};
+    use crate::resp3::commands::admin::{BackgroundJob, BgRewriteAofCommand, BgSaveCommand, DbSizeCommand, FlushAllCommand, FlushDbCommand, FlushMode, LastSaveCommand, ShutdownCommand};
+    use crate::resp3::commands::bitmap::{BitCountCommand, BitOpCommand, BitOperation, BitPosCommand, GetBitCommand, SetBitCommand};
+    use crate::resp3::commands::client::{ClientFlag, ClientFlagCommand, ClientIdCommand, ClientInfo, ClientKillCommand, ClientListCommand, ClientType, HelloCommand, PingCommand};
+    use crate::resp3::commands::config::{ConfigGetCommand, ConfigResetStatCommand, ConfigSetCommand, LatencyHistoryCommand, LatencyLatestCommand, LatencyResetCommand, MemoryDoctorCommand, MemoryStatsCommand, SlowlogGetCommand, SlowlogLenCommand, SlowlogResetCommand};
+    use crate::resp3::commands::eval::{EvalCommand, EvalShaCommand, ScriptExistsCommand, ScriptFlushCommand, ScriptLoadCommand};
+    use crate::resp3::commands::function::{FCallCommand, FunctionDeleteCommand, FunctionListCommand, FunctionLoadCommand};
+    use crate::resp3::commands::geo::{GeoAddCommand, GeoDistCommand, GeoPosCommand, GeoSearchCommand, GeoUnit};
+    use crate::resp3::commands::get::{GetCommand, GetExCommand, LcsCommand, LcsIdxCommand, LcsLenCommand, LcsMatch, LcsMatches, MGetCommand};
+    use crate::resp3::commands::hash::{FieldExpiration, FieldTtl, HExpireCommand, HPExpireCommand, HPTtlCommand, HRandFieldCommand, HRandFieldsCommand, HRandFieldsWithValuesCommand, HTtlCommand};
+    use crate::resp3::commands::hyperloglog::{PfAddCommand, PfCountCommand, PfMergeCommand};
+    use crate::resp3::commands::incr::{IncrByFloatCommand, IncrCommand};
+    use crate::resp3::commands::keys::{CopyCommand, DelCommand, DumpCommand, ExpireTimeCommand, KeyExpiration, KeyTtl, KeysCommand, MemoryUsageCommand, ObjectFreqCommand, PExpireCommand, PExpireTimeCommand, PTtlCommand, PersistCommand, RestoreCommand, ScanCommand, TtlCommand, TypeCommand};
+    use crate::resp3::commands::list::{BlmPopCommand, LLenCommand, LPosCommand, LRangeCommand, LTrimCommand, ListEnd, LmPopCommand, PopCommand, PopCountCommand, PushCommand};
+    use crate::resp3::commands::pubsub::{PubSubChannelsCommand, PubSubNumPatCommand, PubSubNumSubCommand, PublishCommand};
+    use crate::resp3::commands::replication::{FailoverCommand, ReplicaOffset, Role, RoleCommand, ServerRole, WaitCommand};
+    use crate::resp3::commands::options::{ExpireCondition, Expiry, Sample, SetCondition, ToArguments};
+    use crate::resp3::commands::set::{MSetCommand, SetCommand};
+    use crate::resp3::commands::server::{BeginSearch, CommandDocsCommand, CommandInfoCommand, FindKeys};
+    use crate::resp3::commands::sets::{SAddCommand, SInterCardCommand, SMIsMemberCommand, SRandMemberCommand, SRandMembersCommand, SetIntersection, SetStoreCommand};
+    use crate::resp3::commands::sorted_set::{ScoreEnd, ZRandMemberCommand, ZRandMembersCommand, ZRandMembersWithScoresCommand, ZmPopCommand};
+    use crate::resp3::commands::stream::{PendingSummary, StreamTrim, XAddCommand, XLenCommand, XPendingCommand, XPendingRangeCommand, XRangeCommand, XTrimCommand};
+    use crate::resp3::error::{ErrorCode, Resp3Error, ServerError};
+    use crate::resp3::large_value::LargeValue;
+    use crate::resp3::leaderboard::{Leaderboard, LeaderboardEntry};
+    use crate::resp3::lock::{Lock, Redlock};
+    #[cfg(feature="modules-bloom")]
+    use crate::resp3::modules::bloom::{BfAddCommand, BfExistsCommand, BfMAddCommand, BfMExistsCommand, BfReserveCommand, CfAddCommand, CfCountCommand, CfDelCommand, CfExistsCommand, CfMExistsCommand, CfReserveCommand};
+    #[cfg(feature="modules-json")]
+    use crate::resp3::modules::json::{Json, JsonDelCommand, JsonGetCommand, JsonSetCommand};
+    #[cfg(feature="modules-search")]
+    use crate::resp3::modules::search::{AggregateResult, FieldType, FtAggregateCommand, FtCreateCommand, FtDropIndexCommand, FtSearchCommand, Reducer, SearchQuery};
+    #[cfg(feature="modules-timeseries")]
+    use crate::resp3::modules::timeseries::{TsAddCommand, TsCreateCommand, TsGetCommand, TsMRangeCommand, TsRangeCommand};
+    use crate::resp3::monitor::{Monitor, MonitorEvent};
+    use crate::resp3::notifications::{KeyspaceEventKind, KeyspaceNotifications, DEFAULT_EVENTS};
+    use crate::resp3::fuzzing;
+    use crate::resp3::dispatcher::{DispatchStats, Dispatcher};
+    use crate::resp3::pubsub::{PubSub, PubSubEvent};
+    use crate::resp3::queue::ReliableQueue;
+    use crate::resp3::parser::{parse_frame, parse_frame_with_attributes, parse_response, FrameScanner, MAX_NESTING};
+    use crate::resp3::protocol::testvectors::{self, Outcome};
     use crate::resp3::protocol::{BULK_STRING_PREFIX, CRLF};
-    use crate::resp3::utils::command::Command;
-    use crate::resp3::utils::redis_connection::RedisConnection;
+    use crate::resp3::ratelimit::{FixedWindowLimiter, SlidingWindowLimiter};
+    use crate::resp3::scripts::{self, BOUNDED_PUSH, GET_AND_EXPIRE, TOKEN_BUCKET};
+    use crate::resp3::stream_consumer::{ConsumerStats, StreamConsumer};
+    use crate::resp3::stream_producer::StreamProducer;
+    use crate::resp3::testing::{ChaosConfig, Fault, MockServer};
+    use crate::resp3::utils::auto_pipeline::AutoPipeline;
+    use crate::resp3::utils::cached_executor::CachedExecutor;
+    use crate::resp3::utils::command::{parse_inline, Command, EncodingMode, ReplyKind, TypedCommand};
+    use crate::resp3::utils::prepared::PreparedCommand;
+    use crate::resp3::utils::codec::ValueCodec;
+    use crate::resp3::utils::command_executor::CommandExecutor;
+    use crate::resp3::utils::stats::{ServerHints, SizeHistogram, StatsSnapshot};
+    use crate::resp3::utils::command_queue::CommandQueue;
+    use crate::resp3::utils::journal::Journal;
+    use crate::resp3::utils::streaming_pipeline::StreamingPipeline;
+    use crate::resp3::utils::validation::{Utf8Policy, Validation};
+    use crate::resp3::utils::value_guard::{value_indices, ValueGuard};
+    use crate::resp3::utils::key_prefix::KeyPrefix;
+    use crate::resp3::utils::lint::{Lint, Severity};
+    use crate::resp3::utils::proxy::Proxy;
+    use crate::resp3::utils::connection_state::{command_name, ConnectionState};
+    use crate::resp3::utils::redis_connection::{IpPreference, ProtocolVersion, RedisConnection, RedisConnectionBuilder};
+    use crate::resp3::value::{FromResp3Value, Resp3Map, Resp3Value};
+    #[cfg(feature="wire-debug")]
+    use crate::resp3::wire_debug::{describe_reply, describe_request, redact_command, redact_key};
+    use std::time::Duration;
 
     #[test]
     fn a_test_set_command() {
@@ -81,4 +245,3612 @@ mod tests {
         // Step 5: Assert that the GetCommand response is the expected value
         assert_eq!(get_response.trim(), String::new() + BULK_STRING_PREFIX + &test_value.len().to_string() + CRLF + &test_value.to_uppercase());
     }
+
+    #[test]
+    fn test_lock_acquire_and_release() {
+        // Step 1: Set up the Redis connection (ensure Redis is running on localhost:6379)
+        let mut conn = RedisConnection::new("127.0.0.1:6379");
+
+        // Step 2: Acquire the lock, a second attempt must fail while it is held
+        let lock = Lock::acquire(&mut conn, "test_lock", Duration::from_secs(10)).expect("Lock should be acquired");
+        assert!(Lock::acquire(&mut conn, "test_lock", Duration::from_secs(10)).is_none());
+
+        // Step 3: Release the lock, releasing it twice must fail
+        assert!(lock.release(&mut conn));
+        assert!(!lock.release(&mut conn));
+
+        // Step 4: The lock can be acquired again once released
+        let lock = Lock::acquire(&mut conn, "test_lock", Duration::from_secs(10)).expect("Lock should be acquired");
+        assert!(lock.release(&mut conn));
+    }
+
+    #[test]
+    fn test_redlock_acquire_extend_and_release() {
+        // Step 1: Set up the Redlock instances (ensure Redis is running on localhost:6379)
+        let mut redlock = Redlock::new(vec![RedisConnection::new("127.0.0.1:6379")]).with_retry(0, Duration::from_millis(10));
+
+        // Step 2: Acquire the lock, the validity accounts for the clock drift
+        let mut lock = redlock.acquire("test_redlock", Duration::from_secs(10)).expect("Lock should be acquired");
+        assert!(lock.validity() < Duration::from_secs(10));
+        assert!(redlock.acquire("test_redlock", Duration::from_secs(10)).is_none());
+
+        // Step 3: Extend and release the lock
+        assert!(redlock.extend(&mut lock, Duration::from_secs(20)));
+        assert!(lock.validity() > Duration::from_secs(10));
+        assert!(redlock.release(&lock));
+        assert!(!redlock.extend(&mut lock, Duration::from_secs(20)));
+    }
+
+    #[test]
+    fn test_parse_response() {
+        // Step 1: Parse scalar replies
+        assert_eq!(parse_response(b"+OK\r\n").unwrap(), Resp3Value::SimpleString("OK".to_string()));
+        assert_eq!(parse_response(b"-ERR unknown\r\n").unwrap(), Resp3Value::Error("ERR unknown".to_string()));
+        assert_eq!(parse_response(b":-42\r\n").unwrap(), Resp3Value::Integer(-42));
+        assert_eq!(parse_response(b"$5\r\nhe\r\no\r\n").unwrap(), Resp3Value::BulkString(b"he\r\no".to_vec()));
+        assert_eq!(parse_response(b"$-1\r\n").unwrap(), Resp3Value::Null);
+
+        // Step 2: Parse nested arrays
+        assert_eq!(
+            parse_response(b"*2\r\n:1\r\n*1\r\n$1\r\na\r\n").unwrap(),
+            Resp3Value::Array(vec![Resp3Value::Integer(1), Resp3Value::Array(vec![Resp3Value::BulkString(b"a".to_vec())])])
+        );
+
+        // Step 3: Incomplete frames need more bytes, malformed ones are errors
+        assert!(parse_frame(b"*2\r\n:1\r\n").unwrap().is_none());
+        assert!(parse_frame(b"$5\r\nhel").unwrap().is_none());
+        assert_eq!(parse_frame(b":1\r\n:2\r\n").unwrap(), Some((Resp3Value::Integer(1), 4)));
+        assert!(parse_response(b"?1\r\n").is_err());
+        assert!(parse_response(b"$3\r\nabcd\r\n").is_err());
+
+        // Step 4: Fed a byte at a time, the scanner sees each frame whole exactly when the parser does
+        let frames: [&[u8]; 7] = [
+            b"*3\r\n:1\r\n*2\r\n$1\r\na\r\n*0\r\n$-1\r\n",
+            b"~3\r\n#t\r\n!5\r\nERR x\r\n~0\r\n",
+            b"%2\r\n+a\r\n*-1\r\n$2\r\nbc\r\n=8\r\ntxt:d\r\ne\r\n",
+            b"|1\r\n+ttl\r\n:10\r\n*2\r\n|0\r\n:1\r\n>1\r\n_\r\n",
+            b"$10\r\n*1\r\n$1\r\nab\r\n",
+            b"*1\r\n%0\r\n",
+            b",1.5\r\n",
+        ];
+        for frame in frames {
+            let mut scanner = FrameScanner::default();
+            for end in 1..=frame.len() {
+                assert_eq!(scanner.is_complete(&frame[..end]), parse_frame(&frame[..end]).unwrap().is_some(), "{:?}", &frame[..end]);
+            }
+        }
+        assert!(FrameScanner::default().is_complete(b"*x\r\n"));
+
+        // Step 5: RESP3 sets, booleans and blob errors read as the arrays, integers and errors of RESP2
+        let members = parse_response(b"~2\r\n$1\r\na\r\n$1\r\nb\r\n").unwrap();
+        assert_eq!(Vec::<String>::from_resp3_value(members).unwrap(), vec!["a", "b"]);
+        assert!(bool::from_resp3_value(parse_response(b"#t\r\n").unwrap()).unwrap());
+        assert!(!bool::from_resp3_value(parse_response(b"#f\r\n").unwrap()).unwrap());
+        assert_eq!(parse_response(b"!10\r\nERR a\r\nb c\r\n").unwrap(), Resp3Value::Error("ERR a\r\nb c".to_string()));
+        assert!(parse_response(b"#x\r\n").is_err());
+    }
+
+    #[test]
+    fn test_fixed_window_limiter() {
+        // Step 1: Set up the Redis connection (ensure Redis is running on localhost:6379)
+        let mut conn = RedisConnection::new("127.0.0.1:6379");
+        let del_command = EvalCommand::new("return redis.call('del', KEYS[1])".to_string(), vec!["test_fixed_window".to_string()], vec![]);
+        del_command.execute(&mut conn).unwrap();
+
+        // Step 2: The first requests are allowed until the limit is reached
+        for expected_remaining in (0..3).rev() {
+            let decision = FixedWindowLimiter::check(&mut conn, "test_fixed_window", 3, Duration::from_secs(60)).unwrap();
+            assert!(decision.allowed);
+            assert_eq!(decision.remaining, expected_remaining);
+        }
+
+        // Step 3: Requests over the limit are rejected until the window resets
+        let decision = FixedWindowLimiter::check(&mut conn, "test_fixed_window", 3, Duration::from_secs(60)).unwrap();
+        assert!(!decision.allowed);
+        assert_eq!(decision.remaining, 0);
+        assert!(decision.reset_at > std::time::SystemTime::now());
+    }
+
+    #[test]
+    fn test_sliding_window_limiter() {
+        // Step 1: Set up the Redis connection (ensure Redis is running on localhost:6379)
+        let mut conn = RedisConnection::new("127.0.0.1:6379");
+        let del_command = EvalCommand::new("return redis.call('del', KEYS[1])".to_string(), vec!["test_sliding_window".to_string()], vec![]);
+        del_command.execute(&mut conn).unwrap();
+
+        // Step 2: Two requests fit in the window, the third one does not
+        assert!(SlidingWindowLimiter::check(&mut conn, "test_sliding_window", 2, Duration::from_millis(300)).unwrap().allowed);
+        assert!(SlidingWindowLimiter::check(&mut conn, "test_sliding_window", 2, Duration::from_millis(300)).unwrap().allowed);
+        assert!(!SlidingWindowLimiter::check(&mut conn, "test_sliding_window", 2, Duration::from_millis(300)).unwrap().allowed);
+
+        // Step 3: Once the window slid past the first requests, new ones are allowed again
+        std::thread::sleep(Duration::from_millis(350));
+        let decision = SlidingWindowLimiter::check(&mut conn, "test_sliding_window", 2, Duration::from_millis(300)).unwrap();
+        assert!(decision.allowed);
+        assert_eq!(decision.remaining, 1);
+    }
+
+    #[test]
+    fn test_geo_commands() {
+        // Step 1: Set up the Redis connection (ensure Redis is running on localhost:6379)
+        let mut conn = RedisConnection::new("127.0.0.1:6379");
+        let del_command = EvalCommand::new("return redis.call('del', KEYS[1])".to_string(), vec!["test_geo".to_string()], vec![]);
+        del_command.execute(&mut conn).unwrap();
+
+        // Step 2: Add two members with a single GEOADD
+        let geo_add_command = GeoAddCommand::new("test_geo".to_string(), 13.361389, 38.115556, "Palermo".to_string())
+            .location(15.087269, 37.502669, "Catania".to_string());
+        assert_eq!(geo_add_command.query(&mut conn).unwrap(), 2);
+
+        // Step 3: Distances are converted into the requested unit
+        let geo_dist_command = GeoDistCommand::new("test_geo".to_string(), "Palermo".to_string(), "Catania".to_string(), GeoUnit::Kilometers);
+        let distance = geo_dist_command.query(&mut conn).unwrap().expect("Both members exist");
+        assert!((distance - 166.2742).abs() < 0.001);
+        let geo_dist_command = GeoDistCommand::new("test_geo".to_string(), "Palermo".to_string(), "Rome".to_string(), GeoUnit::Meters);
+        assert_eq!(geo_dist_command.query(&mut conn).unwrap(), None);
+
+        // Step 4: Positions come back as longitude/latitude pairs, missing members as None
+        let geo_pos_command = GeoPosCommand::new("test_geo".to_string(), vec!["Palermo".to_string(), "Rome".to_string()]);
+        let positions = geo_pos_command.query(&mut conn).unwrap();
+        let palermo = positions[0].expect("Palermo exists");
+        assert!((palermo.longitude - 13.361389).abs() < 0.0001);
+        assert!((palermo.latitude - 38.115556).abs() < 0.0001);
+        assert_eq!(positions[1], None);
+
+        // Step 5: Search around a position, nearest first, with distances and coordinates
+        let geo_search_command = GeoSearchCommand::from_position("test_geo".to_string(), 15.0, 37.0)
+            .by_radius(200.0, GeoUnit::Kilometers)
+            .ascending()
+            .with_coord()
+            .with_dist();
+        let results = geo_search_command.query(&mut conn).unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].member, "CATANIA");
+        assert!((results[0].distance.unwrap() - 56.4413).abs() < 0.001);
+        assert!(results[0].position.is_some());
+        assert_eq!(results[0].hash, None);
+        assert_eq!(results[1].member, "PALERMO");
+    }
+
+    #[test]
+    fn test_bitmap_commands() {
+        // Step 1: Set up the Redis connection (ensure Redis is running on localhost:6379)
+        let mut conn = RedisConnection::new("127.0.0.1:6379");
+        let keys = vec!["test_bitmap_a".to_string(), "test_bitmap_b".to_string(), "test_bitmap_and".to_string()];
+        let del_command = EvalCommand::new("return redis.call('del', unpack(KEYS))".to_string(), keys, vec![]);
+        del_command.execute(&mut conn).unwrap();
+
+        // Step 2: SETBIT returns the previous value of the bit
+        assert!(!SetBitCommand::new("test_bitmap_a".to_string(), 7, true).query(&mut conn).unwrap());
+        assert!(SetBitCommand::new("test_bitmap_a".to_string(), 7, true).query(&mut conn).unwrap());
+        SetBitCommand::new("test_bitmap_a".to_string(), 100, true).query(&mut conn).unwrap();
+        SetBitCommand::new("test_bitmap_b".to_string(), 100, true).query(&mut conn).unwrap();
+        assert!(GetBitCommand::new("test_bitmap_a".to_string(), 100).query(&mut conn).unwrap());
+        assert!(!GetBitCommand::new("test_bitmap_a".to_string(), 4_000_000).query(&mut conn).unwrap());
+
+        // Step 3: Count the bits in the whole string or in a range
+        assert_eq!(BitCountCommand::new("test_bitmap_a".to_string()).query(&mut conn).unwrap(), 2);
+        assert_eq!(BitCountCommand::new("test_bitmap_a".to_string()).range(0, 0).query(&mut conn).unwrap(), 1);
+        assert_eq!(BitCountCommand::new("test_bitmap_a".to_string()).bit_range(8, 200).query(&mut conn).unwrap(), 1);
+
+        // Step 4: Combine bitmaps, only bit 100 is set in both
+        let bit_op_command = BitOpCommand::new(BitOperation::And, "test_bitmap_and".to_string(), vec!["test_bitmap_a".to_string(), "test_bitmap_b".to_string()]);
+        assert_eq!(bit_op_command.query(&mut conn).unwrap(), 13);
+        assert_eq!(BitPosCommand::new("test_bitmap_and".to_string(), true).query(&mut conn).unwrap(), Some(100));
+
+        // Step 5: Looking for a set bit where there is none gives None
+        assert_eq!(BitPosCommand::new("test_bitmap_and".to_string(), true).range(0, 5).query(&mut conn).unwrap(), None);
+    }
+
+    #[test]
+    fn test_hyperloglog_commands() {
+        // Step 1: Set up the Redis connection (ensure Redis is running on localhost:6379)
+        let mut conn = RedisConnection::new("127.0.0.1:6379");
+        let keys = vec!["test_hll_a".to_string(), "test_hll_b".to_string(), "test_hll_merged".to_string()];
+        let del_command = EvalCommand::new("return redis.call('del', unpack(KEYS))".to_string(), keys, vec![]);
+        del_command.execute(&mut conn).unwrap();
+
+        // Step 2: Add elements, adding the same ones again does not change the estimate
+        let elements = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+        assert!(PfAddCommand::new("test_hll_a".to_string(), elements.clone()).query(&mut conn).unwrap());
+        assert!(!PfAddCommand::new("test_hll_a".to_string(), elements).query(&mut conn).unwrap());
+        let elements = vec!["c".to_string(), "d".to_string()];
+        assert!(PfAddCommand::new("test_hll_b".to_string(), elements).query(&mut conn).unwrap());
+
+        // Step 3: Count one HyperLogLog or the union of several
+        assert_eq!(PfCountCommand::new(vec!["test_hll_a".to_string()]).query(&mut conn).unwrap(), 3);
+        let pf_count_command = PfCountCommand::new(vec!["test_hll_a".to_string(), "test_hll_b".to_string()]);
+        assert_eq!(pf_count_command.query(&mut conn).unwrap(), 4);
+
+        // Step 4: Merge them into a new key
+        let sources = vec!["test_hll_a".to_string(), "test_hll_b".to_string()];
+        assert!(PfMergeCommand::new("test_hll_merged".to_string(), sources).query(&mut conn).unwrap());
+        assert_eq!(PfCountCommand::new(vec!["test_hll_merged".to_string()]).query(&mut conn).unwrap(), 4);
+    }
+
+    #[test]
+    fn test_publish_and_pubsub_commands() {
+        // Step 1: Set up a publisher and a subscriber connection (ensure Redis is running on localhost:6379)
+        struct SubscribeCommand;
+        impl Command for SubscribeCommand {
+            fn get_parts(&self) -> Vec<&str> {
+                vec!["SUBSCRIBE", "test_pubsub_channel"]
+            }
+        }
+        let mut conn = RedisConnection::new("127.0.0.1:6379");
+        let mut subscriber = RedisConnection::new("127.0.0.1:6379");
+        SubscribeCommand.execute(&mut subscriber).unwrap();
+
+        // Step 2: Introspect the subscription, channel names are sent in uppercase
+        let pubsub_channels_command = PubSubChannelsCommand::new().pattern("test_pubsub_*".to_string());
+        assert_eq!(pubsub_channels_command.query(&mut conn).unwrap(), vec!["TEST_PUBSUB_CHANNEL".to_string()]);
+        let channels = vec!["test_pubsub_channel".to_string(), "test_pubsub_nobody".to_string()];
+        let counts = PubSubNumSubCommand::new(channels).query(&mut conn).unwrap();
+        assert_eq!(counts, vec![("TEST_PUBSUB_CHANNEL".to_string(), 1), ("TEST_PUBSUB_NOBODY".to_string(), 0)]);
+        assert!(PubSubNumPatCommand.query(&mut conn).is_ok());
+
+        // Step 3: Publish a message and read it from the subscriber
+        let publish_command = PublishCommand::new("test_pubsub_channel".to_string(), "hello".to_string());
+        assert_eq!(publish_command.query(&mut conn).unwrap(), 1);
+        let message: Vec<String> = Vec::from_resp3_value(subscriber.read_response().unwrap()).unwrap();
+        assert_eq!(message, vec!["message", "TEST_PUBSUB_CHANNEL", "HELLO"]);
+    }
+
+    #[test]
+    fn test_keyspace_notifications() {
+        // Step 1: Subscribe to the key events (ensure Redis is running on localhost:6379)
+        let subscriber = RedisConnection::new("127.0.0.1:6379");
+        let mut notifications = KeyspaceNotifications::subscribe(subscriber, DEFAULT_EVENTS).unwrap();
+        let mut conn = RedisConnection::new("127.0.0.1:6379");
+
+        // Step 2: Set, delete and let a key expire
+        SetCommand::new("test_notify_key".to_string(), "value".to_string()).execute(&mut conn).unwrap();
+        let del_command = EvalCommand::new("return redis.call('del', KEYS[1])".to_string(), vec!["test_notify_key".to_string()], vec![]);
+        del_command.execute(&mut conn).unwrap();
+        SetCommand::new("test_notify_key".to_string(), "value".to_string()).px(50).execute(&mut conn).unwrap();
+
+        // Step 3: The events arrive in order, other tests may produce events on other keys
+        // and SET with PX is also followed by an expire event
+        let kinds: Vec<KeyspaceEventKind> = notifications.by_ref()
+            .map(Result::unwrap)
+            .filter(|event| event.key == "TEST_NOTIFY_KEY")
+            .inspect(|event| assert_eq!(event.db, 0))
+            .map(|event| event.kind)
+            .filter(|kind| !matches!(kind, KeyspaceEventKind::Other(_)))
+            .take(4)
+            .collect();
+        assert_eq!(kinds, vec![KeyspaceEventKind::Set, KeyspaceEventKind::Del, KeyspaceEventKind::Set, KeyspaceEventKind::Expired]);
+
+        // Step 4: Stop listening from the callback
+        SetCommand::new("test_notify_key".to_string(), "value".to_string()).execute(&mut conn).unwrap();
+        let mut received = None;
+        notifications.listen(|event| {
+            received = Some(event.kind);
+            event.key != "TEST_NOTIFY_KEY"
+        }).unwrap();
+        assert_eq!(received, Some(KeyspaceEventKind::Set));
+    }
+
+    #[test]
+    fn test_copy_dump_and_restore_commands() {
+        // Step 1: Set up the Redis connection (ensure Redis is running on localhost:6379)
+        let mut conn = RedisConnection::new("127.0.0.1:6379");
+        let keys = vec!["test_copy_source".to_string(), "test_copy_destination".to_string(), "test_restored".to_string()];
+        let del_command = EvalCommand::new("return redis.call('del', unpack(KEYS))".to_string(), keys, vec![]);
+        del_command.execute(&mut conn).unwrap();
+        SetCommand::new("test_copy_source".to_string(), "value".to_string()).execute(&mut conn).unwrap();
+
+        // Step 2: COPY fails on an existing destination unless REPLACE is given
+        assert!(CopyCommand::new("test_copy_source".to_string(), "test_copy_destination".to_string()).query(&mut conn).unwrap());
+        assert!(!CopyCommand::new("test_copy_source".to_string(), "test_copy_destination".to_string()).query(&mut conn).unwrap());
+        assert!(CopyCommand::new("test_copy_source".to_string(), "test_copy_destination".to_string()).replace().query(&mut conn).unwrap());
+
+        // Step 3: DUMP gives the binary payload, None for a missing key
+        let payload = DumpCommand::new("test_copy_source".to_string()).query(&mut conn).unwrap().unwrap();
+        assert!(std::str::from_utf8(&payload).is_err());
+        assert_eq!(DumpCommand::new("test_missing_key".to_string()).query(&mut conn).unwrap(), None);
+
+        // Step 4: RESTORE the payload under another key
+        assert!(RestoreCommand::new("test_restored".to_string(), 0, payload.clone()).query(&mut conn).unwrap());
+        assert!(RestoreCommand::new("test_restored".to_string(), 0, payload.clone()).query(&mut conn).is_err());
+        assert!(RestoreCommand::new("test_restored".to_string(), 0, payload).replace().query(&mut conn).unwrap());
+        let get_response = GetCommand::new("test_restored".to_string()).process_command(&mut conn);
+        assert_eq!(get_response, "$5\r\nVALUE\r\n");
+    }
+
+    #[test]
+    fn test_wait_and_write_with_ack() {
+        // Step 1: Set up the Redis connection (ensure Redis is running on localhost:6379)
+        let mut conn = RedisConnection::new("127.0.0.1:6379");
+        let mut executor = CommandExecutor::new("127.0.0.1:6379");
+
+        // Step 2: Without replicas WAIT returns 0 once the timeout elapsed
+        assert_eq!(WaitCommand::new(0, Duration::from_millis(10)).query(&mut conn).unwrap(), 0);
+
+        // Step 3: The write reply comes back with the acknowledgements
+        let set_command = SetCommand::new("test_write_with_ack".to_string(), "value".to_string());
+        let (written, acknowledged) = executor.write_with_ack(set_command, 0, Duration::from_millis(10)).unwrap();
+        assert!(written);
+        assert_eq!(acknowledged, 0);
+
+        // Step 4: There is no failover to abort
+        assert!(FailoverCommand::abort().query(&mut conn).is_err());
+    }
+
+    #[test]
+    fn test_config_and_slowlog_commands() {
+        // Step 1: Set up the Redis connection (ensure Redis is running on localhost:6379)
+        let mut conn = RedisConnection::new("127.0.0.1:6379");
+        let config = ConfigGetCommand::new("slowlog-*".to_string()).query(&mut conn).unwrap();
+        let threshold = config["slowlog-log-slower-than"].clone();
+        assert!(config.contains_key("slowlog-max-len"));
+
+        // Step 2: Log every command, run one and read it back from the slowlog
+        assert!(ConfigSetCommand::new("slowlog-log-slower-than".to_string(), "0".to_string()).query(&mut conn).unwrap());
+        assert!(SlowlogResetCommand.query(&mut conn).unwrap());
+        SetCommand::new("test_slowlog_key".to_string(), "value".to_string()).execute(&mut conn).unwrap();
+        let entries = SlowlogGetCommand::new().count(-1).query(&mut conn).unwrap();
+        assert!(SlowlogLenCommand.query(&mut conn).unwrap() >= 1);
+        let entry = entries.iter().find(|entry| entry.arguments.first().map(String::as_str) == Some("SET")).unwrap();
+        assert_eq!(entry.arguments, vec!["SET", "TEST_SLOWLOG_KEY", "VALUE"]);
+
+        // Step 3: Restore the threshold, values are sent as they are
+        let config_set_command = ConfigSetCommand::new("slowlog-log-slower-than".to_string(), threshold.clone())
+            .parameter("notify-keyspace-events".to_string(), "Eg$x".to_string());
+        assert!(config_set_command.query(&mut conn).unwrap());
+        let config = ConfigGetCommand::new("slowlog-log-slower-than".to_string()).pattern("notify-*".to_string()).query(&mut conn).unwrap();
+        assert_eq!(config["slowlog-log-slower-than"], threshold);
+        assert_eq!(config["notify-keyspace-events"].chars().filter(|flag| "Eg$x".contains(*flag)).count(), 4);
+        assert!(ConfigResetStatCommand.query(&mut conn).unwrap());
+    }
+
+    #[test]
+    fn test_connection_state() {
+        // Step 1: Set up the Redis connection (ensure Redis is running on localhost:6379)
+        struct RawCommand(Vec<&'static str>);
+        impl Command for RawCommand {
+            fn get_parts(&self) -> Vec<&str> {
+                self.0.clone()
+            }
+        }
+        let mut conn = RedisConnection::new("127.0.0.1:6379");
+        assert_eq!(conn.state(), ConnectionState::Normal);
+
+        // Step 2: Once subscribed only the subscription commands are sent
+        RawCommand(vec!["SUBSCRIBE", "test_state_channel"]).execute(&mut conn).unwrap();
+        assert_eq!(conn.state(), ConnectionState::Subscribed);
+        let set_command = SetCommand::new("test_state_key".to_string(), "value".to_string());
+        assert!(matches!(set_command.execute(&mut conn), Err(Resp3Error::InvalidState(_))));
+        RawCommand(vec!["UNSUBSCRIBE"]).execute(&mut conn).unwrap();
+        assert_eq!(conn.state(), ConnectionState::Normal);
+        assert!(set_command.query(&mut conn).unwrap());
+
+        // Step 3: Transactions cannot be nested, EXEC or DISCARD end them
+        RawCommand(vec!["MULTI"]).execute(&mut conn).unwrap();
+        assert_eq!(conn.state(), ConnectionState::Transaction);
+        assert!(matches!(RawCommand(vec!["MULTI"]).execute(&mut conn), Err(Resp3Error::InvalidState(_))));
+        RawCommand(vec!["DISCARD"]).execute(&mut conn).unwrap();
+        assert_eq!(conn.state(), ConnectionState::Normal);
+    }
+
+    #[test]
+    fn test_monitor() {
+        // Step 1: Start monitoring (ensure Redis is running on localhost:6379)
+        let mut monitor = Monitor::start(RedisConnection::new("127.0.0.1:6379")).unwrap();
+        let mut conn = RedisConnection::new("127.0.0.1:6379");
+
+        // Step 2: The commands of the other connections are streamed, quotes included
+        SetCommand::new("test_monitor_key".to_string(), "say \"hi\"\n".to_string()).execute(&mut conn).unwrap();
+        let event = monitor.by_ref()
+            .map(Result::unwrap)
+            .find(|event| event.arguments.get(1).map(String::as_str) == Some("TEST_MONITOR_KEY"))
+            .unwrap();
+        assert_eq!(event.arguments, vec!["SET", "TEST_MONITOR_KEY", "SAY \"HI\"\n"]);
+        assert_eq!(event.db, 0);
+        assert!(event.client_address.starts_with("127.0.0.1:"));
+
+        // Step 3: Lines are parsed with their escapes
+        let event = MonitorEvent::parse(r#"1339518083.107412 [3 lua] "get" "a\\b\x41""#).unwrap();
+        assert_eq!(event.timestamp, std::time::UNIX_EPOCH + Duration::from_micros(1339518083107412));
+        assert_eq!((event.db, event.client_address.as_str()), (3, "lua"));
+        assert_eq!(event.arguments, vec!["get", "a\\bA"]);
+        assert!(MonitorEvent::parse("OK").is_err());
+    }
+
+    #[test]
+    fn test_redis_7_commands() {
+        // Step 1: Set up the Redis connection and the data (ensure Redis is running on localhost:6379)
+        let mut conn = RedisConnection::new("127.0.0.1:6379");
+        let keys: Vec<String> = ["test_r7_list", "test_r7_zset", "test_r7_set_a", "test_r7_set_b", "test_r7_string"]
+            .iter().map(|key| key.to_string()).collect();
+        // Values are written in uppercase, as the commands send them
+        let script = "redis.call('del', unpack(KEYS))
+            redis.call('rpush', KEYS[1], 'A', 'B', 'A', 'C')
+            redis.call('zadd', KEYS[2], 1, 'ONE', 2, 'TWO', 3, 'THREE')
+            redis.call('sadd', KEYS[3], 'X', 'Y', 'Z')
+            redis.call('sadd', KEYS[4], 'Y', 'Z')
+            redis.call('set', KEYS[5], 'VALUE', 'pxat', 4102444800000)
+            return 1";
+        EvalCommand::new(script.to_string(), keys, vec![]).execute(&mut conn).unwrap();
+
+        // Step 2: LPOS gives one index, or all of them with COUNT
+        assert_eq!(LPosCommand::new("test_r7_list".to_string(), "a".to_string()).query(&mut conn).unwrap(), vec![0]);
+        assert_eq!(LPosCommand::new("test_r7_list".to_string(), "a".to_string()).count(0).query(&mut conn).unwrap(), vec![0, 2]);
+        assert_eq!(LPosCommand::new("test_r7_list".to_string(), "a".to_string()).rank(-1).query(&mut conn).unwrap(), vec![2]);
+        assert!(LPosCommand::new("test_r7_list".to_string(), "d".to_string()).query(&mut conn).unwrap().is_empty());
+
+        // Step 3: Pop from the first non-empty list or sorted set
+        let keys = vec!["test_r7_missing".to_string(), "test_r7_list".to_string()];
+        let popped = LmPopCommand::new(keys.clone(), ListEnd::Right).count(2).query(&mut conn).unwrap();
+        assert_eq!(popped, Some(("TEST_R7_LIST".to_string(), vec!["C".to_string(), "A".to_string()])));
+        let popped = BlmPopCommand::new(Duration::from_millis(100), keys, ListEnd::Left).query(&mut conn).unwrap();
+        assert_eq!(popped, Some(("TEST_R7_LIST".to_string(), vec!["A".to_string()])));
+        let popped = ZmPopCommand::new(vec!["test_r7_zset".to_string()], ScoreEnd::Max).count(2).query(&mut conn).unwrap();
+        assert_eq!(popped, Some(("TEST_R7_ZSET".to_string(), vec![("THREE".to_string(), 3.0), ("TWO".to_string(), 2.0)])));
+        assert_eq!(LmPopCommand::new(vec!["test_r7_missing".to_string()], ListEnd::Left).query(&mut conn).unwrap(), None);
+
+        // Step 4: Intersection cardinality, expiration times and access frequency
+        let sets = vec!["test_r7_set_a".to_string(), "test_r7_set_b".to_string()];
+        assert_eq!(SInterCardCommand::new(sets.clone()).query(&mut conn).unwrap(), 2);
+        assert_eq!(SInterCardCommand::new(sets).limit(1).query(&mut conn).unwrap(), 1);
+        assert_eq!(ExpireTimeCommand::new("test_r7_string".to_string()).query(&mut conn).unwrap(), KeyExpiration::At(4102444800));
+        assert_eq!(PExpireTimeCommand::new("test_r7_string".to_string()).query(&mut conn).unwrap(), KeyExpiration::At(4102444800000));
+        assert_eq!(ExpireTimeCommand::new("test_r7_set_a".to_string()).query(&mut conn).unwrap(), KeyExpiration::Persistent);
+        assert_eq!(ExpireTimeCommand::new("test_r7_missing".to_string()).query(&mut conn).unwrap(), KeyExpiration::Missing);
+        assert_eq!(ObjectFreqCommand::new("test_r7_missing".to_string()).query(&mut conn).unwrap(), None);
+    }
+
+    #[test]
+    fn test_function_commands() {
+        // Step 1: Set up the Redis connection (ensure Redis is running on localhost:6379)
+        let mut conn = RedisConnection::new("127.0.0.1:6379");
+        let code = "#!lua name=test_library
+            redis.register_function('test_incr_by', function(keys, args)
+                return redis.call('incrby', keys[1], args[1])
+            end)
+            redis.register_function{function_name='test_pair', callback=function(keys, args)
+                return {keys[1], tonumber(args[1])}
+            end, flags={'no-writes'}}";
+        let library = FunctionLoadCommand::new(code.to_string()).replace().query(&mut conn).unwrap();
+        assert_eq!(library, "test_library");
+        assert!(FunctionLoadCommand::new(code.to_string()).query(&mut conn).is_err());
+
+        // Step 2: Call the functions with keys and arguments, decoding nested replies
+        let del_command = EvalCommand::new("return redis.call('del', KEYS[1])".to_string(), vec!["test_function_counter".to_string()], vec![]);
+        del_command.execute(&mut conn).unwrap();
+        let fcall_command = FCallCommand::<i64>::new("test_incr_by".to_string(), vec!["test_function_counter".to_string()], vec!["5".to_string()]);
+        assert_eq!(fcall_command.query(&mut conn).unwrap(), 5);
+        let fcall_command = FCallCommand::<(String, i64)>::read_only("test_pair".to_string(), vec!["test_key".to_string()], vec!["7".to_string()]);
+        assert_eq!(fcall_command.query(&mut conn).unwrap(), ("TEST_KEY".to_string(), 7));
+        let fcall_command = FCallCommand::<Resp3Value>::read_only("test_incr_by".to_string(), vec!["test_function_counter".to_string()], vec!["1".to_string()]);
+        assert!(fcall_command.query(&mut conn).is_err());
+
+        // Step 3: List the library and delete it
+        let libraries = FunctionListCommand::new().library_name("test_lib*".to_string()).query(&mut conn).unwrap();
+        let library = libraries.iter().find(|library| library.name == "test_library").unwrap();
+        let mut functions: Vec<(&str, &[String])> = library.functions.iter().map(|function| (function.name.as_str(), function.flags.as_slice())).collect();
+        functions.sort();
+        assert_eq!(functions, vec![("test_incr_by", &[][..]), ("test_pair", &["no-writes".to_string()][..])]);
+        assert!(FunctionDeleteCommand::new("test_library".to_string()).query(&mut conn).unwrap());
+        assert!(FCallCommand::<Resp3Value>::new("test_pair".to_string(), vec![], vec![]).query(&mut conn).is_err());
+    }
+
+    #[cfg(feature="modules-json")]
+    #[test]
+    fn test_json_commands() {
+        // Step 1: Set up the Redis connection (ensure Redis Stack is running on localhost:6379)
+        let mut conn = RedisConnection::new("127.0.0.1:6379");
+        JsonDelCommand::new("test_json_doc".to_string()).query(&mut conn).unwrap();
+
+        // Step 2: Store a document, its keys and values keep their case
+        let document = serde_json::json!({"name": "Ada", "tags": ["math"], "age": 36});
+        let json_set_command = JsonSetCommand::from_value("test_json_doc".to_string(), "$".to_string(), &document).unwrap();
+        assert!(json_set_command.query(&mut conn).unwrap());
+        let json_set_command = JsonSetCommand::new("test_json_doc".to_string(), "$.name".to_string(), "\"Bob\"".to_string()).nx();
+        assert!(!json_set_command.query(&mut conn).unwrap());
+        let json_set_command = JsonSetCommand::new("test_json_doc".to_string(), "$.city".to_string(), "\"London\"".to_string()).nx();
+        assert!(json_set_command.query(&mut conn).unwrap());
+
+        // Step 3: Read it back whole or by path, decoded with serde
+        let Json(stored) = JsonGetCommand::<serde_json::Value>::new("test_json_doc".to_string()).query(&mut conn).unwrap().unwrap();
+        assert_eq!(stored["name"], "Ada");
+        assert_eq!(stored["city"], "London");
+        let json_get_command = JsonGetCommand::<Vec<u32>>::new("test_json_doc".to_string()).path("$.age".to_string());
+        assert_eq!(json_get_command.query(&mut conn).unwrap(), Some(Json(vec![36])));
+        assert_eq!(JsonGetCommand::<serde_json::Value>::new("test_json_missing".to_string()).query(&mut conn).unwrap(), None);
+
+        // Step 4: Delete a path, then the document
+        assert_eq!(JsonDelCommand::new("test_json_doc".to_string()).path("$.tags".to_string()).query(&mut conn).unwrap(), 1);
+        assert_eq!(JsonDelCommand::new("test_json_doc".to_string()).query(&mut conn).unwrap(), 1);
+    }
+
+    #[cfg(feature="modules-search")]
+    #[test]
+    fn test_search_commands() {
+        // Step 1: Index a few hashes (ensure Redis Stack is running on localhost:6379)
+        let mut conn = RedisConnection::new("127.0.0.1:6379");
+        FtDropIndexCommand::new("test_people".to_string()).delete_documents().execute(&mut conn).ok();
+        let ft_create_command = FtCreateCommand::new("test_people".to_string())
+            .prefix("test_person:".to_string())
+            .field("name".to_string(), FieldType::Text)
+            .field("city".to_string(), FieldType::Tag)
+            .sortable_field("age".to_string(), FieldType::Numeric);
+        assert!(ft_create_command.query(&mut conn).unwrap());
+        // Field names and values are written in uppercase, as the commands send them
+        let keys = vec!["test_person:1".to_string(), "test_person:2".to_string(), "test_person:3".to_string()];
+        let script = "redis.call('hset', KEYS[1], 'NAME', 'ADA LOVELACE', 'CITY', 'LONDON', 'AGE', 36)
+            redis.call('hset', KEYS[2], 'NAME', 'ALAN TURING', 'CITY', 'LONDON', 'AGE', 41)
+            redis.call('hset', KEYS[3], 'NAME', 'GRACE HOPPER', 'CITY', 'NEW YORK', 'AGE', 85)
+            return 1";
+        EvalCommand::new(script.to_string(), keys, vec![]).execute(&mut conn).unwrap();
+
+        // Step 2: Search with the query builder, sorted and paged
+        let query = SearchQuery::new().tag("city", &["london"]).numeric_range("age", 30.0, f64::INFINITY);
+        let result = FtSearchCommand::new("test_people".to_string(), query.to_string())
+            .sort_by("age".to_string(), false)
+            .query(&mut conn)
+            .unwrap();
+        assert_eq!(result.total, 2);
+        assert_eq!(result.docs[0].id, "TEST_PERSON:2");
+        assert_eq!(result.docs[0].fields["NAME"], "ALAN TURING");
+        let query = SearchQuery::new().text("lovelace");
+        let result = FtSearchCommand::new("test_people".to_string(), query.to_string()).no_content().with_scores().query(&mut conn).unwrap();
+        assert_eq!(result.docs.len(), 1);
+        assert!(result.docs[0].score.is_some() && result.docs[0].fields.is_empty());
+
+        // Step 3: Aggregate by city
+        let ft_aggregate_command = FtAggregateCommand::new("test_people".to_string(), SearchQuery::new().to_string())
+            .group_by(&["city"])
+            .reduce(Reducer::Count, "people")
+            .sort_by("people", false);
+        let result = ft_aggregate_command.query(&mut conn).unwrap();
+        assert_eq!(result.rows.len(), 2);
+        assert_eq!((result.rows[0]["CITY"].as_str(), result.rows[0]["PEOPLE"].as_str()), ("LONDON", "2"));
+        assert!(FtDropIndexCommand::new("test_people".to_string()).delete_documents().query(&mut conn).unwrap());
+
+        // Step 4: Index JSON documents, their JSONPath field names are sent as they are
+        FtDropIndexCommand::new("test_people_json".to_string()).delete_documents().execute(&mut conn).ok();
+        let ft_create_command = FtCreateCommand::new("test_people_json".to_string())
+            .on_json()
+            .prefix("test_person_json:".to_string())
+            .field("$.name".to_string(), FieldType::Text);
+        assert!(String::from_utf8(ft_create_command.format_resp_bytes()).unwrap().ends_with("SCHEMA\r\n$6\r\n$.name\r\n$4\r\nTEXT\r\n"));
+        assert!(ft_create_command.query(&mut conn).unwrap());
+        let script = "return redis.call('json.set', KEYS[1], '$', '{\"name\":\"Ada Lovelace\"}')";
+        EvalCommand::new(script.to_string(), vec!["test_person_json:1".to_string()], vec![]).execute(&mut conn).unwrap();
+        let result = FtSearchCommand::new("test_people_json".to_string(), SearchQuery::new().text("lovelace").to_string()).query(&mut conn).unwrap();
+        assert_eq!(result.total, 1);
+        assert_eq!(result.docs[0].fields["$"], "{\"name\":\"Ada Lovelace\"}");
+        assert!(FtDropIndexCommand::new("test_people_json".to_string()).delete_documents().query(&mut conn).unwrap());
+
+        // Step 5: Over RESP3 the results come as maps
+        let text = |text: &str| Resp3Value::BulkString(text.as_bytes().to_vec());
+        let map = |entries: Vec<(&str, Resp3Value)>| Resp3Value::Map(entries.into_iter().map(|(key, value)| (text(key), value)).collect());
+        let document = map(vec![("id", text("doc:1")), ("score", Resp3Value::Double(1.5)), ("extra_attributes", map(vec![("name", text("Ada"))])), ("values", Resp3Value::Array(vec![]))]);
+        let reply = map(vec![("attributes", Resp3Value::Array(vec![])), ("total_results", Resp3Value::Integer(1)), ("results", Resp3Value::Array(vec![document]))]);
+        let result = FtSearchCommand::new("idx".to_string(), "*".to_string()).with_scores().parse_output(reply).unwrap();
+        assert_eq!((result.total, result.docs[0].id.as_str(), result.docs[0].score), (1, "doc:1", Some(1.5)));
+        assert_eq!(result.docs[0].fields["name"], "Ada");
+        let row = map(vec![("extra_attributes", map(vec![("city", text("London")), ("people", text("2"))])), ("values", Resp3Value::Array(vec![]))]);
+        let reply = map(vec![("total_results", Resp3Value::Integer(1)), ("results", Resp3Value::Array(vec![row]))]);
+        let result = AggregateResult::from_resp3_value(reply).unwrap();
+        assert_eq!((result.total, result.rows[0]["city"].as_str()), (1, "London"));
+    }
+
+    #[cfg(feature="modules-timeseries")]
+    #[test]
+    fn test_timeseries_commands() {
+        // Step 1: Create two labelled series (ensure Redis Stack is running on localhost:6379)
+        let mut conn = RedisConnection::new("127.0.0.1:6379");
+        let keys = vec!["test_ts_kitchen".to_string(), "test_ts_garage".to_string()];
+        let del_command = EvalCommand::new("return redis.call('del', unpack(KEYS))".to_string(), keys, vec![]);
+        del_command.execute(&mut conn).unwrap();
+        for (key, room) in [("test_ts_kitchen", "kitchen"), ("test_ts_garage", "garage")] {
+            let ts_create_command = TsCreateCommand::new(key.to_string())
+                .retention(Duration::from_secs(3600))
+                .label("test_sensor".to_string(), "temperature".to_string())
+                .label("room".to_string(), room.to_string());
+            assert!(ts_create_command.query(&mut conn).unwrap());
+        }
+        assert_eq!(TsGetCommand::new("test_ts_kitchen".to_string()).query(&mut conn).unwrap(), None);
+
+        // Step 2: Add samples and read them back
+        for (timestamp, value) in [(1000, 20.5), (2000, 21.0), (3000, 21.5)] {
+            assert_eq!(TsAddCommand::new("test_ts_kitchen".to_string(), timestamp, value).query(&mut conn).unwrap(), timestamp);
+        }
+        TsAddCommand::new("test_ts_garage".to_string(), 2000, 12.0).query(&mut conn).unwrap();
+        assert_eq!(TsGetCommand::new("test_ts_kitchen".to_string()).query(&mut conn).unwrap(), Some((3000, 21.5)));
+        let samples = TsRangeCommand::new("test_ts_kitchen".to_string()).from(1500).query(&mut conn).unwrap();
+        assert_eq!(samples, vec![(2000, 21.0), (3000, 21.5)]);
+        assert_eq!(TsRangeCommand::new("test_ts_kitchen".to_string()).count(1).query(&mut conn).unwrap(), vec![(1000, 20.5)]);
+
+        // Step 3: Query both series by label
+        let mut series = TsMRangeCommand::new(vec!["test_sensor=temperature".to_string()])
+            .from(2000)
+            .to(2000)
+            .with_labels()
+            .query(&mut conn)
+            .unwrap();
+        series.sort_by(|a, b| a.key.cmp(&b.key));
+        assert_eq!(series.len(), 2);
+        assert_eq!((series[0].key.as_str(), series[0].labels["ROOM"].as_str()), ("TEST_TS_GARAGE", "GARAGE"));
+        assert_eq!(series[0].samples, vec![(2000, 12.0)]);
+        assert_eq!(series[1].samples, vec![(2000, 21.0)]);
+        let filters = vec!["test_sensor=temperature".to_string(), "room!=garage".to_string()];
+        let series = TsMRangeCommand::new(filters).query(&mut conn).unwrap();
+        assert_eq!(series.len(), 1);
+        assert!(series[0].labels.is_empty());
+    }
+
+    #[cfg(feature="modules-bloom")]
+    #[test]
+    fn test_bloom_and_cuckoo_filter_commands() {
+        // Step 1: Set up the Redis connection (ensure Redis Stack is running on localhost:6379)
+        let mut conn = RedisConnection::new("127.0.0.1:6379");
+        let keys = vec!["test_bloom".to_string(), "test_cuckoo".to_string()];
+        let del_command = EvalCommand::new("return redis.call('del', unpack(KEYS))".to_string(), keys, vec![]);
+        del_command.execute(&mut conn).unwrap();
+
+        // Step 2: Bloom filters tell whether items were probably added
+        assert!(BfReserveCommand::new("test_bloom".to_string(), 0.001, 1000).query(&mut conn).unwrap());
+        assert!(BfAddCommand::new("test_bloom".to_string(), "a".to_string()).query(&mut conn).unwrap());
+        assert!(!BfAddCommand::new("test_bloom".to_string(), "a".to_string()).query(&mut conn).unwrap());
+        let items = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+        assert_eq!(BfMAddCommand::new("test_bloom".to_string(), items).query(&mut conn).unwrap(), vec![false, true, true]);
+        assert!(BfExistsCommand::new("test_bloom".to_string(), "b".to_string()).query(&mut conn).unwrap());
+        let items = vec!["c".to_string(), "z".to_string()];
+        assert_eq!(BfMExistsCommand::new("test_bloom".to_string(), items).query(&mut conn).unwrap(), vec![true, false]);
+
+        // Step 3: Cuckoo filters also count and delete items
+        assert!(CfReserveCommand::new("test_cuckoo".to_string(), 1000).query(&mut conn).unwrap());
+        assert!(CfAddCommand::new("test_cuckoo".to_string(), "a".to_string()).query(&mut conn).unwrap());
+        assert!(CfAddCommand::new("test_cuckoo".to_string(), "a".to_string()).query(&mut conn).unwrap());
+        assert!(!CfAddCommand::if_not_exists("test_cuckoo".to_string(), "a".to_string()).query(&mut conn).unwrap());
+        assert!(CfCountCommand::new("test_cuckoo".to_string(), "a".to_string()).query(&mut conn).unwrap() >= 2);
+        assert!(CfDelCommand::new("test_cuckoo".to_string(), "a".to_string()).query(&mut conn).unwrap());
+        assert!(CfExistsCommand::new("test_cuckoo".to_string(), "a".to_string()).query(&mut conn).unwrap());
+        assert!(CfDelCommand::new("test_cuckoo".to_string(), "a".to_string()).query(&mut conn).unwrap());
+        let items = vec!["a".to_string(), "b".to_string()];
+        assert_eq!(CfMExistsCommand::new("test_cuckoo".to_string(), items).query(&mut conn).unwrap(), vec![false, false]);
+    }
+
+    #[test]
+    fn test_key_prefix() {
+        // Step 1: Set up the Redis connection and wrap an executor in the tenant namespace
+        let mut conn = RedisConnection::new("127.0.0.1:6379");
+        let keys = vec!["tenant_a:foo".to_string(), "tenant_a:bar".to_string(), "tenant_a:merged".to_string()];
+        let del_command = EvalCommand::new("return redis.call('del', unpack(KEYS))".to_string(), keys, vec![]);
+        del_command.execute(&mut conn).unwrap();
+        let mut tenant = KeyPrefix::new(CommandExecutor::new("127.0.0.1:6379"), "tenant_a:");
+
+        // Step 2: The keys are stored under the prefix
+        assert!(tenant.query(SetCommand::new("foo".to_string(), "hello".to_string())).unwrap());
+        let reply = GetCommand::new("tenant_a:foo".to_string()).execute(&mut conn).unwrap();
+        assert_eq!(reply, Resp3Value::BulkString(b"HELLO".to_vec()));
+        let reply = tenant.execute(GetCommand::new("foo".to_string())).unwrap();
+        assert_eq!(reply, Resp3Value::BulkString(b"HELLO".to_vec()));
+
+        // Step 3: Multi-key commands prefix each of their keys
+        assert!(tenant.query(PfAddCommand::new("bar".to_string(), vec!["a".to_string()])).unwrap());
+        assert!(tenant.query(PfMergeCommand::new("merged".to_string(), vec!["bar".to_string()])).unwrap());
+        let eval_command = EvalCommand::new("return redis.call('exists', KEYS[1], KEYS[2])".to_string(), vec!["foo".to_string(), "merged".to_string()], vec![]);
+        assert_eq!(tenant.execute(eval_command).unwrap(), Resp3Value::Integer(2));
+
+        // Step 4: KEYS and SCAN only see the namespace and return the keys without the prefix
+        let mut found = tenant.query(KeysCommand::new("*".to_string())).unwrap();
+        found.sort();
+        assert_eq!(found, vec!["BAR", "FOO", "MERGED"]);
+        let mut cursor = 0;
+        let mut scanned = Vec::new();
+        loop {
+            let (next, keys) = tenant.query(ScanCommand::new(cursor).pattern("f*".to_string()).count(100)).unwrap();
+            scanned.extend(keys);
+            cursor = next;
+            if cursor == 0 {
+                break;
+            }
+        }
+        assert_eq!(scanned, vec!["FOO"]);
+        assert_eq!(tenant.prefix(), "tenant_a:");
+
+        // Step 5: Over RESP2 the prefixed command still normalizes its reply
+        let server = MockServer::start().unwrap();
+        let flat = Resp3Value::Array(vec![Resp3Value::BulkString(b"matches".to_vec()), Resp3Value::Array(vec![]), Resp3Value::BulkString(b"len".to_vec()), Resp3Value::Integer(0)]);
+        server.reply("LCS", flat);
+        let mut tenant = KeyPrefix::new(CommandExecutor::from_connection(RedisConnection::new(&server.address())), "tenant_a:");
+        let found = tenant.query(LcsIdxCommand::new("foo".to_string(), "bar".to_string())).unwrap();
+        assert_eq!(found, LcsMatches { len: 0, matches: vec![] });
+        assert_eq!(server.commands().last().unwrap()[1..3], ["TENANT_A:FOO", "TENANT_A:BAR"]);
+
+        // Step 6: SCAN sends MATCH only with a pattern, the prefixed one always gets one for the namespace
+        let scanned = Resp3Value::Array(vec![Resp3Value::BulkString(b"0".to_vec()), Resp3Value::Array(vec![Resp3Value::BulkString(b"TENANT_A:FOO".to_vec())])]);
+        server.reply("SCAN", scanned);
+        assert_eq!(tenant.query(ScanCommand::new(0).count(10)).unwrap(), (0, vec!["FOO".to_string()]));
+        assert_eq!(server.commands().last().unwrap(), &["SCAN", "0", "COUNT", "10", "MATCH", "TENANT_A:*"]);
+        assert_eq!(ScanCommand::new(0).count(10).get_parts(), vec!["SCAN", "0", "COUNT", "10"]);
+        assert_eq!(ScanCommand::new(0).pattern("f*".to_string()).get_parts(), vec!["SCAN", "0", "MATCH", "f*"]);
+    }
+
+    #[test]
+    fn test_command_key_specs() {
+        // Step 1: Single-key commands report the key and whether they only read it
+        let get_command = GetCommand::new("foo".to_string());
+        assert_eq!(get_command.keys(), vec!["foo"]);
+        assert!(get_command.is_readonly());
+        let set_command = SetCommand::new("foo".to_string(), "bar".to_string()).nx();
+        assert_eq!(set_command.keys(), vec!["foo"]);
+        assert!(!set_command.is_readonly());
+
+        // Step 2: Multi-key commands report every key, without the other arguments
+        let eval_command = EvalCommand::new("return 1".to_string(), vec!["a".to_string(), "b".to_string()], vec!["c".to_string()]);
+        assert_eq!(eval_command.keys(), vec!["a", "b"]);
+        let bitop_command = BitOpCommand::new(BitOperation::And, "dest".to_string(), vec!["a".to_string(), "b".to_string()]);
+        assert_eq!(bitop_command.keys(), vec!["dest", "a", "b"]);
+        let blmpop_command = BlmPopCommand::new(Duration::from_secs(1), vec!["a".to_string(), "b".to_string()], ListEnd::Left).count(2);
+        assert_eq!(blmpop_command.keys(), vec!["a", "b"]);
+        assert_eq!(CopyCommand::new("a".to_string(), "b".to_string()).db(1).keys(), vec!["a", "b"]);
+        assert_eq!(ObjectFreqCommand::new("a".to_string()).keys(), vec!["a"]);
+        assert!(SInterCardCommand::new(vec!["a".to_string(), "b".to_string()]).is_readonly());
+
+        // Step 3: Commands without keys report none, FCALL_RO is the read-only flavour of FCALL
+        assert!(WaitCommand::new(1, Duration::from_millis(10)).keys().is_empty());
+        assert!(SlowlogLenCommand.keys().is_empty());
+        assert!(!SRandMemberCommand::new("a".to_string()).is_deterministic());
+        assert!(!ObjectFreqCommand::new("a".to_string()).is_deterministic());
+        assert!(get_command.is_deterministic());
+        // KEYS and SCAN take a pattern, which KeyPrefix namespaces but which names no key
+        assert!(KeysCommand::new("a*".to_string()).keys().is_empty());
+        assert_eq!(KeysCommand::new("a*".to_string()).pattern_indices(), vec![1]);
+        assert!(ScanCommand::new(0).pattern("a*".to_string()).keys().is_empty());
+        assert_eq!(ScanCommand::new(0).pattern("a*".to_string()).pattern_indices(), vec![3]);
+        assert!(!FCallCommand::<Resp3Value>::new("f".to_string(), vec!["k".to_string()], vec![]).is_readonly());
+        assert!(FCallCommand::<Resp3Value>::read_only("f".to_string(), vec!["k".to_string()], vec![]).is_readonly());
+
+        // Step 4: Read-only follows the Redis flag, server commands reading no data are not
+        assert!(DbSizeCommand.is_readonly());
+        assert!(!RoleCommand.is_readonly());
+        assert!(!ClientIdCommand.is_readonly());
+        assert!(!LastSaveCommand.is_readonly());
+    }
+
+    #[test]
+    fn test_connection_builder() {
+        // Step 1: Connect with buffers much smaller than the value, so it takes many reads and writes
+        let mut conn = RedisConnection::builder("127.0.0.1:6379").read_buffer_size(16).write_buffer_size(16).connect().unwrap();
+        let value = "x".repeat(10_000);
+        assert!(SetCommand::new("test_builder".to_string(), value.clone()).query(&mut conn).unwrap());
+
+        // Step 2: Read it back through the small buffer, then on a connection with the defaults
+        let reply = GetCommand::new("test_builder".to_string()).execute(&mut conn).unwrap();
+        assert_eq!(reply, Resp3Value::BulkString(value.to_uppercase().into_bytes()));
+        let mut conn = RedisConnectionBuilder::new("127.0.0.1:6379").connect().unwrap();
+        let reply = GetCommand::new("test_builder".to_string()).execute(&mut conn).unwrap();
+        assert_eq!(reply, Resp3Value::BulkString(value.to_uppercase().into_bytes()));
+
+        // Step 3: Connecting to a closed port is an error instead of a panic
+        assert!(matches!(RedisConnection::builder("127.0.0.1:1").connect(), Err(Resp3Error::Io(_))));
+
+        let del_command = EvalCommand::new("return redis.call('del', unpack(KEYS))".to_string(), vec!["test_builder".to_string()], vec![]);
+        del_command.execute(&mut conn).unwrap();
+    }
+
+    #[test]
+    fn test_variadic_commands() {
+        // Step 1: Set up the Redis connection (ensure Redis is running on localhost:6379)
+        let mut conn = RedisConnection::new("127.0.0.1:6379");
+        DelCommand::new(["test_variadic_list", "test_variadic_set"]).query(&mut conn).unwrap();
+
+        // Step 2: LPUSH and RPUSH take any number of values, from strings or bytes
+        assert_eq!(PushCommand::right("test_variadic_list".to_string(), ["b", "c"]).query(&mut conn).unwrap(), 2);
+        assert_eq!(PushCommand::left("test_variadic_list".to_string(), vec![b"a".to_vec()]).value("z").query(&mut conn).unwrap(), 4);
+        let eval_command = EvalCommand::new("return redis.call('lrange', KEYS[1], 0, -1)".to_string(), vec!["test_variadic_list".to_string()], vec![]);
+        assert_eq!(Vec::<String>::from_resp3_value(eval_command.execute(&mut conn).unwrap()).unwrap(), vec!["Z", "A", "B", "C"]);
+
+        // Step 3: LPOP and RPOP give one element, or an array of them with COUNT
+        assert_eq!(PopCommand::left("test_variadic_list".to_string()).query(&mut conn).unwrap(), Some("Z".to_string()));
+        assert_eq!(PopCountCommand::right("test_variadic_list".to_string(), 2).query(&mut conn).unwrap(), vec!["C", "B"]);
+        assert_eq!(PopCountCommand::left("test_variadic_list".to_string(), 5).query(&mut conn).unwrap(), vec!["A"]);
+        assert_eq!(PopCommand::right("test_variadic_list".to_string()).query(&mut conn).unwrap(), None);
+        assert!(PopCountCommand::left("test_variadic_list".to_string(), 1).query(&mut conn).unwrap().is_empty());
+        assert_eq!(PopCountCommand::left("a".to_string(), 3).format_resp_command(), "*3\r\n$4\r\nLPOP\r\n$1\r\nA\r\n$1\r\n3\r\n");
+
+        // Step 4: SADD only counts the new members, binary ones included
+        let members = vec![b"one".to_vec(), vec![0xff, 0x00], b"one".to_vec()];
+        assert_eq!(SAddCommand::new("test_variadic_set".to_string(), members).query(&mut conn).unwrap(), 2);
+        assert_eq!(SAddCommand::new("test_variadic_set".to_string(), ["two"]).member("one").query(&mut conn).unwrap(), 1);
+
+        // Step 5: DEL removes several keys at once
+        let keys: Vec<String> = vec!["test_variadic_list".to_string(), "test_variadic_set".to_string()];
+        assert_eq!(DelCommand::new(keys).key("test_variadic_missing").query(&mut conn).unwrap(), 2);
+    }
+
+    #[test]
+    fn test_connection_timeout() {
+        // Step 1: A blocking command outlasting the connection timeout fails with a timeout
+        let mut conn = RedisConnection::builder("127.0.0.1:6379").timeout(Duration::from_millis(200)).connect().unwrap();
+        let blmpop_command = BlmPopCommand::new(Duration::from_secs(2), vec!["test_timeout_list".to_string()], ListEnd::Left);
+        let err = blmpop_command.query(&mut conn).unwrap_err();
+        assert!(err.is_timeout(), "{:?}", err);
+
+        // Step 2: Other failures are not timeouts
+        let mut conn = RedisConnection::builder("127.0.0.1:6379").timeout(Duration::from_secs(1)).connect().unwrap();
+        let err = EvalCommand::new("return redis.error_reply('ERR boom')".to_string(), vec![], vec![]).execute(&mut conn).unwrap_err();
+        assert!(matches!(err, Resp3Error::Server(_)) && !err.is_timeout());
+    }
+
+    #[cfg(feature="wire-debug")]
+    #[test]
+    fn test_wire_debug() {
+        // Step 1: Frames are dumped as hex next to their printable characters
+        let (description, redacted) = describe_request(&GetCommand::new("foo".to_string()).format_resp_bytes());
+        assert!(!redacted);
+        assert_eq!(description, "2a 32 0d 0a 24 33 0d 0a 47 45 54 0d 0a 24 33 0d  *2..$3..GET..$3.\n0a 46 4f 4f 0d 0a                                .FOO..");
+
+        // Step 2: Credentials are never logged, neither are the configured commands
+        let auth = EvalCommand::new("return 1".to_string(), vec![], vec![]).format_resp_bytes();
+        assert!(!describe_request(&auth).1);
+        redact_command("eval");
+        let (description, redacted) = describe_request(&auth);
+        assert!(redacted && description.contains("<redacted>") && !description.contains("return"));
+        struct AuthCommand;
+        impl Command for AuthCommand {
+            fn get_parts(&self) -> Vec<&str> {
+                vec!["AUTH", "default", "secret"]
+            }
+        }
+        let (description, redacted) = describe_request(&AuthCommand.format_resp_bytes());
+        assert!(redacted && !description.contains("SECRET"));
+        let (description, redacted) = describe_request(&AclSetUserCommand::new("alice".to_string()).rule(">hunter2").format_resp_bytes());
+        assert!(redacted && !description.contains("hunter"));
+
+        // Step 3: The values of the sensitive keys are hidden, and so are the replies to them
+        redact_key("session:*");
+        let (description, redacted) = describe_request(&SetCommand::new("session:42".to_string(), "token".to_string()).format_resp_bytes());
+        assert!(redacted && description.contains("SESSION:42") && !description.contains("TOKEN"));
+        assert_eq!(describe_reply(b"$5\r\nTOKEN\r\n", true), "<redacted>");
+        assert!(!describe_request(&SetCommand::new("other".to_string(), "token".to_string()).format_resp_bytes()).1);
+
+        // Step 4: Inline commands are redacted too, and stay inline
+        let mut inline = Vec::new();
+        AuthCommand.encode_inline_into(&mut inline);
+        let (description, redacted) = describe_request(&inline);
+        assert!(redacted && description.starts_with("41 55 54 48 20 3c") && !description.contains("SECRET"));
+    }
+
+    #[test]
+    fn test_protocol_desync() {
+        // Step 1: A reply of the wrong type is reported instead of being returned
+        struct MisdeclaredCommand;
+        impl Command for MisdeclaredCommand {
+            fn get_parts(&self) -> Vec<&str> {
+                vec!["ECHO", "hello"]
+            }
+
+            fn expected_reply(&self) -> ReplyKind {
+                ReplyKind::Integer
+            }
+        }
+        let mut conn = RedisConnection::new("127.0.0.1:6379");
+        assert!(matches!(MisdeclaredCommand.execute(&mut conn), Err(Resp3Error::ProtocolDesync(_))));
+
+        // Step 2: The connection was replaced, so the next requests get their own replies
+        assert!(SetCommand::new("test_desync".to_string(), "value".to_string()).query(&mut conn).unwrap());
+        let reply = GetCommand::new("test_desync".to_string()).execute(&mut conn).unwrap();
+        assert_eq!(reply, Resp3Value::BulkString(b"VALUE".to_vec()));
+        assert_eq!(DelCommand::new(["test_desync"]).query(&mut conn).unwrap(), 1);
+
+        // Step 3: Error replies and nil are accepted whatever the expected type
+        assert!(ReplyKind::Integer.matches(&Resp3Value::Error("ERR".to_string())));
+        assert!(ReplyKind::Bulk.matches(&Resp3Value::Null));
+        assert!(!ReplyKind::Integer.matches(&Resp3Value::Null));
+        assert!(!ReplyKind::Array.matches(&Resp3Value::Integer(1)));
+    }
+
+    #[test]
+    fn test_cached_executor() {
+        // Step 1: Set up a cache of two entries in front of an executor
+        let mut conn = RedisConnection::new("127.0.0.1:6379");
+        DelCommand::new(["test_cache_a", "test_cache_b", "test_cache_c"]).query(&mut conn).unwrap();
+        SetCommand::new("test_cache_a".to_string(), "one".to_string()).query(&mut conn).unwrap();
+        let mut cache = CachedExecutor::new(CommandExecutor::new("127.0.0.1:6379"), 2, Duration::from_secs(60));
+
+        // Step 2: Reads are cached, so a change by another client is not seen
+        let reply = cache.execute(GetCommand::new("test_cache_a".to_string())).unwrap();
+        assert_eq!(reply, Resp3Value::BulkString(b"ONE".to_vec()));
+        SetCommand::new("test_cache_a".to_string(), "two".to_string()).query(&mut conn).unwrap();
+        let reply = cache.execute(GetCommand::new("test_cache_a".to_string())).unwrap();
+        assert_eq!(reply, Resp3Value::BulkString(b"ONE".to_vec()));
+        assert_eq!(cache.len(), 1);
+
+        // Step 3: A write through the cache drops the replies involving its key
+        assert!(cache.query(SetCommand::new("test_cache_a".to_string(), "three".to_string())).unwrap());
+        assert!(cache.is_empty());
+        let reply = cache.execute(GetCommand::new("test_cache_a".to_string())).unwrap();
+        assert_eq!(reply, Resp3Value::BulkString(b"THREE".to_vec()));
+
+        // Step 4: The least recently used entry makes room for new ones
+        cache.execute(GetCommand::new("test_cache_b".to_string())).unwrap();
+        cache.execute(GetCommand::new("test_cache_a".to_string())).unwrap();
+        cache.execute(GetCommand::new("test_cache_c".to_string())).unwrap();
+        assert_eq!(cache.len(), 2);
+        SetCommand::new("test_cache_b".to_string(), "new".to_string()).query(&mut conn).unwrap();
+        let reply = cache.execute(GetCommand::new("test_cache_b".to_string())).unwrap();
+        assert_eq!(reply, Resp3Value::BulkString(b"NEW".to_vec()));
+
+        // Step 5: Explicit invalidation and expiration
+        SetCommand::new("test_cache_b".to_string(), "newer".to_string()).query(&mut conn).unwrap();
+        cache.invalidate("test_cache_b");
+        let reply = cache.execute(GetCommand::new("test_cache_b".to_string())).unwrap();
+        assert_eq!(reply, Resp3Value::BulkString(b"NEWER".to_vec()));
+        let mut cache = CachedExecutor::new(cache.into_inner(), 10, Duration::from_millis(50));
+        cache.execute(GetCommand::new("test_cache_a".to_string())).unwrap();
+        SetCommand::new("test_cache_a".to_string(), "four".to_string()).query(&mut conn).unwrap();
+        std::thread::sleep(Duration::from_millis(100));
+        let reply = cache.execute(GetCommand::new("test_cache_a".to_string())).unwrap();
+        assert_eq!(reply, Resp3Value::BulkString(b"FOUR".to_vec()));
+        DelCommand::new(["test_cache_a", "test_cache_b", "test_cache_c"]).query(&mut conn).unwrap();
+
+        // Step 6: Keyless reads are never cached, DBSIZE sees a SET made through the cache
+        let server = MockServer::start().unwrap();
+        let mut cache = CachedExecutor::new(CommandExecutor::new(&server.address()), 10, Duration::from_secs(60));
+        server.reply_once("DBSIZE", Resp3Value::Integer(0));
+        assert_eq!(cache.query(DbSizeCommand).unwrap(), 0);
+        assert!(cache.query(SetCommand::new("test_cache_a".to_string(), "one".to_string())).unwrap());
+        server.reply_once("DBSIZE", Resp3Value::Integer(1));
+        assert_eq!(cache.query(DbSizeCommand).unwrap(), 1);
+        assert!(cache.is_empty());
+
+        // Step 7: Random picks and the times left are asked for every time
+        server.reply_once("SRANDMEMBER", Resp3Value::BulkString(b"A".to_vec()));
+        server.reply_once("SRANDMEMBER", Resp3Value::BulkString(b"B".to_vec()));
+        assert_eq!(cache.query(SRandMemberCommand::new("test_cache_set".to_string())).unwrap(), Some("A".to_string()));
+        assert_eq!(cache.query(SRandMemberCommand::new("test_cache_set".to_string())).unwrap(), Some("B".to_string()));
+        server.reply_once("TTL", Resp3Value::Integer(60));
+        server.reply_once("TTL", Resp3Value::Integer(59));
+        assert_eq!(cache.query(TtlCommand::new("test_cache_a".to_string())).unwrap(), KeyTtl::Expires(Duration::from_secs(60)));
+        assert_eq!(cache.query(TtlCommand::new("test_cache_a".to_string())).unwrap(), KeyTtl::Expires(Duration::from_secs(59)));
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_fire_and_forget() {
+        // Step 1: Set up the Redis connection (ensure Redis is running on localhost:6379)
+        let mut conn = RedisConnection::new("127.0.0.1:6379");
+        DelCommand::new(["test_no_reply", "test_queue"]).query(&mut conn).unwrap();
+
+        // Step 2: A command sent without reply, even a failing one, leaves the next replies in step
+        conn.send_no_reply(&SetCommand::new("test_no_reply".to_string(), "a".to_string())).unwrap();
+        conn.send_no_reply(&SetBitCommand::new("test_no_reply".to_string(), u64::MAX, true)).unwrap();
+        let reply = GetCommand::new("test_no_reply".to_string()).execute(&mut conn).unwrap();
+        assert_eq!(reply, Resp3Value::BulkString(b"A".to_vec()));
+
+        // Step 3: The queue sends everything at once and waits for the server to go through it
+        let mut queue = CommandQueue::new();
+        for index in 0..100 {
+            queue.push(&PushCommand::right("test_queue".to_string(), [index.to_string()])).unwrap();
+        }
+        assert_eq!(queue.len(), 100);
+        assert_eq!(queue.flush(&mut conn).unwrap(), 100);
+        assert!(queue.is_empty());
+        let eval_command = EvalCommand::new("return redis.call('llen', KEYS[1])".to_string(), vec!["test_queue".to_string()], vec![]);
+        assert_eq!(eval_command.execute(&mut conn).unwrap(), Resp3Value::Integer(100));
+
+        // Step 4: Commands whose reply changes the connection cannot go without it
+        struct SubscribeCommand;
+        impl Command for SubscribeCommand {
+            fn get_parts(&self) -> Vec<&str> {
+                vec!["SUBSCRIBE", "test_channel"]
+            }
+        }
+        let subscribe = SubscribeCommand;
+        assert!(matches!(queue.push(&subscribe), Err(Resp3Error::InvalidState(_))));
+        assert!(matches!(conn.send_no_reply(&subscribe), Err(Resp3Error::InvalidState(_))));
+        assert!(queue.is_empty());
+
+        DelCommand::new(["test_no_reply", "test_queue"]).query(&mut conn).unwrap();
+    }
+
+    #[test]
+    fn test_failover_endpoints() {
+        // Step 1: The first endpoint is down, so the connection falls back to the second one
+        let mut conn = RedisConnection::builder("127.0.0.1:1").endpoint("127.0.0.1:6379").connect().unwrap();
+        assert_eq!(conn.current_endpoint(), "127.0.0.1:6379");
+        let health = conn.endpoint_health();
+        assert!(!health[0].healthy);
+        assert_eq!(health[0].consecutive_failures, 1);
+        assert!(health[0].last_error.is_some());
+        assert!(health[1].healthy);
+
+        // Step 2: Reconnecting tries them again in order, counting the failures
+        conn.reconnect().unwrap();
+        assert_eq!(conn.endpoint_health()[0].consecutive_failures, 2);
+        assert!(SetCommand::new("test_failover".to_string(), "a".to_string()).query(&mut conn).unwrap());
+
+        // Step 3: In a random order the reachable endpoint is always found
+        for _ in 0..5 {
+            let conn = RedisConnection::builder("127.0.0.1:1").endpoint("127.0.0.1:6379").randomize(true).connect().unwrap();
+            assert_eq!(conn.current_endpoint(), "127.0.0.1:6379");
+        }
+
+        // Step 4: When none can be reached the error of the last attempt is returned
+        assert!(matches!(RedisConnection::builder("127.0.0.1:1").endpoint("127.0.0.1:2").connect(), Err(Resp3Error::Io(_))));
+
+        DelCommand::new(["test_failover"]).query(&mut conn).unwrap();
+    }
+
+    #[test]
+    fn test_dns_resolution() {
+        // Step 1: A hostname is resolved when connecting, to an address of the preferred family
+        let mut conn = RedisConnection::builder("localhost:6379").prefer_ip(IpPreference::V4).connect().unwrap();
+        assert!(conn.peer_address().unwrap().is_ipv4());
+
+        // Step 2: It is resolved again on reconnect, still reaching the server
+        conn.reconnect().unwrap();
+        assert_eq!(conn.peer_address().unwrap().port(), 6379);
+        assert_eq!(GetCommand::new("test_dns_missing".to_string()).execute(&mut conn).unwrap(), Resp3Value::Null);
+
+        // Step 3: Preferring IPv6 still falls back to IPv4 when that is where the server listens
+        let conn = RedisConnection::builder("localhost:6379").prefer_ip(IpPreference::V6).connect().unwrap();
+        assert_eq!(conn.current_endpoint(), "localhost:6379");
+
+        // Step 4: A name that does not resolve is an I/O error
+        assert!(matches!(RedisConnection::builder("resp3string.invalid:6379").connect(), Err(Resp3Error::Io(_))));
+    }
+
+    // A proxy for one connection that tunnels to the local Redis, sending back the target of the
+    // SOCKS5 request or the HTTP headers
+    fn spawn_test_proxy(socks: bool) -> (String, std::sync::mpsc::Receiver<String>) {
+        use std::io::{BufRead, BufReader, Read, Write};
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let (sender, receiver) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            let (mut client, _) = listener.accept().unwrap();
+            if socks {
+                let mut greeting = [0; 3];
+                client.read_exact(&mut greeting).unwrap();
+                client.write_all(&[5, 0]).unwrap();
+                let mut request = [0; 5];
+                client.read_exact(&mut request).unwrap();
+                let mut host = vec![0; request[4] as usize + 2];
+                client.read_exact(&mut host).unwrap();
+                let port = u16::from_be_bytes([host[host.len() - 2], host[host.len() - 1]]);
+                sender.send(format!("{}:{}", String::from_utf8_lossy(&host[..host.len() - 2]), port)).unwrap();
+                client.write_all(&[5, 0, 0, 1, 127, 0, 0, 1, 0, 0]).unwrap();
+            } else {
+                let mut reader = BufReader::new(client.try_clone().unwrap());
+                let mut headers = String::new();
+                while !headers.ends_with("\r\n\r\n") {
+                    reader.read_line(&mut headers).unwrap();
+                }
+                sender.send(headers).unwrap();
+                client.write_all(b"HTTP/1.1 200 Connection established\r\n\r\n").unwrap();
+            }
+            let server = std::net::TcpStream::connect("127.0.0.1:6379").unwrap();
+            let (mut client_read, mut server_write) = (client.try_clone().unwrap(), server.try_clone().unwrap());
+            std::thread::spawn(move || std::io::copy(&mut client_read, &mut server_write));
+            let (mut server_read, mut client_write) = (server, client);
+            let _ = std::io::copy(&mut server_read, &mut client_write);
+        });
+        (address, receiver)
+    }
+
+    #[test]
+    fn test_proxy_connection() {
+        // Step 1: Through a SOCKS5 proxy, the hostname is sent for the proxy to resolve
+        let (proxy_address, requests) = spawn_test_proxy(true);
+        let mut conn = RedisConnection::builder("localhost:6379").proxy(Proxy::socks5(&proxy_address)).connect().unwrap();
+        assert_eq!(requests.recv().unwrap(), "localhost:6379");
+        assert!(SetCommand::new("test_proxy".to_string(), "a".to_string()).query(&mut conn).unwrap());
+
+        // Step 2: Through an HTTP CONNECT proxy, with basic authentication
+        let (proxy_address, requests) = spawn_test_proxy(false);
+        let proxy = Proxy::http_connect(&proxy_address).credentials("user", "pass");
+        let mut conn = RedisConnection::builder("127.0.0.1:6379").proxy(proxy).connect().unwrap();
+        let headers = requests.recv().unwrap();
+        assert!(headers.starts_with("CONNECT 127.0.0.1:6379 HTTP/1.1\r\n"));
+        assert!(headers.contains("\r\nProxy-Authorization: Basic dXNlcjpwYXNz\r\n"));
+        let reply = GetCommand::new("test_proxy".to_string()).execute(&mut conn).unwrap();
+        assert_eq!(reply, Resp3Value::BulkString(b"A".to_vec()));
+
+        // Step 3: A proxy that cannot be reached is an I/O error
+        assert!(matches!(RedisConnection::builder("127.0.0.1:6379").proxy(Proxy::socks5("127.0.0.1:1")).connect(), Err(Resp3Error::Io(_))));
+
+        DelCommand::new(["test_proxy"]).query(&mut conn).unwrap();
+    }
+
+    #[test]
+    fn test_load_generator() {
+        // Step 1: Set up the Redis connection (ensure Redis is running on localhost:6379)
+        let mut conn = RedisConnection::new("127.0.0.1:6379");
+        let keys: Vec<String> = (0..10).flat_map(|index| [format!("bench:key:{}", index), format!("bench:counter:{}", index)]).collect();
+        DelCommand::new(keys.clone()).query(&mut conn).unwrap();
+
+        // Step 2: Every workload runs to the end, spread over the clients and pipelined
+        for workload in [Workload::Set, Workload::Get, Workload::Incr] {
+            let generator = LoadGenerator::new("127.0.0.1:6379", workload).clients(3).pipeline(4).requests(100).key_space(10);
+            let report = generator.run().unwrap();
+            assert_eq!(report.requests(), 100);
+            assert!(report.requests_per_second() > 0.0);
+            assert!(report.percentile(50.0) <= report.percentile(99.0));
+            assert!(report.percentile(99.0) <= report.max_latency());
+            assert!(report.to_string().starts_with(&format!("{}: 100 requests", workload.as_str())));
+        }
+
+        // Step 3: The keys hold what the workloads sent
+        assert_eq!(IncrCommand::new("bench:counter:0".to_string()).query(&mut conn).unwrap(), 11);
+        let reply = GetCommand::new("bench:key:0".to_string()).execute(&mut conn).unwrap();
+        assert_eq!(reply, Resp3Value::BulkString(b"XXX".to_vec()));
+
+        // Step 4: An error reply fails the run
+        assert!(SetCommand::new("bench:counter:0".to_string(), "a".to_string()).query(&mut conn).unwrap());
+        let result = LoadGenerator::new("127.0.0.1:6379", Workload::Incr).clients(1).requests(2).run();
+        assert!(matches!(result, Err(Resp3Error::Server(_))));
+
+        DelCommand::new(keys).query(&mut conn).unwrap();
+    }
+
+    #[test]
+    fn test_mock_server() {
+        // Step 1: Start the mock server, no Redis needed
+        let server = MockServer::start().unwrap();
+        let mut conn = RedisConnection::new(&server.address());
+
+        // Step 2: GET, SET and DEL work on its own data
+        assert!(SetCommand::new("test_mock".to_string(), "a".to_string()).query(&mut conn).unwrap());
+        assert!(!SetCommand::new("test_mock".to_string(), "b".to_string()).nx().query(&mut conn).unwrap());
+        let reply = GetCommand::new("test_mock".to_string()).execute(&mut conn).unwrap();
+        assert_eq!(reply, Resp3Value::BulkString(b"A".to_vec()));
+        assert_eq!(DelCommand::new(["test_mock", "test_mock_missing"]).query(&mut conn).unwrap(), 1);
+        assert_eq!(conn.request(b"*1\r\n$4\r\nPING\r\n").unwrap(), Resp3Value::SimpleString("PONG".to_string()));
+        assert_eq!(server.commands()[0], vec!["SET", "TEST_MOCK", "A"]);
+
+        // Step 3: Canned replies, once or for every later command
+        server.reply_once("GET", Resp3Value::BulkString(b"canned".to_vec()));
+        server.reply("INCR", Resp3Value::Integer(42));
+        let reply = GetCommand::new("test_mock".to_string()).execute(&mut conn).unwrap();
+        assert_eq!(reply, Resp3Value::BulkString(b"canned".to_vec()));
+        assert_eq!(GetCommand::new("test_mock".to_string()).execute(&mut conn).unwrap(), Resp3Value::Null);
+        assert_eq!(IncrCommand::new("test_mock".to_string()).query(&mut conn).unwrap(), 42);
+        assert!(matches!(conn.request(b"*1\r\n$4\r\nQUIT\r\n"), Err(Resp3Error::Server(_))));
+
+        // Step 4: A reply split in one byte writes is still read whole
+        server.inject(Fault::SplitWrites(1));
+        assert_eq!(IncrCommand::new("test_mock".to_string()).query(&mut conn).unwrap(), 42);
+
+        // Step 5: Truncated replies and disconnects are I/O errors, the connection then reconnects
+        server.inject(Fault::Truncate(3));
+        assert!(matches!(IncrCommand::new("test_mock".to_string()).query(&mut conn), Err(Resp3Error::Io(_))));
+        conn.reconnect().unwrap();
+        server.inject(Fault::Disconnect);
+        assert!(matches!(IncrCommand::new("test_mock".to_string()).query(&mut conn), Err(Resp3Error::Io(_))));
+        conn.reconnect().unwrap();
+        assert_eq!(IncrCommand::new("test_mock".to_string()).query(&mut conn).unwrap(), 42);
+        assert_eq!(server.connections(), 3);
+
+        // Step 6: A delayed reply runs into the read timeout
+        let mut conn = RedisConnection::builder(&server.address()).timeout(Duration::from_millis(50)).connect().unwrap();
+        server.inject(Fault::Delay(Duration::from_millis(200)));
+        assert!(IncrCommand::new("test_mock".to_string()).query(&mut conn).unwrap_err().is_timeout());
+    }
+
+    #[test]
+    fn test_chaos_transport() {
+        // Step 1: Replies arriving one byte at a time and late are still read whole
+        let server = MockServer::start().unwrap();
+        let chaos = ChaosConfig::new().split_reads(1).latency(Duration::from_millis(1));
+        let mut conn = RedisConnection::builder(&server.address()).transport_wrapper(chaos.wrapper()).connect().unwrap();
+        assert!(SetCommand::new("test_chaos".to_string(), "a".to_string()).query(&mut conn).unwrap());
+        let reply = GetCommand::new("test_chaos".to_string()).execute(&mut conn).unwrap();
+        assert_eq!(reply, Resp3Value::BulkString(b"A".to_vec()));
+
+        // Step 2: A truncated reply ends the stream
+        let chaos = ChaosConfig::new().truncate_probability(1.0);
+        let mut conn = RedisConnection::builder(&server.address()).transport_wrapper(chaos.wrapper()).connect().unwrap();
+        let err = GetCommand::new("test_chaos".to_string()).execute(&mut conn).unwrap_err();
+        assert!(matches!(&err, Resp3Error::Io(err) if err.kind() == std::io::ErrorKind::UnexpectedEof));
+
+        // Step 3: With random disconnects a retry loop reconnecting on I/O errors gets every
+        // command through, and the faults are the same on every run of the same seed
+        let run = |seed| {
+            let chaos = ChaosConfig::new().disconnect_probability(0.2).seed(seed);
+            let mut conn = RedisConnection::builder(&server.address()).transport_wrapper(chaos.wrapper()).connect().unwrap();
+            let mut disconnects = 0;
+            for _ in 0..20 {
+                while let Err(Resp3Error::Io(_)) = SetCommand::new("test_chaos".to_string(), "b".to_string()).query(&mut conn) {
+                    disconnects += 1;
+                    conn.reconnect().unwrap();
+                }
+            }
+            disconnects
+        };
+        let disconnects = run(7);
+        assert!(disconnects > 0);
+        assert_eq!(run(7), disconnects);
+    }
+
+    #[test]
+    fn test_custom_transport() {
+        use crate::resp3::utils::transport::{Connector, Transport};
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        // A tunnel of our own, reaching the mock server whatever the endpoint is called
+        #[derive(Debug)]
+        struct Tunnel(String, Arc<AtomicUsize>);
+
+        impl Connector for Tunnel {
+            fn connect(&self, address: &str, _timeout: Option<Duration>) -> std::io::Result<Box<dyn Transport>> {
+                assert_eq!(address, "tunnel://redis");
+                self.1.fetch_add(1, Ordering::SeqCst);
+                Ok(Box::new(std::net::TcpStream::connect(&self.0)?))
+            }
+        }
+
+        // Step 1: The connector opens the streams on connect and on every reconnect
+        let server = MockServer::start().unwrap();
+        let connects = Arc::new(AtomicUsize::new(0));
+        let mut conn = RedisConnection::builder("tunnel://redis").connector(Tunnel(server.address(), connects.clone())).timeout(Duration::from_secs(1)).connect().unwrap();
+        assert!(SetCommand::new("test_transport".to_string(), "a".to_string()).query(&mut conn).unwrap());
+        conn.reconnect().unwrap();
+        assert_eq!(GetCommand::new("test_transport".to_string()).query(&mut conn).unwrap().as_deref(), Some("A"));
+        assert_eq!(connects.load(Ordering::SeqCst), 2);
+
+        // Step 2: The timeout applies to the stream the connector opened, and can be changed on the way
+        server.inject(Fault::Delay(Duration::from_millis(200)));
+        conn.set_timeout(Some(Duration::from_millis(50))).unwrap();
+        assert!(GetCommand::new("test_transport".to_string()).query(&mut conn).unwrap_err().is_timeout());
+
+        // Step 3: Unix sockets are served by the UnixConnector, the path being the endpoint
+        #[cfg(unix)]
+        {
+            use crate::resp3::utils::transport::UnixConnector;
+            use std::io::{Read, Write};
+
+            let path = std::env::temp_dir().join(format!("resp3string-{}.sock", std::process::id()));
+            let _ = std::fs::remove_file(&path);
+            let listener = std::os::unix::net::UnixListener::bind(&path).unwrap();
+            let server = std::thread::spawn(move || {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut request = [0; 14];
+                stream.read_exact(&mut request).unwrap();
+                assert_eq!(&request, b"*1\r\n$4\r\nPING\r\n");
+                stream.write_all(b"+PONG\r\n").unwrap();
+            });
+            let mut conn = RedisConnection::builder(path.to_str().unwrap()).connector(UnixConnector).connect().unwrap();
+            assert_eq!(conn.request(b"*1\r\n$4\r\nPING\r\n").unwrap(), Resp3Value::SimpleString("PONG".to_string()));
+            assert!(conn.peer_address().is_err());
+            server.join().unwrap();
+            std::fs::remove_file(&path).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_connection_events() {
+        use crate::resp3::utils::connection_events::{ConnectionEvent, ConnectionEventKind};
+        use std::sync::{Arc, Mutex};
+
+        let events: Arc<Mutex<Vec<ConnectionEvent>>> = Arc::default();
+        let kinds = || events.lock().unwrap().drain(..).map(|event| (event.kind, event.cause)).collect::<Vec<_>>();
+        let server = MockServer::start().unwrap();
+        let recorded = events.clone();
+        let builder = RedisConnection::builder(&server.address()).events(move |event: &ConnectionEvent| recorded.lock().unwrap().push(event.clone()));
+
+        // Step 1: Connecting and reconnecting, with the endpoint and the time of each event
+        let before = std::time::SystemTime::now();
+        let mut conn = builder.clone().connect().unwrap();
+        let connected = events.lock().unwrap()[0].clone();
+        assert_eq!((connected.kind, connected.endpoint.as_str()), (ConnectionEventKind::Connected, server.address().as_str()));
+        assert!(connected.at >= before);
+        conn.reconnect().unwrap();
+        assert_eq!(kinds(), [(ConnectionEventKind::Connected, None), (ConnectionEventKind::Reconnecting, None), (ConnectionEventKind::Connected, None)]);
+
+        // Step 2: A stream closed by the server is reported once, its cause carried by the reconnect
+        server.inject(Fault::Disconnect);
+        assert!(GetCommand::new("test_events".to_string()).query(&mut conn).is_err());
+        assert!(GetCommand::new("test_events".to_string()).query(&mut conn).is_err());
+        let cause = Some("Connection closed by Redis server".to_string());
+        assert_eq!(kinds(), [(ConnectionEventKind::Disconnected, cause.clone())]);
+        conn.reconnect().unwrap();
+        assert_eq!(kinds(), [(ConnectionEventKind::Reconnecting, cause), (ConnectionEventKind::Connected, None)]);
+
+        // Step 3: Entering and leaving the subscribed state
+        let confirmation = |kind: &str, count| Resp3Value::Array(vec![Resp3Value::BulkString(kind.as_bytes().to_vec()), Resp3Value::BulkString(b"news".to_vec()), Resp3Value::Integer(count)]);
+        server.reply("SUBSCRIBE", confirmation("subscribe", 1));
+        server.reply("UNSUBSCRIBE", confirmation("unsubscribe", 0));
+        conn.request(b"*2\r\n$9\r\nSUBSCRIBE\r\n$4\r\nnews\r\n").unwrap();
+        conn.request(b"*2\r\n$11\r\nUNSUBSCRIBE\r\n$4\r\nnews\r\n").unwrap();
+        assert_eq!(kinds(), [(ConnectionEventKind::Subscribed, None), (ConnectionEventKind::Unsubscribed, None)]);
+
+        // Step 4: Closing is a disconnect asked for, without a cause
+        conn.close().unwrap();
+        drop(conn);
+        assert_eq!(kinds(), [(ConnectionEventKind::Disconnected, None)]);
+    }
+
+    #[test]
+    fn test_parser_fuzzing() {
+        // Step 1: Nesting past the limit is an error instead of a stack overflow
+        let nested = "*1\r\n".repeat(MAX_NESTING + 1) + ":1\r\n";
+        assert!(matches!(parse_response(nested.as_bytes()), Err(Resp3Error::Protocol(_))));
+        let nested = "*1\r\n".repeat(MAX_NESTING) + ":1\r\n";
+        assert!(parse_response(nested.as_bytes()).is_ok());
+        fuzzing::parse_reply("*1\r\n".repeat(100_000).as_bytes());
+
+        // Step 2: Mutations of valid replies hold the invariants of the fuzz target
+        let seeds: [&[u8]; 5] = [b"+OK\r\n", b"$5\r\nhello\r\n", b"*2\r\n:1\r\n$-1\r\n", b"-ERR oops\r\n", b"*-1\r\n"];
+        let symbols = b"\r\n*$:-+0";
+        let mut random: u64 = 0x2545_f491_4f6c_dd1d;
+        for _ in 0..20_000 {
+            random ^= random << 13;
+            random ^= random >> 7;
+            random ^= random << 17;
+            let mut input = seeds[random as usize % seeds.len()].to_vec();
+            let position = (random >> 8) as usize % input.len();
+            match (random >> 40) % 3 {
+                0 => input[position] = (random >> 16) as u8,
+                1 => input.truncate(position),
+                _ => input.insert(position, symbols[(random >> 16) as usize % symbols.len()]),
+            }
+            fuzzing::parse_reply(&input);
+        }
+    }
+
+    #[test]
+    fn test_protocol_test_vectors() {
+        for vector in testvectors::all() {
+            // Step 1: The parser agrees with every vector
+            let frame = parse_frame(vector.frame);
+            match &vector.outcome {
+                Outcome::Value { value: Resp3Value::Double(expected), .. } if expected.is_nan() => {
+                    assert!(matches!(frame, Ok(Some((Resp3Value::Double(double), _))) if double.is_nan()), "{}: {:?}", vector.name, frame);
+                    assert_eq!(Resp3Value::Double(f64::NAN).to_bytes(), vector.frame);
+                }
+                Outcome::Value { value, canonical } => {
+                    assert_eq!(frame.as_ref().unwrap(), &Some((value.clone(), vector.frame.len())), "{}", vector.name);
+                    // Step 2: Encoding gives the canonical frames back
+                    if *canonical {
+                        assert_eq!(value.to_bytes(), vector.frame, "{}", vector.name);
+                    }
+                }
+                Outcome::Incomplete => assert!(matches!(frame, Ok(None)), "{}: {:?}", vector.name, frame),
+                Outcome::Invalid => assert!(matches!(frame, Err(Resp3Error::Protocol(_))), "{}: {:?}", vector.name, frame),
+            }
+            // Step 3: And the invariants of the fuzz target hold on them
+            fuzzing::parse_reply(vector.frame);
+        }
+    }
+
+    #[test]
+    fn test_resp3_numbers() {
+        // Step 1: INCRBYFLOAT replies with a string over RESP2
+        let mut conn = RedisConnection::new("127.0.0.1:6379");
+        DelCommand::new(["TEST_FLOAT"]).execute(&mut conn).unwrap();
+        assert_eq!(IncrByFloatCommand::new("test_float".to_string(), 10.5).query(&mut conn).unwrap(), 10.5);
+        assert_eq!(IncrByFloatCommand::new("test_float".to_string(), -0.25).query(&mut conn).unwrap(), 10.25);
+
+        // Step 2: And with a double over RESP3, infinities included
+        let server = MockServer::start().unwrap();
+        let mut conn = RedisConnection::new(&server.address());
+        server.reply_once("INCRBYFLOAT", Resp3Value::Double(3.5));
+        server.reply_once("INCRBYFLOAT", Resp3Value::Double(f64::NEG_INFINITY));
+        assert_eq!(IncrByFloatCommand::new("test_float".to_string(), 1.0).query(&mut conn).unwrap(), 3.5);
+        assert_eq!(IncrByFloatCommand::new("test_float".to_string(), 1.0).query(&mut conn).unwrap(), f64::NEG_INFINITY);
+        assert!(f64::from_resp3_value(Resp3Value::Double(f64::NAN)).unwrap().is_nan());
+        assert_eq!(server.commands()[0], vec!["INCRBYFLOAT", "TEST_FLOAT", "1"]);
+
+        // Step 3: Big numbers keep all their digits, and convert to i64 only when they fit
+        let digits = "-3492890328409238509324850943850943825024385";
+        assert_eq!(String::from_resp3_value(Resp3Value::BigNumber(digits.to_string())).unwrap(), digits);
+        assert_eq!(i64::from_resp3_value(Resp3Value::BigNumber("42".to_string())).unwrap(), 42);
+        assert!(i64::from_resp3_value(Resp3Value::BigNumber(digits.to_string())).is_err());
+        #[cfg(feature="num-bigint")]
+        {
+            let number = num_bigint::BigInt::from_resp3_value(Resp3Value::BigNumber(digits.to_string())).unwrap();
+            assert_eq!(number.to_string(), digits);
+            assert_eq!(num_bigint::BigInt::from_resp3_value(Resp3Value::Integer(7)).unwrap(), num_bigint::BigInt::from(7));
+        }
+    }
+
+    #[test]
+    fn test_verbatim_strings() {
+        // Step 1: The format of a verbatim reply is kept next to its text
+        let server = MockServer::start().unwrap();
+        let mut conn = RedisConnection::new(&server.address());
+        let lolwut = Resp3Value::VerbatimString { format: "txt".to_string(), text: "Redis ver. 7.2.4\n".to_string() };
+        server.reply_once("GET", lolwut.clone());
+        assert_eq!(GetCommand::new("test_verbatim".to_string()).execute(&mut conn).unwrap(), lolwut);
+
+        // Step 2: It reads as a string like the bulk string RESP2 would send instead
+        server.reply_once("GET", Resp3Value::VerbatimString { format: "mkd".to_string(), text: "# Title".to_string() });
+        let reply = GetCommand::new("test_verbatim".to_string()).execute(&mut conn).unwrap();
+        assert_eq!(String::from_resp3_value(reply).unwrap(), "# Title");
+        assert_eq!(lolwut.as_str(), Some("Redis ver. 7.2.4\n"));
+
+        // Step 3: The text has to follow a three characters format
+        assert!(parse_frame(b"=6\r\nplain!\r\n").is_err());
+    }
+
+    #[test]
+    fn test_map_replies() {
+        // Step 1: A RESP3 map keeps the order the server sent
+        let server = MockServer::start().unwrap();
+        let mut conn = RedisConnection::new(&server.address());
+        let entries = [("server", "redis"), ("version", "7.2.4"), ("proto", "3"), ("mode", "standalone")];
+        let hello: Resp3Map = entries.iter().map(|(key, value)| (Resp3Value::BulkString(key.as_bytes().to_vec()), Resp3Value::BulkString(value.as_bytes().to_vec()))).collect();
+        server.reply("CONFIG", Resp3Value::Map(hello.clone()));
+        let reply = ConfigGetCommand::new("*".to_string()).execute(&mut conn).unwrap();
+        let map = reply.as_map().unwrap();
+        let keys: Vec<&str> = map.keys().filter_map(Resp3Value::as_str).collect();
+        assert_eq!(keys, vec!["server", "version", "proto", "mode"]);
+
+        // Step 2: With typed getters by key
+        assert_eq!(map.get_str("version"), Some("7.2.4"));
+        assert_eq!(map.get_i64("proto"), Some(3));
+        assert_eq!(map.get_i64("server"), None);
+        assert_eq!(map.get("missing"), None);
+        assert_eq!(map.get_as::<String>("mode").unwrap().as_deref(), Some("standalone"));
+
+        // Step 3: Typed commands still decode it, and an ordered map decodes from RESP2 arrays too
+        let parameters = ConfigGetCommand::new("*".to_string()).query(&mut conn).unwrap();
+        assert_eq!(parameters.get("mode").map(String::as_str), Some("standalone"));
+        let flat = Resp3Value::Array(vec![Resp3Value::BulkString(b"a".to_vec()), Resp3Value::Integer(1)]);
+        assert_eq!(Resp3Map::from_resp3_value(flat).unwrap().get_i64("a"), Some(1));
+        assert_eq!(Resp3Value::Map(hello).to_bytes()[..4], *b"%4\r\n");
+    }
+
+    #[test]
+    fn test_nil_replies() {
+        // Step 1: GET decodes a missing key to None instead of the raw nil reply
+        let mut conn = RedisConnection::new("127.0.0.1:6379");
+        DelCommand::new(["TEST_NIL"]).execute(&mut conn).unwrap();
+        assert_eq!(GetCommand::new("test_nil".to_string()).query(&mut conn).unwrap(), None);
+        SetCommand::new("test_nil".to_string(), "value".to_string()).execute(&mut conn).unwrap();
+        assert_eq!(GetCommand::new("test_nil".to_string()).query(&mut conn).unwrap().as_deref(), Some("VALUE"));
+
+        // Step 2: The RESP3 null reads the same as the RESP2 ones
+        for frame in [&b"_\r\n"[..], b"$-1\r\n", b"*-1\r\n"] {
+            assert_eq!(Option::<String>::from_resp3_value(parse_response(frame).unwrap()).unwrap(), None);
+        }
+        let reply = parse_response(b"*3\r\n$1\r\na\r\n_\r\n$1\r\nb\r\n").unwrap();
+        assert_eq!(Vec::<Option<String>>::from_resp3_value(reply).unwrap(), vec![Some("a".to_string()), None, Some("b".to_string())]);
+    }
+
+    #[test]
+    fn test_error_classification() {
+        // Step 1: Error replies are split into their code and message
+        let mut conn = RedisConnection::new("127.0.0.1:6379");
+        DelCommand::new(["TEST_ERRORS"]).execute(&mut conn).unwrap();
+        PushCommand::right("test_errors".to_string(), ["a"]).execute(&mut conn).unwrap();
+        let err = GetCommand::new("test_errors".to_string()).execute(&mut conn).unwrap_err();
+        let server_error = err.server_error().unwrap();
+        assert_eq!(server_error.code, ErrorCode::WrongType);
+        assert_eq!(server_error.message, "Operation against a key holding the wrong kind of value");
+        assert_eq!(err.to_string(), "Server error: WRONGTYPE Operation against a key holding the wrong kind of value");
+        let err = conn.request(b"*1\r\n$7\r\nNOTACMD\r\n").unwrap_err();
+        assert_eq!(err.server_error().unwrap().code, ErrorCode::Err);
+
+        // Step 2: Redirections carry the slot and the node to go to
+        let server = MockServer::start().unwrap();
+        let mut conn = RedisConnection::new(&server.address());
+        server.reply_once("GET", Resp3Value::Error("MOVED 3999 127.0.0.1:6381".to_string()));
+        let err = GetCommand::new("test_errors".to_string()).execute(&mut conn).unwrap_err();
+        assert_eq!(err.server_error().unwrap().code, ErrorCode::Moved { slot: 3999, address: "127.0.0.1:6381".to_string() });
+        assert_eq!(ServerError::parse("ASK 12 [::1]:7000").code, ErrorCode::Ask { slot: 12, address: "[::1]:7000".to_string() });
+
+        // Step 3: The other codes, and replies without one
+        for (reply, code) in [
+            ("LOADING Redis is loading the dataset in memory", ErrorCode::Loading),
+            ("READONLY You can't write against a read only replica.", ErrorCode::ReadOnly),
+            ("NOAUTH Authentication required.", ErrorCode::NoAuth),
+            ("BUSY Redis is busy running a script", ErrorCode::Other("BUSY".to_string())),
+            ("MOVED somewhere", ErrorCode::Other("MOVED".to_string())),
+            ("user_script:1: boom", ErrorCode::None),
+        ] {
+            let err = ServerError::parse(reply);
+            assert_eq!(err.code, code, "{}", reply);
+            assert_eq!(err.to_string(), reply);
+        }
+        assert_eq!(ServerError::parse("ERR").to_string(), "ERR");
+    }
+
+    #[test]
+    fn test_executor_stats() {
+        // Step 1: Every command is counted under its name, with its bytes and latency
+        let mut executor = CommandExecutor::new("127.0.0.1:6379");
+        executor.query(DelCommand::new(["TEST_STATS"])).unwrap();
+        executor.execute(SetCommand::new("test_stats".to_string(), "value".to_string()));
+        assert_eq!(executor.query(GetCommand::new("test_stats".to_string())).unwrap(), Some("VALUE".to_string()));
+        executor.query(GetCommand::new("test_stats".to_string())).unwrap();
+        let stats = executor.stats().snapshot();
+        assert_eq!(stats.total_commands(), 4);
+        assert_eq!(stats.command("get").unwrap().count, 2);
+        assert_eq!(stats.command("SET").unwrap().count, 1);
+        assert!(stats.command("INCR").is_none());
+        // DEL and GET of test_stats are 30 bytes each, SET 41, and the reply of GET 11
+        assert_eq!(stats.bytes_sent, 30 + 41 + 2 * 30);
+        assert_eq!(stats.bytes_received, 4 + 5 + 2 * 11);
+        assert!(stats.average_latency() > Duration::ZERO);
+        assert!(stats.command("GET").unwrap().average_latency() <= stats.command("GET").unwrap().total_latency);
+
+        // Step 2: Errors are counted per command and per kind, the ones of execute included
+        executor.execute(IncrCommand::new("test_stats".to_string()));
+        executor.query(PushCommand::right("test_stats".to_string(), ["a"])).unwrap_err();
+        let stats = executor.stats().snapshot();
+        assert_eq!(stats.command("INCR").unwrap().errors, 1);
+        assert_eq!(stats.command("RPUSH").unwrap().errors, 1);
+        assert_eq!(stats.command("GET").unwrap().errors, 0);
+        assert_eq!(stats.errors.get("ERR"), Some(&1));
+        assert_eq!(stats.errors.get("WRONGTYPE"), Some(&1));
+        assert_eq!(stats.total_errors(), 2);
+
+        // Step 3: The wrappers count what they send, the stats are shared with other threads
+        let shared = executor.stats().clone();
+        let mut cache = CachedExecutor::new(executor, 10, Duration::from_secs(60));
+        cache.query(GetCommand::new("test_stats".to_string())).unwrap();
+        cache.query(GetCommand::new("test_stats".to_string())).unwrap();
+        let count = std::thread::spawn(move || shared.snapshot().command("GET").unwrap().count).join().unwrap();
+        assert_eq!(count, 3);
+
+        // Step 4: Reset starts again from zero
+        let executor = cache.into_inner();
+        executor.stats().reset();
+        assert_eq!(executor.stats().snapshot(), StatsSnapshot::default());
+    }
+
+    #[test]
+    fn test_graceful_close() {
+        // Step 1: Close waits for QUIT, so the fire and forget commands sent before are applied
+        let mut conn = RedisConnection::new("127.0.0.1:6379");
+        DelCommand::new(["TEST_CLOSE"]).execute(&mut conn).unwrap();
+        conn.send_no_reply(&SetCommand::new("test_close".to_string(), "a".to_string())).unwrap();
+        conn.close().unwrap();
+        let mut other = RedisConnection::new("127.0.0.1:6379");
+        assert_eq!(GetCommand::new("test_close".to_string()).query(&mut other).unwrap(), Some("A".to_string()));
+
+        // Step 2: Closing again is a no-op, requests fail until reconnect
+        conn.close().unwrap();
+        assert!(matches!(GetCommand::new("test_close".to_string()).execute(&mut conn), Err(Resp3Error::Io(_))));
+        conn.reconnect().unwrap();
+        assert_eq!(GetCommand::new("test_close".to_string()).query(&mut conn).unwrap(), Some("A".to_string()));
+
+        // Step 3: A connection the server already closed closes without error
+        conn.request(b"*1\r\n$4\r\nQUIT\r\n").unwrap();
+        conn.close().unwrap();
+
+        // Step 4: Subscribed connections skip the messages still in flight
+        struct SubscribeCommand;
+        impl Command for SubscribeCommand {
+            fn get_parts(&self) -> Vec<&str> {
+                vec!["SUBSCRIBE", "test_close_channel"]
+            }
+        }
+        let mut subscriber = RedisConnection::new("127.0.0.1:6379");
+        SubscribeCommand.execute(&mut subscriber).unwrap();
+        PublishCommand::new("test_close_channel".to_string(), "hello".to_string()).query(&mut other).unwrap();
+        subscriber.close().unwrap();
+
+        // Step 5: A server not replying to QUIT does not hold close, nor Drop, longer than a second
+        let server = MockServer::start().unwrap();
+        let mut conn = RedisConnection::new(&server.address());
+        server.inject(Fault::Delay(Duration::from_secs(10)));
+        let started = std::time::Instant::now();
+        assert!(conn.close().unwrap_err().is_timeout());
+        let conn = RedisConnection::new(&server.address());
+        server.inject(Fault::Delay(Duration::from_secs(10)));
+        drop(conn);
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[cfg(feature="deadpool")]
+    #[test]
+    fn test_deadpool_manager() {
+        use crate::resp3::pool::{DeadpoolPool, RedisConnectionManager};
+
+        // Step 1: Connections are opened with the builder's options and reused
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_time().build().unwrap();
+        let manager = RedisConnectionManager::new(RedisConnection::builder("127.0.0.1:6379").timeout(Duration::from_secs(5)));
+        let pool = DeadpoolPool::builder(manager).max_size(2).build().unwrap();
+        runtime.block_on(async {
+            let mut conn = pool.get().await.unwrap();
+            SetCommand::new("test_deadpool".to_string(), "a".to_string()).execute(&mut conn).unwrap();
+            drop(conn);
+            let mut conn = pool.get().await.unwrap();
+            assert_eq!(GetCommand::new("test_deadpool".to_string()).query(&mut conn).unwrap(), Some("A".to_string()));
+            assert_eq!(pool.status().size, 1);
+
+            // Step 2: A connection given back subscribed is replaced by a new one
+            struct SubscribeCommand;
+            impl Command for SubscribeCommand {
+                fn get_parts(&self) -> Vec<&str> {
+                    vec!["SUBSCRIBE", "test_deadpool_channel"]
+                }
+            }
+            SubscribeCommand.execute(&mut conn).unwrap();
+            drop(conn);
+            let conn = pool.get().await.unwrap();
+            assert_eq!(conn.state(), ConnectionState::Normal);
+        });
+    }
+
+    #[cfg(feature="bb8")]
+    #[test]
+    fn test_bb8_manager() {
+        use crate::resp3::pool::RedisConnectionManager;
+
+        // Step 1: Connections are checked with PING before being handed out
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_time().build().unwrap();
+        runtime.block_on(async {
+            let manager = RedisConnectionManager::new(RedisConnection::builder("127.0.0.1:6379"));
+            let pool = bb8::Pool::builder().max_size(2).test_on_check_out(true).build(manager).await.unwrap();
+            let mut conn = pool.get().await.unwrap();
+            SetCommand::new("test_bb8".to_string(), "a".to_string()).execute(&mut conn).unwrap();
+            drop(conn);
+            let mut conn = pool.get().await.unwrap();
+            assert_eq!(GetCommand::new("test_bb8".to_string()).query(&mut conn).unwrap(), Some("A".to_string()));
+
+            // Step 2: Connections given back inside MULTI are dropped
+            conn.request(b"*1\r\n$5\r\nMULTI\r\n").unwrap();
+            drop(conn);
+            let conn = pool.get().await.unwrap();
+            assert_eq!(conn.state(), ConnectionState::Normal);
+        });
+    }
+
+    #[cfg(feature="r2d2")]
+    #[test]
+    fn test_r2d2_manager() {
+        // Step 1: Connections are checked with PING before being handed out
+        let manager = crate::resp3::pool::RedisConnectionManager::new(RedisConnection::builder("127.0.0.1:6379"));
+        let pool = r2d2::Pool::builder().max_size(2).build(manager).unwrap();
+        let mut conn = pool.get().unwrap();
+        SetCommand::new("test_r2d2".to_string(), "a".to_string()).execute(&mut conn).unwrap();
+        drop(conn);
+
+        // Step 2: The pool is shared between threads
+        let handles: Vec<_> = (0..4).map(|_| {
+            let pool = pool.clone();
+            std::thread::spawn(move || GetCommand::new("test_r2d2".to_string()).query(&mut pool.get().unwrap()).unwrap())
+        }).collect();
+        for handle in handles {
+            assert_eq!(handle.join().unwrap(), Some("A".to_string()));
+        }
+        assert!(pool.state().connections <= 2);
+
+        // Step 3: Connections given back inside MULTI are dropped
+        let mut conn = pool.get().unwrap();
+        conn.request(b"*1\r\n$5\r\nMULTI\r\n").unwrap();
+        drop(conn);
+        assert_eq!(pool.get().unwrap().state(), ConnectionState::Normal);
+    }
+
+    #[cfg(feature="tokio")]
+    #[test]
+    fn test_tokio_bridge() {
+        use crate::resp3::tokio_bridge::{BlockingExecutor, PubSubStream};
+
+        // Step 1: Subscribed events arrive through the stream (ensure Redis is running on localhost:6379)
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_time().build().unwrap();
+        let builder = RedisConnection::builder("127.0.0.1:6379").timeout(Duration::from_millis(200));
+        let mut pubsub = PubSub::new(builder.clone().connect().unwrap());
+        pubsub.subscribe(&["test_tokio_channel"]).unwrap();
+        let mut conn = RedisConnection::new("127.0.0.1:6379");
+        DelCommand::new(["test_tokio_list"]).execute(&mut conn).unwrap();
+        runtime.block_on(async {
+            let mut events = PubSubStream::new(pubsub, 16);
+            PublishCommand::new("test_tokio_channel".to_string(), "hello".to_string()).query(&mut conn).unwrap();
+            let event = tokio::time::timeout(Duration::from_secs(2), events.next_event()).await.unwrap();
+            assert_eq!(event.unwrap().unwrap(), PubSubEvent::Message { channel: "TEST_TOKIO_CHANNEL".to_string(), payload: b"HELLO".to_vec() });
+
+            // Step 2: Quiet channels time out on the side of the caller, the stream goes on
+            assert!(tokio::time::timeout(Duration::from_millis(300), events.next_event()).await.is_err());
+            PublishCommand::new("test_tokio_channel".to_string(), "again".to_string()).query(&mut conn).unwrap();
+            assert!(matches!(events.next_event().await, Some(Ok(PubSubEvent::Message { .. }))));
+
+            // Step 3: A blocking pop given up on fails with a timeout, a later one gets the element
+            let executor = BlockingExecutor::new(builder.clone().timeout(Duration::from_secs(5)));
+            let pop = BlmPopCommand::new(Duration::from_millis(500), vec!["test_tokio_list".to_string()], ListEnd::Left);
+            let err = executor.query_timeout(pop, Duration::from_millis(50)).await.unwrap_err();
+            assert!(err.is_timeout());
+            // Its BLMPOP still waits on the server, an element pushed now would go to it
+            tokio::time::sleep(Duration::from_millis(600)).await;
+            PushCommand::right("test_tokio_list".to_string(), ["a"]).execute(&mut conn).unwrap();
+            let pop = BlmPopCommand::new(Duration::from_secs(1), vec!["test_tokio_list".to_string()], ListEnd::Left);
+            assert_eq!(executor.query(pop).await.unwrap(), Some(("TEST_TOKIO_LIST".to_string(), vec!["A".to_string()])));
+        });
+    }
+
+    #[cfg(feature="tokio")]
+    #[test]
+    fn test_multiplexed_connection() {
+        use crate::resp3::multiplexed::MultiplexedConnection;
+
+        // Step 1: RESP3 push messages parse as arrays
+        let push = parse_response(b">3\r\n$7\r\nmessage\r\n$2\r\nch\r\n$2\r\nhi\r\n").unwrap();
+        assert_eq!(push, Resp3Value::Array(vec![Resp3Value::BulkString(b"message".to_vec()), Resp3Value::BulkString(b"ch".to_vec()), Resp3Value::BulkString(b"hi".to_vec())]));
+
+        // Step 2: Tasks sharing the connection each get the reply of their own command
+        let server = MockServer::start().unwrap();
+        server.reply("HELLO", Resp3Value::Map(Resp3Map::new()));
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_time().build().unwrap();
+        let conn = MultiplexedConnection::connect(RedisConnection::builder(&server.address()).timeout(Duration::from_secs(2))).unwrap();
+        runtime.block_on(async {
+            let tasks: Vec<_> = (0..50).map(|n| {
+                let conn = conn.clone();
+                tokio::spawn(async move {
+                    conn.request(&SetCommand::new(format!("key{}", n), format!("value{}", n))).await.unwrap();
+                    conn.query(&GetCommand::new(format!("key{}", n))).await.unwrap()
+                })
+            }).collect();
+            for (n, task) in tasks.into_iter().enumerate() {
+                assert_eq!(task.await.unwrap(), Some(format!("VALUE{}", n)));
+            }
+            assert_eq!(server.connections(), 1);
+
+            // Step 3: A task giving up leaves its reply behind, the next one is not shifted
+            server.inject(Fault::Delay(Duration::from_millis(200)));
+            assert!(tokio::time::timeout(Duration::from_millis(50), conn.query(&GetCommand::new("key1".to_string()))).await.is_err());
+            assert_eq!(conn.query(&GetCommand::new("key2".to_string())).await.unwrap(), Some("VALUE2".to_string()));
+            assert_eq!(conn.in_flight(), 0);
+
+            // Step 4: A reply that does not parse fails its command only, the connection goes on
+            server.reply_once("GET", Resp3Value::VerbatimString { format: "x".to_string(), text: "bad".to_string() });
+            assert!(matches!(conn.query(&GetCommand::new("key1".to_string())).await, Err(Resp3Error::Protocol(_))));
+            assert_eq!(conn.query(&GetCommand::new("key2".to_string())).await.unwrap(), Some("VALUE2".to_string()));
+
+            // Step 5: Commands changing the state of the connection for everyone are refused
+            struct MultiCommand;
+            impl Command for MultiCommand {
+                fn get_parts(&self) -> Vec<&str> {
+                    vec!["MULTI"]
+                }
+            }
+            let err = conn.request(&MultiCommand).await.unwrap_err();
+            assert!(matches!(err, Resp3Error::InvalidState(_)));
+
+            // Step 6: The connection dropped, the waiting and later commands fail
+            server.inject(Fault::Disconnect);
+            assert!(matches!(conn.query(&GetCommand::new("key1".to_string())).await, Err(Resp3Error::Io(_))));
+            assert!(matches!(conn.query(&GetCommand::new("key2".to_string())).await, Err(Resp3Error::Io(_))));
+            assert!(conn.subscribe(&["test_multiplexed_channel"]).is_err());
+        });
+
+        // Step 7: Pub/Sub messages go to the receivers while commands go on (ensure Redis is running on localhost:6379)
+        let conn = MultiplexedConnection::connect(RedisConnection::builder("127.0.0.1:6379")).unwrap();
+        let mut publisher = RedisConnection::new("127.0.0.1:6379");
+        runtime.block_on(async {
+            let mut events = conn.events();
+            conn.subscribe(&["test_multiplexed_channel"]).unwrap();
+            SetCommand::new("test_multiplexed_key".to_string(), "a".to_string()).execute(&mut publisher).unwrap();
+            assert_eq!(conn.query(&GetCommand::new("test_multiplexed_key".to_string())).await.unwrap(), Some("A".to_string()));
+            PublishCommand::new("test_multiplexed_channel".to_string(), "hello".to_string()).query(&mut publisher).unwrap();
+            let event = tokio::time::timeout(Duration::from_secs(2), events.recv()).await.unwrap().unwrap();
+            assert_eq!(event, PubSubEvent::Message { channel: "TEST_MULTIPLEXED_CHANNEL".to_string(), payload: b"HELLO".to_vec() });
+            assert_eq!(conn.query(&GetCommand::new("test_multiplexed_key".to_string())).await.unwrap(), Some("A".to_string()));
+        });
+    }
+
+    #[test]
+    fn test_cache() {
+        // Step 1: A miss computes and stores the value, as it is, a hit returns it
+        let mut cache = Cache::new(RedisConnection::new("127.0.0.1:6379"));
+        cache.forget("test_cache").unwrap();
+        let value = cache.remember("test_cache", Duration::from_secs(60), || "Computed".to_string()).unwrap();
+        assert_eq!(value, "Computed");
+        let value = cache.remember("test_cache", Duration::from_secs(60), || panic!("Should be cached")).unwrap();
+        assert_eq!(value, "Computed");
+        let mut conn = RedisConnection::new("127.0.0.1:6379");
+        let pttl = conn.request(b"*2\r\n$4\r\nPTTL\r\n$10\r\nTEST_CACHE\r\n").unwrap().as_i64().unwrap();
+        assert!(pttl > 0 && pttl <= 60_000);
+
+        // Step 2: Without a TTL the value does not expire, forget drops it
+        cache.forget("test_cache").unwrap();
+        assert_eq!(cache.get_or_insert_with("test_cache", || "forever".to_string()).unwrap(), "forever");
+        assert_eq!(conn.request(b"*2\r\n$4\r\nPTTL\r\n$10\r\nTEST_CACHE\r\n").unwrap().as_i64(), Some(-1));
+        assert!(cache.forget("test_cache").unwrap());
+        assert!(!cache.forget("test_cache").unwrap());
+        assert_eq!(cache.get("test_cache").unwrap(), None);
+
+        // Step 3: Clients missing at the same time wait for the one computing the value
+        cache.forget("test_cache_stampede").unwrap();
+        let computed = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let handles: Vec<_> = (0..4).map(|_| {
+            let computed = computed.clone();
+            std::thread::spawn(move || {
+                let mut cache = Cache::new(RedisConnection::new("127.0.0.1:6379")).poll_interval(Duration::from_millis(5));
+                cache.remember("test_cache_stampede", Duration::from_secs(60), || {
+                    computed.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                    std::thread::sleep(Duration::from_millis(100));
+                    "slow".to_string()
+                }).unwrap()
+            })
+        }).collect();
+        for handle in handles {
+            assert_eq!(handle.join().unwrap(), "slow");
+        }
+        assert_eq!(computed.load(std::sync::atomic::Ordering::SeqCst), 1);
+        cache.forget("test_cache_stampede").unwrap();
+
+        // Step 4: After the lock TTL the waiting clients compute the value themselves
+        Lock::acquire(&mut conn, "test_cache:lock", Duration::from_secs(10)).unwrap();
+        let mut cache = cache.lock_ttl(Duration::from_millis(50));
+        assert_eq!(cache.remember("test_cache", Duration::from_secs(60), || "anyway".to_string()).unwrap(), "anyway");
+        cache.forget("test_cache").unwrap();
+        cache.forget("test_cache:lock").unwrap();
+    }
+
+    #[cfg(feature="sessions")]
+    #[test]
+    fn test_session_store() {
+        use crate::resp3::commands::keys::PExpireCommand;
+        use crate::resp3::session::SessionStore;
+        use std::collections::BTreeMap;
+
+        // Step 1: Sessions round trip as JSON, with their case
+        let mut store = SessionStore::new(RedisConnection::new("127.0.0.1:6379"), Duration::from_secs(60)).prefix("test_session:");
+        let data = BTreeMap::from([("user".to_string(), "Alice".to_string())]);
+        let id = store.create(&data).unwrap();
+        assert_eq!(store.load::<BTreeMap<String, String>>(&id).unwrap(), Some(data.clone()));
+
+        // Step 2: Loading slides the expiration, saving only updates existing sessions
+        let mut conn = RedisConnection::new("127.0.0.1:6379");
+        let key = format!("test_session:{}", id);
+        PExpireCommand::new(key.clone(), 1_000).query(&mut conn).unwrap();
+        store.load::<BTreeMap<String, String>>(&id).unwrap();
+        let pttl = conn.request(format!("*2\r\n$4\r\nPTTL\r\n${}\r\n{}\r\n", key.len(), key.to_uppercase()).as_bytes()).unwrap().as_i64().unwrap();
+        assert!(pttl > 1_000);
+        let updated = BTreeMap::from([("user".to_string(), "Bob".to_string())]);
+        assert!(store.save(&id, &updated).unwrap());
+        assert_eq!(store.load::<BTreeMap<String, String>>(&id).unwrap(), Some(updated.clone()));
+        assert!(store.touch(&id).unwrap());
+
+        // Step 3: Destroyed sessions are gone
+        assert!(store.destroy(&id).unwrap());
+        assert!(!store.destroy(&id).unwrap());
+        assert_eq!(store.load::<BTreeMap<String, String>>(&id).unwrap(), None);
+        assert!(!store.save(&id, &updated).unwrap());
+        assert!(!store.touch(&id).unwrap());
+
+        // Step 4: Signed IDs are checked before going to Redis
+        let mut store = store.signed(b"secret");
+        let id = store.create(&data).unwrap();
+        let (raw_id, signature) = id.split_once('.').unwrap();
+        assert_eq!(signature.len(), 64);
+        assert_eq!(store.load::<BTreeMap<String, String>>(&id).unwrap(), Some(data.clone()));
+        assert_eq!(store.load::<BTreeMap<String, String>>(raw_id).unwrap(), None);
+        assert_eq!(store.load::<BTreeMap<String, String>>(&format!("{}.{}", raw_id, "0".repeat(64))).unwrap(), None);
+        let mut other = SessionStore::new(RedisConnection::new("127.0.0.1:6379"), Duration::from_secs(60)).prefix("test_session:").signed(b"other");
+        assert_eq!(other.load::<BTreeMap<String, String>>(&id).unwrap(), None);
+        assert!(!other.destroy(&id).unwrap());
+        assert!(store.destroy(&id).unwrap());
+    }
+
+    #[test]
+    fn test_reliable_queue() {
+        // Step 1: Jobs are reserved in the order they were pushed, with their case
+        let mut conn = RedisConnection::new("127.0.0.1:6379");
+        DelCommand::new(["TEST_QUEUE", "TEST_QUEUE:PROCESSING", "TEST_QUEUE:RESERVED"]).execute(&mut conn).unwrap();
+        let queue = ReliableQueue::new("test_queue");
+        queue.push(&mut conn, "Job 1").unwrap();
+        assert_eq!(queue.push(&mut conn, "Job 2").unwrap(), 2);
+        assert_eq!(queue.reserve(&mut conn).unwrap(), Some("Job 1".to_string()));
+        assert_eq!(queue.len(&mut conn).unwrap(), 1);
+        assert_eq!(queue.processing_len(&mut conn).unwrap(), 1);
+
+        // Step 2: Acked jobs are gone, requeued ones go to the end of the queue
+        assert!(queue.ack(&mut conn, "Job 1").unwrap());
+        assert!(!queue.ack(&mut conn, "Job 1").unwrap());
+        queue.push(&mut conn, "Job 3").unwrap();
+        assert_eq!(queue.reserve(&mut conn).unwrap(), Some("Job 2".to_string()));
+        assert!(queue.requeue(&mut conn, "Job 2").unwrap());
+        assert!(!queue.requeue(&mut conn, "Job 2").unwrap());
+        assert_eq!(queue.reserve(&mut conn).unwrap(), Some("Job 3".to_string()));
+        assert_eq!(queue.reserve(&mut conn).unwrap(), Some("Job 2".to_string()));
+        assert_eq!(queue.reserve(&mut conn).unwrap(), None);
+
+        // Step 3: Jobs reserved longer than the visibility timeout are put back first
+        assert_eq!(queue.reap(&mut conn).unwrap(), 0);
+        let queue = queue.visibility_timeout(Duration::from_millis(50));
+        queue.push(&mut conn, "Job 4").unwrap();
+        std::thread::sleep(Duration::from_millis(60));
+        assert_eq!(queue.reap(&mut conn).unwrap(), 2);
+        assert_eq!(queue.processing_len(&mut conn).unwrap(), 0);
+        assert_eq!(queue.reserve(&mut conn).unwrap(), Some("Job 3".to_string()));
+        assert_eq!(queue.reserve(&mut conn).unwrap(), Some("Job 2".to_string()));
+        assert_eq!(queue.reserve(&mut conn).unwrap(), Some("Job 4".to_string()));
+        for job in ["Job 2", "Job 3", "Job 4"] {
+            assert!(queue.ack(&mut conn, job).unwrap());
+        }
+        assert_eq!(queue.reap(&mut conn).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_leaderboard() {
+        // Step 1: Scores add up, in the numeric type of the leaderboard
+        let mut conn = RedisConnection::new("127.0.0.1:6379");
+        DelCommand::new(["TEST_LEADERBOARD"]).execute(&mut conn).unwrap();
+        let board = Leaderboard::<u64>::new("test_leaderboard");
+        assert_eq!(board.add_score(&mut conn, "alice", 10).unwrap(), 10);
+        assert_eq!(board.add_score(&mut conn, "alice", 5).unwrap(), 15);
+        for (member, score) in [("bob", 30), ("carol", 20), ("dave", 5), ("erin", 1)] {
+            board.set_score(&mut conn, member, score, false).unwrap();
+        }
+        assert_eq!(board.score_of(&mut conn, "alice").unwrap(), Some(15));
+        assert_eq!(board.score_of(&mut conn, "nobody").unwrap(), None);
+        assert_eq!(board.len(&mut conn).unwrap(), 5);
+
+        // Step 2: Ranks start at the highest score
+        assert_eq!(board.rank_of(&mut conn, "bob").unwrap(), Some(0));
+        assert_eq!(board.rank_of(&mut conn, "alice").unwrap(), Some(2));
+        assert_eq!(board.rank_of(&mut conn, "nobody").unwrap(), None);
+        let top = board.top_n(&mut conn, 2).unwrap();
+        assert_eq!(top, vec![
+            LeaderboardEntry { rank: 0, member: "BOB".to_string(), score: 30 },
+            LeaderboardEntry { rank: 1, member: "CAROL".to_string(), score: 20 },
+        ]);
+        assert!(board.top_n(&mut conn, 0).unwrap().is_empty());
+        assert_eq!(board.top_n(&mut conn, 100).unwrap().len(), 5);
+
+        // Step 3: The window around a member stops at both ends
+        let around: Vec<(u64, String)> = board.around_member(&mut conn, "alice", 1).unwrap().into_iter().map(|entry| (entry.rank, entry.member)).collect();
+        assert_eq!(around, vec![(1, "CAROL".to_string()), (2, "ALICE".to_string()), (3, "DAVE".to_string())]);
+        assert_eq!(board.around_member(&mut conn, "bob", 2).unwrap().len(), 3);
+        assert!(board.around_member(&mut conn, "nobody", 2).unwrap().is_empty());
+
+        // Step 4: Only higher scores replace the current one when asked, members can be removed
+        board.set_score(&mut conn, "bob", 3, true).unwrap();
+        assert_eq!(board.score_of(&mut conn, "bob").unwrap(), Some(30));
+        board.set_score(&mut conn, "bob", 3, false).unwrap();
+        assert_eq!(board.score_of(&mut conn, "bob").unwrap(), Some(3));
+        assert!(board.remove(&mut conn, "bob").unwrap());
+        assert!(!board.remove(&mut conn, "bob").unwrap());
+
+        // Step 5: Fractional scores with a float leaderboard
+        let board = Leaderboard::<f64>::new("test_leaderboard");
+        assert_eq!(board.add_score(&mut conn, "erin", 0.5).unwrap(), 1.5);
+        assert_eq!(board.top_n(&mut conn, 1).unwrap()[0].score, 20.0);
+
+        // Step 6: RESP3 sends the scores of a range as pairs
+        conn.request(b"*2\r\n$5\r\nHELLO\r\n$1\r\n3\r\n").unwrap();
+        assert_eq!(board.top_n(&mut conn, 1).unwrap()[0].member, "CAROL");
+        assert_eq!(board.score_of(&mut conn, "erin").unwrap(), Some(1.5));
+    }
+
+    #[test]
+    fn test_pubsub_resubscribe() {
+        // Step 1: Messages of channels and patterns, the names are sent in uppercase
+        let mut conn = RedisConnection::new("127.0.0.1:6379");
+        let mut subscriber_conn = RedisConnection::new("127.0.0.1:6379");
+        let client_id = subscriber_conn.request(b"*2\r\n$6\r\nCLIENT\r\n$2\r\nID\r\n").unwrap().as_i64().unwrap();
+        let mut subscriber = PubSub::new(subscriber_conn);
+        subscriber.subscribe(&["test_resub_a", "test_resub_b", "test_resub_a"]).unwrap();
+        subscriber.psubscribe(&["test_resub_p*"]).unwrap();
+        assert_eq!(subscriber.channels(), ["test_resub_a".to_string(), "test_resub_b".to_string()]);
+        PublishCommand::new("test_resub_b".to_string(), "one".to_string()).query(&mut conn).unwrap();
+        PublishCommand::new("test_resub_pattern".to_string(), "two".to_string()).query(&mut conn).unwrap();
+        assert_eq!(subscriber.next_event().unwrap(), PubSubEvent::Message { channel: "TEST_RESUB_B".to_string(), payload: b"ONE".to_vec() });
+        assert_eq!(subscriber.next_event().unwrap(), PubSubEvent::PatternMessage {
+            pattern: "TEST_RESUB_P*".to_string(),
+            channel: "TEST_RESUB_PATTERN".to_string(),
+            payload: b"TWO".to_vec(),
+        });
+
+        // Step 2: After the connection is killed the subscriptions come back, with an event
+        subscriber.unsubscribe(&["test_resub_b"]).unwrap();
+        let kill = format!("*4\r\n$6\r\nCLIENT\r\n$4\r\nKILL\r\n$2\r\nID\r\n${}\r\n{}\r\n", client_id.to_string().len(), client_id);
+        conn.request(kill.as_bytes()).unwrap();
+        assert_eq!(subscriber.next_event().unwrap(), PubSubEvent::Resubscribed);
+        let counts = PubSubNumSubCommand::new(vec!["test_resub_a".to_string(), "test_resub_b".to_string()]).query(&mut conn).unwrap();
+        assert_eq!(counts, vec![("TEST_RESUB_A".to_string(), 1), ("TEST_RESUB_B".to_string(), 0)]);
+        PublishCommand::new("test_resub_a".to_string(), "three".to_string()).query(&mut conn).unwrap();
+        PublishCommand::new("test_resub_pattern".to_string(), "four".to_string()).query(&mut conn).unwrap();
+        assert_eq!(subscriber.next_event().unwrap(), PubSubEvent::Message { channel: "TEST_RESUB_A".to_string(), payload: b"THREE".to_vec() });
+        assert!(matches!(subscriber.next_event().unwrap(), PubSubEvent::PatternMessage { payload, .. } if payload == b"FOUR"));
+
+        // Step 3: Payloads that are not UTF-8 arrive as they were published
+        conn.request(b"*3\r\n$7\r\nPUBLISH\r\n$12\r\nTEST_RESUB_A\r\n$2\r\n\xff\x00\r\n").unwrap();
+        let event = subscriber.next_event().unwrap();
+        assert_eq!(event, PubSubEvent::Message { channel: "TEST_RESUB_A".to_string(), payload: vec![0xff, 0x00] });
+        assert_eq!(event.payload(), Some(&[0xff, 0x00][..]));
+        assert_eq!(event.payload_str(), None);
+        let message = Resp3Value::Array(vec![
+            Resp3Value::BulkString(b"pmessage".to_vec()),
+            Resp3Value::BulkString(b"P*".to_vec()),
+            Resp3Value::BulkString(b"P\xff".to_vec()),
+            Resp3Value::BulkString(b"HI".to_vec()),
+        ]);
+        let event = PubSubEvent::from_message(message).unwrap();
+        assert_eq!(event, PubSubEvent::PatternMessage { pattern: "P*".to_string(), channel: "P\u{fffd}".to_string(), payload: b"HI".to_vec() });
+        assert_eq!(event.payload_str(), Some("HI"));
+    }
+
+    #[test]
+    fn test_streaming_pipeline() {
+        // Step 1: Thousands of commands go through with never more than the window in flight
+        let mut conn = RedisConnection::new("127.0.0.1:6379");
+        DelCommand::new(vec!["test_stream_counter".to_string()]).query(&mut conn).unwrap();
+        let mut pipeline = StreamingPipeline::new(&mut conn).window(16);
+        for index in 0..5000 {
+            pipeline.push(&SetCommand::new(format!("test_stream:{}", index % 100), format!("value{}", index))).unwrap();
+            assert!(pipeline.in_flight() <= 16);
+        }
+        pipeline.push(&IncrCommand::new("test_stream:0".to_string())).unwrap();
+        pipeline.push(&IncrCommand::new("test_stream_counter".to_string())).unwrap();
+        let summary = pipeline.finish().unwrap();
+        assert_eq!(summary.replies, 5002);
+        assert_eq!(summary.errors.len(), 1);
+        assert_eq!(summary.errors[0].0, 5000);
+        assert_eq!(summary.errors[0].1.code, ErrorCode::Err);
+        assert_eq!(GetCommand::new("test_stream:99".to_string()).query(&mut conn).unwrap(), Some("VALUE4999".to_string()));
+
+        // Step 2: Commands changing the state of the connection are rejected. A pipeline dropped
+        // early reads the replies of the batch it wrote and discards the one it did not
+        struct MultiCommand;
+        impl Command for MultiCommand {
+            fn get_parts(&self) -> Vec<&str> {
+                vec!["MULTI"]
+            }
+        }
+        let mut pipeline = StreamingPipeline::new(&mut conn).window(4);
+        assert!(matches!(pipeline.push(&MultiCommand), Err(Resp3Error::InvalidState(_))));
+        for _ in 0..3 {
+            pipeline.push(&IncrCommand::new("test_stream_counter".to_string())).unwrap();
+        }
+        drop(pipeline);
+        assert_eq!(GetCommand::new("test_stream_counter".to_string()).query(&mut conn).unwrap(), Some("3".to_string()));
+        DelCommand::new(vec!["test_stream_counter".to_string()]).query(&mut conn).unwrap();
+    }
+
+    #[test]
+    fn test_memory_scanner() {
+        // Step 1: Patterns match like in KEYS
+        assert!(glob_match(b"user:*", b"user:42"));
+        assert!(glob_match(b"h?llo", b"hello"));
+        assert!(glob_match(b"h[a-e]llo", b"hello"));
+        assert!(!glob_match(b"h[^e]llo", b"hello"));
+        assert!(glob_match(b"a\\*b", b"a*b"));
+        assert!(!glob_match(b"a\\*b", b"axb"));
+        assert!(!glob_match(b"user:*", b"session:1"));
+
+        // Step 2: TYPE and MEMORY USAGE of single keys
+        let mut conn = RedisConnection::new("127.0.0.1:6379");
+        let keys: Vec<String> = KeysCommand::new("test_mem_*".to_string()).query(&mut conn).unwrap();
+        if !keys.is_empty() {
+            DelCommand::new(keys).query(&mut conn).unwrap();
+        }
+        for index in 0..5 {
+            SetCommand::new(format!("test_mem_user:{}", index), "value".to_string()).query(&mut conn).unwrap();
+        }
+        SetCommand::new("test_mem_user:big".to_string(), "x".repeat(2000)).query(&mut conn).unwrap();
+        PushCommand::left("test_mem_list".to_string(), ["a", "b"]).query(&mut conn).unwrap();
+        SetCommand::new("test_mem_misc".to_string(), "value".to_string()).query(&mut conn).unwrap();
+        assert_eq!(TypeCommand::new("test_mem_list".to_string()).query(&mut conn).unwrap(), "list");
+        assert_eq!(TypeCommand::new("test_mem_missing".to_string()).query(&mut conn).unwrap(), "none");
+        assert!(MemoryUsageCommand::new("test_mem_user:big".to_string()).samples(0).query(&mut conn).unwrap().unwrap() > 2000);
+        assert_eq!(MemoryUsageCommand::new("test_mem_missing".to_string()).query(&mut conn).unwrap(), None);
+
+        // Step 3: The scan adds the keys up per group, the ungrouped ones included
+        let report = MemoryScanner::new()
+            .pattern("test_mem_*")
+            .count(1000)
+            .group("users", "test_mem_user:*")
+            .group("lists", "test_mem_l*")
+            .top(2)
+            .scan(&mut conn)
+            .unwrap();
+        assert_eq!(report.keys, 8);
+        assert_eq!(report.groups.iter().map(|group| group.keys).sum::<u64>(), 8);
+        assert_eq!(report.groups.iter().map(|group| group.bytes).sum::<u64>(), report.bytes);
+        let users = report.group("users").unwrap();
+        assert_eq!(users.keys, 6);
+        assert_eq!(users.types["string"].0, 6);
+        assert_eq!(report.groups[0].name, "users");
+        assert_eq!(report.group("lists").unwrap().types["list"].0, 1);
+        assert_eq!(report.group(OTHER_GROUP).unwrap().keys, 1);
+        assert_eq!(report.top_keys.len(), 2);
+        assert_eq!(report.top_keys[0].key, "TEST_MEM_USER:BIG");
+        assert!(report.top_keys[0].bytes >= report.top_keys[1].bytes);
+
+        // Step 4: A sample of the keyspace
+        let report = MemoryScanner::new().pattern("test_mem_*").count(1000).max_keys(3).scan(&mut conn).unwrap();
+        assert_eq!(report.keys, 3);
+        assert_eq!(report.groups.len(), 1);
+    }
+
+    #[test]
+    fn test_key_ttl() {
+        // Step 1: Missing and persistent keys have no time to live
+        let mut conn = RedisConnection::new("127.0.0.1:6379");
+        DelCommand::new(["test_ttl_key"]).query(&mut conn).unwrap();
+        assert_eq!(TtlCommand::new("test_ttl_key".to_string()).query(&mut conn).unwrap(), KeyTtl::NoKey);
+        SetCommand::new("test_ttl_key".to_string(), "value".to_string()).query(&mut conn).unwrap();
+        assert_eq!(PTtlCommand::new("test_ttl_key".to_string()).query(&mut conn).unwrap(), KeyTtl::NoExpiry);
+        assert_eq!(TtlCommand::new("test_ttl_key".to_string()).query(&mut conn).unwrap().as_duration(), None);
+        assert!(!PersistCommand::new("test_ttl_key".to_string()).query(&mut conn).unwrap());
+
+        // Step 2: Seconds for TTL, milliseconds for PTTL
+        PExpireCommand::new("test_ttl_key".to_string(), 60_000).query(&mut conn).unwrap();
+        assert_eq!(TtlCommand::new("test_ttl_key".to_string()).query(&mut conn).unwrap(), KeyTtl::Expires(Duration::from_secs(60)));
+        let ttl = PTtlCommand::new("test_ttl_key".to_string()).query(&mut conn).unwrap().as_duration().unwrap();
+        assert!(ttl > Duration::from_secs(59) && ttl <= Duration::from_secs(60));
+        assert!(matches!(PExpireTimeCommand::new("test_ttl_key".to_string()).query(&mut conn).unwrap(), KeyExpiration::At(_)));
+
+        // Step 3: PERSIST removes the expiration
+        assert!(PersistCommand::new("test_ttl_key".to_string()).query(&mut conn).unwrap());
+        assert_eq!(TtlCommand::new("test_ttl_key".to_string()).query(&mut conn).unwrap(), KeyTtl::NoExpiry);
+        assert_eq!(ExpireTimeCommand::new("test_ttl_key".to_string()).query(&mut conn).unwrap(), KeyExpiration::Persistent);
+        DelCommand::new(["test_ttl_key"]).query(&mut conn).unwrap();
+    }
+
+    #[cfg(feature="otel")]
+    #[test]
+    fn test_otel_spans() {
+        use opentelemetry::global::BoxedTracer;
+        use opentelemetry::trace::{Span, SpanKind, Status, TraceContextExt, Tracer, TracerProvider};
+        use opentelemetry::{Context, KeyValue, Value};
+        use opentelemetry_sdk::trace::{InMemorySpanExporter, SdkTracerProvider};
+
+        // Step 1: Every command gets a client span, child of the current one
+        let exporter = InMemorySpanExporter::default();
+        let provider = SdkTracerProvider::builder().with_simple_exporter(exporter.clone()).build();
+        let mut conn = RedisConnection::builder("127.0.0.1:6379").tracer(BoxedTracer::new(Box::new(provider.tracer("test")))).connect().unwrap();
+        let parent = provider.tracer("test").start("checkout");
+        let parent_id = parent.span_context().span_id();
+        {
+            let _guard = Context::current_with_span(parent).attach();
+            SetCommand::new("test_otel_key".to_string(), "value".to_string()).query(&mut conn).unwrap();
+            GetCommand::new("test_otel_key".to_string()).query(&mut conn).unwrap();
+            assert!(IncrCommand::new("test_otel_key".to_string()).query(&mut conn).is_err());
+        }
+        let spans = exporter.get_finished_spans().unwrap();
+        let names: Vec<&str> = spans.iter().map(|span| span.name.as_ref()).collect();
+        // The parent ends with the context it was attached to
+        assert_eq!(names, ["SET", "GET", "INCR", "checkout"]);
+        let attribute = |index: usize, key: &str| spans[index].attributes.iter().find(|attribute| attribute.key.as_str() == key).map(|attribute| attribute.value.clone());
+        for (index, span) in spans[..3].iter().enumerate() {
+            assert_eq!(span.span_kind, SpanKind::Client);
+            assert_eq!(span.parent_span_id, parent_id);
+            assert_eq!(attribute(index, "db.system"), Some(Value::from("redis")));
+            assert_eq!(attribute(index, "net.peer.name"), Some(Value::from("127.0.0.1")));
+            assert_eq!(attribute(index, "net.peer.port"), Some(Value::I64(6379)));
+        }
+        assert_eq!(attribute(1, "db.operation"), Some(Value::from("GET")));
+
+        // Step 2: Error replies and failed connections mark the span as an error
+        assert_eq!(spans[1].status, Status::Unset);
+        assert!(matches!(&spans[2].status, Status::Error { description } if description.starts_with("ERR")));
+        assert!(spans[2].attributes.contains(&KeyValue::new("db.response.status_code", "ERR")));
+        exporter.reset();
+        let client_id = conn.request(b"*2\r\n$6\r\nCLIENT\r\n$2\r\nID\r\n").unwrap().as_i64().unwrap();
+        let mut killer = RedisConnection::new("127.0.0.1:6379");
+        let kill = format!("*4\r\n$6\r\nCLIENT\r\n$4\r\nKILL\r\n$2\r\nID\r\n${}\r\n{}\r\n", client_id.to_string().len(), client_id);
+        killer.request(kill.as_bytes()).unwrap();
+        assert!(GetCommand::new("test_otel_key".to_string()).query(&mut conn).is_err());
+        let spans = exporter.get_finished_spans().unwrap();
+        let failed = spans.last().unwrap();
+        assert_eq!(failed.name, "GET");
+        assert!(matches!(failed.status, Status::Error { .. }));
+        assert_eq!(failed.events.events[0].name, "exception");
+        DelCommand::new(["test_otel_key"]).query(&mut killer).unwrap();
+    }
+
+    #[test]
+    fn test_command_validation() {
+        // Step 1: Valid commands go through, an "EX" value is not taken for the option
+        let validation = Validation::new().max_bulk_size(32).utf8(Utf8Policy::Keys);
+        let mut conn = RedisConnection::builder("127.0.0.1:6379").validation(validation).connect().unwrap();
+        SetCommand::new("test_valid_key".to_string(), "ex".to_string()).ex(10).query(&mut conn).unwrap();
+        GetExCommand::new("test_valid_key".to_string()).px(5000).query(&mut conn).unwrap();
+        let invalid = |result: Result<Resp3Value, Resp3Error>| match result {
+            Err(Resp3Error::InvalidArgument(message)) => message,
+            result => panic!("Expected an invalid argument, got {:?}", result),
+        };
+
+        // Step 2: Each check, before anything is sent
+        let message = invalid(SetCommand::new("".to_string(), "value".to_string()).execute(&mut conn));
+        assert_eq!(message, "SET key at argument 1 is empty");
+        let message = invalid(SetCommand::new("test_valid_key".to_string(), "x".repeat(33)).execute(&mut conn));
+        assert_eq!(message, "SET argument 2 is 33 bytes long, over the limit of 32");
+        let message = invalid(SetCommand::new("test_valid_key".to_string(), "value".to_string()).ex(0).execute(&mut conn));
+        assert_eq!(message, "SET needs a positive EX, got 0");
+        let message = invalid(SetCommand::new("test_valid_key".to_string(), "value".to_string()).ex(i64::MAX as u64 / 10).execute(&mut conn));
+        assert!(message.starts_with("SET has EX 922337203685477580 out of range"), "{}", message);
+        assert!(invalid(DelCommand::new([vec![0xff, 0xfe]]).execute(&mut conn)).ends_with("key at argument 1 is not valid UTF-8"));
+        struct ExpireCommand;
+        impl Command for ExpireCommand {
+            fn get_parts(&self) -> Vec<&str> {
+                vec!["EXPIRE", "test_valid_key", "soon"]
+            }
+
+            fn key_indices(&self) -> Vec<usize> {
+                vec![1]
+            }
+        }
+        assert_eq!(invalid(ExpireCommand.execute(&mut conn)), "EXPIRE needs an integer for the expiration, got 'soon'");
+        assert!(matches!(conn.send_no_reply(&ExpireCommand), Err(Resp3Error::InvalidArgument(_))));
+
+        // Step 3: The connection is still in step, and unchecked connections send anything
+        assert_eq!(GetCommand::new("test_valid_key".to_string()).query(&mut conn).unwrap(), Some("EX".to_string()));
+        let mut unchecked = RedisConnection::new("127.0.0.1:6379");
+        assert!(matches!(ExpireCommand.execute(&mut unchecked), Err(Resp3Error::Server(_))));
+        DelCommand::new(["test_valid_key"]).query(&mut unchecked).unwrap();
+    }
+
+    #[test]
+    fn test_protocol_negotiation() {
+        // Step 1: A server refusing HELLO 3 is spoken to in RESP2, its flat arrays become maps
+        let server = MockServer::start().unwrap();
+        let flat = Resp3Value::Array(vec![Resp3Value::BulkString(b"maxmemory".to_vec()), Resp3Value::BulkString(b"0".to_vec())]);
+        server.reply("CONFIG", flat);
+        let mut conn = RedisConnection::builder(&server.address()).protocol(ProtocolVersion::Auto).connect().unwrap();
+        assert_eq!(conn.protocol(), ProtocolVersion::Resp2);
+        assert_eq!(server.commands()[0], ["HELLO", "3"]);
+        let reply = ConfigGetCommand::new("maxmemory".to_string()).execute(&mut conn).unwrap();
+        assert_eq!(reply.as_map().unwrap().get_str("maxmemory"), Some("0"));
+        assert_eq!(ConfigGetCommand::new("maxmemory".to_string()).query(&mut conn).unwrap()["maxmemory"], "0");
+        assert!(matches!(RedisConnection::builder(&server.address()).protocol(ProtocolVersion::Resp3).connect(), Err(Resp3Error::Server(_))));
+
+        // Step 2: A proxy closing the connection on HELLO gets a new one
+        let connections = server.connections();
+        server.inject(Fault::Disconnect);
+        let mut conn = RedisConnection::builder(&server.address()).protocol(ProtocolVersion::Auto).connect().unwrap();
+        assert_eq!(conn.protocol(), ProtocolVersion::Resp2);
+        assert_eq!(PingCommand.query(&mut conn).unwrap(), "PONG");
+        assert_eq!(server.connections(), connections + 2);
+
+        // Step 3: Redis takes HELLO 3 and sends the same map, on every reconnect
+        let mut conn = RedisConnection::builder("127.0.0.1:6379").protocol(ProtocolVersion::Auto).connect().unwrap();
+        assert_eq!(conn.protocol(), ProtocolVersion::Resp3);
+        assert!(matches!(ConfigGetCommand::new("maxmemory".to_string()).execute(&mut conn).unwrap(), Resp3Value::Map(_)));
+        conn.reconnect().unwrap();
+        assert_eq!(conn.protocol(), ProtocolVersion::Resp3);
+        assert_eq!(HelloCommand::new(3).query(&mut conn).unwrap().get_i64("proto"), Some(3));
+        let mut conn = RedisConnection::new("127.0.0.1:6379");
+        assert_eq!(conn.protocol(), ProtocolVersion::Resp2);
+        assert!(matches!(ConfigGetCommand::new("maxmemory".to_string()).execute(&mut conn).unwrap(), Resp3Value::Map(_)));
+    }
+
+    #[test]
+    fn test_client_flags() {
+        // Step 1: The flags are set right after connecting, and again after a reconnect
+        let flags = |conn: &mut RedisConnection| {
+            let info = String::from_resp3_value(conn.request(b"*2\r\n$6\r\nCLIENT\r\n$4\r\nINFO\r\n").unwrap()).unwrap();
+            info.split(' ').find_map(|field| field.strip_prefix("flags=")).unwrap().to_string()
+        };
+        let mut conn = RedisConnection::builder("127.0.0.1:6379").no_evict(true).no_touch(true).connect().unwrap();
+        assert!(flags(&mut conn).contains('e') && flags(&mut conn).contains('T'));
+        conn.reconnect().unwrap();
+        assert!(flags(&mut conn).contains('e') && flags(&mut conn).contains('T'));
+        let mut plain = RedisConnection::new("127.0.0.1:6379");
+        assert_eq!(flags(&mut plain), "N");
+
+        // Step 2: Each flag on its own, and turned off by the command
+        let mut conn = RedisConnection::builder("127.0.0.1:6379").no_touch(true).connect().unwrap();
+        assert_eq!(flags(&mut conn), "T");
+        assert!(ClientFlagCommand::new(ClientFlag::NoTouch, false).query(&mut conn).unwrap());
+        assert!(ClientFlagCommand::new(ClientFlag::NoEvict, true).query(&mut conn).unwrap());
+        assert_eq!(flags(&mut conn), "e");
+
+        // Step 3: A server refusing a flag fails the connect
+        let server = MockServer::start().unwrap();
+        assert!(matches!(RedisConnection::builder(&server.address()).no_evict(true).connect(), Err(Resp3Error::Server(_))));
+        assert_eq!(server.commands()[0], ["CLIENT", "NO-EVICT", "ON"]);
+    }
+
+    #[test]
+    fn test_scripts() {
+        // Step 1: Load a script and run it by its digest
+        let mut conn = RedisConnection::new("127.0.0.1:6379");
+        ScriptFlushCommand::new().query(&mut conn).unwrap();
+        let sha = ScriptLoadCommand::new("return ARGV[1]".to_string()).query(&mut conn).unwrap();
+        assert_eq!(sha.len(), 40);
+        let reply = EvalShaCommand::new(sha.clone(), vec![], vec!["Hello".to_string()]).verbatim_args().execute(&mut conn).unwrap();
+        assert_eq!(reply, Resp3Value::BulkString(b"Hello".to_vec()));
+        assert_eq!(ScriptExistsCommand::new([sha.as_str(), "0000000000000000000000000000000000000000"]).query(&mut conn).unwrap(), vec![true, false]);
+
+        // Step 2: The bundled scripts are loaded again after a flush
+        assert!(ScriptFlushCommand::new().synchronous().query(&mut conn).unwrap());
+        let error = EvalShaCommand::new(sha, vec![], vec![]).execute(&mut conn).unwrap_err();
+        assert_eq!(error.kind(), "NOSCRIPT");
+        SetCommand::new("test_scripts_value".to_string(), "Kept".to_string()).verbatim_value().query(&mut conn).unwrap();
+        assert_eq!(scripts::get_and_expire(&mut conn, "test_scripts_value", Duration::from_secs(60)).unwrap(), Some("Kept".to_string()));
+        let sha = GET_AND_EXPIRE.load(&mut conn).unwrap();
+        assert_eq!(ScriptExistsCommand::new([sha]).query(&mut conn).unwrap(), vec![true]);
+        assert!(matches!(TtlCommand::new("test_scripts_value".to_string()).query(&mut conn).unwrap(), KeyTtl::Expires(ttl) if ttl > Duration::from_secs(50)));
+        ScriptFlushCommand::new().query(&mut conn).unwrap();
+        assert_eq!(scripts::get_and_expire(&mut conn, "test_scripts_missing", Duration::from_secs(60)).unwrap(), None);
+
+        // Step 3: A bounded list keeps the newest entries
+        DelCommand::new(["test_scripts_list", "test_scripts_bucket"]).query(&mut conn).unwrap();
+        for (index, value) in ["a", "b", "c", "d"].iter().enumerate() {
+            assert_eq!(scripts::bounded_push(&mut conn, "test_scripts_list", value, 3).unwrap(), (index as u64 + 1).min(3));
+        }
+        let list = BOUNDED_PUSH.invoke(&mut conn, vec!["test_scripts_list".to_string()], vec!["3".to_string(), "e".to_string()]).unwrap();
+        assert_eq!(list, Resp3Value::Integer(3));
+        let values = EvalCommand::new("return redis.call('lrange', KEYS[1], 0, -1)".to_string(), vec!["test_scripts_list".to_string()], vec![]).execute(&mut conn).unwrap();
+        assert_eq!(Vec::<String>::from_resp3_value(values).unwrap(), vec!["e", "d", "c"]);
+        assert!(scripts::bounded_push(&mut conn, "test_scripts_list", "f", 0).is_err());
+
+        // Step 4: The token bucket lets bursts through up to its capacity, then waits for refills
+        let first = scripts::token_bucket(&mut conn, "test_scripts_bucket", 3, 1.0, 2).unwrap();
+        assert!(first.allowed);
+        assert_eq!(first.remaining, 1);
+        let second = scripts::token_bucket(&mut conn, "test_scripts_bucket", 3, 1.0, 2).unwrap();
+        assert!(!second.allowed);
+        assert!(second.reset_at > std::time::SystemTime::now() + Duration::from_millis(500));
+        assert!(TOKEN_BUCKET.invoke(&mut conn, vec!["test_scripts_bucket".to_string()], vec!["3".to_string(), "1".to_string(), "1".to_string()]).is_ok());
+        assert!(scripts::token_bucket(&mut conn, "test_scripts_bucket", 3, 0.0, 1).is_err());
+    }
+
+    #[test]
+    fn test_command_journal() {
+        // Step 1: A write failing on a dropped link is journaled, on disk
+        let server = MockServer::start().unwrap();
+        server.reply("EVAL", Resp3Value::Array(vec![Resp3Value::Integer(1), Resp3Value::SimpleString("OK".to_string())]));
+        let path = std::env::temp_dir().join(format!("resp3string_journal_{}.log", std::process::id()));
+        let copy = path.with_extension("copy");
+        std::fs::remove_file(&path).ok();
+        let mut executor = CommandExecutor::new(&server.address()).with_journal(Journal::open(&path, 2).unwrap());
+        server.inject(Fault::Disconnect);
+        let id = match executor.query(SetCommand::new("test_journal_a".to_string(), "1".to_string())) {
+            Err(Resp3Error::Journaled(id)) => id,
+            reply => panic!("Expected the write to be journaled, got {:?}", reply),
+        };
+        let journal = Journal::open(&path, 2).unwrap();
+        let entry = journal.entries().next().unwrap();
+        assert_eq!((entry.id.as_str(), entry.keys.as_slice()), (id.as_str(), &[1][..]));
+        assert_eq!(entry.parts, vec![b"SET".to_vec(), b"TEST_JOURNAL_A".to_vec(), b"1".to_vec()]);
+
+        // Step 2: The next write reconnects and replays the journal first
+        assert!(executor.query(SetCommand::new("test_journal_b".to_string(), "2".to_string())).unwrap());
+        assert!(executor.journal().unwrap().is_empty());
+        assert_eq!(Journal::open(&path, 2).unwrap().len(), 0);
+        let markers: Vec<String> = server.commands().into_iter().filter(|parts| parts[0] == "EVAL").map(|parts| parts[3].clone()).collect();
+        assert_eq!(markers.len(), 3);
+        assert_eq!(markers[..2], [format!("journal:{}", id), format!("journal:{}", id)]);
+
+        // Step 3: Once the journal is full the I/O error comes back
+        let mut executor = CommandExecutor::new(&server.address()).with_journal(Journal::in_memory(0));
+        server.inject(Fault::Disconnect);
+        assert!(matches!(executor.query(SetCommand::new("test_journal_c".to_string(), "3".to_string())), Err(Resp3Error::Io(_))));
+
+        // Step 4: The commands scripts cannot run are sent as they are
+        let mut executor = CommandExecutor::new(&server.address()).with_journal(Journal::in_memory(2));
+        server.reply("CONFIG", Resp3Value::SimpleString("OK".to_string()));
+        executor.query(ConfigSetCommand::new("maxmemory".to_string(), "1mb".to_string())).unwrap();
+        executor.request(&EvalCommand::new("return 1".to_string(), vec![], vec![])).unwrap();
+        let sent = server.commands();
+        assert_eq!((sent[sent.len() - 2][0].as_str(), sent[sent.len() - 1][1].as_str()), ("CONFIG", "return 1"));
+
+        // Step 5: A write replayed twice runs once (ensure Redis is running on localhost:6379)
+        let mut executor = CommandExecutor::new(&server.address()).with_journal(Journal::open(&path, 2).unwrap());
+        server.inject(Fault::Disconnect);
+        assert!(matches!(executor.query(IncrCommand::new("test_journal_counter".to_string())), Err(Resp3Error::Journaled(_))));
+        std::fs::copy(&path, &copy).unwrap();
+        let mut conn = RedisConnection::new("127.0.0.1:6379");
+        DelCommand::new(["test_journal_counter"]).query(&mut conn).unwrap();
+        let mut executor = CommandExecutor::new("127.0.0.1:6379").with_journal(Journal::open(&path, 2).unwrap());
+        assert_eq!(executor.replay().unwrap(), 1);
+        let mut executor = CommandExecutor::new("127.0.0.1:6379").with_journal(Journal::open(&copy, 2).unwrap());
+        assert_eq!(executor.replay().unwrap(), 0);
+        assert_eq!(executor.query(GetCommand::new("test_journal_counter".to_string())).unwrap(), Some("1".to_string()));
+        std::fs::remove_file(&path).ok();
+        std::fs::remove_file(&copy).ok();
+    }
+
+    #[test]
+    fn test_clustered_multi_key() {
+        // Step 1: Two mock masters sharing the slots, either one answers CLUSTER SLOTS
+        let nodes = [MockServer::start().unwrap(), MockServer::start().unwrap()];
+        let node = |address: String, start: i64, end: i64| {
+            let (host, port) = address.split_once(':').unwrap();
+            Resp3Value::Array(vec![
+                Resp3Value::Integer(start),
+                Resp3Value::Integer(end),
+                Resp3Value::Array(vec![Resp3Value::BulkString(host.as_bytes().to_vec()), Resp3Value::Integer(port.parse().unwrap())]),
+            ])
+        };
+        let slots = Resp3Value::Array(vec![node(nodes[1].address(), 8192, 16383), node(nodes[0].address(), 0, 8191)]);
+        for server in &nodes {
+            server.reply("CLUSTER", slots.clone());
+        }
+        let mut cluster = ClusterConnections::connect(RedisConnection::builder(&nodes[0].address())).unwrap();
+        assert_eq!(cluster.slots().iter().map(|range| (range.start, range.end)).collect::<Vec<_>>(), vec![(0, 8191), (8192, 16383)]);
+        assert_eq!(cluster.node_for_slot(9000), Some(nodes[1].address().as_str()));
+
+        // Step 2: The keys are split by slot, each node only gets its own, one slot per command
+        let keys: Vec<String> = (0..8).map(|index| format!("test_cluster_{}", index)).collect();
+        let pairs: Vec<(&str, String)> = keys.iter().map(|key| (key.as_str(), format!("Value of {}", key))).collect();
+        let pairs: Vec<(&str, &str)> = pairs.iter().map(|(key, value)| (*key, value.as_str())).collect();
+        cluster.mset_clustered(&pairs).unwrap();
+        for (server, range) in nodes.iter().zip([0..=8191, 8192..=16383]) {
+            let commands = server.commands().into_iter().filter(|parts| parts[0] == "MSET").collect::<Vec<_>>();
+            assert!(!commands.is_empty());
+            for parts in commands {
+                let slots: Vec<u16> = parts[1..].iter().step_by(2).map(|key| key_slot(key.as_bytes())).collect();
+                assert!(slots.iter().all(|slot| *slot == slots[0] && range.contains(slot)));
+            }
+        }
+
+        // Step 3: The values come back in the order of the keys
+        let mut wanted: Vec<&str> = keys.iter().rev().map(String::as_str).collect();
+        wanted.insert(3, "test_cluster_missing");
+        let values = cluster.mget_clustered(&wanted).unwrap();
+        let expected: Vec<Option<String>> = wanted.iter().map(|key| (*key != "test_cluster_missing").then(|| format!("Value of {}", key))).collect();
+        assert_eq!(values, expected);
+        assert_eq!(cluster.mget_clustered(&[]).unwrap(), Vec::<Option<String>>::new());
+
+        // Step 4: A MOVED reply refreshes the slot ranges before the commands are sent again
+        let refreshes = nodes.iter().map(|server| server.commands().iter().filter(|parts| parts[0] == "CLUSTER").count()).sum::<usize>();
+        nodes[0].reply_once("MGET", Resp3Value::Error(format!("MOVED 1 {}", nodes[0].address())));
+        nodes[1].reply_once("MGET", Resp3Value::Error(format!("MOVED 9000 {}", nodes[1].address())));
+        assert_eq!(cluster.mget_clustered(&wanted).unwrap(), expected);
+        assert_eq!(nodes.iter().map(|server| server.commands().iter().filter(|parts| parts[0] == "CLUSTER").count()).sum::<usize>(), refreshes + 1);
+        let mut conn = RedisConnection::new(&nodes[0].address());
+        assert!(MGetCommand::new(["test_cluster_missing"]).query(&mut conn).unwrap()[0].is_none());
+        assert!(MSetCommand::new([("test_cluster_plain", "Value")]).verbatim_values().query(&mut conn).unwrap());
+
+        // Step 5: SCAN runs on every master, a key returned by two nodes after moving comes once
+        let scan_reply = |cursor: &str, keys: &[&str]| Resp3Value::Array(vec![
+            Resp3Value::BulkString(cursor.as_bytes().to_vec()),
+            Resp3Value::Array(keys.iter().map(|key| Resp3Value::BulkString(key.as_bytes().to_vec())).collect()),
+        ]);
+        nodes[0].reply_once("SCAN", scan_reply("7", &["A", "B"]));
+        nodes[0].reply_once("SCAN", scan_reply("0", &["C"]));
+        nodes[1].reply_once("SCAN", scan_reply("0", &["B", "D"]));
+        let mut scan = cluster.scan("*").count(10);
+        let mut keys: Vec<String> = scan.by_ref().collect::<Result<_, _>>().unwrap();
+        keys.sort();
+        assert_eq!(keys, ["A", "B", "C", "D"]);
+        assert_eq!(scan.progress(), ScanProgress { nodes: 2, finished: 2, calls: 3, keys: 4, duplicates: 1 });
+        let scans: Vec<Vec<String>> = nodes[0].commands().into_iter().filter(|parts| parts[0] == "SCAN").collect();
+        assert_eq!(scans[1], ["SCAN", "7", "MATCH", "*", "COUNT", "10"]);
+
+        // Step 6: A failing node ends the scan with its error
+        nodes[1].reply_once("SCAN", Resp3Value::Error("ERR scan failed".to_string()));
+        nodes[0].reply_once("SCAN", scan_reply("0", &[]));
+        let mut scan = cluster.scan("*");
+        assert!(matches!(scan.next(), Some(Err(Resp3Error::Server(_)))));
+        assert!(scan.next().is_none());
+    }
+
+    #[test]
+    fn test_key_slots() {
+        // Step 1: The slots of the Redis Cluster specification
+        assert_eq!(key_slot(b"123456789"), 12739);
+        assert_eq!(key_slot(b""), 0);
+        assert_eq!(key_slot(b"foo"), 12182);
+        assert_eq!(key_slot(b"bar"), 5061);
+        assert!((0..1000).all(|index| key_slot(format!("key:{}", index).as_bytes()) < SLOT_COUNT));
+
+        // Step 2: Only the first non-empty {...} is hashed
+        assert_eq!(hash_tag(b"{user1000}.following"), Some(&b"user1000"[..]));
+        assert_eq!(hash_tag(b"foo{}{bar}"), None);
+        assert_eq!(hash_tag(b"foo{{bar}}zap"), Some(&b"{bar"[..]));
+        assert_eq!(hash_tag(b"foo{bar}{zap}"), Some(&b"bar"[..]));
+        assert_eq!(hash_tag(b"{unclosed"), None);
+        assert_eq!(key_slot(b"{user1000}.following"), key_slot(b"user1000"));
+
+        // Step 3: Keys are hashed in the case they are sent with
+        assert_eq!(slot_for_key("{user1000}.following"), key_slot(b"USER1000"));
+        assert_ne!(slot_for_key("foo"), key_slot(b"foo"));
+
+        // Step 4: The keys of a multi-key command must share a slot
+        assert_eq!(command_slot(&MGetCommand::new(["{cart:7}:items", "{cart:7}:total"])).unwrap(), Some(slot_for_key("cart:7")));
+        assert_eq!(command_slot(&PingCommand).unwrap(), None);
+        assert!(matches!(command_slot(&MSetCommand::new([("a", "1"), ("b", "2")])), Err(Resp3Error::InvalidArgument(_))));
+        let verbatim = TypeCommand::new("foo".to_string()).verbatim_key();
+        assert_eq!(command_slot(&verbatim).unwrap(), Some(key_slot(b"foo")));
+    }
+
+    #[test]
+    fn test_set_algebra() {
+        // Step 1: Three overlapping sets (ensure Redis is running on localhost:6379)
+        let mut conn = RedisConnection::new("127.0.0.1:6379");
+        let keys = ["test_algebra_a", "test_algebra_b", "test_algebra_c", "test_algebra_out"];
+        DelCommand::new(keys).query(&mut conn).unwrap();
+        SAddCommand::new(keys[0].to_string(), ["1", "2", "3", "4"]).query(&mut conn).unwrap();
+        SAddCommand::new(keys[1].to_string(), ["2", "3", "4", "5"]).query(&mut conn).unwrap();
+        SAddCommand::new(keys[2].to_string(), ["3", "4", "6"]).query(&mut conn).unwrap();
+        let sets = |count: usize| keys[..count].iter().map(|key| key.to_string()).collect::<Vec<_>>();
+
+        // Step 2: Store the intersection, union and difference
+        assert_eq!(SetStoreCommand::inter(keys[3].to_string(), sets(3)).query(&mut conn).unwrap(), 2);
+        assert_eq!(SetStoreCommand::union(keys[3].to_string(), sets(3)).query(&mut conn).unwrap(), 6);
+        assert_eq!(SetStoreCommand::diff(keys[3].to_string(), sets(2)).query(&mut conn).unwrap(), 1);
+        assert_eq!(SMIsMemberCommand::new(keys[3].to_string(), ["1", "2", "missing"]).query(&mut conn).unwrap(), vec![true, false, false]);
+
+        // Step 3: The fluent intersection counts, with or without a limit, and stores
+        let intersection = SetIntersection::of(keys[0]).and(keys[1]);
+        assert_eq!(intersection.count(&mut conn).unwrap(), 3);
+        assert_eq!(intersection.clone().limit(2).count(&mut conn).unwrap(), 2);
+        assert!(!intersection.is_empty(&mut conn).unwrap());
+        assert!(SetIntersection::of(keys[0]).and("test_algebra_missing").is_empty(&mut conn).unwrap());
+        assert_eq!(intersection.and(keys[2]).store(&mut conn, keys[3]).unwrap(), 2);
+        assert_eq!(SMIsMemberCommand::new(keys[3].to_string(), ["3", "4", "2"]).query(&mut conn).unwrap(), vec![true, true, false]);
+        DelCommand::new(keys).query(&mut conn).unwrap();
+    }
+
+    #[test]
+    fn test_command_introspection() {
+        // Step 1: Describe known and unknown commands (ensure Redis is running on localhost:6379)
+        let mut conn = RedisConnection::new("127.0.0.1:6379");
+        let infos = CommandInfoCommand::new(["get", "mget", "nosuchcmd"]).query(&mut conn).unwrap();
+        assert_eq!(infos.len(), 3);
+        let get = infos[0].as_ref().unwrap();
+        assert_eq!((get.name.as_str(), get.arity, get.first_key, get.last_key, get.step), ("get", 2, 1, 1, 1));
+        assert!(get.has_flag("READONLY") && get.accepts(2) && !get.accepts(3));
+        assert_eq!(get.key_specs[0].begin_search, BeginSearch::Index(1));
+        assert_eq!(get.key_specs[0].find_keys, FindKeys::Range { last_key: 0, key_step: 1, limit: 0 });
+        let mget = infos[1].as_ref().unwrap();
+        assert!(mget.arity < 0 && mget.accepts(4) && !mget.accepts(1));
+        assert!(infos[2].is_none());
+
+        // Step 2: The documentation tells when a command was added
+        let docs = CommandDocsCommand::new(["getex"]).query(&mut conn).unwrap();
+        assert_eq!((docs["getex"].since.as_str(), docs["getex"].group.as_str()), ("6.2.0", "string"));
+        assert!(!docs["getex"].arguments.is_empty());
+
+        // Step 3: Feature detection, answered once per command
+        assert!(conn.supports("GETEX").unwrap());
+        assert!(conn.supports("getex").unwrap());
+        assert!(!conn.supports("NOSUCHCMD").unwrap());
+    }
+
+    #[test]
+    fn test_capped_list() {
+        // Step 1: Push recent items, the oldest ones are dropped (ensure Redis is running on localhost:6379)
+        let mut conn = RedisConnection::new("127.0.0.1:6379");
+        let key = "test_capped_list";
+        DelCommand::new([key]).query(&mut conn).unwrap();
+        assert_eq!(scripts::capped_list_push(&mut conn, key, &["view:1", "view:2"], 3).unwrap(), 2);
+        assert_eq!(scripts::capped_list_push(&mut conn, key, &["view:3", "View:4"], 3).unwrap(), 3);
+        assert_eq!(LRangeCommand::new(key.to_string(), 0, -1).query(&mut conn).unwrap(), vec!["View:4", "view:3", "view:2"]);
+        assert!(scripts::capped_list_push(&mut conn, key, &[], 3).is_err());
+        assert!(scripts::capped_list_push(&mut conn, key, &["view:5"], 0).is_err());
+
+        // Step 2: Trim from either end
+        PushCommand::right(key.to_string(), ["a", "b"]).query(&mut conn).unwrap();
+        assert!(LTrimCommand::keep_last(key.to_string(), 4).query(&mut conn).unwrap());
+        assert_eq!(LRangeCommand::new(key.to_string(), 0, -1).query(&mut conn).unwrap(), vec!["view:3", "view:2", "A", "B"]);
+        assert!(LTrimCommand::keep_first(key.to_string(), 1).query(&mut conn).unwrap());
+        assert_eq!(LRangeCommand::new(key.to_string(), 0, -1).query(&mut conn).unwrap(), vec!["view:3"]);
+        assert!(LTrimCommand::keep_first(key.to_string(), 0).query(&mut conn).unwrap());
+        assert_eq!(LLenCommand::new(key.to_string()).query(&mut conn).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_typed_options() {
+        // Step 1: The options expand into their tokens, the last one of a kind wins
+        assert_eq!(Expiry::ExAt(1700000000).to_arguments(), vec!["EXAT", "1700000000"]);
+        assert_eq!(Expiry::after(Duration::from_millis(1500)), Expiry::Px(1500));
+        assert_eq!(Some(SetCondition::Nx).to_arguments(), vec!["NX"]);
+        assert_eq!(None::<Expiry>.to_arguments(), Vec::<String>::new());
+        let set = SetCommand::new("key".to_string(), "value".to_string()).nx().xx().ex(10).expiry(Expiry::KeepTtl);
+        assert_eq!(set.get_parts(), vec!["SET", "key", "value", "XX", "KEEPTTL"]);
+        assert_eq!(GetExCommand::new("key".to_string()).px(10).expiry(Expiry::KeepTtl).get_parts(), vec!["GETEX", "key"]);
+        assert_eq!(CopyCommand::new("a".to_string(), "b".to_string()).db(1).db(2).condition(SetCondition::Xx).get_parts(), vec!["COPY", "a", "b", "DB", "2", "REPLACE"]);
+
+        // Step 2: The server understands them (ensure Redis is running on localhost:6379)
+        let mut conn = RedisConnection::new("127.0.0.1:6379");
+        let keys = ["test_options_a", "test_options_b"];
+        DelCommand::new(keys).query(&mut conn).unwrap();
+        assert!(SetCommand::new(keys[0].to_string(), "1".to_string()).condition(SetCondition::Nx).expiry(Expiry::Ex(100)).query(&mut conn).unwrap());
+        assert!(!SetCommand::new(keys[0].to_string(), "2".to_string()).condition(SetCondition::Nx).query(&mut conn).unwrap());
+        assert!(SetCommand::new(keys[0].to_string(), "3".to_string()).expiry(Expiry::KeepTtl).query(&mut conn).unwrap());
+        assert!(matches!(TtlCommand::new(keys[0].to_string()).query(&mut conn).unwrap(), KeyTtl::Expires(ttl) if ttl > Duration::from_secs(90)));
+        let later = std::time::SystemTime::now() + Duration::from_secs(1000);
+        assert_eq!(GetExCommand::new(keys[0].to_string()).expiry(Expiry::at(later)).query(&mut conn).unwrap(), Some("3".to_string()));
+        assert!(matches!(TtlCommand::new(keys[0].to_string()).query(&mut conn).unwrap(), KeyTtl::Expires(ttl) if ttl > Duration::from_secs(900)));
+        assert!(CopyCommand::new(keys[0].to_string(), keys[1].to_string()).condition(SetCondition::Nx).query(&mut conn).unwrap());
+        assert!(!CopyCommand::new(keys[0].to_string(), keys[1].to_string()).condition(SetCondition::Nx).query(&mut conn).unwrap());
+        assert!(CopyCommand::new(keys[0].to_string(), keys[1].to_string()).condition(SetCondition::Xx).query(&mut conn).unwrap());
+        assert!(SetCommand::new(keys[0].to_string(), "4".to_string()).expiry(Expiry::Persist).query(&mut conn).unwrap());
+        assert_eq!(TtlCommand::new(keys[0].to_string()).query(&mut conn).unwrap(), KeyTtl::NoExpiry);
+        DelCommand::new(keys).query(&mut conn).unwrap();
+    }
+
+    #[test]
+    fn test_server_hints() {
+        // Step 1: Attributes before a reply are returned apart, those inside it are skipped
+        let frame = b"|1\r\n+key-popularity\r\n%1\r\n$1\r\na\r\n,0.5\r\n*2\r\n:1\r\n|1\r\n+ttl\r\n:3\r\n:2\r\n";
+        let (value, attributes, length) = parse_frame_with_attributes(frame).unwrap().unwrap();
+        assert_eq!(value, Resp3Value::Array(vec![Resp3Value::Integer(1), Resp3Value::Integer(2)]));
+        assert_eq!(length, frame.len());
+        let attributes = attributes.unwrap();
+        assert_eq!(attributes.get("key-popularity").and_then(|keys| keys.as_map()).and_then(|keys| keys.get_f64("a")), Some(0.5));
+        assert_eq!(parse_frame(frame).unwrap(), Some((value, frame.len())));
+        assert_eq!(parse_frame_with_attributes(&frame[..20]).unwrap(), None);
+        assert_eq!(parse_frame_with_attributes(b":7\r\n").unwrap(), Some((Resp3Value::Integer(7), None, 4)));
+
+        // Step 2: The hints are gathered by kind
+        let hints = ServerHints::new();
+        hints.record("GET", &attributes);
+        let (_, offset, _) = parse_frame_with_attributes(b"|2\r\n+replication-offset\r\n:42\r\n+shard\r\n+s1\r\n+OK\r\n").unwrap().unwrap();
+        let offset = offset.unwrap();
+        hints.record("SET", &offset);
+        let snapshot = hints.snapshot();
+        assert_eq!(snapshot.replication_offset, Some(42));
+        assert_eq!(snapshot.other.get("shard"), Some(&Resp3Value::SimpleString("s1".to_string())));
+        assert_eq!((snapshot.commands["GET"], snapshot.commands["SET"]), (1, 1));
+        hints.reset();
+        assert_eq!(hints.snapshot(), Default::default());
+
+        // Step 3: An executor over RESP3 collects the hints of its replies (ensure Redis is running on localhost:6379)
+        struct DebugAttribCommand;
+        impl Command for DebugAttribCommand {
+            fn get_parts(&self) -> Vec<&str> {
+                vec!["DEBUG", "PROTOCOL", "ATTRIB"]
+            }
+        }
+        let conn = RedisConnection::builder("127.0.0.1:6379").protocol(ProtocolVersion::Resp3).connect().unwrap();
+        let mut executor = CommandExecutor::from_connection(conn);
+        let reply = executor.request(&DebugAttribCommand).unwrap();
+        assert_eq!(reply.as_str(), Some("Some real reply following the attribute"));
+        executor.query(GetCommand::new("test_hints_missing".to_string())).unwrap();
+        let snapshot = executor.server_hints().snapshot();
+        assert_eq!(snapshot.hot_keys(1), vec![("a", 0.1923)]);
+        assert_eq!(snapshot.hot_keys(5).len(), 2);
+        assert_eq!(snapshot.commands.get("DEBUG"), Some(&1));
+        assert_eq!(snapshot.commands.get("GET"), None);
+
+        // Step 4: Over RESP2 there are none
+        let mut executor = CommandExecutor::new("127.0.0.1:6379");
+        executor.request(&DebugAttribCommand).unwrap();
+        assert!(executor.server_hints().snapshot().commands.is_empty());
+    }
+
+    #[test]
+    fn test_dispatcher() {
+        // Step 1: Route channels and patterns to handlers (ensure Redis is running on localhost:6379)
+        let mut conn = RedisConnection::new("127.0.0.1:6379");
+        let subscriber = RedisConnection::builder("127.0.0.1:6379").timeout(Duration::from_millis(50)).connect().unwrap();
+        let received = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let (orders, news) = (received.clone(), received.clone());
+        let dispatcher = Dispatcher::new(PubSub::new(subscriber))
+            .workers(2)
+            .route("test_dispatch_orders", move |event| orders.lock().unwrap().push(("orders", event.clone())))
+            .route("test_dispatch_news.*", move |event| news.lock().unwrap().push(("news", event.clone())))
+            .route("test_dispatch_news.*", |_| panic!("handler failure"))
+            .start()
+            .unwrap();
+        for (channel, payload) in [("test_dispatch_orders", "o1"), ("test_dispatch_news.sport", "n1"), ("test_dispatch_other", "x"), ("test_dispatch_orders", "o2")] {
+            PublishCommand::new(channel.to_string(), payload.to_string()).query(&mut conn).unwrap();
+        }
+        let deadline = std::time::Instant::now() + Duration::from_secs(5);
+        while dispatcher.stats().handled < 3 && std::time::Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        // Step 2: Each message reached the handlers of its route, a panicking one did not stop the others
+        assert_eq!(dispatcher.stats(), DispatchStats { received: 3, handled: 3, unrouted: 0, panicked: 1 });
+        let mut received = received.lock().unwrap().clone();
+        received.sort_by_key(|(route, event)| (*route, format!("{:?}", event)));
+        assert_eq!(received, vec![
+            ("news", PubSubEvent::PatternMessage { pattern: "TEST_DISPATCH_NEWS.*".to_string(), channel: "TEST_DISPATCH_NEWS.SPORT".to_string(), payload: b"N1".to_vec() }),
+            ("orders", PubSubEvent::Message { channel: "TEST_DISPATCH_ORDERS".to_string(), payload: b"O1".to_vec() }),
+            ("orders", PubSubEvent::Message { channel: "TEST_DISPATCH_ORDERS".to_string(), payload: b"O2".to_vec() }),
+        ]);
+
+        // Step 3: Shutting down gives the subscriber back, still subscribed
+        assert!(dispatcher.is_running());
+        let subscriber = dispatcher.shutdown().unwrap();
+        assert_eq!(subscriber.channels(), ["TEST_DISPATCH_ORDERS".to_string()]);
+        assert_eq!(subscriber.patterns(), ["TEST_DISPATCH_NEWS.*".to_string()]);
+    }
+
+    #[test]
+    fn test_acl_users() {
+        // Step 1: Create a user with passwords, keys, channels and commands (ensure Redis is running on localhost:6379)
+        let mut conn = RedisConnection::new("127.0.0.1:6379");
+        assert_eq!(AclWhoAmICommand.query(&mut conn).unwrap(), "default");
+        AclDelUserCommand::new(["test_acl_Reader"]).query(&mut conn).unwrap();
+        let rules = ["on", ">Secret", "~CACHE:*", "%R~SHARED:*", "&news", "+@read", "-keys"];
+        assert!(AclSetUserCommand::new("test_acl_Reader".to_string()).rules(rules).rule("+config|get").query(&mut conn).unwrap());
+        assert!(AclListCommand.query(&mut conn).unwrap().iter().any(|line| line.starts_with("user test_acl_Reader on")));
+
+        // Step 2: Read it back, structured
+        let user = AclGetUserCommand::new("test_acl_Reader".to_string()).query(&mut conn).unwrap().unwrap();
+        assert!(user.is_enabled() && !user.has_flag("nopass"));
+        assert_eq!(user.passwords.len(), 1);
+        assert_ne!(user.passwords[0], "Secret");
+        assert_eq!(user.keys, vec![
+            KeyPattern { pattern: "CACHE:*".to_string(), read: true, write: true },
+            KeyPattern { pattern: "SHARED:*".to_string(), read: true, write: false },
+        ]);
+        assert_eq!(user.channels, vec!["news"]);
+        assert_eq!(user.command_rules, vec![
+            CommandRule::Deny("@all".to_string()),
+            CommandRule::Allow("@read".to_string()),
+            CommandRule::Deny("keys".to_string()),
+            CommandRule::Allow("config|get".to_string()),
+        ]);
+        assert!(user.selectors.is_empty());
+        assert_eq!(AclGetUserCommand::new("test_acl_reader".to_string()).query(&mut conn).unwrap(), None);
+
+        // Step 3: Categories, and the cleanup
+        assert!(AclCatCommand::new().query(&mut conn).unwrap().contains(&"dangerous".to_string()));
+        assert!(AclCatCommand::category("string".to_string()).query(&mut conn).unwrap().contains(&"get".to_string()));
+        assert!(AclCatCommand::category("nosuchcategory".to_string()).query(&mut conn).is_err());
+        assert_eq!(AclDelUserCommand::new(["test_acl_Reader", "test_acl_missing"]).query(&mut conn).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_latency_and_memory_diagnostics() {
+        // Step 1: Record latency events above 1ms and cause one (ensure Redis is running on localhost:6379)
+        let mut conn = RedisConnection::new("127.0.0.1:6379");
+        struct DebugSleepCommand;
+        impl Command for DebugSleepCommand {
+            fn get_parts(&self) -> Vec<&str> {
+                vec!["DEBUG", "SLEEP", "0.01"]
+            }
+        }
+        assert!(ConfigSetCommand::new("latency-monitor-threshold".to_string(), "1".to_string()).query(&mut conn).unwrap());
+        LatencyResetCommand::new().query(&mut conn).unwrap();
+        DebugSleepCommand.execute(&mut conn).unwrap();
+
+        // Step 2: The latest spike of each event, and the history of one
+        let events = LatencyLatestCommand.query(&mut conn).unwrap();
+        let event = events.iter().find(|event| event.name == "command").unwrap();
+        assert!(event.latest >= std::time::Duration::from_millis(10) && event.max >= event.latest);
+        assert!(event.timestamp > std::time::UNIX_EPOCH);
+        let history = LatencyHistoryCommand::new("command".to_string()).query(&mut conn).unwrap();
+        assert_eq!(history.last().map(|sample| sample.latency), Some(event.latest));
+        assert!(LatencyHistoryCommand::new("fork".to_string()).query(&mut conn).unwrap().is_empty());
+
+        // Step 3: Resetting some events or all of them
+        assert_eq!(LatencyResetCommand::events(["command", "fork"]).query(&mut conn).unwrap(), 1);
+        assert!(LatencyLatestCommand.query(&mut conn).unwrap().is_empty());
+        assert!(ConfigSetCommand::new("latency-monitor-threshold".to_string(), "0".to_string()).query(&mut conn).unwrap());
+
+        // Step 4: Memory statistics, with the overhead of the databases holding keys
+        SetCommand::new("test_memory_stats_key".to_string(), "value".to_string()).execute(&mut conn).unwrap();
+        let stats = MemoryStatsCommand.query(&mut conn).unwrap();
+        assert!(stats.peak_allocated > 0 && stats.total_allocated > 0);
+        assert!(stats.keys_count >= 1 && stats.fragmentation > 0.0);
+        assert!(stats.databases.contains_key(&0));
+        assert!(!stats.other.keys().any(|name| name.starts_with("db.")));
+        assert!(!MemoryDoctorCommand.query(&mut conn).unwrap().is_empty());
+
+        // Step 5: The same over RESP3, where the doctor replies with verbatim text
+        let mut conn = RedisConnection::builder("127.0.0.1:6379").protocol(ProtocolVersion::Resp3).connect().unwrap();
+        assert!(MemoryStatsCommand.query(&mut conn).unwrap().databases.contains_key(&0));
+        assert!(!MemoryDoctorCommand.query(&mut conn).unwrap().is_empty());
+        DelCommand::new(["test_memory_stats_key"]).execute(&mut conn).unwrap();
+    }
+
+    #[test]
+    fn test_admin_commands() {
+        // Step 1: A background save, and when it finished (ensure Redis is running on localhost:6379)
+        let mut conn = RedisConnection::new("127.0.0.1:6379");
+        let before = std::time::SystemTime::now() - Duration::from_secs(2);
+        assert!(matches!(BgSaveCommand::schedule().query(&mut conn).unwrap(), BackgroundJob::Started | BackgroundJob::Scheduled));
+        let mut last_save = LastSaveCommand.query(&mut conn).unwrap();
+        for _ in 0..50 {
+            if last_save >= before {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(100));
+            last_save = LastSaveCommand.query(&mut conn).unwrap();
+        }
+        assert!(last_save >= before);
+        assert!(matches!(BgRewriteAofCommand.query(&mut conn).unwrap(), BackgroundJob::Started | BackgroundJob::Scheduled));
+        assert_eq!(BackgroundJob::from_resp3_value(Resp3Value::SimpleString("Background saving scheduled".to_string())).unwrap(), BackgroundJob::Scheduled);
+
+        // Step 2: There is no shutdown to abort
+        assert!(matches!(ShutdownCommand::abort().query(&mut conn), Err(Resp3Error::Server(_))));
+        assert_eq!(ShutdownCommand::new().save(false).now().format_resp_command(), "*3\r\n$8\r\nSHUTDOWN\r\n$6\r\nNOSAVE\r\n$3\r\nNOW\r\n");
+
+        // Step 3: A server closing the connection on SHUTDOWN, instead of replying, shut down
+        let server = MockServer::start().unwrap();
+        let mut conn = RedisConnection::new(&server.address());
+        server.inject(Fault::Disconnect);
+        assert!(ShutdownCommand::new().query(&mut conn).unwrap());
+        assert_eq!(server.commands().last().unwrap(), &["SHUTDOWN"]);
+        assert!(conn.close().is_ok());
+
+        // Step 4: A refused shutdown is an error
+        let mut conn = RedisConnection::new(&server.address());
+        server.reply("SHUTDOWN", Resp3Value::Error("ERR Errors trying to SHUTDOWN. Check logs.".to_string()));
+        assert!(matches!(ShutdownCommand::new().force().query(&mut conn), Err(Resp3Error::Server(_))));
+    }
+
+    #[test]
+    fn test_flush_guard() {
+        // Step 1: Flushing is refused before being sent (ensure Redis is running on localhost:6379)
+        struct SelectCommand;
+        impl Command for SelectCommand {
+            fn get_parts(&self) -> Vec<&str> {
+                vec!["SELECT", "9"]
+            }
+        }
+        let mut conn = RedisConnection::new("127.0.0.1:6379");
+        SelectCommand.execute(&mut conn).unwrap();
+        SetCommand::new("test_flush_key".to_string(), "value".to_string()).execute(&mut conn).unwrap();
+        let err = FlushDbCommand::new().query(&mut conn).unwrap_err();
+        assert!(matches!(&err, Resp3Error::InvalidArgument(message) if message.starts_with("FLUSHDB is destructive")));
+        assert!(matches!(conn.send_no_reply(&FlushAllCommand::new()), Err(Resp3Error::InvalidArgument(_))));
+        assert!(DbSizeCommand.query(&mut conn).unwrap() >= 1);
+
+        // Step 2: Once allowed, the database is flushed, the others are not
+        conn.allow_destructive(true);
+        assert!(FlushDbCommand::new().mode(FlushMode::Sync).query(&mut conn).unwrap());
+        assert_eq!(DbSizeCommand.query(&mut conn).unwrap(), 0);
+        assert_eq!(FlushAllCommand::new().mode(FlushMode::Async).format_resp_command(), "*2\r\n$8\r\nFLUSHALL\r\n$5\r\nASYNC\r\n");
+
+        // Step 3: Or allowed from the start, e.g. for admin tools
+        let server = MockServer::start().unwrap();
+        server.reply("FLUSHALL", Resp3Value::SimpleString("OK".to_string()));
+        let mut conn = RedisConnection::builder(&server.address()).allow_destructive(true).connect().unwrap();
+        assert!(FlushAllCommand::new().mode(FlushMode::Async).query(&mut conn).unwrap());
+        assert_eq!(server.commands().last().unwrap(), &["FLUSHALL", "ASYNC"]);
+
+        // Step 4: The wrappers and the codecs keep the guard, a key prefix refuses flushing even when allowed
+        let server = MockServer::start().unwrap();
+        server.reply("FLUSHDB", Resp3Value::SimpleString("OK".to_string()));
+        let conn = RedisConnection::builder(&server.address()).connect().unwrap();
+        let mut executor = CommandExecutor::from_connection(conn).with_journal(Journal::in_memory(10));
+        assert!(matches!(executor.query(FlushDbCommand::new()), Err(Resp3Error::InvalidArgument(_))));
+        let conn = RedisConnection::builder(&server.address()).connect().unwrap();
+        struct Identity;
+        impl ValueCodec for Identity {
+            fn encode<'a>(&self, value: &'a [u8]) -> Result<std::borrow::Cow<'a, [u8]>, Resp3Error> {
+                Ok(value.into())
+            }
+            fn decode<'a>(&self, value: &'a [u8]) -> Result<std::borrow::Cow<'a, [u8]>, Resp3Error> {
+                Ok(value.into())
+            }
+        }
+        let mut executor = CommandExecutor::from_connection(conn).with_codec(Identity);
+        assert!(matches!(executor.query(FlushDbCommand::new()), Err(Resp3Error::InvalidArgument(_))));
+        let conn = RedisConnection::builder(&server.address()).allow_destructive(true).connect().unwrap();
+        let mut tenant = KeyPrefix::new(CommandExecutor::from_connection(conn), "tenant:");
+        let err = tenant.query(FlushDbCommand::new()).unwrap_err();
+        assert!(matches!(&err, Resp3Error::InvalidArgument(message) if message.contains("key prefix")));
+        assert!(!server.commands().iter().any(|command| command.iter().any(|part| part == "FLUSHDB")));
+    }
+
+    #[test]
+    fn test_stream_consumer() {
+        // Step 1: Produce entries, the values verbatim (ensure Redis is running on localhost:6379)
+        let mut conn = RedisConnection::new("127.0.0.1:6379");
+        DelCommand::new(["test_stream_events"]).execute(&mut conn).unwrap();
+        for user in ["ada", "grace", "linus"] {
+            XAddCommand::new("test_stream_events".to_string(), [("event", "signup"), ("user", user)]).verbatim_values().query(&mut conn).unwrap();
+        }
+        assert_eq!(XLenCommand::new("test_stream_events".to_string()).query(&mut conn).unwrap(), 3);
+        let entries = XRangeCommand::new("test_stream_events".to_string(), "-", "+").count(1).query(&mut conn).unwrap();
+        assert_eq!(entries[0].get("user"), Some("ada"));
+
+        // Step 2: A consumer creates the group and leaves an entry unacknowledged on shutdown
+        let builder = RedisConnection::builder("127.0.0.1:6379").timeout(Duration::from_secs(2));
+        let consumer = |conn: RedisConnection, name: &str| StreamConsumer::new(conn, "test_stream_events", "workers", name).batch_size(2).block(Duration::from_millis(50));
+        let mut first = consumer(builder.clone().connect().unwrap(), "first");
+        let batch = first.next_batch().unwrap();
+        assert_eq!(batch.iter().map(|entry| entry.get("user").unwrap()).collect::<Vec<_>>(), ["ada", "grace"]);
+        assert_eq!(first.ack([batch[0].id.as_str()]).unwrap(), 1);
+        assert_eq!(first.unacked().collect::<Vec<_>>(), [batch[1].id.as_str()]);
+        first.shutdown().unwrap();
+
+        // Step 3: Restarted, it gets its entry again before the new ones, over RESP3 this time
+        let mut first = consumer(builder.clone().protocol(ProtocolVersion::Resp3).connect().unwrap(), "first");
+        let recovered = first.next_batch().unwrap();
+        assert_eq!(recovered, batch[1..]);
+        first.ack([recovered[0].id.clone()]).unwrap();
+        let batch = first.next_batch().unwrap();
+        assert_eq!(batch.iter().map(|entry| entry.get("user").unwrap()).collect::<Vec<_>>(), ["linus"]);
+        assert!(first.next_batch().unwrap().is_empty());
+        assert_eq!(first.stats(), ConsumerStats { delivered: 2, recovered: 1, claimed: 0, acked: 1 });
+
+        // Step 4: Another consumer takes the entry over once it is idle, the group sees who has it
+        std::thread::sleep(Duration::from_millis(20));
+        let mut second = consumer(builder.clone().connect().unwrap(), "second").claim_idle(Duration::from_millis(10));
+        let claimed = second.next_batch().unwrap();
+        assert_eq!(claimed, batch);
+        assert_eq!(second.stats().claimed, 1);
+        let pending = XPendingRangeCommand::new("test_stream_events".to_string(), "workers".to_string(), "-", "+", 10).query(&mut conn).unwrap();
+        assert_eq!((pending[0].consumer.as_str(), pending[0].deliveries), ("SECOND", 2));
+        assert_eq!(XPendingCommand::new("test_stream_events".to_string(), "workers".to_string()).query(&mut conn).unwrap().consumers, [("SECOND".to_string(), 1)]);
+
+        // Step 5: Stopping from another thread, nothing is left pending
+        second.ack([claimed[0].id.clone()]).unwrap();
+        let handle = second.stop_handle();
+        std::thread::spawn(move || handle.stop()).join().unwrap();
+        assert!(second.is_stopped() && second.next_batch().unwrap().is_empty());
+        second.shutdown().unwrap();
+        let summary = XPendingCommand::new("test_stream_events".to_string(), "workers".to_string()).query(&mut conn).unwrap();
+        assert_eq!(summary, PendingSummary::default());
+        DelCommand::new(["test_stream_events"]).execute(&mut conn).unwrap();
+    }
+
+    #[test]
+    fn test_stream_producer() {
+        // Step 1: Trimming goes before the id, exact or approximate, by length or by id
+        let add = XAddCommand::new("events".to_string(), [("event", "signup")]).trim(StreamTrim::MinId("1700000000000".to_string()), false);
+        assert_eq!(add.get_parts(), ["XADD", "events", "MINID", "=", "1700000000000", "*", "event", "signup"]);
+        assert_eq!(XAddCommand::new("events".to_string(), [("event", "signup")]).max_len(100).get_parts()[2..5], ["MAXLEN", "~", "100"]);
+        assert_eq!(XTrimCommand::new("events".to_string(), StreamTrim::MaxLen(10), true).get_parts(), ["XTRIM", "events", "MAXLEN", "~", "10"]);
+        assert_eq!(StreamTrim::since(std::time::UNIX_EPOCH + Duration::from_secs(2)), StreamTrim::MinId("2000".to_string()));
+
+        // Step 2: A batch is one write, only its last entry trims (ensure Redis is running on localhost:6379)
+        let mut conn = RedisConnection::new("127.0.0.1:6379");
+        DelCommand::new(["test_stream_producer"]).execute(&mut conn).unwrap();
+        let producer = StreamProducer::new("test_stream_producer").max_len(3).exact();
+        let ids = producer.append_batch(&mut conn, (0..5).map(|n| [("n", n.to_string())])).unwrap();
+        assert_eq!(ids.len(), 5);
+        assert_eq!(producer.len(&mut conn).unwrap(), 3);
+        let entries = XRangeCommand::new("test_stream_producer".to_string(), "-", "+").query(&mut conn).unwrap();
+        assert_eq!(entries.iter().map(|entry| entry.id.as_str()).collect::<Vec<_>>(), ids[2..]);
+        assert!(producer.append_batch(&mut conn, Vec::<[(&str, &str); 0]>::new()).unwrap().is_empty());
+
+        // Step 3: Single appends trim too, the ids in order, with the time of the event if given
+        let id = producer.append(&mut conn, &[("n", "5")]).unwrap();
+        assert!(id > ids[4]);
+        assert_eq!(producer.len(&mut conn).unwrap(), 3);
+        let later = std::time::SystemTime::now() + Duration::from_secs(60);
+        let id = producer.append_at(&mut conn, later, &[("n", "6")]).unwrap();
+        let millis = later.duration_since(std::time::UNIX_EPOCH).unwrap().as_millis();
+        assert_eq!(id, format!("{}-0", millis));
+
+        // Step 4: By age, the entries older than the cut go on the next trim
+        let producer = StreamProducer::new("test_stream_producer").max_age(Duration::ZERO).exact();
+        assert_eq!(producer.trim(&mut conn).unwrap(), 2);
+        assert_eq!(producer.len(&mut conn).unwrap(), 1);
+        assert_eq!(StreamProducer::new("test_stream_producer").trim(&mut conn).unwrap(), 0);
+
+        // Step 5: An error reply fails the batch, the entries before it are kept
+        let err = producer.append_batch(&mut conn, [vec![("n", "7")], vec![]]).unwrap_err();
+        assert!(matches!(err, Resp3Error::Server(_)));
+        assert_eq!(producer.len(&mut conn).unwrap(), 2);
+        DelCommand::new(["test_stream_producer"]).execute(&mut conn).unwrap();
+    }
+
+    #[test]
+    fn test_prepared_command() {
+        // Step 1: The encoding is the one of the command built as usual
+        let prepared = PreparedCommand::new("set").argument().argument().token("ex").token("60");
+        let mut buffer = Vec::new();
+        prepared.encode_into(&mut buffer, &[b"key", b"value"]).unwrap();
+        assert_eq!(buffer, SetCommand::new("key".to_string(), "value".to_string()).ex(60).format_resp_bytes());
+        assert_eq!(prepared.arguments(), 2);
+        assert!(matches!(prepared.encode_into(&mut buffer, &[b"key"]), Err(Resp3Error::InvalidArgument(_))));
+
+        // Step 2: Sent with the arguments of each call, the verbatim ones as they are
+        let server = MockServer::start().unwrap();
+        let mut conn = RedisConnection::new(&server.address());
+        let set = PreparedCommand::new("SET").argument().verbatim_argument();
+        let get = PreparedCommand::new("GET").argument();
+        for index in 0..3 {
+            let (key, value) = (format!("key{}", index), format!("value {}", index));
+            assert_eq!(set.request(&mut conn, &[key.as_bytes(), value.as_bytes()]).unwrap(), Resp3Value::SimpleString("OK".to_string()));
+            assert_eq!(get.query::<Option<String>>(&mut conn, &[key.as_bytes()]).unwrap(), Some(value));
+        }
+        assert_eq!(server.commands().last().unwrap(), &["GET", "KEY2"]);
+        assert!(matches!(get.request(&mut conn, &[]), Err(Resp3Error::InvalidArgument(_))));
+    }
+
+    #[test]
+    fn test_auto_pipeline() {
+        // Step 1: Commands sent from several threads share the pipelines
+        let server = MockServer::start().unwrap();
+        let pipeline = AutoPipeline::builder().window(Duration::from_millis(5)).max_batch(64).start(RedisConnection::new(&server.address()));
+        let threads: Vec<_> = (0..8).map(|thread| {
+            let pipeline = pipeline.clone();
+            std::thread::spawn(move || {
+                for index in 0..10 {
+                    let key = format!("key{}_{}", thread, index);
+                    pipeline.query(&SetCommand::new(key.clone(), format!("value{}", index))).unwrap();
+                    assert_eq!(pipeline.query(&GetCommand::new(key)).unwrap(), Some(format!("VALUE{}", index)));
+                }
+            })
+        }).collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        let (flushes, commands) = pipeline.flushes();
+        assert_eq!(commands, 160);
+        assert!(flushes < commands, "{} flushes", flushes);
+        assert_eq!(server.connections(), 1);
+
+        // Step 2: Error replies go to their own caller only, state changes are refused
+        server.reply_once("GET", Resp3Value::Error("ERR boom".to_string()));
+        let err = pipeline.query(&GetCommand::new("key0_0".to_string())).unwrap_err();
+        assert_eq!(err.server_error().unwrap().message, "boom");
+        assert_eq!(pipeline.query(&GetCommand::new("key0_0".to_string())).unwrap(), Some("VALUE0".to_string()));
+        struct SubscribeCommand;
+        impl Command for SubscribeCommand {
+            fn get_parts(&self) -> Vec<&str> {
+                vec!["SUBSCRIBE", "channel"]
+            }
+        }
+        impl TypedCommand for SubscribeCommand {
+            type Output = Resp3Value;
+        }
+        assert!(matches!(pipeline.query(&SubscribeCommand), Err(Resp3Error::InvalidState(_))));
+
+        // Step 3: A dropped connection fails its batch, the next one reconnects
+        server.inject(Fault::Disconnect);
+        assert!(matches!(pipeline.query(&GetCommand::new("key0_0".to_string())), Err(Resp3Error::Io(_))));
+        assert_eq!(pipeline.query(&GetCommand::new("key0_0".to_string())).unwrap(), Some("VALUE0".to_string()));
+        assert_eq!(server.connections(), 2);
+    }
+
+    #[test]
+    fn test_hash_field_expiration() {
+        // Step 1: The fields go last, after the condition
+        let command = HExpireCommand::new("hash".to_string(), 60, ["a", "b"]).condition(ExpireCondition::Gt);
+        assert_eq!(command.get_parts(), ["HEXPIRE", "hash", "60", "GT", "FIELDS", "2", "a", "b"]);
+        assert_eq!(HPTtlCommand::new("hash".to_string(), ["a"]).get_parts(), ["HPTTL", "hash", "FIELDS", "1", "a"]);
+
+        // Step 2: A server without them fails cleanly, without the command being sent
+        let server = MockServer::start().unwrap();
+        server.reply("COMMAND", Resp3Value::Array(vec![Resp3Value::Null]));
+        let mut conn = RedisConnection::new(&server.address());
+        let err = HTtlCommand::new("hash".to_string(), ["a"]).query(&mut conn).unwrap_err();
+        assert!(matches!(&err, Resp3Error::UnsupportedCommand(command) if command == "HTTL"));
+        assert_eq!(err.kind(), "UNSUPPORTED_COMMAND");
+        assert!(server.commands().iter().all(|parts| parts[0] != "HTTL"));
+
+        // Step 3: Each field gets its own result (ensure Redis 7.4+ is running on localhost:6379)
+        let mut conn = RedisConnection::new("127.0.0.1:6379");
+        if !conn.supports("HEXPIRE").unwrap() {
+            return;
+        }
+        let script = "redis.call('del', KEYS[1]) return redis.call('hset', KEYS[1], 'A', '1', 'B', '2', 'C', '3')";
+        EvalCommand::new(script.to_string(), vec!["test_hash_ttl".to_string()], vec![]).execute(&mut conn).unwrap();
+        let set = HExpireCommand::new("test_hash_ttl".to_string(), 100, ["a", "missing"]).query(&mut conn).unwrap();
+        assert_eq!(set, [FieldExpiration::Set, FieldExpiration::NoField]);
+        let set = HPExpireCommand::new("test_hash_ttl".to_string(), 50_000, ["a", "b"]).condition(ExpireCondition::Nx).query(&mut conn).unwrap();
+        assert_eq!(set, [FieldExpiration::NotSet, FieldExpiration::Set]);
+        let ttls = HTtlCommand::new("test_hash_ttl".to_string(), ["a", "c", "missing"]).query(&mut conn).unwrap();
+        assert!(matches!(ttls[0], FieldTtl::Expires(ttl) if ttl > Duration::from_secs(90)));
+        assert_eq!(ttls[1..], [FieldTtl::NoExpiry, FieldTtl::NoField]);
+        let ttls = HPTtlCommand::new("test_hash_ttl".to_string(), ["b"]).query(&mut conn).unwrap();
+        assert!(matches!(ttls[0], FieldTtl::Expires(ttl) if ttl <= Duration::from_secs(50) && ttl > Duration::from_secs(40)));
+        DelCommand::new(["test_hash_ttl"]).execute(&mut conn).unwrap();
+    }
+
+    #[test]
+    fn test_client_list_and_kill() {
+        // Step 1: Every field of a line is kept, the usual ones typed
+        let line = "id=7 addr=10.0.0.1:50188 laddr=10.0.0.2:6379 fd=8 name= age=12 idle=3 flags=N db=2 sub=1 psub=0 multi=-1 cmd=client|list user=app lib-name=redis-rs";
+        let info = ClientInfo::parse(line).unwrap();
+        assert_eq!((info.id, info.addr.as_str(), info.laddr.as_str(), info.name.as_str()), (7, "10.0.0.1:50188", "10.0.0.2:6379", ""));
+        assert_eq!((info.age, info.idle, info.db, info.sub, info.multi), (Duration::from_secs(12), Duration::from_secs(3), 2, 1, None));
+        assert_eq!((info.cmd.as_str(), info.user.as_str(), info.get("lib-name"), info.get("fd")), ("client|list", "app", Some("redis-rs"), Some("8")));
+        assert!(ClientInfo::parse("id=7 oops").is_err());
+        assert!(ClientInfo::parse("id=seven").is_err());
+
+        // Step 2: The filters go in the order given, their values as they are
+        let kill = ClientKillCommand::new().user("App").client_type(ClientType::PubSub).skip_me(true);
+        assert_eq!(kill.get_parts(), ["CLIENT", "KILL", "USER", "App", "TYPE", "PUBSUB", "SKIPME", "YES"]);
+        assert!(kill.is_verbatim(3));
+        assert_eq!(ClientListCommand::new().ids([1, 2]).get_parts(), ["CLIENT", "LIST", "ID", "1", "2"]);
+
+        // Step 3: A reply of several lines gives one ClientInfo each
+        let server = MockServer::start().unwrap();
+        server.reply("CLIENT", Resp3Value::BulkString(format!("{}\nid=8 addr=10.0.0.3:50190 flags=P\n", line).into_bytes()));
+        let mut conn = RedisConnection::new(&server.address());
+        let clients = ClientListCommand::new().query(&mut conn).unwrap();
+        assert_eq!(clients.iter().map(|client| client.id).collect::<Vec<_>>(), [7, 8]);
+        assert_eq!(clients[1].flags, "P");
+
+        // Step 4: A connection is found in the list and killed by id (ensure Redis is running on localhost:6379)
+        let mut admin = RedisConnection::new("127.0.0.1:6379");
+        let mut victim = RedisConnection::new("127.0.0.1:6379");
+        let id = ClientIdCommand.query(&mut victim).unwrap();
+        let clients = ClientListCommand::new().client_type(ClientType::Normal).query(&mut admin).unwrap();
+        let listed = clients.iter().find(|client| client.id == id).unwrap();
+        assert_eq!(listed.cmd, "client|id");
+        assert_eq!(ClientKillCommand::new().addr(&listed.addr).query(&mut admin).unwrap(), 1);
+        assert_eq!(ClientKillCommand::new().id(id).query(&mut admin).unwrap(), 0);
+        assert!(ClientListCommand::new().query(&mut admin).unwrap().iter().all(|client| client.id != id));
+    }
+
+    #[test]
+    fn test_verify_role() {
+        // Step 1: The three shapes of ROLE replies
+        let bulk = |text: &str| Resp3Value::BulkString(text.as_bytes().to_vec());
+        let master = Resp3Value::Array(vec![bulk("master"), Resp3Value::Integer(3129659), Resp3Value::Array(vec![
+            Resp3Value::Array(vec![bulk("127.0.0.1"), bulk("9001"), bulk("3129242")]),
+        ])]);
+        let replica = Resp3Value::Array(vec![bulk("slave"), bulk("127.0.0.1"), Resp3Value::Integer(9000), bulk("connected"), Resp3Value::Integer(3167038)]);
+        let replicas = vec![ReplicaOffset { host: "127.0.0.1".to_string(), port: 9001, offset: 3129242 }];
+        assert_eq!(Role::from_resp3_value(master.clone()).unwrap(), Role::Master { offset: 3129659, replicas });
+        let role = Role::from_resp3_value(replica.clone()).unwrap();
+        assert_eq!(role.server_role(), ServerRole::Replica);
+        assert!(matches!(role, Role::Replica { master_port: 9000, offset: 3167038, .. }));
+        let sentinel = Resp3Value::Array(vec![bulk("sentinel"), Resp3Value::Array(vec![bulk("mymaster")])]);
+        assert_eq!(Role::from_resp3_value(sentinel).unwrap(), Role::Sentinel { masters: vec!["mymaster".to_string()] });
+        assert!(Role::from_resp3_value(Resp3Value::Array(vec![bulk("master")])).is_err());
+
+        // Step 2: A node demoted while connected fails the reconnect and is marked unhealthy
+        let server = MockServer::start().unwrap();
+        server.reply("ROLE", master);
+        let mut conn = RedisConnection::builder(&server.address()).verify_role(ServerRole::Master).connect().unwrap();
+        server.reply("ROLE", replica);
+        let err = conn.reconnect().unwrap_err();
+        assert!(matches!(&err, Resp3Error::WrongRole { expected, actual, .. } if expected == "master" && actual == "replica"));
+        assert_eq!(err.kind(), "WRONG_ROLE");
+        assert!(!conn.endpoint_health()[0].healthy);
+        assert!(RedisConnection::builder(&server.address()).verify_role(ServerRole::Master).connect().is_err());
+        assert!(RedisConnection::builder(&server.address()).verify_role(ServerRole::Replica).connect().is_ok());
+
+        // Step 3: A standalone server is a master (ensure Redis is running on localhost:6379)
+        let mut conn = RedisConnection::builder("127.0.0.1:6379").verify_role(ServerRole::Master).connect().unwrap();
+        assert_eq!(RoleCommand.query(&mut conn).unwrap().server_role(), ServerRole::Master);
+        assert!(RedisConnection::builder("127.0.0.1:6379").verify_role(ServerRole::Replica).connect().is_err());
+    }
+
+    #[test]
+    fn test_value_size_guard() {
+        // Step 1: Sizes fall in power of two buckets, the quantiles are their upper bounds
+        let mut histogram = SizeHistogram::default();
+        for size in [0, 1, 3, 5, 6, 1000, 70_000] {
+            histogram.record(size);
+        }
+        assert_eq!(histogram.buckets(), [(0, 1), (1, 1), (3, 1), (7, 2), (1023, 1), (131_071, 1)]);
+        assert_eq!((histogram.count(), histogram.total_bytes(), histogram.max()), (7, 71_015, 70_000));
+        assert_eq!((histogram.quantile(0.5), histogram.quantile(0.8), histogram.quantile(1.0)), (7, 1023, 70_000));
+        assert_eq!(SizeHistogram::default().quantile(0.99), 0);
+
+        // Step 2: Only the values are measured, not the keys, fields or options
+        assert_eq!(value_indices("SET", 6), [2]);
+        assert_eq!(value_indices("SETEX", 4), [3]);
+        assert_eq!(value_indices("MSET", 5), [2, 4]);
+        assert_eq!(value_indices("HSET", 6), [3, 5]);
+        assert_eq!(value_indices("RPUSH", 4), [2, 3]);
+        assert!(value_indices("GET", 2).is_empty());
+
+        // Step 3: Oversized values are rejected before being sent, the others recorded
+        let server = MockServer::start().unwrap();
+        let mut executor = CommandExecutor::from_connection(RedisConnection::new(&server.address())).with_value_guard(ValueGuard::reject(16));
+        assert!(executor.query(SetCommand::new("small".to_string(), "x".repeat(10))).unwrap());
+        let err = executor.query(SetCommand::new("large".to_string(), "x".repeat(17))).unwrap_err();
+        assert!(matches!(&err, Resp3Error::InvalidArgument(message) if message.contains("17 bytes")));
+        assert!(server.commands().iter().all(|parts| parts.get(1).map(String::as_str) != Some("LARGE")));
+        assert!(executor.query(MSetCommand::new([("a", "1234"), ("b", "")])).unwrap());
+        let stats = executor.stats().snapshot();
+        assert_eq!((stats.value_sizes.count(), stats.value_sizes.total_bytes(), stats.oversized_values), (3, 14, 1));
+
+        // Step 4: A warning guard lets them through, counting them all the same
+        let mut executor = CommandExecutor::from_connection(RedisConnection::new(&server.address())).with_value_guard(ValueGuard::warn(16));
+        assert!(executor.query(SetCommand::new("large".to_string(), "x".repeat(100))).unwrap());
+        let stats = executor.stats().snapshot();
+        assert_eq!((stats.value_sizes.max(), stats.oversized_values), (100, 1));
+    }
+
+    #[cfg(feature="lz4")]
+    #[test]
+    fn test_value_compression() {
+        use crate::resp3::utils::compression::Compression;
+
+        // Step 1: Values round trip in the LZ4 block format, small or incompressible ones untouched
+        let compression = Compression::lz4(16);
+        let mut seed = 7u32;
+        let noise: Vec<u8> = (0..2000).map(|_| { seed = seed.wrapping_mul(1103515245).wrapping_add(12345); (seed >> 16) as u8 }).collect();
+        let text = "the quick brown fox jumps over the lazy dog, ".repeat(100).into_bytes();
+        for value in [vec![b'a'; 20], vec![b'a'; 70_000], text.clone(), noise.clone(), [text.clone(), noise.clone()].concat(), b"0123456789abcdef".to_vec()] {
+            let compressed = compression.compress(&value);
+            assert_eq!(Compression::decompress(&compressed).unwrap(), value.as_slice());
+        }
+        let written_before = [&[0xFF, b'R', b'3', b'Z', 1, 20, 0, 0, 0][..], &[0x1A, b'a', 1, 0, 0x50, b'a', b'a', b'a', b'a', b'a']].concat();
+        assert_eq!(Compression::decompress(&written_before).unwrap(), &[b'a'; 20][..]);
+        assert!(compression.compress(&text).len() < text.len() / 10);
+        assert_eq!(compression.compress(&noise), noise.as_slice());
+        assert_eq!(compression.compress(b"short"), &b"short"[..]);
+        assert_eq!(Compression::decompress(b"legacy value").unwrap(), &b"legacy value"[..]);
+        let mut corrupt = compression.compress(&text).into_owned();
+        corrupt.truncate(corrupt.len() - 3);
+        assert!(Compression::decompress(&corrupt).is_err());
+        let oversized = [&[0xFF, b'R', b'3', b'Z', 1, 0xFF, 0xFF, 0xFF, 0xFF][..], &[0x1A, b'a', 1, 0, 0x50]].concat();
+        assert!(Compression::decompress(&oversized).is_err());
+
+        // Step 2: The executor compresses what it writes and decompresses what it reads
+        let server = MockServer::start().unwrap();
+        let mut executor = CommandExecutor::from_connection(RedisConnection::new(&server.address())).with_codec(compression);
+        let value = "Compressed ".repeat(50);
+        assert!(executor.query(SetCommand::new("big".to_string(), value.clone())).unwrap());
+        assert!(server.commands().last().unwrap()[2].len() < 100);
+        assert_eq!(executor.query(GetCommand::new("big".to_string())).unwrap(), Some(value.to_uppercase()));
+        let stats = executor.stats().snapshot();
+        assert!(stats.value_sizes.max() < 100);
+
+        // Step 3: Values written without it are read as they are
+        let mut plain = CommandExecutor::from_connection(RedisConnection::new(&server.address()));
+        assert!(plain.query(SetCommand::new("legacy".to_string(), value.clone())).unwrap());
+        assert_eq!(executor.query(GetCommand::new("legacy".to_string())).unwrap(), Some(value.to_uppercase()));
+    }
+
+    #[test]
+    fn test_value_codec() {
+        // Reverses the values behind a marker byte, standing in for an encryption
+        struct Reversed;
+        impl ValueCodec for Reversed {
+            fn encode<'a>(&self, value: &'a [u8]) -> Result<std::borrow::Cow<'a, [u8]>, Resp3Error> {
+                Ok([&[0xFE][..], &value.iter().rev().copied().collect::<Vec<u8>>()].concat().into())
+            }
+            fn decode<'a>(&self, value: &'a [u8]) -> Result<std::borrow::Cow<'a, [u8]>, Resp3Error> {
+                match value.split_first() {
+                    Some((0xFE, rest)) => Ok(rest.iter().rev().copied().collect::<Vec<u8>>().into()),
+                    _ => Ok(value.into()),
+                }
+            }
+        }
+
+        // Step 1: Only the values are encoded, uppercased first like they would have been stored
+        let server = MockServer::start().unwrap();
+        let mut executor = CommandExecutor::from_connection(RedisConnection::new(&server.address())).with_codec(Reversed);
+        assert!(executor.query(MSetCommand::new([("a", "one"), ("b", "two")])).unwrap());
+        let sent = server.commands().last().unwrap().clone();
+        assert_eq!((sent[1].as_str(), sent[3].as_str()), ("A", "B"));
+        assert!(sent[2].ends_with("ENO") && sent[4].ends_with("OWT"));
+
+        // Step 2: The values read are decoded, the ones it did not encode left alone
+        let mut plain = CommandExecutor::from_connection(RedisConnection::new(&server.address()));
+        assert!(plain.query(SetCommand::new("c".to_string(), "three".to_string())).unwrap());
+        let values = executor.query(MGetCommand::new(["a", "b", "c", "missing"])).unwrap();
+        assert_eq!(values, [Some("ONE".to_string()), Some("TWO".to_string()), Some("THREE".to_string()), None]);
+    }
+
+    #[cfg(feature="encryption")]
+    #[test]
+    fn test_aes_gcm_codec() {
+        use crate::resp3::utils::encryption::AesGcmCodec;
+
+        // Step 1: Values are sealed with a random nonce and open with the key they name
+        let codec = AesGcmCodec::new(2, [7; 32]);
+        let sealed = codec.encode(b"card 4242").unwrap().into_owned();
+        assert_eq!(AesGcmCodec::encrypted_with(&sealed), Some(2));
+        assert_ne!(codec.encode(b"card 4242").unwrap(), sealed.as_slice());
+        assert_eq!(codec.decode(&sealed).unwrap(), &b"card 4242"[..]);
+        assert!(codec.decode(b"plain").is_err());
+        assert_eq!(AesGcmCodec::new(2, [7; 32]).allow_plaintext().decode(b"plain").unwrap(), &b"plain"[..]);
+
+        // Step 2: Tampered values and unknown keys fail, previous keys still decrypt
+        let mut tampered = sealed.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert!(codec.decode(&tampered).is_err());
+        assert!(AesGcmCodec::new(3, [7; 32]).decode(&sealed).is_err());
+        let rotated = AesGcmCodec::new(3, [9; 32]).decrypt_with(2, [7; 32]);
+        assert_eq!(rotated.decode(&sealed).unwrap(), &b"card 4242"[..]);
+        assert_eq!(AesGcmCodec::encrypted_with(&rotated.encode(b"x").unwrap()), Some(3));
+
+        // Step 3: The executor stores them encrypted and reads them back in clear
+        let server = MockServer::start().unwrap();
+        let mut executor = CommandExecutor::from_connection(RedisConnection::new(&server.address())).with_codec(rotated);
+        assert!(executor.query(SetCommand::new("secret".to_string(), "pin 1234".to_string()).verbatim_value()).unwrap());
+        assert!(!server.commands().last().unwrap()[2].contains("pin"));
+        assert_eq!(executor.query(GetCommand::new("secret".to_string())).unwrap(), Some("pin 1234".to_string()));
+
+        // Step 4: Values stored in clear are refused, bound values only open under their own key
+        let mut plain = CommandExecutor::from_connection(RedisConnection::new(&server.address()));
+        assert!(plain.query(SetCommand::new("secret".to_string(), "pin 0000".to_string())).unwrap());
+        assert!(matches!(executor.query(GetCommand::new("secret".to_string())), Err(Resp3Error::UnexpectedReply(_))));
+        let mut bound = CommandExecutor::from_connection(RedisConnection::new(&server.address())).with_codec(AesGcmCodec::new(1, [5; 32]).bind_to_key());
+        assert!(bound.query(MSetCommand::new([("alice", "pin 1111"), ("bob", "pin 2222")]).verbatim_values()).unwrap());
+        let values = bound.query(MGetCommand::new(["alice", "bob"])).unwrap();
+        assert_eq!(values, [Some("pin 1111".to_string()), Some("pin 2222".to_string())]);
+        let mut conn = RedisConnection::new(&server.address());
+        let sealed: Option<Vec<u8>> = PreparedCommand::new("GET").argument().query(&mut conn, &[b"alice"]).unwrap();
+        PreparedCommand::new("SET").argument().verbatim_argument().request(&mut conn, &[b"bob", &sealed.unwrap()]).unwrap();
+        assert!(bound.query(GetCommand::new("bob".to_string())).is_err());
+    }
+
+    #[test]
+    fn test_random_sampling() {
+        // Step 1: Samples with repeats go as negative counts
+        assert_eq!(HRandFieldsWithValuesCommand::new("h".to_string(), Sample::WithRepeats(3)).get_parts(), ["HRANDFIELD", "h", "-3", "WITHVALUES"]);
+        assert_eq!(ZRandMembersCommand::new("z".to_string(), Sample::Distinct(2)).get_parts(), ["ZRANDMEMBER", "z", "2"]);
+
+        // Step 2: The pairs come nested over RESP3 and flat over RESP2
+        let bulk = |text: &str| Resp3Value::BulkString(text.as_bytes().to_vec());
+        let command = ZRandMembersWithScoresCommand::new("z".to_string(), Sample::Distinct(2));
+        let nested = Resp3Value::Array(vec![Resp3Value::Array(vec![bulk("a"), Resp3Value::Double(1.5)]), Resp3Value::Array(vec![bulk("b"), Resp3Value::Double(2.0)])]);
+        let flat = Resp3Value::Array(vec![bulk("a"), bulk("1.5"), bulk("b"), bulk("2")]);
+        let expected = vec![("a".to_string(), 1.5), ("b".to_string(), 2.0)];
+        assert_eq!(command.parse_output(nested).unwrap(), expected);
+        assert_eq!(command.parse_output(flat).unwrap(), expected);
+        assert!(command.parse_output(Resp3Value::Array(Vec::new())).unwrap().is_empty());
+
+        // Step 3: Distinct samples stop at the size of the key, repeats do not (ensure Redis is running on localhost:6379)
+        let mut conn = RedisConnection::new("127.0.0.1:6379");
+        let script = "redis.call('del', KEYS[1], KEYS[2], KEYS[3]) redis.call('hset', KEYS[1], 'A', '1', 'B', '2') \
+            redis.call('sadd', KEYS[2], 'A', 'B', 'C') return redis.call('zadd', KEYS[3], 1, 'A', 2, 'B')";
+        let keys = ["test_sample_hash", "test_sample_set", "test_sample_zset"].map(String::from).to_vec();
+        EvalCommand::new(script.to_string(), keys.clone(), vec![]).execute(&mut conn).unwrap();
+        assert!(matches!(HRandFieldCommand::new(keys[0].clone()).query(&mut conn).unwrap().as_deref(), Some("A" | "B")));
+        assert_eq!(HRandFieldsCommand::new(keys[0].clone(), Sample::Distinct(5)).query(&mut conn).unwrap().len(), 2);
+        let mut pairs = HRandFieldsWithValuesCommand::new(keys[0].clone(), Sample::Distinct(2)).query(&mut conn).unwrap();
+        pairs.sort();
+        assert_eq!(pairs, [("A".to_string(), "1".to_string()), ("B".to_string(), "2".to_string())]);
+        assert!(SRandMemberCommand::new(keys[1].clone()).query(&mut conn).unwrap().is_some());
+        assert_eq!(SRandMembersCommand::new(keys[1].clone(), Sample::WithRepeats(10)).query(&mut conn).unwrap().len(), 10);
+        assert!(matches!(ZRandMemberCommand::new(keys[2].clone()).query(&mut conn).unwrap().as_deref(), Some("A" | "B")));
+        let scores = ZRandMembersWithScoresCommand::new(keys[2].clone(), Sample::WithRepeats(4)).query(&mut conn).unwrap();
+        assert!(scores.len() == 4 && scores.iter().all(|(member, score)| (member == "A" && *score == 1.0) || (member == "B" && *score == 2.0)));
+        assert!(SRandMemberCommand::new("test_sample_missing".to_string()).query(&mut conn).unwrap().is_none());
+        DelCommand::new(keys.iter().map(String::as_str)).execute(&mut conn).unwrap();
+    }
+
+    #[test]
+    fn test_command_lint() {
+        // Step 1: Each kind has its severity, overridden command by command
+        let lint = Lint::new().dangerous(Severity::Deny).command("smembers", Severity::Warn).allow("FLUSHDB");
+        assert_eq!(lint.lint("keys"), Some((Severity::Deny, "it blocks the server while walking every key, use SCAN")));
+        assert_eq!(lint.lint("GETSET").map(|(severity, _)| severity), Some(Severity::Warn));
+        assert_eq!(lint.lint("SMEMBERS").map(|(severity, _)| severity), Some(Severity::Warn));
+        assert_eq!(lint.lint("FLUSHDB"), None);
+        assert_eq!(lint.lint("GET"), None);
+        assert_eq!(Lint::new().deprecated(Severity::Allow).lint("SETNX"), None);
+
+        // Step 2: Denied commands are never sent, the others go through
+        let server = MockServer::start().unwrap();
+        server.reply("KEYS", Resp3Value::Array(vec![]));
+        let mut executor = CommandExecutor::from_connection(RedisConnection::new(&server.address())).with_lint(lint);
+        let err = executor.query(KeysCommand::new("*".to_string())).unwrap_err();
+        assert!(matches!(&err, Resp3Error::InvalidArgument(message) if message.starts_with("KEYS is denied")));
+        assert!(server.commands().iter().all(|parts| parts[0] != "KEYS"));
+        assert!(executor.query(SetCommand::new("a".to_string(), "1".to_string())).unwrap());
+
+        // Step 3: Allowed again, the command is sent
+        let mut executor = CommandExecutor::from_connection(RedisConnection::new(&server.address())).with_lint(Lint::new().allow("KEYS"));
+        assert!(executor.query(KeysCommand::new("*".to_string())).unwrap().is_empty());
+        assert_eq!(server.commands().last().unwrap()[0], "KEYS");
+    }
+
+    #[test]
+    fn test_lcs() {
+        // Step 1: The options follow IDX
+        let command = LcsIdxCommand::new("a".to_string(), "b".to_string()).min_match_len(4).with_match_len();
+        assert_eq!(command.get_parts(), ["LCS", "a", "b", "IDX", "MINMATCHLEN", "4", "WITHMATCHLEN"]);
+
+        // Step 2: The nested ranges decode from RESP2 as from RESP3
+        let integer = Resp3Value::Integer;
+        let range = |start, end| Resp3Value::Array(vec![integer(start), integer(end)]);
+        let matches = Resp3Value::Array(vec![
+            Resp3Value::Array(vec![range(4, 7), range(5, 8), integer(4)]),
+            Resp3Value::Array(vec![range(2, 3), range(0, 1), integer(2)]),
+        ]);
+        let reply = Resp3Value::Array(vec![Resp3Value::BulkString(b"matches".to_vec()), matches, Resp3Value::BulkString(b"len".to_vec()), integer(6)]);
+        let expected = LcsMatches { len: 6, matches: vec![
+            LcsMatch { first: 4..=7, second: 5..=8, len: Some(4) },
+            LcsMatch { first: 2..=3, second: 0..=1, len: Some(2) },
+        ]};
+        assert_eq!(command.parse_output(command.normalize_resp2(reply)).unwrap(), expected);
+        assert!(LcsMatch::from_resp3_value(Resp3Value::Array(vec![range(1, 2)])).is_err());
+
+        // Step 3: The subsequence, its length and where it is (ensure Redis 7.0+ is running on localhost:6379)
+        let mut conn = RedisConnection::new("127.0.0.1:6379");
+        SetCommand::new("test_lcs_1".to_string(), "ohmytext".to_string()).execute(&mut conn).unwrap();
+        SetCommand::new("test_lcs_2".to_string(), "mynewtext".to_string()).execute(&mut conn).unwrap();
+        let keys = || ("test_lcs_1".to_string(), "test_lcs_2".to_string());
+        assert_eq!(LcsCommand::new(keys().0, keys().1).query(&mut conn).unwrap(), "MYTEXT");
+        assert_eq!(LcsLenCommand::new(keys().0, keys().1).query(&mut conn).unwrap(), 6);
+        let found = LcsIdxCommand::new(keys().0, keys().1).min_match_len(4).with_match_len().query(&mut conn).unwrap();
+        assert_eq!(found, LcsMatches { len: 6, matches: vec![LcsMatch { first: 4..=7, second: 5..=8, len: Some(4) }] });
+        let found = LcsIdxCommand::new(keys().0, keys().1).query(&mut conn).unwrap();
+        assert_eq!(found.matches.len(), 2);
+        assert!(found.matches.iter().all(|found| found.len.is_none()));
+        DelCommand::new(["test_lcs_1", "test_lcs_2"]).execute(&mut conn).unwrap();
+    }
+
+    #[test]
+    fn test_large_value() {
+        // Step 1: The chunks go in one pipeline before the manifest, binary bytes untouched
+        let server = MockServer::start().unwrap();
+        let mut conn = RedisConnection::new(&server.address());
+        let blob = LargeValue::new("blob").chunk_size(1024);
+        let value: Vec<u8> = (0..3000u32).map(|byte| (byte * 7) as u8).collect();
+        let manifest = blob.write(&mut conn, &value).unwrap();
+        assert_eq!((manifest.chunks, manifest.length, manifest.checksum), (3, 3000, 0xb26b_8940));
+        let commands = server.commands();
+        let sets: Vec<_> = commands.iter().filter(|parts| parts[0] == "SET").map(|parts| parts[1].clone()).collect();
+        let chunk = |index| blob.chunk_key(&manifest.generation, index).to_uppercase();
+        assert_eq!(sets, [chunk(0), chunk(1), chunk(2), "BLOB".to_string()]);
+        assert_eq!(blob.read(&mut conn).unwrap().unwrap(), value);
+
+        // Step 2: A new write replaces the manifest then drops the chunks of the old one
+        let manifest = blob.write(&mut conn, b"small").unwrap();
+        assert_eq!(manifest.chunks, 1);
+        let deleted = server.commands().last().unwrap().clone();
+        assert_eq!(deleted, ["DEL".to_string(), chunk(0), chunk(1), chunk(2)]);
+        assert_eq!(blob.read(&mut conn).unwrap().unwrap(), b"small");
+
+        // Step 3: Chunks that do not add up to the manifest are an error, not a value
+        let key = blob.chunk_key(&manifest.generation, 0);
+        SetCommand::new(key, "smell".to_string()).verbatim_value().execute(&mut conn).unwrap();
+        assert!(matches!(blob.read(&mut conn), Err(Resp3Error::UnexpectedReply(message)) if message.contains("manifest")));
+        assert!(blob.delete(&mut conn).unwrap());
+        assert!(blob.read(&mut conn).unwrap().is_none());
+        assert!(!blob.delete(&mut conn).unwrap());
+
+        // Step 4: Values spanning a few chunks, then an empty one with no chunk at all (ensure Redis is running on localhost:6379)
+        let mut conn = RedisConnection::new("127.0.0.1:6379");
+        let blob = LargeValue::new("test_large_value").chunk_size(100_000).ttl(Duration::from_secs(60));
+        let value: Vec<u8> = (0..250_000u32).map(|byte| (byte % 251) as u8).collect();
+        blob.write(&mut conn, &value).unwrap();
+        assert_eq!(blob.read(&mut conn).unwrap().unwrap(), value);
+        assert_eq!(blob.write(&mut conn, b"").unwrap().chunks, 0);
+        assert_eq!(blob.read(&mut conn).unwrap().unwrap(), b"");
+        assert!(blob.delete(&mut conn).unwrap());
+    }
+
+    #[cfg(feature="blobs")]
+    #[test]
+    fn test_blob_store() {
+        use crate::resp3::blob_store::BlobStore;
+
+        // Step 1: Blobs are keyed by the hex SHA-256 of their content
+        let store = BlobStore::new("assets");
+        let digest = BlobStore::digest(b"abc");
+        assert_eq!(digest, "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+        assert_eq!(store.blob_key(&digest), format!("assets:blob:{}", digest));
+
+        // Step 2: The reference is counted before the SET NX, the binary content sent as it is
+        let server = MockServer::start().unwrap();
+        let mut conn = RedisConnection::new(&server.address());
+        server.reply("HINCRBY", Resp3Value::Integer(1));
+        let value = [0xFF, 0x00, b'a', b'\n'];
+        let stored = store.store(&mut conn, &value).unwrap();
+        assert!(stored.created && stored.references == 1);
+        let commands = server.commands();
+        assert_eq!(commands[commands.len() - 2], ["HINCRBY", "ASSETS:REFS", &stored.digest.to_uppercase(), "1"]);
+        assert_eq!(commands[commands.len() - 1][3], "NX");
+        server.reply("HINCRBY", Resp3Value::Integer(2));
+        assert!(!store.store(&mut conn, &value).unwrap().created);
+        assert_eq!(store.load(&mut conn, &stored.digest).unwrap().unwrap(), value);
+
+        // Step 3: Content that does not hash to its key is an error
+        SetCommand::new(store.blob_key(&stored.digest), "tampered".to_string()).execute(&mut conn).unwrap();
+        assert!(matches!(store.load(&mut conn, &stored.digest), Err(Resp3Error::UnexpectedReply(_))));
+        assert!(store.load(&mut conn, &digest).unwrap().is_none());
+
+        // Step 4: The blob goes away with its last reference (ensure Redis is running on localhost:6379)
+        let mut conn = RedisConnection::new("127.0.0.1:6379");
+        let store = BlobStore::new("{test_blob_store}");
+        let first = store.store(&mut conn, b"shared payload").unwrap();
+        let second = store.store(&mut conn, b"shared payload").unwrap();
+        assert_eq!((first.created, second.created, second.references), (true, false, 2));
+        assert_eq!(store.references(&mut conn, &first.digest).unwrap(), 2);
+        assert_eq!(store.release(&mut conn, &first.digest).unwrap(), 1);
+        assert_eq!(store.load(&mut conn, &first.digest).unwrap().unwrap(), b"shared payload");
+        assert_eq!(store.release(&mut conn, &first.digest).unwrap(), 0);
+        assert!(store.load(&mut conn, &first.digest).unwrap().is_none());
+        assert_eq!(store.references(&mut conn, &first.digest).unwrap(), 0);
+        assert_eq!(store.release(&mut conn, &first.digest).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_inline_encoding() {
+        // Step 1: Plain arguments go as they are, the others double quoted and escaped
+        let mut inline = Vec::new();
+        GetCommand::new("foo".to_string()).encode_inline_into(&mut inline);
+        assert_eq!(inline, b"GET FOO\r\n");
+        inline.clear();
+        SetCommand::new("a key".to_string(), "line\nbreak \"q\" \\ \u{1}é".to_string()).encode_with(EncodingMode::Inline, &mut inline);
+        assert_eq!(inline, b"SET \"A KEY\" \"LINE\\nBREAK \\\"Q\\\" \\\\ \\x01\\xc3\\x89\"\r\n");
+        assert_eq!(parse_inline(&inline).unwrap(), vec![b"SET".to_vec(), b"A KEY".to_vec(), "LINE\nBREAK \"Q\" \\ \u{1}É".as_bytes().to_vec()]);
+        assert_eq!(command_name(b"subscribe \"news\"\r\n").as_deref(), Some("SUBSCRIBE"));
+        assert!(parse_inline(b"GET \"open\r\n").is_none());
+
+        // Step 2: The server reads them like the RESP encoding, empty arguments included
+        let mut conn = RedisConnection::builder("127.0.0.1:6379").encoding(EncodingMode::Inline).connect().unwrap();
+        let mut resp = RedisConnection::new("127.0.0.1:6379");
+        for value in ["a b\r\nc\t'd'", ""] {
+            SetCommand::new("test inline".to_string(), value.to_string()).execute(&mut conn).unwrap();
+            let reply = GetCommand::new("test inline".to_string()).execute(&mut resp).unwrap();
+            assert_eq!(reply, Resp3Value::BulkString(value.to_uppercase().into_bytes()));
+        }
+        assert_eq!(GetCommand::new("test inline".to_string()).execute(&mut conn).unwrap(), Resp3Value::BulkString(Vec::new()));
+
+        // Step 3: The connection state follows inline commands as well
+        conn.send_no_reply(&DelCommand::new(["test inline"])).unwrap();
+        assert_eq!(GetCommand::new("test inline".to_string()).execute(&mut conn).unwrap(), Resp3Value::Null);
+        struct SubscribeCommand;
+        impl Command for SubscribeCommand {
+            fn get_parts(&self) -> Vec<&str> {
+                vec!["SUBSCRIBE", "test inline"]
+            }
+        }
+        SubscribeCommand.execute(&mut conn).unwrap();
+        assert_eq!(conn.state(), ConnectionState::Subscribed);
+    }
+
+    #[cfg(feature="cli")]
+    #[test]
+    fn test_cli() {
+        // Step 1: Options come first, the rest is the command
+        let args = ["-p", "6380", "-a", "secret", "--timeout", "1.5", "set", "-h", "x"].map(String::from);
+        let options = CliOptions::parse(args).unwrap();
+        assert_eq!(options.address(), "127.0.0.1:6380");
+        assert_eq!(options.password.as_deref(), Some("secret"));
+        assert_eq!(options.timeout, Some(Duration::from_millis(1500)));
+        assert_eq!(options.command, vec!["set", "-h", "x"]);
+        assert!(CliOptions::parse(["--user".to_string(), "admin".to_string()]).is_err());
+        assert!(CliOptions::parse(["--colour".to_string()]).is_err());
+
+        // Step 2: Lines are split like redis-cli does
+        assert_eq!(cli::split_line(r#"set "a key" 'it\'s' "\x41\n" plain"#).unwrap(), vec!["set", "a key", "it's", "A\n", "plain"]);
+        assert!(cli::split_line(r#"get "open"#).is_err());
+        assert!(cli::split_line(r#"get "a"b"#).is_err());
+        assert!(cli::split_line("   ").unwrap().is_empty());
+
+        // Step 3: Replies are printed like redis-cli does
+        let reply = Resp3Value::Array(vec![
+            Resp3Value::BulkString(b"a\"b".to_vec()),
+            Resp3Value::Array(vec![Resp3Value::Integer(1), Resp3Value::Null]),
+            Resp3Value::Array(vec![]),
+        ]);
+        assert_eq!(cli::format_reply(&reply), "1) \"a\\\"b\"\n2) 1) (integer) 1\n   2) (nil)\n3) (empty array)");
+        assert_eq!(cli::format_reply(&Resp3Value::Error("ERR no".to_string())), "(error) ERR no");
+        let map: Resp3Map = [(Resp3Value::SimpleString("a".to_string()), Resp3Value::Array(vec![Resp3Value::Integer(1), Resp3Value::Double(0.5)]))].into_iter().collect();
+        assert_eq!(cli::format_reply(&Resp3Value::Map(map)), "1# a => 1) (integer) 1\n        2) (double) 0.5");
+
+        // Step 4: --pipe sends RESP or one command per line, as typed, counting the error replies
+        let mut conn = CliOptions::default().connect().unwrap();
+        let (replies, errors) = cli::pipe(&mut conn, b"set TEST_CLI 'a b'\nrpush TEST_CLI c\n\nget TEST_CLI\n").unwrap();
+        assert_eq!((replies, errors), (3, 1));
+        let reply = GetCommand::new("test_cli".to_string()).execute(&mut conn).unwrap();
+        assert_eq!(reply, Resp3Value::BulkString(b"a b".to_vec()));
+        let input = DelCommand::new(["test_cli"]).format_resp_bytes().repeat(2);
+        assert_eq!(cli::pipe(&mut conn, &input).unwrap(), (2, 0));
+    }
+
+    #[cfg(feature="cli")]
+    #[test]
+    fn test_cli_dump_restore() {
+        // Step 1: dump and restore are tool modes only without the arguments of DUMP and RESTORE
+        let parse = |args: &[&str]| CliOptions::parse(args.iter().map(|arg| arg.to_string())).unwrap();
+        assert_eq!(parse(&["dump", "--pattern", "user:*"]).dump.as_deref(), Some("user:*"));
+        assert_eq!(parse(&["dump"]).dump.as_deref(), Some("*"));
+        assert_eq!(parse(&["dump", "mykey"]).command, vec!["dump", "mykey"]);
+        assert!(parse(&["restore"]).restore);
+        assert!(CliOptions::parse(["dump", "--pattern"].map(String::from)).is_err());
+
+        // Step 2: One key of every supported type, one with a TTL, and a key outside the pattern
+        let mut conn = CliOptions::default().connect().unwrap();
+        let setup = "del dump:str dump:hash dump:list dump:set dump:zset\n\
+            set dump:str 'hello world' px 60000\n\
+            hset dump:hash name Ada lang Rust\n\
+            rpush dump:list c a b a\n\
+            sadd dump:set x\n\
+            zadd dump:zset 1.5 alice 2 bob\n\
+            set other:str untouched\n";
+        assert_eq!(cli::pipe(&mut conn, setup.as_bytes()).unwrap().1, 0);
+
+        // Step 3: Every matching key is written as a JSON line, with its TTL
+        let sorted = |output: Vec<u8>| {
+            let mut lines: Vec<serde_json::Value> = String::from_utf8(output).unwrap().lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+            lines.sort_by_key(|line| line["key"].as_str().unwrap().to_string());
+            lines
+        };
+        let mut output = Vec::new();
+        assert_eq!(cli::dump(&mut conn, "dump:*", &mut output).unwrap(), (5, 0));
+        let dumped = sorted(output);
+        assert_eq!(dumped[0]["key"], "dump:hash");
+        assert_eq!(dumped[0]["value"], serde_json::json!({"name": "Ada", "lang": "Rust"}));
+        assert_eq!(dumped[1]["value"], serde_json::json!(["c", "a", "b", "a"]));
+        assert_eq!(dumped[1]["pttl"], -1);
+        assert_eq!((dumped[3]["type"].as_str(), dumped[3]["value"].as_str()), (Some("string"), Some("hello world")));
+        assert!((1..=60000).contains(&dumped[3]["pttl"].as_i64().unwrap()));
+        assert_eq!(dumped[4]["value"], serde_json::json!([["alice", "1.5"], ["bob", "2"]]));
+
+        // Step 4: Restoring replaces the keys, the TTL is kept
+        let input: String = dumped.iter().map(|line| format!("{}\n", line)).collect();
+        cli::pipe(&mut conn, b"del dump:str dump:hash dump:list\nsadd dump:set y\n").unwrap();
+        assert_eq!(cli::restore(&mut conn, input.as_bytes()).unwrap(), 5);
+        let mut output = Vec::new();
+        cli::dump(&mut conn, "dump:*", &mut output).unwrap();
+        let restored = sorted(output);
+        for (restored, dumped) in restored.iter().zip(&dumped) {
+            assert_eq!((&restored["key"], &restored["value"]), (&dumped["key"], &dumped["value"]));
+        }
+        assert!((1..=60000).contains(&restored[3]["pttl"].as_i64().unwrap()));
+        assert_eq!(restored[1]["pttl"], -1);
+
+        // Step 5: Invalid lines are reported with their number
+        let err = cli::restore(&mut conn, "\n{\"key\": \"dump:bad\", \"type\": \"stream\", \"value\": []}\n".as_bytes()).unwrap_err();
+        assert!(err.to_string().contains("Line 2: Unsupported type stream"), "{}", err);
+    }
 }
@@ -0,0 +1,232 @@
+// src/resp3/analysis.rs
+
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap};
+
+use crate::resp3::commands::keys::{MemoryUsageCommand, ScanCommand, TypeCommand};
+use crate::resp3::error::{Resp3Error, ServerError};
+use crate::resp3::utils::command::{Command, TypedCommand};
+use crate::resp3::utils::redis_connection::RedisConnection;
+use crate::resp3::value::{FromResp3Value, Resp3Value};
+
+// Where the keys matching none of the groups are counted
+pub const OTHER_GROUP: &str = "other";
+
+// Walks the keyspace with SCAN and asks TYPE and MEMORY USAGE of every key, pipelined per
+// batch, adding them up per group of keys, e.g.
+// MemoryScanner::new().group("sessions", "session:*").group("users", "user:*").top(20).scan(&mut conn)
+// SCAN may return a key more than once, it is then counted more than once
+#[derive(Debug, Clone)]
+pub struct MemoryScanner {
+    pattern: String,
+    count: u64,
+    samples: Option<u64>,
+    max_keys: Option<u64>,
+    top: usize,
+    // Name and pattern, in uppercase like the patterns sent to the server
+    groups: Vec<(String, String)>,
+}
+
+impl MemoryScanner {
+    pub fn new() -> Self {
+        MemoryScanner { pattern: "*".to_string(), count: 100, samples: None, max_keys: None, top: 10, groups: Vec::new() }
+    }
+
+    // Only the keys matching the pattern are scanned
+    pub fn pattern(mut self, pattern: &str) -> Self {
+        self.pattern = pattern.to_string();
+        self
+    }
+
+    // The COUNT hint of each SCAN, which is also the size of the pipelined batches
+    pub fn count(mut self, count: u64) -> Self {
+        self.count = count.max(1);
+        self
+    }
+
+    // The SAMPLES of MEMORY USAGE, how many nested values the size of aggregates is
+    // estimated from. 0 measures all of them, which is slow on big keys
+    pub fn samples(mut self, samples: u64) -> Self {
+        self.samples = Some(samples);
+        self
+    }
+
+    // Stops after that many keys, the report then covers a sample of the keyspace
+    pub fn max_keys(mut self, max_keys: u64) -> Self {
+        self.max_keys = Some(max_keys);
+        self
+    }
+
+    // Number of biggest keys kept in the report
+    pub fn top(mut self, top: usize) -> Self {
+        self.top = top;
+        self
+    }
+
+    // A key goes to the first group whose glob-style pattern it matches, as in KEYS
+    pub fn group(mut self, name: &str, pattern: &str) -> Self {
+        self.groups.push((name.to_string(), pattern.to_uppercase()));
+        self
+    }
+
+    pub fn scan(&self, conn: &mut RedisConnection) -> Result<MemoryReport, Resp3Error> {
+        let mut groups: BTreeMap<String, GroupUsage> = BTreeMap::new();
+        let mut biggest: BinaryHeap<Reverse<(u64, String, String)>> = BinaryHeap::new();
+        let mut report = MemoryReport::default();
+        let mut buffer = Vec::new();
+        let mut cursor = 0;
+        loop {
+            let (next, mut keys) = ScanCommand::new(cursor).pattern(self.pattern.clone()).count(self.count).query(conn)?;
+            if let Some(max_keys) = self.max_keys {
+                keys.truncate(max_keys.saturating_sub(report.keys) as usize);
+            }
+
+            buffer.clear();
+            for key in &keys {
+                TypeCommand::new(key.clone()).verbatim_key().encode_into(&mut buffer);
+                let memory_usage = MemoryUsageCommand::new(key.clone()).verbatim_key();
+                match self.samples {
+                    Some(samples) => memory_usage.samples(samples).encode_into(&mut buffer),
+                    None => memory_usage.encode_into(&mut buffer),
+                }
+            }
+            let mut replies = conn.request_pipelined(&buffer, keys.len() * 2)?.into_iter();
+            for key in keys {
+                let (Some(key_type), Some(bytes)) = (replies.next(), replies.next()) else { break };
+                let (key_type, bytes) = (String::from_resp3_value(server_reply(key_type)?)?, Option::<u64>::from_resp3_value(server_reply(bytes)?)?);
+                // Deleted or expired since SCAN returned it
+                let Some(bytes) = bytes else { continue };
+
+                report.keys += 1;
+                report.bytes += bytes;
+                let group = self.groups.iter().find(|(_, pattern)| glob_match(pattern.as_bytes(), key.as_bytes())).map_or(OTHER_GROUP, |(name, _)| name);
+                groups.entry(group.to_string()).or_insert_with(|| GroupUsage::new(group)).add(&key_type, bytes);
+                biggest.push(Reverse((bytes, key, key_type)));
+                if biggest.len() > self.top {
+                    biggest.pop();
+                }
+            }
+
+            cursor = next;
+            if cursor == 0 || self.max_keys.is_some_and(|max_keys| report.keys >= max_keys) {
+                break;
+            }
+        }
+
+        report.groups = groups.into_values().collect();
+        report.groups.sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.name.cmp(&b.name)));
+        report.top_keys = biggest.into_sorted_vec().into_iter().map(|Reverse((bytes, key, key_type))| KeyUsage { key, key_type, bytes }).collect();
+        Ok(report)
+    }
+}
+
+impl Default for MemoryScanner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MemoryReport {
+    pub keys: u64,
+    pub bytes: u64,
+    // Biggest first, the groups without keys are left out
+    pub groups: Vec<GroupUsage>,
+    // Biggest first
+    pub top_keys: Vec<KeyUsage>,
+}
+
+impl MemoryReport {
+    pub fn group(&self, name: &str) -> Option<&GroupUsage> {
+        self.groups.iter().find(|group| group.name == name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GroupUsage {
+    pub name: String,
+    pub keys: u64,
+    pub bytes: u64,
+    // Number of keys and bytes by type, e.g. "hash"
+    pub types: BTreeMap<String, (u64, u64)>,
+}
+
+impl GroupUsage {
+    fn new(name: &str) -> Self {
+        GroupUsage { name: name.to_string(), keys: 0, bytes: 0, types: BTreeMap::new() }
+    }
+
+    fn add(&mut self, key_type: &str, bytes: u64) {
+        self.keys += 1;
+        self.bytes += bytes;
+        let (keys, type_bytes) = self.types.entry(key_type.to_string()).or_default();
+        *keys += 1;
+        *type_bytes += bytes;
+    }
+
+    pub fn average_bytes(&self) -> u64 {
+        self.bytes.checked_div(self.keys).unwrap_or(0)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyUsage {
+    pub key: String,
+    pub key_type: String,
+    pub bytes: u64,
+}
+
+// The pipelined replies come back as they are, error replies included
+fn server_reply(reply: Resp3Value) -> Result<Resp3Value, Resp3Error> {
+    match reply {
+        Resp3Value::Error(message) => Err(Resp3Error::Server(ServerError::parse(&message))),
+        reply => Ok(reply),
+    }
+}
+
+// The glob-style patterns of KEYS and SCAN: *, ?, [abc], [^a-z] and \ to escape
+pub fn glob_match(pattern: &[u8], text: &[u8]) -> bool {
+    match pattern.split_first() {
+        None => text.is_empty(),
+        Some((b'*', rest)) => (0..=text.len()).any(|skip| glob_match(rest, &text[skip..])),
+        Some((b'?', rest)) => !text.is_empty() && glob_match(rest, &text[1..]),
+        Some((b'[', rest)) => {
+            let Some((&byte, text_rest)) = text.split_first() else { return false };
+            match match_class(rest, byte) {
+                Some((matched, rest)) => matched && glob_match(rest, text_rest),
+                // An unclosed [ matches itself
+                None => byte == b'[' && glob_match(rest, text_rest),
+            }
+        }
+        Some((b'\\', [escaped, rest @ ..])) => text.first() == Some(escaped) && glob_match(rest, &text[1..]),
+        Some((literal, rest)) => text.first() == Some(literal) && glob_match(rest, &text[1..]),
+    }
+}
+
+// Whether the byte is in the class starting after [, and the pattern after the closing ]
+fn match_class(mut pattern: &[u8], byte: u8) -> Option<(bool, &[u8])> {
+    let negated = pattern.first() == Some(&b'^');
+    if negated {
+        pattern = &pattern[1..];
+    }
+    let mut matched = false;
+    loop {
+        match pattern {
+            [] => return None,
+            [b']', rest @ ..] => return Some((matched != negated, rest)),
+            [b'\\', escaped, rest @ ..] => {
+                matched |= *escaped == byte;
+                pattern = rest;
+            }
+            [start, b'-', end, rest @ ..] if *end != b']' => {
+                let (low, high) = if start <= end { (*start, *end) } else { (*end, *start) };
+                matched |= (low..=high).contains(&byte);
+                pattern = rest;
+            }
+            [literal, rest @ ..] => {
+                matched |= *literal == byte;
+                pattern = rest;
+            }
+        }
+    }
+}
@@ -0,0 +1,209 @@
+// src/resp3/bench.rs
+
+use std::fmt;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::resp3::commands::get::GetCommand;
+use crate::resp3::commands::incr::IncrCommand;
+use crate::resp3::commands::set::SetCommand;
+use crate::resp3::error::{Resp3Error, ServerError};
+use crate::resp3::utils::command::Command;
+use crate::resp3::utils::redis_connection::RedisConnection;
+use crate::resp3::value::Resp3Value;
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Workload {
+    Set,
+    Get,
+    Incr,
+}
+
+impl Workload {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Workload::Set => "SET",
+            Workload::Get => "GET",
+            Workload::Incr => "INCR",
+        }
+    }
+
+    // INCR gets its own keys, it would fail on the values left by SET
+    pub fn key_prefix(&self) -> &'static str {
+        match self {
+            Workload::Set | Workload::Get => "bench:key:",
+            Workload::Incr => "bench:counter:",
+        }
+    }
+
+    fn encode_into(&self, key: String, payload: &str, buffer: &mut Vec<u8>) {
+        match self {
+            Workload::Set => SetCommand::new(key, payload.to_string()).encode_into(buffer),
+            Workload::Get => GetCommand::new(key).encode_into(buffer),
+            Workload::Incr => IncrCommand::new(key).encode_into(buffer),
+        }
+    }
+}
+
+// A redis-benchmark like load, e.g.
+// LoadGenerator::new("127.0.0.1:6379", Workload::Set).clients(50).pipeline(16).run()
+#[derive(Debug, Clone)]
+pub struct LoadGenerator {
+    address: String,
+    workload: Workload,
+    clients: usize,
+    pipeline: usize,
+    payload_size: usize,
+    requests: usize,
+    key_space: usize,
+}
+
+impl LoadGenerator {
+    // Same defaults as redis-benchmark, except for the number of random keys
+    pub fn new(address: &str, workload: Workload) -> Self {
+        LoadGenerator {
+            address: address.to_string(),
+            workload,
+            clients: 50,
+            pipeline: 1,
+            payload_size: 3,
+            requests: 100_000,
+            key_space: 10_000,
+        }
+    }
+
+    // Number of connections, each sending from its own thread
+    pub fn clients(mut self, clients: usize) -> Self {
+        self.clients = clients.max(1);
+        self
+    }
+
+    // Number of commands written before reading their replies
+    pub fn pipeline(mut self, depth: usize) -> Self {
+        self.pipeline = depth.max(1);
+        self
+    }
+
+    // Size in bytes of the SET values
+    pub fn payload_size(mut self, size: usize) -> Self {
+        self.payload_size = size;
+        self
+    }
+
+    // Total number of commands, over all the clients
+    pub fn requests(mut self, requests: usize) -> Self {
+        self.requests = requests;
+        self
+    }
+
+    // The commands go to keys bench:key:0 to bench:key:<key_space - 1>, bench:counter: for INCR
+    pub fn key_space(mut self, key_space: usize) -> Self {
+        self.key_space = key_space.max(1);
+        self
+    }
+
+    // Runs the workload and waits for every client to finish, failing on the first error reply
+    pub fn run(&self) -> Result<BenchReport, Resp3Error> {
+        let payload = "x".repeat(self.payload_size);
+        let started = Instant::now();
+        let results: Vec<Result<Vec<Duration>, Resp3Error>> = thread::scope(|scope| {
+            let handles: Vec<_> = (0..self.clients)
+                .map(|client| {
+                    // The first clients take the remainder
+                    let requests = self.requests / self.clients + usize::from(client < self.requests % self.clients);
+                    let payload = &payload;
+                    scope.spawn(move || self.run_client(client, requests, payload))
+                })
+                .collect();
+            handles.into_iter().map(|handle| handle.join().expect("Benchmark client panicked")).collect()
+        });
+        let elapsed = started.elapsed();
+
+        let mut latencies = Vec::with_capacity(self.requests);
+        for result in results {
+            latencies.extend(result?);
+        }
+        latencies.sort_unstable();
+        Ok(BenchReport { workload: self.workload, clients: self.clients, pipeline: self.pipeline, elapsed, latencies })
+    }
+
+    // Every command of a pipeline gets the latency of the whole round trip, as in redis-benchmark
+    fn run_client(&self, client: usize, requests: usize, payload: &str) -> Result<Vec<Duration>, Resp3Error> {
+        let mut conn = RedisConnection::builder(&self.address).connect()?;
+        let mut latencies = Vec::with_capacity(requests);
+        let mut buffer = Vec::new();
+        let mut sent = 0;
+        while sent < requests {
+            let count = self.pipeline.min(requests - sent);
+            buffer.clear();
+            for index in sent..sent + count {
+                let key = format!("{}{}", self.workload.key_prefix(), (client * requests + index) % self.key_space);
+                self.workload.encode_into(key, payload, &mut buffer);
+            }
+
+            let started = Instant::now();
+            let replies = conn.request_pipelined(&buffer, count)?;
+            let latency = started.elapsed();
+            if let Some(Resp3Value::Error(message)) = replies.into_iter().find(|reply| matches!(reply, Resp3Value::Error(_))) {
+                return Err(Resp3Error::Server(ServerError::parse(&message)));
+            }
+            latencies.extend(std::iter::repeat_n(latency, count));
+            sent += count;
+        }
+        Ok(latencies)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BenchReport {
+    pub workload: Workload,
+    pub clients: usize,
+    pub pipeline: usize,
+    pub elapsed: Duration,
+    // One per command, sorted
+    latencies: Vec<Duration>,
+}
+
+impl BenchReport {
+    pub fn requests(&self) -> usize {
+        self.latencies.len()
+    }
+
+    pub fn requests_per_second(&self) -> f64 {
+        self.requests() as f64 / self.elapsed.as_secs_f64()
+    }
+
+    // The latency under which the given percentage of the commands completed, e.g. 99.0
+    pub fn percentile(&self, percentage: f64) -> Duration {
+        if self.latencies.is_empty() {
+            return Duration::ZERO;
+        }
+        let rank = (percentage / 100.0 * self.latencies.len() as f64).ceil() as usize;
+        self.latencies[rank.clamp(1, self.latencies.len()) - 1]
+    }
+
+    pub fn max_latency(&self) -> Duration {
+        self.latencies.last().copied().unwrap_or_default()
+    }
+}
+
+impl fmt::Display for BenchReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let millis = |latency: Duration| latency.as_secs_f64() * 1000.0;
+        write!(
+            f,
+            "{}: {} requests in {:.2} s, {} clients, pipeline {}, {:.0} requests per second, latency ms p50={:.3} p95={:.3} p99={:.3} max={:.3}",
+            self.workload.as_str(),
+            self.requests(),
+            self.elapsed.as_secs_f64(),
+            self.clients,
+            self.pipeline,
+            self.requests_per_second(),
+            millis(self.percentile(50.0)),
+            millis(self.percentile(95.0)),
+            millis(self.percentile(99.0)),
+            millis(self.max_latency()),
+        )
+    }
+}
@@ -2,12 +2,123 @@ extern crate jni;
 
 use crate::resp3::commands::get::GetCommand;
 use crate::resp3::commands::set::SetCommand;
+use crate::resp3::error::{Resp3Error, ServerError};
 use crate::resp3::utils::redis_connection::RedisConnection;
-use jni::objects::{JClass, JString};
-use jni::sys::{jlong, jstring};
+use crate::resp3::value::Resp3Value;
+use jni::objects::{JClass, JObject, JString, JThrowable, JValue};
+use jni::sys::{jlong, jobject, jobjectArray, jstring};
 use jni::JNIEnv;
 use std::ffi::CString;
-use crate::resp3::utils::command::Command;
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Mutex, OnceLock};
+use std::thread;
+use crate::resp3::utils::command::{Command, TypedCommand};
+
+type Job = Box<dyn FnOnce() + Send>;
+
+// The asynchronous commands run one at a time on a single thread owned by the library, so two
+// of them never share a connection at once. They must not be mixed with synchronous calls on
+// the same connection, and the command and the connection must stay alive until the callback
+fn worker() -> &'static Mutex<Sender<Job>> {
+    static WORKER: OnceLock<Mutex<Sender<Job>>> = OnceLock::new();
+    WORKER.get_or_init(|| {
+        let (sender, receiver) = mpsc::channel::<Job>();
+        thread::Builder::new()
+            .name("resp3string-worker".to_string())
+            .spawn(move || receiver.into_iter().for_each(|job| job()))
+            .expect("Couldn't start the worker thread!");
+        Mutex::new(sender)
+    })
+}
+
+// Runs the command on the worker and calls callback.accept(reply, error), a
+// java.util.function.BiConsumer<Object, Throwable> with exactly one of them not null, the
+// reply as java_object makes it
+fn execute_async<C: Command + 'static>(env: JNIEnv, command_ptr: jlong, conn_ptr: jlong, callback: JObject) {
+    let vm = env.get_java_vm().expect("Couldn't get the Java VM!");
+    let callback = env.new_global_ref(callback).expect("Couldn't reference the callback!");
+
+    let job = move || {
+        let command: &C = unsafe { &*(command_ptr as *mut C) };
+        let conn: &mut RedisConnection = unsafe { &mut *(conn_ptr as *mut RedisConnection) };
+        let result = command.execute(conn);
+
+        // A daemon thread does not keep the JVM alive, and stays attached for the next jobs
+        let env = vm.attach_current_thread_as_daemon().expect("Couldn't attach the worker thread!");
+        // The thread never returns to Java, which would free the local references, so each job
+        // frees its own with its frame
+        let called = env.with_local_frame(16, || {
+            let (reply, error) = match result {
+                Ok(reply) => (java_object(&env, reply), JObject::null()),
+                Err(err) => (JObject::null(), error_object(&env, &err)),
+            };
+            env.call_method(callback.as_obj(), "accept", "(Ljava/lang/Object;Ljava/lang/Object;)V", &[JValue::Object(reply), JValue::Object(error)])?;
+            Ok(JObject::null())
+        });
+        // An exception thrown by the callback is printed and cleared, the worker goes on with the next jobs
+        if called.is_err() && env.exception_check().unwrap_or(false) {
+            let _ = env.exception_describe();
+            let _ = env.exception_clear();
+        }
+    };
+    // Panicking across extern "C" would abort the JVM, Java gets an exception instead
+    if worker().lock().unwrap().send(Box::new(job)).is_err() {
+        let _ = env.throw_new("java/lang/IllegalStateException", "The worker thread stopped");
+    }
+}
+
+// The reply as Java objects: java.util.List for arrays, java.util.Map (keeping the order of
+// the server) for maps, Long for integers, Double for doubles, java.math.BigInteger for big
+// numbers, String for the simple, verbatim and UTF-8 bulk strings, byte[] for the other bulk
+// strings, RuntimeException for the errors nested in arrays (e.g. in EXEC replies) and null
+fn java_object<'a>(env: &JNIEnv<'a>, reply: Resp3Value) -> JObject<'a> {
+    match reply {
+        Resp3Value::Null => JObject::null(),
+        Resp3Value::SimpleString(text) | Resp3Value::VerbatimString { text, .. } => java_string(env, text),
+        Resp3Value::BulkString(bytes) => match String::from_utf8(bytes) {
+            Ok(text) => java_string(env, text),
+            Err(err) => env.byte_array_from_slice(err.as_bytes()).expect("Couldn't create Java byte array!").into(),
+        },
+        Resp3Value::Error(message) => error_object(env, &Resp3Error::Server(ServerError::parse(&message))),
+        Resp3Value::Integer(integer) => env.new_object("java/lang/Long", "(J)V", &[JValue::Long(integer)]).expect("Couldn't create Java Long!"),
+        Resp3Value::Double(double) => env.new_object("java/lang/Double", "(D)V", &[JValue::Double(double)]).expect("Couldn't create Java Double!"),
+        Resp3Value::BigNumber(digits) => {
+            let digits = java_string(env, digits);
+            env.new_object("java/math/BigInteger", "(Ljava/lang/String;)V", &[JValue::Object(digits)]).expect("Couldn't create Java BigInteger!")
+        }
+        Resp3Value::Array(values) => {
+            let list = env.new_object("java/util/ArrayList", "(I)V", &[JValue::Int(values.len() as i32)]).expect("Couldn't create Java list!");
+            for value in values {
+                let element = java_object(env, value);
+                env.call_method(list, "add", "(Ljava/lang/Object;)Z", &[JValue::Object(element)]).expect("Couldn't add to Java list!");
+                // Large replies would run out of local references otherwise
+                env.delete_local_ref(element).expect("Couldn't delete local reference!");
+            }
+            list
+        }
+        Resp3Value::Map(map) => {
+            let java_map = env.new_object("java/util/LinkedHashMap", "()V", &[]).expect("Couldn't create Java map!");
+            for (key, value) in map {
+                let (key, value) = (java_object(env, key), java_object(env, value));
+                env.call_method(java_map, "put", "(Ljava/lang/Object;Ljava/lang/Object;)Ljava/lang/Object;", &[JValue::Object(key), JValue::Object(value)])
+                    .expect("Couldn't put in Java map!");
+                env.delete_local_ref(key).expect("Couldn't delete local reference!");
+                env.delete_local_ref(value).expect("Couldn't delete local reference!");
+            }
+            java_map
+        }
+    }
+}
+
+fn java_string<'a>(env: &JNIEnv<'a>, text: String) -> JObject<'a> {
+    env.new_string(text).expect("Couldn't create Java string!").into()
+}
+
+fn error_object<'a>(env: &JNIEnv<'a>, err: &Resp3Error) -> JObject<'a> {
+    let message: JObject = env.new_string(err.to_string()).expect("Couldn't create Java string!").into();
+    env.new_object("java/lang/RuntimeException", "(Ljava/lang/String;)V", &[JValue::Object(message)])
+        .expect("Couldn't create the exception!")
+}
 
 // JNI wrapper to create RedisConnection in Java
 #[no_mangle]
@@ -51,6 +162,18 @@ pub extern "C" fn Java_TestRedis_executeSetCommand(
     env.new_string(output.to_str().unwrap()).expect("Couldn't create Java string!").into_inner()
 }
 
+// JNI wrapper to execute SetCommand in Java without blocking the calling thread
+#[no_mangle]
+pub extern "C" fn Java_TestRedis_executeSetCommandAsync(
+    env: JNIEnv,
+    _class: JClass,
+    set_command_ptr: jlong,
+    conn_ptr: jlong,
+    callback: JObject,
+) {
+    execute_async::<SetCommand>(env, set_command_ptr, conn_ptr, callback);
+}
+
 // JNI wrapper to create GetCommand in Java
 #[no_mangle]
 pub extern "C" fn Java_TestRedis_createGetCommand(
@@ -79,6 +202,76 @@ pub extern "C" fn Java_TestRedis_executeGetCommand(
     env.new_string(output.to_str().unwrap()).expect("Couldn't create Java string!").into_inner()
 }
 
+// JNI wrapper to execute GetCommand in Java without blocking the calling thread
+#[no_mangle]
+pub extern "C" fn Java_TestRedis_executeGetCommandAsync(
+    env: JNIEnv,
+    _class: JClass,
+    get_command_ptr: jlong,
+    conn_ptr: jlong,
+    callback: JObject,
+) {
+    execute_async::<GetCommand>(env, get_command_ptr, conn_ptr, callback);
+}
+
+// JNI wrapper to read the value of GetCommand in Java, null for a missing key. Failures are
+// thrown as RuntimeException
+#[no_mangle]
+pub extern "C" fn Java_TestRedis_queryGetCommand(
+    env: JNIEnv,
+    _class: JClass,
+    get_command_ptr: jlong,
+    conn_ptr: jlong,
+) -> jstring {
+    let get_command: &GetCommand = unsafe { &*(get_command_ptr as *mut GetCommand) };
+    let conn: &mut RedisConnection = unsafe { &mut *(conn_ptr as *mut RedisConnection) };
+
+    match get_command.query(conn) {
+        Ok(Some(value)) => env.new_string(value).expect("Couldn't create Java string!").into_inner(),
+        Ok(None) => JObject::null().into_inner(),
+        Err(err) => {
+            env.throw(JThrowable::from(error_object(&env, &err))).expect("Couldn't throw the exception!");
+            JObject::null().into_inner()
+        }
+    }
+}
+
+// A command built from Java arguments, sent UPPERCASE like SetCommand and GetCommand
+struct Arguments(Vec<String>);
+
+impl Command for Arguments {
+    fn get_parts(&self) -> Vec<&str> {
+        self.0.iter().map(String::as_str).collect()
+    }
+}
+
+// JNI wrapper to send any command in Java, e.g.
+// executeCommand(conn, new String[] {"HGETALL", "user:1"}) returns a java.util.Map, see
+// java_object for the other replies. Error replies are thrown as RuntimeException
+#[no_mangle]
+pub extern "C" fn Java_TestRedis_executeCommand(
+    env: JNIEnv,
+    _class: JClass,
+    conn_ptr: jlong,
+    arguments: jobjectArray,
+) -> jobject {
+    let conn: &mut RedisConnection = unsafe { &mut *(conn_ptr as *mut RedisConnection) };
+
+    let length = env.get_array_length(arguments).expect("Couldn't get the Java array length!");
+    let arguments = (0..length).map(|index| {
+        let argument = env.get_object_array_element(arguments, index).expect("Couldn't get the Java array element!");
+        env.get_string(JString::from(argument)).expect("Couldn't get Java string!").into()
+    }).collect();
+
+    match Arguments(arguments).execute(conn) {
+        Ok(reply) => java_object(&env, reply).into_inner(),
+        Err(err) => {
+            env.throw(JThrowable::from(error_object(&env, &err))).expect("Couldn't throw the exception!");
+            JObject::null().into_inner()
+        }
+    }
+}
+
 // JNI wrapper to free RedisConnection memory in Java
 #[no_mangle]
 pub extern "C" fn Java_TestRedis_freeRedisConnection(
@@ -1,11 +1,43 @@
 mod python_bindings {
-    use std::net::TcpStream;
-    use pyo3::exceptions::PyRuntimeError;
+    use std::time::Duration;
     use pyo3::prelude::*;
+    use pyo3::types::{PyBytes, PyDict, PyList, PyLong, PyString, PyTuple};
     use crate::resp3::commands::get::GetCommand;
     use crate::resp3::commands::set::SetCommand;
-    use crate::resp3::utils::command::Command;
+    use crate::resp3::error::{Resp3Error, ServerError};
+    use crate::resp3::utils::command::{text_parts, Command, TypedCommand};
     use crate::resp3::utils::redis_connection::RedisConnection;
+    use crate::resp3::value::Resp3Value;
+
+    // The Python exceptions, modelled after redis-py so the same except clauses work
+    mod exceptions {
+        use pyo3::create_exception;
+        use pyo3::exceptions::PyException;
+
+        create_exception!(resp3string, Resp3Error, PyException, "Base class of the errors raised by resp3string");
+        create_exception!(resp3string, ConnectionError, Resp3Error, "The connection to the Redis server failed");
+        create_exception!(resp3string, TimeoutError, Resp3Error, "The Redis server did not answer within the timeout");
+        create_exception!(resp3string, AuthenticationError, ConnectionError, "The Redis server rejected the credentials");
+        create_exception!(resp3string, ResponseError, Resp3Error, "The Redis server replied with an error");
+        create_exception!(resp3string, WatchError, Resp3Error, "A watched key changed, the transaction was not run");
+    }
+
+    impl From<Resp3Error> for PyErr {
+        fn from(err: Resp3Error) -> PyErr {
+            let message = err.to_string();
+            match &err {
+                _ if err.is_timeout() => exceptions::TimeoutError::new_err(message),
+                Resp3Error::Io(_) => exceptions::ConnectionError::new_err(message),
+                Resp3Error::Server(reply) if is_authentication_error(reply.code_name()) => exceptions::AuthenticationError::new_err(message),
+                Resp3Error::Server(_) => exceptions::ResponseError::new_err(message),
+                _ => exceptions::Resp3Error::new_err(message),
+            }
+        }
+    }
+
+    fn is_authentication_error(code: &str) -> bool {
+        ["NOAUTH", "WRONGPASS", "NOPERM"].contains(&code)
+    }
 
     #[pyclass]
     pub struct PyRedisConnection {
@@ -14,14 +46,15 @@ mod python_bindings {
 
     #[pymethods]
     impl PyRedisConnection {
+        // The timeout, in seconds, bounds connecting and every read and write
         #[new]
-        pub fn new(address: &str) -> PyResult<Self> {
-            match TcpStream::connect(address) {
-                Ok(_) => Ok(PyRedisConnection {
-                    conn: RedisConnection::new(address), // Use the Rust implementation
-                }),
-                Err(_) => Err(PyRuntimeError::new_err("Failed to connect to Redis server")),
+        #[pyo3(signature = (address, timeout=None))]
+        pub fn new(address: &str, timeout: Option<f64>) -> PyResult<Self> {
+            let mut builder = RedisConnection::builder(address);
+            if let Some(timeout) = timeout {
+                builder = builder.timeout(Duration::from_secs_f64(timeout));
             }
+            Ok(PyRedisConnection { conn: builder.connect()? })
         }
 
         pub fn send_command(&mut self, command: &str) -> PyResult<String> {
@@ -29,8 +62,8 @@ mod python_bindings {
             Ok(self.conn.send_command(command))
         }
 
-        pub fn close(&mut self) {
-            self.conn.close();
+        pub fn close(&mut self) -> PyResult<()> {
+            Ok(self.conn.close()?)
         }
     }
 
@@ -51,6 +84,11 @@ mod python_bindings {
         pub fn execute(&self, conn: &mut PyRedisConnection) -> PyResult<String> {
             Ok(self.command.process_command(&mut conn.conn))
         }
+
+        // Like execute, with the reply decoded and the failures raised as exceptions
+        pub fn query(&self, conn: &mut PyRedisConnection) -> PyResult<bool> {
+            Ok(self.command.query(&mut conn.conn)?)
+        }
     }
 
     #[pyclass]
@@ -70,13 +108,317 @@ mod python_bindings {
         pub fn execute(&self, conn: &mut PyRedisConnection) -> PyResult<String> {
             Ok(self.command.process_command(&mut conn.conn))
         }
+
+        // Like execute, with None for a missing key and the failures raised as exceptions
+        pub fn query(&self, conn: &mut PyRedisConnection) -> PyResult<Option<String>> {
+            Ok(self.command.query(&mut conn.conn)?)
+        }
+    }
+
+    // The reply as Python objects: list, dict, int, float, str for the simple, verbatim and UTF-8
+    // bulk strings, bytes for the other bulk strings, None, and a ResponseError instance for the
+    // errors, which pipelines return in place of the replies of the commands that failed
+    fn to_python(py: Python, reply: Resp3Value) -> PyResult<PyObject> {
+        Ok(match reply {
+            Resp3Value::Null => py.None(),
+            Resp3Value::SimpleString(text) | Resp3Value::VerbatimString { text, .. } => text.into_py(py),
+            Resp3Value::BulkString(bytes) => match String::from_utf8(bytes) {
+                Ok(text) => text.into_py(py),
+                Err(err) => PyBytes::new(py, err.as_bytes()).into_py(py),
+            },
+            Resp3Value::Error(message) => PyErr::from(Resp3Error::Server(ServerError::parse(&message))).into_value(py).into_py(py),
+            Resp3Value::Integer(integer) => integer.into_py(py),
+            Resp3Value::Double(double) => double.into_py(py),
+            Resp3Value::BigNumber(digits) => py.get_type::<PyLong>().call1((digits,))?.into_py(py),
+            Resp3Value::Array(values) => {
+                let values = values.into_iter().map(|value| to_python(py, value)).collect::<PyResult<Vec<_>>>()?;
+                PyList::new(py, values).into_py(py)
+            }
+            Resp3Value::Map(map) => {
+                let dict = PyDict::new(py);
+                for (key, value) in map {
+                    dict.set_item(to_python(py, key)?, to_python(py, value)?)?;
+                }
+                dict.into_py(py)
+            }
+        })
+    }
+
+    // The arguments of a command: bytes as they are, anything else as its str()
+    fn arguments(args: &PyTuple) -> PyResult<Vec<Vec<u8>>> {
+        if args.is_empty() {
+            return Err(exceptions::Resp3Error::new_err("A command needs at least its name"));
+        }
+        args.iter().map(|arg| match arg.downcast::<PyBytes>() {
+            Ok(bytes) => Ok(bytes.as_bytes().to_vec()),
+            Err(_) => Ok(arg.str()?.to_str()?.as_bytes().to_vec()),
+        }).collect()
+    }
+
+    // A command built from Python arguments, normalized like the Rust commands: UPPERCASE,
+    // except the bytes that are not valid UTF-8
+    struct Arguments(Vec<Vec<u8>>);
+
+    impl Command for Arguments {
+        fn get_parts(&self) -> Vec<&str> {
+            text_parts(&self.get_binary_parts())
+        }
+
+        fn get_binary_parts(&self) -> Vec<&[u8]> {
+            self.0.iter().map(Vec::as_slice).collect()
+        }
+    }
+
+    // The commands of a pipeline or a transaction, encoded as they are queued
+    #[derive(Default)]
+    struct Batch {
+        buffer: Vec<u8>,
+        len: usize,
+    }
+
+    impl Batch {
+        fn push(&mut self, args: &PyTuple) -> PyResult<()> {
+            Arguments(arguments(args)?).encode_into(&mut self.buffer);
+            self.len += 1;
+            Ok(())
+        }
+
+        fn take(&mut self) -> (Vec<u8>, usize) {
+            (std::mem::take(&mut self.buffer), std::mem::take(&mut self.len))
+        }
+    }
+
+    // Commands sent in one write with their replies read back in order, e.g.
+    // PyPipeline().set("a", "1").get("a").execute(conn). Empty again after execute
+    #[pyclass]
+    pub struct PyPipeline {
+        batch: Batch,
+    }
+
+    #[pymethods]
+    impl PyPipeline {
+        #[new]
+        pub fn new() -> Self {
+            PyPipeline { batch: Batch::default() }
+        }
+
+        #[pyo3(signature = (*args))]
+        pub fn execute_command<'a>(mut slf: PyRefMut<'a, Self>, args: &PyTuple) -> PyResult<PyRefMut<'a, Self>> {
+            slf.batch.push(args)?;
+            Ok(slf)
+        }
+
+        pub fn set<'a>(slf: PyRefMut<'a, Self>, py: Python, key: &str, value: &str) -> PyResult<PyRefMut<'a, Self>> {
+            PyPipeline::execute_command(slf, PyTuple::new(py, ["SET", key, value]))
+        }
+
+        pub fn get<'a>(slf: PyRefMut<'a, Self>, py: Python, key: &str) -> PyResult<PyRefMut<'a, Self>> {
+            PyPipeline::execute_command(slf, PyTuple::new(py, ["GET", key]))
+        }
+
+        pub fn __len__(&self) -> usize {
+            self.batch.len
+        }
+
+        // One reply per command, the first error reply raised unless raise_on_error is False,
+        // which returns it in the list instead
+        #[pyo3(signature = (conn, raise_on_error=true))]
+        pub fn execute(&mut self, py: Python, conn: &mut PyRedisConnection, raise_on_error: bool) -> PyResult<PyObject> {
+            let (buffer, len) = self.batch.take();
+            let replies = conn.conn.request_pipelined(&buffer, len)?;
+            replies_to_python(py, replies, raise_on_error)
+        }
+    }
+
+    fn replies_to_python(py: Python, replies: Vec<Resp3Value>, raise_on_error: bool) -> PyResult<PyObject> {
+        if raise_on_error {
+            if let Some(Resp3Value::Error(message)) = replies.iter().find(|reply| matches!(reply, Resp3Value::Error(_))) {
+                return Err(Resp3Error::Server(ServerError::parse(message)).into());
+            }
+        }
+        to_python(py, Resp3Value::Array(replies))
+    }
+
+    // Commands run atomically between MULTI and EXEC, sent in one write on execute. Keys
+    // watched beforehand make it fail with WatchError when another client changed them, e.g.
+    // tx.watch(conn, "balance"), read it, then tx.set("balance", new).execute(conn)
+    #[pyclass]
+    pub struct PyTransaction {
+        batch: Batch,
+    }
+
+    #[pymethods]
+    impl PyTransaction {
+        #[new]
+        pub fn new() -> Self {
+            PyTransaction { batch: Batch::default() }
+        }
+
+        // Sent right away, the keys stay watched until execute, discard or unwatch
+        #[pyo3(signature = (conn, *keys))]
+        pub fn watch(&self, py: Python, conn: &mut PyRedisConnection, keys: &PyTuple) -> PyResult<()> {
+            let mut watch = Batch::default();
+            let mut args: Vec<&PyAny> = vec![PyString::new(py, "WATCH").as_ref()];
+            args.extend(keys.iter());
+            watch.push(PyTuple::new(py, args))?;
+            conn.conn.request(&watch.buffer)?;
+            Ok(())
+        }
+
+        pub fn unwatch(&self, conn: &mut PyRedisConnection) -> PyResult<()> {
+            conn.conn.request(b"*1\r\n$7\r\nUNWATCH\r\n")?;
+            Ok(())
+        }
+
+        #[pyo3(signature = (*args))]
+        pub fn execute_command<'a>(mut slf: PyRefMut<'a, Self>, args: &PyTuple) -> PyResult<PyRefMut<'a, Self>> {
+            slf.batch.push(args)?;
+            Ok(slf)
+        }
+
+        pub fn set<'a>(slf: PyRefMut<'a, Self>, py: Python, key: &str, value: &str) -> PyResult<PyRefMut<'a, Self>> {
+            PyTransaction::execute_command(slf, PyTuple::new(py, ["SET", key, value]))
+        }
+
+        pub fn get<'a>(slf: PyRefMut<'a, Self>, py: Python, key: &str) -> PyResult<PyRefMut<'a, Self>> {
+            PyTransaction::execute_command(slf, PyTuple::new(py, ["GET", key]))
+        }
+
+        pub fn __len__(&self) -> usize {
+            self.batch.len
+        }
+
+        // Drops the queued commands and the watched keys
+        pub fn discard(&mut self, conn: &mut PyRedisConnection) -> PyResult<()> {
+            self.batch.take();
+            self.unwatch(conn)
+        }
+
+        // The replies of the commands like PyPipeline.execute. A command refused while queuing,
+        // e.g. with the wrong number of arguments, aborts the whole transaction with ResponseError
+        #[pyo3(signature = (conn, raise_on_error=true))]
+        pub fn execute(&mut self, py: Python, conn: &mut PyRedisConnection, raise_on_error: bool) -> PyResult<PyObject> {
+            let (queued, len) = self.batch.take();
+            // MULTI to EXEC in one go, the connection is back to normal once all the replies are read
+            let mut buffer = b"*1\r\n$5\r\nMULTI\r\n".to_vec();
+            buffer.extend_from_slice(&queued);
+            buffer.extend_from_slice(b"*1\r\n$4\r\nEXEC\r\n");
+            let mut replies = conn.conn.request_pipelined(&buffer, len + 2)?;
+            match replies.pop() {
+                Some(Resp3Value::Array(replies)) => replies_to_python(py, replies, raise_on_error),
+                Some(Resp3Value::Null) => Err(exceptions::WatchError::new_err("Watched keys changed, the transaction was not run")),
+                Some(Resp3Value::Error(message)) => {
+                    // EXECABORT only says that the transaction was discarded, the reason is in the queuing error
+                    let reason = replies.into_iter().find_map(|reply| match reply {
+                        Resp3Value::Error(reason) => Some(reason),
+                        _ => None,
+                    });
+                    Err(Resp3Error::Server(ServerError::parse(&reason.unwrap_or(message))).into())
+                }
+                reply => Err(Resp3Error::UnexpectedReply(format!("Expected the replies of EXEC, got {:?}", reply)).into()),
+            }
+        }
+    }
+
+    // The awaitable API for asyncio applications, on the multiplexed connection of the tokio
+    // feature: the commands of every task share one connection and the event loop never blocks.
+    // pyo3-asyncio runs the commands on its tokio runtime and resolves the asyncio futures
+    #[cfg(feature = "python-asyncio")]
+    mod asyncio {
+        use std::io;
+        use std::time::Duration;
+        use pyo3::prelude::*;
+        use pyo3::types::{PyString, PyTuple};
+        use crate::resp3::error::Resp3Error;
+        use crate::resp3::multiplexed::MultiplexedConnection;
+        use crate::resp3::utils::redis_connection::RedisConnection;
+        use crate::resp3::value::Resp3Value;
+        use super::{arguments, exceptions, to_python, Arguments};
+
+        // A connection for asyncio, e.g. conn = await AsyncPyRedisConnection.connect("127.0.0.1:6379")
+        // then await conn.set("key", "value") from any number of tasks. Arguments are sent like
+        // the pipelines send them. The commands that change the state of the connection
+        // (MULTI, SUBSCRIBE, SELECT...) are refused, and a connection that drops is not opened
+        // again: its pending and later commands raise ConnectionError, connect anew
+        #[pyclass]
+        pub struct AsyncPyRedisConnection {
+            conn: Option<MultiplexedConnection>,
+        }
+
+        impl AsyncPyRedisConnection {
+            fn request(&self, args: &PyTuple) -> PyResult<(MultiplexedConnection, Arguments)> {
+                match &self.conn {
+                    Some(conn) => Ok((conn.clone(), Arguments(arguments(args)?))),
+                    None => Err(exceptions::ConnectionError::new_err("The connection is closed")),
+                }
+            }
+        }
+
+        #[pymethods]
+        impl AsyncPyRedisConnection {
+            // Connects on a blocking thread of the runtime. The timeout, in seconds, bounds
+            // connecting and the writes, wrap the commands in asyncio.wait_for to bound them
+            #[staticmethod]
+            #[pyo3(signature = (address, timeout=None))]
+            pub fn connect<'py>(py: Python<'py>, address: &str, timeout: Option<f64>) -> PyResult<&'py PyAny> {
+                let mut builder = RedisConnection::builder(address);
+                if let Some(timeout) = timeout {
+                    builder = builder.timeout(Duration::from_secs_f64(timeout));
+                }
+                pyo3_asyncio::tokio::future_into_py(py, async move {
+                    let connecting = tokio::task::spawn_blocking(move || MultiplexedConnection::connect(builder));
+                    let conn = connecting.await.map_err(|err| Resp3Error::Io(io::Error::other(err)))??;
+                    Python::with_gil(|py| Ok(Py::new(py, AsyncPyRedisConnection { conn: Some(conn) })?.into_py(py)))
+                })
+            }
+
+            // Resolves to the reply like PyPipeline.execute returns them, error replies raised
+            #[pyo3(signature = (*args))]
+            pub fn execute<'py>(&self, py: Python<'py>, args: &PyTuple) -> PyResult<&'py PyAny> {
+                let (conn, command) = self.request(args)?;
+                pyo3_asyncio::tokio::future_into_py(py, async move {
+                    let reply = conn.request(&command).await?;
+                    Python::with_gil(|py| to_python(py, reply))
+                })
+            }
+
+            // Resolves to the value, None for a missing key
+            pub fn get<'py>(&self, py: Python<'py>, key: &PyAny) -> PyResult<&'py PyAny> {
+                let args: Vec<&PyAny> = vec![PyString::new(py, "GET").as_ref(), key];
+                self.execute(py, PyTuple::new(py, args))
+            }
+
+            // Resolves to True once the value is set
+            pub fn set<'py>(&self, py: Python<'py>, key: &PyAny, value: &PyAny) -> PyResult<&'py PyAny> {
+                let args: Vec<&PyAny> = vec![PyString::new(py, "SET").as_ref(), key, value];
+                let (conn, command) = self.request(PyTuple::new(py, args))?;
+                pyo3_asyncio::tokio::future_into_py(py, async move {
+                    Ok(conn.request(&command).await? != Resp3Value::Null)
+                })
+            }
+
+            // The commands on their way still get their replies, the connection closes after them
+            pub fn close(&mut self) {
+                self.conn = None;
+            }
+        }
     }
 
     #[pymodule]
-    fn resp3string(_py: Python, m: &PyModule) -> PyResult<()> {
+    fn resp3string(py: Python, m: &PyModule) -> PyResult<()> {
+        m.add("Resp3Error", py.get_type::<exceptions::Resp3Error>())?;
+        m.add("ConnectionError", py.get_type::<exceptions::ConnectionError>())?;
+        m.add("TimeoutError", py.get_type::<exceptions::TimeoutError>())?;
+        m.add("AuthenticationError", py.get_type::<exceptions::AuthenticationError>())?;
+        m.add("ResponseError", py.get_type::<exceptions::ResponseError>())?;
+        m.add("WatchError", py.get_type::<exceptions::WatchError>())?;
         m.add_class::<PyRedisConnection>()?;
         m.add_class::<PySetCommand>()?;
         m.add_class::<PyGetCommand>()?;
+        m.add_class::<PyPipeline>()?;
+        m.add_class::<PyTransaction>()?;
+        #[cfg(feature = "python-asyncio")]
+        m.add_class::<asyncio::AsyncPyRedisConnection>()?;
         Ok(())
     }
 }
\ No newline at end of file
@@ -0,0 +1,334 @@
+mod ruby_bindings {
+    use std::cell::RefCell;
+    use std::time::Duration;
+    use magnus::prelude::*;
+    use magnus::scan_args::scan_args;
+    use magnus::typed_data::Obj;
+    use magnus::value::Lazy;
+    use magnus::{function, method, Error, ExceptionClass, RModule, RString, Ruby, TryConvert, Value};
+    use crate::resp3::error::{Resp3Error, ServerError};
+    use crate::resp3::pubsub::{PubSub, PubSubEvent};
+    use crate::resp3::utils::command::{text_parts, Command};
+    use crate::resp3::utils::redis_connection::{RedisConnection, RedisConnectionBuilder};
+    use crate::resp3::value::Resp3Value;
+
+    // The Ruby exceptions, modelled after redis-rb so the same rescue clauses work
+    static MODULE: Lazy<RModule> = Lazy::new(|ruby| ruby.define_module("Resp3string").unwrap());
+    static BASE_ERROR: Lazy<ExceptionClass> = Lazy::new(|ruby| {
+        ruby.get_inner(&MODULE).define_error("Error", ruby.exception_standard_error()).unwrap()
+    });
+    static CONNECTION_ERROR: Lazy<ExceptionClass> = Lazy::new(|ruby| {
+        ruby.get_inner(&MODULE).define_error("ConnectionError", ruby.get_inner(&BASE_ERROR)).unwrap()
+    });
+    static TIMEOUT_ERROR: Lazy<ExceptionClass> = Lazy::new(|ruby| {
+        ruby.get_inner(&MODULE).define_error("TimeoutError", ruby.get_inner(&BASE_ERROR)).unwrap()
+    });
+    static AUTHENTICATION_ERROR: Lazy<ExceptionClass> = Lazy::new(|ruby| {
+        ruby.get_inner(&MODULE).define_error("AuthenticationError", ruby.get_inner(&CONNECTION_ERROR)).unwrap()
+    });
+    static COMMAND_ERROR: Lazy<ExceptionClass> = Lazy::new(|ruby| {
+        ruby.get_inner(&MODULE).define_error("CommandError", ruby.get_inner(&BASE_ERROR)).unwrap()
+    });
+
+    fn exception_class(ruby: &Ruby, err: &Resp3Error) -> ExceptionClass {
+        match err {
+            _ if err.is_timeout() => ruby.get_inner(&TIMEOUT_ERROR),
+            Resp3Error::Io(_) => ruby.get_inner(&CONNECTION_ERROR),
+            Resp3Error::Server(reply) if is_authentication_error(reply.code_name()) => ruby.get_inner(&AUTHENTICATION_ERROR),
+            Resp3Error::Server(_) => ruby.get_inner(&COMMAND_ERROR),
+            _ => ruby.get_inner(&BASE_ERROR),
+        }
+    }
+
+    fn is_authentication_error(code: &str) -> bool {
+        ["NOAUTH", "WRONGPASS", "NOPERM"].contains(&code)
+    }
+
+    impl From<Resp3Error> for Error {
+        fn from(err: Resp3Error) -> Error {
+            // Only converted by the methods below, which Ruby calls on its own threads
+            let ruby = Ruby::get().expect("Resp3Error converted outside of a Ruby thread");
+            Error::new(exception_class(&ruby, &err), err.to_string())
+        }
+    }
+
+    fn builder(address: &str, timeout: Option<f64>) -> RedisConnectionBuilder {
+        let mut builder = RedisConnection::builder(address);
+        if let Some(timeout) = timeout {
+            builder = builder.timeout(Duration::from_secs_f64(timeout));
+        }
+        builder
+    }
+
+    // The reply as Ruby objects: Array, Hash, Integer, Float, String for the simple, verbatim
+    // and UTF-8 bulk strings, binary String for the other bulk strings, nil, and a CommandError
+    // instance for the errors, which pipelines return in place of the replies of the commands
+    // that failed
+    fn to_ruby(ruby: &Ruby, reply: Resp3Value) -> Result<Value, Error> {
+        Ok(match reply {
+            Resp3Value::Null => ruby.qnil().as_value(),
+            Resp3Value::SimpleString(text) | Resp3Value::VerbatimString { text, .. } => ruby.str_new(&text).as_value(),
+            Resp3Value::BulkString(bytes) => match std::str::from_utf8(&bytes) {
+                Ok(text) => ruby.str_new(text).as_value(),
+                Err(_) => ruby.str_from_slice(&bytes).as_value(),
+            },
+            Resp3Value::Error(message) => {
+                let err = Resp3Error::Server(ServerError::parse(&message));
+                exception_class(ruby, &err).new_instance((err.to_string(),))?.as_value()
+            }
+            Resp3Value::Integer(integer) => ruby.integer_from_i64(integer).as_value(),
+            Resp3Value::Double(double) => ruby.float_from_f64(double).as_value(),
+            Resp3Value::BigNumber(digits) => ruby.str_new(&digits).funcall("to_i", ())?,
+            Resp3Value::Array(values) => {
+                let array = ruby.ary_new_capa(values.len());
+                for value in values {
+                    array.push(to_ruby(ruby, value)?)?;
+                }
+                array.as_value()
+            }
+            Resp3Value::Map(map) => {
+                let hash = ruby.hash_new();
+                for (key, value) in map {
+                    hash.aset(to_ruby(ruby, key)?, to_ruby(ruby, value)?)?;
+                }
+                hash.as_value()
+            }
+        })
+    }
+
+    // The arguments of a command: strings as they are, anything else (symbols, numbers...) as
+    // its to_s
+    fn arguments(ruby: &Ruby, args: &[Value]) -> Result<Vec<Vec<u8>>, Error> {
+        if args.is_empty() {
+            return Err(Error::new(ruby.get_inner(&BASE_ERROR), "A command needs at least its name"));
+        }
+        args.iter().map(|arg| {
+            let string = match RString::from_value(*arg) {
+                Some(string) => string,
+                None => arg.to_r_string()?,
+            };
+            // Copied before any other call into Ruby, which could move the string
+            Ok(unsafe { string.as_slice() }.to_vec())
+        }).collect()
+    }
+
+    // A command built from Ruby arguments, normalized like the Rust commands and the channels
+    // of the subscribers: UPPERCASE, except the strings that are not valid UTF-8
+    struct Arguments(Vec<Vec<u8>>);
+
+    impl Command for Arguments {
+        fn get_parts(&self) -> Vec<&str> {
+            text_parts(&self.get_binary_parts())
+        }
+
+        fn get_binary_parts(&self) -> Vec<&[u8]> {
+            self.0.iter().map(Vec::as_slice).collect()
+        }
+    }
+
+    // The commands of a pipeline, encoded as they are queued
+    #[derive(Default)]
+    struct Batch {
+        buffer: Vec<u8>,
+        len: usize,
+    }
+
+    impl Batch {
+        fn push(&mut self, ruby: &Ruby, args: &[Value]) -> Result<(), Error> {
+            Arguments(arguments(ruby, args)?).encode_into(&mut self.buffer);
+            self.len += 1;
+            Ok(())
+        }
+
+        fn take(&mut self) -> (Vec<u8>, usize) {
+            (std::mem::take(&mut self.buffer), std::mem::take(&mut self.len))
+        }
+    }
+
+    #[magnus::wrap(class = "Resp3string::Connection", free_immediately, size)]
+    struct RbConnection {
+        conn: RefCell<RedisConnection>,
+    }
+
+    impl RbConnection {
+        // Connection.new(address, timeout = nil), the timeout in seconds bounds connecting
+        // and every read and write
+        fn new(args: &[Value]) -> Result<Self, Error> {
+            let args = scan_args::<(String,), (Option<f64>,), (), (), (), ()>(args)?;
+            let conn = builder(&args.required.0, args.optional.0).connect()?;
+            Ok(RbConnection { conn: RefCell::new(conn) })
+        }
+
+        // conn.call("SET", "key", "value"), the reply decoded and the error replies raised
+        fn call(ruby: &Ruby, rb_self: &Self, args: &[Value]) -> Result<Value, Error> {
+            let mut command = Batch::default();
+            command.push(ruby, args)?;
+            let reply = rb_self.conn.borrow_mut().request(&command.buffer)?;
+            to_ruby(ruby, reply)
+        }
+
+        fn close(&self) -> Result<(), Error> {
+            Ok(self.conn.borrow_mut().close()?)
+        }
+    }
+
+    // Commands sent in one write with their replies read back in order, e.g.
+    // Resp3string::Pipeline.new.set("a", "1").get("a").execute(conn). Empty again after execute
+    #[magnus::wrap(class = "Resp3string::Pipeline", free_immediately, size)]
+    struct RbPipeline {
+        batch: RefCell<Batch>,
+    }
+
+    impl RbPipeline {
+        fn new() -> Self {
+            RbPipeline { batch: RefCell::new(Batch::default()) }
+        }
+
+        fn call(ruby: &Ruby, rb_self: Obj<Self>, args: &[Value]) -> Result<Obj<Self>, Error> {
+            rb_self.batch.borrow_mut().push(ruby, args)?;
+            Ok(rb_self)
+        }
+
+        fn set(ruby: &Ruby, rb_self: Obj<Self>, key: Value, value: Value) -> Result<Obj<Self>, Error> {
+            RbPipeline::call(ruby, rb_self, &[ruby.str_new("SET").as_value(), key, value])
+        }
+
+        fn get(ruby: &Ruby, rb_self: Obj<Self>, key: Value) -> Result<Obj<Self>, Error> {
+            RbPipeline::call(ruby, rb_self, &[ruby.str_new("GET").as_value(), key])
+        }
+
+        fn length(&self) -> usize {
+            self.batch.borrow().len
+        }
+
+        // execute(conn, raise_on_error = true): one reply per command, the first error reply
+        // raised unless raise_on_error is false, which returns it in the array instead
+        fn execute(ruby: &Ruby, rb_self: &Self, args: &[Value]) -> Result<Value, Error> {
+            let args = scan_args::<(Value,), (Option<bool>,), (), (), (), ()>(args)?;
+            let conn = <&RbConnection>::try_convert(args.required.0)?;
+            let raise_on_error = args.optional.0.unwrap_or(true);
+
+            let (buffer, len) = rb_self.batch.borrow_mut().take();
+            let replies = conn.conn.borrow_mut().request_pipelined(&buffer, len)?;
+            if raise_on_error {
+                if let Some(Resp3Value::Error(message)) = replies.iter().find(|reply| matches!(reply, Resp3Value::Error(_))) {
+                    return Err(Resp3Error::Server(ServerError::parse(message)).into());
+                }
+            }
+            to_ruby(ruby, Resp3Value::Array(replies))
+        }
+    }
+
+    // A subscriber on a connection of its own, e.g. Resp3string::Subscriber.new("127.0.0.1:6379", 1.0)
+    // then subscribe("news") and listen { |kind, channel, payload| ... }. A dropped connection
+    // is opened again with every subscription, reported by a ["resubscribed"] event
+    #[magnus::wrap(class = "Resp3string::Subscriber", free_immediately, size)]
+    struct RbSubscriber {
+        pubsub: RefCell<PubSub>,
+    }
+
+    // ["message", channel, payload], ["pmessage", pattern, channel, payload] or ["resubscribed"],
+    // the messages as Redis sends them, the payloads binary Strings when they are not valid UTF-8
+    fn event_to_ruby(ruby: &Ruby, event: PubSubEvent) -> Result<Value, Error> {
+        let (names, payload) = match event {
+            PubSubEvent::Message { channel, payload } => (vec!["message".to_string(), channel], Some(payload)),
+            PubSubEvent::PatternMessage { pattern, channel, payload } => (vec!["pmessage".to_string(), pattern, channel], Some(payload)),
+            PubSubEvent::Resubscribed => (vec!["resubscribed".to_string()], None),
+        };
+        let parts = names.into_iter().map(Resp3Value::SimpleString).chain(payload.map(Resp3Value::BulkString)).collect();
+        to_ruby(ruby, Resp3Value::Array(parts))
+    }
+
+    fn names(args: &[Value]) -> Result<Vec<String>, Error> {
+        args.iter().map(|arg| String::try_convert(*arg)).collect()
+    }
+
+    impl RbSubscriber {
+        // Subscriber.new(address, timeout = nil), the timeout bounds each wait for a message
+        fn new(args: &[Value]) -> Result<Self, Error> {
+            let args = scan_args::<(String,), (Option<f64>,), (), (), (), ()>(args)?;
+            let conn = builder(&args.required.0, args.optional.0).connect()?;
+            Ok(RbSubscriber { pubsub: RefCell::new(PubSub::new(conn)) })
+        }
+
+        fn subscribe(&self, channels: &[Value]) -> Result<(), Error> {
+            let channels = names(channels)?;
+            Ok(self.pubsub.borrow_mut().subscribe(&channels.iter().map(String::as_str).collect::<Vec<_>>())?)
+        }
+
+        fn psubscribe(&self, patterns: &[Value]) -> Result<(), Error> {
+            let patterns = names(patterns)?;
+            Ok(self.pubsub.borrow_mut().psubscribe(&patterns.iter().map(String::as_str).collect::<Vec<_>>())?)
+        }
+
+        fn unsubscribe(&self, channels: &[Value]) -> Result<(), Error> {
+            let channels = names(channels)?;
+            Ok(self.pubsub.borrow_mut().unsubscribe(&channels.iter().map(String::as_str).collect::<Vec<_>>())?)
+        }
+
+        fn punsubscribe(&self, patterns: &[Value]) -> Result<(), Error> {
+            let patterns = names(patterns)?;
+            Ok(self.pubsub.borrow_mut().punsubscribe(&patterns.iter().map(String::as_str).collect::<Vec<_>>())?)
+        }
+
+        // The next event, nil when none came within the timeout
+        fn next_message(ruby: &Ruby, rb_self: &Self) -> Result<Option<Value>, Error> {
+            match rb_self.pubsub.borrow_mut().next_event() {
+                Ok(event) => Ok(Some(event_to_ruby(ruby, event)?)),
+                Err(err) if err.is_timeout() => Ok(None),
+                Err(err) => Err(err.into()),
+            }
+        }
+
+        // Yields every event until the block breaks or the connection fails. Each timeout
+        // gives Ruby the chance to handle interrupts, e.g. Ctrl-C or Thread#kill
+        fn listen(ruby: &Ruby, rb_self: &Self) -> Result<(), Error> {
+            if !ruby.block_given() {
+                return Err(Error::new(ruby.exception_arg_error(), "listen needs a block"));
+            }
+            loop {
+                // Not borrowed while the block runs, which may change the subscriptions
+                let event = rb_self.pubsub.borrow_mut().next_event();
+                match event {
+                    Ok(event) => {
+                        let _: Value = ruby.yield_value(event_to_ruby(ruby, event)?)?;
+                    }
+                    Err(err) if err.is_timeout() => ruby.thread_check_ints()?,
+                    Err(err) => return Err(err.into()),
+                }
+            }
+        }
+    }
+
+    #[magnus::init]
+    fn init(ruby: &Ruby) -> Result<(), Error> {
+        let module = ruby.get_inner(&MODULE);
+        for error in [&BASE_ERROR, &CONNECTION_ERROR, &TIMEOUT_ERROR, &AUTHENTICATION_ERROR, &COMMAND_ERROR] {
+            Lazy::force(error, ruby);
+        }
+
+        let connection = module.define_class("Connection", ruby.class_object())?;
+        connection.define_singleton_method("new", function!(RbConnection::new, -1))?;
+        connection.define_method("call", method!(RbConnection::call, -1))?;
+        connection.define_method("close", method!(RbConnection::close, 0))?;
+
+        let pipeline = module.define_class("Pipeline", ruby.class_object())?;
+        pipeline.define_singleton_method("new", function!(RbPipeline::new, 0))?;
+        pipeline.define_method("call", method!(RbPipeline::call, -1))?;
+        pipeline.define_method("set", method!(RbPipeline::set, 2))?;
+        pipeline.define_method("get", method!(RbPipeline::get, 1))?;
+        pipeline.define_method("length", method!(RbPipeline::length, 0))?;
+        pipeline.define_method("size", method!(RbPipeline::length, 0))?;
+        pipeline.define_method("execute", method!(RbPipeline::execute, -1))?;
+
+        let subscriber = module.define_class("Subscriber", ruby.class_object())?;
+        subscriber.define_singleton_method("new", function!(RbSubscriber::new, -1))?;
+        subscriber.define_method("subscribe", method!(RbSubscriber::subscribe, -1))?;
+        subscriber.define_method("psubscribe", method!(RbSubscriber::psubscribe, -1))?;
+        subscriber.define_method("unsubscribe", method!(RbSubscriber::unsubscribe, -1))?;
+        subscriber.define_method("punsubscribe", method!(RbSubscriber::punsubscribe, -1))?;
+        subscriber.define_method("next_message", method!(RbSubscriber::next_message, 0))?;
+        subscriber.define_method("listen", method!(RbSubscriber::listen, 0))?;
+        Ok(())
+    }
+}
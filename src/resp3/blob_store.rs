@@ -0,0 +1,90 @@
+// src/resp3/blob_store.rs
+
+use sha2::{Digest, Sha256};
+
+use crate::resp3::commands::eval::EvalCommand;
+use crate::resp3::commands::hash::{HGetCommand, HIncrByCommand};
+use crate::resp3::error::Resp3Error;
+use crate::resp3::utils::command::{Command, TypedCommand};
+use crate::resp3::utils::prepared::PreparedCommand;
+use crate::resp3::utils::redis_connection::RedisConnection;
+use crate::resp3::value::{FromResp3Value, Resp3Value};
+
+// Drops one reference and the blob with the last one, in one step so a concurrent store of
+// the same content either keeps it alive or writes it again. Unknown digests are left alone
+const RELEASE_SCRIPT: &str = "if redis.call('hexists', KEYS[2], ARGV[1]) == 0 then return 0 end \
+    local references = redis.call('hincrby', KEYS[2], ARGV[1], -1) \
+    if references <= 0 then redis.call('hdel', KEYS[2], ARGV[1]) redis.call('del', KEYS[1]) return 0 end \
+    return references";
+
+// What store did with a value
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoredBlob {
+    // Lowercase hex SHA-256 of the value, the handle to load and release it
+    pub digest: String,
+    // False when the same content was already stored
+    pub created: bool,
+    pub references: u64,
+}
+
+// Immutable values stored once under their SHA-256, e.g. BlobStore::new("assets") keeps them in
+// assets:blob:<digest> and counts their references in the assets:refs hash. Storing content
+// that is already there only adds a reference, the write itself is a SET NX. Loads check the
+// digest of what they read. In a cluster, make the prefix a hash tag like "{assets}" so the
+// release script finds the blob and its count on the same node
+pub struct BlobStore {
+    prefix: String,
+}
+
+impl BlobStore {
+    pub fn new(prefix: &str) -> Self {
+        BlobStore { prefix: prefix.to_string() }
+    }
+
+    pub fn digest(value: &[u8]) -> String {
+        Sha256::digest(value).iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+
+    pub fn blob_key(&self, digest: &str) -> String {
+        format!("{}:blob:{}", self.prefix, digest)
+    }
+
+    pub fn refs_key(&self) -> String {
+        format!("{}:refs", self.prefix)
+    }
+
+    // Adds a reference to the value, writing it unless the same content is already stored.
+    // The reference is counted first, so a release racing with it never deletes the blob
+    // between the two steps
+    pub fn store(&self, conn: &mut RedisConnection, value: &[u8]) -> Result<StoredBlob, Resp3Error> {
+        let digest = BlobStore::digest(value);
+        let references = HIncrByCommand::new(self.refs_key(), digest.clone(), 1).query(conn)?;
+        let reply = PreparedCommand::new("SET").argument().verbatim_argument().token("NX")
+            .request(conn, &[self.blob_key(&digest).as_bytes(), value])?;
+        Ok(StoredBlob { digest, created: reply != Resp3Value::Null, references: references.max(0) as u64 })
+    }
+
+    // None when nothing is stored under the digest
+    pub fn load(&self, conn: &mut RedisConnection, digest: &str) -> Result<Option<Vec<u8>>, Resp3Error> {
+        let value: Option<Vec<u8>> = PreparedCommand::new("GET").argument().query(conn, &[self.blob_key(digest).as_bytes()])?;
+        match value {
+            Some(value) if !BlobStore::digest(&value).eq_ignore_ascii_case(digest) => {
+                Err(Resp3Error::UnexpectedReply(format!("Blob {} does not match its digest", digest)))
+            }
+            value => Ok(value),
+        }
+    }
+
+    // 0 when the digest is not referenced
+    pub fn references(&self, conn: &mut RedisConnection, digest: &str) -> Result<u64, Resp3Error> {
+        let references = HGetCommand::new(self.refs_key(), digest.to_string()).query(conn)?;
+        Ok(references.and_then(|references| references.parse().ok()).unwrap_or(0))
+    }
+
+    // Drops a reference and returns how many are left, the blob is deleted with the last one
+    pub fn release(&self, conn: &mut RedisConnection, digest: &str) -> Result<u64, Resp3Error> {
+        let command = EvalCommand::new(RELEASE_SCRIPT.to_string(), vec![self.blob_key(digest), self.refs_key()], vec![digest.to_string()]);
+        let references = i64::from_resp3_value(command.execute(conn)?)?;
+        Ok(references.max(0) as u64)
+    }
+}
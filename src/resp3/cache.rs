@@ -0,0 +1,103 @@
+// src/resp3/cache.rs
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::resp3::commands::get::GetCommand;
+use crate::resp3::commands::keys::DelCommand;
+use crate::resp3::commands::set::SetCommand;
+use crate::resp3::error::Resp3Error;
+use crate::resp3::lock::Lock;
+use crate::resp3::utils::command::TypedCommand;
+use crate::resp3::utils::redis_connection::RedisConnection;
+
+// Values computed once and kept in Redis, e.g.
+// cache.remember("user:42", Duration::from_secs(60), || load_user(42))
+// On a miss only one client computes the value, the others wait for it under a short lock
+// kept in <key>:lock. The values are stored as they are, the keys UPPERCASE as usual
+pub struct Cache {
+    conn: RedisConnection,
+    lock_ttl: Duration,
+    poll_interval: Duration,
+}
+
+impl Cache {
+    pub fn new(conn: RedisConnection) -> Self {
+        Cache { conn, lock_ttl: Duration::from_secs(5), poll_interval: Duration::from_millis(20) }
+    }
+
+    // How long the other clients wait for the one computing a value, after that they compute
+    // it themselves. Should be longer than the computation
+    pub fn lock_ttl(mut self, lock_ttl: Duration) -> Self {
+        self.lock_ttl = lock_ttl;
+        self
+    }
+
+    // How often the waiting clients look for the value
+    pub fn poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    pub fn into_inner(self) -> RedisConnection {
+        self.conn
+    }
+
+    pub fn get(&mut self, key: &str) -> Result<Option<String>, Resp3Error> {
+        GetCommand::new(key.to_string()).query(&mut self.conn)
+    }
+
+    // Returns the cached value, or computes and stores it with the TTL
+    pub fn remember<F: FnOnce() -> String>(&mut self, key: &str, ttl: Duration, compute: F) -> Result<String, Resp3Error> {
+        self.get_or_compute(key, Some(ttl), compute)
+    }
+
+    // Like remember, the value then stays until it is forgotten or evicted
+    pub fn get_or_insert_with<F: FnOnce() -> String>(&mut self, key: &str, compute: F) -> Result<String, Resp3Error> {
+        self.get_or_compute(key, None, compute)
+    }
+
+    // Drops the cached value, returns false if there was none
+    pub fn forget(&mut self, key: &str) -> Result<bool, Resp3Error> {
+        Ok(DelCommand::new([key]).query(&mut self.conn)? > 0)
+    }
+
+    fn get_or_compute<F: FnOnce() -> String>(&mut self, key: &str, ttl: Option<Duration>, compute: F) -> Result<String, Resp3Error> {
+        let lock_key = format!("{}:lock", key);
+        let deadline = Instant::now() + self.lock_ttl;
+        loop {
+            if let Some(value) = self.get(key)? {
+                return Ok(value);
+            }
+            // The lock is free again when the client holding it gave up without storing a value
+            if let Some(lock) = Lock::acquire(&mut self.conn, &lock_key, self.lock_ttl) {
+                // Another client may have stored the value and released the lock since the miss
+                if let Some(value) = self.get(key)? {
+                    lock.release(&mut self.conn);
+                    return Ok(value);
+                }
+                let value = compute();
+                let stored = self.store(key, &value, ttl);
+                lock.release(&mut self.conn);
+                stored?;
+                return Ok(value);
+            }
+            if Instant::now() >= deadline {
+                let value = compute();
+                self.store(key, &value, ttl)?;
+                return Ok(value);
+            }
+            thread::sleep(self.poll_interval);
+        }
+    }
+
+    fn store(&mut self, key: &str, value: &str, ttl: Option<Duration>) -> Result<(), Resp3Error> {
+        let mut command = SetCommand::new(key.to_string(), value.to_string()).verbatim_value();
+        if let Some(ttl) = ttl {
+            // PX 0 is an error, the shortest TTL is 1 millisecond
+            command = command.px(ttl.as_millis().max(1) as u64);
+        }
+        command.query(&mut self.conn)?;
+        Ok(())
+    }
+}
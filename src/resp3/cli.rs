@@ -0,0 +1,620 @@
+// src/resp3/cli.rs
+
+use std::collections::HashSet;
+use std::io::{self, BufRead, Read, Write};
+use std::time::Duration;
+
+use serde_json::{json, Value};
+
+use crate::resp3::error::{Resp3Error, ServerError};
+use crate::resp3::parser::parse_frame;
+use crate::resp3::utils::command::Command;
+use crate::resp3::utils::connection_state::ConnectionState;
+use crate::resp3::utils::redis_connection::{RedisConnection, RedisConnectionBuilder};
+#[cfg(feature="tls")]
+use crate::resp3::utils::tls::TlsOptions;
+use crate::resp3::value::{format_double, pairs, FromResp3Value, Resp3Value};
+
+// Commands sent at once by --pipe before reading their replies
+const PIPE_BATCH_SIZE: usize = 1000;
+
+// Keys walked per SCAN call by dump
+const DUMP_SCAN_COUNT: &str = "1000";
+
+pub const USAGE: &str = "\
+Usage: resp3-cli [OPTIONS] [COMMAND [ARG...]]
+       resp3-cli [OPTIONS] dump [--pattern <pattern>] > keys.jsonl
+       resp3-cli [OPTIONS] restore < keys.jsonl
+
+Sends the command and prints the reply, or starts an interactive session without one.
+dump writes the string, hash, list, set and sorted set keys matching the pattern (default: *)
+as JSON lines, with their TTL, and restore writes them back, replacing existing keys.
+
+Options:
+  -h <host>          Server hostname (default: 127.0.0.1)
+  -p <port>          Server port (default: 6379)
+  -a <password>      Password to AUTH with
+  --user <username>  Username to AUTH with, together with -a
+  --timeout <secs>   Connect, read and write timeout
+  --tls              Connect over TLS
+  --cacert <file>    CA certificate, PEM encoded, to verify the server with
+  --sni <name>       Name to verify the server certificate against
+  --insecure         Do not verify the server certificate
+  --pipe             Send the commands read from stdin, RESP encoded or one per line
+  --help             Print this help";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CliOptions {
+    pub host: String,
+    pub port: u16,
+    pub user: Option<String>,
+    pub password: Option<String>,
+    pub timeout: Option<Duration>,
+    pub tls: bool,
+    pub ca_certificate: Option<String>,
+    pub sni: Option<String>,
+    pub insecure: bool,
+    pub pipe: bool,
+    // The pattern of the keys to dump to stdout
+    pub dump: Option<String>,
+    // Restores the keys dumped to stdin
+    pub restore: bool,
+    pub help: bool,
+    // The one-shot command, empty for the interactive session
+    pub command: Vec<String>,
+}
+
+impl Default for CliOptions {
+    fn default() -> Self {
+        CliOptions {
+            host: "127.0.0.1".to_string(),
+            port: 6379,
+            user: None,
+            password: None,
+            timeout: None,
+            tls: false,
+            ca_certificate: None,
+            sni: None,
+            insecure: false,
+            pipe: false,
+            dump: None,
+            restore: false,
+            help: false,
+            command: Vec::new(),
+        }
+    }
+}
+
+impl CliOptions {
+    // Parses the arguments following the program name, the first one that is not an option
+    // starts the command
+    pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Self, String> {
+        let mut options = CliOptions::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let mut value = |name: &str| args.next().ok_or_else(|| format!("Missing value for {}", name));
+            match arg.as_str() {
+                "-h" => options.host = value("-h")?,
+                "-p" => {
+                    let port = value("-p")?;
+                    options.port = port.parse().map_err(|_| format!("Invalid port {}", port))?;
+                }
+                "-a" => options.password = Some(value("-a")?),
+                "--user" => options.user = Some(value("--user")?),
+                "--timeout" => {
+                    let seconds = value("--timeout")?;
+                    let seconds: f64 = seconds.parse().map_err(|_| format!("Invalid timeout {}", seconds))?;
+                    options.timeout = Some(Duration::from_secs_f64(seconds));
+                }
+                "--tls" => options.tls = true,
+                "--cacert" => options.ca_certificate = Some(value("--cacert")?),
+                "--sni" => options.sni = Some(value("--sni")?),
+                "--insecure" => options.insecure = true,
+                "--pipe" => options.pipe = true,
+                "--help" => options.help = true,
+                option if option.starts_with("--") => return Err(format!("Unknown option {}", option)),
+                _ => {
+                    options.command.push(arg);
+                    options.command.extend(args);
+                    break;
+                }
+            }
+        }
+        if options.user.is_some() && options.password.is_none() {
+            return Err("--user needs a password with -a".to_string());
+        }
+        options.parse_tool_mode()?;
+        Ok(options)
+    }
+
+    // dump and restore take the place of the DUMP and RESTORE commands when called without
+    // their key arguments, "dump mykey" still sends DUMP
+    fn parse_tool_mode(&mut self) -> Result<(), String> {
+        let Some(name) = self.command.first() else { return Ok(()) };
+        if name.eq_ignore_ascii_case("dump") && (self.command.len() == 1 || self.command[1] == "--pattern") {
+            self.dump = Some(match &self.command[1..] {
+                [] => "*".to_string(),
+                [_, pattern] => pattern.clone(),
+                [_] => return Err("Missing value for --pattern".to_string()),
+                _ => return Err("dump takes no arguments besides --pattern".to_string()),
+            });
+            self.command.clear();
+        } else if name.eq_ignore_ascii_case("restore") && self.command.len() == 1 {
+            self.restore = true;
+            self.command.clear();
+        }
+        Ok(())
+    }
+
+    pub fn address(&self) -> String {
+        if self.host.contains(':') {
+            format!("[{}]:{}", self.host, self.port)
+        } else {
+            format!("{}:{}", self.host, self.port)
+        }
+    }
+
+    pub fn connect(&self) -> Result<RedisConnection, Resp3Error> {
+        let mut builder = RedisConnectionBuilder::new(&self.address());
+        if let Some(timeout) = self.timeout {
+            builder = builder.timeout(timeout);
+        }
+        builder = self.configure_tls(builder)?;
+        let mut conn = builder.connect()?;
+
+        if let Some(password) = &self.password {
+            let mut parts = vec!["AUTH".to_string()];
+            parts.extend(self.user.clone());
+            parts.push(password.clone());
+            conn.request_command(&CliCommand(parts))?;
+        }
+        Ok(conn)
+    }
+
+    #[cfg(feature="tls")]
+    fn configure_tls(&self, builder: RedisConnectionBuilder) -> Result<RedisConnectionBuilder, Resp3Error> {
+        if !self.tls {
+            return Ok(builder);
+        }
+        let mut tls = TlsOptions::new().accept_invalid_certificates(self.insecure);
+        if let Some(path) = &self.ca_certificate {
+            tls = tls.ca_certificate(&std::fs::read(path)?);
+        }
+        if let Some(sni) = &self.sni {
+            tls = tls.domain(sni);
+        }
+        Ok(builder.tls(tls))
+    }
+
+    #[cfg(not(feature="tls"))]
+    fn configure_tls(&self, builder: RedisConnectionBuilder) -> Result<RedisConnectionBuilder, Resp3Error> {
+        if self.tls || self.ca_certificate.is_some() || self.sni.is_some() || self.insecure {
+            return Err(Resp3Error::InvalidState("resp3-cli was built without the tls feature".to_string()));
+        }
+        Ok(builder)
+    }
+}
+
+// The arguments as typed, only the command name is uppercased
+struct CliCommand(Vec<String>);
+
+impl<const N: usize> From<[&str; N]> for CliCommand {
+    fn from(parts: [&str; N]) -> Self {
+        CliCommand(parts.map(str::to_string).to_vec())
+    }
+}
+
+impl Command for CliCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        self.0.iter().map(String::as_str).collect()
+    }
+
+    fn is_verbatim(&self, index: usize) -> bool {
+        index > 0
+    }
+}
+
+// Splits a line into arguments like redis-cli: blanks separate them, double quotes allow the
+// \n, \r, \t, \b, \a, \\, \" and \xHH escapes, single quotes only \'. Arguments are
+// strings, so \xHH above 7f is sent as the UTF-8 encoding of that code point
+pub fn split_line(line: &str) -> Result<Vec<String>, String> {
+    let mut args = Vec::new();
+    let mut chars = line.chars().peekable();
+    loop {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        let Some(&first) = chars.peek() else { return Ok(args) };
+
+        let mut arg = String::new();
+        if first == '"' || first == '\'' {
+            chars.next();
+            loop {
+                match chars.next() {
+                    None => return Err("Unbalanced quotes".to_string()),
+                    Some(c) if c == first => break,
+                    Some('\\') if first == '\'' => match chars.next_if_eq(&'\'') {
+                        Some(quote) => arg.push(quote),
+                        None => arg.push('\\'),
+                    },
+                    Some('\\') => match chars.next() {
+                        Some('n') => arg.push('\n'),
+                        Some('r') => arg.push('\r'),
+                        Some('t') => arg.push('\t'),
+                        Some('b') => arg.push('\u{8}'),
+                        Some('a') => arg.push('\u{7}'),
+                        Some('x') => {
+                            let hex: String = [chars.next(), chars.next()].into_iter().flatten().collect();
+                            match u8::from_str_radix(&hex, 16) {
+                                Ok(byte) if hex.len() == 2 => arg.push(byte as char),
+                                _ => return Err(format!("Invalid escape \\x{}", hex)),
+                            }
+                        }
+                        Some(c) => arg.push(c),
+                        None => return Err("Unbalanced quotes".to_string()),
+                    },
+                    Some(c) => arg.push(c),
+                }
+            }
+            if chars.peek().is_some_and(|c| !c.is_whitespace()) {
+                return Err("Closing quote must be followed by a space".to_string());
+            }
+        } else {
+            while let Some(c) = chars.next_if(|c| !c.is_whitespace()) {
+                arg.push(c);
+            }
+        }
+        args.push(arg);
+    }
+}
+
+// Formats the reply like redis-cli does in a terminal: (integer) 1, "bulk", (nil), nested arrays
+// numbered, map entries as 1# "key" => value
+pub fn format_reply(reply: &Resp3Value) -> String {
+    let mut output = String::new();
+    write_reply(reply, 0, &mut output);
+    output
+}
+
+fn write_reply(reply: &Resp3Value, indent: usize, output: &mut String) {
+    match reply {
+        Resp3Value::SimpleString(string) => output.push_str(string),
+        Resp3Value::Error(message) => output.push_str(&format!("(error) {}", message)),
+        Resp3Value::Integer(integer) => output.push_str(&format!("(integer) {}", integer)),
+        Resp3Value::BulkString(bytes) => output.push_str(&quote(bytes)),
+        Resp3Value::Null => output.push_str("(nil)"),
+        Resp3Value::Double(double) => output.push_str(&format!("(double) {}", format_double(*double))),
+        Resp3Value::BigNumber(digits) => output.push_str(&format!("(big number) {}", digits)),
+        // Printed as it is, like redis-cli does
+        Resp3Value::VerbatimString { text, .. } => output.push_str(text),
+        Resp3Value::Map(map) if map.is_empty() => output.push_str("(empty hash)"),
+        Resp3Value::Map(map) => {
+            let width = map.len().to_string().len();
+            for (index, (key, value)) in map.iter().enumerate() {
+                if index > 0 {
+                    output.push('\n');
+                    output.push_str(&" ".repeat(indent));
+                }
+                let mut label = format!("{:>width$}# ", index + 1, width = width);
+                write_reply(key, indent + label.len(), &mut label);
+                label.push_str(" => ");
+                output.push_str(&label);
+                write_reply(value, indent + label.len(), output);
+            }
+        }
+        Resp3Value::Array(values) if values.is_empty() => output.push_str("(empty array)"),
+        Resp3Value::Array(values) => {
+            let width = values.len().to_string().len();
+            for (index, value) in values.iter().enumerate() {
+                if index > 0 {
+                    output.push('\n');
+                    output.push_str(&" ".repeat(indent));
+                }
+                let label = format!("{:>width$}) ", index + 1, width = width);
+                output.push_str(&label);
+                write_reply(value, indent + label.len(), output);
+            }
+        }
+    }
+}
+
+fn quote(bytes: &[u8]) -> String {
+    let mut quoted = String::from("\"");
+    for &byte in bytes {
+        match byte {
+            b'"' => quoted.push_str("\\\""),
+            b'\\' => quoted.push_str("\\\\"),
+            b'\n' => quoted.push_str("\\n"),
+            b'\r' => quoted.push_str("\\r"),
+            b'\t' => quoted.push_str("\\t"),
+            0x20..=0x7e => quoted.push(byte as char),
+            _ => quoted.push_str(&format!("\\x{:02x}", byte)),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+// Sends the commands of the input in batches, RESP encoded when it starts with '*', else one
+// per line. Returns the number of replies and how many of them were errors
+pub fn pipe(conn: &mut RedisConnection, input: &[u8]) -> Result<(usize, usize), Resp3Error> {
+    let encoded;
+    let input = if input.first() == Some(&b'*') {
+        input
+    } else {
+        let mut buffer = Vec::new();
+        for line in String::from_utf8_lossy(input).lines() {
+            let args = split_line(line).map_err(Resp3Error::InvalidState)?;
+            if !args.is_empty() {
+                CliCommand(args).encode_into(&mut buffer);
+            }
+        }
+        encoded = buffer;
+        &encoded
+    };
+
+    let (mut replies, mut errors) = (0, 0);
+    let mut start = 0;
+    while start < input.len() {
+        let (mut end, mut count) = (start, 0);
+        while count < PIPE_BATCH_SIZE && end < input.len() {
+            match parse_frame(&input[end..])? {
+                Some((_, length)) => end += length,
+                None => return Err(Resp3Error::Protocol("Incomplete command at the end of the input".to_string())),
+            }
+            count += 1;
+        }
+        for reply in conn.request_pipelined(&input[start..end], count)? {
+            replies += 1;
+            if matches!(reply, Resp3Value::Error(_)) {
+                errors += 1;
+            }
+        }
+        start = end;
+    }
+    Ok((replies, errors))
+}
+
+// Writes every key matching the pattern as a JSON line, e.g.
+// {"key":"user:1","pttl":-1,"type":"hash","value":{"name":"Ada"}}
+// with the remaining time to live in milliseconds, -1 without expiration. Zset members go as
+// [member, score] pairs, the scores as the server formats them. Returns the number of keys
+// written and of keys skipped for their type (streams, module types)
+pub fn dump(conn: &mut RedisConnection, pattern: &str, output: &mut impl Write) -> Result<(usize, usize), Resp3Error> {
+    let (mut dumped, mut skipped) = (0, 0);
+    // SCAN may return a key more than once
+    let mut seen = HashSet::new();
+    let mut cursor = 0;
+    loop {
+        let scan = CliCommand::from(["SCAN", &cursor.to_string(), "MATCH", pattern, "COUNT", DUMP_SCAN_COUNT]);
+        let (next, keys) = <(u64, Vec<String>)>::from_resp3_value(conn.request_command(&scan)?)?;
+        for key in keys {
+            if !seen.insert(key.clone()) {
+                continue;
+            }
+            match dump_key(conn, &key)? {
+                Some(Some(record)) => {
+                    writeln!(output, "{}", record)?;
+                    dumped += 1;
+                }
+                Some(None) => skipped += 1,
+                // Deleted or expired since the SCAN
+                None => {}
+            }
+        }
+        if next == 0 {
+            output.flush()?;
+            return Ok((dumped, skipped));
+        }
+        cursor = next;
+    }
+}
+
+// The record of the key, None when it no longer exists and Some(None) when its type is not supported
+fn dump_key(conn: &mut RedisConnection, key: &str) -> Result<Option<Option<Value>>, Resp3Error> {
+    let mut buffer = Vec::new();
+    CliCommand::from(["TYPE", key]).encode_into(&mut buffer);
+    CliCommand::from(["PTTL", key]).encode_into(&mut buffer);
+    let mut replies = conn.request_pipelined(&buffer, 2)?.into_iter();
+    let key_type = String::from_resp3_value(replies.next().unwrap_or(Resp3Value::Null))?;
+    let pttl = i64::from_resp3_value(replies.next().unwrap_or(Resp3Value::Null))?;
+    if key_type == "none" || pttl == -2 {
+        return Ok(None);
+    }
+
+    let read = match key_type.as_str() {
+        "string" => CliCommand::from(["GET", key]),
+        "hash" => CliCommand::from(["HGETALL", key]),
+        "list" => CliCommand::from(["LRANGE", key, "0", "-1"]),
+        "set" => CliCommand::from(["SMEMBERS", key]),
+        "zset" => CliCommand::from(["ZRANGE", key, "0", "-1", "WITHSCORES"]),
+        _ => return Ok(Some(None)),
+    };
+    let reply = conn.request_command(&read)?;
+    // A collection read empty was deleted in the meantime, Redis does not keep empty ones
+    if reply.is_null() || reply.as_array().is_some_and(|values| values.is_empty()) {
+        return Ok(None);
+    }
+    let value = match key_type.as_str() {
+        "string" => json!(String::from_resp3_value(reply)?),
+        "hash" => Value::Object(pairs::<String, String>(reply)?.into_iter().map(|(field, value)| (field, json!(value))).collect()),
+        "zset" => json!(pairs::<String, String>(reply)?),
+        _ => json!(Vec::<String>::from_resp3_value(reply)?),
+    };
+    Ok(Some(Some(json!({"key": key, "type": key_type, "pttl": pttl, "value": value}))))
+}
+
+// Writes back the keys of a dump, replacing the existing ones, and returns how many were restored
+pub fn restore(conn: &mut RedisConnection, input: impl BufRead) -> Result<usize, Resp3Error> {
+    let mut restored = 0;
+    for (index, line) in input.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let invalid = |message: String| Resp3Error::InvalidState(format!("Line {}: {}", index + 1, message));
+        let record: Value = serde_json::from_str(&line).map_err(|err| invalid(err.to_string()))?;
+        let commands = restore_commands(&record).map_err(invalid)?;
+
+        let mut buffer = Vec::new();
+        for command in &commands {
+            command.encode_into(&mut buffer);
+        }
+        for reply in conn.request_pipelined(&buffer, commands.len())? {
+            if let Resp3Value::Error(message) = reply {
+                return Err(Resp3Error::Server(ServerError::parse(&message)));
+            }
+        }
+        restored += 1;
+    }
+    Ok(restored)
+}
+
+// DEL, the write of the value and PEXPIRE when the key had a time to live
+fn restore_commands(record: &Value) -> Result<Vec<CliCommand>, String> {
+    let field = |name: &str| record.get(name).ok_or_else(|| format!("Missing \"{}\"", name));
+    let string = |value: &Value| value.as_str().map(str::to_string).ok_or_else(|| format!("Expected a string, got {}", value));
+    let strings = |value: &Value| match value.as_array() {
+        Some(values) => values.iter().map(string).collect::<Result<Vec<_>, _>>(),
+        None => Err(format!("Expected an array, got {}", value)),
+    };
+    let key = string(field("key")?)?;
+    let key_type = string(field("type")?)?;
+    let value = field("value")?;
+
+    let mut write = vec![key.clone()];
+    let name = match key_type.as_str() {
+        "string" => {
+            write.push(string(value)?);
+            "SET"
+        }
+        "hash" => {
+            let Some(fields) = value.as_object() else { return Err(format!("Expected an object, got {}", value)) };
+            for (field, value) in fields {
+                write.push(field.clone());
+                write.push(string(value)?);
+            }
+            "HSET"
+        }
+        "list" => {
+            write.extend(strings(value)?);
+            "RPUSH"
+        }
+        "set" => {
+            write.extend(strings(value)?);
+            "SADD"
+        }
+        "zset" => {
+            let Some(members) = value.as_array() else { return Err(format!("Expected an array, got {}", value)) };
+            for member in members {
+                match strings(member)?.as_slice() {
+                    [member, score] => write.extend([score.clone(), member.clone()]),
+                    _ => return Err(format!("Expected a [member, score] pair, got {}", member)),
+                }
+            }
+            "ZADD"
+        }
+        key_type => return Err(format!("Unsupported type {}", key_type)),
+    };
+    if write.len() == 1 {
+        return Err(format!("Empty value for {}", key));
+    }
+    write.insert(0, name.to_string());
+
+    let mut commands = vec![CliCommand(vec!["DEL".to_string(), key.clone()]), CliCommand(write)];
+    match record.get("pttl").and_then(Value::as_i64) {
+        Some(pttl) if pttl >= 0 => commands.push(CliCommand(vec!["PEXPIRE".to_string(), key, pttl.to_string()])),
+        _ => {}
+    }
+    Ok(commands)
+}
+
+// Runs one command and prints its reply, or what the server pushes after it on a subscribed
+// or monitoring connection, until the stream ends
+fn run_command(conn: &mut RedisConnection, args: Vec<String>, output: &mut impl Write) -> Result<(), Resp3Error> {
+    let reply = match conn.request_command(&CliCommand(args)) {
+        Ok(reply) => reply,
+        Err(Resp3Error::Server(err)) => Resp3Value::Error(err.to_string()),
+        Err(err) => return Err(err),
+    };
+    writeln!(output, "{}", format_reply(&reply))?;
+    while matches!(conn.state(), ConnectionState::Subscribed | ConnectionState::Monitoring) {
+        let message = conn.read_response()?;
+        writeln!(output, "{}", format_reply(&message))?;
+        output.flush()?;
+    }
+    Ok(())
+}
+
+fn repl(conn: &mut RedisConnection, options: &CliOptions) -> Result<(), Resp3Error> {
+    let prompt = format!("{}> ", options.address());
+    let stdin = io::stdin();
+    let mut stdout = io::stdout();
+    let mut lines = stdin.lock().lines();
+    loop {
+        write!(stdout, "{}", prompt)?;
+        stdout.flush()?;
+        let Some(line) = lines.next() else { return Ok(()) };
+        let args = match split_line(&line?) {
+            Ok(args) if args.is_empty() => continue,
+            Ok(args) => args,
+            Err(err) => {
+                writeln!(stdout, "Invalid argument(s): {}", err)?;
+                continue;
+            }
+        };
+        if ["quit", "exit"].contains(&args[0].to_lowercase().as_str()) {
+            return Ok(());
+        }
+        match run_command(conn, args, &mut stdout) {
+            Ok(()) => {}
+            Err(err @ (Resp3Error::InvalidState(_) | Resp3Error::InvalidArgument(_) | Resp3Error::UnexpectedReply(_))) => writeln!(stdout, "(error) {}", err)?,
+            Err(err) => {
+                writeln!(stdout, "Error: {}, reconnecting", err)?;
+                if let Ok(new_conn) = options.connect() {
+                    *conn = new_conn;
+                }
+            }
+        }
+    }
+}
+
+pub fn run(options: &CliOptions) -> Result<(), Resp3Error> {
+    let mut conn = options.connect()?;
+    if options.pipe {
+        let mut input = Vec::new();
+        io::stdin().read_to_end(&mut input)?;
+        let (replies, errors) = pipe(&mut conn, &input)?;
+        println!("All data transferred. errors: {}, replies: {}", errors, replies);
+        Ok(())
+    } else if let Some(pattern) = &options.dump {
+        let (dumped, skipped) = dump(&mut conn, pattern, &mut io::BufWriter::new(io::stdout().lock()))?;
+        eprintln!("Dumped {} keys, skipped {} of unsupported types", dumped, skipped);
+        Ok(())
+    } else if options.restore {
+        let restored = restore(&mut conn, io::stdin().lock())?;
+        eprintln!("Restored {} keys", restored);
+        Ok(())
+    } else if options.command.is_empty() {
+        repl(&mut conn, options)
+    } else {
+        run_command(&mut conn, options.command.clone(), &mut io::stdout())
+    }
+}
+
+// Entry point of the resp3-cli binary
+pub fn main() {
+    let options = match CliOptions::parse(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("{}\n\n{}", err, USAGE);
+            std::process::exit(2);
+        }
+    };
+    if options.help {
+        println!("{}", USAGE);
+        return;
+    }
+    if let Err(err) = run(&options) {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,351 @@
+// src/resp3/cluster.rs
+
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::thread;
+
+use crate::resp3::commands::cluster::{ClusterSlotsCommand, SlotRange};
+use crate::resp3::commands::get::MGetCommand;
+use crate::resp3::commands::keys::ScanCommand;
+use crate::resp3::commands::set::MSetCommand;
+use crate::resp3::error::{ErrorCode, Resp3Error, ServerError};
+use crate::resp3::utils::command::{Command, TypedCommand};
+use crate::resp3::utils::redis_connection::{RedisConnection, RedisConnectionBuilder};
+use crate::resp3::value::{FromResp3Value, Resp3Value};
+
+pub const SLOT_COUNT: u16 = 16384;
+
+// CRC16-CCITT (XMODEM), the checksum of the Redis Cluster specification
+fn crc16(data: &[u8]) -> u16 {
+    let mut crc: u16 = 0;
+    for &byte in data {
+        crc ^= (byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 { (crc << 1) ^ 0x1021 } else { crc << 1 };
+        }
+    }
+    crc
+}
+
+// The hash tag of a key: the part between the first { and the next }, when there is one
+// and it is not empty. Keys with the same tag are in the same slot, e.g. {user:42}:cart and
+// {user:42}:orders
+pub fn hash_tag(key: &[u8]) -> Option<&[u8]> {
+    let open = key.iter().position(|&byte| byte == b'{')?;
+    let rest = &key[open + 1..];
+    let close = rest.iter().position(|&byte| byte == b'}')?;
+    Some(&rest[..close]).filter(|tag| !tag.is_empty())
+}
+
+// The slot of a key as sent to the server, only its hash tag is hashed when it has one.
+// Keys are sent UPPERCASE unless the command sends them verbatim, which changes their slot
+pub fn key_slot(key: &[u8]) -> u16 {
+    crc16(hash_tag(key).unwrap_or(key)) % SLOT_COUNT
+}
+
+// The slot of a key as given to the commands, which send it UPPERCASE, e.g. to pre-shard keys
+pub fn slot_for_key(key: &str) -> u16 {
+    key_slot(key.to_uppercase().as_bytes())
+}
+
+// The slot all the keys of the command are in, None when it has no keys. Fails when the
+// keys span several slots, which a cluster refuses with CROSSSLOT
+pub fn command_slot<C: Command + ?Sized>(command: &C) -> Result<Option<u16>, Resp3Error> {
+    let parts = command.get_binary_parts();
+    let mut slot = None;
+    for index in command.key_indices() {
+        let Some(key) = parts.get(index) else { continue };
+        let key_slot = match std::str::from_utf8(key) {
+            Ok(text) if !command.is_verbatim(index) => slot_for_key(text),
+            _ => key_slot(key),
+        };
+        match slot {
+            Some(slot) if slot != key_slot => {
+                let name = parts.first().map(|name| String::from_utf8_lossy(name).to_uppercase()).unwrap_or_default();
+                return Err(Resp3Error::InvalidArgument(format!("{} has keys in slots {} and {}, they must share a hash tag", name, slot, key_slot)));
+            }
+            _ => slot = Some(key_slot),
+        }
+    }
+    Ok(slot)
+}
+
+// The replies of one multi-key command, sent for the keys at these positions
+type SlotReply = (Vec<usize>, Resp3Value);
+
+// The next cursor of one SCAN and the keys it returned
+type ScanReply = (u64, Vec<String>);
+
+// Connections to the masters of a Redis Cluster, for the multi-key operations whose keys
+// span several slots, which a single MGET or MSET refuses with CROSSSLOT
+pub struct ClusterConnections {
+    builder: RedisConnectionBuilder,
+    slots: Vec<SlotRange>,
+    connections: HashMap<String, RedisConnection>,
+}
+
+impl ClusterConnections {
+    // Connects to the node of the builder and asks it the slot ranges. The other nodes are
+    // connected to when first needed, with the same options
+    pub fn connect(builder: RedisConnectionBuilder) -> Result<Self, Resp3Error> {
+        let seed = builder.address().to_string();
+        let conn = builder.clone().connect()?;
+        let mut cluster = ClusterConnections { builder, slots: Vec::new(), connections: HashMap::from([(seed, conn)]) };
+        cluster.refresh()?;
+        Ok(cluster)
+    }
+
+    // Asks the slot ranges again, e.g. after a resharding, from the first node that answers
+    pub fn refresh(&mut self) -> Result<(), Resp3Error> {
+        let mut last_error = Resp3Error::InvalidState("No cluster node is connected".to_string());
+        for conn in self.connections.values_mut() {
+            match ClusterSlotsCommand.query(conn) {
+                Ok(mut slots) => {
+                    slots.sort_by_key(|range| range.start);
+                    self.slots = slots;
+                    return Ok(());
+                }
+                Err(err) => last_error = err,
+            }
+        }
+        Err(last_error)
+    }
+
+    // Sorted by first slot
+    pub fn slots(&self) -> &[SlotRange] {
+        &self.slots
+    }
+
+    // The address of the master serving the slot, None when no node does
+    pub fn node_for_slot(&self, slot: u16) -> Option<&str> {
+        self.slots.iter().find(|range| range.contains(slot)).map(|range| range.master.as_str())
+    }
+
+    // The connection to the node, opened when first needed
+    pub fn connection(&mut self, address: &str) -> Result<&mut RedisConnection, Resp3Error> {
+        if !self.connections.contains_key(address) {
+            let conn = self.builder.for_address(address).connect()?;
+            self.connections.insert(address.to_string(), conn);
+        }
+        Ok(self.connections.get_mut(address).unwrap())
+    }
+
+    // MGET of keys in any slots: one MGET per slot, pipelined per node, the nodes queried
+    // concurrently. The values come back in the order of the keys, as they were stored
+    pub fn mget_clustered(&mut self, keys: &[&str]) -> Result<Vec<Option<String>>, Resp3Error> {
+        let mut values = vec![None; keys.len()];
+        let replies = self.dispatch(keys, &|indices, buffer| MGetCommand::new(indices.iter().map(|&index| keys[index])).encode_into(buffer))?;
+        for (indices, reply) in replies {
+            let reply_values = Vec::<Option<String>>::from_resp3_value(reply)?;
+            if reply_values.len() != indices.len() {
+                return Err(Resp3Error::UnexpectedReply(format!("Expected {} values from MGET, got {}", indices.len(), reply_values.len())));
+            }
+            for (index, value) in indices.into_iter().zip(reply_values) {
+                values[index] = value;
+            }
+        }
+        Ok(values)
+    }
+
+    // MSET of keys in any slots, split like mget_clustered and with the values stored as they
+    // are. Each slot is written atomically, the whole is not: on an error some slots may be written
+    pub fn mset_clustered(&mut self, pairs: &[(&str, &str)]) -> Result<(), Resp3Error> {
+        let keys: Vec<&str> = pairs.iter().map(|(key, _)| *key).collect();
+        let encode = |indices: &[usize], buffer: &mut Vec<u8>| MSetCommand::new(indices.iter().map(|&index| pairs[index])).verbatim_values().encode_into(buffer);
+        for (_, reply) in self.dispatch(&keys, &encode)? {
+            bool::from_resp3_value(reply)?;
+        }
+        Ok(())
+    }
+
+    // Every key matching the pattern, from all the masters, e.g. cluster.scan("session:*").count(500)
+    pub fn scan(&mut self, pattern: &str) -> ClusterScanIterator<'_> {
+        let mut masters: Vec<String> = self.slots.iter().map(|range| range.master.clone()).collect();
+        masters.sort();
+        masters.dedup();
+        let progress = ScanProgress { nodes: masters.len(), ..ScanProgress::default() };
+        ClusterScanIterator {
+            cluster: self,
+            pattern: pattern.to_string(),
+            count: 100,
+            cursors: masters.into_iter().map(|node| (node, 0)).collect(),
+            keys: VecDeque::new(),
+            seen: HashSet::new(),
+            progress,
+            failed: false,
+        }
+    }
+
+    // One SCAN on each of the nodes, concurrently, returning their next cursors and keys
+    fn scan_nodes(&mut self, cursors: &[(String, u64)], pattern: &str, count: u64) -> Result<Vec<(String, u64, Vec<String>)>, Resp3Error> {
+        for (node, _) in cursors {
+            self.connection(node)?;
+        }
+        let mut pending: HashMap<&str, u64> = cursors.iter().map(|(node, cursor)| (node.as_str(), *cursor)).collect();
+        let results: Vec<(String, Result<ScanReply, Resp3Error>)> = thread::scope(|scope| {
+            let handles: Vec<_> = self.connections.iter_mut()
+                .filter_map(|(node, conn)| pending.remove(node.as_str()).map(|cursor| (node, conn, cursor)))
+                .map(|(node, conn, cursor)| (node.clone(), scope.spawn(move || ScanCommand::new(cursor).pattern(pattern.to_string()).count(count).query(conn))))
+                .collect();
+            handles.into_iter().map(|(node, handle)| (node, handle.join().expect("Cluster scan thread panicked"))).collect()
+        });
+
+        let mut scanned = Vec::new();
+        for (node, result) in results {
+            match result {
+                Ok((cursor, keys)) => scanned.push((node, cursor, keys)),
+                Err(err) => {
+                    if matches!(err, Resp3Error::Io(_)) {
+                        self.connections.remove(&node);
+                    }
+                    return Err(err);
+                }
+            }
+        }
+        Ok(scanned)
+    }
+
+    // Sends a command per slot, encoded for the keys at the given positions, and returns
+    // their replies. A MOVED or ASK reply refreshes the slot ranges and sends them all again, once
+    fn dispatch(&mut self, keys: &[&str], encode: &(dyn Fn(&[usize], &mut Vec<u8>) + Sync)) -> Result<Vec<SlotReply>, Resp3Error> {
+        let mut by_slot: BTreeMap<u16, Vec<usize>> = BTreeMap::new();
+        for (index, key) in keys.iter().enumerate() {
+            by_slot.entry(slot_for_key(key)).or_default().push(index);
+        }
+
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            match self.dispatch_once(&by_slot, encode) {
+                Err(Resp3Error::Server(ServerError { code: ErrorCode::Moved { .. } | ErrorCode::Ask { .. }, .. })) if attempt == 1 => self.refresh()?,
+                result => return result,
+            }
+        }
+    }
+
+    fn dispatch_once(&mut self, by_slot: &BTreeMap<u16, Vec<usize>>, encode: &(dyn Fn(&[usize], &mut Vec<u8>) + Sync)) -> Result<Vec<SlotReply>, Resp3Error> {
+        let mut by_node: HashMap<String, Vec<&Vec<usize>>> = HashMap::new();
+        for (&slot, indices) in by_slot {
+            let node = self.node_for_slot(slot).ok_or_else(|| Resp3Error::InvalidState(format!("No cluster node serves slot {}", slot)))?;
+            by_node.entry(node.to_string()).or_default().push(indices);
+        }
+        for node in by_node.keys() {
+            self.connection(node)?;
+        }
+
+        let results: Vec<(String, Result<Vec<SlotReply>, Resp3Error>)> = thread::scope(|scope| {
+            let handles: Vec<_> = self.connections.iter_mut()
+                .filter_map(|(node, conn)| by_node.remove(node).map(|groups| (node, conn, groups)))
+                .map(|(node, conn, groups)| (node.clone(), scope.spawn(move || {
+                    let mut buffer = Vec::new();
+                    for indices in &groups {
+                        encode(indices, &mut buffer);
+                    }
+                    let replies = conn.request_pipelined(&buffer, groups.len())?;
+                    Ok(groups.into_iter().cloned().zip(replies).collect())
+                })))
+                .collect();
+            handles.into_iter().map(|(node, handle)| (node, handle.join().expect("Cluster request thread panicked"))).collect()
+        });
+
+        let mut replies = Vec::new();
+        for (node, result) in results {
+            match result {
+                Ok(node_replies) => replies.extend(node_replies),
+                Err(err) => {
+                    // Opened again on the next request
+                    if matches!(err, Resp3Error::Io(_)) {
+                        self.connections.remove(&node);
+                    }
+                    return Err(err);
+                }
+            }
+        }
+        // The pipelined replies come back as they are, error replies included
+        for (_, reply) in &replies {
+            if let Resp3Value::Error(message) = reply {
+                return Err(Resp3Error::Server(ServerError::parse(message)));
+            }
+        }
+        Ok(replies)
+    }
+}
+
+// How far a ClusterScanIterator got
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ScanProgress {
+    // The masters scanned, as they were when the scan started
+    pub nodes: usize,
+    // The ones whose cursor came back to 0
+    pub finished: usize,
+    // The SCAN calls sent, on all the nodes
+    pub calls: u64,
+    // The distinct keys returned so far
+    pub keys: u64,
+    // Keys returned again, by the same node or by another one after the slot moved
+    pub duplicates: u64,
+}
+
+// The keys of every master, scanned concurrently: each round sends one SCAN to each node
+// still scanning and yields the keys of the round, each one once. Remembering the keys takes
+// memory in proportion to the keyspace. A key moved during the scan is yielded by whichever
+// node returned it first, one that moved between two cursors may be missed. Ends after the
+// first error
+pub struct ClusterScanIterator<'a> {
+    cluster: &'a mut ClusterConnections,
+    pattern: String,
+    count: u64,
+    // The nodes still scanning, with their next cursor
+    cursors: Vec<(String, u64)>,
+    keys: VecDeque<String>,
+    seen: HashSet<String>,
+    progress: ScanProgress,
+    failed: bool,
+}
+
+impl ClusterScanIterator<'_> {
+    // The COUNT hint of each SCAN
+    pub fn count(mut self, count: u64) -> Self {
+        self.count = count;
+        self
+    }
+
+    pub fn progress(&self) -> ScanProgress {
+        self.progress
+    }
+
+    fn scan_round(&mut self) -> Result<(), Resp3Error> {
+        let scanned = self.cluster.scan_nodes(&self.cursors, &self.pattern, self.count)?;
+        self.progress.calls += scanned.len() as u64;
+        for (node, cursor, keys) in scanned {
+            if cursor == 0 {
+                self.progress.finished += 1;
+                self.cursors.retain(|(scanning, _)| *scanning != node);
+            } else if let Some(entry) = self.cursors.iter_mut().find(|(scanning, _)| *scanning == node) {
+                entry.1 = cursor;
+            }
+            for key in keys {
+                if self.seen.insert(key.clone()) {
+                    self.progress.keys += 1;
+                    self.keys.push_back(key);
+                } else {
+                    self.progress.duplicates += 1;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Iterator for ClusterScanIterator<'_> {
+    type Item = Result<String, Resp3Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.keys.is_empty() && !self.cursors.is_empty() && !self.failed {
+            if let Err(err) = self.scan_round() {
+                self.failed = true;
+                return Some(Err(err));
+            }
+        }
+        self.keys.pop_front().map(Ok)
+    }
+}
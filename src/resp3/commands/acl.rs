@@ -0,0 +1,311 @@
+use std::collections::HashMap;
+
+use crate::resp3::error::Resp3Error;
+use crate::resp3::utils::command::{Command, ReplyKind, TypedCommand};
+use crate::resp3::value::{FromResp3Value, Resp3Value};
+
+// The user the connection is authenticated as, "default" without AUTH
+pub struct AclWhoAmICommand;
+
+impl Command for AclWhoAmICommand {
+    fn get_parts(&self) -> Vec<&str> {
+        vec!["ACL", "WHOAMI"]
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Bulk
+    }
+}
+
+impl TypedCommand for AclWhoAmICommand {
+    type Output = String;
+}
+
+// Every user as an ACL rule line, e.g. "user default on nopass ~* &* +@all"
+pub struct AclListCommand;
+
+impl Command for AclListCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        vec!["ACL", "LIST"]
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Array
+    }
+}
+
+impl TypedCommand for AclListCommand {
+    type Output = Vec<String>;
+}
+
+// Creates the user, or changes it, with the rules applied in order. The user name and the
+// rules are sent as they are: passwords are case sensitive, and key patterns have to match
+// the keys as the commands send them, UPPERCASE unless verbatim
+pub struct AclSetUserCommand {
+    user: String,
+    rules: Vec<String>,
+}
+
+impl AclSetUserCommand {
+    pub fn new(user: String) -> Self {
+        AclSetUserCommand { user, rules: Vec::new() }
+    }
+
+    // e.g. "on", ">password", "~cache:*", "&notifications", "+@read", "-flushall"
+    pub fn rule(mut self, rule: &str) -> Self {
+        self.rules.push(rule.to_string());
+        self
+    }
+
+    pub fn rules<I, S>(mut self, rules: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.rules.extend(rules.into_iter().map(Into::into));
+        self
+    }
+}
+
+impl Command for AclSetUserCommand {
+    fn is_verbatim(&self, index: usize) -> bool {
+        index >= 2
+    }
+
+    fn get_parts(&self) -> Vec<&str> {
+        let mut parts = vec!["ACL", "SETUSER", &self.user];
+        parts.extend(self.rules.iter().map(String::as_str));
+        parts
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Status
+    }
+}
+
+impl TypedCommand for AclSetUserCommand {
+    type Output = bool;
+}
+
+pub struct AclDelUserCommand {
+    users: Vec<String>,
+}
+
+impl AclDelUserCommand {
+    pub fn new<I, S>(users: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        AclDelUserCommand { users: users.into_iter().map(Into::into).collect() }
+    }
+}
+
+impl Command for AclDelUserCommand {
+    fn is_verbatim(&self, index: usize) -> bool {
+        index >= 2
+    }
+
+    fn get_parts(&self) -> Vec<&str> {
+        let mut parts = vec!["ACL", "DELUSER"];
+        parts.extend(self.users.iter().map(String::as_str));
+        parts
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Integer
+    }
+}
+
+impl TypedCommand for AclDelUserCommand {
+    // The number of users deleted, the default user cannot be
+    type Output = u64;
+}
+
+// The categories of commands, e.g. "read" or "dangerous", or the commands of one of them
+pub struct AclCatCommand {
+    category: Option<String>,
+}
+
+impl AclCatCommand {
+    pub fn new() -> Self {
+        AclCatCommand { category: None }
+    }
+
+    pub fn category(category: String) -> Self {
+        AclCatCommand { category: Some(category) }
+    }
+}
+
+impl Default for AclCatCommand {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Command for AclCatCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        let mut parts = vec!["ACL", "CAT"];
+        parts.extend(self.category.as_deref());
+        parts
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Array
+    }
+}
+
+impl TypedCommand for AclCatCommand {
+    // Lowercase
+    type Output = Vec<String>;
+}
+
+// A pattern of the keys a user may access, and how
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyPattern {
+    pub pattern: String,
+    pub read: bool,
+    pub write: bool,
+}
+
+impl KeyPattern {
+    // "~pattern" for reads and writes, "%R~pattern", "%W~pattern" or "%RW~pattern" (Redis 7.0+),
+    // or the bare pattern of Redis 6
+    fn parse(rule: &str) -> Self {
+        let (permissions, pattern) = match rule.strip_prefix('%').and_then(|rule| rule.split_once('~')) {
+            Some((permissions, pattern)) => (permissions.to_uppercase(), pattern),
+            None => ("RW".to_string(), rule.strip_prefix('~').unwrap_or(rule)),
+        };
+        KeyPattern { pattern: pattern.to_string(), read: permissions.contains('R'), write: permissions.contains('W') }
+    }
+}
+
+// One rule of the commands a user may run, in the order they were given, the last matching one wins
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CommandRule {
+    // A command, "command|subcommand", or "@category"
+    Allow(String),
+    Deny(String),
+}
+
+impl CommandRule {
+    // "+get -@dangerous", the rules Redis does not express with + and - are left out
+    fn parse_all(rules: &str) -> Vec<Self> {
+        rules.split_whitespace().filter_map(|rule| match (rule.strip_prefix('+'), rule.strip_prefix('-')) {
+            (Some(target), _) => Some(CommandRule::Allow(target.to_string())),
+            (_, Some(target)) => Some(CommandRule::Deny(target.to_string())),
+            _ => None,
+        }).collect()
+    }
+}
+
+// What a user may do, as ACL GETUSER describes it
+#[derive(Debug, Clone, PartialEq)]
+pub struct UserAcl {
+    // e.g. on, off, nopass, skip-sanitize-payload
+    pub flags: Vec<String>,
+    // SHA-256 digests of the passwords as hex, the passwords themselves are not kept
+    pub passwords: Vec<String>,
+    // e.g. "+@all -debug", as Redis writes it
+    pub commands: String,
+    pub command_rules: Vec<CommandRule>,
+    pub keys: Vec<KeyPattern>,
+    // Glob patterns of the Pub/Sub channels, without their "&"
+    pub channels: Vec<String>,
+    // The additional sets of permissions of Redis 7.0, each allowing on its own
+    pub selectors: Vec<AclSelector>,
+}
+
+impl UserAcl {
+    pub fn is_enabled(&self) -> bool {
+        self.has_flag("on")
+    }
+
+    pub fn has_flag(&self, flag: &str) -> bool {
+        self.flags.iter().any(|candidate| candidate.eq_ignore_ascii_case(flag))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AclSelector {
+    pub commands: String,
+    pub command_rules: Vec<CommandRule>,
+    pub keys: Vec<KeyPattern>,
+    pub channels: Vec<String>,
+}
+
+// The field, nil when missing
+fn field<T: FromResp3Value>(fields: &mut HashMap<String, Resp3Value>, name: &str) -> Result<T, Resp3Error> {
+    T::from_resp3_value(fields.remove(name).unwrap_or(Resp3Value::Null))
+}
+
+// Redis 7.0 sends the patterns as one string, "~a %R~b", Redis 6 as an array
+fn patterns(value: Option<Resp3Value>) -> Result<Vec<String>, Resp3Error> {
+    match value {
+        None | Some(Resp3Value::Null) => Ok(Vec::new()),
+        Some(value @ Resp3Value::Array(_)) => Vec::from_resp3_value(value),
+        Some(value) => Ok(String::from_resp3_value(value)?.split_whitespace().map(str::to_string).collect()),
+    }
+}
+
+// The commands, their rules, the keys and the channels, shared by users and selectors
+type Permissions = (String, Vec<CommandRule>, Vec<KeyPattern>, Vec<String>);
+
+fn permissions(fields: &mut HashMap<String, Resp3Value>) -> Result<Permissions, Resp3Error> {
+    let commands: String = field::<Option<String>>(fields, "commands")?.unwrap_or_default();
+    let rules = CommandRule::parse_all(&commands);
+    let keys = patterns(fields.remove("keys"))?.iter().map(|rule| KeyPattern::parse(rule)).collect();
+    let channels = patterns(fields.remove("channels"))?.into_iter()
+        .map(|channel| channel.strip_prefix('&').map(str::to_string).unwrap_or(channel))
+        .collect();
+    Ok((commands, rules, keys, channels))
+}
+
+impl FromResp3Value for AclSelector {
+    fn from_resp3_value(value: Resp3Value) -> Result<Self, Resp3Error> {
+        let mut fields: HashMap<String, Resp3Value> = HashMap::from_resp3_value(value)?;
+        let (commands, command_rules, keys, channels) = permissions(&mut fields)?;
+        Ok(AclSelector { commands, command_rules, keys, channels })
+    }
+}
+
+impl FromResp3Value for UserAcl {
+    fn from_resp3_value(value: Resp3Value) -> Result<Self, Resp3Error> {
+        let mut fields: HashMap<String, Resp3Value> = HashMap::from_resp3_value(value)?;
+        let (commands, command_rules, keys, channels) = permissions(&mut fields)?;
+        Ok(UserAcl {
+            flags: field(&mut fields, "flags")?,
+            passwords: field(&mut fields, "passwords")?,
+            commands,
+            command_rules,
+            keys,
+            channels,
+            selectors: field(&mut fields, "selectors")?,
+        })
+    }
+}
+
+pub struct AclGetUserCommand {
+    user: String,
+}
+
+impl AclGetUserCommand {
+    pub fn new(user: String) -> Self {
+        AclGetUserCommand { user }
+    }
+}
+
+impl Command for AclGetUserCommand {
+    fn is_verbatim(&self, index: usize) -> bool {
+        index == 2
+    }
+
+    fn get_parts(&self) -> Vec<&str> {
+        vec!["ACL", "GETUSER", &self.user]
+    }
+}
+
+impl TypedCommand for AclGetUserCommand {
+    // None when there is no such user
+    type Output = Option<UserAcl>;
+}
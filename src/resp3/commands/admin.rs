@@ -0,0 +1,312 @@
+use std::time::SystemTime;
+
+use crate::resp3::error::Resp3Error;
+use crate::resp3::utils::command::{Command, ReplyKind, TypedCommand};
+use crate::resp3::utils::redis_connection::RedisConnection;
+use crate::resp3::value::{unexpected, FromResp3Value, Resp3Value};
+
+// What the server did with a background save or rewrite
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BackgroundJob {
+    Started,
+    // Queued behind the one in progress, e.g. BGSAVE SCHEDULE during an AOF rewrite
+    Scheduled,
+}
+
+// From the status, e.g. "Background saving started"
+impl FromResp3Value for BackgroundJob {
+    fn from_resp3_value(value: Resp3Value) -> Result<Self, Resp3Error> {
+        let status = match &value {
+            Resp3Value::SimpleString(status) => status.to_lowercase(),
+            _ => return unexpected("the status of a background job", &value),
+        };
+        match status {
+            status if status.ends_with("scheduled") => Ok(BackgroundJob::Scheduled),
+            status if status.ends_with("started") => Ok(BackgroundJob::Started),
+            _ => unexpected("the status of a background job", &value),
+        }
+    }
+}
+
+// Saves the dataset to disk in a forked process, see LastSaveCommand for when it finished
+pub struct BgSaveCommand {
+    schedule: bool,
+}
+
+impl BgSaveCommand {
+    // Fails while an AOF rewrite is in progress
+    pub fn new() -> Self {
+        BgSaveCommand { schedule: false }
+    }
+
+    // Saves once the AOF rewrite in progress finished, instead of failing
+    pub fn schedule() -> Self {
+        BgSaveCommand { schedule: true }
+    }
+}
+
+impl Default for BgSaveCommand {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Command for BgSaveCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        let mut parts = vec!["BGSAVE"];
+        if self.schedule {
+            parts.push("SCHEDULE");
+        }
+        parts
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Status
+    }
+}
+
+impl TypedCommand for BgSaveCommand {
+    type Output = BackgroundJob;
+}
+
+// Rewrites the append only file in a forked process, scheduled when a save is in progress
+pub struct BgRewriteAofCommand;
+
+impl Command for BgRewriteAofCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        vec!["BGREWRITEAOF"]
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Status
+    }
+}
+
+impl TypedCommand for BgRewriteAofCommand {
+    type Output = BackgroundJob;
+}
+
+// When the last successful save finished, the server start when it never saved
+pub struct LastSaveCommand;
+
+impl Command for LastSaveCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        vec!["LASTSAVE"]
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Integer
+    }
+}
+
+impl TypedCommand for LastSaveCommand {
+    type Output = SystemTime;
+}
+
+// Stops the server. It closes the connection instead of replying, which query takes as
+// success rather than as a failed connection: see RedisConnection::request_closing
+pub struct ShutdownCommand {
+    save: Option<bool>,
+    now: bool,
+    force: bool,
+    abort: bool,
+}
+
+impl ShutdownCommand {
+    // Saves when save points are configured
+    pub fn new() -> Self {
+        ShutdownCommand { save: None, now: false, force: false, abort: false }
+    }
+
+    // Aborts the shutdown in progress, e.g. one waiting for replicas to catch up (Redis 7.0+)
+    pub fn abort() -> Self {
+        ShutdownCommand { abort: true, ..Self::new() }
+    }
+
+    // Saves even without save points, or does not save even with them
+    pub fn save(mut self, save: bool) -> Self {
+        self.save = Some(save);
+        self
+    }
+
+    // Does not wait for the replicas to catch up (Redis 7.0+)
+    pub fn now(mut self) -> Self {
+        self.now = true;
+        self
+    }
+
+    // Stops even when the save or the AOF rewrite fails (Redis 7.0+)
+    pub fn force(mut self) -> Self {
+        self.force = true;
+        self
+    }
+}
+
+impl Default for ShutdownCommand {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Command for ShutdownCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        let mut parts = vec!["SHUTDOWN"];
+        match self.save {
+            Some(true) => parts.push("SAVE"),
+            Some(false) => parts.push("NOSAVE"),
+            None => {}
+        }
+        if self.now {
+            parts.push("NOW");
+        }
+        if self.force {
+            parts.push("FORCE");
+        }
+        if self.abort {
+            parts.push("ABORT");
+        }
+        parts
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Status
+    }
+}
+
+impl TypedCommand for ShutdownCommand {
+    // True once the server closed the connection, or aborted the shutdown. A refused
+    // shutdown, e.g. when the save failed, is an error and the server keeps running
+    type Output = bool;
+
+    fn query(&self, conn: &mut RedisConnection) -> Result<bool, Resp3Error> {
+        match conn.request_closing(self)? {
+            Some(reply) => self.parse_output(reply),
+            None => Ok(true),
+        }
+    }
+}
+
+// The number of keys of the selected database
+pub struct DbSizeCommand;
+
+impl Command for DbSizeCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        vec!["DBSIZE"]
+    }
+
+    fn is_readonly(&self) -> bool {
+        true
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Integer
+    }
+}
+
+impl TypedCommand for DbSizeCommand {
+    type Output = u64;
+}
+
+// How a flush frees the memory of the keys
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FlushMode {
+    // Before replying
+    Sync,
+    // In a background thread, the keys are gone before the reply all the same
+    Async,
+}
+
+impl FlushMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FlushMode::Sync => "SYNC",
+            FlushMode::Async => "ASYNC",
+        }
+    }
+}
+
+// Deletes every key of the selected database. Destructive: the connection has to allow it,
+// see RedisConnectionBuilder::allow_destructive
+pub struct FlushDbCommand {
+    mode: Option<FlushMode>,
+}
+
+impl FlushDbCommand {
+    // In the mode of the lazyfree-lazy-user-flush setting
+    pub fn new() -> Self {
+        FlushDbCommand { mode: None }
+    }
+
+    pub fn mode(mut self, mode: FlushMode) -> Self {
+        self.mode = Some(mode);
+        self
+    }
+}
+
+impl Default for FlushDbCommand {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Command for FlushDbCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        let mut parts = vec!["FLUSHDB"];
+        parts.extend(self.mode.as_ref().map(FlushMode::as_str));
+        parts
+    }
+
+    fn is_destructive(&self) -> bool {
+        true
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Status
+    }
+}
+
+impl TypedCommand for FlushDbCommand {
+    type Output = bool;
+}
+
+// Deletes every key of every database, like FlushDbCommand otherwise
+pub struct FlushAllCommand {
+    mode: Option<FlushMode>,
+}
+
+impl FlushAllCommand {
+    pub fn new() -> Self {
+        FlushAllCommand { mode: None }
+    }
+
+    pub fn mode(mut self, mode: FlushMode) -> Self {
+        self.mode = Some(mode);
+        self
+    }
+}
+
+impl Default for FlushAllCommand {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Command for FlushAllCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        let mut parts = vec!["FLUSHALL"];
+        parts.extend(self.mode.as_ref().map(FlushMode::as_str));
+        parts
+    }
+
+    fn is_destructive(&self) -> bool {
+        true
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Status
+    }
+}
+
+impl TypedCommand for FlushAllCommand {
+    type Output = bool;
+}
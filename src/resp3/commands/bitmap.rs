@@ -0,0 +1,223 @@
+use crate::resp3::error::Resp3Error;
+use crate::resp3::utils::command::{Command, ReplyKind, TypedCommand};
+use crate::resp3::value::{FromResp3Value, Resp3Value};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BitOperation {
+    And,
+    Or,
+    Xor,
+    Not,
+}
+
+impl BitOperation {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BitOperation::And => "AND",
+            BitOperation::Or => "OR",
+            BitOperation::Xor => "XOR",
+            BitOperation::Not => "NOT",
+        }
+    }
+}
+
+pub struct SetBitCommand {
+    key: String,
+    offset: String,
+    value: &'static str,
+}
+
+impl SetBitCommand {
+    pub fn new(key: String, offset: u64, value: bool) -> Self {
+        SetBitCommand { key, offset: offset.to_string(), value: if value { "1" } else { "0" } }
+    }
+}
+
+impl Command for SetBitCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        vec!["SETBIT", &self.key, &self.offset, self.value]
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![1]
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Integer
+    }
+}
+
+impl TypedCommand for SetBitCommand {
+    // The value the bit had before
+    type Output = bool;
+}
+
+pub struct GetBitCommand {
+    key: String,
+    offset: String,
+}
+
+impl GetBitCommand {
+    pub fn new(key: String, offset: u64) -> Self {
+        GetBitCommand { key, offset: offset.to_string() }
+    }
+}
+
+impl Command for GetBitCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        vec!["GETBIT", &self.key, &self.offset]
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![1]
+    }
+
+    fn is_readonly(&self) -> bool {
+        true
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Integer
+    }
+}
+
+impl TypedCommand for GetBitCommand {
+    type Output = bool;
+}
+
+pub struct BitCountCommand {
+    key: String,
+    range: Vec<String>,
+}
+
+impl BitCountCommand {
+    pub fn new(key: String) -> Self {
+        BitCountCommand { key, range: Vec::new() }
+    }
+
+    // Counts only the bytes from start to end, negative indexes count from the end
+    pub fn range(mut self, start: i64, end: i64) -> Self {
+        self.range = vec![start.to_string(), end.to_string()];
+        self
+    }
+
+    // Counts only the bits from start to end (Redis 7.0+)
+    pub fn bit_range(mut self, start: i64, end: i64) -> Self {
+        self.range = vec![start.to_string(), end.to_string(), "BIT".to_string()];
+        self
+    }
+}
+
+impl Command for BitCountCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        let mut parts = vec!["BITCOUNT", &self.key];
+        parts.extend(self.range.iter().map(String::as_str));
+        parts
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![1]
+    }
+
+    fn is_readonly(&self) -> bool {
+        true
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Integer
+    }
+}
+
+impl TypedCommand for BitCountCommand {
+    type Output = u64;
+}
+
+pub struct BitOpCommand {
+    operation: BitOperation,
+    destination: String,
+    keys: Vec<String>,
+}
+
+impl BitOpCommand {
+    // NOT takes exactly one source key
+    pub fn new(operation: BitOperation, destination: String, keys: Vec<String>) -> Self {
+        BitOpCommand { operation, destination, keys }
+    }
+}
+
+impl Command for BitOpCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        let mut parts = vec!["BITOP", self.operation.as_str(), &self.destination];
+        parts.extend(self.keys.iter().map(String::as_str));
+        parts
+    }
+
+    // The destination and the source keys
+    fn key_indices(&self) -> Vec<usize> {
+        (2..3 + self.keys.len()).collect()
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Integer
+    }
+}
+
+impl TypedCommand for BitOpCommand {
+    // Length in bytes of the destination string
+    type Output = u64;
+}
+
+pub struct BitPosCommand {
+    key: String,
+    bit: &'static str,
+    range: Vec<String>,
+}
+
+impl BitPosCommand {
+    pub fn new(key: String, bit: bool) -> Self {
+        BitPosCommand { key, bit: if bit { "1" } else { "0" }, range: Vec::new() }
+    }
+
+    // Looks only at the bytes from start to end, negative indexes count from the end
+    pub fn range(mut self, start: i64, end: i64) -> Self {
+        self.range = vec![start.to_string(), end.to_string()];
+        self
+    }
+
+    // Looks only at the bits from start to end (Redis 7.0+)
+    pub fn bit_range(mut self, start: i64, end: i64) -> Self {
+        self.range = vec![start.to_string(), end.to_string(), "BIT".to_string()];
+        self
+    }
+}
+
+impl Command for BitPosCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        let mut parts = vec!["BITPOS", &self.key, self.bit];
+        parts.extend(self.range.iter().map(String::as_str));
+        parts
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![1]
+    }
+
+    fn is_readonly(&self) -> bool {
+        true
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Integer
+    }
+}
+
+impl TypedCommand for BitPosCommand {
+    // Offset of the first bit with the requested value, None when there is none
+    type Output = Option<u64>;
+
+    // Redis replies -1 rather than nil when the bit is not found
+    fn parse_output(&self, reply: Resp3Value) -> Result<Self::Output, Resp3Error> {
+        let position = i64::from_resp3_value(reply)?;
+        Ok(u64::try_from(position).ok())
+    }
+}
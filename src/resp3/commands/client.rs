@@ -0,0 +1,389 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::resp3::error::Resp3Error;
+use crate::resp3::utils::command::{Command, ReplyKind, TypedCommand};
+use crate::resp3::value::{flat_to_map, FromResp3Value, Resp3Map, Resp3Value};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ClientReplyMode {
+    On,
+    // No replies until CLIENT REPLY ON
+    Off,
+    // No reply to the next command
+    Skip,
+}
+
+impl ClientReplyMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ClientReplyMode::On => "ON",
+            ClientReplyMode::Off => "OFF",
+            ClientReplyMode::Skip => "SKIP",
+        }
+    }
+}
+
+// Only ON gets a reply, OFF and SKIP are meant to be sent together with the commands
+// that follow them, as RedisConnection::send_no_reply and CommandQueue do
+pub struct ClientReplyCommand {
+    mode: ClientReplyMode,
+}
+
+impl ClientReplyCommand {
+    pub fn new(mode: ClientReplyMode) -> Self {
+        ClientReplyCommand { mode }
+    }
+}
+
+impl Command for ClientReplyCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        vec!["CLIENT", "REPLY", self.mode.as_str()]
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Status
+    }
+}
+
+impl TypedCommand for ClientReplyCommand {
+    type Output = bool;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ClientFlag {
+    // The connection is not evicted by maxmemory-clients, e.g. for admin connections (Redis 7.0+)
+    NoEvict,
+    // Commands do not touch the LRU or LFU of the keys they read, e.g. for analytics scans (Redis 7.2+)
+    NoTouch,
+}
+
+impl ClientFlag {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ClientFlag::NoEvict => "NO-EVICT",
+            ClientFlag::NoTouch => "NO-TOUCH",
+        }
+    }
+}
+
+// CLIENT NO-EVICT or CLIENT NO-TOUCH, for the current connection only. The builder of
+// RedisConnection sets them on every connect and reconnect
+pub struct ClientFlagCommand {
+    flag: ClientFlag,
+    enabled: bool,
+}
+
+impl ClientFlagCommand {
+    pub fn new(flag: ClientFlag, enabled: bool) -> Self {
+        ClientFlagCommand { flag, enabled }
+    }
+}
+
+impl Command for ClientFlagCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        vec!["CLIENT", self.flag.as_str(), if self.enabled { "ON" } else { "OFF" }]
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Status
+    }
+}
+
+impl TypedCommand for ClientFlagCommand {
+    type Output = bool;
+}
+
+// The server closes the connection after replying, RedisConnection::close sends it
+pub struct QuitCommand;
+
+impl Command for QuitCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        vec!["QUIT"]
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Status
+    }
+}
+
+// Switches the protocol of the connection, replying with the server's name, version, role,
+// modules and the like
+pub struct HelloCommand {
+    version: String,
+}
+
+impl HelloCommand {
+    // 2 or 3, servers older than Redis 6 do not know the command
+    pub fn new(version: u8) -> Self {
+        HelloCommand { version: version.to_string() }
+    }
+}
+
+impl Command for HelloCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        vec!["HELLO", &self.version]
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Array
+    }
+
+    fn normalize_resp2(&self, reply: Resp3Value) -> Resp3Value {
+        flat_to_map(reply)
+    }
+}
+
+impl TypedCommand for HelloCommand {
+    type Output = Resp3Map;
+}
+
+// Checks that the connection is alive, e.g. before handing out a pooled one
+pub struct PingCommand;
+
+impl Command for PingCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        vec!["PING"]
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Status
+    }
+}
+
+impl TypedCommand for PingCommand {
+    type Output = String;
+}
+
+// The kinds of connections CLIENT LIST and CLIENT KILL filter on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClientType {
+    Normal,
+    Master,
+    Replica,
+    PubSub,
+}
+
+impl ClientType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ClientType::Normal => "NORMAL",
+            ClientType::Master => "MASTER",
+            ClientType::Replica => "REPLICA",
+            ClientType::PubSub => "PUBSUB",
+        }
+    }
+}
+
+// One connection of CLIENT LIST. The fields every server since Redis 6.0 sends are typed, the
+// others are left in fields, e.g. info.get("tot-mem") or info.get("lib-name") on Redis 7.2+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ClientInfo {
+    pub id: u64,
+    // The address of the client, "ip:port"
+    pub addr: String,
+    // The address of the server it connected to, empty before Redis 6.2
+    pub laddr: String,
+    // Empty unless set with CLIENT SETNAME
+    pub name: String,
+    pub age: Duration,
+    pub idle: Duration,
+    // One letter per flag, e.g. "N" for none, "P" for Pub/Sub, "x" inside MULTI
+    pub flags: String,
+    pub db: u64,
+    // The number of channels and patterns subscribed to
+    pub sub: u64,
+    pub psub: u64,
+    // The number of commands queued inside MULTI, None outside
+    pub multi: Option<u64>,
+    // The last command, e.g. "client|list"
+    pub cmd: String,
+    pub user: String,
+    // Every field as sent, the ones above included
+    pub fields: HashMap<String, String>,
+}
+
+impl ClientInfo {
+    // e.g. "id=3 addr=127.0.0.1:50188 laddr=127.0.0.1:6379 fd=8 name= age=0 idle=0 flags=N db=0 ..."
+    pub fn parse(line: &str) -> Result<Self, Resp3Error> {
+        let mut fields = HashMap::new();
+        for pair in line.split_whitespace() {
+            let (field, value) = pair.split_once('=')
+                .ok_or_else(|| Resp3Error::UnexpectedReply(format!("Expected field=value in CLIENT LIST, got {}", pair)))?;
+            fields.insert(field.to_string(), value.to_string());
+        }
+        let text = |field: &str| fields.get(field).cloned().unwrap_or_default();
+        let number = |field: &str| -> Result<u64, Resp3Error> {
+            match fields.get(field) {
+                None => Ok(0),
+                Some(value) => value.parse().map_err(|_| Resp3Error::UnexpectedReply(format!("Expected a number for {} in CLIENT LIST, got {}", field, value))),
+            }
+        };
+        Ok(ClientInfo {
+            id: number("id")?,
+            addr: text("addr"),
+            laddr: text("laddr"),
+            name: text("name"),
+            age: Duration::from_secs(number("age")?),
+            idle: Duration::from_secs(number("idle")?),
+            flags: text("flags"),
+            db: number("db")?,
+            sub: number("sub")?,
+            psub: number("psub")?,
+            multi: fields.get("multi").and_then(|multi| multi.parse().ok()),
+            cmd: text("cmd"),
+            user: text("user"),
+            fields,
+        })
+    }
+
+    pub fn get(&self, field: &str) -> Option<&str> {
+        self.fields.get(field).map(String::as_str)
+    }
+}
+
+// From one line of CLIENT LIST, or the whole reply of CLIENT INFO
+impl FromResp3Value for ClientInfo {
+    fn from_resp3_value(value: Resp3Value) -> Result<Self, Resp3Error> {
+        ClientInfo::parse(&String::from_resp3_value(value)?)
+    }
+}
+
+// The connections to the server, every one or those of a type or with the given ids (Redis 6.2+)
+pub struct ClientListCommand {
+    options: Vec<String>,
+}
+
+impl ClientListCommand {
+    pub fn new() -> Self {
+        ClientListCommand { options: Vec::new() }
+    }
+
+    pub fn client_type(mut self, client_type: ClientType) -> Self {
+        self.options.extend(["TYPE".to_string(), client_type.as_str().to_string()]);
+        self
+    }
+
+    pub fn ids(mut self, ids: impl IntoIterator<Item = u64>) -> Self {
+        self.options.push("ID".to_string());
+        self.options.extend(ids.into_iter().map(|id| id.to_string()));
+        self
+    }
+}
+
+impl Default for ClientListCommand {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Command for ClientListCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        let mut parts = vec!["CLIENT", "LIST"];
+        parts.extend(self.options.iter().map(String::as_str));
+        parts
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Bulk
+    }
+}
+
+impl TypedCommand for ClientListCommand {
+    type Output = Vec<ClientInfo>;
+
+    // One line per connection, in a bulk string over RESP2 and a verbatim string over RESP3
+    fn parse_output(&self, reply: Resp3Value) -> Result<Self::Output, Resp3Error> {
+        let list = String::from_resp3_value(reply)?;
+        list.lines().filter(|line| !line.is_empty()).map(ClientInfo::parse).collect()
+    }
+}
+
+// The id of the current connection, to find it in CLIENT LIST or leave it out of CLIENT KILL
+pub struct ClientIdCommand;
+
+impl Command for ClientIdCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        vec!["CLIENT", "ID"]
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Integer
+    }
+}
+
+impl TypedCommand for ClientIdCommand {
+    type Output = u64;
+}
+
+// Closes the connections matching every filter given, e.g. ClientKillCommand::new().user("app").skip_me(true).
+// The values are sent as they are, user names being case sensitive
+pub struct ClientKillCommand {
+    filters: Vec<String>,
+}
+
+impl ClientKillCommand {
+    pub fn new() -> Self {
+        ClientKillCommand { filters: Vec::new() }
+    }
+
+    pub fn id(self, id: u64) -> Self {
+        self.filter("ID", id.to_string())
+    }
+
+    // The address of the client, "ip:port" as in ClientInfo::addr
+    pub fn addr(self, addr: &str) -> Self {
+        self.filter("ADDR", addr.to_string())
+    }
+
+    // The address of the server the clients connected to (Redis 6.2+)
+    pub fn laddr(self, laddr: &str) -> Self {
+        self.filter("LADDR", laddr.to_string())
+    }
+
+    pub fn client_type(self, client_type: ClientType) -> Self {
+        self.filter("TYPE", client_type.as_str().to_string())
+    }
+
+    pub fn user(self, user: &str) -> Self {
+        self.filter("USER", user.to_string())
+    }
+
+    // Whether the current connection may be closed too, it is by default
+    pub fn skip_me(self, skip: bool) -> Self {
+        self.filter("SKIPME", if skip { "YES" } else { "NO" }.to_string())
+    }
+
+    fn filter(mut self, name: &str, value: String) -> Self {
+        self.filters.push(name.to_string());
+        self.filters.push(value);
+        self
+    }
+}
+
+impl Default for ClientKillCommand {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Command for ClientKillCommand {
+    fn is_verbatim(&self, index: usize) -> bool {
+        index >= 2
+    }
+
+    fn get_parts(&self) -> Vec<&str> {
+        let mut parts = vec!["CLIENT", "KILL"];
+        parts.extend(self.filters.iter().map(String::as_str));
+        parts
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Integer
+    }
+}
+
+impl TypedCommand for ClientKillCommand {
+    // The number of connections closed
+    type Output = u64;
+}
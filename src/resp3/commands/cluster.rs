@@ -0,0 +1,70 @@
+use crate::resp3::error::Resp3Error;
+use crate::resp3::utils::command::{Command, ReplyKind, TypedCommand};
+use crate::resp3::value::{unexpected, FromResp3Value, Resp3Value};
+
+// A range of hash slots and the nodes serving it, as host:port
+#[derive(Debug, Clone, PartialEq)]
+pub struct SlotRange {
+    pub start: u16,
+    pub end: u16,
+    pub master: String,
+    pub replicas: Vec<String>,
+}
+
+impl SlotRange {
+    pub fn contains(&self, slot: u16) -> bool {
+        (self.start..=self.end).contains(&slot)
+    }
+}
+
+// [start, end, [host, port, id, ...], [host, port, id, ...]...], the first node is the master
+impl FromResp3Value for SlotRange {
+    fn from_resp3_value(value: Resp3Value) -> Result<Self, Resp3Error> {
+        let Resp3Value::Array(fields) = value else { return unexpected("a slot range", &value) };
+        let mut fields = fields.into_iter();
+        let (Some(start), Some(end), Some(master)) = (fields.next(), fields.next(), fields.next()) else {
+            return Err(Resp3Error::UnexpectedReply("Expected a slot range of at least three elements".to_string()));
+        };
+        Ok(SlotRange {
+            start: small_integer(start)?,
+            end: small_integer(end)?,
+            master: node_address(master)?,
+            replicas: fields.map(node_address).collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+fn small_integer(value: Resp3Value) -> Result<u16, Resp3Error> {
+    let integer = u64::from_resp3_value(value)?;
+    u16::try_from(integer).map_err(|_| Resp3Error::UnexpectedReply(format!("Expected a slot or a port, got {}", integer)))
+}
+
+fn node_address(value: Resp3Value) -> Result<String, Resp3Error> {
+    let (host, port): (String, u16) = match value {
+        Resp3Value::Array(fields) if fields.len() >= 2 => {
+            let mut fields = fields.into_iter();
+            (String::from_resp3_value(fields.next().unwrap())?, small_integer(fields.next().unwrap())?)
+        }
+        value => return unexpected("a node of a slot range", &value),
+    };
+    // IPv6 hosts are bracketed so that the port can be told apart
+    Ok(if host.contains(':') { format!("[{}]:{}", host, port) } else { format!("{}:{}", host, port) })
+}
+
+// The slot ranges of the cluster and the nodes serving them (deprecated in Redis 7.0 for
+// CLUSTER SHARDS, which older servers do not know)
+pub struct ClusterSlotsCommand;
+
+impl Command for ClusterSlotsCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        vec!["CLUSTER", "SLOTS"]
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Array
+    }
+}
+
+impl TypedCommand for ClusterSlotsCommand {
+    type Output = Vec<SlotRange>;
+}
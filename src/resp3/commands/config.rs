@@ -0,0 +1,436 @@
+use std::collections::{BTreeMap, HashMap};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::resp3::error::Resp3Error;
+use crate::resp3::utils::command::{Command, ReplyKind, TypedCommand};
+use crate::resp3::value::{flat_to_map, unexpected, FromResp3Value, Resp3Value};
+
+const FIRST_VALUE_INDEX: usize = 3;
+
+pub struct ConfigGetCommand {
+    patterns: Vec<String>,
+}
+
+impl ConfigGetCommand {
+    // Glob-style pattern matched against the parameter names, e.g. "maxmemory*"
+    pub fn new(pattern: String) -> Self {
+        ConfigGetCommand { patterns: vec![pattern] }
+    }
+
+    // Matches one more pattern in the same command (Redis 7.0+)
+    pub fn pattern(mut self, pattern: String) -> Self {
+        self.patterns.push(pattern);
+        self
+    }
+}
+
+impl Command for ConfigGetCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        let mut parts = vec!["CONFIG", "GET"];
+        parts.extend(self.patterns.iter().map(String::as_str));
+        parts
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Array
+    }
+
+    fn normalize_resp2(&self, reply: Resp3Value) -> Resp3Value {
+        flat_to_map(reply)
+    }
+}
+
+impl TypedCommand for ConfigGetCommand {
+    // Matching parameter names, lowercase, with their values
+    type Output = HashMap<String, String>;
+}
+
+pub struct ConfigSetCommand {
+    parameters: Vec<String>,
+}
+
+impl ConfigSetCommand {
+    pub fn new(parameter: String, value: String) -> Self {
+        ConfigSetCommand { parameters: vec![parameter, value] }
+    }
+
+    // Sets one more parameter atomically with the others (Redis 7.0+)
+    pub fn parameter(mut self, parameter: String, value: String) -> Self {
+        self.parameters.push(parameter);
+        self.parameters.push(value);
+        self
+    }
+}
+
+impl Command for ConfigSetCommand {
+    // Some values are case sensitive (e.g. notify-keyspace-events flags), so they are sent as they are
+    fn is_verbatim(&self, index: usize) -> bool {
+        index >= FIRST_VALUE_INDEX && (index - FIRST_VALUE_INDEX).is_multiple_of(2)
+    }
+
+    fn get_parts(&self) -> Vec<&str> {
+        let mut parts = vec!["CONFIG", "SET"];
+        parts.extend(self.parameters.iter().map(String::as_str));
+        parts
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Status
+    }
+}
+
+impl TypedCommand for ConfigSetCommand {
+    type Output = bool;
+}
+
+// Resets the statistics reported by INFO, including the command and keyspace hit counters
+pub struct ConfigResetStatCommand;
+
+impl Command for ConfigResetStatCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        vec!["CONFIG", "RESETSTAT"]
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Status
+    }
+}
+
+impl TypedCommand for ConfigResetStatCommand {
+    type Output = bool;
+}
+
+// A command that took longer than slowlog-log-slower-than
+#[derive(Debug, Clone, PartialEq)]
+pub struct SlowlogEntry {
+    pub id: u64,
+    pub timestamp: SystemTime,
+    pub duration: Duration,
+    // Redis truncates long argument lists and values, binary values are decoded lossily
+    pub arguments: Vec<String>,
+    pub client_address: String,
+    pub client_name: String,
+}
+
+impl FromResp3Value for SlowlogEntry {
+    fn from_resp3_value(value: Resp3Value) -> Result<Self, Resp3Error> {
+        let values = match value {
+            Resp3Value::Array(values) if values.len() >= 4 => values,
+            value => return unexpected("a slowlog entry", &value),
+        };
+        let mut values = values.into_iter();
+        let id = u64::from_resp3_value(values.next().unwrap())?;
+        let timestamp = UNIX_EPOCH + Duration::from_secs(u64::from_resp3_value(values.next().unwrap())?);
+        let duration = Duration::from_micros(u64::from_resp3_value(values.next().unwrap())?);
+        let arguments = Vec::<Vec<u8>>::from_resp3_value(values.next().unwrap())?
+            .iter()
+            .map(|argument| String::from_utf8_lossy(argument).into_owned())
+            .collect();
+        // The client fields were added in Redis 4.0
+        let client_address = values.next().map(String::from_resp3_value).transpose()?.unwrap_or_default();
+        let client_name = values.next().map(String::from_resp3_value).transpose()?.unwrap_or_default();
+
+        Ok(SlowlogEntry { id, timestamp, duration, arguments, client_address, client_name })
+    }
+}
+
+pub struct SlowlogGetCommand {
+    count: Option<String>,
+}
+
+impl SlowlogGetCommand {
+    // The 10 most recent entries
+    pub fn new() -> Self {
+        SlowlogGetCommand { count: None }
+    }
+
+    // The count most recent entries, all of them with -1
+    pub fn count(mut self, count: i64) -> Self {
+        self.count = Some(count.to_string());
+        self
+    }
+}
+
+impl Default for SlowlogGetCommand {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Command for SlowlogGetCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        let mut parts = vec!["SLOWLOG", "GET"];
+        parts.extend(self.count.as_deref());
+        parts
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Array
+    }
+}
+
+impl TypedCommand for SlowlogGetCommand {
+    // Most recent first
+    type Output = Vec<SlowlogEntry>;
+}
+
+pub struct SlowlogLenCommand;
+
+impl Command for SlowlogLenCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        vec!["SLOWLOG", "LEN"]
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Integer
+    }
+}
+
+impl TypedCommand for SlowlogLenCommand {
+    type Output = u64;
+}
+
+pub struct SlowlogResetCommand;
+
+impl Command for SlowlogResetCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        vec!["SLOWLOG", "RESET"]
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Status
+    }
+}
+
+impl TypedCommand for SlowlogResetCommand {
+    type Output = bool;
+}
+
+// The latest spike of a latency event, e.g. "command" or "fork", with the worst one recorded.
+// Events are only recorded above latency-monitor-threshold, which is 0, off, by default
+#[derive(Debug, Clone, PartialEq)]
+pub struct LatencyEvent {
+    pub name: String,
+    pub timestamp: SystemTime,
+    pub latest: Duration,
+    pub max: Duration,
+}
+
+impl FromResp3Value for LatencyEvent {
+    fn from_resp3_value(value: Resp3Value) -> Result<Self, Resp3Error> {
+        let values = match value {
+            Resp3Value::Array(values) if values.len() >= 4 => values,
+            value => return unexpected("a latency event", &value),
+        };
+        let mut values = values.into_iter();
+        Ok(LatencyEvent {
+            name: String::from_resp3_value(values.next().unwrap())?,
+            timestamp: UNIX_EPOCH + Duration::from_secs(u64::from_resp3_value(values.next().unwrap())?),
+            latest: Duration::from_millis(u64::from_resp3_value(values.next().unwrap())?),
+            max: Duration::from_millis(u64::from_resp3_value(values.next().unwrap())?),
+        })
+    }
+}
+
+pub struct LatencyLatestCommand;
+
+impl Command for LatencyLatestCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        vec!["LATENCY", "LATEST"]
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Array
+    }
+}
+
+impl TypedCommand for LatencyLatestCommand {
+    type Output = Vec<LatencyEvent>;
+}
+
+// A spike of a latency event, Redis keeps the last 160 of each
+#[derive(Debug, Clone, PartialEq)]
+pub struct LatencySample {
+    pub timestamp: SystemTime,
+    pub latency: Duration,
+}
+
+impl FromResp3Value for LatencySample {
+    fn from_resp3_value(value: Resp3Value) -> Result<Self, Resp3Error> {
+        let (timestamp, latency): (u64, u64) = match value {
+            Resp3Value::Array(values) if values.len() == 2 => {
+                let mut values = values.into_iter();
+                (u64::from_resp3_value(values.next().unwrap())?, u64::from_resp3_value(values.next().unwrap())?)
+            }
+            value => return unexpected("a latency sample", &value),
+        };
+        Ok(LatencySample { timestamp: UNIX_EPOCH + Duration::from_secs(timestamp), latency: Duration::from_millis(latency) })
+    }
+}
+
+pub struct LatencyHistoryCommand {
+    event: String,
+}
+
+impl LatencyHistoryCommand {
+    // The event names are lowercase, e.g. "command", see LatencyLatestCommand
+    pub fn new(event: String) -> Self {
+        LatencyHistoryCommand { event }
+    }
+}
+
+impl Command for LatencyHistoryCommand {
+    // Event names are case sensitive
+    fn is_verbatim(&self, index: usize) -> bool {
+        index == 2
+    }
+
+    fn get_parts(&self) -> Vec<&str> {
+        vec!["LATENCY", "HISTORY", &self.event]
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Array
+    }
+}
+
+impl TypedCommand for LatencyHistoryCommand {
+    // Oldest first, empty for an event never recorded
+    type Output = Vec<LatencySample>;
+}
+
+pub struct LatencyResetCommand {
+    events: Vec<String>,
+}
+
+impl LatencyResetCommand {
+    // Every event
+    pub fn new() -> Self {
+        LatencyResetCommand { events: Vec::new() }
+    }
+
+    pub fn events<I, S>(events: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        LatencyResetCommand { events: events.into_iter().map(Into::into).collect() }
+    }
+}
+
+impl Default for LatencyResetCommand {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Command for LatencyResetCommand {
+    fn is_verbatim(&self, index: usize) -> bool {
+        index >= 2
+    }
+
+    fn get_parts(&self) -> Vec<&str> {
+        let mut parts = vec!["LATENCY", "RESET"];
+        parts.extend(self.events.iter().map(String::as_str));
+        parts
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Integer
+    }
+}
+
+impl TypedCommand for LatencyResetCommand {
+    // The number of events reset
+    type Output = u64;
+}
+
+// A report of the memory issues Redis can see, in English, for people rather than programs
+pub struct MemoryDoctorCommand;
+
+impl Command for MemoryDoctorCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        vec!["MEMORY", "DOCTOR"]
+    }
+}
+
+impl TypedCommand for MemoryDoctorCommand {
+    type Output = String;
+}
+
+// The overhead of the hash tables of a logical database, in bytes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DbOverhead {
+    pub main: u64,
+    pub expires: u64,
+}
+
+// In bytes unless said otherwise. Fields missing from older servers are 0
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MemoryStats {
+    pub peak_allocated: u64,
+    pub total_allocated: u64,
+    pub startup_allocated: u64,
+    pub overhead_total: u64,
+    pub keys_count: u64,
+    pub dataset_bytes: u64,
+    // Of the memory allocated past startup
+    pub dataset_percentage: f64,
+    // Of the peak
+    pub peak_percentage: f64,
+    // The memory the process holds as a multiple of the memory allocated
+    pub fragmentation: f64,
+    // By database number, for the databases holding keys
+    pub databases: BTreeMap<u32, DbOverhead>,
+    // The other fields, which differ between versions and allocators, by name
+    pub other: HashMap<String, Resp3Value>,
+}
+
+impl FromResp3Value for MemoryStats {
+    fn from_resp3_value(value: Resp3Value) -> Result<Self, Resp3Error> {
+        let fields: HashMap<String, Resp3Value> = HashMap::from_resp3_value(value)?;
+        let mut stats = MemoryStats::default();
+        for (name, value) in fields {
+            match name.as_str() {
+                "peak.allocated" => stats.peak_allocated = u64::from_resp3_value(value)?,
+                "total.allocated" => stats.total_allocated = u64::from_resp3_value(value)?,
+                "startup.allocated" => stats.startup_allocated = u64::from_resp3_value(value)?,
+                "overhead.total" => stats.overhead_total = u64::from_resp3_value(value)?,
+                "keys.count" => stats.keys_count = u64::from_resp3_value(value)?,
+                "dataset.bytes" => stats.dataset_bytes = u64::from_resp3_value(value)?,
+                "dataset.percentage" => stats.dataset_percentage = f64::from_resp3_value(value)?,
+                "peak.percentage" => stats.peak_percentage = f64::from_resp3_value(value)?,
+                "fragmentation" => stats.fragmentation = f64::from_resp3_value(value)?,
+                _ => match name.strip_prefix("db.").and_then(|db| db.parse().ok()) {
+                    Some(db) => {
+                        let mut overhead: HashMap<String, u64> = HashMap::from_resp3_value(value)?;
+                        stats.databases.insert(db, DbOverhead {
+                            main: overhead.remove("overhead.hashtable.main").unwrap_or_default(),
+                            expires: overhead.remove("overhead.hashtable.expires").unwrap_or_default(),
+                        });
+                    }
+                    None => {
+                        stats.other.insert(name, value);
+                    }
+                },
+            }
+        }
+        Ok(stats)
+    }
+}
+
+pub struct MemoryStatsCommand;
+
+impl Command for MemoryStatsCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        vec!["MEMORY", "STATS"]
+    }
+
+    fn normalize_resp2(&self, reply: Resp3Value) -> Resp3Value {
+        flat_to_map(reply)
+    }
+}
+
+impl TypedCommand for MemoryStatsCommand {
+    type Output = MemoryStats;
+}
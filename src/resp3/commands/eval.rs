@@ -0,0 +1,191 @@
+use crate::resp3::utils::command::{Command, ReplyKind, TypedCommand};
+
+const SCRIPT_INDEX: usize = 1;
+
+pub struct EvalCommand {
+    script: String,
+    num_keys: String,
+    keys: Vec<String>,
+    args: Vec<String>,
+    verbatim_args: bool,
+}
+
+impl EvalCommand {
+    pub fn new(script: String, keys: Vec<String>, args: Vec<String>) -> Self {
+        EvalCommand { script, num_keys: keys.len().to_string(), keys, args, verbatim_args: false }
+    }
+
+    // Send the arguments as they are instead of UPPERCASE, e.g. for payloads
+    pub fn verbatim_args(mut self) -> Self {
+        self.verbatim_args = true;
+        self
+    }
+}
+
+impl Command for EvalCommand {
+    // Lua is case sensitive, so the script body is sent as it is
+    fn is_verbatim(&self, index: usize) -> bool {
+        index == SCRIPT_INDEX || (self.verbatim_args && index >= 3 + self.keys.len())
+    }
+
+    fn get_parts(&self) -> Vec<&str> {
+        let mut parts = vec!["EVAL", &self.script, &self.num_keys];
+        parts.extend(self.keys.iter().map(String::as_str));
+        parts.extend(self.args.iter().map(String::as_str));
+        parts
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        (3..3 + self.keys.len()).collect()
+    }
+}
+
+// Runs a script loaded with SCRIPT LOAD (or sent once with EVAL) by its SHA1 digest, the
+// server replies NOSCRIPT when it does not know it, e.g. after SCRIPT FLUSH or a restart
+pub struct EvalShaCommand {
+    sha: String,
+    num_keys: String,
+    keys: Vec<String>,
+    args: Vec<String>,
+    verbatim_args: bool,
+}
+
+impl EvalShaCommand {
+    pub fn new(sha: String, keys: Vec<String>, args: Vec<String>) -> Self {
+        EvalShaCommand { sha, num_keys: keys.len().to_string(), keys, args, verbatim_args: false }
+    }
+
+    // Send the arguments as they are instead of UPPERCASE, e.g. for payloads
+    pub fn verbatim_args(mut self) -> Self {
+        self.verbatim_args = true;
+        self
+    }
+}
+
+impl Command for EvalShaCommand {
+    // The digests are lowercase hex, as SCRIPT LOAD returns them
+    fn is_verbatim(&self, index: usize) -> bool {
+        index == SCRIPT_INDEX || (self.verbatim_args && index >= 3 + self.keys.len())
+    }
+
+    fn get_parts(&self) -> Vec<&str> {
+        let mut parts = vec!["EVALSHA", &self.sha, &self.num_keys];
+        parts.extend(self.keys.iter().map(String::as_str));
+        parts.extend(self.args.iter().map(String::as_str));
+        parts
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        (3..3 + self.keys.len()).collect()
+    }
+}
+
+// Caches the script on the server without running it
+pub struct ScriptLoadCommand {
+    script: String,
+}
+
+impl ScriptLoadCommand {
+    pub fn new(script: String) -> Self {
+        ScriptLoadCommand { script }
+    }
+}
+
+impl Command for ScriptLoadCommand {
+    fn is_verbatim(&self, index: usize) -> bool {
+        index == 2
+    }
+
+    fn get_parts(&self) -> Vec<&str> {
+        vec!["SCRIPT", "LOAD", &self.script]
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Bulk
+    }
+}
+
+impl TypedCommand for ScriptLoadCommand {
+    // The SHA1 digest to call the script with EVALSHA
+    type Output = String;
+}
+
+pub struct ScriptExistsCommand {
+    shas: Vec<String>,
+}
+
+impl ScriptExistsCommand {
+    pub fn new<I, S>(shas: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        ScriptExistsCommand { shas: shas.into_iter().map(Into::into).collect() }
+    }
+}
+
+impl Command for ScriptExistsCommand {
+    fn is_verbatim(&self, index: usize) -> bool {
+        index >= 2
+    }
+
+    fn get_parts(&self) -> Vec<&str> {
+        let mut parts = vec!["SCRIPT", "EXISTS"];
+        parts.extend(self.shas.iter().map(String::as_str));
+        parts
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Array
+    }
+}
+
+impl TypedCommand for ScriptExistsCommand {
+    // Whether each script is cached, in the order of the digests
+    type Output = Vec<bool>;
+}
+
+// Empties the script cache of the server
+pub struct ScriptFlushCommand {
+    mode: Option<&'static str>,
+}
+
+impl ScriptFlushCommand {
+    pub fn new() -> Self {
+        ScriptFlushCommand { mode: None }
+    }
+
+    // Frees the scripts in the background (Redis 6.2+)
+    pub fn asynchronous(mut self) -> Self {
+        self.mode = Some("ASYNC");
+        self
+    }
+
+    // Frees the scripts before replying (Redis 6.2+), the default unless lazyfree-lazy-user-flush is set
+    pub fn synchronous(mut self) -> Self {
+        self.mode = Some("SYNC");
+        self
+    }
+}
+
+impl Default for ScriptFlushCommand {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Command for ScriptFlushCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        let mut parts = vec!["SCRIPT", "FLUSH"];
+        parts.extend(self.mode);
+        parts
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Status
+    }
+}
+
+impl TypedCommand for ScriptFlushCommand {
+    type Output = bool;
+}
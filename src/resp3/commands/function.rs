@@ -0,0 +1,240 @@
+use std::collections::HashMap;
+use std::marker::PhantomData;
+
+use crate::resp3::error::Resp3Error;
+use crate::resp3::utils::command::{Command, ReplyKind, TypedCommand};
+use crate::resp3::value::{FromResp3Value, Resp3Value};
+
+const FUNCTION_INDEX: usize = 1;
+const LIBRARY_INDEX: usize = 2;
+
+pub struct FunctionLoadCommand {
+    replace: bool,
+    code: String,
+}
+
+impl FunctionLoadCommand {
+    // The code starts with the #!lua name=<library> shebang and registers the functions
+    pub fn new(code: String) -> Self {
+        FunctionLoadCommand { replace: false, code }
+    }
+
+    // Replaces the library if it is already loaded
+    pub fn replace(mut self) -> Self {
+        self.replace = true;
+        self
+    }
+}
+
+impl Command for FunctionLoadCommand {
+    // Lua is case sensitive, so the code, always the last part, is sent as it is
+    fn is_verbatim(&self, index: usize) -> bool {
+        index == self.get_parts().len() - 1
+    }
+
+    fn get_parts(&self) -> Vec<&str> {
+        let mut parts = vec!["FUNCTION", "LOAD"];
+        if self.replace {
+            parts.push("REPLACE");
+        }
+        parts.push(&self.code);
+        parts
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Bulk
+    }
+}
+
+impl TypedCommand for FunctionLoadCommand {
+    // Name of the loaded library
+    type Output = String;
+}
+
+pub struct FunctionDeleteCommand {
+    library: String,
+}
+
+impl FunctionDeleteCommand {
+    pub fn new(library: String) -> Self {
+        FunctionDeleteCommand { library }
+    }
+}
+
+impl Command for FunctionDeleteCommand {
+    // Library names are sent as they are, as in the shebang they were loaded with
+    fn is_verbatim(&self, index: usize) -> bool {
+        index == LIBRARY_INDEX
+    }
+
+    fn get_parts(&self) -> Vec<&str> {
+        vec!["FUNCTION", "DELETE", &self.library]
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Status
+    }
+}
+
+impl TypedCommand for FunctionDeleteCommand {
+    type Output = bool;
+}
+
+// Deletes every library
+pub struct FunctionFlushCommand;
+
+impl Command for FunctionFlushCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        vec!["FUNCTION", "FLUSH"]
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Status
+    }
+}
+
+impl TypedCommand for FunctionFlushCommand {
+    type Output = bool;
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FunctionInfo {
+    pub name: String,
+    pub description: Option<String>,
+    // e.g. no-writes for the functions that can be called with FCALL_RO
+    pub flags: Vec<String>,
+}
+
+impl FromResp3Value for FunctionInfo {
+    fn from_resp3_value(value: Resp3Value) -> Result<Self, Resp3Error> {
+        let mut fields: HashMap<String, Resp3Value> = HashMap::from_resp3_value(value)?;
+        Ok(FunctionInfo {
+            name: field(&mut fields, "name")?,
+            description: field(&mut fields, "description")?,
+            flags: field(&mut fields, "flags")?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FunctionLibrary {
+    pub name: String,
+    pub engine: String,
+    pub functions: Vec<FunctionInfo>,
+    // Only when listed with with_code
+    pub code: Option<String>,
+}
+
+impl FromResp3Value for FunctionLibrary {
+    fn from_resp3_value(value: Resp3Value) -> Result<Self, Resp3Error> {
+        let mut fields: HashMap<String, Resp3Value> = HashMap::from_resp3_value(value)?;
+        Ok(FunctionLibrary {
+            name: field(&mut fields, "library_name")?,
+            engine: field(&mut fields, "engine")?,
+            functions: field(&mut fields, "functions")?,
+            code: field(&mut fields, "library_code")?,
+        })
+    }
+}
+
+// Missing fields decode as nil, so that optional ones become None
+fn field<T: FromResp3Value>(fields: &mut HashMap<String, Resp3Value>, name: &str) -> Result<T, Resp3Error> {
+    T::from_resp3_value(fields.remove(name).unwrap_or(Resp3Value::Null))
+}
+
+pub struct FunctionListCommand {
+    options: Vec<String>,
+}
+
+impl FunctionListCommand {
+    pub fn new() -> Self {
+        FunctionListCommand { options: Vec::new() }
+    }
+
+    // Lists only the libraries whose name matches the glob-style pattern
+    pub fn library_name(mut self, pattern: String) -> Self {
+        self.options.push("LIBRARYNAME".to_string());
+        self.options.push(pattern);
+        self
+    }
+
+    pub fn with_code(mut self) -> Self {
+        self.options.push("WITHCODE".to_string());
+        self
+    }
+}
+
+impl Default for FunctionListCommand {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Command for FunctionListCommand {
+    // The library name pattern is sent as it is
+    fn is_verbatim(&self, index: usize) -> bool {
+        index > 2 && self.options[index - 3] == "LIBRARYNAME"
+    }
+
+    fn get_parts(&self) -> Vec<&str> {
+        let mut parts = vec!["FUNCTION", "LIST"];
+        parts.extend(self.options.iter().map(String::as_str));
+        parts
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Array
+    }
+}
+
+impl TypedCommand for FunctionListCommand {
+    type Output = Vec<FunctionLibrary>;
+}
+
+// Calls a function of a loaded library, the reply is decoded into T (Resp3Value to
+// inspect it as it is), e.g. FCallCommand::<Vec<(String, i64)>>::new(...)
+pub struct FCallCommand<T = Resp3Value> {
+    name: &'static str,
+    function: String,
+    num_keys: String,
+    keys: Vec<String>,
+    args: Vec<String>,
+    output: PhantomData<T>,
+}
+
+impl<T: FromResp3Value> FCallCommand<T> {
+    pub fn new(function: String, keys: Vec<String>, args: Vec<String>) -> Self {
+        FCallCommand { name: "FCALL", function, num_keys: keys.len().to_string(), keys, args, output: PhantomData }
+    }
+
+    // FCALL_RO, only for the functions flagged no-writes, which can run on replicas
+    pub fn read_only(function: String, keys: Vec<String>, args: Vec<String>) -> Self {
+        FCallCommand { name: "FCALL_RO", ..Self::new(function, keys, args) }
+    }
+}
+
+impl<T> Command for FCallCommand<T> {
+    // Function names are sent as they are, as registered by the library
+    fn is_verbatim(&self, index: usize) -> bool {
+        index == FUNCTION_INDEX
+    }
+
+    fn get_parts(&self) -> Vec<&str> {
+        let mut parts = vec![self.name, &self.function, &self.num_keys];
+        parts.extend(self.keys.iter().map(String::as_str));
+        parts.extend(self.args.iter().map(String::as_str));
+        parts
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        (3..3 + self.keys.len()).collect()
+    }
+
+    fn is_readonly(&self) -> bool {
+        self.name == "FCALL_RO"
+    }
+}
+
+impl<T: FromResp3Value> TypedCommand for FCallCommand<T> {
+    type Output = T;
+}
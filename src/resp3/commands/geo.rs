@@ -0,0 +1,277 @@
+use crate::resp3::error::Resp3Error;
+use crate::resp3::utils::command::{Command, ReplyKind, TypedCommand};
+use crate::resp3::value::{unexpected, FromResp3Value, Resp3Value};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GeoUnit {
+    Meters,
+    Kilometers,
+    Miles,
+    Feet,
+}
+
+impl GeoUnit {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GeoUnit::Meters => "m",
+            GeoUnit::Kilometers => "km",
+            GeoUnit::Miles => "mi",
+            GeoUnit::Feet => "ft",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GeoPosition {
+    pub longitude: f64,
+    pub latitude: f64,
+}
+
+impl FromResp3Value for GeoPosition {
+    fn from_resp3_value(value: Resp3Value) -> Result<Self, Resp3Error> {
+        let (longitude, latitude) = <(f64, f64)>::from_resp3_value(value)?;
+        Ok(GeoPosition { longitude, latitude })
+    }
+}
+
+// A GEOSEARCH match, the optional fields are present when requested with the WITH* options
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeoSearchResult {
+    pub member: String,
+    pub distance: Option<f64>,
+    pub hash: Option<i64>,
+    pub position: Option<GeoPosition>,
+}
+
+impl FromResp3Value for GeoSearchResult {
+    fn from_resp3_value(value: Resp3Value) -> Result<Self, Resp3Error> {
+        match value {
+            Resp3Value::Array(values) if !values.is_empty() => {
+                let mut values = values.into_iter();
+                let mut result = GeoSearchResult {
+                    member: String::from_resp3_value(values.next().unwrap())?,
+                    distance: None,
+                    hash: None,
+                    position: None,
+                };
+                // Redis always sends them in the same order, each with its own reply type
+                for value in values {
+                    match value {
+                        Resp3Value::BulkString(_) => result.distance = Some(f64::from_resp3_value(value)?),
+                        Resp3Value::Integer(hash) => result.hash = Some(hash),
+                        Resp3Value::Array(_) => result.position = Some(GeoPosition::from_resp3_value(value)?),
+                        value => return unexpected("a distance, hash or position", &value),
+                    }
+                }
+                Ok(result)
+            }
+            value => Ok(GeoSearchResult {
+                member: String::from_resp3_value(value)?,
+                distance: None,
+                hash: None,
+                position: None,
+            }),
+        }
+    }
+}
+
+pub struct GeoAddCommand {
+    key: String,
+    locations: Vec<String>,
+}
+
+impl GeoAddCommand {
+    pub fn new(key: String, longitude: f64, latitude: f64, member: String) -> Self {
+        GeoAddCommand { key, locations: Vec::new() }.location(longitude, latitude, member)
+    }
+
+    // Adds one more member to the same command
+    pub fn location(mut self, longitude: f64, latitude: f64, member: String) -> Self {
+        self.locations.push(longitude.to_string());
+        self.locations.push(latitude.to_string());
+        self.locations.push(member);
+        self
+    }
+}
+
+impl Command for GeoAddCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        let mut parts = vec!["GEOADD", &self.key];
+        parts.extend(self.locations.iter().map(String::as_str));
+        parts
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![1]
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Integer
+    }
+}
+
+impl TypedCommand for GeoAddCommand {
+    // Number of members added
+    type Output = i64;
+}
+
+pub struct GeoDistCommand {
+    key: String,
+    member1: String,
+    member2: String,
+    unit: GeoUnit,
+}
+
+impl GeoDistCommand {
+    pub fn new(key: String, member1: String, member2: String, unit: GeoUnit) -> Self {
+        GeoDistCommand { key, member1, member2, unit }
+    }
+}
+
+impl Command for GeoDistCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        vec!["GEODIST", &self.key, &self.member1, &self.member2, self.unit.as_str()]
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![1]
+    }
+
+    fn is_readonly(&self) -> bool {
+        true
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Bulk
+    }
+}
+
+impl TypedCommand for GeoDistCommand {
+    // None when one of the members does not exist
+    type Output = Option<f64>;
+}
+
+pub struct GeoPosCommand {
+    key: String,
+    members: Vec<String>,
+}
+
+impl GeoPosCommand {
+    pub fn new(key: String, members: Vec<String>) -> Self {
+        GeoPosCommand { key, members }
+    }
+}
+
+impl Command for GeoPosCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        let mut parts = vec!["GEOPOS", &self.key];
+        parts.extend(self.members.iter().map(String::as_str));
+        parts
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![1]
+    }
+
+    fn is_readonly(&self) -> bool {
+        true
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Array
+    }
+}
+
+impl TypedCommand for GeoPosCommand {
+    // One position per requested member, None for the missing ones
+    type Output = Vec<Option<GeoPosition>>;
+}
+
+pub struct GeoSearchCommand {
+    key: String,
+    origin: Vec<String>,
+    shape: Vec<String>,
+    options: Vec<String>,
+}
+
+impl GeoSearchCommand {
+    // Searches around the position of an existing member
+    pub fn from_member(key: String, member: String) -> Self {
+        GeoSearchCommand { key, origin: vec!["FROMMEMBER".to_string(), member], shape: Vec::new(), options: Vec::new() }
+    }
+
+    // Searches around the given position
+    pub fn from_position(key: String, longitude: f64, latitude: f64) -> Self {
+        let origin = vec!["FROMLONLAT".to_string(), longitude.to_string(), latitude.to_string()];
+        GeoSearchCommand { key, origin, shape: Vec::new(), options: Vec::new() }
+    }
+
+    pub fn by_radius(mut self, radius: f64, unit: GeoUnit) -> Self {
+        self.shape = vec!["BYRADIUS".to_string(), radius.to_string(), unit.as_str().to_string()];
+        self
+    }
+
+    pub fn by_box(mut self, width: f64, height: f64, unit: GeoUnit) -> Self {
+        self.shape = vec!["BYBOX".to_string(), width.to_string(), height.to_string(), unit.as_str().to_string()];
+        self
+    }
+
+    // Nearest first
+    pub fn ascending(mut self) -> Self {
+        self.options.push("ASC".to_string());
+        self
+    }
+
+    // Farthest first
+    pub fn descending(mut self) -> Self {
+        self.options.push("DESC".to_string());
+        self
+    }
+
+    pub fn count(mut self, count: u64) -> Self {
+        self.options.push("COUNT".to_string());
+        self.options.push(count.to_string());
+        self
+    }
+
+    pub fn with_coord(mut self) -> Self {
+        self.options.push("WITHCOORD".to_string());
+        self
+    }
+
+    pub fn with_dist(mut self) -> Self {
+        self.options.push("WITHDIST".to_string());
+        self
+    }
+
+    pub fn with_hash(mut self) -> Self {
+        self.options.push("WITHHASH".to_string());
+        self
+    }
+}
+
+impl Command for GeoSearchCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        let mut parts = vec!["GEOSEARCH", &self.key];
+        parts.extend(self.origin.iter().map(String::as_str));
+        parts.extend(self.shape.iter().map(String::as_str));
+        parts.extend(self.options.iter().map(String::as_str));
+        parts
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![1]
+    }
+
+    fn is_readonly(&self) -> bool {
+        true
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Array
+    }
+}
+
+impl TypedCommand for GeoSearchCommand {
+    type Output = Vec<GeoSearchResult>;
+}
@@ -1,4 +1,9 @@
-use crate::resp3::utils::command::Command;
+use std::ops::RangeInclusive;
+
+use crate::resp3::commands::options::{Expiry, ToArguments};
+use crate::resp3::error::Resp3Error;
+use crate::resp3::utils::command::{Command, ReplyKind, TypedCommand};
+use crate::resp3::value::{flat_to_map, unexpected, FromResp3Value, Resp3Value};
 
 pub struct GetCommand {
     key: String,
@@ -14,4 +19,292 @@ impl Command for GetCommand {
     fn get_parts(&self) -> Vec<&str> {
         vec!["GET", &self.key]
     }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![1]
+    }
+
+    fn is_readonly(&self) -> bool {
+        true
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Bulk
+    }
+}
+
+impl TypedCommand for GetCommand {
+    // None when the key does not exist, over RESP2 and RESP3 alike
+    type Output = Option<String>;
+}
+
+// GET that also sets or removes the expiration of the key, Redis 6.2+
+pub struct GetExCommand {
+    key: String,
+    expiry: Vec<String>,
+}
+
+impl GetExCommand {
+    pub fn new(key: String) -> Self {
+        GetExCommand { key, expiry: Vec::new() }
+    }
+
+    // Expire the key after the given number of milliseconds
+    pub fn px(self, milliseconds: u64) -> Self {
+        self.expiry(Expiry::Px(milliseconds))
+    }
+
+    // Remove the expiration of the key
+    pub fn persist(self) -> Self {
+        self.expiry(Expiry::Persist)
+    }
+
+    // Replaces the previous expiry, if any. KeepTtl is the default, GETEX leaves the expiration
+    // of the key alone unless told otherwise
+    pub fn expiry(mut self, expiry: Expiry) -> Self {
+        self.expiry = match expiry {
+            Expiry::KeepTtl => Vec::new(),
+            expiry => expiry.to_arguments(),
+        };
+        self
+    }
+}
+
+impl Command for GetExCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        let mut parts = vec!["GETEX", &self.key];
+        parts.extend(self.expiry.iter().map(String::as_str));
+        parts
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![1]
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Bulk
+    }
+}
+
+impl TypedCommand for GetExCommand {
+    type Output = Option<String>;
+}
+
+// GET of several keys at once. In a cluster all the keys must hash to the same slot, see
+// ClusterConnections::mget_clustered for the others
+pub struct MGetCommand {
+    keys: Vec<String>,
+}
+
+impl MGetCommand {
+    pub fn new<I, S>(keys: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        MGetCommand { keys: keys.into_iter().map(Into::into).collect() }
+    }
+}
+
+impl Command for MGetCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        let mut parts = vec!["MGET"];
+        parts.extend(self.keys.iter().map(String::as_str));
+        parts
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        (1..=self.keys.len()).collect()
+    }
+
+    fn is_readonly(&self) -> bool {
+        true
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Array
+    }
+}
+
+impl TypedCommand for MGetCommand {
+    // In the order of the keys, None for the missing ones
+    type Output = Vec<Option<String>>;
+}
+
+pub struct LcsCommand {
+    keys: [String; 2],
+}
+
+impl LcsCommand {
+    // The longest common subsequence of the strings of the two keys, missing keys counting as
+    // empty strings (Redis 7.0+)
+    pub fn new(key1: String, key2: String) -> Self {
+        LcsCommand { keys: [key1, key2] }
+    }
+}
+
+impl Command for LcsCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        vec!["LCS", &self.keys[0], &self.keys[1]]
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![1, 2]
+    }
+
+    fn is_readonly(&self) -> bool {
+        true
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Bulk
+    }
+}
+
+impl TypedCommand for LcsCommand {
+    type Output = String;
+}
+
+pub struct LcsLenCommand {
+    lcs: LcsCommand,
+}
+
+impl LcsLenCommand {
+    // Only the length of the longest common subsequence
+    pub fn new(key1: String, key2: String) -> Self {
+        LcsLenCommand { lcs: LcsCommand::new(key1, key2) }
+    }
+}
+
+impl Command for LcsLenCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        let mut parts = self.lcs.get_parts();
+        parts.push("LEN");
+        parts
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![1, 2]
+    }
+
+    fn is_readonly(&self) -> bool {
+        true
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Integer
+    }
+}
+
+impl TypedCommand for LcsLenCommand {
+    type Output = u64;
+}
+
+// A run of bytes both strings share, as byte offsets into each of them, both ends included
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LcsMatch {
+    pub first: RangeInclusive<u64>,
+    pub second: RangeInclusive<u64>,
+    // Only with LcsIdxCommand::with_match_len
+    pub len: Option<u64>,
+}
+
+// Each match is [[start, end], [start, end]] with the length last when asked for
+impl FromResp3Value for LcsMatch {
+    fn from_resp3_value(value: Resp3Value) -> Result<Self, Resp3Error> {
+        let range = |value: Resp3Value| -> Result<RangeInclusive<u64>, Resp3Error> {
+            let (start, end) = <(u64, u64)>::from_resp3_value(value)?;
+            Ok(start..=end)
+        };
+        match value {
+            Resp3Value::Array(values) if values.len() == 2 || values.len() == 3 => {
+                let mut values = values.into_iter();
+                let first = range(values.next().unwrap())?;
+                let second = range(values.next().unwrap())?;
+                let len = values.next().map(u64::from_resp3_value).transpose()?;
+                Ok(LcsMatch { first, second, len })
+            }
+            value => unexpected("a match of two ranges", &value),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LcsMatches {
+    // From the end of the strings to their start, the order Redis finds them in
+    pub matches: Vec<LcsMatch>,
+    // The length of the whole subsequence, the matches shorter than the minimum included
+    pub len: u64,
+}
+
+impl FromResp3Value for LcsMatches {
+    fn from_resp3_value(value: Resp3Value) -> Result<Self, Resp3Error> {
+        let map = match value {
+            Resp3Value::Map(map) => map,
+            value => return unexpected("a map of matches and length", &value),
+        };
+        let matches = map.get_as("matches")?.ok_or_else(|| Resp3Error::UnexpectedReply("Expected the matches of LCS".to_string()))?;
+        let len = map.get_as("len")?.ok_or_else(|| Resp3Error::UnexpectedReply("Expected the length of LCS".to_string()))?;
+        Ok(LcsMatches { matches, len })
+    }
+}
+
+pub struct LcsIdxCommand {
+    lcs: LcsCommand,
+    min_match_len: Option<String>,
+    with_match_len: bool,
+}
+
+impl LcsIdxCommand {
+    // Where the common subsequence is in each string, e.g. to highlight the differences of two
+    // versions of a text
+    pub fn new(key1: String, key2: String) -> Self {
+        LcsIdxCommand { lcs: LcsCommand::new(key1, key2), min_match_len: None, with_match_len: false }
+    }
+
+    // Leaves out the matches shorter than this
+    pub fn min_match_len(mut self, len: u64) -> Self {
+        self.min_match_len = Some(len.to_string());
+        self
+    }
+
+    // Adds the length of each match
+    pub fn with_match_len(mut self) -> Self {
+        self.with_match_len = true;
+        self
+    }
+}
+
+impl Command for LcsIdxCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        let mut parts = self.lcs.get_parts();
+        parts.push("IDX");
+        if let Some(len) = &self.min_match_len {
+            parts.extend(["MINMATCHLEN", len]);
+        }
+        if self.with_match_len {
+            parts.push("WITHMATCHLEN");
+        }
+        parts
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![1, 2]
+    }
+
+    fn is_readonly(&self) -> bool {
+        true
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Array
+    }
+
+    fn normalize_resp2(&self, reply: Resp3Value) -> Resp3Value {
+        flat_to_map(reply)
+    }
+}
+
+impl TypedCommand for LcsIdxCommand {
+    type Output = LcsMatches;
 }
@@ -0,0 +1,429 @@
+use std::time::Duration;
+
+use crate::resp3::commands::options::{ExpireCondition, Sample, ToArguments};
+use crate::resp3::error::Resp3Error;
+use crate::resp3::utils::command::{Command, ReplyKind, TypedCommand};
+use crate::resp3::utils::redis_connection::RedisConnection;
+use crate::resp3::value::{nested_pairs, unexpected, FromResp3Value, Resp3Value};
+
+// What HEXPIRE and HPEXPIRE did to one of the fields
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldExpiration {
+    Set,
+    // The field or the key does not exist
+    NoField,
+    // The condition of the command was not met
+    NotSet,
+    // The expiration was in the past, so the field was deleted
+    Deleted,
+}
+
+impl FromResp3Value for FieldExpiration {
+    fn from_resp3_value(value: Resp3Value) -> Result<Self, Resp3Error> {
+        match value {
+            Resp3Value::Integer(1) => Ok(FieldExpiration::Set),
+            Resp3Value::Integer(-2) => Ok(FieldExpiration::NoField),
+            Resp3Value::Integer(0) => Ok(FieldExpiration::NotSet),
+            Resp3Value::Integer(2) => Ok(FieldExpiration::Deleted),
+            value => unexpected("a field expiration result", &value),
+        }
+    }
+}
+
+// The time to live of a field reported by HTTL and HPTTL, in place of their -2 and -1
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FieldTtl {
+    // The field or the key does not exist
+    NoField,
+    NoExpiry,
+    Expires(Duration),
+}
+
+// In seconds like HTTL replies, HPTtlCommand converts its milliseconds itself
+impl FromResp3Value for FieldTtl {
+    fn from_resp3_value(value: Resp3Value) -> Result<Self, Resp3Error> {
+        field_ttl(value, Duration::from_secs)
+    }
+}
+
+fn field_ttl(reply: Resp3Value, unit: fn(u64) -> Duration) -> Result<FieldTtl, Resp3Error> {
+    match reply {
+        Resp3Value::Integer(-2) => Ok(FieldTtl::NoField),
+        Resp3Value::Integer(-1) => Ok(FieldTtl::NoExpiry),
+        Resp3Value::Integer(ttl) if ttl >= 0 => Ok(FieldTtl::Expires(unit(ttl as u64))),
+        reply => unexpected("a field time to live", &reply),
+    }
+}
+
+// The "FIELDS <count> <field>..." arguments ending the hash field expiration commands
+fn fields_arguments(fields: impl IntoIterator<Item = impl Into<String>>) -> Vec<String> {
+    let fields: Vec<String> = fields.into_iter().map(Into::into).collect();
+    let mut arguments = vec!["FIELDS".to_string(), fields.len().to_string()];
+    arguments.extend(fields);
+    arguments
+}
+
+// The hash field expiration commands need Redis 7.4+, older servers fail with
+// UnsupportedCommand before anything is sent
+fn query_supported<C: TypedCommand + ?Sized>(command: &C, conn: &mut RedisConnection) -> Result<C::Output, Resp3Error> {
+    let name = command.get_parts().first().copied().unwrap_or_default();
+    conn.require(name)?;
+    let reply = command.execute(conn)?;
+    command.parse_output(reply)
+}
+
+pub struct HExpireCommand {
+    name: &'static str,
+    key: String,
+    time: String,
+    condition: Option<ExpireCondition>,
+    fields: Vec<String>,
+}
+
+impl HExpireCommand {
+    // The fields expire after this many seconds, the other fields of the hash stay (Redis 7.4+)
+    pub fn new(key: String, seconds: u64, fields: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        HExpireCommand { name: "HEXPIRE", key, time: seconds.to_string(), condition: None, fields: fields_arguments(fields) }
+    }
+
+    pub fn condition(mut self, condition: ExpireCondition) -> Self {
+        self.condition = Some(condition);
+        self
+    }
+}
+
+impl Command for HExpireCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        let mut parts = vec![self.name, &self.key, &self.time];
+        parts.extend(self.condition.map(|condition| condition.as_str()));
+        parts.extend(self.fields.iter().map(String::as_str));
+        parts
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![1]
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Array
+    }
+}
+
+impl TypedCommand for HExpireCommand {
+    // One for each field, in the order they were given
+    type Output = Vec<FieldExpiration>;
+
+    fn query(&self, conn: &mut RedisConnection) -> Result<Self::Output, Resp3Error> {
+        query_supported(self, conn)
+    }
+}
+
+pub struct HPExpireCommand {
+    expire: HExpireCommand,
+}
+
+impl HPExpireCommand {
+    // HEXPIRE in milliseconds
+    pub fn new(key: String, milliseconds: u64, fields: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        HPExpireCommand { expire: HExpireCommand { name: "HPEXPIRE", ..HExpireCommand::new(key, milliseconds, fields) } }
+    }
+
+    pub fn condition(mut self, condition: ExpireCondition) -> Self {
+        self.expire = self.expire.condition(condition);
+        self
+    }
+}
+
+impl Command for HPExpireCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        self.expire.get_parts()
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![1]
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Array
+    }
+}
+
+impl TypedCommand for HPExpireCommand {
+    type Output = Vec<FieldExpiration>;
+
+    fn query(&self, conn: &mut RedisConnection) -> Result<Self::Output, Resp3Error> {
+        query_supported(self, conn)
+    }
+}
+
+pub struct HTtlCommand {
+    name: &'static str,
+    key: String,
+    fields: Vec<String>,
+}
+
+impl HTtlCommand {
+    // Rounded to the second, HPTtlCommand has the milliseconds (Redis 7.4+)
+    pub fn new(key: String, fields: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        HTtlCommand { name: "HTTL", key, fields: fields_arguments(fields) }
+    }
+}
+
+impl Command for HTtlCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        let mut parts = vec![self.name, &self.key];
+        parts.extend(self.fields.iter().map(String::as_str));
+        parts
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![1]
+    }
+
+    fn is_readonly(&self) -> bool {
+        true
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Array
+    }
+}
+
+impl TypedCommand for HTtlCommand {
+    // One for each field, in the order they were given
+    type Output = Vec<FieldTtl>;
+
+    fn query(&self, conn: &mut RedisConnection) -> Result<Self::Output, Resp3Error> {
+        query_supported(self, conn)
+    }
+}
+
+pub struct HPTtlCommand {
+    ttl: HTtlCommand,
+}
+
+impl HPTtlCommand {
+    pub fn new(key: String, fields: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        HPTtlCommand { ttl: HTtlCommand { name: "HPTTL", ..HTtlCommand::new(key, fields) } }
+    }
+}
+
+impl Command for HPTtlCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        self.ttl.get_parts()
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![1]
+    }
+
+    fn is_readonly(&self) -> bool {
+        true
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Array
+    }
+}
+
+impl TypedCommand for HPTtlCommand {
+    type Output = Vec<FieldTtl>;
+
+    fn query(&self, conn: &mut RedisConnection) -> Result<Self::Output, Resp3Error> {
+        query_supported(self, conn)
+    }
+
+    fn parse_output(&self, reply: Resp3Value) -> Result<Self::Output, Resp3Error> {
+        match reply {
+            Resp3Value::Array(values) => values.into_iter().map(|value| field_ttl(value, Duration::from_millis)).collect(),
+            reply => unexpected("an array", &reply),
+        }
+    }
+}
+
+pub struct HRandFieldCommand {
+    key: String,
+}
+
+impl HRandFieldCommand {
+    // One field picked at random, None when the key does not exist (Redis 6.2+)
+    pub fn new(key: String) -> Self {
+        HRandFieldCommand { key }
+    }
+}
+
+impl Command for HRandFieldCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        vec!["HRANDFIELD", &self.key]
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![1]
+    }
+
+    fn is_readonly(&self) -> bool {
+        true
+    }
+
+    fn is_deterministic(&self) -> bool {
+        false
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Bulk
+    }
+}
+
+impl TypedCommand for HRandFieldCommand {
+    type Output = Option<String>;
+}
+
+pub struct HRandFieldsCommand {
+    key: String,
+    count: Vec<String>,
+}
+
+impl HRandFieldsCommand {
+    // Empty when the key does not exist
+    pub fn new(key: String, sample: Sample) -> Self {
+        HRandFieldsCommand { key, count: sample.to_arguments() }
+    }
+}
+
+impl Command for HRandFieldsCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        let mut parts = vec!["HRANDFIELD", &self.key];
+        parts.extend(self.count.iter().map(String::as_str));
+        parts
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![1]
+    }
+
+    fn is_readonly(&self) -> bool {
+        true
+    }
+
+    fn is_deterministic(&self) -> bool {
+        false
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Array
+    }
+}
+
+impl TypedCommand for HRandFieldsCommand {
+    type Output = Vec<String>;
+}
+
+pub struct HRandFieldsWithValuesCommand {
+    sample: HRandFieldsCommand,
+}
+
+impl HRandFieldsWithValuesCommand {
+    // The fields with their values, RESP3 sends a [field, value] array per field
+    pub fn new(key: String, sample: Sample) -> Self {
+        HRandFieldsWithValuesCommand { sample: HRandFieldsCommand::new(key, sample) }
+    }
+}
+
+impl Command for HRandFieldsWithValuesCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        let mut parts = self.sample.get_parts();
+        parts.push("WITHVALUES");
+        parts
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![1]
+    }
+
+    fn is_readonly(&self) -> bool {
+        true
+    }
+
+    fn is_deterministic(&self) -> bool {
+        false
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Array
+    }
+}
+
+impl TypedCommand for HRandFieldsWithValuesCommand {
+    type Output = Vec<(String, String)>;
+
+    fn parse_output(&self, reply: Resp3Value) -> Result<Self::Output, Resp3Error> {
+        nested_pairs(reply)
+    }
+}
+
+pub struct HGetCommand {
+    key: String,
+    field: String,
+}
+
+impl HGetCommand {
+    // None when the field or the key does not exist
+    pub fn new(key: String, field: String) -> Self {
+        HGetCommand { key, field }
+    }
+}
+
+impl Command for HGetCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        vec!["HGET", &self.key, &self.field]
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![1]
+    }
+
+    fn is_readonly(&self) -> bool {
+        true
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Bulk
+    }
+}
+
+impl TypedCommand for HGetCommand {
+    type Output = Option<String>;
+}
+
+pub struct HIncrByCommand {
+    key: String,
+    field: String,
+    increment: String,
+}
+
+impl HIncrByCommand {
+    // Adds the increment, possibly negative, to the integer stored in the field, a missing
+    // field counts as 0
+    pub fn new(key: String, field: String, increment: i64) -> Self {
+        HIncrByCommand { key, field, increment: increment.to_string() }
+    }
+}
+
+impl Command for HIncrByCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        vec!["HINCRBY", &self.key, &self.field, &self.increment]
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![1]
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Integer
+    }
+}
+
+impl TypedCommand for HIncrByCommand {
+    // The value after the increment
+    type Output = i64;
+}
@@ -0,0 +1,101 @@
+use crate::resp3::utils::command::{Command, ReplyKind, TypedCommand};
+
+pub struct PfAddCommand {
+    key: String,
+    elements: Vec<String>,
+}
+
+impl PfAddCommand {
+    pub fn new(key: String, elements: Vec<String>) -> Self {
+        PfAddCommand { key, elements }
+    }
+}
+
+impl Command for PfAddCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        let mut parts = vec!["PFADD", &self.key];
+        parts.extend(self.elements.iter().map(String::as_str));
+        parts
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![1]
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Integer
+    }
+}
+
+impl TypedCommand for PfAddCommand {
+    // Whether the estimated cardinality changed
+    type Output = bool;
+}
+
+pub struct PfCountCommand {
+    keys: Vec<String>,
+}
+
+impl PfCountCommand {
+    // With more than one key the count is of the union of the HyperLogLogs
+    pub fn new(keys: Vec<String>) -> Self {
+        PfCountCommand { keys }
+    }
+}
+
+impl Command for PfCountCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        let mut parts = vec!["PFCOUNT"];
+        parts.extend(self.keys.iter().map(String::as_str));
+        parts
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        (1..1 + self.keys.len()).collect()
+    }
+
+    fn is_readonly(&self) -> bool {
+        true
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Integer
+    }
+}
+
+impl TypedCommand for PfCountCommand {
+    // Approximated number of unique elements
+    type Output = u64;
+}
+
+pub struct PfMergeCommand {
+    destination: String,
+    sources: Vec<String>,
+}
+
+impl PfMergeCommand {
+    pub fn new(destination: String, sources: Vec<String>) -> Self {
+        PfMergeCommand { destination, sources }
+    }
+}
+
+impl Command for PfMergeCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        let mut parts = vec!["PFMERGE", &self.destination];
+        parts.extend(self.sources.iter().map(String::as_str));
+        parts
+    }
+
+    // The destination and the source keys
+    fn key_indices(&self) -> Vec<usize> {
+        (1..2 + self.sources.len()).collect()
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Status
+    }
+}
+
+impl TypedCommand for PfMergeCommand {
+    type Output = bool;
+}
@@ -0,0 +1,63 @@
+use crate::resp3::utils::command::{Command, ReplyKind, TypedCommand};
+use crate::resp3::value::format_double;
+
+pub struct IncrCommand {
+    key: String,
+}
+
+impl IncrCommand {
+    // Increments the integer stored at the key, a missing key counts as 0
+    pub fn new(key: String) -> Self {
+        IncrCommand { key }
+    }
+}
+
+impl Command for IncrCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        vec!["INCR", &self.key]
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![1]
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Integer
+    }
+}
+
+impl TypedCommand for IncrCommand {
+    // The value after the increment
+    type Output = i64;
+}
+
+pub struct IncrByFloatCommand {
+    key: String,
+    increment: String,
+}
+
+impl IncrByFloatCommand {
+    // Adds the increment, possibly negative, to the number stored at the key
+    pub fn new(key: String, increment: f64) -> Self {
+        IncrByFloatCommand { key, increment: format_double(increment) }
+    }
+}
+
+impl Command for IncrByFloatCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        vec!["INCRBYFLOAT", &self.key, &self.increment]
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![1]
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Bulk
+    }
+}
+
+impl TypedCommand for IncrByFloatCommand {
+    // The value after the increment, sent as a string over RESP2 and as a double over RESP3
+    type Output = f64;
+}
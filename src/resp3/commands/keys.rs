@@ -0,0 +1,694 @@
+use std::time::Duration;
+
+use crate::resp3::commands::options::SetCondition;
+use crate::resp3::error::Resp3Error;
+use crate::resp3::utils::command::{text_parts, Command, ReplyKind, TypedCommand};
+use crate::resp3::value::{unexpected, FromResp3Value, Resp3Value};
+
+const PAYLOAD_INDEX: usize = 3;
+
+pub struct CopyCommand {
+    source: String,
+    destination: String,
+    db: Vec<String>,
+    replace: bool,
+}
+
+impl CopyCommand {
+    pub fn new(source: String, destination: String) -> Self {
+        CopyCommand { source, destination, db: Vec::new(), replace: false }
+    }
+
+    // Copies into another logical database of the same instance, replaces the previous one if any
+    pub fn db(mut self, db: u32) -> Self {
+        self.db = vec!["DB".to_string(), db.to_string()];
+        self
+    }
+
+    // Overwrites the destination if it already exists
+    pub fn replace(mut self) -> Self {
+        self.replace = true;
+        self
+    }
+
+    // Nx is the default, Xx is replace. COPY has no way to only copy over an existing destination,
+    // so Xx also copies when there is none
+    pub fn condition(mut self, condition: SetCondition) -> Self {
+        self.replace = condition == SetCondition::Xx;
+        self
+    }
+}
+
+impl Command for CopyCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        let mut parts = vec!["COPY", &self.source, &self.destination];
+        parts.extend(self.db.iter().map(String::as_str));
+        if self.replace {
+            parts.push("REPLACE");
+        }
+        parts
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![1, 2]
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Integer
+    }
+}
+
+impl TypedCommand for CopyCommand {
+    // False when the source does not exist or the destination does without REPLACE
+    type Output = bool;
+}
+
+pub struct DumpCommand {
+    key: String,
+}
+
+impl DumpCommand {
+    pub fn new(key: String) -> Self {
+        DumpCommand { key }
+    }
+}
+
+impl Command for DumpCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        vec!["DUMP", &self.key]
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![1]
+    }
+
+    fn is_readonly(&self) -> bool {
+        true
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Bulk
+    }
+}
+
+impl TypedCommand for DumpCommand {
+    // The serialized value, None when the key does not exist
+    type Output = Option<Vec<u8>>;
+}
+
+pub struct RestoreCommand {
+    key: String,
+    ttl: String,
+    payload: Vec<u8>,
+    options: Vec<String>,
+}
+
+impl RestoreCommand {
+    // A ttl of 0 creates the key without expiration
+    pub fn new(key: String, ttl_millis: u64, payload: Vec<u8>) -> Self {
+        RestoreCommand { key, ttl: ttl_millis.to_string(), payload, options: Vec::new() }
+    }
+
+    // Overwrites the key if it already exists
+    pub fn replace(mut self) -> Self {
+        self.options.push("REPLACE".to_string());
+        self
+    }
+
+    // The ttl is an absolute Unix time in milliseconds
+    pub fn absttl(mut self) -> Self {
+        self.options.push("ABSTTL".to_string());
+        self
+    }
+}
+
+impl Command for RestoreCommand {
+    // The payload is binary and must reach Redis untouched
+    fn is_verbatim(&self, index: usize) -> bool {
+        index == PAYLOAD_INDEX
+    }
+
+    // The payload is only sent through get_binary_parts
+    fn get_parts(&self) -> Vec<&str> {
+        let mut parts = vec!["RESTORE", &self.key, &self.ttl];
+        parts.extend(self.options.iter().map(String::as_str));
+        parts
+    }
+
+    fn get_binary_parts(&self) -> Vec<&[u8]> {
+        let mut parts = vec!["RESTORE".as_bytes(), self.key.as_bytes(), self.ttl.as_bytes(), &self.payload];
+        parts.extend(self.options.iter().map(String::as_bytes));
+        parts
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![1]
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Status
+    }
+}
+
+impl TypedCommand for RestoreCommand {
+    type Output = bool;
+}
+
+// The expiration reported by EXPIRETIME and PEXPIRETIME
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum KeyExpiration {
+    Missing,
+    Persistent,
+    // Unix time in seconds for EXPIRETIME, in milliseconds for PEXPIRETIME
+    At(u64),
+}
+
+impl FromResp3Value for KeyExpiration {
+    fn from_resp3_value(value: Resp3Value) -> Result<Self, Resp3Error> {
+        match value {
+            Resp3Value::Integer(-2) => Ok(KeyExpiration::Missing),
+            Resp3Value::Integer(-1) => Ok(KeyExpiration::Persistent),
+            Resp3Value::Integer(time) if time >= 0 => Ok(KeyExpiration::At(time as u64)),
+            value => unexpected("an expiration time", &value),
+        }
+    }
+}
+
+// The time to live reported by TTL and PTTL, in place of their -2 and -1
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum KeyTtl {
+    NoKey,
+    NoExpiry,
+    Expires(Duration),
+}
+
+impl KeyTtl {
+    // None unless the key exists and expires
+    pub fn as_duration(&self) -> Option<Duration> {
+        match self {
+            KeyTtl::Expires(ttl) => Some(*ttl),
+            _ => None,
+        }
+    }
+}
+
+// In seconds like TTL replies, PTtlCommand converts its milliseconds itself
+impl FromResp3Value for KeyTtl {
+    fn from_resp3_value(value: Resp3Value) -> Result<Self, Resp3Error> {
+        key_ttl(value, Duration::from_secs)
+    }
+}
+
+fn key_ttl(reply: Resp3Value, unit: fn(u64) -> Duration) -> Result<KeyTtl, Resp3Error> {
+    match reply {
+        Resp3Value::Integer(-2) => Ok(KeyTtl::NoKey),
+        Resp3Value::Integer(-1) => Ok(KeyTtl::NoExpiry),
+        Resp3Value::Integer(ttl) if ttl >= 0 => Ok(KeyTtl::Expires(unit(ttl as u64))),
+        reply => unexpected("a time to live", &reply),
+    }
+}
+
+pub struct TtlCommand {
+    key: String,
+}
+
+impl TtlCommand {
+    // Rounded to the second, PTtlCommand has the milliseconds
+    pub fn new(key: String) -> Self {
+        TtlCommand { key }
+    }
+}
+
+impl Command for TtlCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        vec!["TTL", &self.key]
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![1]
+    }
+
+    fn is_readonly(&self) -> bool {
+        true
+    }
+
+    fn is_deterministic(&self) -> bool {
+        false
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Integer
+    }
+}
+
+impl TypedCommand for TtlCommand {
+    type Output = KeyTtl;
+}
+
+pub struct PTtlCommand {
+    key: String,
+}
+
+impl PTtlCommand {
+    pub fn new(key: String) -> Self {
+        PTtlCommand { key }
+    }
+}
+
+impl Command for PTtlCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        vec!["PTTL", &self.key]
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![1]
+    }
+
+    fn is_readonly(&self) -> bool {
+        true
+    }
+
+    fn is_deterministic(&self) -> bool {
+        false
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Integer
+    }
+}
+
+impl TypedCommand for PTtlCommand {
+    type Output = KeyTtl;
+
+    fn parse_output(&self, reply: Resp3Value) -> Result<KeyTtl, Resp3Error> {
+        key_ttl(reply, Duration::from_millis)
+    }
+}
+
+pub struct PersistCommand {
+    key: String,
+}
+
+impl PersistCommand {
+    pub fn new(key: String) -> Self {
+        PersistCommand { key }
+    }
+}
+
+impl Command for PersistCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        vec!["PERSIST", &self.key]
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![1]
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Integer
+    }
+}
+
+impl TypedCommand for PersistCommand {
+    // False when the key does not exist or has no expiration
+    type Output = bool;
+}
+
+pub struct ExpireTimeCommand {
+    key: String,
+}
+
+impl ExpireTimeCommand {
+    // Redis 7.0+
+    pub fn new(key: String) -> Self {
+        ExpireTimeCommand { key }
+    }
+}
+
+impl Command for ExpireTimeCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        vec!["EXPIRETIME", &self.key]
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![1]
+    }
+
+    fn is_readonly(&self) -> bool {
+        true
+    }
+
+    fn is_deterministic(&self) -> bool {
+        false
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Integer
+    }
+}
+
+impl TypedCommand for ExpireTimeCommand {
+    type Output = KeyExpiration;
+}
+
+pub struct PExpireTimeCommand {
+    key: String,
+}
+
+impl PExpireTimeCommand {
+    // Redis 7.0+
+    pub fn new(key: String) -> Self {
+        PExpireTimeCommand { key }
+    }
+}
+
+impl Command for PExpireTimeCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        vec!["PEXPIRETIME", &self.key]
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![1]
+    }
+
+    fn is_readonly(&self) -> bool {
+        true
+    }
+
+    fn is_deterministic(&self) -> bool {
+        false
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Integer
+    }
+}
+
+impl TypedCommand for PExpireTimeCommand {
+    type Output = KeyExpiration;
+}
+
+pub struct PExpireCommand {
+    key: String,
+    milliseconds: String,
+}
+
+impl PExpireCommand {
+    pub fn new(key: String, milliseconds: u64) -> Self {
+        PExpireCommand { key, milliseconds: milliseconds.to_string() }
+    }
+}
+
+impl Command for PExpireCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        vec!["PEXPIRE", &self.key, &self.milliseconds]
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![1]
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Integer
+    }
+}
+
+impl TypedCommand for PExpireCommand {
+    // False when the key does not exist
+    type Output = bool;
+}
+
+pub struct ObjectFreqCommand {
+    key: String,
+}
+
+impl ObjectFreqCommand {
+    // Only available with an LFU maxmemory-policy
+    pub fn new(key: String) -> Self {
+        ObjectFreqCommand { key }
+    }
+}
+
+impl Command for ObjectFreqCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        vec!["OBJECT", "FREQ", &self.key]
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![2]
+    }
+
+    fn is_readonly(&self) -> bool {
+        true
+    }
+
+    fn is_deterministic(&self) -> bool {
+        false
+    }
+}
+
+impl TypedCommand for ObjectFreqCommand {
+    // Logarithmic access frequency counter, None when the key does not exist
+    type Output = Option<u64>;
+}
+
+pub struct DelCommand {
+    keys: Vec<Vec<u8>>,
+}
+
+impl DelCommand {
+    // Binary-safe, e.g. DelCommand::new(["a", "b"]) or DelCommand::new(keys_as_bytes)
+    pub fn new(keys: impl IntoIterator<Item = impl Into<Vec<u8>>>) -> Self {
+        DelCommand { keys: keys.into_iter().map(Into::into).collect() }
+    }
+
+    // Adds one more key to the same command
+    pub fn key(mut self, key: impl Into<Vec<u8>>) -> Self {
+        self.keys.push(key.into());
+        self
+    }
+}
+
+impl Command for DelCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        text_parts(&self.get_binary_parts())
+    }
+
+    fn get_binary_parts(&self) -> Vec<&[u8]> {
+        let mut parts = vec!["DEL".as_bytes()];
+        parts.extend(self.keys.iter().map(Vec::as_slice));
+        parts
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        (1..1 + self.keys.len()).collect()
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Integer
+    }
+}
+
+impl TypedCommand for DelCommand {
+    // Number of keys deleted
+    type Output = u64;
+}
+
+pub struct KeysCommand {
+    pattern: String,
+}
+
+impl KeysCommand {
+    // Blocks the server while it walks the whole keyspace, prefer ScanCommand in production
+    pub fn new(pattern: String) -> Self {
+        KeysCommand { pattern }
+    }
+}
+
+impl Command for KeysCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        vec!["KEYS", &self.pattern]
+    }
+
+    fn pattern_indices(&self) -> Vec<usize> {
+        vec![1]
+    }
+
+    fn is_readonly(&self) -> bool {
+        true
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Array
+    }
+}
+
+impl TypedCommand for KeysCommand {
+    type Output = Vec<String>;
+}
+
+pub struct ScanCommand {
+    cursor: String,
+    pattern: Option<String>,
+    options: Vec<String>,
+}
+
+impl ScanCommand {
+    // Starts with cursor 0 and continues with the returned cursor until it is 0 again
+    pub fn new(cursor: u64) -> Self {
+        ScanCommand { cursor: cursor.to_string(), pattern: None, options: Vec::new() }
+    }
+
+    pub fn pattern(mut self, pattern: String) -> Self {
+        self.pattern = Some(pattern);
+        self
+    }
+
+    // A hint of how many keys to walk per call
+    pub fn count(mut self, count: u64) -> Self {
+        self.options.push("COUNT".to_string());
+        self.options.push(count.to_string());
+        self
+    }
+
+    // Only returns keys of the given type, e.g. "zset"
+    pub fn key_type(mut self, key_type: String) -> Self {
+        self.options.push("TYPE".to_string());
+        self.options.push(key_type);
+        self
+    }
+}
+
+impl Command for ScanCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        let mut parts = vec!["SCAN", &self.cursor];
+        if let Some(pattern) = &self.pattern {
+            parts.extend(["MATCH", pattern]);
+        }
+        parts.extend(self.options.iter().map(String::as_str));
+        parts
+    }
+
+    fn pattern_indices(&self) -> Vec<usize> {
+        match self.pattern {
+            Some(_) => vec![3],
+            None => Vec::new(),
+        }
+    }
+
+    fn pattern_option(&self) -> Option<&str> {
+        match self.pattern {
+            Some(_) => None,
+            None => Some("MATCH"),
+        }
+    }
+
+    fn is_readonly(&self) -> bool {
+        true
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Array
+    }
+}
+
+impl TypedCommand for ScanCommand {
+    // The next cursor and a batch of keys, possibly empty
+    type Output = (u64, Vec<String>);
+}
+
+pub struct TypeCommand {
+    key: String,
+    verbatim_key: bool,
+}
+
+impl TypeCommand {
+    pub fn new(key: String) -> Self {
+        TypeCommand { key, verbatim_key: false }
+    }
+
+    // Sends the key as given instead of in uppercase, e.g. for keys returned by SCAN
+    pub fn verbatim_key(mut self) -> Self {
+        self.verbatim_key = true;
+        self
+    }
+}
+
+impl Command for TypeCommand {
+    fn is_verbatim(&self, index: usize) -> bool {
+        self.verbatim_key && index == 1
+    }
+
+    fn get_parts(&self) -> Vec<&str> {
+        vec!["TYPE", &self.key]
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![1]
+    }
+
+    fn is_readonly(&self) -> bool {
+        true
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Status
+    }
+}
+
+impl TypedCommand for TypeCommand {
+    // e.g. "string" or "zset", "none" when the key does not exist
+    type Output = String;
+}
+
+pub struct MemoryUsageCommand {
+    key: String,
+    samples: Option<String>,
+    verbatim_key: bool,
+}
+
+impl MemoryUsageCommand {
+    pub fn new(key: String) -> Self {
+        MemoryUsageCommand { key, samples: None, verbatim_key: false }
+    }
+
+    // Number of nested values sampled to estimate the size of aggregates, 0 for all of them.
+    // The server default is 5
+    pub fn samples(mut self, samples: u64) -> Self {
+        self.samples = Some(samples.to_string());
+        self
+    }
+
+    // Sends the key as given instead of in uppercase, e.g. for keys returned by SCAN
+    pub fn verbatim_key(mut self) -> Self {
+        self.verbatim_key = true;
+        self
+    }
+}
+
+impl Command for MemoryUsageCommand {
+    fn is_verbatim(&self, index: usize) -> bool {
+        self.verbatim_key && index == 2
+    }
+
+    fn get_parts(&self) -> Vec<&str> {
+        let mut parts = vec!["MEMORY", "USAGE", &self.key];
+        if let Some(samples) = &self.samples {
+            parts.extend(["SAMPLES", samples.as_str()]);
+        }
+        parts
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![2]
+    }
+
+    fn is_readonly(&self) -> bool {
+        true
+    }
+}
+
+impl TypedCommand for MemoryUsageCommand {
+    // Bytes used by the key and its value, None when the key does not exist
+    type Output = Option<u64>;
+}
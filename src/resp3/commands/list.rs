@@ -0,0 +1,424 @@
+use std::time::Duration;
+
+use crate::resp3::error::Resp3Error;
+use crate::resp3::utils::command::{text_parts, Command, ReplyKind, TypedCommand};
+use crate::resp3::value::{FromResp3Value, Resp3Value};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ListEnd {
+    Left,
+    Right,
+}
+
+impl ListEnd {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ListEnd::Left => "LEFT",
+            ListEnd::Right => "RIGHT",
+        }
+    }
+}
+
+pub struct LPosCommand {
+    key: String,
+    element: String,
+    options: Vec<String>,
+}
+
+impl LPosCommand {
+    pub fn new(key: String, element: String) -> Self {
+        LPosCommand { key, element, options: Vec::new() }
+    }
+
+    // Skips the first rank - 1 matches, a negative rank searches from the tail
+    pub fn rank(mut self, rank: i64) -> Self {
+        self.options.push("RANK".to_string());
+        self.options.push(rank.to_string());
+        self
+    }
+
+    // Returns up to count matches instead of the first one, 0 for all of them
+    pub fn count(mut self, count: u64) -> Self {
+        self.options.push("COUNT".to_string());
+        self.options.push(count.to_string());
+        self
+    }
+
+    // Compares at most maxlen elements
+    pub fn maxlen(mut self, maxlen: u64) -> Self {
+        self.options.push("MAXLEN".to_string());
+        self.options.push(maxlen.to_string());
+        self
+    }
+}
+
+impl Command for LPosCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        let mut parts = vec!["LPOS", &self.key, &self.element];
+        parts.extend(self.options.iter().map(String::as_str));
+        parts
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![1]
+    }
+
+    fn is_readonly(&self) -> bool {
+        true
+    }
+}
+
+impl TypedCommand for LPosCommand {
+    // Indexes of the matching elements, at most one without count
+    type Output = Vec<u64>;
+
+    // Without COUNT Redis replies with a single index or nil instead of an array
+    fn parse_output(&self, reply: Resp3Value) -> Result<Self::Output, Resp3Error> {
+        match reply {
+            Resp3Value::Integer(_) => Ok(vec![u64::from_resp3_value(reply)?]),
+            reply => Vec::from_resp3_value(reply),
+        }
+    }
+}
+
+pub struct LmPopCommand {
+    num_keys: String,
+    keys: Vec<String>,
+    end: ListEnd,
+    count: Option<String>,
+}
+
+impl LmPopCommand {
+    // Pops from the first non-empty list of the keys (Redis 7.0+)
+    pub fn new(keys: Vec<String>, end: ListEnd) -> Self {
+        LmPopCommand { num_keys: keys.len().to_string(), keys, end, count: None }
+    }
+
+    pub fn count(mut self, count: u64) -> Self {
+        self.count = Some(count.to_string());
+        self
+    }
+}
+
+impl Command for LmPopCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        let mut parts = vec!["LMPOP", &self.num_keys];
+        parts.extend(self.keys.iter().map(String::as_str));
+        parts.push(self.end.as_str());
+        if let Some(count) = &self.count {
+            parts.push("COUNT");
+            parts.push(count);
+        }
+        parts
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        (2..2 + self.keys.len()).collect()
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Array
+    }
+}
+
+impl TypedCommand for LmPopCommand {
+    // The key popped from with its elements, None when all the lists are empty
+    type Output = Option<(String, Vec<String>)>;
+}
+
+pub struct BlmPopCommand {
+    timeout: String,
+    pop: LmPopCommand,
+}
+
+impl BlmPopCommand {
+    // Blocks until one of the lists has elements or the timeout elapsed, a zero timeout blocks forever
+    pub fn new(timeout: Duration, keys: Vec<String>, end: ListEnd) -> Self {
+        BlmPopCommand { timeout: timeout.as_secs_f64().to_string(), pop: LmPopCommand::new(keys, end) }
+    }
+
+    pub fn count(mut self, count: u64) -> Self {
+        self.pop = self.pop.count(count);
+        self
+    }
+}
+
+impl Command for BlmPopCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        let mut parts = vec!["BLMPOP", &self.timeout];
+        parts.extend(self.pop.get_parts().into_iter().skip(1));
+        parts
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        self.pop.key_indices().into_iter().map(|index| index + 1).collect()
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Array
+    }
+}
+
+impl TypedCommand for BlmPopCommand {
+    // None when the timeout elapsed
+    type Output = Option<(String, Vec<String>)>;
+}
+
+pub struct PushCommand {
+    end: ListEnd,
+    key: String,
+    values: Vec<Vec<u8>>,
+    verbatim_values: bool,
+}
+
+impl PushCommand {
+    // LPUSH, the values end up in reverse order at the head of the list. Binary-safe,
+    // e.g. PushCommand::left(key, ["a", "b"])
+    pub fn left(key: String, values: impl IntoIterator<Item = impl Into<Vec<u8>>>) -> Self {
+        PushCommand { end: ListEnd::Left, key, values: values.into_iter().map(Into::into).collect(), verbatim_values: false }
+    }
+
+    // RPUSH, the values end up in order at the tail of the list
+    pub fn right(key: String, values: impl IntoIterator<Item = impl Into<Vec<u8>>>) -> Self {
+        PushCommand { end: ListEnd::Right, ..Self::left(key, values) }
+    }
+
+    // Adds one more value to the same command
+    pub fn value(mut self, value: impl Into<Vec<u8>>) -> Self {
+        self.values.push(value.into());
+        self
+    }
+
+    // Send the values as they are instead of UPPERCASE, e.g. for payloads
+    pub fn verbatim_values(mut self) -> Self {
+        self.verbatim_values = true;
+        self
+    }
+}
+
+impl Command for PushCommand {
+    fn is_verbatim(&self, index: usize) -> bool {
+        self.verbatim_values && index >= 2
+    }
+
+    fn get_parts(&self) -> Vec<&str> {
+        text_parts(&self.get_binary_parts())
+    }
+
+    fn get_binary_parts(&self) -> Vec<&[u8]> {
+        let name = match self.end {
+            ListEnd::Left => "LPUSH",
+            ListEnd::Right => "RPUSH",
+        };
+        let mut parts = vec![name.as_bytes(), self.key.as_bytes()];
+        parts.extend(self.values.iter().map(Vec::as_slice));
+        parts
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![1]
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Integer
+    }
+}
+
+impl TypedCommand for PushCommand {
+    // Length of the list after the push
+    type Output = u64;
+}
+
+pub struct PopCommand {
+    end: ListEnd,
+    key: String,
+}
+
+impl PopCommand {
+    // LPOP, the first element
+    pub fn left(key: String) -> Self {
+        PopCommand { end: ListEnd::Left, key }
+    }
+
+    // RPOP, the last element
+    pub fn right(key: String) -> Self {
+        PopCommand { end: ListEnd::Right, key }
+    }
+}
+
+impl Command for PopCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        vec![pop_name(self.end), &self.key]
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![1]
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Bulk
+    }
+}
+
+impl TypedCommand for PopCommand {
+    // None when the key does not exist
+    type Output = Option<String>;
+}
+
+// LPOP and RPOP with COUNT (Redis 6.2+), which reply with an array even for a single element
+pub struct PopCountCommand {
+    end: ListEnd,
+    key: String,
+    count: String,
+}
+
+impl PopCountCommand {
+    // Up to count elements from the head, in the order they were in the list
+    pub fn left(key: String, count: u64) -> Self {
+        PopCountCommand { end: ListEnd::Left, key, count: count.to_string() }
+    }
+
+    // Up to count elements from the tail, the last one first
+    pub fn right(key: String, count: u64) -> Self {
+        PopCountCommand { end: ListEnd::Right, ..Self::left(key, count) }
+    }
+}
+
+impl Command for PopCountCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        vec![pop_name(self.end), &self.key, &self.count]
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![1]
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Array
+    }
+}
+
+impl TypedCommand for PopCountCommand {
+    // Empty when the key does not exist
+    type Output = Vec<String>;
+}
+
+fn pop_name(end: ListEnd) -> &'static str {
+    match end {
+        ListEnd::Left => "LPOP",
+        ListEnd::Right => "RPOP",
+    }
+}
+
+pub struct LLenCommand {
+    key: String,
+}
+
+impl LLenCommand {
+    pub fn new(key: String) -> Self {
+        LLenCommand { key }
+    }
+}
+
+impl Command for LLenCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        vec!["LLEN", &self.key]
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![1]
+    }
+
+    fn is_readonly(&self) -> bool {
+        true
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Integer
+    }
+}
+
+impl TypedCommand for LLenCommand {
+    // Zero when the key does not exist
+    type Output = u64;
+}
+
+pub struct LRangeCommand {
+    key: String,
+    start: String,
+    stop: String,
+}
+
+impl LRangeCommand {
+    // Both ends included, negative indexes count from the tail, e.g. 0 and -1 for the whole list
+    pub fn new(key: String, start: i64, stop: i64) -> Self {
+        LRangeCommand { key, start: start.to_string(), stop: stop.to_string() }
+    }
+}
+
+impl Command for LRangeCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        vec!["LRANGE", &self.key, &self.start, &self.stop]
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![1]
+    }
+
+    fn is_readonly(&self) -> bool {
+        true
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Array
+    }
+}
+
+impl TypedCommand for LRangeCommand {
+    // Empty when the key does not exist or the range is out of the list
+    type Output = Vec<String>;
+}
+
+pub struct LTrimCommand {
+    key: String,
+    start: String,
+    stop: String,
+}
+
+impl LTrimCommand {
+    // Keeps the elements from start to stop, both included, like LRangeCommand. The key is
+    // deleted when nothing is left
+    pub fn new(key: String, start: i64, stop: i64) -> Self {
+        LTrimCommand { key, start: start.to_string(), stop: stop.to_string() }
+    }
+
+    // Keeps the first count elements, e.g. the most recent ones of a list pushed on the left
+    pub fn keep_first(key: String, count: u64) -> Self {
+        match count {
+            0 => Self::new(key, 1, 0),
+            count => Self::new(key, 0, count as i64 - 1),
+        }
+    }
+
+    // Keeps the last count elements, e.g. the most recent ones of a list pushed on the right
+    pub fn keep_last(key: String, count: u64) -> Self {
+        match count {
+            0 => Self::new(key, 1, 0),
+            count => Self::new(key, -(count as i64), -1),
+        }
+    }
+}
+
+impl Command for LTrimCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        vec!["LTRIM", &self.key, &self.start, &self.stop]
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![1]
+    }
+}
+
+impl TypedCommand for LTrimCommand {
+    type Output = bool;
+}
@@ -0,0 +1,124 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// An option of a command that expands into its arguments, token first, e.g. ["EX", "60"]
+pub trait ToArguments {
+    fn to_arguments(&self) -> Vec<String>;
+}
+
+impl<T: ToArguments> ToArguments for Option<T> {
+    fn to_arguments(&self) -> Vec<String> {
+        self.as_ref().map(ToArguments::to_arguments).unwrap_or_default()
+    }
+}
+
+// The expiration given to a key by SET and GETEX
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Expiry {
+    // In seconds, or milliseconds, from now
+    Ex(u64),
+    Px(u64),
+    // At a Unix time in seconds, or milliseconds
+    ExAt(i64),
+    PxAt(i64),
+    // Keeps the expiration the key already has, what GETEX does without options
+    KeepTtl,
+    // Removes the expiration, what SET does without options
+    Persist,
+}
+
+impl Expiry {
+    // Px, rounded down to milliseconds
+    pub fn after(duration: Duration) -> Self {
+        Expiry::Px(duration.as_millis() as u64)
+    }
+
+    // PxAt, times before the Unix epoch are sent as 0
+    pub fn at(time: SystemTime) -> Self {
+        Expiry::PxAt(time.duration_since(UNIX_EPOCH).map(|since| since.as_millis() as i64).unwrap_or(0))
+    }
+}
+
+impl ToArguments for Expiry {
+    fn to_arguments(&self) -> Vec<String> {
+        match self {
+            Expiry::Ex(seconds) => vec!["EX".to_string(), seconds.to_string()],
+            Expiry::Px(milliseconds) => vec!["PX".to_string(), milliseconds.to_string()],
+            Expiry::ExAt(seconds) => vec!["EXAT".to_string(), seconds.to_string()],
+            Expiry::PxAt(milliseconds) => vec!["PXAT".to_string(), milliseconds.to_string()],
+            Expiry::KeepTtl => vec!["KEEPTTL".to_string()],
+            Expiry::Persist => vec!["PERSIST".to_string()],
+        }
+    }
+}
+
+// Whether a write depends on the key already existing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SetCondition {
+    // Only when the key does not exist
+    Nx,
+    // Only when it does
+    Xx,
+}
+
+impl SetCondition {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SetCondition::Nx => "NX",
+            SetCondition::Xx => "XX",
+        }
+    }
+}
+
+impl ToArguments for SetCondition {
+    fn to_arguments(&self) -> Vec<String> {
+        vec![self.as_str().to_string()]
+    }
+}
+
+// When an expiration is set, against the one there already is, e.g. for HEXPIRE
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExpireCondition {
+    // Only when there is no expiration
+    Nx,
+    // Only when there is one
+    Xx,
+    // Only when the new one is later, no expiration counting as infinite
+    Gt,
+    // Only when the new one is sooner
+    Lt,
+}
+
+impl ExpireCondition {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ExpireCondition::Nx => "NX",
+            ExpireCondition::Xx => "XX",
+            ExpireCondition::Gt => "GT",
+            ExpireCondition::Lt => "LT",
+        }
+    }
+}
+
+impl ToArguments for ExpireCondition {
+    fn to_arguments(&self) -> Vec<String> {
+        vec![self.as_str().to_string()]
+    }
+}
+
+// How many elements HRANDFIELD, SRANDMEMBER and ZRANDMEMBER pick
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sample {
+    // Up to this many different elements, fewer when the key has fewer
+    Distinct(u64),
+    // Exactly this many picks, the same element may come up several times
+    WithRepeats(u64),
+}
+
+impl ToArguments for Sample {
+    fn to_arguments(&self) -> Vec<String> {
+        match self {
+            Sample::Distinct(count) => vec![count.to_string()],
+            Sample::WithRepeats(count) => vec![format!("-{}", count)],
+        }
+    }
+}
@@ -0,0 +1,117 @@
+use crate::resp3::error::Resp3Error;
+use crate::resp3::utils::command::{Command, ReplyKind, TypedCommand};
+use crate::resp3::value::{pairs, Resp3Value};
+
+pub struct PublishCommand {
+    channel: String,
+    message: String,
+}
+
+impl PublishCommand {
+    pub fn new(channel: String, message: String) -> Self {
+        PublishCommand { channel, message }
+    }
+}
+
+impl Command for PublishCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        vec!["PUBLISH", &self.channel, &self.message]
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Integer
+    }
+}
+
+impl TypedCommand for PublishCommand {
+    // Number of clients that received the message
+    type Output = u64;
+}
+
+pub struct PubSubChannelsCommand {
+    pattern: Option<String>,
+}
+
+impl PubSubChannelsCommand {
+    pub fn new() -> Self {
+        PubSubChannelsCommand { pattern: None }
+    }
+
+    // Lists only the channels matching the glob-style pattern
+    pub fn pattern(mut self, pattern: String) -> Self {
+        self.pattern = Some(pattern);
+        self
+    }
+}
+
+impl Default for PubSubChannelsCommand {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Command for PubSubChannelsCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        let mut parts = vec!["PUBSUB", "CHANNELS"];
+        parts.extend(self.pattern.as_deref());
+        parts
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Array
+    }
+}
+
+impl TypedCommand for PubSubChannelsCommand {
+    // Channels with at least one subscriber, pattern subscriptions are not counted
+    type Output = Vec<String>;
+}
+
+pub struct PubSubNumSubCommand {
+    channels: Vec<String>,
+}
+
+impl PubSubNumSubCommand {
+    pub fn new(channels: Vec<String>) -> Self {
+        PubSubNumSubCommand { channels }
+    }
+}
+
+impl Command for PubSubNumSubCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        let mut parts = vec!["PUBSUB", "NUMSUB"];
+        parts.extend(self.channels.iter().map(String::as_str));
+        parts
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Array
+    }
+}
+
+impl TypedCommand for PubSubNumSubCommand {
+    // Each requested channel with its number of subscribers, in the requested order
+    type Output = Vec<(String, u64)>;
+
+    // Redis replies with a flat array alternating channel and count
+    fn parse_output(&self, reply: Resp3Value) -> Result<Self::Output, Resp3Error> {
+        pairs(reply)
+    }
+}
+
+pub struct PubSubNumPatCommand;
+
+impl Command for PubSubNumPatCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        vec!["PUBSUB", "NUMPAT"]
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Integer
+    }
+}
+
+impl TypedCommand for PubSubNumPatCommand {
+    // Number of patterns subscribed to by all the clients
+    type Output = u64;
+}
@@ -0,0 +1,193 @@
+use std::fmt;
+use std::time::Duration;
+
+use crate::resp3::error::Resp3Error;
+use crate::resp3::utils::command::{Command, ReplyKind, TypedCommand};
+use crate::resp3::value::{unexpected, FromResp3Value, Resp3Value};
+
+pub struct WaitCommand {
+    num_replicas: String,
+    timeout: String,
+}
+
+impl WaitCommand {
+    // Blocks until the previous writes of this connection reached num_replicas replicas or
+    // the timeout elapsed, a zero timeout blocks forever
+    pub fn new(num_replicas: u64, timeout: Duration) -> Self {
+        WaitCommand { num_replicas: num_replicas.to_string(), timeout: timeout.as_millis().to_string() }
+    }
+}
+
+impl Command for WaitCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        vec!["WAIT", &self.num_replicas, &self.timeout]
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Integer
+    }
+}
+
+impl TypedCommand for WaitCommand {
+    // Number of replicas that acknowledged the writes
+    type Output = u64;
+}
+
+pub struct FailoverCommand {
+    options: Vec<String>,
+}
+
+impl FailoverCommand {
+    // Promotes one of the replicas of this master (Redis 6.2+)
+    pub fn new() -> Self {
+        FailoverCommand { options: Vec::new() }
+    }
+
+    // Aborts the failover in progress
+    pub fn abort() -> Self {
+        FailoverCommand { options: vec!["ABORT".to_string()] }
+    }
+
+    // Promotes this specific replica
+    pub fn to(mut self, host: &str, port: u16) -> Self {
+        self.options.push("TO".to_string());
+        self.options.push(host.to_string());
+        self.options.push(port.to_string());
+        self
+    }
+
+    // Fails over even if the replica did not catch up before the timeout, requires to and timeout
+    pub fn force(mut self) -> Self {
+        self.options.push("FORCE".to_string());
+        self
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.options.push("TIMEOUT".to_string());
+        self.options.push(timeout.as_millis().to_string());
+        self
+    }
+}
+
+impl Default for FailoverCommand {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Command for FailoverCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        let mut parts = vec!["FAILOVER"];
+        parts.extend(self.options.iter().map(String::as_str));
+        parts
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Status
+    }
+}
+
+impl TypedCommand for FailoverCommand {
+    type Output = bool;
+}
+
+// What a server is, as in the first element of the ROLE reply
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ServerRole {
+    Master,
+    Replica,
+    Sentinel,
+}
+
+impl fmt::Display for ServerRole {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ServerRole::Master => write!(f, "master"),
+            ServerRole::Replica => write!(f, "replica"),
+            ServerRole::Sentinel => write!(f, "sentinel"),
+        }
+    }
+}
+
+// A replica connected to a master, as listed by ROLE
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplicaOffset {
+    pub host: String,
+    pub port: u16,
+    // The replication offset the replica acknowledged
+    pub offset: u64,
+}
+
+// The reply of ROLE, which unlike INFO replication works on every server, Sentinel included
+#[derive(Debug, Clone, PartialEq)]
+pub enum Role {
+    Master { offset: u64, replicas: Vec<ReplicaOffset> },
+    // The state is "connect", "connecting", "sync" or "connected", the offset -1 before the first sync
+    Replica { master_host: String, master_port: u16, state: String, offset: i64 },
+    // The names of the masters the Sentinel monitors
+    Sentinel { masters: Vec<String> },
+}
+
+impl Role {
+    pub fn server_role(&self) -> ServerRole {
+        match self {
+            Role::Master { .. } => ServerRole::Master,
+            Role::Replica { .. } => ServerRole::Replica,
+            Role::Sentinel { .. } => ServerRole::Sentinel,
+        }
+    }
+}
+
+impl FromResp3Value for Role {
+    fn from_resp3_value(value: Resp3Value) -> Result<Self, Resp3Error> {
+        let values = match value {
+            Resp3Value::Array(values) if !values.is_empty() => values,
+            value => return unexpected("a ROLE reply", &value),
+        };
+        let mut values = values.into_iter();
+        let role = String::from_resp3_value(values.next().unwrap())?;
+        let mut next = || values.next().ok_or_else(|| Resp3Error::UnexpectedReply(format!("Expected more elements in the ROLE reply of a {}", role)));
+        match role.as_str() {
+            "master" => {
+                let offset = u64::from_resp3_value(next()?)?;
+                // Each replica is [host, port, offset], the numbers as strings
+                let replicas: Vec<Vec<String>> = Vec::from_resp3_value(next()?)?;
+                let replicas = replicas.into_iter().map(|replica| match replica.as_slice() {
+                    [host, port, offset] => match (port.parse(), offset.parse()) {
+                        (Ok(port), Ok(offset)) => Ok(ReplicaOffset { host: host.clone(), port, offset }),
+                        _ => Err(Resp3Error::UnexpectedReply(format!("Expected a port and an offset, got {:?}", replica))),
+                    },
+                    _ => Err(Resp3Error::UnexpectedReply(format!("Expected a host, a port and an offset, got {:?}", replica))),
+                }).collect::<Result<_, _>>()?;
+                Ok(Role::Master { offset, replicas })
+            }
+            "slave" | "replica" => {
+                let master_host = String::from_resp3_value(next()?)?;
+                let master_port = u64::from_resp3_value(next()?)?;
+                let master_port = u16::try_from(master_port).map_err(|_| Resp3Error::UnexpectedReply(format!("Expected a port, got {}", master_port)))?;
+                let state = String::from_resp3_value(next()?)?;
+                let offset = i64::from_resp3_value(next()?)?;
+                Ok(Role::Replica { master_host, master_port, state, offset })
+            }
+            "sentinel" => Ok(Role::Sentinel { masters: Vec::from_resp3_value(next()?)? }),
+            _ => Err(Resp3Error::UnexpectedReply(format!("Expected master, slave or sentinel, got {}", role))),
+        }
+    }
+}
+
+// The role of the server, RedisConnectionBuilder::verify_role sends it on every connect
+pub struct RoleCommand;
+
+impl Command for RoleCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        vec!["ROLE"]
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Array
+    }
+}
+
+impl TypedCommand for RoleCommand {
+    type Output = Role;
+}
@@ -0,0 +1,258 @@
+use std::collections::HashMap;
+
+use crate::resp3::error::Resp3Error;
+use crate::resp3::utils::command::{Command, ReplyKind, TypedCommand};
+use crate::resp3::value::{unexpected, FromResp3Value, Resp3Value};
+
+// Where the first key of a key spec is: at an index, or after a keyword
+#[derive(Debug, Clone, PartialEq)]
+pub enum BeginSearch {
+    Index(i64),
+    // Searched from start_from, backwards when it is negative
+    Keyword { keyword: String, start_from: i64 },
+    // e.g. the commands whose keys depend on their arguments
+    Unknown,
+}
+
+// How many keys follow the first one
+#[derive(Debug, Clone, PartialEq)]
+pub enum FindKeys {
+    // Up to last_key, relative to the first key, -1 for the last argument. With limit > 1,
+    // only that fraction of the remaining arguments
+    Range { last_key: i64, key_step: i64, limit: i64 },
+    // The number of keys is the argument at key_num_index, relative to the first key
+    KeyNum { key_num_index: i64, first_key: i64, key_step: i64 },
+    Unknown,
+}
+
+// How a command finds its keys and what it does with them (Redis 7.0+)
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeySpec {
+    // e.g. RW, access, update
+    pub flags: Vec<String>,
+    pub begin_search: BeginSearch,
+    pub find_keys: FindKeys,
+}
+
+impl FromResp3Value for KeySpec {
+    fn from_resp3_value(value: Resp3Value) -> Result<Self, Resp3Error> {
+        let mut fields: HashMap<String, Resp3Value> = HashMap::from_resp3_value(value)?;
+        let (begin_type, mut begin) = search_spec(field(&mut fields, "begin_search")?)?;
+        let (find_type, mut find) = search_spec(field(&mut fields, "find_keys")?)?;
+        Ok(KeySpec {
+            flags: field(&mut fields, "flags")?,
+            begin_search: match begin_type.as_str() {
+                "index" => BeginSearch::Index(field(&mut begin, "index")?),
+                "keyword" => BeginSearch::Keyword { keyword: field(&mut begin, "keyword")?, start_from: field(&mut begin, "startfrom")? },
+                _ => BeginSearch::Unknown,
+            },
+            find_keys: match find_type.as_str() {
+                "range" => FindKeys::Range { last_key: field(&mut find, "lastkey")?, key_step: field(&mut find, "keystep")?, limit: field(&mut find, "limit")? },
+                "keynum" => FindKeys::KeyNum { key_num_index: field(&mut find, "keynumidx")?, first_key: field(&mut find, "firstkey")?, key_step: field(&mut find, "keystep")? },
+                _ => FindKeys::Unknown,
+            },
+        })
+    }
+}
+
+// {type, spec}, the spec is a map of its own
+fn search_spec(value: Resp3Value) -> Result<(String, HashMap<String, Resp3Value>), Resp3Error> {
+    let mut fields: HashMap<String, Resp3Value> = HashMap::from_resp3_value(value)?;
+    let search_type: String = field(&mut fields, "type")?;
+    let spec = match fields.remove("spec") {
+        Some(Resp3Value::Null) | None => HashMap::new(),
+        Some(spec) => HashMap::from_resp3_value(spec)?,
+    };
+    Ok((search_type, spec))
+}
+
+// Missing fields decode as nil, so that optional ones become None or empty
+fn field<T: FromResp3Value>(fields: &mut HashMap<String, Resp3Value>, name: &str) -> Result<T, Resp3Error> {
+    T::from_resp3_value(fields.remove(name).unwrap_or(Resp3Value::Null))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommandInfo {
+    // Lowercase, subcommands as command|subcommand
+    pub name: String,
+    // The number of arguments with the name, negative when it is a minimum
+    pub arity: i64,
+    // e.g. readonly, write, fast, denyoom
+    pub flags: Vec<String>,
+    pub first_key: i64,
+    pub last_key: i64,
+    pub step: i64,
+    // Empty before Redis 6.0
+    pub acl_categories: Vec<String>,
+    // The fields of Redis 7.0, empty before
+    pub tips: Vec<String>,
+    pub key_specs: Vec<KeySpec>,
+    pub subcommands: Vec<CommandInfo>,
+}
+
+impl CommandInfo {
+    // Whether the command takes that many arguments, its name included
+    pub fn accepts(&self, count: usize) -> bool {
+        match self.arity {
+            arity if arity < 0 => count as i64 >= -arity,
+            arity => count as i64 == arity,
+        }
+    }
+
+    pub fn has_flag(&self, flag: &str) -> bool {
+        self.flags.iter().any(|candidate| candidate.eq_ignore_ascii_case(flag))
+    }
+}
+
+// [name, arity, flags, first key, last key, step, acl categories, tips, key specs, subcommands],
+// older servers stop after step or after the categories
+impl FromResp3Value for CommandInfo {
+    fn from_resp3_value(value: Resp3Value) -> Result<Self, Resp3Error> {
+        let Resp3Value::Array(fields) = value else { return unexpected("a command description", &value) };
+        if fields.len() < 6 {
+            return Err(Resp3Error::UnexpectedReply(format!("Expected at least 6 fields describing a command, got {}", fields.len())));
+        }
+        let mut fields = fields.into_iter();
+        let mut next = || fields.next().unwrap_or(Resp3Value::Null);
+        Ok(CommandInfo {
+            name: String::from_resp3_value(next())?,
+            arity: i64::from_resp3_value(next())?,
+            flags: Vec::from_resp3_value(next())?,
+            first_key: i64::from_resp3_value(next())?,
+            last_key: i64::from_resp3_value(next())?,
+            step: i64::from_resp3_value(next())?,
+            acl_categories: Vec::from_resp3_value(next())?,
+            tips: Vec::from_resp3_value(next())?,
+            key_specs: Vec::from_resp3_value(next())?,
+            subcommands: Vec::from_resp3_value(next())?,
+        })
+    }
+}
+
+pub struct CommandInfoCommand {
+    names: Vec<String>,
+}
+
+impl CommandInfoCommand {
+    // Subcommands are named command|subcommand, e.g. "config|get". Every command when there
+    // are no names (Redis 7.0+, COMMAND before)
+    pub fn new<I, S>(names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        CommandInfoCommand { names: names.into_iter().map(Into::into).collect() }
+    }
+}
+
+impl Command for CommandInfoCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        let mut parts = vec!["COMMAND", "INFO"];
+        parts.extend(self.names.iter().map(String::as_str));
+        parts
+    }
+
+    fn is_readonly(&self) -> bool {
+        true
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Array
+    }
+}
+
+impl TypedCommand for CommandInfoCommand {
+    // In the order of the names, None for the commands the server does not know
+    type Output = Vec<Option<CommandInfo>>;
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArgumentDoc {
+    pub name: String,
+    // e.g. key, string, integer, pure-token, oneof, block
+    pub arg_type: String,
+    // The keyword before the value, e.g. EX
+    pub token: Option<String>,
+    // e.g. optional, multiple
+    pub flags: Vec<String>,
+    // The arguments of a oneof or a block
+    pub arguments: Vec<ArgumentDoc>,
+}
+
+impl FromResp3Value for ArgumentDoc {
+    fn from_resp3_value(value: Resp3Value) -> Result<Self, Resp3Error> {
+        let mut fields: HashMap<String, Resp3Value> = HashMap::from_resp3_value(value)?;
+        Ok(ArgumentDoc {
+            name: field(&mut fields, "name")?,
+            arg_type: field(&mut fields, "type")?,
+            token: field(&mut fields, "token")?,
+            flags: field(&mut fields, "flags")?,
+            arguments: field(&mut fields, "arguments")?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommandDocs {
+    // Empty when the server has none, e.g. for some module commands
+    pub summary: String,
+    // The version of Redis that added the command, e.g. "6.2.0"
+    pub since: String,
+    // e.g. string, generic, connection
+    pub group: String,
+    pub complexity: Option<String>,
+    pub deprecated_since: Option<String>,
+    pub arguments: Vec<ArgumentDoc>,
+    // By name, as command|subcommand
+    pub subcommands: HashMap<String, CommandDocs>,
+}
+
+impl FromResp3Value for CommandDocs {
+    fn from_resp3_value(value: Resp3Value) -> Result<Self, Resp3Error> {
+        let mut fields: HashMap<String, Resp3Value> = HashMap::from_resp3_value(value)?;
+        Ok(CommandDocs {
+            summary: field::<Option<String>>(&mut fields, "summary")?.unwrap_or_default(),
+            since: field::<Option<String>>(&mut fields, "since")?.unwrap_or_default(),
+            group: field::<Option<String>>(&mut fields, "group")?.unwrap_or_default(),
+            complexity: field(&mut fields, "complexity")?,
+            deprecated_since: field(&mut fields, "deprecated_since")?,
+            arguments: field(&mut fields, "arguments")?,
+            subcommands: match fields.remove("subcommands") {
+                Some(Resp3Value::Null) | None => HashMap::new(),
+                Some(subcommands) => HashMap::from_resp3_value(subcommands)?,
+            },
+        })
+    }
+}
+
+// The documentation of the commands, every command when there are no names (Redis 7.0+)
+pub struct CommandDocsCommand {
+    names: Vec<String>,
+}
+
+impl CommandDocsCommand {
+    pub fn new<I, S>(names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        CommandDocsCommand { names: names.into_iter().map(Into::into).collect() }
+    }
+}
+
+impl Command for CommandDocsCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        let mut parts = vec!["COMMAND", "DOCS"];
+        parts.extend(self.names.iter().map(String::as_str));
+        parts
+    }
+
+    fn is_readonly(&self) -> bool {
+        true
+    }
+}
+
+impl TypedCommand for CommandDocsCommand {
+    // By lowercase name, the commands the server does not know are left out
+    type Output = HashMap<String, CommandDocs>;
+}
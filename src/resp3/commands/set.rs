@@ -1,18 +1,133 @@
-use crate::resp3::utils::command::Command;
+use crate::resp3::commands::options::{Expiry, SetCondition, ToArguments};
+use crate::resp3::utils::command::{Command, ReplyKind, TypedCommand};
 
 pub struct SetCommand {
     key: String,
     value: String,
+    condition: Option<SetCondition>,
+    expiry: Vec<String>,
+    verbatim_value: bool,
 }
 
 impl SetCommand {
     pub fn new(key: String, value: String) -> Self {
-        SetCommand { key, value }
+        SetCommand { key, value, condition: None, expiry: Vec::new(), verbatim_value: false }
+    }
+
+    // Only set the key if it does not already exist
+    pub fn nx(self) -> Self {
+        self.condition(SetCondition::Nx)
+    }
+
+    // Only set the key if it already exists
+    pub fn xx(self) -> Self {
+        self.condition(SetCondition::Xx)
+    }
+
+    // Replaces the previous condition, if any
+    pub fn condition(mut self, condition: SetCondition) -> Self {
+        self.condition = Some(condition);
+        self
+    }
+
+    // Expire the key after the given number of seconds
+    pub fn ex(self, seconds: u64) -> Self {
+        self.expiry(Expiry::Ex(seconds))
+    }
+
+    // Expire the key after the given number of milliseconds
+    pub fn px(self, milliseconds: u64) -> Self {
+        self.expiry(Expiry::Px(milliseconds))
+    }
+
+    // Replaces the previous expiry, if any. Persist is the default, SET removes the expiration
+    // of the key unless told to keep it
+    pub fn expiry(mut self, expiry: Expiry) -> Self {
+        self.expiry = match expiry {
+            Expiry::Persist => Vec::new(),
+            expiry => expiry.to_arguments(),
+        };
+        self
+    }
+
+    // Send the value as it is instead of UPPERCASE, e.g. for serialized data
+    pub fn verbatim_value(mut self) -> Self {
+        self.verbatim_value = true;
+        self
     }
 }
 
 impl Command for SetCommand {
+    fn is_verbatim(&self, index: usize) -> bool {
+        self.verbatim_value && index == 2
+    }
+
     fn get_parts(&self) -> Vec<&str> {
-        vec!["SET", &self.key, &self.value]
+        let mut parts = vec!["SET", &self.key, &self.value];
+        parts.extend(self.condition.map(|condition| condition.as_str()));
+        parts.extend(self.expiry.iter().map(String::as_str));
+        parts
     }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![1]
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Status
+    }
+}
+
+impl TypedCommand for SetCommand {
+    // False when NX or XX prevented the write
+    type Output = bool;
+}
+
+// SET of several keys at once, atomically. In a cluster all the keys must hash to the same
+// slot, see ClusterConnections::mset_clustered for the others
+pub struct MSetCommand {
+    pairs: Vec<String>,
+    verbatim_values: bool,
+}
+
+impl MSetCommand {
+    pub fn new<I, K, V>(pairs: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<String>,
+        V: Into<String>,
+    {
+        let pairs = pairs.into_iter().flat_map(|(key, value)| [key.into(), value.into()]).collect();
+        MSetCommand { pairs, verbatim_values: false }
+    }
+
+    // Send the values as they are instead of UPPERCASE, e.g. for serialized data
+    pub fn verbatim_values(mut self) -> Self {
+        self.verbatim_values = true;
+        self
+    }
+}
+
+impl Command for MSetCommand {
+    fn is_verbatim(&self, index: usize) -> bool {
+        self.verbatim_values && index > 0 && index.is_multiple_of(2)
+    }
+
+    fn get_parts(&self) -> Vec<&str> {
+        let mut parts = vec!["MSET"];
+        parts.extend(self.pairs.iter().map(String::as_str));
+        parts
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        (1..self.pairs.len()).step_by(2).collect()
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Status
+    }
+}
+
+impl TypedCommand for MSetCommand {
+    type Output = bool;
 }
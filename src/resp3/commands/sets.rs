@@ -0,0 +1,313 @@
+use crate::resp3::commands::options::{Sample, ToArguments};
+use crate::resp3::error::Resp3Error;
+use crate::resp3::utils::command::{text_parts, Command, ReplyKind, TypedCommand};
+use crate::resp3::utils::redis_connection::RedisConnection;
+
+pub struct SInterCardCommand {
+    num_keys: String,
+    keys: Vec<String>,
+    limit: Option<String>,
+}
+
+impl SInterCardCommand {
+    // Cardinality of the intersection of the sets, without transferring it (Redis 7.0+)
+    pub fn new(keys: Vec<String>) -> Self {
+        SInterCardCommand { num_keys: keys.len().to_string(), keys, limit: None }
+    }
+
+    // Stops counting once the limit is reached
+    pub fn limit(mut self, limit: u64) -> Self {
+        self.limit = Some(limit.to_string());
+        self
+    }
+}
+
+impl Command for SInterCardCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        let mut parts = vec!["SINTERCARD", &self.num_keys];
+        parts.extend(self.keys.iter().map(String::as_str));
+        if let Some(limit) = &self.limit {
+            parts.push("LIMIT");
+            parts.push(limit);
+        }
+        parts
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        (2..2 + self.keys.len()).collect()
+    }
+
+    fn is_readonly(&self) -> bool {
+        true
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Integer
+    }
+}
+
+impl TypedCommand for SInterCardCommand {
+    type Output = u64;
+}
+
+pub struct SAddCommand {
+    key: String,
+    members: Vec<Vec<u8>>,
+}
+
+impl SAddCommand {
+    // Binary-safe, e.g. SAddCommand::new(key, ["a", "b"])
+    pub fn new(key: String, members: impl IntoIterator<Item = impl Into<Vec<u8>>>) -> Self {
+        SAddCommand { key, members: members.into_iter().map(Into::into).collect() }
+    }
+
+    // Adds one more member to the same command
+    pub fn member(mut self, member: impl Into<Vec<u8>>) -> Self {
+        self.members.push(member.into());
+        self
+    }
+}
+
+impl Command for SAddCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        text_parts(&self.get_binary_parts())
+    }
+
+    fn get_binary_parts(&self) -> Vec<&[u8]> {
+        let mut parts = vec!["SADD".as_bytes(), self.key.as_bytes()];
+        parts.extend(self.members.iter().map(Vec::as_slice));
+        parts
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![1]
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Integer
+    }
+}
+
+impl TypedCommand for SAddCommand {
+    // Number of members added, not counting the ones already in the set
+    type Output = u64;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SetOperation {
+    Inter,
+    Union,
+    Diff,
+}
+
+// Stores the intersection, union or difference of the sets in destination, replacing it
+pub struct SetStoreCommand {
+    operation: SetOperation,
+    destination: String,
+    keys: Vec<String>,
+}
+
+impl SetStoreCommand {
+    // SINTERSTORE, the members in every set
+    pub fn inter(destination: String, keys: Vec<String>) -> Self {
+        SetStoreCommand { operation: SetOperation::Inter, destination, keys }
+    }
+
+    // SUNIONSTORE, the members in any set
+    pub fn union(destination: String, keys: Vec<String>) -> Self {
+        SetStoreCommand { operation: SetOperation::Union, destination, keys }
+    }
+
+    // SDIFFSTORE, the members of the first set in none of the others
+    pub fn diff(destination: String, keys: Vec<String>) -> Self {
+        SetStoreCommand { operation: SetOperation::Diff, destination, keys }
+    }
+}
+
+impl Command for SetStoreCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        let name = match self.operation {
+            SetOperation::Inter => "SINTERSTORE",
+            SetOperation::Union => "SUNIONSTORE",
+            SetOperation::Diff => "SDIFFSTORE",
+        };
+        let mut parts = vec![name, &self.destination];
+        parts.extend(self.keys.iter().map(String::as_str));
+        parts
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        (1..2 + self.keys.len()).collect()
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Integer
+    }
+}
+
+impl TypedCommand for SetStoreCommand {
+    // Number of members in destination, which is deleted when there are none
+    type Output = u64;
+}
+
+pub struct SMIsMemberCommand {
+    key: String,
+    members: Vec<Vec<u8>>,
+}
+
+impl SMIsMemberCommand {
+    // Binary-safe, e.g. SMIsMemberCommand::new(key, ["a", "b"]) (Redis 6.2+)
+    pub fn new(key: String, members: impl IntoIterator<Item = impl Into<Vec<u8>>>) -> Self {
+        SMIsMemberCommand { key, members: members.into_iter().map(Into::into).collect() }
+    }
+}
+
+impl Command for SMIsMemberCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        text_parts(&self.get_binary_parts())
+    }
+
+    fn get_binary_parts(&self) -> Vec<&[u8]> {
+        let mut parts = vec!["SMISMEMBER".as_bytes(), self.key.as_bytes()];
+        parts.extend(self.members.iter().map(Vec::as_slice));
+        parts
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![1]
+    }
+
+    fn is_readonly(&self) -> bool {
+        true
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Array
+    }
+}
+
+impl TypedCommand for SMIsMemberCommand {
+    // Whether each member is in the set, in the order of the members
+    type Output = Vec<bool>;
+}
+
+// An intersection built up set by set, e.g.
+// SetIntersection::of("tag:rust").and("tag:redis").limit(100).count(&mut conn)
+#[derive(Debug, Clone)]
+pub struct SetIntersection {
+    keys: Vec<String>,
+    limit: Option<u64>,
+}
+
+impl SetIntersection {
+    pub fn of(key: &str) -> Self {
+        SetIntersection { keys: vec![key.to_string()], limit: None }
+    }
+
+    pub fn and(mut self, key: &str) -> Self {
+        self.keys.push(key.to_string());
+        self
+    }
+
+    // Counting stops there, which is cheaper on big sets. Only applies to count
+    pub fn limit(mut self, limit: u64) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    // Number of members in every set, at most the limit, with SINTERCARD (Redis 7.0+)
+    pub fn count(&self, conn: &mut RedisConnection) -> Result<u64, Resp3Error> {
+        let command = SInterCardCommand::new(self.keys.clone());
+        match self.limit {
+            Some(limit) => command.limit(limit).query(conn),
+            None => command.query(conn),
+        }
+    }
+
+    // Whether the sets have no member in common, stopping at the first one found
+    pub fn is_empty(&self, conn: &mut RedisConnection) -> Result<bool, Resp3Error> {
+        Ok(SInterCardCommand::new(self.keys.clone()).limit(1).query(conn)? == 0)
+    }
+
+    // Stores the intersection in destination with SINTERSTORE, returns its size
+    pub fn store(&self, conn: &mut RedisConnection, destination: &str) -> Result<u64, Resp3Error> {
+        SetStoreCommand::inter(destination.to_string(), self.keys.clone()).query(conn)
+    }
+}
+
+pub struct SRandMemberCommand {
+    key: String,
+}
+
+impl SRandMemberCommand {
+    // One member picked at random, None when the key does not exist, the set is left as it is unlike SPOP
+    pub fn new(key: String) -> Self {
+        SRandMemberCommand { key }
+    }
+}
+
+impl Command for SRandMemberCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        vec!["SRANDMEMBER", &self.key]
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![1]
+    }
+
+    fn is_readonly(&self) -> bool {
+        true
+    }
+
+    fn is_deterministic(&self) -> bool {
+        false
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Bulk
+    }
+}
+
+impl TypedCommand for SRandMemberCommand {
+    type Output = Option<String>;
+}
+
+pub struct SRandMembersCommand {
+    key: String,
+    count: Vec<String>,
+}
+
+impl SRandMembersCommand {
+    // Empty when the key does not exist
+    pub fn new(key: String, sample: Sample) -> Self {
+        SRandMembersCommand { key, count: sample.to_arguments() }
+    }
+}
+
+impl Command for SRandMembersCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        let mut parts = vec!["SRANDMEMBER", &self.key];
+        parts.extend(self.count.iter().map(String::as_str));
+        parts
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![1]
+    }
+
+    fn is_readonly(&self) -> bool {
+        true
+    }
+
+    fn is_deterministic(&self) -> bool {
+        false
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Array
+    }
+}
+
+impl TypedCommand for SRandMembersCommand {
+    type Output = Vec<String>;
+}
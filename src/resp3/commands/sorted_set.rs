@@ -0,0 +1,477 @@
+use crate::resp3::commands::options::{Sample, ToArguments};
+use crate::resp3::error::Resp3Error;
+use crate::resp3::utils::command::{Command, ReplyKind, TypedCommand};
+use crate::resp3::value::{format_double, nested_pairs, Resp3Value};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScoreEnd {
+    Min,
+    Max,
+}
+
+impl ScoreEnd {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ScoreEnd::Min => "MIN",
+            ScoreEnd::Max => "MAX",
+        }
+    }
+}
+
+pub struct ZmPopCommand {
+    num_keys: String,
+    keys: Vec<String>,
+    end: ScoreEnd,
+    count: Option<String>,
+}
+
+impl ZmPopCommand {
+    // Pops the lowest or highest scores from the first non-empty sorted set of the keys (Redis 7.0+)
+    pub fn new(keys: Vec<String>, end: ScoreEnd) -> Self {
+        ZmPopCommand { num_keys: keys.len().to_string(), keys, end, count: None }
+    }
+
+    pub fn count(mut self, count: u64) -> Self {
+        self.count = Some(count.to_string());
+        self
+    }
+}
+
+impl Command for ZmPopCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        let mut parts = vec!["ZMPOP", &self.num_keys];
+        parts.extend(self.keys.iter().map(String::as_str));
+        parts.push(self.end.as_str());
+        if let Some(count) = &self.count {
+            parts.push("COUNT");
+            parts.push(count);
+        }
+        parts
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        (2..2 + self.keys.len()).collect()
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Array
+    }
+}
+
+impl TypedCommand for ZmPopCommand {
+    // The key popped from with its members and scores, None when all the sorted sets are empty
+    type Output = Option<(String, Vec<(String, f64)>)>;
+}
+
+pub struct ZAddCommand {
+    key: String,
+    options: Vec<&'static str>,
+    // Score and member, in that order as they are sent
+    entries: Vec<(String, String)>,
+}
+
+impl ZAddCommand {
+    pub fn new(key: String, member: String, score: f64) -> Self {
+        ZAddCommand { key, options: Vec::new(), entries: Vec::new() }.member(member, score)
+    }
+
+    // Adds one more member to the same command
+    pub fn member(mut self, member: String, score: f64) -> Self {
+        self.entries.push((format_double(score), member));
+        self
+    }
+
+    // Only adds new members
+    pub fn nx(mut self) -> Self {
+        self.options.push("NX");
+        self
+    }
+
+    // Only updates existing members
+    pub fn xx(mut self) -> Self {
+        self.options.push("XX");
+        self
+    }
+
+    // Only updates a score when the new one is greater, e.g. to keep the best score
+    pub fn gt(mut self) -> Self {
+        self.options.push("GT");
+        self
+    }
+
+    // Only updates a score when the new one is lower
+    pub fn lt(mut self) -> Self {
+        self.options.push("LT");
+        self
+    }
+}
+
+impl Command for ZAddCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        let mut parts = vec!["ZADD", &self.key];
+        parts.extend(self.options.iter().copied());
+        for (score, member) in &self.entries {
+            parts.push(score);
+            parts.push(member);
+        }
+        parts
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![1]
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Integer
+    }
+}
+
+impl TypedCommand for ZAddCommand {
+    // Number of members added, the updated ones not included
+    type Output = u64;
+}
+
+pub struct ZIncrByCommand {
+    key: String,
+    increment: String,
+    member: String,
+}
+
+impl ZIncrByCommand {
+    pub fn new(key: String, member: String, increment: f64) -> Self {
+        ZIncrByCommand { key, increment: format_double(increment), member }
+    }
+}
+
+impl Command for ZIncrByCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        vec!["ZINCRBY", &self.key, &self.increment, &self.member]
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![1]
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Bulk
+    }
+}
+
+impl TypedCommand for ZIncrByCommand {
+    // The new score of the member
+    type Output = f64;
+}
+
+pub struct ZScoreCommand {
+    key: String,
+    member: String,
+}
+
+impl ZScoreCommand {
+    pub fn new(key: String, member: String) -> Self {
+        ZScoreCommand { key, member }
+    }
+}
+
+impl Command for ZScoreCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        vec!["ZSCORE", &self.key, &self.member]
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![1]
+    }
+
+    fn is_readonly(&self) -> bool {
+        true
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Bulk
+    }
+}
+
+impl TypedCommand for ZScoreCommand {
+    // None when the member is not in the sorted set
+    type Output = Option<f64>;
+}
+
+pub struct ZRankCommand {
+    key: String,
+    member: String,
+    reverse: bool,
+}
+
+impl ZRankCommand {
+    // Rank 0 is the lowest score
+    pub fn new(key: String, member: String) -> Self {
+        ZRankCommand { key, member, reverse: false }
+    }
+
+    // ZREVRANK, rank 0 is the highest score
+    pub fn reverse(mut self) -> Self {
+        self.reverse = true;
+        self
+    }
+}
+
+impl Command for ZRankCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        let name = if self.reverse { "ZREVRANK" } else { "ZRANK" };
+        vec![name, &self.key, &self.member]
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![1]
+    }
+
+    fn is_readonly(&self) -> bool {
+        true
+    }
+
+    // An integer, or nil which ReplyKind::Integer does not accept
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Any
+    }
+}
+
+impl TypedCommand for ZRankCommand {
+    // None when the member is not in the sorted set
+    type Output = Option<u64>;
+}
+
+pub struct ZRangeWithScoresCommand {
+    key: String,
+    start: String,
+    stop: String,
+    reverse: bool,
+}
+
+impl ZRangeWithScoresCommand {
+    // The members between the two ranks, both included, negative ranks count from the end
+    pub fn new(key: String, start: i64, stop: i64) -> Self {
+        ZRangeWithScoresCommand { key, start: start.to_string(), stop: stop.to_string(), reverse: false }
+    }
+
+    // Ranks count from the highest score
+    pub fn reverse(mut self) -> Self {
+        self.reverse = true;
+        self
+    }
+}
+
+impl Command for ZRangeWithScoresCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        let mut parts = vec!["ZRANGE", &self.key, &self.start, &self.stop];
+        if self.reverse {
+            parts.push("REV");
+        }
+        parts.push("WITHSCORES");
+        parts
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![1]
+    }
+
+    fn is_readonly(&self) -> bool {
+        true
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Array
+    }
+}
+
+impl TypedCommand for ZRangeWithScoresCommand {
+    type Output = Vec<(String, f64)>;
+
+    fn parse_output(&self, reply: Resp3Value) -> Result<Self::Output, Resp3Error> {
+        nested_pairs(reply)
+    }
+}
+
+pub struct ZCardCommand {
+    key: String,
+}
+
+impl ZCardCommand {
+    pub fn new(key: String) -> Self {
+        ZCardCommand { key }
+    }
+}
+
+impl Command for ZCardCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        vec!["ZCARD", &self.key]
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![1]
+    }
+
+    fn is_readonly(&self) -> bool {
+        true
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Integer
+    }
+}
+
+impl TypedCommand for ZCardCommand {
+    // Zero when the key does not exist
+    type Output = u64;
+}
+
+pub struct ZRemCommand {
+    key: String,
+    members: Vec<String>,
+}
+
+impl ZRemCommand {
+    pub fn new(key: String, members: Vec<String>) -> Self {
+        ZRemCommand { key, members }
+    }
+}
+
+impl Command for ZRemCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        let mut parts = vec!["ZREM", &self.key];
+        parts.extend(self.members.iter().map(String::as_str));
+        parts
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![1]
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Integer
+    }
+}
+
+impl TypedCommand for ZRemCommand {
+    // Number of members removed
+    type Output = u64;
+}
+
+pub struct ZRandMemberCommand {
+    key: String,
+}
+
+impl ZRandMemberCommand {
+    // One member picked at random, None when the key does not exist (Redis 6.2+)
+    pub fn new(key: String) -> Self {
+        ZRandMemberCommand { key }
+    }
+}
+
+impl Command for ZRandMemberCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        vec!["ZRANDMEMBER", &self.key]
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![1]
+    }
+
+    fn is_readonly(&self) -> bool {
+        true
+    }
+
+    fn is_deterministic(&self) -> bool {
+        false
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Bulk
+    }
+}
+
+impl TypedCommand for ZRandMemberCommand {
+    type Output = Option<String>;
+}
+
+pub struct ZRandMembersCommand {
+    key: String,
+    count: Vec<String>,
+}
+
+impl ZRandMembersCommand {
+    // Empty when the key does not exist
+    pub fn new(key: String, sample: Sample) -> Self {
+        ZRandMembersCommand { key, count: sample.to_arguments() }
+    }
+}
+
+impl Command for ZRandMembersCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        let mut parts = vec!["ZRANDMEMBER", &self.key];
+        parts.extend(self.count.iter().map(String::as_str));
+        parts
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![1]
+    }
+
+    fn is_readonly(&self) -> bool {
+        true
+    }
+
+    fn is_deterministic(&self) -> bool {
+        false
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Array
+    }
+}
+
+impl TypedCommand for ZRandMembersCommand {
+    type Output = Vec<String>;
+}
+
+pub struct ZRandMembersWithScoresCommand {
+    sample: ZRandMembersCommand,
+}
+
+impl ZRandMembersWithScoresCommand {
+    // The members with their scores, RESP3 sends a [member, score] array per member
+    pub fn new(key: String, sample: Sample) -> Self {
+        ZRandMembersWithScoresCommand { sample: ZRandMembersCommand::new(key, sample) }
+    }
+}
+
+impl Command for ZRandMembersWithScoresCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        let mut parts = self.sample.get_parts();
+        parts.push("WITHSCORES");
+        parts
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![1]
+    }
+
+    fn is_readonly(&self) -> bool {
+        true
+    }
+
+    fn is_deterministic(&self) -> bool {
+        false
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Array
+    }
+}
+
+impl TypedCommand for ZRandMembersWithScoresCommand {
+    type Output = Vec<(String, f64)>;
+
+    fn parse_output(&self, reply: Resp3Value) -> Result<Self::Output, Resp3Error> {
+        nested_pairs(reply)
+    }
+}
@@ -0,0 +1,686 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::resp3::error::Resp3Error;
+use crate::resp3::utils::command::{Command, ReplyKind, TypedCommand};
+use crate::resp3::value::{pairs, unexpected, FromResp3Value, Resp3Value};
+
+// An entry of a stream, its fields in the order they were added
+#[derive(Debug, Clone, PartialEq)]
+pub struct StreamEntry {
+    // e.g. "1700000000000-0", milliseconds and a sequence number
+    pub id: String,
+    // Empty for an entry deleted while pending, which XREADGROUP still lists
+    pub fields: Vec<(String, String)>,
+}
+
+impl StreamEntry {
+    // The value of the first field with this name
+    pub fn get(&self, field: &str) -> Option<&str> {
+        self.fields.iter().find(|(name, _)| name == field).map(|(_, value)| value.as_str())
+    }
+}
+
+// [id, [field, value, ...]]
+impl FromResp3Value for StreamEntry {
+    fn from_resp3_value(value: Resp3Value) -> Result<Self, Resp3Error> {
+        let (id, fields): (String, Resp3Value) = FromResp3Value::from_resp3_value(value)?;
+        let fields = match fields {
+            Resp3Value::Null => Vec::new(),
+            fields => pairs(fields)?,
+        };
+        Ok(StreamEntry { id, fields })
+    }
+}
+
+// The entries read from each stream, by key
+pub type StreamReads = Vec<(String, Vec<StreamEntry>)>;
+
+// RESP3 sends a map of the streams, RESP2 an array of [key, entries], nil for none
+fn stream_reads(reply: Resp3Value) -> Result<StreamReads, Resp3Error> {
+    match reply {
+        Resp3Value::Null => Ok(Vec::new()),
+        Resp3Value::Map(map) => map.into_iter().map(|(key, entries)| Ok((String::from_resp3_value(key)?, Vec::from_resp3_value(entries)?))).collect(),
+        reply => Vec::from_resp3_value(reply),
+    }
+}
+
+// How XADD and XTRIM trim a stream, by length or by age
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StreamTrim {
+    // Keeps the newest entries
+    MaxLen(u64),
+    // Keeps the entries from this id, e.g. "1700000000000" for the ones newer than that time
+    MinId(String),
+}
+
+impl StreamTrim {
+    // MinId for the entries added from that time on
+    pub fn since(time: SystemTime) -> Self {
+        StreamTrim::MinId(time.duration_since(UNIX_EPOCH).map(|since| since.as_millis()).unwrap_or(0).to_string())
+    }
+
+    fn arguments(&self, approximate: bool) -> Vec<String> {
+        let (strategy, threshold) = match self {
+            StreamTrim::MaxLen(len) => ("MAXLEN", len.to_string()),
+            StreamTrim::MinId(id) => ("MINID", id.clone()),
+        };
+        vec![strategy.to_string(), if approximate { "~" } else { "=" }.to_string(), threshold]
+    }
+}
+
+pub struct XAddCommand {
+    key: String,
+    id: String,
+    trim: Vec<String>,
+    fields: Vec<String>,
+    verbatim_values: bool,
+}
+
+impl XAddCommand {
+    // With an id generated by the server, e.g. XAddCommand::new(key, [("event", "signup")])
+    pub fn new<I, F, V>(key: String, fields: I) -> Self
+    where
+        I: IntoIterator<Item = (F, V)>,
+        F: Into<String>,
+        V: Into<String>,
+    {
+        let fields = fields.into_iter().flat_map(|(field, value)| [field.into(), value.into()]).collect();
+        XAddCommand { key, id: "*".to_string(), trim: Vec::new(), fields, verbatim_values: false }
+    }
+
+    // An explicit id, greater than the last one of the stream, or "<milliseconds>-*"
+    pub fn id(mut self, id: &str) -> Self {
+        self.id = id.to_string();
+        self
+    }
+
+    // Trims the stream to about this many entries
+    pub fn max_len(self, max_len: u64) -> Self {
+        self.trim(StreamTrim::MaxLen(max_len), true)
+    }
+
+    // Trims the stream while adding the entry. Approximate trimming only removes whole nodes of
+    // the stream, so a few more entries may stay, and is much cheaper than trimming exactly
+    pub fn trim(mut self, trim: StreamTrim, approximate: bool) -> Self {
+        self.trim = trim.arguments(approximate);
+        self
+    }
+
+    // Send the fields and values as they are instead of UPPERCASE, e.g. for payloads
+    pub fn verbatim_values(mut self) -> Self {
+        self.verbatim_values = true;
+        self
+    }
+}
+
+impl Command for XAddCommand {
+    fn is_verbatim(&self, index: usize) -> bool {
+        self.verbatim_values && index >= 3 + self.trim.len()
+    }
+
+    fn get_parts(&self) -> Vec<&str> {
+        let mut parts = vec!["XADD", &self.key];
+        parts.extend(self.trim.iter().map(String::as_str));
+        parts.push(&self.id);
+        parts.extend(self.fields.iter().map(String::as_str));
+        parts
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![1]
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Bulk
+    }
+}
+
+impl TypedCommand for XAddCommand {
+    // The id of the entry
+    type Output = String;
+}
+
+pub struct XTrimCommand {
+    key: String,
+    trim: Vec<String>,
+}
+
+impl XTrimCommand {
+    pub fn new(key: String, trim: StreamTrim, approximate: bool) -> Self {
+        XTrimCommand { key, trim: trim.arguments(approximate) }
+    }
+}
+
+impl Command for XTrimCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        let mut parts = vec!["XTRIM", &self.key];
+        parts.extend(self.trim.iter().map(String::as_str));
+        parts
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![1]
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Integer
+    }
+}
+
+impl TypedCommand for XTrimCommand {
+    // The number of entries removed
+    type Output = u64;
+}
+
+pub struct XLenCommand {
+    key: String,
+}
+
+impl XLenCommand {
+    pub fn new(key: String) -> Self {
+        XLenCommand { key }
+    }
+}
+
+impl Command for XLenCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        vec!["XLEN", &self.key]
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![1]
+    }
+
+    fn is_readonly(&self) -> bool {
+        true
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Integer
+    }
+}
+
+impl TypedCommand for XLenCommand {
+    type Output = u64;
+}
+
+pub struct XRangeCommand {
+    key: String,
+    start: String,
+    end: String,
+    count: Option<String>,
+}
+
+impl XRangeCommand {
+    // From start to end included, "-" and "+" for the first and last ids, "(" before an id excludes it
+    pub fn new(key: String, start: &str, end: &str) -> Self {
+        XRangeCommand { key, start: start.to_string(), end: end.to_string(), count: None }
+    }
+
+    pub fn count(mut self, count: u64) -> Self {
+        self.count = Some(count.to_string());
+        self
+    }
+}
+
+impl Command for XRangeCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        let mut parts = vec!["XRANGE", &self.key, &self.start, &self.end];
+        if let Some(count) = &self.count {
+            parts.extend(["COUNT", count]);
+        }
+        parts
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![1]
+    }
+
+    fn is_readonly(&self) -> bool {
+        true
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Array
+    }
+}
+
+impl TypedCommand for XRangeCommand {
+    type Output = Vec<StreamEntry>;
+}
+
+// Consumer group and consumer names are sent UPPERCASE, like keys
+pub struct XGroupCreateCommand {
+    key: String,
+    group: String,
+    id: String,
+    mkstream: bool,
+}
+
+impl XGroupCreateCommand {
+    // The group gets the entries after the id, "$" for the ones added from now on, "0" for all of them
+    pub fn new(key: String, group: String, id: &str) -> Self {
+        XGroupCreateCommand { key, group, id: id.to_string(), mkstream: false }
+    }
+
+    // Creates an empty stream when there is none, instead of failing
+    pub fn mkstream(mut self) -> Self {
+        self.mkstream = true;
+        self
+    }
+}
+
+impl Command for XGroupCreateCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        let mut parts = vec!["XGROUP", "CREATE", &self.key, &self.group, &self.id];
+        if self.mkstream {
+            parts.push("MKSTREAM");
+        }
+        parts
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![2]
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Status
+    }
+}
+
+impl TypedCommand for XGroupCreateCommand {
+    // A group that already exists is a BUSYGROUP error
+    type Output = bool;
+}
+
+pub struct XReadGroupCommand {
+    group: String,
+    consumer: String,
+    count: Option<String>,
+    block: Option<String>,
+    no_ack: bool,
+    keys: Vec<String>,
+    ids: Vec<String>,
+}
+
+impl XReadGroupCommand {
+    // Reads from each stream after its id: ">" for entries never delivered to the group, any
+    // other id for the entries already delivered to this consumer and not acknowledged
+    pub fn new<I, K, D>(group: String, consumer: String, streams: I) -> Self
+    where
+        I: IntoIterator<Item = (K, D)>,
+        K: Into<String>,
+        D: Into<String>,
+    {
+        let (keys, ids) = streams.into_iter().map(|(key, id)| (key.into(), id.into())).unzip();
+        XReadGroupCommand { group, consumer, count: None, block: None, no_ack: false, keys, ids }
+    }
+
+    // At most this many entries per stream
+    pub fn count(mut self, count: u64) -> Self {
+        self.count = Some(count.to_string());
+        self
+    }
+
+    // Waits up to the timeout for new entries, forever with a zero one. The read timeout of
+    // the connection has to be longer
+    pub fn block(mut self, timeout: Duration) -> Self {
+        self.block = Some(timeout.as_millis().to_string());
+        self
+    }
+
+    // Acknowledges the entries as they are delivered, at-most-once
+    pub fn no_ack(mut self) -> Self {
+        self.no_ack = true;
+        self
+    }
+
+    fn options_len(&self) -> usize {
+        2 * self.count.iter().count() + 2 * self.block.iter().count() + usize::from(self.no_ack)
+    }
+}
+
+impl Command for XReadGroupCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        let mut parts = vec!["XREADGROUP", "GROUP", &self.group, &self.consumer];
+        if let Some(count) = &self.count {
+            parts.extend(["COUNT", count]);
+        }
+        if let Some(block) = &self.block {
+            parts.extend(["BLOCK", block]);
+        }
+        if self.no_ack {
+            parts.push("NOACK");
+        }
+        parts.push("STREAMS");
+        parts.extend(self.keys.iter().map(String::as_str));
+        parts.extend(self.ids.iter().map(String::as_str));
+        parts
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        let first = 5 + self.options_len();
+        (first..first + self.keys.len()).collect()
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Array
+    }
+
+    fn normalize_resp2(&self, reply: Resp3Value) -> Resp3Value {
+        match reply {
+            Resp3Value::Array(streams) => Resp3Value::Map(streams.into_iter().map(|stream| match stream {
+                Resp3Value::Array(pair) if pair.len() == 2 => {
+                    let mut pair = pair.into_iter();
+                    (pair.next().unwrap(), pair.next().unwrap())
+                }
+                stream => (stream, Resp3Value::Null),
+            }).collect()),
+            reply => reply,
+        }
+    }
+}
+
+impl TypedCommand for XReadGroupCommand {
+    // Only the streams with entries, empty when the timeout elapsed
+    type Output = StreamReads;
+
+    fn parse_output(&self, reply: Resp3Value) -> Result<StreamReads, Resp3Error> {
+        stream_reads(reply)
+    }
+}
+
+pub struct XAckCommand {
+    key: String,
+    group: String,
+    ids: Vec<String>,
+}
+
+impl XAckCommand {
+    pub fn new<I, S>(key: String, group: String, ids: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        XAckCommand { key, group, ids: ids.into_iter().map(Into::into).collect() }
+    }
+}
+
+impl Command for XAckCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        let mut parts = vec!["XACK", &self.key, &self.group];
+        parts.extend(self.ids.iter().map(String::as_str));
+        parts
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![1]
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Integer
+    }
+}
+
+impl TypedCommand for XAckCommand {
+    // The number of entries acknowledged, those not pending are not counted
+    type Output = u64;
+}
+
+// The entries of a group delivered and not acknowledged yet
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct PendingSummary {
+    pub count: u64,
+    // None when nothing is pending
+    pub smallest_id: Option<String>,
+    pub greatest_id: Option<String>,
+    // The consumers with pending entries, and how many
+    pub consumers: Vec<(String, u64)>,
+}
+
+// [count, smallest id, greatest id, [[consumer, count], ...]]
+impl FromResp3Value for PendingSummary {
+    fn from_resp3_value(value: Resp3Value) -> Result<Self, Resp3Error> {
+        let fields = match value {
+            Resp3Value::Array(fields) if fields.len() == 4 => fields,
+            value => return unexpected("a summary of pending entries", &value),
+        };
+        let mut fields = fields.into_iter();
+        let mut next = || fields.next().unwrap();
+        Ok(PendingSummary {
+            count: u64::from_resp3_value(next())?,
+            smallest_id: Option::from_resp3_value(next())?,
+            greatest_id: Option::from_resp3_value(next())?,
+            consumers: Option::<Vec<(String, u64)>>::from_resp3_value(next())?.unwrap_or_default(),
+        })
+    }
+}
+
+pub struct XPendingCommand {
+    key: String,
+    group: String,
+}
+
+impl XPendingCommand {
+    pub fn new(key: String, group: String) -> Self {
+        XPendingCommand { key, group }
+    }
+}
+
+impl Command for XPendingCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        vec!["XPENDING", &self.key, &self.group]
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![1]
+    }
+
+    fn is_readonly(&self) -> bool {
+        true
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Array
+    }
+}
+
+impl TypedCommand for XPendingCommand {
+    type Output = PendingSummary;
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PendingEntry {
+    pub id: String,
+    pub consumer: String,
+    // Since it was last delivered
+    pub idle: Duration,
+    // How many times it was delivered, claims included
+    pub deliveries: u64,
+}
+
+// [id, consumer, idle milliseconds, deliveries]
+impl FromResp3Value for PendingEntry {
+    fn from_resp3_value(value: Resp3Value) -> Result<Self, Resp3Error> {
+        let fields = match value {
+            Resp3Value::Array(fields) if fields.len() == 4 => fields,
+            value => return unexpected("a pending entry", &value),
+        };
+        let mut fields = fields.into_iter();
+        let mut next = || fields.next().unwrap();
+        Ok(PendingEntry {
+            id: String::from_resp3_value(next())?,
+            consumer: String::from_resp3_value(next())?,
+            idle: Duration::from_millis(u64::from_resp3_value(next())?),
+            deliveries: u64::from_resp3_value(next())?,
+        })
+    }
+}
+
+// The pending entries themselves, by id
+pub struct XPendingRangeCommand {
+    key: String,
+    group: String,
+    idle: Option<String>,
+    start: String,
+    end: String,
+    count: String,
+    consumer: Option<String>,
+}
+
+impl XPendingRangeCommand {
+    // Up to count entries from start to end, "-" and "+" for all of them
+    pub fn new(key: String, group: String, start: &str, end: &str, count: u64) -> Self {
+        XPendingRangeCommand { key, group, idle: None, start: start.to_string(), end: end.to_string(), count: count.to_string(), consumer: None }
+    }
+
+    // Only the entries delivered at least that long ago (Redis 6.2+)
+    pub fn idle(mut self, idle: Duration) -> Self {
+        self.idle = Some(idle.as_millis().to_string());
+        self
+    }
+
+    // Only the entries delivered to this consumer
+    pub fn consumer(mut self, consumer: String) -> Self {
+        self.consumer = Some(consumer);
+        self
+    }
+}
+
+impl Command for XPendingRangeCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        let mut parts = vec!["XPENDING", &self.key, &self.group];
+        if let Some(idle) = &self.idle {
+            parts.extend(["IDLE", idle]);
+        }
+        parts.extend([self.start.as_str(), &self.end, &self.count]);
+        parts.extend(self.consumer.as_deref());
+        parts
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![1]
+    }
+
+    fn is_readonly(&self) -> bool {
+        true
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Array
+    }
+}
+
+impl TypedCommand for XPendingRangeCommand {
+    type Output = Vec<PendingEntry>;
+}
+
+// The entries XAUTOCLAIM moved to the consumer
+#[derive(Debug, Clone, PartialEq)]
+pub struct AutoClaimed {
+    // Where the next call continues, "0-0" once every pending entry was looked at
+    pub next_id: String,
+    pub entries: Vec<StreamEntry>,
+    // Pending entries no longer in the stream, dropped from the pending list (Redis 7.0+)
+    pub deleted_ids: Vec<String>,
+}
+
+impl AutoClaimed {
+    pub fn is_complete(&self) -> bool {
+        self.next_id == "0-0"
+    }
+}
+
+// [next id, entries, deleted ids], Redis 6.2 sends no deleted ids
+impl FromResp3Value for AutoClaimed {
+    fn from_resp3_value(value: Resp3Value) -> Result<Self, Resp3Error> {
+        let fields = match value {
+            Resp3Value::Array(fields) if fields.len() >= 2 => fields,
+            value => return unexpected("the result of XAUTOCLAIM", &value),
+        };
+        let mut fields = fields.into_iter();
+        Ok(AutoClaimed {
+            next_id: String::from_resp3_value(fields.next().unwrap())?,
+            entries: Vec::from_resp3_value(fields.next().unwrap())?,
+            deleted_ids: fields.next().map(Vec::from_resp3_value).transpose()?.unwrap_or_default(),
+        })
+    }
+}
+
+// Gives the consumer the pending entries idle for at least min_idle, e.g. those of a consumer
+// that died, which counts as a delivery (Redis 6.2+)
+pub struct XAutoClaimCommand {
+    key: String,
+    group: String,
+    consumer: String,
+    min_idle: String,
+    start: String,
+    count: Option<String>,
+}
+
+impl XAutoClaimCommand {
+    // From the pending entry start, "0-0" for the first one, see AutoClaimed::next_id
+    pub fn new(key: String, group: String, consumer: String, min_idle: Duration, start: &str) -> Self {
+        XAutoClaimCommand { key, group, consumer, min_idle: min_idle.as_millis().to_string(), start: start.to_string(), count: None }
+    }
+
+    // How many pending entries to look at, 100 by default
+    pub fn count(mut self, count: u64) -> Self {
+        self.count = Some(count.to_string());
+        self
+    }
+}
+
+impl Command for XAutoClaimCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        let mut parts = vec!["XAUTOCLAIM", &self.key, &self.group, &self.consumer, &self.min_idle, &self.start];
+        if let Some(count) = &self.count {
+            parts.extend(["COUNT", count]);
+        }
+        parts
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![1]
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Array
+    }
+}
+
+impl TypedCommand for XAutoClaimCommand {
+    type Output = AutoClaimed;
+}
+
+pub struct XGroupDelConsumerCommand {
+    key: String,
+    group: String,
+    consumer: String,
+}
+
+impl XGroupDelConsumerCommand {
+    // Its pending entries are dropped with it, without being acknowledged
+    pub fn new(key: String, group: String, consumer: String) -> Self {
+        XGroupDelConsumerCommand { key, group, consumer }
+    }
+}
+
+impl Command for XGroupDelConsumerCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        vec!["XGROUP", "DELCONSUMER", &self.key, &self.group, &self.consumer]
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![2]
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Integer
+    }
+}
+
+impl TypedCommand for XGroupDelConsumerCommand {
+    // The number of pending entries it had
+    type Output = u64;
+}
@@ -0,0 +1,170 @@
+// src/resp3/dispatcher.rs
+
+use std::collections::HashMap;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+
+use crate::resp3::error::Resp3Error;
+use crate::resp3::pubsub::{PubSub, PubSubEvent};
+
+type Handler = Arc<dyn Fn(&PubSubEvent) + Send + Sync>;
+
+// Routes with these characters are glob patterns, subscribed to with PSUBSCRIBE
+fn is_pattern(route: &str) -> bool {
+    route.contains(['*', '?', '['])
+}
+
+// Calls handlers for the messages of a PubSub, by channel or pattern, on a pool of threads.
+// The routes are subscribed to as messages are: exact channels with SUBSCRIBE, patterns such
+// as "orders.*" with PSUBSCRIBE, the server matching them. A message published on a channel
+// matching several routes reaches each of them once, Resubscribed events reach none
+pub struct Dispatcher {
+    pubsub: PubSub,
+    routes: HashMap<String, Vec<Handler>>,
+    workers: usize,
+}
+
+impl Dispatcher {
+    pub fn new(pubsub: PubSub) -> Self {
+        Dispatcher { pubsub, routes: HashMap::new(), workers: 4 }
+    }
+
+    // Calls the handler for the messages of the channel, or of the channels matching the
+    // pattern. Several handlers may share a route
+    pub fn route<F: Fn(&PubSubEvent) + Send + Sync + 'static>(mut self, route: &str, handler: F) -> Self {
+        // Sent UPPERCASE, so the messages come back with their names UPPERCASE
+        self.routes.entry(route.to_uppercase()).or_default().push(Arc::new(handler));
+        self
+    }
+
+    // The threads running the handlers, 4 by default. With more than one, the messages of a
+    // channel may be handled out of order
+    pub fn workers(mut self, workers: usize) -> Self {
+        self.workers = workers.max(1);
+        self
+    }
+
+    // Subscribes to the routes and starts dispatching. The connection of the PubSub should have
+    // a timeout, see RedisConnectionBuilder::timeout: shutdown waits for the next message or
+    // timeout to stop reading
+    pub fn start(mut self) -> Result<RunningDispatcher, Resp3Error> {
+        let (patterns, channels): (Vec<&str>, Vec<&str>) = self.routes.keys().map(String::as_str).partition(|route| is_pattern(route));
+        self.pubsub.subscribe(&channels)?;
+        self.pubsub.psubscribe(&patterns)?;
+
+        let (sender, receiver) = mpsc::channel::<(PubSubEvent, Vec<Handler>)>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        let stopping = Arc::new(AtomicBool::new(false));
+        let counters = Arc::new(DispatchCounters::default());
+        let workers: Vec<JoinHandle<()>> = (0..self.workers).map(|_| {
+            let (receiver, counters) = (receiver.clone(), counters.clone());
+            thread::spawn(move || work(&receiver, &counters))
+        }).collect();
+
+        let (mut pubsub, routes) = (self.pubsub, self.routes);
+        let (reader_stopping, reader_counters) = (stopping.clone(), counters.clone());
+        let reader = thread::spawn(move || {
+            let result = loop {
+                if reader_stopping.load(Ordering::SeqCst) {
+                    break Ok(());
+                }
+                let event = match pubsub.next_event() {
+                    Ok(event) => event,
+                    Err(err @ Resp3Error::Io(_)) if err.is_timeout() => continue,
+                    Err(err) => break Err(err),
+                };
+                let route = match &event {
+                    PubSubEvent::Message { channel, .. } => channel,
+                    PubSubEvent::PatternMessage { pattern, .. } => pattern,
+                    PubSubEvent::Resubscribed => continue,
+                };
+                match routes.get(route) {
+                    Some(handlers) => {
+                        reader_counters.received.fetch_add(1, Ordering::SeqCst);
+                        // The workers only go away once the sender is dropped
+                        let _ = sender.send((event, handlers.clone()));
+                    }
+                    None => {
+                        reader_counters.unrouted.fetch_add(1, Ordering::SeqCst);
+                    }
+                }
+            };
+            // The workers finish the messages already queued, then stop
+            drop(sender);
+            for worker in workers {
+                let _ = worker.join();
+            }
+            result.map(|_| pubsub)
+        });
+        Ok(RunningDispatcher { reader, stopping, counters })
+    }
+}
+
+fn work(receiver: &Mutex<Receiver<(PubSubEvent, Vec<Handler>)>>, counters: &DispatchCounters) {
+    loop {
+        // The lock is only held while waiting, not while handling
+        let next = receiver.lock().unwrap().recv();
+        let Ok((event, handlers)) = next else { return };
+        for handler in handlers {
+            // A panicking handler must not take its worker down with it
+            if panic::catch_unwind(AssertUnwindSafe(|| handler(&event))).is_err() {
+                counters.panicked.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+        counters.handled.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+#[derive(Debug, Default)]
+struct DispatchCounters {
+    received: AtomicU64,
+    handled: AtomicU64,
+    unrouted: AtomicU64,
+    panicked: AtomicU64,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DispatchStats {
+    // Messages given to the workers
+    pub received: u64,
+    // Those whose handlers all ran
+    pub handled: u64,
+    // Messages of no route, e.g. of a subscription the PubSub had before
+    pub unrouted: u64,
+    // Handler calls that panicked
+    pub panicked: u64,
+}
+
+// A dispatcher reading messages on its own thread, until shutdown or a connection failure
+// it could not recover from
+pub struct RunningDispatcher {
+    reader: JoinHandle<Result<PubSub, Resp3Error>>,
+    stopping: Arc<AtomicBool>,
+    counters: Arc<DispatchCounters>,
+}
+
+impl RunningDispatcher {
+    pub fn stats(&self) -> DispatchStats {
+        DispatchStats {
+            received: self.counters.received.load(Ordering::SeqCst),
+            handled: self.counters.handled.load(Ordering::SeqCst),
+            unrouted: self.counters.unrouted.load(Ordering::SeqCst),
+            panicked: self.counters.panicked.load(Ordering::SeqCst),
+        }
+    }
+
+    // False once reading stopped, after a failure or shutdown
+    pub fn is_running(&self) -> bool {
+        !self.reader.is_finished()
+    }
+
+    // Stops reading, waits for the handlers of the messages already read, and gives the PubSub
+    // back still subscribed. Returns the error that stopped the dispatcher, if one did
+    pub fn shutdown(self) -> Result<PubSub, Resp3Error> {
+        self.stopping.store(true, Ordering::SeqCst);
+        self.reader.join().unwrap_or_else(|_| Err(Resp3Error::InvalidState("The dispatcher thread panicked".to_string())))
+    }
+}
@@ -0,0 +1,178 @@
+// src/resp3/error.rs
+
+use std::fmt;
+use std::io;
+
+#[derive(Debug)]
+pub enum Resp3Error {
+    // The connection to the Redis server failed
+    Io(io::Error),
+    // The server sent something that is not valid RESP
+    Protocol(String),
+    // The server replied with an error, e.g. "ERR unknown command"
+    Server(ServerError),
+    // The reply is valid RESP but not of the shape the caller expected
+    UnexpectedReply(String),
+    // The command cannot be sent in the current state of the connection, e.g. GET while subscribed
+    InvalidState(String),
+    // The command was rejected before it was sent, see Validation and RedisConnectionBuilder::allow_destructive
+    InvalidArgument(String),
+    // The reply does not fit the command, so replies and requests got out of step. The connection
+    // was reconnected, as every later reply would have been the one of the previous request
+    ProtocolDesync(String),
+    // The server was unreachable and the write was kept in the journal of the executor, to be
+    // replayed once it is reachable again. Holds the idempotency key of the entry
+    Journaled(String),
+    // The server does not know the command, e.g. HEXPIRE before Redis 7.4, see RedisConnection::require
+    UnsupportedCommand(String),
+    // The server is not of the role RedisConnectionBuilder::verify_role asked for, e.g. a former
+    // master demoted by a failover. Holds the endpoint, the expected role and the actual one
+    WrongRole { endpoint: String, expected: String, actual: String },
+}
+
+impl Resp3Error {
+    // The parsed reply when the server replied with an error, to branch on its code
+    pub fn server_error(&self) -> Option<&ServerError> {
+        match self {
+            Resp3Error::Server(err) => Some(err),
+            _ => None,
+        }
+    }
+
+    // The code of server errors, a name for the other variants, e.g. to count errors by kind
+    pub fn kind(&self) -> &str {
+        match self {
+            Resp3Error::Io(_) => "IO",
+            Resp3Error::Protocol(_) => "PROTOCOL",
+            Resp3Error::Server(err) if err.code == ErrorCode::None => "SERVER",
+            Resp3Error::Server(err) => err.code_name(),
+            Resp3Error::UnexpectedReply(_) => "UNEXPECTED_REPLY",
+            Resp3Error::InvalidState(_) => "INVALID_STATE",
+            Resp3Error::InvalidArgument(_) => "INVALID_ARGUMENT",
+            Resp3Error::ProtocolDesync(_) => "PROTOCOL_DESYNC",
+            Resp3Error::Journaled(_) => "JOURNALED",
+            Resp3Error::UnsupportedCommand(_) => "UNSUPPORTED_COMMAND",
+            Resp3Error::WrongRole { .. } => "WRONG_ROLE",
+        }
+    }
+
+    // A read or write that did not complete within the connection timeout
+    pub fn is_timeout(&self) -> bool {
+        match self {
+            Resp3Error::Io(err) => matches!(err.kind(), io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock),
+            _ => false,
+        }
+    }
+}
+
+// An error reply split into its code, the first word when it is uppercase, and the rest
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServerError {
+    pub code: ErrorCode,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ErrorCode {
+    Err,
+    WrongType,
+    // The slot lives on another cluster node from now on
+    Moved { slot: u16, address: String },
+    // The slot is being migrated, only the next command goes to the other node, after ASKING
+    Ask { slot: u16, address: String },
+    // The server is still loading its dataset
+    Loading,
+    // A write sent to a replica
+    ReadOnly,
+    NoAuth,
+    // Any other code, e.g. "BUSY" or "WRONGPASS"
+    Other(String),
+    // Replies that do not start with a code, e.g. from Lua's error()
+    None,
+}
+
+impl ServerError {
+    pub fn parse(reply: &str) -> Self {
+        let (word, rest) = reply.split_once(' ').unwrap_or((reply, ""));
+        let is_code = !word.is_empty() && word.bytes().all(|byte| byte.is_ascii_uppercase() || byte.is_ascii_digit() || byte == b'_');
+        if !is_code {
+            return ServerError { code: ErrorCode::None, message: reply.to_string() };
+        }
+        let redirect = || {
+            let (slot, address) = rest.split_once(' ')?;
+            Some((slot.parse().ok()?, address.to_string()))
+        };
+        let code = match word {
+            "ERR" => ErrorCode::Err,
+            "WRONGTYPE" => ErrorCode::WrongType,
+            "LOADING" => ErrorCode::Loading,
+            "READONLY" => ErrorCode::ReadOnly,
+            "NOAUTH" => ErrorCode::NoAuth,
+            "MOVED" | "ASK" => match redirect() {
+                Some((slot, address)) if word == "MOVED" => ErrorCode::Moved { slot, address },
+                Some((slot, address)) => ErrorCode::Ask { slot, address },
+                None => ErrorCode::Other(word.to_string()),
+            },
+            code => ErrorCode::Other(code.to_string()),
+        };
+        ServerError { code, message: rest.to_string() }
+    }
+
+    // The code as the server sent it, empty when there was none
+    pub fn code_name(&self) -> &str {
+        match &self.code {
+            ErrorCode::Err => "ERR",
+            ErrorCode::WrongType => "WRONGTYPE",
+            ErrorCode::Moved { .. } => "MOVED",
+            ErrorCode::Ask { .. } => "ASK",
+            ErrorCode::Loading => "LOADING",
+            ErrorCode::ReadOnly => "READONLY",
+            ErrorCode::NoAuth => "NOAUTH",
+            ErrorCode::Other(code) => code,
+            ErrorCode::None => "",
+        }
+    }
+}
+
+// The reply as the server sent it
+impl fmt::Display for ServerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.code_name(), self.message.as_str()) {
+            ("", message) => write!(f, "{}", message),
+            (code, "") => write!(f, "{}", code),
+            (code, message) => write!(f, "{} {}", code, message),
+        }
+    }
+}
+
+impl fmt::Display for Resp3Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Resp3Error::Io(err) => write!(f, "I/O error: {}", err),
+            Resp3Error::Protocol(message) => write!(f, "Protocol error: {}", message),
+            Resp3Error::Server(err) => write!(f, "Server error: {}", err),
+            Resp3Error::UnexpectedReply(message) => write!(f, "Unexpected reply: {}", message),
+            Resp3Error::InvalidState(message) => write!(f, "Invalid connection state: {}", message),
+            Resp3Error::InvalidArgument(message) => write!(f, "Invalid argument: {}", message),
+            Resp3Error::ProtocolDesync(message) => write!(f, "Protocol desync: {}", message),
+            Resp3Error::Journaled(id) => write!(f, "Server unreachable, write journaled as {}", id),
+            Resp3Error::UnsupportedCommand(command) => write!(f, "The server does not support {}", command),
+            Resp3Error::WrongRole { endpoint, expected, actual } => write!(f, "Expected a {} at {}, found a {}", expected, endpoint, actual),
+        }
+    }
+}
+
+impl std::error::Error for Resp3Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Resp3Error::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Resp3Error {
+    fn from(err: io::Error) -> Self {
+        Resp3Error::Io(err)
+    }
+}
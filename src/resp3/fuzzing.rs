@@ -0,0 +1,30 @@
+// src/resp3/fuzzing.rs
+
+use crate::resp3::parser::{parse_frame, parse_response};
+
+// Prefixes are only all checked on inputs up to this size, checking them is quadratic
+const MAX_PREFIX_CHECKED_INPUT: usize = 1024;
+
+// Entry point of the fuzz targets (see fuzz/). Feeds the bytes to the parser as a reply, panicking
+// only when an invariant does not hold: a frame never claims more bytes than it was given,
+// every shorter prefix of a complete frame is incomplete rather than an error or another
+// frame, and parse_response agrees with parse_frame
+pub fn parse_reply(data: &[u8]) {
+    let frame = parse_frame(data);
+    match (&frame, parse_response(data)) {
+        // Compared encoded, a nan double is not equal to itself
+        (Ok(Some((value, _))), Ok(response)) => assert_eq!(value.to_bytes(), response.to_bytes(), "parse_response disagrees with parse_frame"),
+        (Ok(None), Err(_)) | (Err(_), Err(_)) => {}
+        (frame, response) => panic!("parse_frame returned {:?} but parse_response {:?}", frame, response),
+    }
+
+    if let Ok(Some((_, length))) = frame {
+        assert!(length <= data.len(), "Frame of {} bytes out of {}", length, data.len());
+        if data.len() <= MAX_PREFIX_CHECKED_INPUT {
+            for end in 0..length {
+                let prefix = parse_frame(&data[..end]);
+                assert!(matches!(prefix, Ok(None)), "Prefix of {} bytes parsed as {:?}", end, prefix);
+            }
+        }
+    }
+}
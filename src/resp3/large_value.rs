@@ -0,0 +1,194 @@
+// src/resp3/large_value.rs
+
+use std::time::Duration;
+
+use crate::resp3::commands::get::GetCommand;
+use crate::resp3::commands::keys::DelCommand;
+use crate::resp3::commands::set::SetCommand;
+use crate::resp3::error::{Resp3Error, ServerError};
+use crate::resp3::utils::command::TypedCommand;
+use crate::resp3::utils::prepared::PreparedCommand;
+use crate::resp3::utils::redis_connection::RedisConnection;
+use crate::resp3::utils::token::generate_token;
+use crate::resp3::value::Resp3Value;
+
+// Below the 1 MB most proxies cap a request or a reply at, with room for the framing
+const DEFAULT_CHUNK_SIZE: usize = 512 * 1024;
+const MANIFEST_VERSION: &str = "LV1";
+// A read racing with writes starts over on the new manifest this many times before failing
+const READ_ATTEMPTS: usize = 3;
+
+// What the key of a large value holds: where its chunks are and what they add up to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Manifest {
+    // Random per write, so a write never touches the chunks a reader may still be fetching
+    pub generation: String,
+    pub chunks: usize,
+    pub length: usize,
+    // CRC-32 (IEEE) of the whole value
+    pub checksum: u32,
+}
+
+impl Manifest {
+    fn encode(&self) -> String {
+        format!("{} {} {} {} {:08x}", MANIFEST_VERSION, self.generation, self.chunks, self.length, self.checksum)
+    }
+
+    fn parse(manifest: &str) -> Result<Self, Resp3Error> {
+        let invalid = || Resp3Error::UnexpectedReply(format!("Invalid large value manifest: {}", manifest));
+        match manifest.split(' ').collect::<Vec<_>>()[..] {
+            [MANIFEST_VERSION, generation, chunks, length, checksum] => Ok(Manifest {
+                generation: generation.to_string(),
+                chunks: chunks.parse().map_err(|_| invalid())?,
+                length: length.parse().map_err(|_| invalid())?,
+                checksum: u32::from_str_radix(checksum, 16).map_err(|_| invalid())?,
+            }),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+// A value too large for one request split over chunk keys, e.g. LargeValue::new("report:2024").chunk_size(256 * 1024).
+// The key holds a manifest and the chunks go to key:chunk:generation:index, written before the
+// manifest and deleted after the next one replaces it, so readers see the old value or the new
+// one, never a mix. Reads check the length and the checksum of what they put back together.
+// Keys are sent UPPERCASE like every other part, the chunks and the manifest as they are
+pub struct LargeValue {
+    key: String,
+    chunk_size: usize,
+    ttl: Option<Duration>,
+}
+
+impl LargeValue {
+    pub fn new(key: &str) -> Self {
+        LargeValue { key: key.to_string(), chunk_size: DEFAULT_CHUNK_SIZE, ttl: None }
+    }
+
+    // The largest chunk in bytes, at least 1
+    pub fn chunk_size(mut self, bytes: usize) -> Self {
+        self.chunk_size = bytes.max(1);
+        self
+    }
+
+    // Expires the manifest and the chunks together, rounded to the millisecond
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    pub fn chunk_key(&self, generation: &str, index: usize) -> String {
+        format!("{}:chunk:{}:{}", self.key, generation, index)
+    }
+
+    // Replaces the value, the chunks pipelined in one round trip, and returns the new manifest
+    pub fn write(&self, conn: &mut RedisConnection, value: &[u8]) -> Result<Manifest, Resp3Error> {
+        let previous = self.manifest(conn)?;
+        let manifest = Manifest {
+            generation: generate_token(),
+            chunks: value.len().div_ceil(self.chunk_size),
+            length: value.len(),
+            checksum: crc32(value),
+        };
+
+        let ttl = self.ttl.map(|ttl| ttl.as_millis().max(1).to_string());
+        let mut set = PreparedCommand::new("SET").argument().verbatim_argument();
+        if let Some(ttl) = &ttl {
+            set = set.token("PX").token(ttl);
+        }
+        let mut commands = Vec::with_capacity(value.len() + manifest.chunks * 64);
+        for (index, chunk) in value.chunks(self.chunk_size).enumerate() {
+            set.encode_into(&mut commands, &[self.chunk_key(&manifest.generation, index).as_bytes(), chunk])?;
+        }
+        for reply in conn.request_pipelined(&commands, manifest.chunks)? {
+            if let Resp3Value::Error(message) = reply {
+                return Err(Resp3Error::Server(ServerError::parse(&message)));
+            }
+        }
+
+        let mut command = SetCommand::new(self.key.clone(), manifest.encode()).verbatim_value();
+        if let Some(ttl) = self.ttl {
+            command = command.px(ttl.as_millis().max(1) as u64);
+        }
+        command.query(conn)?;
+
+        if let Some(previous) = previous {
+            self.delete_chunks(conn, &previous)?;
+        }
+        Ok(manifest)
+    }
+
+    // The value put back together, None when the key does not exist
+    pub fn read(&self, conn: &mut RedisConnection) -> Result<Option<Vec<u8>>, Resp3Error> {
+        let get = PreparedCommand::new("GET").argument();
+        let mut attempt = 1;
+        loop {
+            let Some(manifest) = self.manifest(conn)? else { return Ok(None) };
+            let mut commands = Vec::with_capacity(manifest.chunks * 64);
+            for index in 0..manifest.chunks {
+                get.encode_into(&mut commands, &[self.chunk_key(&manifest.generation, index).as_bytes()])?;
+            }
+
+            let mut value = Vec::with_capacity(manifest.length);
+            let mut missing = None;
+            for (index, reply) in conn.request_pipelined(&commands, manifest.chunks)?.into_iter().enumerate() {
+                match reply {
+                    Resp3Value::BulkString(chunk) => value.extend_from_slice(&chunk),
+                    Resp3Value::Null => {
+                        missing = Some(index);
+                        break;
+                    }
+                    Resp3Value::Error(message) => return Err(Resp3Error::Server(ServerError::parse(&message))),
+                    reply => return Err(Resp3Error::UnexpectedReply(format!("Expected a chunk, got {:?}", reply))),
+                }
+            }
+
+            if let Some(index) = missing {
+                // Replaced while being read: the chunks went away with the manifest they belonged to
+                if attempt < READ_ATTEMPTS && self.manifest(conn)?.is_some_and(|current| current.generation != manifest.generation) {
+                    attempt += 1;
+                    continue;
+                }
+                return Err(Resp3Error::UnexpectedReply(format!("Chunk {} of {} is missing", index, self.key)));
+            }
+            if value.len() != manifest.length || crc32(&value) != manifest.checksum {
+                return Err(Resp3Error::UnexpectedReply(format!("Large value {} does not match its manifest", self.key)));
+            }
+            return Ok(Some(value));
+        }
+    }
+
+    // None when the key does not exist
+    pub fn manifest(&self, conn: &mut RedisConnection) -> Result<Option<Manifest>, Resp3Error> {
+        GetCommand::new(self.key.clone()).query(conn)?.map(|manifest| Manifest::parse(&manifest)).transpose()
+    }
+
+    // Deletes the manifest then the chunks, false when the key did not exist
+    pub fn delete(&self, conn: &mut RedisConnection) -> Result<bool, Resp3Error> {
+        let Some(manifest) = self.manifest(conn)? else { return Ok(false) };
+        DelCommand::new([self.key.clone()]).query(conn)?;
+        self.delete_chunks(conn, &manifest)?;
+        Ok(true)
+    }
+
+    fn delete_chunks(&self, conn: &mut RedisConnection, manifest: &Manifest) -> Result<(), Resp3Error> {
+        // Bounded batches, a DEL of thousands of keys is a request of its own over the proxy limits
+        let keys: Vec<String> = (0..manifest.chunks).map(|index| self.chunk_key(&manifest.generation, index)).collect();
+        for batch in keys.chunks(1000) {
+            DelCommand::new(batch.iter().cloned()).query(conn)?;
+        }
+        Ok(())
+    }
+}
+
+// CRC-32 with the IEEE polynomial, the one of zlib and Ethernet, bit by bit as values are
+// checked once per read
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in bytes {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xEDB8_8320 & (crc & 1).wrapping_neg());
+        }
+    }
+    !crc
+}
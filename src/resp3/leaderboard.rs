@@ -0,0 +1,115 @@
+// src/resp3/leaderboard.rs
+
+use std::marker::PhantomData;
+
+use crate::resp3::commands::sorted_set::{ZAddCommand, ZCardCommand, ZIncrByCommand, ZRangeWithScoresCommand, ZRankCommand, ZRemCommand, ZScoreCommand};
+use crate::resp3::error::Resp3Error;
+use crate::resp3::utils::command::TypedCommand;
+use crate::resp3::utils::redis_connection::RedisConnection;
+
+// The numeric types scores can be read as. Sorted sets keep doubles, so integers are exact
+// up to 2^53 and the conversions back saturate
+pub trait Score: Copy {
+    fn to_f64(self) -> f64;
+
+    fn from_f64(score: f64) -> Self;
+}
+
+macro_rules! impl_score {
+    ($($number:ty),*) => {
+        $(impl Score for $number {
+            fn to_f64(self) -> f64 {
+                self as f64
+            }
+
+            fn from_f64(score: f64) -> Self {
+                score as $number
+            }
+        })*
+    };
+}
+
+impl_score!(i32, i64, u32, u64, f32, f64);
+
+// A member with its place, rank 0 being the highest score
+#[derive(Debug, Clone, PartialEq)]
+pub struct LeaderboardEntry<S> {
+    pub rank: u64,
+    pub member: String,
+    pub score: S,
+}
+
+// Highest scores first on a sorted set, e.g. Leaderboard::<u64>::new("game:scores").
+// Members are sent UPPERCASE like every other part
+pub struct Leaderboard<S: Score> {
+    key: String,
+    score: PhantomData<S>,
+}
+
+impl<S: Score> Leaderboard<S> {
+    pub fn new(key: &str) -> Self {
+        Leaderboard { key: key.to_string(), score: PhantomData }
+    }
+
+    // Adds to the score of the member, starting from zero, and returns the new score
+    pub fn add_score(&self, conn: &mut RedisConnection, member: &str, points: S) -> Result<S, Resp3Error> {
+        let score = ZIncrByCommand::new(self.key.clone(), member.to_string(), points.to_f64()).query(conn)?;
+        Ok(S::from_f64(score))
+    }
+
+    // Replaces the score of the member, or only raises it with only_if_higher (e.g. best lap times
+    // kept as negative numbers)
+    pub fn set_score(&self, conn: &mut RedisConnection, member: &str, score: S, only_if_higher: bool) -> Result<(), Resp3Error> {
+        let mut command = ZAddCommand::new(self.key.clone(), member.to_string(), score.to_f64());
+        if only_if_higher {
+            command = command.gt();
+        }
+        command.query(conn)?;
+        Ok(())
+    }
+
+    pub fn score_of(&self, conn: &mut RedisConnection, member: &str) -> Result<Option<S>, Resp3Error> {
+        let score = ZScoreCommand::new(self.key.clone(), member.to_string()).query(conn)?;
+        Ok(score.map(S::from_f64))
+    }
+
+    // None when the member has no score
+    pub fn rank_of(&self, conn: &mut RedisConnection, member: &str) -> Result<Option<u64>, Resp3Error> {
+        ZRankCommand::new(self.key.clone(), member.to_string()).reverse().query(conn)
+    }
+
+    pub fn top_n(&self, conn: &mut RedisConnection, count: u64) -> Result<Vec<LeaderboardEntry<S>>, Resp3Error> {
+        if count == 0 {
+            return Ok(Vec::new());
+        }
+        self.range(conn, 0, count - 1)
+    }
+
+    // The member with up to radius members ranked above and below it, empty when the member
+    // has no score. The rank and the range are read with two commands, so a concurrent
+    // update may shift the window by a few places
+    pub fn around_member(&self, conn: &mut RedisConnection, member: &str, radius: u64) -> Result<Vec<LeaderboardEntry<S>>, Resp3Error> {
+        match self.rank_of(conn, member)? {
+            Some(rank) => self.range(conn, rank.saturating_sub(radius), rank.saturating_add(radius)),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    // Returns false if the member had no score
+    pub fn remove(&self, conn: &mut RedisConnection, member: &str) -> Result<bool, Resp3Error> {
+        Ok(ZRemCommand::new(self.key.clone(), vec![member.to_string()]).query(conn)? > 0)
+    }
+
+    // The number of members with a score
+    pub fn len(&self, conn: &mut RedisConnection) -> Result<u64, Resp3Error> {
+        ZCardCommand::new(self.key.clone()).query(conn)
+    }
+
+    fn range(&self, conn: &mut RedisConnection, start: u64, stop: u64) -> Result<Vec<LeaderboardEntry<S>>, Resp3Error> {
+        let stop = i64::try_from(stop).unwrap_or(i64::MAX);
+        let entries = ZRangeWithScoresCommand::new(self.key.clone(), start as i64, stop).reverse().query(conn)?;
+        Ok(entries.into_iter().enumerate().map(|(index, (member, score))| {
+            LeaderboardEntry { rank: start + index as u64, member, score: S::from_f64(score) }
+        }).collect())
+    }
+}
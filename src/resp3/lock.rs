@@ -0,0 +1,181 @@
+// src/resp3/lock.rs
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::resp3::commands::eval::EvalCommand;
+use crate::resp3::commands::set::SetCommand;
+use crate::resp3::protocol::{CRLF, INTEGER_PREFIX, SIMPLE_STRING_PREFIX};
+use crate::resp3::utils::command::Command;
+use crate::resp3::utils::redis_connection::RedisConnection;
+use crate::resp3::utils::token::generate_token;
+
+// Deletes the key only if it still holds our token
+const RELEASE_SCRIPT: &str = "if redis.call('get', KEYS[1]) == ARGV[1] then return redis.call('del', KEYS[1]) else return 0 end";
+
+// Resets the expiration only if the key still holds our token
+const EXTEND_SCRIPT: &str = "if redis.call('get', KEYS[1]) == ARGV[1] then return redis.call('pexpire', KEYS[1], ARGV[2]) else return 0 end";
+
+// Clock drift allowance as suggested by the Redlock algorithm: 1% of the TTL plus 2 milliseconds
+const CLOCK_DRIFT_FACTOR: f64 = 0.01;
+const CLOCK_DRIFT_MIN: Duration = Duration::from_millis(2);
+
+// A lock held on one or more Redis instances, identified by a random token
+pub struct Lock {
+    key: String,
+    token: String,
+    validity: Duration,
+}
+
+impl Lock {
+    // Acquires a lock on a single instance with SET key token NX PX ttl
+    pub fn acquire(conn: &mut RedisConnection, key: &str, ttl: Duration) -> Option<Lock> {
+        let token = generate_token();
+        if lock_instance(conn, key, &token, ttl) {
+            Some(Lock { key: key.to_string(), token, validity: ttl })
+        } else {
+            None
+        }
+    }
+
+    // Releases the lock, returns false if it had already expired or was taken by someone else
+    pub fn release(&self, conn: &mut RedisConnection) -> bool {
+        unlock_instance(conn, &self.key, &self.token)
+    }
+
+    // Resets the lock TTL, returns false if the lock is no longer ours
+    pub fn extend(&mut self, conn: &mut RedisConnection, ttl: Duration) -> bool {
+        if extend_instance(conn, &self.key, &self.token, ttl) {
+            self.validity = ttl;
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn token(&self) -> &str {
+        &self.token
+    }
+
+    // How long the lock can be considered held, measured from the moment it was acquired or extended
+    pub fn validity(&self) -> Duration {
+        self.validity
+    }
+}
+
+// Redlock spans a lock over multiple independent Redis instances, a lock is held
+// only when the majority of them granted it within its TTL
+pub struct Redlock {
+    connections: Vec<RedisConnection>,
+    retry_count: u32,
+    retry_delay: Duration,
+}
+
+impl Redlock {
+    pub fn new(connections: Vec<RedisConnection>) -> Self {
+        Redlock { connections, retry_count: 3, retry_delay: Duration::from_millis(200) }
+    }
+
+    pub fn with_retry(mut self, retry_count: u32, retry_delay: Duration) -> Self {
+        self.retry_count = retry_count;
+        self.retry_delay = retry_delay;
+        self
+    }
+
+    pub fn quorum(&self) -> usize {
+        self.connections.len() / 2 + 1
+    }
+
+    pub fn acquire(&mut self, key: &str, ttl: Duration) -> Option<Lock> {
+        for attempt in 0..=self.retry_count {
+            let token = generate_token();
+            let start = Instant::now();
+            let locked = self.connections.iter_mut()
+                .map(|conn| lock_instance(conn, key, &token, ttl))
+                .filter(|locked| *locked)
+                .count();
+
+            let elapsed = start.elapsed();
+            let drift = ttl.mul_f64(CLOCK_DRIFT_FACTOR) + CLOCK_DRIFT_MIN;
+            if locked >= self.quorum() && ttl > elapsed + drift {
+                return Some(Lock { key: key.to_string(), token, validity: ttl - elapsed - drift });
+            }
+
+            // Not enough instances, or it took too long: unlock everywhere, even where it seemed to fail
+            for conn in self.connections.iter_mut() {
+                unlock_instance(conn, key, &token);
+            }
+            if attempt < self.retry_count {
+                thread::sleep(jitter(self.retry_delay));
+            }
+        }
+        None
+    }
+
+    pub fn release(&mut self, lock: &Lock) -> bool {
+        let released = self.connections.iter_mut()
+            .map(|conn| unlock_instance(conn, &lock.key, &lock.token))
+            .filter(|released| *released)
+            .count();
+        released >= self.quorum()
+    }
+
+    pub fn extend(&mut self, lock: &mut Lock, ttl: Duration) -> bool {
+        let start = Instant::now();
+        let extended = self.connections.iter_mut()
+            .map(|conn| extend_instance(conn, &lock.key, &lock.token, ttl))
+            .filter(|extended| *extended)
+            .count();
+
+        let elapsed = start.elapsed();
+        let drift = ttl.mul_f64(CLOCK_DRIFT_FACTOR) + CLOCK_DRIFT_MIN;
+        if extended >= self.quorum() && ttl > elapsed + drift {
+            lock.validity = ttl - elapsed - drift;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+fn lock_instance(conn: &mut RedisConnection, key: &str, token: &str, ttl: Duration) -> bool {
+    let set_command = SetCommand::new(key.to_string(), token.to_string())
+        .nx()
+        .px(ttl.as_millis() as u64);
+    let response = set_command.process_command(conn);
+    response == String::new() + SIMPLE_STRING_PREFIX + "OK" + CRLF
+}
+
+fn unlock_instance(conn: &mut RedisConnection, key: &str, token: &str) -> bool {
+    let eval_command = EvalCommand::new(
+        RELEASE_SCRIPT.to_string(),
+        vec![key.to_string()],
+        vec![token.to_string()],
+    );
+    let response = eval_command.process_command(conn);
+    response == String::new() + INTEGER_PREFIX + "1" + CRLF
+}
+
+fn extend_instance(conn: &mut RedisConnection, key: &str, token: &str, ttl: Duration) -> bool {
+    let eval_command = EvalCommand::new(
+        EXTEND_SCRIPT.to_string(),
+        vec![key.to_string()],
+        vec![token.to_string(), ttl.as_millis().to_string()],
+    );
+    let response = eval_command.process_command(conn);
+    response == String::new() + INTEGER_PREFIX + "1" + CRLF
+}
+
+// Random delay between half and the full retry delay, so competing clients don't retry in lockstep
+fn jitter(delay: Duration) -> Duration {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u128(delay.as_nanos());
+    let fraction = (hasher.finish() % 1000) as f64 / 1000.0;
+    delay.mul_f64(0.5 + fraction / 2.0)
+}
@@ -0,0 +1,367 @@
+use crate::resp3::utils::command::{Command, ReplyKind, TypedCommand};
+
+pub struct BfReserveCommand {
+    key: String,
+    error_rate: String,
+    capacity: String,
+}
+
+impl BfReserveCommand {
+    // Creates a Bloom filter for about capacity items with the given false positive rate,
+    // filters created by BF.ADD use the server defaults instead
+    pub fn new(key: String, error_rate: f64, capacity: u64) -> Self {
+        BfReserveCommand { key, error_rate: error_rate.to_string(), capacity: capacity.to_string() }
+    }
+}
+
+impl Command for BfReserveCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        vec!["BF.RESERVE", &self.key, &self.error_rate, &self.capacity]
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![1]
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Status
+    }
+}
+
+impl TypedCommand for BfReserveCommand {
+    type Output = bool;
+}
+
+pub struct BfAddCommand {
+    key: String,
+    item: String,
+}
+
+impl BfAddCommand {
+    pub fn new(key: String, item: String) -> Self {
+        BfAddCommand { key, item }
+    }
+}
+
+impl Command for BfAddCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        vec!["BF.ADD", &self.key, &self.item]
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![1]
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Integer
+    }
+}
+
+impl TypedCommand for BfAddCommand {
+    // False when the item may already have been added
+    type Output = bool;
+}
+
+pub struct BfMAddCommand {
+    key: String,
+    items: Vec<String>,
+}
+
+impl BfMAddCommand {
+    pub fn new(key: String, items: Vec<String>) -> Self {
+        BfMAddCommand { key, items }
+    }
+}
+
+impl Command for BfMAddCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        let mut parts = vec!["BF.MADD", &self.key];
+        parts.extend(self.items.iter().map(String::as_str));
+        parts
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![1]
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Array
+    }
+}
+
+impl TypedCommand for BfMAddCommand {
+    // One per item, in the same order
+    type Output = Vec<bool>;
+}
+
+pub struct BfExistsCommand {
+    key: String,
+    item: String,
+}
+
+impl BfExistsCommand {
+    pub fn new(key: String, item: String) -> Self {
+        BfExistsCommand { key, item }
+    }
+}
+
+impl Command for BfExistsCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        vec!["BF.EXISTS", &self.key, &self.item]
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![1]
+    }
+
+    fn is_readonly(&self) -> bool {
+        true
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Integer
+    }
+}
+
+impl TypedCommand for BfExistsCommand {
+    // False means certainly not added, true means probably added
+    type Output = bool;
+}
+
+pub struct BfMExistsCommand {
+    key: String,
+    items: Vec<String>,
+}
+
+impl BfMExistsCommand {
+    pub fn new(key: String, items: Vec<String>) -> Self {
+        BfMExistsCommand { key, items }
+    }
+}
+
+impl Command for BfMExistsCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        let mut parts = vec!["BF.MEXISTS", &self.key];
+        parts.extend(self.items.iter().map(String::as_str));
+        parts
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![1]
+    }
+
+    fn is_readonly(&self) -> bool {
+        true
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Array
+    }
+}
+
+impl TypedCommand for BfMExistsCommand {
+    // One per item, in the same order
+    type Output = Vec<bool>;
+}
+
+pub struct CfReserveCommand {
+    key: String,
+    capacity: String,
+}
+
+impl CfReserveCommand {
+    // Creates a cuckoo filter for about capacity items
+    pub fn new(key: String, capacity: u64) -> Self {
+        CfReserveCommand { key, capacity: capacity.to_string() }
+    }
+}
+
+impl Command for CfReserveCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        vec!["CF.RESERVE", &self.key, &self.capacity]
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![1]
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Status
+    }
+}
+
+impl TypedCommand for CfReserveCommand {
+    type Output = bool;
+}
+
+pub struct CfAddCommand {
+    name: &'static str,
+    key: String,
+    item: String,
+}
+
+impl CfAddCommand {
+    // Adds the item, even if it was already added, cuckoo filters count the duplicates
+    pub fn new(key: String, item: String) -> Self {
+        CfAddCommand { name: "CF.ADD", key, item }
+    }
+
+    // CF.ADDNX, only adds the item if it does not exist yet
+    pub fn if_not_exists(key: String, item: String) -> Self {
+        CfAddCommand { name: "CF.ADDNX", key, item }
+    }
+}
+
+impl Command for CfAddCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        vec![self.name, &self.key, &self.item]
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![1]
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Integer
+    }
+}
+
+impl TypedCommand for CfAddCommand {
+    // False when CF.ADDNX found the item
+    type Output = bool;
+}
+
+pub struct CfExistsCommand {
+    key: String,
+    item: String,
+}
+
+impl CfExistsCommand {
+    pub fn new(key: String, item: String) -> Self {
+        CfExistsCommand { key, item }
+    }
+}
+
+impl Command for CfExistsCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        vec!["CF.EXISTS", &self.key, &self.item]
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![1]
+    }
+
+    fn is_readonly(&self) -> bool {
+        true
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Integer
+    }
+}
+
+impl TypedCommand for CfExistsCommand {
+    // False means certainly not added, true means probably added
+    type Output = bool;
+}
+
+pub struct CfMExistsCommand {
+    key: String,
+    items: Vec<String>,
+}
+
+impl CfMExistsCommand {
+    pub fn new(key: String, items: Vec<String>) -> Self {
+        CfMExistsCommand { key, items }
+    }
+}
+
+impl Command for CfMExistsCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        let mut parts = vec!["CF.MEXISTS", &self.key];
+        parts.extend(self.items.iter().map(String::as_str));
+        parts
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![1]
+    }
+
+    fn is_readonly(&self) -> bool {
+        true
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Array
+    }
+}
+
+impl TypedCommand for CfMExistsCommand {
+    // One per item, in the same order
+    type Output = Vec<bool>;
+}
+
+pub struct CfDelCommand {
+    key: String,
+    item: String,
+}
+
+impl CfDelCommand {
+    // Deletes one occurrence of the item, unlike Bloom filters cuckoo filters support it
+    pub fn new(key: String, item: String) -> Self {
+        CfDelCommand { key, item }
+    }
+}
+
+impl Command for CfDelCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        vec!["CF.DEL", &self.key, &self.item]
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![1]
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Integer
+    }
+}
+
+impl TypedCommand for CfDelCommand {
+    // False when the item was not found
+    type Output = bool;
+}
+
+pub struct CfCountCommand {
+    key: String,
+    item: String,
+}
+
+impl CfCountCommand {
+    pub fn new(key: String, item: String) -> Self {
+        CfCountCommand { key, item }
+    }
+}
+
+impl Command for CfCountCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        vec!["CF.COUNT", &self.key, &self.item]
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![1]
+    }
+
+    fn is_readonly(&self) -> bool {
+        true
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Integer
+    }
+}
+
+impl TypedCommand for CfCountCommand {
+    // Estimated number of times the item was added
+    type Output = u64;
+}
@@ -0,0 +1,176 @@
+use std::marker::PhantomData;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::resp3::error::Resp3Error;
+use crate::resp3::utils::command::{Command, ReplyKind, TypedCommand};
+use crate::resp3::value::{FromResp3Value, Resp3Value};
+
+const KEY_INDEX: usize = 1;
+
+// JSONPath expressions and JSON documents are case sensitive, only the command name and
+// the key go through the uppercasing
+fn is_verbatim_json_part(index: usize) -> bool {
+    index > KEY_INDEX
+}
+
+// A JSON reply decoded with serde
+#[derive(Debug, Clone, PartialEq)]
+pub struct Json<T>(pub T);
+
+impl<T: DeserializeOwned> FromResp3Value for Json<T> {
+    fn from_resp3_value(value: Resp3Value) -> Result<Self, Resp3Error> {
+        let text = String::from_resp3_value(value)?;
+        serde_json::from_str(&text)
+            .map(Json)
+            .map_err(|err| Resp3Error::UnexpectedReply(format!("Invalid JSON reply: {}", err)))
+    }
+}
+
+pub struct JsonSetCommand {
+    key: String,
+    path: String,
+    json: String,
+    condition: Option<&'static str>,
+}
+
+impl JsonSetCommand {
+    // Sets the JSON text at the path, "$" for the whole document
+    pub fn new(key: String, path: String, json: String) -> Self {
+        JsonSetCommand { key, path, json, condition: None }
+    }
+
+    // Serializes the value with serde
+    pub fn from_value<T: Serialize>(key: String, path: String, value: &T) -> Result<Self, serde_json::Error> {
+        Ok(Self::new(key, path, serde_json::to_string(value)?))
+    }
+
+    // Only set the path if it does not already exist
+    pub fn nx(mut self) -> Self {
+        self.condition = Some("NX");
+        self
+    }
+
+    // Only set the path if it already exists
+    pub fn xx(mut self) -> Self {
+        self.condition = Some("XX");
+        self
+    }
+}
+
+impl Command for JsonSetCommand {
+    fn is_verbatim(&self, index: usize) -> bool {
+        is_verbatim_json_part(index)
+    }
+
+    fn get_parts(&self) -> Vec<&str> {
+        let mut parts = vec!["JSON.SET", &self.key, &self.path, &self.json];
+        parts.extend(self.condition);
+        parts
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![1]
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Status
+    }
+}
+
+impl TypedCommand for JsonSetCommand {
+    // False when NX or XX prevented the write
+    type Output = bool;
+}
+
+// Gets the document or the given paths, decoded into T. With a JSONPath ("$..") path Redis
+// replies with the array of the matches, with several paths with an object keyed by path
+pub struct JsonGetCommand<T = serde_json::Value> {
+    key: String,
+    paths: Vec<String>,
+    output: PhantomData<T>,
+}
+
+impl<T: DeserializeOwned> JsonGetCommand<T> {
+    pub fn new(key: String) -> Self {
+        JsonGetCommand { key, paths: Vec::new(), output: PhantomData }
+    }
+
+    pub fn path(mut self, path: String) -> Self {
+        self.paths.push(path);
+        self
+    }
+}
+
+impl<T> Command for JsonGetCommand<T> {
+    fn is_verbatim(&self, index: usize) -> bool {
+        is_verbatim_json_part(index)
+    }
+
+    fn get_parts(&self) -> Vec<&str> {
+        let mut parts = vec!["JSON.GET", &self.key];
+        parts.extend(self.paths.iter().map(String::as_str));
+        parts
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![1]
+    }
+
+    fn is_readonly(&self) -> bool {
+        true
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Bulk
+    }
+}
+
+impl<T: DeserializeOwned> TypedCommand for JsonGetCommand<T> {
+    // None when the key does not exist
+    type Output = Option<Json<T>>;
+}
+
+pub struct JsonDelCommand {
+    key: String,
+    path: Option<String>,
+}
+
+impl JsonDelCommand {
+    // Deletes the whole document
+    pub fn new(key: String) -> Self {
+        JsonDelCommand { key, path: None }
+    }
+
+    // Deletes only the values matching the path
+    pub fn path(mut self, path: String) -> Self {
+        self.path = Some(path);
+        self
+    }
+}
+
+impl Command for JsonDelCommand {
+    fn is_verbatim(&self, index: usize) -> bool {
+        is_verbatim_json_part(index)
+    }
+
+    fn get_parts(&self) -> Vec<&str> {
+        let mut parts = vec!["JSON.DEL", &self.key];
+        parts.extend(self.path.as_deref());
+        parts
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![1]
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Integer
+    }
+}
+
+impl TypedCommand for JsonDelCommand {
+    // Number of values deleted
+    type Output = u64;
+}
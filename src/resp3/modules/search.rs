@@ -0,0 +1,466 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::resp3::error::Resp3Error;
+use crate::resp3::utils::command::{Command, ReplyKind, TypedCommand};
+use crate::resp3::value::{unexpected, FromResp3Value, Resp3Map, Resp3Value};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FieldType {
+    Text,
+    Numeric,
+    Tag,
+}
+
+impl FieldType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FieldType::Text => "TEXT",
+            FieldType::Numeric => "NUMERIC",
+            FieldType::Tag => "TAG",
+        }
+    }
+}
+
+pub struct FtCreateCommand {
+    index: String,
+    on: &'static str,
+    prefix_count: String,
+    prefixes: Vec<String>,
+    schema: Vec<String>,
+    // The positions in schema of the field names
+    names: Vec<usize>,
+}
+
+impl FtCreateCommand {
+    // Indexes the hashes whose key matches one of the prefixes, all of them without a prefix
+    pub fn new(index: String) -> Self {
+        FtCreateCommand { index, on: "HASH", prefix_count: "0".to_string(), prefixes: Vec::new(), schema: Vec::new(), names: Vec::new() }
+    }
+
+    // Indexes JSON documents instead, field names are then JSONPath expressions, sent as they are
+    pub fn on_json(mut self) -> Self {
+        self.on = "JSON";
+        self
+    }
+
+    pub fn prefix(mut self, prefix: String) -> Self {
+        self.prefixes.push(prefix);
+        self.prefix_count = self.prefixes.len().to_string();
+        self
+    }
+
+    pub fn field(mut self, name: String, field_type: FieldType) -> Self {
+        self.names.push(self.schema.len());
+        self.schema.push(name);
+        self.schema.push(field_type.as_str().to_string());
+        self
+    }
+
+    // A field the results can be sorted by
+    pub fn sortable_field(self, name: String, field_type: FieldType) -> Self {
+        let mut command = self.field(name, field_type);
+        command.schema.push("SORTABLE".to_string());
+        command
+    }
+}
+
+impl Command for FtCreateCommand {
+    // JSONPath is case sensitive, the hash field names are uppercased like the values HSET sends
+    fn is_verbatim(&self, index: usize) -> bool {
+        let schema_start = if self.prefixes.is_empty() { 5 } else { 7 + self.prefixes.len() };
+        self.on == "JSON" && index >= schema_start && self.names.contains(&(index - schema_start))
+    }
+
+    fn get_parts(&self) -> Vec<&str> {
+        let mut parts = vec!["FT.CREATE", &self.index, "ON", self.on];
+        if !self.prefixes.is_empty() {
+            parts.push("PREFIX");
+            parts.push(&self.prefix_count);
+            parts.extend(self.prefixes.iter().map(String::as_str));
+        }
+        parts.push("SCHEMA");
+        parts.extend(self.schema.iter().map(String::as_str));
+        parts
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Status
+    }
+}
+
+impl TypedCommand for FtCreateCommand {
+    type Output = bool;
+}
+
+pub struct FtDropIndexCommand {
+    index: String,
+    delete_documents: bool,
+}
+
+impl FtDropIndexCommand {
+    // Drops the index, keeping the indexed documents
+    pub fn new(index: String) -> Self {
+        FtDropIndexCommand { index, delete_documents: false }
+    }
+
+    pub fn delete_documents(mut self) -> Self {
+        self.delete_documents = true;
+        self
+    }
+}
+
+impl Command for FtDropIndexCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        let mut parts = vec!["FT.DROPINDEX", &self.index];
+        if self.delete_documents {
+            parts.push("DD");
+        }
+        parts
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Status
+    }
+}
+
+impl TypedCommand for FtDropIndexCommand {
+    type Output = bool;
+}
+
+// Builds a query from clauses that must all match, e.g.
+// SearchQuery::new().text("lovelace").tag("city", &["london"]).to_string()
+#[derive(Debug, Clone, Default)]
+pub struct SearchQuery {
+    clauses: Vec<String>,
+}
+
+impl SearchQuery {
+    pub fn new() -> Self {
+        SearchQuery { clauses: Vec::new() }
+    }
+
+    // Full-text terms, searched in every TEXT field
+    pub fn text(mut self, terms: &str) -> Self {
+        self.clauses.push(terms.to_string());
+        self
+    }
+
+    // Full-text terms searched in one field only
+    pub fn text_in(mut self, field: &str, terms: &str) -> Self {
+        self.clauses.push(format!("@{}:({})", field, terms));
+        self
+    }
+
+    // Matches any of the tags
+    pub fn tag(mut self, field: &str, tags: &[&str]) -> Self {
+        self.clauses.push(format!("@{}:{{{}}}", field, tags.join(" | ")));
+        self
+    }
+
+    // Inclusive range, infinite bounds are allowed
+    pub fn numeric_range(mut self, field: &str, min: f64, max: f64) -> Self {
+        self.clauses.push(format!("@{}:[{} {}]", field, bound(min), bound(max)));
+        self
+    }
+
+    // Excludes the documents matching the query
+    pub fn not(mut self, query: SearchQuery) -> Self {
+        self.clauses.push(format!("-({})", query));
+        self
+    }
+}
+
+fn bound(value: f64) -> String {
+    if value == f64::INFINITY {
+        "+inf".to_string()
+    } else if value == f64::NEG_INFINITY {
+        "-inf".to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+// Without clauses the query matches every document
+impl fmt::Display for SearchQuery {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.clauses.is_empty() {
+            write!(f, "*")
+        } else {
+            write!(f, "{}", self.clauses.join(" "))
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Document {
+    pub id: String,
+    // Only when searched with with_scores
+    pub score: Option<f64>,
+    // Empty when searched with no_content
+    pub fields: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchResult {
+    // Number of matching documents, not only the ones of the returned page
+    pub total: u64,
+    pub docs: Vec<Document>,
+}
+
+// Each document is its id, followed by its score and its fields when requested
+fn search_result(reply: Resp3Value, with_scores: bool, with_content: bool) -> Result<SearchResult, Resp3Error> {
+    let values = match reply {
+        Resp3Value::Array(values) if !values.is_empty() => values,
+        Resp3Value::Map(map) => return search_result_map(map),
+        reply => return unexpected("a search result", &reply),
+    };
+    let mut values = values.into_iter();
+    let total = u64::from_resp3_value(values.next().unwrap())?;
+
+    let mut docs = Vec::new();
+    while let Some(id) = values.next() {
+        let mut document = Document { id: String::from_resp3_value(id)?, score: None, fields: HashMap::new() };
+        if with_scores {
+            let score = values.next().ok_or_else(|| Resp3Error::UnexpectedReply("Missing document score".to_string()))?;
+            document.score = Some(f64::from_resp3_value(score)?);
+        }
+        if with_content {
+            let fields = values.next().ok_or_else(|| Resp3Error::UnexpectedReply("Missing document fields".to_string()))?;
+            document.fields = HashMap::from_resp3_value(fields)?;
+        }
+        docs.push(document);
+    }
+
+    Ok(SearchResult { total, docs })
+}
+
+// Over RESP3 the total and the documents are entries of a map, each document a map of its own
+fn search_result_map(map: Resp3Map) -> Result<SearchResult, Resp3Error> {
+    let total = map.get_as::<u64>("total_results")?.ok_or_else(|| Resp3Error::UnexpectedReply("Missing total_results".to_string()))?;
+    let mut docs = Vec::new();
+    for result in result_maps(&map)? {
+        let id = result.get_as::<String>("id")?.ok_or_else(|| Resp3Error::UnexpectedReply("Missing document id".to_string()))?;
+        let score = result.get_as::<f64>("score")?;
+        let fields = result.get_as::<HashMap<String, String>>("extra_attributes")?.unwrap_or_default();
+        docs.push(Document { id, score, fields });
+    }
+    Ok(SearchResult { total, docs })
+}
+
+fn result_maps(map: &Resp3Map) -> Result<Vec<Resp3Map>, Resp3Error> {
+    map.get_as::<Vec<Resp3Value>>("results")?.unwrap_or_default().into_iter().map(|result| match result {
+        Resp3Value::Map(result) => Ok(result),
+        result => unexpected("a result map", &result),
+    }).collect()
+}
+
+impl FromResp3Value for SearchResult {
+    fn from_resp3_value(value: Resp3Value) -> Result<Self, Resp3Error> {
+        search_result(value, false, true)
+    }
+}
+
+pub struct FtSearchCommand {
+    index: String,
+    query: String,
+    options: Vec<String>,
+    with_scores: bool,
+    no_content: bool,
+}
+
+impl FtSearchCommand {
+    // The query is in the RediSearch syntax, see SearchQuery to build one
+    pub fn new(index: String, query: String) -> Self {
+        FtSearchCommand { index, query, options: Vec::new(), with_scores: false, no_content: false }
+    }
+
+    // Returns only the document ids
+    pub fn no_content(mut self) -> Self {
+        self.no_content = true;
+        self
+    }
+
+    pub fn with_scores(mut self) -> Self {
+        self.with_scores = true;
+        self
+    }
+
+    // Returns only these fields of the documents
+    pub fn return_fields(mut self, fields: Vec<String>) -> Self {
+        self.options.push("RETURN".to_string());
+        self.options.push(fields.len().to_string());
+        self.options.extend(fields);
+        self
+    }
+
+    // The field must be sortable
+    pub fn sort_by(mut self, field: String, ascending: bool) -> Self {
+        self.options.push("SORTBY".to_string());
+        self.options.push(field);
+        self.options.push(if ascending { "ASC" } else { "DESC" }.to_string());
+        self
+    }
+
+    // Pages through the results, 10 documents from the first one by default
+    pub fn limit(mut self, offset: u64, count: u64) -> Self {
+        self.options.push("LIMIT".to_string());
+        self.options.push(offset.to_string());
+        self.options.push(count.to_string());
+        self
+    }
+}
+
+impl Command for FtSearchCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        let mut parts = vec!["FT.SEARCH", &self.index, &self.query];
+        if self.no_content {
+            parts.push("NOCONTENT");
+        }
+        if self.with_scores {
+            parts.push("WITHSCORES");
+        }
+        parts.extend(self.options.iter().map(String::as_str));
+        parts
+    }
+
+    fn is_readonly(&self) -> bool {
+        true
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Array
+    }
+}
+
+impl TypedCommand for FtSearchCommand {
+    type Output = SearchResult;
+
+    // The layout of the documents depends on the options
+    fn parse_output(&self, reply: Resp3Value) -> Result<Self::Output, Resp3Error> {
+        search_result(reply, self.with_scores, !self.no_content)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Reducer<'a> {
+    Count,
+    CountDistinct(&'a str),
+    Sum(&'a str),
+    Avg(&'a str),
+    Min(&'a str),
+    Max(&'a str),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AggregateResult {
+    pub total: u64,
+    // The fields of each row, the group by fields and the reducer aliases after a group_by
+    pub rows: Vec<HashMap<String, String>>,
+}
+
+impl FromResp3Value for AggregateResult {
+    fn from_resp3_value(value: Resp3Value) -> Result<Self, Resp3Error> {
+        let values = match value {
+            Resp3Value::Array(values) if !values.is_empty() => values,
+            // Over RESP3 each row is under extra_attributes of its result
+            Resp3Value::Map(map) => {
+                let total = map.get_as::<u64>("total_results")?.ok_or_else(|| Resp3Error::UnexpectedReply("Missing total_results".to_string()))?;
+                let rows = result_maps(&map)?.into_iter()
+                    .map(|result| Ok(result.get_as::<HashMap<String, String>>("extra_attributes")?.unwrap_or_default()))
+                    .collect::<Result<_, Resp3Error>>()?;
+                return Ok(AggregateResult { total, rows });
+            }
+            value => return unexpected("an aggregate result", &value),
+        };
+        let mut values = values.into_iter();
+        let total = u64::from_resp3_value(values.next().unwrap())?;
+        let rows = values.map(HashMap::from_resp3_value).collect::<Result<_, _>>()?;
+        Ok(AggregateResult { total, rows })
+    }
+}
+
+// The steps run in the order they are added, e.g.
+// FtAggregateCommand::new(index, "*".to_string()).group_by(&["city"]).reduce(Reducer::Count, "people")
+pub struct FtAggregateCommand {
+    index: String,
+    query: String,
+    steps: Vec<String>,
+}
+
+impl FtAggregateCommand {
+    pub fn new(index: String, query: String) -> Self {
+        FtAggregateCommand { index, query, steps: Vec::new() }
+    }
+
+    // Loads document fields that are not sortable
+    pub fn load(mut self, fields: &[&str]) -> Self {
+        self.steps.push("LOAD".to_string());
+        self.push_fields(fields);
+        self
+    }
+
+    pub fn group_by(mut self, fields: &[&str]) -> Self {
+        self.steps.push("GROUPBY".to_string());
+        self.push_fields(fields);
+        self
+    }
+
+    // Adds a reduced field to the previous group_by
+    pub fn reduce(mut self, reducer: Reducer, alias: &str) -> Self {
+        let (function, field) = match reducer {
+            Reducer::Count => ("COUNT", None),
+            Reducer::CountDistinct(field) => ("COUNT_DISTINCT", Some(field)),
+            Reducer::Sum(field) => ("SUM", Some(field)),
+            Reducer::Avg(field) => ("AVG", Some(field)),
+            Reducer::Min(field) => ("MIN", Some(field)),
+            Reducer::Max(field) => ("MAX", Some(field)),
+        };
+        self.steps.push("REDUCE".to_string());
+        self.steps.push(function.to_string());
+        self.push_fields(&field.into_iter().collect::<Vec<_>>());
+        self.steps.push("AS".to_string());
+        self.steps.push(alias.to_string());
+        self
+    }
+
+    pub fn sort_by(mut self, field: &str, ascending: bool) -> Self {
+        self.steps.push("SORTBY".to_string());
+        self.steps.push("2".to_string());
+        self.steps.push(format!("@{}", field));
+        self.steps.push(if ascending { "ASC" } else { "DESC" }.to_string());
+        self
+    }
+
+    pub fn limit(mut self, offset: u64, count: u64) -> Self {
+        self.steps.push("LIMIT".to_string());
+        self.steps.push(offset.to_string());
+        self.steps.push(count.to_string());
+        self
+    }
+
+    fn push_fields(&mut self, fields: &[&str]) {
+        self.steps.push(fields.len().to_string());
+        self.steps.extend(fields.iter().map(|field| format!("@{}", field)));
+    }
+}
+
+impl Command for FtAggregateCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        let mut parts = vec!["FT.AGGREGATE", &self.index, &self.query];
+        parts.extend(self.steps.iter().map(String::as_str));
+        parts
+    }
+
+    fn is_readonly(&self) -> bool {
+        true
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Array
+    }
+}
+
+impl TypedCommand for FtAggregateCommand {
+    type Output = AggregateResult;
+}
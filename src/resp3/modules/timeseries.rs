@@ -0,0 +1,293 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::resp3::error::Resp3Error;
+use crate::resp3::utils::command::{Command, ReplyKind, TypedCommand};
+use crate::resp3::value::{unexpected, FromResp3Value, Resp3Value};
+
+// A sample: Unix time in milliseconds and value
+pub type Sample = (i64, f64);
+
+pub struct TsCreateCommand {
+    key: String,
+    options: Vec<String>,
+    labels: Vec<String>,
+}
+
+impl TsCreateCommand {
+    pub fn new(key: String) -> Self {
+        TsCreateCommand { key, options: Vec::new(), labels: Vec::new() }
+    }
+
+    // Samples older than the retention are dropped, they are kept forever by default
+    pub fn retention(mut self, retention: Duration) -> Self {
+        self.options.push("RETENTION".to_string());
+        self.options.push(retention.as_millis().to_string());
+        self
+    }
+
+    // Labels are what TS.MRANGE filters the series by
+    pub fn label(mut self, name: String, value: String) -> Self {
+        self.labels.push(name);
+        self.labels.push(value);
+        self
+    }
+}
+
+impl Command for TsCreateCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        let mut parts = vec!["TS.CREATE", &self.key];
+        parts.extend(self.options.iter().map(String::as_str));
+        if !self.labels.is_empty() {
+            parts.push("LABELS");
+            parts.extend(self.labels.iter().map(String::as_str));
+        }
+        parts
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![1]
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Status
+    }
+}
+
+impl TypedCommand for TsCreateCommand {
+    type Output = bool;
+}
+
+pub struct TsAddCommand {
+    key: String,
+    timestamp: String,
+    value: String,
+}
+
+impl TsAddCommand {
+    // Adds a sample at the given Unix time in milliseconds, the series is created if needed
+    pub fn new(key: String, timestamp: i64, value: f64) -> Self {
+        TsAddCommand { key, timestamp: timestamp.to_string(), value: value.to_string() }
+    }
+
+    // Adds a sample at the current time of the server
+    pub fn now(key: String, value: f64) -> Self {
+        TsAddCommand { key, timestamp: "*".to_string(), value: value.to_string() }
+    }
+}
+
+impl Command for TsAddCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        vec!["TS.ADD", &self.key, &self.timestamp, &self.value]
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![1]
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Integer
+    }
+}
+
+impl TypedCommand for TsAddCommand {
+    // Timestamp of the added sample
+    type Output = i64;
+}
+
+pub struct TsGetCommand {
+    key: String,
+}
+
+impl TsGetCommand {
+    pub fn new(key: String) -> Self {
+        TsGetCommand { key }
+    }
+}
+
+impl Command for TsGetCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        vec!["TS.GET", &self.key]
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![1]
+    }
+
+    fn is_readonly(&self) -> bool {
+        true
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Array
+    }
+}
+
+impl TypedCommand for TsGetCommand {
+    // The latest sample, None when the series is empty
+    type Output = Option<Sample>;
+
+    // An empty series replies with an empty array
+    fn parse_output(&self, reply: Resp3Value) -> Result<Self::Output, Resp3Error> {
+        match reply {
+            Resp3Value::Array(values) if values.is_empty() => Ok(None),
+            reply => Option::from_resp3_value(reply),
+        }
+    }
+}
+
+// The from and to bounds shared by the range commands, the whole series by default
+struct Range {
+    from: String,
+    to: String,
+    options: Vec<String>,
+}
+
+impl Range {
+    fn new() -> Self {
+        Range { from: "-".to_string(), to: "+".to_string(), options: Vec::new() }
+    }
+
+    fn count(&mut self, count: u64) {
+        self.options.push("COUNT".to_string());
+        self.options.push(count.to_string());
+    }
+}
+
+pub struct TsRangeCommand {
+    key: String,
+    range: Range,
+}
+
+impl TsRangeCommand {
+    pub fn new(key: String) -> Self {
+        TsRangeCommand { key, range: Range::new() }
+    }
+
+    // Inclusive, in milliseconds
+    pub fn from(mut self, timestamp: i64) -> Self {
+        self.range.from = timestamp.to_string();
+        self
+    }
+
+    // Inclusive, in milliseconds
+    pub fn to(mut self, timestamp: i64) -> Self {
+        self.range.to = timestamp.to_string();
+        self
+    }
+
+    pub fn count(mut self, count: u64) -> Self {
+        self.range.count(count);
+        self
+    }
+}
+
+impl Command for TsRangeCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        let mut parts = vec!["TS.RANGE", &self.key, &self.range.from, &self.range.to];
+        parts.extend(self.range.options.iter().map(String::as_str));
+        parts
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        vec![1]
+    }
+
+    fn is_readonly(&self) -> bool {
+        true
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Array
+    }
+}
+
+impl TypedCommand for TsRangeCommand {
+    // Oldest first
+    type Output = Vec<Sample>;
+}
+
+// The samples of one of the series matched by TS.MRANGE
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimeSeries {
+    pub key: String,
+    // Only when queried with with_labels
+    pub labels: HashMap<String, String>,
+    pub samples: Vec<Sample>,
+}
+
+impl FromResp3Value for TimeSeries {
+    fn from_resp3_value(value: Resp3Value) -> Result<Self, Resp3Error> {
+        let values = match value {
+            Resp3Value::Array(values) if values.len() == 3 => values,
+            value => return unexpected("a key, its labels and samples", &value),
+        };
+        let mut values = values.into_iter();
+        let key = String::from_resp3_value(values.next().unwrap())?;
+        let labels: Vec<(String, String)> = Vec::from_resp3_value(values.next().unwrap())?;
+        let samples = Vec::from_resp3_value(values.next().unwrap())?;
+        Ok(TimeSeries { key, labels: labels.into_iter().collect(), samples })
+    }
+}
+
+pub struct TsMRangeCommand {
+    range: Range,
+    with_labels: bool,
+    filters: Vec<String>,
+}
+
+impl TsMRangeCommand {
+    // Queries every series matching all the label filters, e.g. "sensor=temperature",
+    // "room!=kitchen", "building=(a,b)", at least one of them must be a = or =() filter
+    pub fn new(filters: Vec<String>) -> Self {
+        TsMRangeCommand { range: Range::new(), with_labels: false, filters }
+    }
+
+    // Inclusive, in milliseconds
+    pub fn from(mut self, timestamp: i64) -> Self {
+        self.range.from = timestamp.to_string();
+        self
+    }
+
+    // Inclusive, in milliseconds
+    pub fn to(mut self, timestamp: i64) -> Self {
+        self.range.to = timestamp.to_string();
+        self
+    }
+
+    // The maximum number of samples per series
+    pub fn count(mut self, count: u64) -> Self {
+        self.range.count(count);
+        self
+    }
+
+    pub fn with_labels(mut self) -> Self {
+        self.with_labels = true;
+        self
+    }
+}
+
+impl Command for TsMRangeCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        let mut parts = vec!["TS.MRANGE", &self.range.from, &self.range.to];
+        parts.extend(self.range.options.iter().map(String::as_str));
+        if self.with_labels {
+            parts.push("WITHLABELS");
+        }
+        parts.push("FILTER");
+        parts.extend(self.filters.iter().map(String::as_str));
+        parts
+    }
+
+    fn is_readonly(&self) -> bool {
+        true
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Array
+    }
+}
+
+impl TypedCommand for TsMRangeCommand {
+    type Output = Vec<TimeSeries>;
+}
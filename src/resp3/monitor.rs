@@ -0,0 +1,124 @@
+// src/resp3/monitor.rs
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::resp3::error::Resp3Error;
+use crate::resp3::utils::command::Command;
+use crate::resp3::utils::redis_connection::RedisConnection;
+use crate::resp3::value::Resp3Value;
+
+struct MonitorCommand;
+
+impl Command for MonitorCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        vec!["MONITOR"]
+    }
+}
+
+// A command processed by the server, as reported by MONITOR
+#[derive(Debug, Clone, PartialEq)]
+pub struct MonitorEvent {
+    pub timestamp: SystemTime,
+    pub db: u32,
+    // ip:port of the client, "lua" for the commands run by scripts
+    pub client_address: String,
+    // Binary arguments are decoded lossily
+    pub arguments: Vec<String>,
+}
+
+impl MonitorEvent {
+    // Parses a line like 1339518083.107412 [0 127.0.0.1:60866] "set" "key" "va\"lue"
+    pub fn parse(line: &str) -> Result<Self, Resp3Error> {
+        let invalid = || Resp3Error::UnexpectedReply(format!("Not a MONITOR line: {}", line));
+
+        let (timestamp, rest) = line.split_once(" [").ok_or_else(invalid)?;
+        let (client, rest) = rest.split_once("] ").ok_or_else(invalid)?;
+        let (db, client_address) = client.split_once(' ').ok_or_else(invalid)?;
+
+        // Seconds and microseconds, parsed apart to keep the precision a float would lose
+        let (seconds, micros) = timestamp.split_once('.').ok_or_else(invalid)?;
+        let seconds: u64 = seconds.parse().map_err(|_| invalid())?;
+        let micros: u64 = micros.parse().map_err(|_| invalid())?;
+        let db = db.parse().map_err(|_| invalid())?;
+        let arguments = parse_arguments(rest).ok_or_else(invalid)?;
+
+        Ok(MonitorEvent {
+            timestamp: UNIX_EPOCH + Duration::from_secs(seconds) + Duration::from_micros(micros),
+            db,
+            client_address: client_address.to_string(),
+            arguments,
+        })
+    }
+}
+
+// Undoes the quoting Redis applies to each argument (sdscatrepr)
+fn parse_arguments(input: &str) -> Option<Vec<String>> {
+    let mut arguments = Vec::new();
+    let mut bytes = input.bytes();
+
+    loop {
+        match bytes.next() {
+            None => return Some(arguments),
+            Some(b' ') => continue,
+            Some(b'"') => {}
+            Some(_) => return None,
+        }
+
+        let mut argument = Vec::new();
+        loop {
+            match bytes.next()? {
+                b'"' => break,
+                b'\\' => match bytes.next()? {
+                    b'n' => argument.push(b'\n'),
+                    b'r' => argument.push(b'\r'),
+                    b't' => argument.push(b'\t'),
+                    b'a' => argument.push(7),
+                    b'b' => argument.push(8),
+                    b'x' => {
+                        let hex = [bytes.next()?, bytes.next()?];
+                        argument.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+                    }
+                    escaped => argument.push(escaped),
+                },
+                byte => argument.push(byte),
+            }
+        }
+        arguments.push(String::from_utf8_lossy(&argument).into_owned());
+    }
+}
+
+// Streams the commands processed by the server. MONITOR is expensive for the server, and the
+// connection cannot be used for anything else while this lives
+pub struct Monitor {
+    conn: RedisConnection,
+    failed: bool,
+}
+
+impl Monitor {
+    pub fn start(mut conn: RedisConnection) -> Result<Self, Resp3Error> {
+        MonitorCommand.execute(&mut conn)?;
+        Ok(Monitor { conn, failed: false })
+    }
+
+    // Blocks until the next command is processed
+    pub fn next_event(&mut self) -> Result<MonitorEvent, Resp3Error> {
+        match self.conn.read_response()? {
+            Resp3Value::SimpleString(line) => MonitorEvent::parse(&line),
+            reply => Err(Resp3Error::UnexpectedReply(format!("Expected a MONITOR line, got {:?}", reply))),
+        }
+    }
+}
+
+// Ends after the first error, as the connection cannot be trusted anymore
+impl Iterator for Monitor {
+    type Item = Result<MonitorEvent, Resp3Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        let event = self.next_event();
+        self.failed = event.is_err();
+        Some(event)
+    }
+}
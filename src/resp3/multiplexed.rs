@@ -0,0 +1,189 @@
+// src/resp3/multiplexed.rs
+
+use std::collections::VecDeque;
+use std::io::{self, Write};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use tokio::sync::{broadcast, oneshot};
+
+use crate::resp3::error::{Resp3Error, ServerError};
+use crate::resp3::pubsub::{PubSubEvent, SubscriptionCommand};
+use crate::resp3::utils::command::{Command, TypedCommand};
+use crate::resp3::utils::connection_state::command_name;
+use crate::resp3::utils::redis_connection::{ProtocolVersion, RedisConnectionBuilder};
+use crate::resp3::utils::transport::Transport;
+use crate::resp3::value::Resp3Value;
+
+// Commands that change the state of the connection for every task sharing it
+const EXCLUSIVE_COMMANDS: [&str; 16] = [
+    "SUBSCRIBE", "PSUBSCRIBE", "SSUBSCRIBE", "UNSUBSCRIBE", "PUNSUBSCRIBE", "SUNSUBSCRIBE",
+    "MULTI", "EXEC", "DISCARD", "WATCH", "UNWATCH", "MONITOR", "QUIT", "RESET", "SELECT", "HELLO",
+];
+
+type Waiter = oneshot::Sender<Result<Resp3Value, Resp3Error>>;
+
+// The tasks waiting for a reply, in the order their commands were written
+#[derive(Default)]
+struct Waiters {
+    queue: VecDeque<Waiter>,
+    // Set by the reader when the connection failed, with the reason
+    closed: Option<String>,
+}
+
+// The last handle gone, the writer thread sees the channel closed and shuts the stream down,
+// then the reader stops on the end of the stream
+struct Shared {
+    commands: Sender<Vec<u8>>,
+    waiters: Arc<Mutex<Waiters>>,
+    events: broadcast::Sender<PubSubEvent>,
+}
+
+// One RESP3 connection shared by any number of tasks, e.g. MultiplexedConnection::connect(builder)
+// then a clone per task. A thread writes the commands as they come, so no task blocks on the
+// stream, and another reads the replies, handing each to the task that is next in line, while
+// the Pub/Sub messages go to the receivers of events. A task that stops waiting leaves its
+// reply to be skipped, so the others keep theirs, and a reply that does not parse fails its
+// command only. Blocking commands hold up every reply after theirs, and the connection is not
+// opened again when it drops: the waiting and later commands fail, make a new one
+#[derive(Clone)]
+pub struct MultiplexedConnection {
+    shared: Arc<Shared>,
+}
+
+impl MultiplexedConnection {
+    // Negotiates RESP3 whatever the builder asks for, push messages need it. Set a timeout
+    // for the writes, the reads wait as long as the connection is open
+    pub fn connect(builder: RedisConnectionBuilder) -> Result<Self, Resp3Error> {
+        let mut conn = builder.protocol(ProtocolVersion::Resp3).connect()?;
+        let writer = conn.try_clone_transport()?;
+        let (commands, queued) = mpsc::channel();
+        thread::spawn(move || write_commands(writer, queued));
+        let waiters = Arc::new(Mutex::new(Waiters::default()));
+        let (events, _) = broadcast::channel(1024);
+
+        let (reader_waiters, reader_events) = (Arc::clone(&waiters), events.clone());
+        thread::spawn(move || loop {
+            match conn.read_message() {
+                Ok((Ok(message), true)) => {
+                    // No receiver is not an error, nobody listens
+                    if let Some(event) = PubSubEvent::from_message(message) {
+                        let _ = reader_events.send(event);
+                    }
+                }
+                Ok((Err(err), true)) => log::debug!("Skipped a push message that does not parse: {}", err),
+                // A reply that does not parse fails its own command only
+                Ok((reply, false)) => {
+                    let waiter = reader_waiters.lock().unwrap().queue.pop_front();
+                    if let Some(waiter) = waiter {
+                        let _ = waiter.send(reply);
+                    }
+                }
+                Err(err) if err.is_timeout() => continue,
+                Err(err) => {
+                    let mut waiters = reader_waiters.lock().unwrap();
+                    let reason = err.to_string();
+                    for waiter in waiters.queue.drain(..) {
+                        let _ = waiter.send(Err(closed_error(&reason)));
+                    }
+                    waiters.closed = Some(reason);
+                    return;
+                }
+            }
+        });
+        Ok(MultiplexedConnection { shared: Arc::new(Shared { commands, waiters, events }) })
+    }
+
+    pub async fn query<C: TypedCommand + ?Sized>(&self, command: &C) -> Result<C::Output, Resp3Error> {
+        let reply = self.request(command).await?;
+        command.parse_output(reply)
+    }
+
+    // Error replies are returned as Resp3Error::Server, like RedisConnection::request_command does
+    pub async fn request<C: Command + ?Sized>(&self, command: &C) -> Result<Resp3Value, Resp3Error> {
+        let mut encoded = Vec::new();
+        command.encode_into(&mut encoded);
+        let name = command_name(&encoded).unwrap_or_default();
+        if EXCLUSIVE_COMMANDS.contains(&name.as_str()) {
+            return Err(Resp3Error::InvalidState(format!("{} cannot be sent on a multiplexed connection", name)));
+        }
+
+        let (waiter, reply) = oneshot::channel();
+        self.write(encoded, Some(waiter))?;
+        let reply = reply.await.map_err(|_| closed_error("the reader stopped"))??;
+        let expected = command.expected_reply();
+        match reply {
+            Resp3Value::Error(message) => Err(Resp3Error::Server(ServerError::parse(&message))),
+            reply if !expected.matches(&reply) => Err(Resp3Error::UnexpectedReply(format!("Expected {:?} reply, got {:?}", expected, reply))),
+            reply => Ok(reply),
+        }
+    }
+
+    // The Pub/Sub messages received from now on. A receiver that falls behind by more than
+    // 1024 messages skips the oldest ones and is told how many with a Lagged error
+    pub fn events(&self) -> broadcast::Receiver<PubSubEvent> {
+        self.shared.events.subscribe()
+    }
+
+    // The confirmations come as push messages, so nothing is waited for
+    pub fn subscribe(&self, channels: &[&str]) -> Result<(), Resp3Error> {
+        self.send_subscription("SUBSCRIBE", channels)
+    }
+
+    pub fn psubscribe(&self, patterns: &[&str]) -> Result<(), Resp3Error> {
+        self.send_subscription("PSUBSCRIBE", patterns)
+    }
+
+    pub fn unsubscribe(&self, channels: &[&str]) -> Result<(), Resp3Error> {
+        self.send_subscription("UNSUBSCRIBE", channels)
+    }
+
+    pub fn punsubscribe(&self, patterns: &[&str]) -> Result<(), Resp3Error> {
+        self.send_subscription("PUNSUBSCRIBE", patterns)
+    }
+
+    // The tasks waiting for a reply
+    pub fn in_flight(&self) -> usize {
+        self.shared.waiters.lock().unwrap().queue.len()
+    }
+
+    fn send_subscription(&self, name: &'static str, names: &[&str]) -> Result<(), Resp3Error> {
+        let names: Vec<String> = names.iter().map(|name| name.to_string()).collect();
+        let mut encoded = Vec::new();
+        SubscriptionCommand { name, names: &names }.encode_into(&mut encoded);
+        self.write(encoded, None)
+    }
+
+    // The waiter is queued together with the command, under the lock, so the waiters are in the
+    // order of the commands on the wire
+    fn write(&self, encoded: Vec<u8>, waiter: Option<Waiter>) -> Result<(), Resp3Error> {
+        let mut waiters = self.shared.waiters.lock().unwrap();
+        if let Some(reason) = &waiters.closed {
+            return Err(closed_error(reason));
+        }
+        if self.shared.commands.send(encoded).is_err() {
+            return Err(closed_error("the writer stopped"));
+        }
+        waiters.queue.extend(waiter);
+        Ok(())
+    }
+}
+
+// Writes every command queued since the last flush before flushing, so commands sent together
+// leave together. A failed write shuts the stream down, the reader then fails the waiters
+fn write_commands(mut writer: Box<dyn Transport>, queued: Receiver<Vec<u8>>) {
+    while let Ok(encoded) = queued.recv() {
+        let written = writer.write_all(&encoded)
+            .and_then(|_| queued.try_iter().try_for_each(|encoded| writer.write_all(&encoded)))
+            .and_then(|_| writer.flush());
+        if written.is_err() {
+            break;
+        }
+    }
+    let _ = writer.shutdown();
+}
+
+fn closed_error(reason: &str) -> Resp3Error {
+    Resp3Error::Io(io::Error::new(io::ErrorKind::BrokenPipe, format!("Multiplexed connection closed: {}", reason)))
+}
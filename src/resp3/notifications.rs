@@ -0,0 +1,116 @@
+// src/resp3/notifications.rs
+
+use crate::resp3::commands::config::ConfigSetCommand;
+use crate::resp3::error::Resp3Error;
+use crate::resp3::utils::command::Command;
+use crate::resp3::utils::redis_connection::RedisConnection;
+use crate::resp3::value::{FromResp3Value, Resp3Value};
+
+// Key-event notifications for generic commands, strings and expirations
+pub const DEFAULT_EVENTS: &str = "Eg$x";
+
+const KEYEVENT_PATTERN: &str = "__keyevent@*__:*";
+
+// Channel patterns are case sensitive, so the pattern is sent as it is
+struct PSubscribeCommand;
+
+impl Command for PSubscribeCommand {
+    fn get_parts(&self) -> Vec<&str> {
+        vec!["PSUBSCRIBE", KEYEVENT_PATTERN]
+    }
+
+    fn is_verbatim(&self, _index: usize) -> bool {
+        true
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum KeyspaceEventKind {
+    Set,
+    Del,
+    Expired,
+    // Any other event, with its name as sent by Redis (e.g. "expire", "rename_from")
+    Other(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyspaceEvent {
+    pub kind: KeyspaceEventKind,
+    pub key: String,
+    pub db: u32,
+}
+
+impl KeyspaceEvent {
+    // Decodes a pmessage sent on a __keyevent@<db>__:<event> channel
+    fn from_message(message: Resp3Value) -> Result<Option<Self>, Resp3Error> {
+        let parts: Vec<String> = Vec::from_resp3_value(message)?;
+        let (channel, key) = match parts.as_slice() {
+            [kind, _, channel, key] if kind == "pmessage" => (channel, key),
+            _ => return Ok(None),
+        };
+
+        let (db, event) = channel
+            .strip_prefix("__keyevent@")
+            .and_then(|rest| rest.split_once("__:"))
+            .ok_or_else(|| Resp3Error::UnexpectedReply(format!("Not a key-event channel: {}", channel)))?;
+        let db = db.parse()
+            .map_err(|_| Resp3Error::UnexpectedReply(format!("Invalid database index in channel: {}", channel)))?;
+        let kind = match event {
+            "set" => KeyspaceEventKind::Set,
+            "del" => KeyspaceEventKind::Del,
+            "expired" => KeyspaceEventKind::Expired,
+            other => KeyspaceEventKind::Other(other.to_string()),
+        };
+
+        Ok(Some(KeyspaceEvent { kind, key: key.clone(), db }))
+    }
+}
+
+// Receives the key-event notifications of every database. The connection is in subscriber
+// mode for as long as this lives, so it cannot be used for anything else
+pub struct KeyspaceNotifications {
+    conn: RedisConnection,
+    failed: bool,
+}
+
+impl KeyspaceNotifications {
+    // Enables the given notify-keyspace-events flags on the server and subscribes to them,
+    // the flags must include E for key-event notifications to be sent at all
+    pub fn subscribe(mut conn: RedisConnection, events: &str) -> Result<Self, Resp3Error> {
+        ConfigSetCommand::new("notify-keyspace-events".to_string(), events.to_string()).execute(&mut conn)?;
+        PSubscribeCommand.execute(&mut conn)?;
+        Ok(KeyspaceNotifications { conn, failed: false })
+    }
+
+    // Blocks until the next event
+    pub fn next_event(&mut self) -> Result<KeyspaceEvent, Resp3Error> {
+        loop {
+            if let Some(event) = KeyspaceEvent::from_message(self.conn.read_response()?)? {
+                return Ok(event);
+            }
+        }
+    }
+
+    // Calls the callback for each event until it returns false or the connection fails
+    pub fn listen<F: FnMut(KeyspaceEvent) -> bool>(&mut self, mut callback: F) -> Result<(), Resp3Error> {
+        loop {
+            if !callback(self.next_event()?) {
+                return Ok(());
+            }
+        }
+    }
+}
+
+// Ends after the first error, as the connection cannot be trusted anymore
+impl Iterator for KeyspaceNotifications {
+    type Item = Result<KeyspaceEvent, Resp3Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        let event = self.next_event();
+        self.failed = event.is_err();
+        Some(event)
+    }
+}
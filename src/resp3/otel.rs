@@ -0,0 +1,49 @@
+// src/resp3/otel.rs
+
+use opentelemetry::global::{self, BoxedSpan, BoxedTracer};
+use opentelemetry::trace::{Span, SpanKind, Status, Tracer};
+use opentelemetry::KeyValue;
+
+use crate::resp3::error::{Resp3Error, ServerError};
+use crate::resp3::utils::proxy::split_host_port;
+use crate::resp3::value::Resp3Value;
+
+// The instrumentation scope of the spans made with the global tracer provider
+pub const TRACER_NAME: &str = "resp3string";
+
+// A client span named after the command, child of the current context, with the attributes
+// of the database semantic conventions. Without a tracer of its own the connection uses the
+// global one, a no-op until the application installs a tracer provider
+pub(crate) fn start_span(tracer: Option<&BoxedTracer>, command: &str, endpoint: &str) -> BoxedSpan {
+    let mut attributes = vec![KeyValue::new("db.system", "redis"), KeyValue::new("db.operation", command.to_string())];
+    match split_host_port(endpoint) {
+        Ok((host, port)) => {
+            attributes.push(KeyValue::new("net.peer.name", host.to_string()));
+            attributes.push(KeyValue::new("net.peer.port", i64::from(port)));
+        }
+        Err(_) => attributes.push(KeyValue::new("net.peer.name", endpoint.to_string())),
+    }
+    let name = if command.is_empty() { "redis".to_string() } else { command.to_string() };
+    let build = |tracer: &BoxedTracer| tracer.span_builder(name).with_kind(SpanKind::Client).with_attributes(attributes).start(tracer);
+    match tracer {
+        Some(tracer) => build(tracer),
+        None => build(&global::tracer(TRACER_NAME)),
+    }
+}
+
+// Error replies count as errors of the span, like the failures to send or read
+pub(crate) fn end_span(span: &mut BoxedSpan, result: Result<&Resp3Value, &Resp3Error>) {
+    match result {
+        Ok(Resp3Value::Error(message)) => {
+            let error = ServerError::parse(message);
+            span.set_attribute(KeyValue::new("db.response.status_code", error.code_name().to_string()));
+            span.set_status(Status::error(message.clone()));
+        }
+        Ok(_) => {}
+        Err(err) => {
+            span.record_error(err);
+            span.set_status(Status::error(err.to_string()));
+        }
+    }
+    span.end();
+}
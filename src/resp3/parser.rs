@@ -0,0 +1,308 @@
+// src/resp3/parser.rs
+
+use crate::resp3::error::Resp3Error;
+use crate::resp3::value::{Resp3Map, Resp3Value};
+
+// Deeper arrays and maps are rejected instead of overflowing the stack, Redis replies nest a few levels
+pub const MAX_NESTING: usize = 512;
+
+// Parses the first complete reply in the input
+pub fn parse_response(input: &[u8]) -> Result<Resp3Value, Resp3Error> {
+    match parse_frame(input)? {
+        Some((value, _)) => Ok(value),
+        None => Err(Resp3Error::Protocol("Incomplete response".to_string())),
+    }
+}
+
+// Parses the first reply in the input, returning it together with the number of bytes it took,
+// or None when more bytes are needed to complete it. RESP3 attributes are skipped
+pub fn parse_frame(input: &[u8]) -> Result<Option<(Resp3Value, usize)>, Resp3Error> {
+    parse_at(input, 0, 0)
+}
+
+// Like parse_frame, also returning the attributes sent before the reply, e.g. the key-popularity
+// hints of the server. Those inside arrays and maps are skipped, they describe a single element
+pub fn parse_frame_with_attributes(input: &[u8]) -> Result<Option<(Resp3Value, Option<Resp3Map>, usize)>, Resp3Error> {
+    let mut attributes: Option<Resp3Map> = None;
+    let mut position = 0;
+    while input.get(position) == Some(&b'|') {
+        let Some(line_end) = find_crlf(input, position) else { return Ok(None) };
+        let Some((attribute, after)) = parse_aggregate(input, b'%', &input[position + 1..line_end], line_end + 2, 0)? else { return Ok(None) };
+        if let Resp3Value::Map(map) = attribute {
+            let merged = attributes.get_or_insert_with(Resp3Map::new);
+            for (key, value) in map {
+                merged.insert(key, value);
+            }
+        }
+        position = after;
+    }
+    Ok(parse_at(input, position, 0)?.map(|(value, length)| (value, attributes, length)))
+}
+
+// Finds where a frame arriving in pieces ends without parsing it, resuming from where the previous
+// call stopped, so a large reply is walked once instead of once per read. Malformed input counts as
+// complete, parse_frame then reports it
+#[derive(Debug, Default)]
+pub(crate) struct FrameScanner {
+    position: usize,
+    // The elements still expected by each aggregate being read, and whether it is an attribute
+    open: Vec<(i64, bool)>,
+    // Where the frame ends, once complete and well-formed down to the lengths
+    end: Option<usize>,
+}
+
+impl FrameScanner {
+    pub(crate) fn is_complete(&mut self, input: &[u8]) -> bool {
+        loop {
+            let Some(line_end) = find_crlf(input, self.position) else { return false };
+            if line_end == self.position {
+                return true;
+            }
+            let prefix = input[self.position];
+            let next = line_end + 2;
+            let header = integer(&input[self.position + 1..line_end]);
+            let complete = match (prefix, header) {
+                (b'*' | b'%' | b'~' | b'>' | b'|', Ok(header)) => {
+                    let elements = if matches!(prefix, b'%' | b'|') { header.checked_mul(2) } else { Some(header) };
+                    self.position = next;
+                    match elements {
+                        Some(-1) if prefix == b'*' => self.close_value(false),
+                        Some(0) => self.close_value(prefix == b'|'),
+                        Some(elements) if elements > 0 && self.open.len() < MAX_NESTING => {
+                            self.open.push((elements, prefix == b'|'));
+                            false
+                        }
+                        _ => return true,
+                    }
+                }
+                (b'$', Ok(-1)) => {
+                    self.position = next;
+                    self.close_value(false)
+                }
+                (b'$' | b'=' | b'!', Ok(header)) => {
+                    match usize::try_from(header).ok().and_then(|length| next.checked_add(length)?.checked_add(2)) {
+                        Some(end) if input.len() < end => return false,
+                        Some(end) => {
+                            self.position = end;
+                            self.close_value(false)
+                        }
+                        None => return true,
+                    }
+                }
+                // Simple strings, errors, numbers and the like fit on their line, their content
+                // is for the parser to check
+                (b'+' | b'-' | b':' | b'_' | b',' | b'(' | b'#', _) => {
+                    self.position = next;
+                    self.close_value(false)
+                }
+                _ => return true,
+            };
+            if complete {
+                self.end = Some(self.position);
+                return true;
+            }
+        }
+    }
+
+    // The length of the frame found complete, None when it was malformed. A frame whose content
+    // fails to parse can then be skipped, the stream stays in step
+    pub(crate) fn frame_length(&self) -> Option<usize> {
+        self.end
+    }
+
+    // An attribute does not take a slot, the value it describes follows. True once the frame is whole
+    fn close_value(&mut self, attribute: bool) -> bool {
+        if attribute {
+            return false;
+        }
+        while let Some((remaining, _)) = self.open.last_mut() {
+            *remaining -= 1;
+            if *remaining > 0 {
+                return false;
+            }
+            if let Some((_, true)) = self.open.pop() {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+// Only the line and the dispatch live here, its stack frame is paid for every nesting level
+fn parse_at(input: &[u8], start: usize, depth: usize) -> Result<Option<(Resp3Value, usize)>, Resp3Error> {
+    let line_end = match find_crlf(input, start) {
+        Some(line_end) => line_end,
+        None => return Ok(None),
+    };
+    if line_end == start {
+        return Err(Resp3Error::Protocol("Empty line where a reply was expected".to_string()));
+    }
+
+    let line = &input[start + 1..line_end];
+    let next = line_end + 2;
+    match input[start] {
+        // Push messages, e.g. the Pub/Sub ones over RESP3, are arrays sent without being asked for.
+        // Sets, e.g. the SMEMBERS replies over RESP3, are read as the arrays RESP2 sends
+        prefix @ (b'*' | b'%' | b'~' | b'>') => parse_aggregate(input, prefix, line, next, depth),
+        b'|' => parse_attributed(input, line, next, depth),
+        prefix => parse_scalar(input, prefix, line, next),
+    }
+}
+
+// Arrays, and maps whose keys and values alternate the same way. Recursion goes through here,
+// so the checks and conversions are left to other functions
+fn parse_aggregate(input: &[u8], prefix: u8, line: &[u8], next: usize, depth: usize) -> Result<Option<(Resp3Value, usize)>, Resp3Error> {
+    let Some(elements) = aggregate_length(prefix, line, depth)? else { return Ok(Some((Resp3Value::Null, next))) };
+    let mut values = Vec::new();
+    let mut position = next;
+    for _ in 0..elements {
+        match parse_at(input, position, depth + 1)? {
+            Some((value, after)) => {
+                values.push(value);
+                position = after;
+            }
+            None => return Ok(None),
+        }
+    }
+    Ok(Some((aggregate(prefix, values), position)))
+}
+
+// An attribute, a map of details about the reply that follows, then the reply itself. Only the
+// reply is kept
+fn parse_attributed(input: &[u8], line: &[u8], next: usize, depth: usize) -> Result<Option<(Resp3Value, usize)>, Resp3Error> {
+    let Some((_, after)) = parse_aggregate(input, b'%', line, next, depth)? else { return Ok(None) };
+    if depth == MAX_NESTING {
+        return Err(Resp3Error::Protocol(format!("Replies nested deeper than {} levels", MAX_NESTING)));
+    }
+    parse_at(input, after, depth + 1)
+}
+
+// The number of elements that follow, twice the length of a map, or None for a null array
+fn aggregate_length(prefix: u8, line: &[u8], depth: usize) -> Result<Option<i64>, Resp3Error> {
+    let kind = match prefix {
+        b'%' => "map",
+        b'~' => "set",
+        _ => "array",
+    };
+    let count = integer(line)?;
+    if count == -1 && prefix == b'*' {
+        return Ok(None);
+    }
+    if count < 0 {
+        return Err(Resp3Error::Protocol(format!("Invalid {} length {}", kind, count)));
+    }
+    if count > 0 && depth == MAX_NESTING {
+        return Err(Resp3Error::Protocol(format!("Replies nested deeper than {} levels", MAX_NESTING)));
+    }
+    match prefix {
+        b'%' => count.checked_mul(2).map(Some).ok_or_else(|| Resp3Error::Protocol(format!("Invalid map length {}", count))),
+        _ => Ok(Some(count)),
+    }
+}
+
+fn aggregate(prefix: u8, values: Vec<Resp3Value>) -> Resp3Value {
+    match prefix {
+        b'%' => {
+            let mut values = values.into_iter();
+            let mut map = Resp3Map::new();
+            while let (Some(key), Some(value)) = (values.next(), values.next()) {
+                map.insert(key, value);
+            }
+            Resp3Value::Map(map)
+        }
+        _ => Resp3Value::Array(values),
+    }
+}
+
+fn parse_scalar(input: &[u8], prefix: u8, line: &[u8], next: usize) -> Result<Option<(Resp3Value, usize)>, Resp3Error> {
+    match prefix {
+        b'+' => Ok(Some((Resp3Value::SimpleString(utf8(line)?), next))),
+        b'-' => Ok(Some((Resp3Value::Error(utf8(line)?), next))),
+        b':' => Ok(Some((Resp3Value::Integer(integer(line)?), next))),
+        b'_' if line.is_empty() => Ok(Some((Resp3Value::Null, next))),
+        b'_' => Err(Resp3Error::Protocol("Null reply with a payload".to_string())),
+        b',' => Ok(Some((Resp3Value::Double(double(line)?), next))),
+        b'(' => Ok(Some((Resp3Value::BigNumber(big_number(line)?), next))),
+        b'$' => {
+            if integer(line)? == -1 {
+                return Ok(Some((Resp3Value::Null, next)));
+            }
+            Ok(blob(input, line, next, "Bulk string")?.map(|(bytes, end)| (Resp3Value::BulkString(bytes.to_vec()), end)))
+        }
+        // Booleans are read as the 1 and 0 RESP2 sends in their place
+        b'#' => match line {
+            b"t" => Ok(Some((Resp3Value::Integer(1), next))),
+            b"f" => Ok(Some((Resp3Value::Integer(0), next))),
+            _ => Err(Resp3Error::Protocol(format!("Invalid boolean '{}'", String::from_utf8_lossy(line)))),
+        },
+        // Blob errors are errors whose message may span lines
+        b'!' => {
+            let Some((bytes, end)) = blob(input, line, next, "Blob error")? else { return Ok(None) };
+            Ok(Some((Resp3Value::Error(utf8(bytes)?), end)))
+        }
+        b'=' => {
+            let Some((bytes, end)) = blob(input, line, next, "Verbatim string")? else { return Ok(None) };
+            // A three characters format, a colon, then the text
+            match (bytes.get(..3), bytes.get(3)) {
+                (Some(format), Some(b':')) => Ok(Some((Resp3Value::VerbatimString { format: utf8(format)?, text: utf8(&bytes[4..])? }, end))),
+                _ => Err(Resp3Error::Protocol("Verbatim string without a format".to_string())),
+            }
+        }
+        prefix => Err(Resp3Error::Protocol(format!("Unknown reply type '{}'", prefix as char))),
+    }
+}
+
+// The bytes of a length prefixed reply, the header line being its length, and where it ends
+fn blob<'a>(input: &'a [u8], line: &[u8], next: usize, kind: &str) -> Result<Option<(&'a [u8], usize)>, Resp3Error> {
+    let length = integer(line)?;
+    let length = usize::try_from(length)
+        .map_err(|_| Resp3Error::Protocol(format!("Invalid {} length {}", kind.to_lowercase(), length)))?;
+    let end = next.checked_add(length)
+        .ok_or_else(|| Resp3Error::Protocol(format!("Invalid {} length {}", kind.to_lowercase(), length)))?;
+    if input.len() < end + 2 {
+        return Ok(None);
+    }
+    if &input[end..end + 2] != b"\r\n" {
+        return Err(Resp3Error::Protocol(format!("{} is not terminated by CRLF", kind)));
+    }
+    Ok(Some((&input[next..end], end + 2)))
+}
+
+fn find_crlf(input: &[u8], start: usize) -> Option<usize> {
+    input.get(start..)?
+        .windows(2)
+        .position(|window| window == b"\r\n")
+        .map(|offset| start + offset)
+}
+
+fn utf8(line: &[u8]) -> Result<String, Resp3Error> {
+    String::from_utf8(line.to_vec()).map_err(|_| Resp3Error::Protocol("Reply is not valid UTF-8".to_string()))
+}
+
+fn integer(line: &[u8]) -> Result<i64, Resp3Error> {
+    utf8(line)?
+        .parse()
+        .map_err(|_| Resp3Error::Protocol(format!("Invalid integer '{}'", String::from_utf8_lossy(line))))
+}
+
+// inf, -inf and nan are matched exactly, Rust would also accept e.g. "infinity"
+fn double(line: &[u8]) -> Result<f64, Resp3Error> {
+    let invalid = || Resp3Error::Protocol(format!("Invalid double '{}'", String::from_utf8_lossy(line)));
+    match line {
+        b"inf" => Ok(f64::INFINITY),
+        b"-inf" => Ok(f64::NEG_INFINITY),
+        b"nan" | b"-nan" => Ok(f64::NAN),
+        _ if line.iter().all(|byte| byte.is_ascii_digit() || matches!(byte, b'-' | b'+' | b'.' | b'e' | b'E')) => {
+            utf8(line)?.parse().map_err(|_| invalid())
+        }
+        _ => Err(invalid()),
+    }
+}
+
+fn big_number(line: &[u8]) -> Result<String, Resp3Error> {
+    let digits = line.strip_prefix(b"-").unwrap_or(line);
+    if digits.is_empty() || !digits.iter().all(u8::is_ascii_digit) {
+        return Err(Resp3Error::Protocol(format!("Invalid big number '{}'", String::from_utf8_lossy(line))));
+    }
+    utf8(line)
+}
@@ -0,0 +1,88 @@
+// src/resp3/pool.rs
+
+use crate::resp3::commands::client::PingCommand;
+use crate::resp3::error::Resp3Error;
+use crate::resp3::utils::command::TypedCommand;
+use crate::resp3::utils::connection_state::ConnectionState;
+use crate::resp3::utils::redis_connection::{RedisConnection, RedisConnectionBuilder};
+
+// Opens the connections of a deadpool, bb8 or r2d2 pool with the options of the builder.
+// The connections block, so with the async pools connecting and checking them blocks the
+// task asking the pool for one: give the builder a timeout, or use them from spawn_blocking
+#[derive(Debug, Clone)]
+pub struct RedisConnectionManager {
+    builder: RedisConnectionBuilder,
+}
+
+impl RedisConnectionManager {
+    pub fn new(builder: RedisConnectionBuilder) -> Self {
+        RedisConnectionManager { builder }
+    }
+
+    fn connect(&self) -> Result<RedisConnection, Resp3Error> {
+        self.builder.clone().connect()
+    }
+
+    // Connections given back subscribed, monitoring or inside MULTI would surprise the next user
+    fn check(conn: &mut RedisConnection) -> Result<(), Resp3Error> {
+        if conn.state() != ConnectionState::Normal {
+            return Err(Resp3Error::InvalidState(format!("Connection given back to the pool while {:?}", conn.state())));
+        }
+        PingCommand.query(conn)?;
+        Ok(())
+    }
+}
+
+// Spares naming the object type, which deadpool cannot infer
+#[cfg(feature="deadpool")]
+pub type DeadpoolPool = deadpool::managed::Pool<RedisConnectionManager>;
+
+#[cfg(feature="deadpool")]
+impl deadpool::managed::Manager for RedisConnectionManager {
+    type Type = RedisConnection;
+    type Error = Resp3Error;
+
+    async fn create(&self) -> Result<RedisConnection, Resp3Error> {
+        self.connect()
+    }
+
+    async fn recycle(&self, conn: &mut RedisConnection, _: &deadpool::managed::Metrics) -> deadpool::managed::RecycleResult<Resp3Error> {
+        Ok(Self::check(conn)?)
+    }
+}
+
+#[cfg(feature="bb8")]
+impl bb8::ManageConnection for RedisConnectionManager {
+    type Connection = RedisConnection;
+    type Error = Resp3Error;
+
+    async fn connect(&self) -> Result<RedisConnection, Resp3Error> {
+        RedisConnectionManager::connect(self)
+    }
+
+    async fn is_valid(&self, conn: &mut RedisConnection) -> Result<(), Resp3Error> {
+        Self::check(conn)
+    }
+
+    fn has_broken(&self, conn: &mut RedisConnection) -> bool {
+        conn.state() != ConnectionState::Normal
+    }
+}
+
+#[cfg(feature="r2d2")]
+impl r2d2::ManageConnection for RedisConnectionManager {
+    type Connection = RedisConnection;
+    type Error = Resp3Error;
+
+    fn connect(&self) -> Result<RedisConnection, Resp3Error> {
+        RedisConnectionManager::connect(self)
+    }
+
+    fn is_valid(&self, conn: &mut RedisConnection) -> Result<(), Resp3Error> {
+        Self::check(conn)
+    }
+
+    fn has_broken(&self, conn: &mut RedisConnection) -> bool {
+        conn.state() != ConnectionState::Normal
+    }
+}
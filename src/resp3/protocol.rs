@@ -4,9 +4,17 @@
 pub const SIMPLE_STRING_PREFIX: &str = "+";
 pub const BULK_STRING_PREFIX: &str = "$";
 pub const ERROR_PREFIX: &str = "-";
+pub const INTEGER_PREFIX: &str = ":";
 pub const ARRAY_PREFIX: &str = "*";
+pub const DOUBLE_PREFIX: &str = ",";
+pub const BIG_NUMBER_PREFIX: &str = "(";
+pub const VERBATIM_STRING_PREFIX: &str = "=";
+pub const MAP_PREFIX: &str = "%";
 
 // Line endings
 pub const CR: &str = "\r";
 pub const LF: &str = "\n";
 pub const CRLF: &str = "\r\n";
+
+// Canonical frames to check parsers and encoders against
+pub mod testvectors;
@@ -0,0 +1,180 @@
+// src/resp3/protocol/testvectors.rs
+
+use crate::resp3::value::Resp3Value;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RespVersion {
+    Resp2,
+    Resp3,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Outcome {
+    // The frame decodes to the value. Canonical frames are also what encoding the value gives
+    Value { value: Resp3Value, canonical: bool },
+    // A prefix of a valid frame, more bytes are needed
+    Incomplete,
+    // Not valid RESP, it has to be rejected
+    Invalid,
+}
+
+// A frame as a server sends it, with what a conforming parser makes of it
+#[derive(Debug, Clone, PartialEq)]
+pub struct TestVector {
+    pub name: &'static str,
+    pub version: RespVersion,
+    pub frame: &'static [u8],
+    pub outcome: Outcome,
+}
+
+fn value(name: &'static str, frame: &'static [u8], value: Resp3Value) -> TestVector {
+    TestVector { name, version: RespVersion::Resp2, frame, outcome: Outcome::Value { value, canonical: true } }
+}
+
+fn resp3(name: &'static str, frame: &'static [u8], value: Resp3Value) -> TestVector {
+    TestVector { name, version: RespVersion::Resp3, frame, outcome: Outcome::Value { value, canonical: true } }
+}
+
+fn other(name: &'static str, version: RespVersion, frame: &'static [u8], outcome: Outcome) -> TestVector {
+    TestVector { name, version, frame, outcome }
+}
+
+fn bulk(bytes: &[u8]) -> Resp3Value {
+    Resp3Value::BulkString(bytes.to_vec())
+}
+
+fn map(entries: Vec<(Resp3Value, Resp3Value)>) -> Resp3Value {
+    Resp3Value::Map(entries.into_iter().collect())
+}
+
+fn simple(string: &str) -> Resp3Value {
+    Resp3Value::SimpleString(string.to_string())
+}
+
+// The examples of the RESP specification (https://redis.io/docs/reference/protocol-spec/),
+// plus the edge cases around them
+pub fn all() -> Vec<TestVector> {
+    use Outcome::{Incomplete, Invalid};
+    use RespVersion::{Resp2, Resp3};
+
+    vec![
+        value("simple string", b"+OK\r\n", simple("OK")),
+        value("empty simple string", b"+\r\n", simple("")),
+        value("error", b"-ERR unknown command 'foobar'\r\n", Resp3Value::Error("ERR unknown command 'foobar'".to_string())),
+        value("error with a prefix", b"-WRONGTYPE Operation against a key holding the wrong kind of value\r\n",
+            Resp3Value::Error("WRONGTYPE Operation against a key holding the wrong kind of value".to_string())),
+        value("zero", b":0\r\n", Resp3Value::Integer(0)),
+        value("integer", b":1000\r\n", Resp3Value::Integer(1000)),
+        value("negative integer", b":-1\r\n", Resp3Value::Integer(-1)),
+        value("largest integer", b":9223372036854775807\r\n", Resp3Value::Integer(i64::MAX)),
+        other("integer with a plus sign", Resp2, b":+1\r\n", Outcome::Value { value: Resp3Value::Integer(1), canonical: false }),
+        value("bulk string", b"$5\r\nhello\r\n", bulk(b"hello")),
+        value("empty bulk string", b"$0\r\n\r\n", bulk(b"")),
+        value("bulk string holding CRLF", b"$4\r\na\r\nb\r\n", bulk(b"a\r\nb")),
+        value("binary bulk string", b"$3\r\n\x00\xff\x80\r\n", bulk(b"\x00\xff\x80")),
+        value("null bulk string", b"$-1\r\n", Resp3Value::Null),
+        value("empty array", b"*0\r\n", Resp3Value::Array(vec![])),
+        value("array of bulk strings", b"*2\r\n$5\r\nhello\r\n$5\r\nworld\r\n", Resp3Value::Array(vec![bulk(b"hello"), bulk(b"world")])),
+        value("array of integers", b"*3\r\n:1\r\n:2\r\n:3\r\n",
+            Resp3Value::Array(vec![Resp3Value::Integer(1), Resp3Value::Integer(2), Resp3Value::Integer(3)])),
+        value("array of mixed types", b"*5\r\n:1\r\n:2\r\n:3\r\n:4\r\n$5\r\nhello\r\n", Resp3Value::Array(vec![
+            Resp3Value::Integer(1), Resp3Value::Integer(2), Resp3Value::Integer(3), Resp3Value::Integer(4), bulk(b"hello"),
+        ])),
+        value("nested arrays", b"*2\r\n*3\r\n:1\r\n:2\r\n:3\r\n*2\r\n+Hello\r\n-World\r\n", Resp3Value::Array(vec![
+            Resp3Value::Array(vec![Resp3Value::Integer(1), Resp3Value::Integer(2), Resp3Value::Integer(3)]),
+            Resp3Value::Array(vec![simple("Hello"), Resp3Value::Error("World".to_string())]),
+        ])),
+        value("null in an array", b"*3\r\n$5\r\nhello\r\n$-1\r\n$5\r\nworld\r\n",
+            Resp3Value::Array(vec![bulk(b"hello"), Resp3Value::Null, bulk(b"world")])),
+        other("null array", Resp2, b"*-1\r\n", Outcome::Value { value: Resp3Value::Null, canonical: false }),
+
+        other("simple string without CRLF", Resp2, b"+OK", Incomplete),
+        other("simple string without LF", Resp2, b"+OK\r", Incomplete),
+        other("bulk string cut short", Resp2, b"$5\r\nhel", Incomplete),
+        other("bulk string without CRLF", Resp2, b"$5\r\nhello", Incomplete),
+        other("array missing elements", Resp2, b"*2\r\n:1\r\n", Incomplete),
+        other("nothing", Resp2, b"", Incomplete),
+
+        other("empty line", Resp2, b"\r\n", Invalid),
+        other("unknown type", Resp2, b"?x\r\n", Invalid),
+        other("integer that is not a number", Resp2, b":abc\r\n", Invalid),
+        other("integer out of range", Resp2, b":9223372036854775808\r\n", Invalid),
+        other("bulk string longer than its length", Resp2, b"$3\r\nhello\r\n", Invalid),
+        other("negative bulk string length", Resp2, b"$-5\r\n", Invalid),
+        other("negative array length", Resp2, b"*-2\r\n", Invalid),
+        other("simple string that is not UTF-8", Resp2, b"+\xff\r\n", Invalid),
+
+        // RESP3 has a single null, encoded back as the RESP2 null bulk string
+        other("null", Resp3, b"_\r\n", Outcome::Value { value: Resp3Value::Null, canonical: false }),
+        other("null in an array", Resp3, b"*2\r\n_\r\n:1\r\n", Outcome::Value { value: Resp3Value::Array(vec![Resp3Value::Null, Resp3Value::Integer(1)]), canonical: false }),
+        other("null with a payload", Resp3, b"_x\r\n", Invalid),
+        other("true", Resp3, b"#t\r\n", Outcome::Value { value: Resp3Value::Integer(1), canonical: false }),
+        other("false", Resp3, b"#f\r\n", Outcome::Value { value: Resp3Value::Integer(0), canonical: false }),
+        other("boolean that is not t or f", Resp3, b"#x\r\n", Invalid),
+        other("boolean with a payload", Resp3, b"#tt\r\n", Invalid),
+        other("boolean in an array", Resp3, b"*2\r\n#t\r\n#f\r\n", Outcome::Value {
+            value: Resp3Value::Array(vec![Resp3Value::Integer(1), Resp3Value::Integer(0)]),
+            canonical: false,
+        }),
+        resp3("double", b",1.23\r\n", Resp3Value::Double(1.23)),
+        resp3("integral double", b",10\r\n", Resp3Value::Double(10.0)),
+        resp3("negative double", b",-0.5\r\n", Resp3Value::Double(-0.5)),
+        resp3("infinite double", b",inf\r\n", Resp3Value::Double(f64::INFINITY)),
+        resp3("negative infinite double", b",-inf\r\n", Resp3Value::Double(f64::NEG_INFINITY)),
+        resp3("not a number double", b",nan\r\n", Resp3Value::Double(f64::NAN)),
+        other("double with an exponent", Resp3, b",1.5e3\r\n", Outcome::Value { value: Resp3Value::Double(1500.0), canonical: false }),
+        resp3("big number", b"(3492890328409238509324850943850943825024385\r\n",
+            Resp3Value::BigNumber("3492890328409238509324850943850943825024385".to_string())),
+        resp3("negative big number", b"(-3492890328409238509324850943850943825024385\r\n",
+            Resp3Value::BigNumber("-3492890328409238509324850943850943825024385".to_string())),
+        other("double cut short", Resp3, b",1.2", Incomplete),
+        other("double that is not a number", Resp3, b",abc\r\n", Invalid),
+        other("spelled out infinite double", Resp3, b",infinity\r\n", Invalid),
+        other("empty double", Resp3, b",\r\n", Invalid),
+        other("big number that is not a number", Resp3, b"(12a\r\n", Invalid),
+        other("big number with only a sign", Resp3, b"(-\r\n", Invalid),
+        other("blob error", Resp3, b"!21\r\nSYNTAX invalid syntax\r\n", Outcome::Value { value: Resp3Value::Error("SYNTAX invalid syntax".to_string()), canonical: false }),
+        other("blob error spanning lines", Resp3, b"!9\r\nERR a\r\nbc\r\n", Outcome::Value { value: Resp3Value::Error("ERR a\r\nbc".to_string()), canonical: false }),
+        other("empty blob error", Resp3, b"!0\r\n\r\n", Outcome::Value { value: Resp3Value::Error(String::new()), canonical: false }),
+        other("blob error cut short", Resp3, b"!21\r\nSYNTAX", Incomplete),
+        other("blob error longer than its length", Resp3, b"!2\r\nERR\r\n", Invalid),
+        resp3("verbatim string", b"=15\r\ntxt:Some string\r\n",
+            Resp3Value::VerbatimString { format: "txt".to_string(), text: "Some string".to_string() }),
+        resp3("markdown verbatim string", b"=11\r\nmkd:# Title\r\n", Resp3Value::VerbatimString { format: "mkd".to_string(), text: "# Title".to_string() }),
+        resp3("empty verbatim string", b"=4\r\ntxt:\r\n", Resp3Value::VerbatimString { format: "txt".to_string(), text: String::new() }),
+        other("verbatim string cut short", Resp3, b"=15\r\ntxt:Some", Incomplete),
+        other("verbatim string without a format", Resp3, b"=3\r\ntxt\r\n", Invalid),
+        other("verbatim string with a short format", Resp3, b"=5\r\ntx:ab\r\n", Invalid),
+        other("null verbatim string", Resp3, b"=-1\r\n", Invalid),
+        resp3("map", b"%2\r\n+first\r\n:1\r\n+second\r\n:2\r\n", map(vec![
+            (simple("first"), Resp3Value::Integer(1)), (simple("second"), Resp3Value::Integer(2)),
+        ])),
+        resp3("empty map", b"%0\r\n", map(vec![])),
+        resp3("map of arrays", b"%1\r\n$5\r\nflags\r\n*1\r\n+noscript\r\n", map(vec![
+            (bulk(b"flags"), Resp3Value::Array(vec![simple("noscript")])),
+        ])),
+        other("map missing a value", Resp3, b"%1\r\n+first\r\n", Incomplete),
+        other("negative map length", Resp3, b"%-1\r\n", Invalid),
+        other("set", Resp3, b"~2\r\n+orange\r\n+apple\r\n", Outcome::Value { value: Resp3Value::Array(vec![simple("orange"), simple("apple")]), canonical: false }),
+        other("empty set", Resp3, b"~0\r\n", Outcome::Value { value: Resp3Value::Array(vec![]), canonical: false }),
+        other("set in a map", Resp3, b"%1\r\n+flags\r\n~1\r\n+readonly\r\n", Outcome::Value {
+            value: map(vec![(simple("flags"), Resp3Value::Array(vec![simple("readonly")]))]),
+            canonical: false,
+        }),
+        other("set missing a member", Resp3, b"~2\r\n+orange\r\n", Incomplete),
+        other("negative set length", Resp3, b"~-1\r\n", Invalid),
+        // Push messages are read as arrays, RedisConnection tells them apart by their first byte
+        other("push", Resp3, b">3\r\n+message\r\n+somechannel\r\n+this is the message\r\n", Outcome::Value {
+            value: Resp3Value::Array(vec![
+                Resp3Value::SimpleString("message".to_string()),
+                Resp3Value::SimpleString("somechannel".to_string()),
+                Resp3Value::SimpleString("this is the message".to_string()),
+            ]),
+            canonical: false,
+        }),
+        // The attribute is left out of the value, parse_frame_with_attributes returns it apart
+        other("attribute", Resp3, b"|1\r\n+key-popularity\r\n%2\r\n$1\r\na\r\n,0.1923\r\n$1\r\nb\r\n,0.0012\r\n*2\r\n:2039123\r\n:9543892\r\n",
+            Outcome::Value { value: Resp3Value::Array(vec![Resp3Value::Integer(2039123), Resp3Value::Integer(9543892)]), canonical: false }),
+        other("attribute without its reply", Resp3, b"|1\r\n+ttl\r\n:3\r\n", Incomplete),
+    ]
+}
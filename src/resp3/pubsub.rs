@@ -0,0 +1,209 @@
+// src/resp3/pubsub.rs
+
+use std::collections::VecDeque;
+
+use crate::resp3::error::Resp3Error;
+use crate::resp3::utils::command::Command;
+use crate::resp3::utils::redis_connection::RedisConnection;
+use crate::resp3::value::{FromResp3Value, Resp3Value};
+
+// SUBSCRIBE and the like with any number of channels or patterns, which are sent UPPERCASE
+// like PublishCommand sends them
+pub(crate) struct SubscriptionCommand<'a> {
+    pub(crate) name: &'static str,
+    pub(crate) names: &'a [String],
+}
+
+impl Command for SubscriptionCommand<'_> {
+    fn get_parts(&self) -> Vec<&str> {
+        let mut parts = vec![self.name];
+        parts.extend(self.names.iter().map(String::as_str));
+        parts
+    }
+}
+
+// The payloads are kept as they were published, which may not be UTF-8, see payload_str
+#[derive(Debug, Clone, PartialEq)]
+pub enum PubSubEvent {
+    Message { channel: String, payload: Vec<u8> },
+    // Received through a pattern subscription
+    PatternMessage { pattern: String, channel: String, payload: Vec<u8> },
+    // The connection dropped and was opened again with every subscription, the messages
+    // published in between are lost
+    Resubscribed,
+}
+
+impl PubSubEvent {
+    // None for the confirmations of the subscriptions. Names that are not valid UTF-8 are
+    // decoded lossily rather than losing the message
+    pub(crate) fn from_message(message: Resp3Value) -> Option<Self> {
+        let Resp3Value::Array(parts) = message else { return None };
+        let mut parts: Vec<Vec<u8>> = parts.into_iter().map(|part| Vec::<u8>::from_resp3_value(part).ok()).collect::<Option<_>>()?;
+        let payload = parts.pop()?;
+        let names: Vec<String> = parts.iter().map(|name| String::from_utf8_lossy(name).into_owned()).collect();
+        match <[String; 2]>::try_from(names) {
+            Ok([kind, channel]) if kind == "message" => Some(PubSubEvent::Message { channel, payload }),
+            Ok(_) => None,
+            Err(names) => match <[String; 3]>::try_from(names) {
+                Ok([kind, pattern, channel]) if kind == "pmessage" => Some(PubSubEvent::PatternMessage { pattern, channel, payload }),
+                _ => None,
+            },
+        }
+    }
+
+    // None for Resubscribed
+    pub fn payload(&self) -> Option<&[u8]> {
+        match self {
+            PubSubEvent::Message { payload, .. } | PubSubEvent::PatternMessage { payload, .. } => Some(payload),
+            PubSubEvent::Resubscribed => None,
+        }
+    }
+
+    // The payload as text, None for Resubscribed and for the payloads that are not valid UTF-8
+    pub fn payload_str(&self) -> Option<&str> {
+        self.payload().and_then(|payload| std::str::from_utf8(payload).ok())
+    }
+}
+
+// A subscriber that remembers its channels and patterns. When the connection drops it
+// reconnects, subscribes to them again and reports it with a Resubscribed event
+pub struct PubSub {
+    conn: RedisConnection,
+    channels: Vec<String>,
+    patterns: Vec<String>,
+    // Messages read in place of the reply to a subscription change
+    pending: VecDeque<PubSubEvent>,
+    // Set when the connection dropped, until it is open and subscribed again
+    disconnected: bool,
+    failed: bool,
+}
+
+impl PubSub {
+    pub fn new(conn: RedisConnection) -> Self {
+        PubSub { conn, channels: Vec::new(), patterns: Vec::new(), pending: VecDeque::new(), disconnected: false, failed: false }
+    }
+
+    pub fn channels(&self) -> &[String] {
+        &self.channels
+    }
+
+    pub fn patterns(&self) -> &[String] {
+        &self.patterns
+    }
+
+    pub fn subscribe(&mut self, channels: &[&str]) -> Result<(), Resp3Error> {
+        let channels = new_names(&self.channels, channels);
+        self.channels.extend(channels.iter().cloned());
+        self.send("SUBSCRIBE", &channels)
+    }
+
+    pub fn psubscribe(&mut self, patterns: &[&str]) -> Result<(), Resp3Error> {
+        let patterns = new_names(&self.patterns, patterns);
+        self.patterns.extend(patterns.iter().cloned());
+        self.send("PSUBSCRIBE", &patterns)
+    }
+
+    pub fn unsubscribe(&mut self, channels: &[&str]) -> Result<(), Resp3Error> {
+        let channels: Vec<String> = channels.iter().map(|channel| channel.to_string()).collect();
+        self.channels.retain(|channel| !channels.contains(channel));
+        self.send("UNSUBSCRIBE", &channels)
+    }
+
+    pub fn punsubscribe(&mut self, patterns: &[&str]) -> Result<(), Resp3Error> {
+        let patterns: Vec<String> = patterns.iter().map(|pattern| pattern.to_string()).collect();
+        self.patterns.retain(|pattern| !patterns.contains(pattern));
+        self.send("PUNSUBSCRIBE", &patterns)
+    }
+
+    // Blocks until the next message. A dropped connection is opened again before reading on,
+    // when that fails the error is returned and the next call tries again. Timeouts are
+    // returned as they are
+    pub fn next_event(&mut self) -> Result<PubSubEvent, Resp3Error> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Ok(event);
+            }
+            if self.disconnected {
+                self.resubscribe()?;
+                self.disconnected = false;
+                return Ok(PubSubEvent::Resubscribed);
+            }
+            match self.conn.read_response() {
+                Ok(message) => {
+                    if let Some(event) = PubSubEvent::from_message(message) {
+                        return Ok(event);
+                    }
+                }
+                Err(err @ Resp3Error::Io(_)) if err.is_timeout() => return Err(err),
+                Err(Resp3Error::Io(_)) => self.disconnected = true,
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    // Calls the callback for each event until it returns false or the connection fails
+    pub fn listen<F: FnMut(PubSubEvent) -> bool>(&mut self, mut callback: F) -> Result<(), Resp3Error> {
+        loop {
+            if !callback(self.next_event()?) {
+                return Ok(());
+            }
+        }
+    }
+
+    pub fn into_inner(self) -> RedisConnection {
+        self.conn
+    }
+
+    // The change is kept even when the connection dropped, the reconnect then applies it
+    fn send(&mut self, name: &'static str, names: &[String]) -> Result<(), Resp3Error> {
+        if names.is_empty() || self.disconnected {
+            return Ok(());
+        }
+        match self.conn.request_command(&SubscriptionCommand { name, names }) {
+            Ok(reply) => {
+                self.pending.extend(PubSubEvent::from_message(reply));
+                Ok(())
+            }
+            Err(err @ Resp3Error::Io(_)) if err.is_timeout() => Err(err),
+            Err(Resp3Error::Io(_)) => {
+                self.disconnected = true;
+                Ok(())
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    fn resubscribe(&mut self) -> Result<(), Resp3Error> {
+        self.conn.reconnect()?;
+        for (name, names) in [("SUBSCRIBE", &self.channels), ("PSUBSCRIBE", &self.patterns)] {
+            if !names.is_empty() {
+                self.conn.request_command(&SubscriptionCommand { name, names })?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn new_names(current: &[String], names: &[&str]) -> Vec<String> {
+    let mut added: Vec<String> = Vec::new();
+    for name in names {
+        if !current.iter().chain(&added).any(|known| known == name) {
+            added.push(name.to_string());
+        }
+    }
+    added
+}
+
+// Ends after the first error, reconnect failures included
+impl Iterator for PubSub {
+    type Item = Result<PubSubEvent, Resp3Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        let event = self.next_event();
+        self.failed = event.is_err();
+        Some(event)
+    }
+}
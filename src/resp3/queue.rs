@@ -0,0 +1,134 @@
+// src/resp3/queue.rs
+
+use std::time::Duration;
+
+use crate::resp3::commands::eval::EvalCommand;
+use crate::resp3::commands::list::{LLenCommand, PushCommand};
+use crate::resp3::error::Resp3Error;
+use crate::resp3::utils::command::{Command, TypedCommand};
+use crate::resp3::utils::redis_connection::RedisConnection;
+use crate::resp3::value::{FromResp3Value, Resp3Value};
+
+// Server time in milliseconds, so the workers' clocks do not matter
+const NOW: &str = "\
+local time = redis.call('time')
+local now = tonumber(time[1]) * 1000 + math.floor(tonumber(time[2]) / 1000)
+";
+
+// Moves the oldest job to the processing list and records when it was reserved
+const RESERVE_SCRIPT: &str = "
+local job = redis.call('lmove', KEYS[1], KEYS[2], 'RIGHT', 'LEFT')
+if job then
+    redis.call('hset', KEYS[3], job, now)
+end
+return job";
+
+// Drops the job from the processing list, returns the number of entries removed
+const ACK_SCRIPT: &str = "\
+local removed = redis.call('lrem', KEYS[1], 1, ARGV[1])
+if removed > 0 and redis.call('lpos', KEYS[1], ARGV[1]) == false then
+    redis.call('hdel', KEYS[2], ARGV[1])
+end
+return removed";
+
+// Like ack, then puts the job back at the end of the queue
+const REQUEUE_SCRIPT: &str = "\
+local removed = redis.call('lrem', KEYS[1], 1, ARGV[1])
+if removed > 0 then
+    if redis.call('lpos', KEYS[1], ARGV[1]) == false then
+        redis.call('hdel', KEYS[2], ARGV[1])
+    end
+    redis.call('lpush', KEYS[3], ARGV[1])
+end
+return removed";
+
+// Puts the jobs reserved longer than the visibility timeout ago back at the front of the
+// queue, the first reserved first. Jobs without a reservation time, e.g. moved there by hand, get one now
+const REAP_SCRIPT: &str = "
+local reaped = 0
+for _, job in ipairs(redis.call('lrange', KEYS[1], 0, -1)) do
+    local reserved = redis.call('hget', KEYS[2], job)
+    if not reserved then
+        redis.call('hset', KEYS[2], job, now)
+    elseif now - tonumber(reserved) >= tonumber(ARGV[1]) then
+        redis.call('lrem', KEYS[1], 1, job)
+        redis.call('hdel', KEYS[2], job)
+        redis.call('rpush', KEYS[3], job)
+        reaped = reaped + 1
+    end
+end
+return reaped";
+
+// At-least-once job processing on lists: reserve moves a job to <name>:processing, where it
+// stays until it is acked or requeued. Jobs of workers that died while processing them are
+// put back by reap once the visibility timeout elapsed, so jobs must be safe to run twice.
+// Jobs are identified by their payload, which is stored as it is
+pub struct ReliableQueue {
+    queue: String,
+    processing: String,
+    reserved: String,
+    visibility_timeout: Duration,
+}
+
+impl ReliableQueue {
+    pub fn new(name: &str) -> Self {
+        ReliableQueue {
+            queue: name.to_string(),
+            processing: format!("{}:processing", name),
+            reserved: format!("{}:reserved", name),
+            visibility_timeout: Duration::from_secs(30),
+        }
+    }
+
+    // How long a job may stay reserved before reap gives it to another worker
+    pub fn visibility_timeout(mut self, timeout: Duration) -> Self {
+        self.visibility_timeout = timeout;
+        self
+    }
+
+    // Returns the number of jobs waiting
+    pub fn push(&self, conn: &mut RedisConnection, job: &str) -> Result<u64, Resp3Error> {
+        PushCommand::left(self.queue.clone(), [job]).verbatim_values().query(conn)
+    }
+
+    // The oldest job waiting, None when the queue is empty
+    pub fn reserve(&self, conn: &mut RedisConnection) -> Result<Option<String>, Resp3Error> {
+        let script = [NOW, RESERVE_SCRIPT].concat();
+        let reply = EvalCommand::new(script, vec![self.queue.clone(), self.processing.clone(), self.reserved.clone()], Vec::new()).execute(conn)?;
+        Option::from_resp3_value(reply)
+    }
+
+    // The job is done, returns false if it was not reserved (e.g. reaped in the meantime)
+    pub fn ack(&self, conn: &mut RedisConnection, job: &str) -> Result<bool, Resp3Error> {
+        let keys = vec![self.processing.clone(), self.reserved.clone()];
+        removed(EvalCommand::new(ACK_SCRIPT.to_string(), keys, vec![job.to_string()]).verbatim_args().execute(conn)?)
+    }
+
+    // The job failed and goes back at the end of the queue, returns false if it was not reserved
+    pub fn requeue(&self, conn: &mut RedisConnection, job: &str) -> Result<bool, Resp3Error> {
+        let keys = vec![self.processing.clone(), self.reserved.clone(), self.queue.clone()];
+        removed(EvalCommand::new(REQUEUE_SCRIPT.to_string(), keys, vec![job.to_string()]).verbatim_args().execute(conn)?)
+    }
+
+    // Returns the number of stuck jobs put back, to be called periodically by any worker
+    pub fn reap(&self, conn: &mut RedisConnection) -> Result<u64, Resp3Error> {
+        let script = [NOW, REAP_SCRIPT].concat();
+        let keys = vec![self.processing.clone(), self.reserved.clone(), self.queue.clone()];
+        let timeout = self.visibility_timeout.as_millis().to_string();
+        u64::from_resp3_value(EvalCommand::new(script, keys, vec![timeout]).execute(conn)?)
+    }
+
+    // The number of jobs waiting
+    pub fn len(&self, conn: &mut RedisConnection) -> Result<u64, Resp3Error> {
+        LLenCommand::new(self.queue.clone()).query(conn)
+    }
+
+    // The number of jobs reserved and not acked yet
+    pub fn processing_len(&self, conn: &mut RedisConnection) -> Result<u64, Resp3Error> {
+        LLenCommand::new(self.processing.clone()).query(conn)
+    }
+}
+
+fn removed(reply: Resp3Value) -> Result<bool, Resp3Error> {
+    Ok(u64::from_resp3_value(reply)? > 0)
+}
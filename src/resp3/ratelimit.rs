@@ -0,0 +1,103 @@
+// src/resp3/ratelimit.rs
+
+use std::time::{Duration, SystemTime};
+
+use crate::resp3::commands::eval::EvalCommand;
+use crate::resp3::error::Resp3Error;
+use crate::resp3::utils::command::Command;
+use crate::resp3::utils::redis_connection::RedisConnection;
+use crate::resp3::utils::token::generate_token;
+use crate::resp3::value::Resp3Value;
+
+// Counts requests in the current window, the window starts with the first request and
+// the counter expires with it. Returns {allowed, remaining, milliseconds until reset}
+const FIXED_WINDOW_SCRIPT: &str = "\
+local current = redis.call('incr', KEYS[1])
+if current == 1 then
+    redis.call('pexpire', KEYS[1], ARGV[2])
+end
+local ttl = redis.call('pttl', KEYS[1])
+if ttl < 0 then
+    redis.call('pexpire', KEYS[1], ARGV[2])
+    ttl = tonumber(ARGV[2])
+end
+local limit = tonumber(ARGV[1])
+if current > limit then
+    return {0, 0, ttl}
+end
+return {1, limit - current, ttl}";
+
+// Keeps a sorted set of request timestamps, dropping the ones that left the window.
+// Returns {allowed, remaining, milliseconds until the oldest request leaves the window}
+const SLIDING_WINDOW_SCRIPT: &str = "\
+local time = redis.call('time')
+local now = tonumber(time[1]) * 1000 + math.floor(tonumber(time[2]) / 1000)
+local limit = tonumber(ARGV[1])
+local window = tonumber(ARGV[2])
+redis.call('zremrangebyscore', KEYS[1], 0, now - window)
+local count = redis.call('zcard', KEYS[1])
+local allowed = 0
+if count < limit then
+    redis.call('zadd', KEYS[1], now, ARGV[3])
+    redis.call('pexpire', KEYS[1], window)
+    count = count + 1
+    allowed = 1
+end
+local oldest = redis.call('zrange', KEYS[1], 0, 0, 'WITHSCORES')
+local reset = window
+if oldest[2] then
+    reset = tonumber(oldest[2]) + window - now
+end
+return {allowed, limit - count, reset}";
+
+// The outcome of a rate limit check
+#[derive(Debug, Clone, PartialEq)]
+pub struct Decision {
+    pub allowed: bool,
+    pub remaining: u64,
+    pub reset_at: SystemTime,
+}
+
+// Allows up to limit requests per window, with the counter reset at the end of each window
+pub struct FixedWindowLimiter;
+
+impl FixedWindowLimiter {
+    pub fn check(conn: &mut RedisConnection, key: &str, limit: u64, window: Duration) -> Result<Decision, Resp3Error> {
+        let eval_command = EvalCommand::new(
+            FIXED_WINDOW_SCRIPT.to_string(),
+            vec![key.to_string()],
+            vec![limit.to_string(), window.as_millis().to_string()],
+        );
+        decision(eval_command.execute(conn)?)
+    }
+}
+
+// Allows up to limit requests in any window-long span of time, smoothing the bursts a
+// fixed window allows at its boundaries at the cost of one sorted set entry per request
+pub struct SlidingWindowLimiter;
+
+impl SlidingWindowLimiter {
+    pub fn check(conn: &mut RedisConnection, key: &str, limit: u64, window: Duration) -> Result<Decision, Resp3Error> {
+        let eval_command = EvalCommand::new(
+            SLIDING_WINDOW_SCRIPT.to_string(),
+            vec![key.to_string()],
+            vec![limit.to_string(), window.as_millis().to_string(), generate_token()],
+        );
+        decision(eval_command.execute(conn)?)
+    }
+}
+
+pub(crate) fn decision(reply: Resp3Value) -> Result<Decision, Resp3Error> {
+    let values: Vec<i64> = reply.as_array()
+        .map(|values| values.iter().filter_map(Resp3Value::as_i64).collect())
+        .unwrap_or_default();
+
+    match values.as_slice() {
+        [allowed, remaining, reset_after] => Ok(Decision {
+            allowed: *allowed == 1,
+            remaining: (*remaining).max(0) as u64,
+            reset_at: SystemTime::now() + Duration::from_millis((*reset_after).max(0) as u64),
+        }),
+        _ => Err(Resp3Error::UnexpectedReply(format!("Expected {{allowed, remaining, reset}}, got {:?}", reply))),
+    }
+}
@@ -0,0 +1,137 @@
+// src/resp3/scripts.rs
+
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use crate::resp3::commands::eval::{EvalShaCommand, ScriptLoadCommand};
+use crate::resp3::error::{ErrorCode, Resp3Error};
+use crate::resp3::ratelimit::{decision, Decision};
+use crate::resp3::utils::command::{Command, TypedCommand};
+use crate::resp3::utils::redis_connection::RedisConnection;
+use crate::resp3::value::{FromResp3Value, Resp3Value};
+
+// A Lua script run with EVALSHA, loaded with SCRIPT LOAD the first time and again whenever
+// the server answers NOSCRIPT, e.g. after a restart or SCRIPT FLUSH
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Script {
+    pub name: &'static str,
+    pub code: &'static str,
+}
+
+// The digests returned by SCRIPT LOAD, by code. They only depend on the code, so they hold
+// for every server
+fn digests() -> &'static Mutex<HashMap<&'static str, String>> {
+    static DIGESTS: OnceLock<Mutex<HashMap<&'static str, String>>> = OnceLock::new();
+    DIGESTS.get_or_init(Default::default)
+}
+
+impl Script {
+    pub const fn new(name: &'static str, code: &'static str) -> Self {
+        Script { name, code }
+    }
+
+    // Loads the script on the server, returns its SHA1 digest
+    pub fn load(&self, conn: &mut RedisConnection) -> Result<String, Resp3Error> {
+        let sha = ScriptLoadCommand::new(self.code.to_string()).query(conn)?;
+        digests().lock().unwrap().insert(self.code, sha.clone());
+        Ok(sha)
+    }
+
+    // The arguments are sent as they are, the keys UPPERCASE as usual
+    pub fn invoke(&self, conn: &mut RedisConnection, keys: Vec<String>, args: Vec<String>) -> Result<Resp3Value, Resp3Error> {
+        let known = digests().lock().unwrap().get(self.code).cloned();
+        let sha = match known {
+            Some(sha) => sha,
+            None => self.load(conn)?,
+        };
+        match EvalShaCommand::new(sha, keys.clone(), args.clone()).verbatim_args().execute(conn) {
+            Err(Resp3Error::Server(error)) if error.code == ErrorCode::Other("NOSCRIPT".to_string()) => {
+                let sha = self.load(conn)?;
+                EvalShaCommand::new(sha, keys, args).verbatim_args().execute(conn)
+            }
+            reply => reply,
+        }
+    }
+}
+
+// Returns the value of KEYS[1] and sets its TTL to ARGV[1] milliseconds, nil when there is no
+// such key. GETEX does the same from Redis 6.2 on
+pub const GET_AND_EXPIRE: Script = Script::new("get_and_expire", "\
+local value = redis.call('get', KEYS[1])
+if value then
+    redis.call('pexpire', KEYS[1], ARGV[1])
+end
+return value");
+
+// Pushes ARGV[2..] at the head of the list KEYS[1] and trims it to ARGV[1] entries, dropping the
+// oldest ones. Returns the length of the list
+pub const BOUNDED_PUSH: Script = Script::new("bounded_push", "\
+local length = redis.call('lpush', KEYS[1], unpack(ARGV, 2))
+local max = tonumber(ARGV[1])
+if length > max then
+    redis.call('ltrim', KEYS[1], 0, max - 1)
+    length = max
+end
+return length");
+
+// A bucket of ARGV[1] tokens in the hash KEYS[1], refilled with ARGV[2] tokens per second on
+// server time, taking ARGV[3] tokens when there are enough. The hash expires once the bucket
+// would be full again. Returns {allowed, tokens left, milliseconds until enough tokens}
+pub const TOKEN_BUCKET: Script = Script::new("token_bucket", "\
+local time = redis.call('time')
+local now = tonumber(time[1]) * 1000 + math.floor(tonumber(time[2]) / 1000)
+local capacity = tonumber(ARGV[1])
+local rate = tonumber(ARGV[2]) / 1000
+local requested = tonumber(ARGV[3])
+local bucket = redis.call('hmget', KEYS[1], 'tokens', 'updated')
+local tokens = tonumber(bucket[1]) or capacity
+local updated = tonumber(bucket[2]) or now
+tokens = math.min(capacity, tokens + math.max(0, now - updated) * rate)
+local allowed = 0
+local wait = 0
+if tokens >= requested then
+    tokens = tokens - requested
+    allowed = 1
+else
+    wait = math.ceil((requested - tokens) / rate)
+end
+redis.call('hset', KEYS[1], 'tokens', tostring(tokens), 'updated', now)
+redis.call('pexpire', KEYS[1], math.max(1, math.ceil((capacity - tokens) / rate)))
+return {allowed, math.floor(tokens), wait}");
+
+// Runs GET_AND_EXPIRE, the TTL is rounded down to milliseconds
+pub fn get_and_expire(conn: &mut RedisConnection, key: &str, ttl: Duration) -> Result<Option<String>, Resp3Error> {
+    let reply = GET_AND_EXPIRE.invoke(conn, vec![key.to_string()], vec![ttl.as_millis().max(1).to_string()])?;
+    Option::from_resp3_value(reply)
+}
+
+// Runs BOUNDED_PUSH, returns the length of the list
+pub fn bounded_push(conn: &mut RedisConnection, key: &str, value: &str, max_len: u64) -> Result<u64, Resp3Error> {
+    capped_list_push(conn, key, &[value], max_len)
+}
+
+// The "recent items" list: pushes the values at the head, the last one first, and keeps the
+// max_len most recent ones, atomically. LPUSH then LTRIM sent apart would let another client
+// see, or push into, the list before it is trimmed. The values are stored as they are
+pub fn capped_list_push(conn: &mut RedisConnection, key: &str, values: &[&str], max_len: u64) -> Result<u64, Resp3Error> {
+    if max_len == 0 {
+        return Err(Resp3Error::InvalidArgument("capped_list_push needs a max_len of at least 1".to_string()));
+    }
+    if values.is_empty() {
+        return Err(Resp3Error::InvalidArgument("capped_list_push needs at least one value".to_string()));
+    }
+    let mut args = vec![max_len.to_string()];
+    args.extend(values.iter().map(|value| value.to_string()));
+    let reply = BOUNDED_PUSH.invoke(conn, vec![key.to_string()], args)?;
+    u64::from_resp3_value(reply)
+}
+
+// Runs TOKEN_BUCKET, reset_at is when the requested tokens are available, now when they were taken
+pub fn token_bucket(conn: &mut RedisConnection, key: &str, capacity: u64, refill_per_second: f64, requested: u64) -> Result<Decision, Resp3Error> {
+    if !(refill_per_second.is_finite() && refill_per_second > 0.0) {
+        return Err(Resp3Error::InvalidArgument(format!("token_bucket needs a positive refill rate, got {}", refill_per_second)));
+    }
+    let args = vec![capacity.to_string(), refill_per_second.to_string(), requested.to_string()];
+    decision(TOKEN_BUCKET.invoke(conn, vec![key.to_string()], args)?)
+}
@@ -0,0 +1,137 @@
+// src/resp3/session.rs
+
+use std::time::Duration;
+
+use hmac::{Hmac, Mac};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use sha2::Sha256;
+
+use crate::resp3::commands::get::GetExCommand;
+use crate::resp3::commands::keys::{DelCommand, PExpireCommand};
+use crate::resp3::commands::set::SetCommand;
+use crate::resp3::error::Resp3Error;
+use crate::resp3::utils::command::TypedCommand;
+use crate::resp3::utils::redis_connection::RedisConnection;
+use crate::resp3::utils::token::generate_token;
+
+// Web sessions kept as JSON strings under <prefix><id>. Every load and save pushes the
+// expiration back by the TTL, so only sessions left unused for that long expire
+pub struct SessionStore {
+    conn: RedisConnection,
+    prefix: String,
+    ttl: Duration,
+    secret: Option<Vec<u8>>,
+}
+
+impl SessionStore {
+    pub fn new(conn: RedisConnection, ttl: Duration) -> Self {
+        SessionStore { conn, prefix: "session:".to_string(), ttl, secret: None }
+    }
+
+    pub fn prefix(mut self, prefix: &str) -> Self {
+        self.prefix = prefix.to_string();
+        self
+    }
+
+    // Signs the session IDs with HMAC-SHA256, "<id>.<signature>", so IDs that were not
+    // handed out by a store with the same secret are rejected without asking Redis
+    pub fn signed(mut self, secret: &[u8]) -> Self {
+        self.secret = Some(secret.to_vec());
+        self
+    }
+
+    pub fn into_inner(self) -> RedisConnection {
+        self.conn
+    }
+
+    // Stores a new session and returns its ID, the one to put in the cookie
+    pub fn create<T: Serialize>(&mut self, data: &T) -> Result<String, Resp3Error> {
+        let id = generate_token();
+        self.set(&id, data, SetCommand::nx)?;
+        Ok(match &self.secret {
+            Some(secret) => format!("{}.{}", id, hex(&sign(secret, &id))),
+            None => id,
+        })
+    }
+
+    // None when the session expired, was destroyed or the ID is not valid
+    pub fn load<T: DeserializeOwned>(&mut self, session_id: &str) -> Result<Option<T>, Resp3Error> {
+        let key = match self.key(session_id) {
+            Some(key) => key,
+            None => return Ok(None),
+        };
+        let data = GetExCommand::new(key).px(self.ttl_millis()).query(&mut self.conn)?;
+        data.map(|data| serde_json::from_str(&data).map_err(|err| Resp3Error::UnexpectedReply(format!("Invalid session data: {}", err))))
+            .transpose()
+    }
+
+    // Replaces the data of an existing session, returns false if it is gone
+    pub fn save<T: Serialize>(&mut self, session_id: &str, data: &T) -> Result<bool, Resp3Error> {
+        match self.verify(session_id) {
+            Some(id) => self.set(id, data, SetCommand::xx),
+            None => Ok(false),
+        }
+    }
+
+    // Resets the expiration without reading the data, returns false if the session is gone
+    pub fn touch(&mut self, session_id: &str) -> Result<bool, Resp3Error> {
+        match self.key(session_id) {
+            Some(key) => PExpireCommand::new(key, self.ttl_millis()).query(&mut self.conn),
+            None => Ok(false),
+        }
+    }
+
+    // Returns false if the session was already gone
+    pub fn destroy(&mut self, session_id: &str) -> Result<bool, Resp3Error> {
+        match self.key(session_id) {
+            Some(key) => Ok(DelCommand::new([key]).query(&mut self.conn)? > 0),
+            None => Ok(false),
+        }
+    }
+
+    fn set<T: Serialize>(&mut self, id: &str, data: &T, condition: fn(SetCommand) -> SetCommand) -> Result<bool, Resp3Error> {
+        let data = serde_json::to_string(data).map_err(|err| Resp3Error::InvalidState(format!("Cannot serialize the session: {}", err)))?;
+        let command = SetCommand::new(format!("{}{}", self.prefix, id), data).verbatim_value().px(self.ttl_millis());
+        condition(command).query(&mut self.conn)
+    }
+
+    fn key(&self, session_id: &str) -> Option<String> {
+        self.verify(session_id).map(|id| format!("{}{}", self.prefix, id))
+    }
+
+    // The ID without its signature, None when the signature does not match
+    fn verify<'a>(&self, session_id: &'a str) -> Option<&'a str> {
+        let secret = match &self.secret {
+            Some(secret) => secret,
+            None => return Some(session_id),
+        };
+        let (id, signature) = session_id.rsplit_once('.')?;
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("HMAC takes keys of any size");
+        mac.update(id.as_bytes());
+        mac.verify_slice(&unhex(signature)?).ok()?;
+        Some(id)
+    }
+
+    fn ttl_millis(&self) -> u64 {
+        // PX 0 is an error, the shortest TTL is 1 millisecond
+        self.ttl.as_millis().max(1) as u64
+    }
+}
+
+fn sign(secret: &[u8], id: &str) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("HMAC takes keys of any size");
+    mac.update(id.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn unhex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) || !hex.is_ascii() {
+        return None;
+    }
+    (0..hex.len()).step_by(2).map(|index| u8::from_str_radix(&hex[index..index + 2], 16).ok()).collect()
+}
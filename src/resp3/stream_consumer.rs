@@ -0,0 +1,246 @@
+// src/resp3/stream_consumer.rs
+
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::resp3::commands::stream::{StreamEntry, XAckCommand, XAutoClaimCommand, XGroupCreateCommand, XGroupDelConsumerCommand, XReadGroupCommand};
+use crate::resp3::error::{ErrorCode, Resp3Error};
+use crate::resp3::utils::command::TypedCommand;
+use crate::resp3::utils::redis_connection::RedisConnection;
+
+// Where a read of the group starts, see next_batch
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Phase {
+    // The group may not exist yet
+    Creating,
+    // The entries this consumer was given before it restarted, and did not acknowledge
+    Recovering,
+    Reading,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ConsumerStats {
+    // Entries returned by next_batch, redeliveries included
+    pub delivered: u64,
+    // Of those, the ones delivered before to this consumer
+    pub recovered: u64,
+    // Of those, the ones taken over from idle consumers
+    pub claimed: u64,
+    pub acked: u64,
+}
+
+// Stops a StreamConsumer from another thread, e.g. on a signal
+#[derive(Debug, Clone)]
+pub struct StopHandle {
+    stopping: Arc<AtomicBool>,
+}
+
+impl StopHandle {
+    // next_batch returns no more entries, the one blocked returns once its wait is over
+    pub fn stop(&self) {
+        self.stopping.store(true, Ordering::SeqCst);
+    }
+}
+
+// At-least-once processing of a stream by a consumer group: next_batch gives the entries to
+// process, ack marks them done. The group is created on the first batch, with the stream when
+// there is none. Entries left unacknowledged by this consumer are given again after a restart,
+// those of other consumers once they have been idle for claim_idle, so processing an entry
+// must be safe to repeat. The group and consumer names are sent UPPERCASE like keys, the
+// fields and values come back as the producers sent them
+pub struct StreamConsumer {
+    conn: RedisConnection,
+    stream: String,
+    group: String,
+    consumer: String,
+    start_id: String,
+    batch_size: u64,
+    block: Duration,
+    claim_idle: Duration,
+    claim_interval: Duration,
+    phase: Phase,
+    // The entries of this consumer are read again after this id while recovering
+    recover_cursor: String,
+    // The pending entry the next XAUTOCLAIM starts from
+    claim_cursor: String,
+    last_claim: Option<Instant>,
+    unacked: HashSet<String>,
+    stats: ConsumerStats,
+    stopping: Arc<AtomicBool>,
+}
+
+impl StreamConsumer {
+    pub fn new(conn: RedisConnection, stream: &str, group: &str, consumer: &str) -> Self {
+        StreamConsumer {
+            conn,
+            stream: stream.to_string(),
+            group: group.to_string(),
+            consumer: consumer.to_string(),
+            start_id: "0".to_string(),
+            batch_size: 100,
+            block: Duration::from_secs(1),
+            claim_idle: Duration::from_secs(60),
+            claim_interval: Duration::from_secs(10),
+            phase: Phase::Creating,
+            recover_cursor: "0".to_string(),
+            claim_cursor: "0-0".to_string(),
+            last_claim: None,
+            unacked: HashSet::new(),
+            stats: ConsumerStats::default(),
+            stopping: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    // Where a group created by this consumer starts: "0", the default, for every entry of the
+    // stream, "$" for the ones added from now on
+    pub fn start_id(mut self, id: &str) -> Self {
+        self.start_id = id.to_string();
+        self
+    }
+
+    // The most entries a batch holds, 100 by default
+    pub fn batch_size(mut self, size: u64) -> Self {
+        self.batch_size = size.max(1);
+        self
+    }
+
+    // How long next_batch waits for new entries, 1s by default. The read timeout of the
+    // connection has to be longer, and stopping waits for it
+    pub fn block(mut self, timeout: Duration) -> Self {
+        self.block = timeout;
+        self
+    }
+
+    // How long an entry of another consumer stays unacknowledged before this one takes it
+    // over, 60s by default. It should be well over the time processing a batch takes
+    pub fn claim_idle(mut self, idle: Duration) -> Self {
+        self.claim_idle = idle;
+        self
+    }
+
+    // How often next_batch looks for entries to take over, 10s by default
+    pub fn claim_interval(mut self, interval: Duration) -> Self {
+        self.claim_interval = interval;
+        self
+    }
+
+    pub fn stop_handle(&self) -> StopHandle {
+        StopHandle { stopping: self.stopping.clone() }
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.stopping.load(Ordering::SeqCst)
+    }
+
+    // The next entries to process, empty when none arrived before the block timeout or once
+    // stopped. In order: the entries of this consumer left from before, those of idle
+    // consumers when it is time to claim them, then the ones never delivered
+    pub fn next_batch(&mut self) -> Result<Vec<StreamEntry>, Resp3Error> {
+        if self.is_stopped() {
+            return Ok(Vec::new());
+        }
+        if self.phase == Phase::Creating {
+            self.create_group()?;
+            self.phase = Phase::Recovering;
+        }
+        if self.phase == Phase::Recovering {
+            let cursor = self.recover_cursor.clone();
+            let entries = self.read(&cursor, false)?;
+            if let Some(last) = entries.last() {
+                self.recover_cursor = last.id.clone();
+                self.stats.recovered += entries.len() as u64;
+                return Ok(self.deliver(entries));
+            }
+            self.phase = Phase::Reading;
+        }
+        if self.last_claim.is_none_or(|last| last.elapsed() >= self.claim_interval) {
+            self.last_claim = Some(Instant::now());
+            let entries = self.claim()?;
+            if !entries.is_empty() {
+                self.stats.claimed += entries.len() as u64;
+                return Ok(self.deliver(entries));
+            }
+        }
+        let entries = self.read(">", true)?;
+        Ok(self.deliver(entries))
+    }
+
+    // Marks the entries processed, returns how many were still pending. An entry claimed by
+    // another consumer in the meantime is acknowledged all the same
+    pub fn ack<I, S>(&mut self, ids: I) -> Result<u64, Resp3Error>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let ids: Vec<String> = ids.into_iter().map(Into::into).collect();
+        if ids.is_empty() {
+            return Ok(0);
+        }
+        let acked = XAckCommand::new(self.stream.clone(), self.group.clone(), ids.iter().cloned()).query(&mut self.conn)?;
+        for id in &ids {
+            self.unacked.remove(id);
+        }
+        self.stats.acked += acked;
+        Ok(acked)
+    }
+
+    // The ids of the entries delivered by this consumer and not acknowledged yet
+    pub fn unacked(&self) -> impl Iterator<Item = &str> {
+        self.unacked.iter().map(String::as_str)
+    }
+
+    pub fn stats(&self) -> ConsumerStats {
+        self.stats
+    }
+
+    // Stops and gives the connection back. The entries not acknowledged stay pending, for this
+    // consumer after a restart or for the others to claim. A consumer with none is removed from
+    // the group, so the group does not collect the names of consumers gone for good
+    pub fn shutdown(mut self) -> Result<RedisConnection, Resp3Error> {
+        self.stop_handle().stop();
+        // While recovering, some of its entries may not be known yet
+        if self.phase == Phase::Reading && self.unacked.is_empty() {
+            XGroupDelConsumerCommand::new(self.stream.clone(), self.group.clone(), self.consumer.clone()).query(&mut self.conn)?;
+        }
+        Ok(self.conn)
+    }
+
+    fn create_group(&mut self) -> Result<(), Resp3Error> {
+        match XGroupCreateCommand::new(self.stream.clone(), self.group.clone(), &self.start_id).mkstream().query(&mut self.conn) {
+            Err(Resp3Error::Server(err)) if err.code == ErrorCode::Other("BUSYGROUP".to_string()) => Ok(()),
+            result => result.map(|_| ()),
+        }
+    }
+
+    fn read(&mut self, id: &str, block: bool) -> Result<Vec<StreamEntry>, Resp3Error> {
+        let mut command = XReadGroupCommand::new(self.group.clone(), self.consumer.clone(), [(self.stream.as_str(), id)]).count(self.batch_size);
+        if block {
+            command = command.block(self.block);
+        }
+        let reads = command.query(&mut self.conn)?;
+        Ok(reads.into_iter().flat_map(|(_, entries)| entries).collect())
+    }
+
+    // One XAUTOCLAIM, the next one continues where it stopped
+    fn claim(&mut self) -> Result<Vec<StreamEntry>, Resp3Error> {
+        let command = XAutoClaimCommand::new(self.stream.clone(), self.group.clone(), self.consumer.clone(), self.claim_idle, &self.claim_cursor).count(self.batch_size);
+        let claimed = command.query(&mut self.conn)?;
+        self.claim_cursor = claimed.next_id;
+        Ok(claimed.entries)
+    }
+
+    // Entries deleted from the stream while pending have no fields left to process, they are
+    // acknowledged rather than delivered
+    fn deliver(&mut self, entries: Vec<StreamEntry>) -> Vec<StreamEntry> {
+        let (entries, deleted): (Vec<StreamEntry>, Vec<StreamEntry>) = entries.into_iter().partition(|entry| !entry.fields.is_empty());
+        if !deleted.is_empty() {
+            // Best effort, they are given again otherwise
+            let _ = self.ack(deleted.into_iter().map(|entry| entry.id));
+        }
+        self.unacked.extend(entries.iter().map(|entry| entry.id.clone()));
+        self.stats.delivered += entries.len() as u64;
+        entries
+    }
+}
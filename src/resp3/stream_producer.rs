@@ -0,0 +1,124 @@
+// src/resp3/stream_producer.rs
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::resp3::commands::stream::{StreamTrim, XAddCommand, XLenCommand, XTrimCommand};
+use crate::resp3::error::{Resp3Error, ServerError};
+use crate::resp3::utils::command::{Command, TypedCommand};
+use crate::resp3::utils::redis_connection::RedisConnection;
+use crate::resp3::value::{FromResp3Value, Resp3Value};
+
+#[derive(Debug, Clone)]
+enum Retention {
+    Keep,
+    Trim(StreamTrim),
+    // MinId for the entries younger than this, from the clock of the producer
+    MaxAge(Duration),
+}
+
+// Appends events to a stream, with the ids generated by the server: in order, unique, and
+// starting with the time in milliseconds. The stream is trimmed as entries are added, so it
+// does not grow without bounds. The fields and values are stored as they are
+pub struct StreamProducer {
+    stream: String,
+    retention: Retention,
+    approximate: bool,
+}
+
+impl StreamProducer {
+    // Keeps every entry until trimmed otherwise
+    pub fn new(stream: &str) -> Self {
+        StreamProducer { stream: stream.to_string(), retention: Retention::Keep, approximate: true }
+    }
+
+    // Keeps about the newest max_len entries
+    pub fn max_len(mut self, max_len: u64) -> Self {
+        self.retention = Retention::Trim(StreamTrim::MaxLen(max_len));
+        self
+    }
+
+    // Keeps the entries from this id on, e.g. the ones of the current day
+    pub fn min_id(mut self, id: &str) -> Self {
+        self.retention = Retention::Trim(StreamTrim::MinId(id.to_string()));
+        self
+    }
+
+    // Keeps the entries added less than max_age ago
+    pub fn max_age(mut self, max_age: Duration) -> Self {
+        self.retention = Retention::MaxAge(max_age);
+        self
+    }
+
+    // Trims exactly instead of by whole nodes of the stream, which costs more on every append
+    pub fn exact(mut self) -> Self {
+        self.approximate = false;
+        self
+    }
+
+    // Returns the id of the entry
+    pub fn append(&self, conn: &mut RedisConnection, fields: &[(&str, &str)]) -> Result<String, Resp3Error> {
+        self.entry(fields.iter().copied(), true).query(conn)
+    }
+
+    // With the time of the id set by the producer, e.g. the time of the event, the server
+    // adding the sequence number (Redis 7.0+). The time must not be before the last entry
+    pub fn append_at(&self, conn: &mut RedisConnection, time: SystemTime, fields: &[(&str, &str)]) -> Result<String, Resp3Error> {
+        let millis = time.duration_since(UNIX_EPOCH).map(|since| since.as_millis()).unwrap_or(0);
+        self.entry(fields.iter().copied(), true).id(&format!("{}-*", millis)).query(conn)
+    }
+
+    // Appends the entries in one write, for ingesting at high rates. Trimming once after the last
+    // one keeps what trimming after each would. Returns the ids in order; on an error reply the
+    // entries before it were appended
+    pub fn append_batch<I, E, F, V>(&self, conn: &mut RedisConnection, entries: I) -> Result<Vec<String>, Resp3Error>
+    where
+        I: IntoIterator<Item = E>,
+        E: IntoIterator<Item = (F, V)>,
+        F: Into<String>,
+        V: Into<String>,
+    {
+        let entries: Vec<Vec<(String, String)>> = entries.into_iter()
+            .map(|fields| fields.into_iter().map(|(field, value)| (field.into(), value.into())).collect())
+            .collect();
+        let mut buffer = Vec::new();
+        for (index, fields) in entries.iter().enumerate() {
+            let fields = fields.iter().map(|(field, value)| (field.as_str(), value.as_str()));
+            self.entry(fields, index + 1 == entries.len()).encode_into(&mut buffer);
+        }
+        if entries.is_empty() {
+            return Ok(Vec::new());
+        }
+        conn.request_pipelined(&buffer, entries.len())?.into_iter().map(|reply| match reply {
+            Resp3Value::Error(message) => Err(Resp3Error::Server(ServerError::parse(&message))),
+            reply => String::from_resp3_value(reply),
+        }).collect()
+    }
+
+    // Trims the stream now, e.g. after the retention changed. Returns the number of entries removed
+    pub fn trim(&self, conn: &mut RedisConnection) -> Result<u64, Resp3Error> {
+        match self.trim_strategy() {
+            Some(trim) => XTrimCommand::new(self.stream.clone(), trim, self.approximate).query(conn),
+            None => Ok(0),
+        }
+    }
+
+    pub fn len(&self, conn: &mut RedisConnection) -> Result<u64, Resp3Error> {
+        XLenCommand::new(self.stream.clone()).query(conn)
+    }
+
+    fn trim_strategy(&self) -> Option<StreamTrim> {
+        match &self.retention {
+            Retention::Keep => None,
+            Retention::Trim(trim) => Some(trim.clone()),
+            Retention::MaxAge(max_age) => Some(StreamTrim::since(SystemTime::now() - *max_age)),
+        }
+    }
+
+    fn entry<'a>(&self, fields: impl IntoIterator<Item = (&'a str, &'a str)>, trim: bool) -> XAddCommand {
+        let command = XAddCommand::new(self.stream.clone(), fields).verbatim_values();
+        match self.trim_strategy().filter(|_| trim) {
+            Some(strategy) => command.trim(strategy, self.approximate),
+            None => command,
+        }
+    }
+}
@@ -0,0 +1,394 @@
+// src/resp3/testing.rs
+
+use std::collections::{HashMap, VecDeque};
+use std::io::{self, Read, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use crate::resp3::parser::parse_frame;
+use crate::resp3::utils::transport::{Transport, TransportWrapper};
+use crate::resp3::value::Resp3Value;
+
+// Misbehaviour applied to the next reply of the mock server, on whichever connection sends
+// the next command
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fault {
+    // Sends the reply in writes of at most this many bytes
+    SplitWrites(usize),
+    // Sends only the first bytes of the reply, then closes the connection
+    Truncate(usize),
+    // Closes the connection without replying
+    Disconnect,
+    // Waits before replying
+    Delay(Duration),
+}
+
+#[derive(Default)]
+struct MockState {
+    data: HashMap<Vec<u8>, Vec<u8>>,
+    replies: HashMap<String, Resp3Value>,
+    replies_once: HashMap<String, VecDeque<Resp3Value>>,
+    faults: VecDeque<Fault>,
+    commands: Vec<Vec<String>>,
+    connections: usize,
+}
+
+// An in-process RESP server on a random local port, for tests that cannot reach a Redis server.
+// It knows PING, GET, SET (NX and XX, expirations are ignored), MGET, MSET and DEL, any other command can
+// be given a canned reply. It stops when dropped
+pub struct MockServer {
+    address: SocketAddr,
+    state: Arc<Mutex<MockState>>,
+    stopped: Arc<AtomicBool>,
+}
+
+impl MockServer {
+    pub fn start() -> io::Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let address = listener.local_addr()?;
+        let state = Arc::new(Mutex::new(MockState::default()));
+        let stopped = Arc::new(AtomicBool::new(false));
+
+        let (accept_state, accept_stopped) = (state.clone(), stopped.clone());
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                if accept_stopped.load(Ordering::SeqCst) {
+                    break;
+                }
+                let Ok(stream) = stream else { continue };
+                let _ = stream.set_nodelay(true);
+                accept_state.lock().unwrap().connections += 1;
+                let state = accept_state.clone();
+                thread::spawn(move || serve(stream, state));
+            }
+        });
+        Ok(MockServer { address, state, stopped })
+    }
+
+    // The address to connect to, e.g. RedisConnection::new(&server.address())
+    pub fn address(&self) -> String {
+        self.address.to_string()
+    }
+
+    // Replies to every later command with this name, instead of running it
+    pub fn reply(&self, command: &str, reply: Resp3Value) {
+        self.state.lock().unwrap().replies.insert(command.to_uppercase(), reply);
+    }
+
+    // Replies to the next command with this name only, queued after the previous ones
+    pub fn reply_once(&self, command: &str, reply: Resp3Value) {
+        self.state.lock().unwrap().replies_once.entry(command.to_uppercase()).or_default().push_back(reply);
+    }
+
+    // Applies the fault to the next reply, queued after the previous ones
+    pub fn inject(&self, fault: Fault) {
+        self.state.lock().unwrap().faults.push_back(fault);
+    }
+
+    // Every command received so far, each one as its parts
+    pub fn commands(&self) -> Vec<Vec<String>> {
+        self.state.lock().unwrap().commands.clone()
+    }
+
+    // Number of connections accepted so far
+    pub fn connections(&self) -> usize {
+        self.state.lock().unwrap().connections
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        // Wakes up the accept loop so it sees the flag
+        let _ = TcpStream::connect(self.address);
+    }
+}
+
+fn serve(mut stream: TcpStream, state: Arc<Mutex<MockState>>) {
+    let mut buffer = Vec::new();
+    let mut chunk = [0; 4096];
+    loop {
+        let (request, length) = match parse_frame(&buffer) {
+            Ok(Some(frame)) => frame,
+            Ok(None) => match stream.read(&mut chunk) {
+                Ok(0) | Err(_) => return,
+                Ok(bytes_read) => {
+                    buffer.extend_from_slice(&chunk[..bytes_read]);
+                    continue;
+                }
+            },
+            Err(err) => {
+                let _ = stream.write_all(&Resp3Value::Error(format!("ERR Protocol error: {}", err)).to_bytes());
+                return;
+            }
+        };
+        buffer.drain(..length);
+
+        let parts = match request {
+            Resp3Value::Array(parts) => parts.into_iter().filter_map(|part| match part {
+                Resp3Value::BulkString(bytes) => Some(bytes),
+                _ => None,
+            }).collect(),
+            _ => Vec::new(),
+        };
+        let (reply, fault) = {
+            let mut state = state.lock().unwrap();
+            let reply = respond(&mut state, &parts);
+            (reply, state.faults.pop_front())
+        };
+        if write_reply(&mut stream, &reply.to_bytes(), fault).is_err() {
+            return;
+        }
+    }
+}
+
+fn respond(state: &mut MockState, parts: &[Vec<u8>]) -> Resp3Value {
+    let parts_text: Vec<String> = parts.iter().map(|part| String::from_utf8_lossy(part).into_owned()).collect();
+    state.commands.push(parts_text.clone());
+    let name = match parts_text.first() {
+        Some(name) => name.to_uppercase(),
+        None => return Resp3Value::Error("ERR Protocol error: expected an array of bulk strings".to_string()),
+    };
+    if let Some(reply) = state.replies_once.get_mut(&name).and_then(VecDeque::pop_front) {
+        return reply;
+    }
+    if let Some(reply) = state.replies.get(&name) {
+        return reply.clone();
+    }
+
+    match (name.as_str(), &parts[1..]) {
+        ("PING", []) => Resp3Value::SimpleString("PONG".to_string()),
+        ("PING", [message]) => Resp3Value::BulkString(message.clone()),
+        ("GET", [key]) => state.data.get(key).cloned().map_or(Resp3Value::Null, Resp3Value::BulkString),
+        ("SET", [key, value, options @ ..]) => {
+            let options: Vec<String> = options.iter().map(|option| String::from_utf8_lossy(option).to_uppercase()).collect();
+            let exists = state.data.contains_key(key);
+            if (options.contains(&"NX".to_string()) && exists) || (options.contains(&"XX".to_string()) && !exists) {
+                return Resp3Value::Null;
+            }
+            state.data.insert(key.clone(), value.clone());
+            Resp3Value::SimpleString("OK".to_string())
+        }
+        ("MGET", keys) if !keys.is_empty() => {
+            Resp3Value::Array(keys.iter().map(|key| state.data.get(key).cloned().map_or(Resp3Value::Null, Resp3Value::BulkString)).collect())
+        }
+        ("MSET", pairs) if !pairs.is_empty() && pairs.len() % 2 == 0 => {
+            for pair in pairs.chunks(2) {
+                state.data.insert(pair[0].clone(), pair[1].clone());
+            }
+            Resp3Value::SimpleString("OK".to_string())
+        }
+        ("DEL", keys) if !keys.is_empty() => {
+            Resp3Value::Integer(keys.iter().filter(|key| state.data.remove(*key).is_some()).count() as i64)
+        }
+        ("PING" | "GET" | "SET" | "MGET" | "MSET" | "DEL", _) => {
+            Resp3Value::Error(format!("ERR wrong number of arguments for '{}' command", name.to_lowercase()))
+        }
+        _ => Resp3Value::Error(format!("ERR unknown command '{}'", parts_text[0])),
+    }
+}
+
+fn write_reply(stream: &mut TcpStream, reply: &[u8], fault: Option<Fault>) -> io::Result<()> {
+    match fault {
+        None => stream.write_all(reply),
+        Some(Fault::SplitWrites(size)) => {
+            for piece in reply.chunks(size.max(1)) {
+                stream.write_all(piece)?;
+                stream.flush()?;
+                // Gives the client a chance to read each piece on its own
+                thread::sleep(Duration::from_millis(1));
+            }
+            Ok(())
+        }
+        Some(Fault::Truncate(length)) => {
+            stream.write_all(&reply[..length.min(reply.len())])?;
+            stream.shutdown(Shutdown::Both)?;
+            Err(io::Error::new(io::ErrorKind::ConnectionAborted, "Truncated reply"))
+        }
+        Some(Fault::Disconnect) => {
+            stream.shutdown(Shutdown::Both)?;
+            Err(io::Error::new(io::ErrorKind::ConnectionAborted, "Disconnected"))
+        }
+        Some(Fault::Delay(delay)) => {
+            thread::sleep(delay);
+            stream.write_all(reply)
+        }
+    }
+}
+
+// What a ChaosTransport does to the stream. The random choices come from the seed, so a
+// test sees the same faults on every run
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChaosConfig {
+    latency: Duration,
+    max_read_size: Option<usize>,
+    disconnect_probability: f64,
+    truncate_probability: f64,
+    seed: u64,
+}
+
+impl ChaosConfig {
+    pub fn new() -> Self {
+        ChaosConfig { latency: Duration::ZERO, max_read_size: None, disconnect_probability: 0.0, truncate_probability: 0.0, seed: 1 }
+    }
+
+    // Added before every read and write
+    pub fn latency(mut self, latency: Duration) -> Self {
+        self.latency = latency;
+        self
+    }
+
+    // Reads return at most this many bytes, as if the replies came in small packets
+    pub fn split_reads(mut self, size: usize) -> Self {
+        self.max_read_size = Some(size.max(1));
+        self
+    }
+
+    // Chance for every read and write to fail with ConnectionReset, after which the stream stays broken
+    pub fn disconnect_probability(mut self, probability: f64) -> Self {
+        self.disconnect_probability = probability;
+        self
+    }
+
+    // Chance for every read to return only part of the bytes and then end of stream
+    pub fn truncate_probability(mut self, probability: f64) -> Self {
+        self.truncate_probability = probability;
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    // For RedisConnectionBuilder::transport_wrapper. Each new stream draws from the seed plus the
+    // number of streams before it, so a reconnect does not replay the fault that caused it
+    pub fn wrapper(self) -> TransportWrapper {
+        let streams = AtomicU64::new(0);
+        TransportWrapper::new(move |transport| {
+            let stream = streams.fetch_add(1, Ordering::SeqCst);
+            Box::new(ChaosTransport::new(transport, self.clone().seed(self.seed.wrapping_add(stream))))
+        })
+    }
+}
+
+impl Default for ChaosConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+struct ChaosState {
+    random: u64,
+    broken: Option<io::ErrorKind>,
+    faults: usize,
+}
+
+impl ChaosState {
+    // xorshift64, uniform in [0, 1)
+    fn next_random(&mut self) -> f64 {
+        self.random ^= self.random << 13;
+        self.random ^= self.random >> 7;
+        self.random ^= self.random << 17;
+        (self.random >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+// splitmix64, so close seeds (e.g. 7 and 8) do not start xorshift on tiny numbers. Never 0,
+// which xorshift cannot leave
+fn scramble(seed: u64) -> u64 {
+    let mut random = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    random = (random ^ (random >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    random = (random ^ (random >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    (random ^ (random >> 31)).max(1)
+}
+
+// A transport injecting latency, split reads, disconnects and truncated replies, to exercise
+// the reconnect and retry logic. The clones share the faults, a disconnect breaks both halves
+pub struct ChaosTransport {
+    inner: Box<dyn Transport>,
+    config: ChaosConfig,
+    state: Arc<Mutex<ChaosState>>,
+}
+
+impl ChaosTransport {
+    pub fn new(inner: Box<dyn Transport>, config: ChaosConfig) -> Self {
+        let state = ChaosState { random: scramble(config.seed), broken: None, faults: 0 };
+        ChaosTransport { inner, config, state: Arc::new(Mutex::new(state)) }
+    }
+
+    // Number of disconnects and truncations injected so far
+    pub fn faults(&self) -> usize {
+        self.state.lock().unwrap().faults
+    }
+
+    // Fails the operation when the stream is broken or a disconnect is drawn
+    fn before_io(&self) -> io::Result<()> {
+        if !self.config.latency.is_zero() {
+            thread::sleep(self.config.latency);
+        }
+        let mut state = self.state.lock().unwrap();
+        if state.broken.is_none() && state.next_random() < self.config.disconnect_probability {
+            state.broken = Some(io::ErrorKind::ConnectionReset);
+            state.faults += 1;
+        }
+        match state.broken {
+            Some(kind) => Err(io::Error::new(kind, "Disconnect injected by ChaosTransport")),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Read for ChaosTransport {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        if self.state.lock().unwrap().broken == Some(io::ErrorKind::UnexpectedEof) {
+            return Ok(0);
+        }
+        self.before_io()?;
+        let length = self.config.max_read_size.map_or(buffer.len(), |size| size.min(buffer.len()));
+        let bytes_read = self.inner.read(&mut buffer[..length])?;
+
+        let mut state = self.state.lock().unwrap();
+        if bytes_read > 1 && state.next_random() < self.config.truncate_probability {
+            state.broken = Some(io::ErrorKind::UnexpectedEof);
+            state.faults += 1;
+            return Ok(bytes_read / 2);
+        }
+        Ok(bytes_read)
+    }
+}
+
+impl Write for ChaosTransport {
+    fn write(&mut self, buffer: &[u8]) -> io::Result<usize> {
+        if self.state.lock().unwrap().broken == Some(io::ErrorKind::UnexpectedEof) {
+            return Err(io::Error::new(io::ErrorKind::BrokenPipe, "Stream truncated by ChaosTransport"));
+        }
+        self.before_io()?;
+        self.inner.write(buffer)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl Transport for ChaosTransport {
+    fn try_clone(&self) -> io::Result<Box<dyn Transport>> {
+        Ok(Box::new(ChaosTransport { inner: self.inner.try_clone()?, config: self.config.clone(), state: self.state.clone() }))
+    }
+
+    fn shutdown(&self) -> io::Result<()> {
+        self.inner.shutdown()
+    }
+
+    fn peer_addr(&self) -> io::Result<SocketAddr> {
+        self.inner.peer_addr()
+    }
+
+    fn set_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        self.inner.set_timeout(timeout)
+    }
+}
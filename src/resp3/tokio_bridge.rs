@@ -0,0 +1,110 @@
+// src/resp3/tokio_bridge.rs
+
+use std::io;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::thread;
+use std::time::Duration;
+
+use futures_core::Stream;
+use tokio::sync::mpsc;
+
+use crate::resp3::error::Resp3Error;
+use crate::resp3::pubsub::{PubSub, PubSubEvent};
+use crate::resp3::utils::command::TypedCommand;
+use crate::resp3::utils::redis_connection::{RedisConnection, RedisConnectionBuilder};
+
+// The events of a subscriber as a Stream, read by a thread of their own since the connections
+// block. The subscriber is moved in already subscribed and the stream ends after the first
+// error. Dropping the stream stops the thread once its read returns, so give the connection a
+// timeout for that to happen soon when the channels are quiet
+pub struct PubSubStream {
+    events: mpsc::Receiver<Result<PubSubEvent, Resp3Error>>,
+}
+
+impl PubSubStream {
+    // At most buffer events wait to be taken, the thread stops reading while it is full
+    pub fn new(mut pubsub: PubSub, buffer: usize) -> Self {
+        let (sender, events) = mpsc::channel(buffer.max(1));
+        thread::spawn(move || loop {
+            match pubsub.next_event() {
+                Err(err) if err.is_timeout() && !sender.is_closed() => continue,
+                Err(err) if err.is_timeout() => return,
+                event => {
+                    let failed = event.is_err();
+                    if sender.blocking_send(event).is_err() || failed {
+                        return;
+                    }
+                }
+            }
+        });
+        PubSubStream { events }
+    }
+
+    // None once the stream ended, cancel safe: no event is lost when a select! picks another branch
+    pub async fn next_event(&mut self) -> Option<Result<PubSubEvent, Resp3Error>> {
+        self.events.recv().await
+    }
+}
+
+impl Stream for PubSubStream {
+    type Item = Result<PubSubEvent, Resp3Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.events.poll_recv(cx)
+    }
+}
+
+// Runs blocking commands (BLPOP, XREADGROUP with BLOCK, WAIT...) on the blocking threads of
+// tokio, so they can be awaited, given a timeout and raced in a select!. A command that is
+// given up on, by its timeout or by dropping its future, keeps its connection until the reply
+// arrives: that connection then comes back to the executor if no other command took its place,
+// and the reply is lost, so a popped element goes with it. The next command meanwhile gets a
+// new connection opened with the builder
+pub struct BlockingExecutor {
+    builder: RedisConnectionBuilder,
+    idle: Arc<Mutex<Option<RedisConnection>>>,
+}
+
+impl BlockingExecutor {
+    pub fn new(builder: RedisConnectionBuilder) -> Self {
+        BlockingExecutor { builder, idle: Arc::new(Mutex::new(None)) }
+    }
+
+    pub async fn query<C>(&self, command: C) -> Result<C::Output, Resp3Error>
+    where
+        C: TypedCommand + Send + 'static,
+        C::Output: Send + 'static,
+    {
+        let idle = self.idle.lock().unwrap().take();
+        let builder = self.builder.clone();
+        let slot = Arc::clone(&self.idle);
+        let task = tokio::task::spawn_blocking(move || {
+            let mut conn = match idle {
+                Some(conn) => conn,
+                None => builder.connect()?,
+            };
+            let output = command.query(&mut conn);
+            // A connection that failed is dropped, the next command opens a new one
+            if !matches!(output, Err(Resp3Error::Io(_))) {
+                slot.lock().unwrap().get_or_insert(conn);
+            }
+            output
+        });
+        task.await.map_err(|err| Resp3Error::Io(io::Error::other(err)))?
+    }
+
+    // Gives up after the timeout with a TimedOut error, see Resp3Error::is_timeout. Set the
+    // timeout of the command itself below it, so the server gives up first
+    pub async fn query_timeout<C>(&self, command: C, timeout: Duration) -> Result<C::Output, Resp3Error>
+    where
+        C: TypedCommand + Send + 'static,
+        C::Output: Send + 'static,
+    {
+        match tokio::time::timeout(timeout, self.query(command)).await {
+            Ok(output) => output,
+            Err(_) => Err(Resp3Error::Io(io::Error::new(io::ErrorKind::TimedOut, "No reply to the blocking command within the timeout"))),
+        }
+    }
+}
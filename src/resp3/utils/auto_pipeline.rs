@@ -0,0 +1,198 @@
+use std::io;
+use std::mem;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::resp3::error::{Resp3Error, ServerError};
+use crate::resp3::utils::command::TypedCommand;
+use crate::resp3::utils::connection_state::{command_name, ConnectionState};
+use crate::resp3::utils::redis_connection::{ProtocolVersion, RedisConnection};
+use crate::resp3::value::Resp3Value;
+
+const DEFAULT_WINDOW: Duration = Duration::from_micros(50);
+const DEFAULT_MAX_BATCH: usize = 128;
+
+type Reply = Sender<Result<Resp3Value, Resp3Error>>;
+
+// The commands queued since the last flush, encoded one after the other
+#[derive(Default)]
+struct Batch {
+    buffer: Vec<u8>,
+    replies: Vec<Reply>,
+    stopped: bool,
+}
+
+struct Shared {
+    batch: Mutex<Batch>,
+    queued: Condvar,
+    protocol: ProtocolVersion,
+    batches: AtomicU64,
+    commands: AtomicU64,
+}
+
+// The options of an AutoPipeline, e.g. AutoPipeline::builder().window(Duration::from_micros(100)).start(conn)
+#[derive(Debug, Clone)]
+pub struct AutoPipelineBuilder {
+    window: Duration,
+    max_batch: usize,
+}
+
+impl AutoPipelineBuilder {
+    // How long the first command of a batch waits for others to join it
+    pub fn window(mut self, window: Duration) -> Self {
+        self.window = window;
+        self
+    }
+
+    // A batch this large is flushed without waiting for the end of the window, at least 1
+    pub fn max_batch(mut self, max_batch: usize) -> Self {
+        self.max_batch = max_batch.max(1);
+        self
+    }
+
+    // The connection moves to a thread of its own, which writes the batches and reads their replies
+    pub fn start(self, conn: RedisConnection) -> AutoPipeline {
+        let shared = Arc::new(Shared {
+            batch: Mutex::new(Batch::default()),
+            queued: Condvar::new(),
+            protocol: conn.protocol(),
+            batches: AtomicU64::new(0),
+            commands: AtomicU64::new(0),
+        });
+        let worker = Arc::clone(&shared);
+        thread::spawn(move || run(conn, &worker, self));
+        AutoPipeline { handle: Arc::new(Handle { shared }) }
+    }
+}
+
+// Stops the thread once the last clone of the AutoPipeline is gone
+struct Handle {
+    shared: Arc<Shared>,
+}
+
+impl Drop for Handle {
+    fn drop(&mut self) {
+        self.shared.batch.lock().unwrap().stopped = true;
+        self.shared.queued.notify_one();
+    }
+}
+
+// Coalesces the commands sent from any number of threads within a short window, or up to a
+// batch size, into one pipeline: one write and one read for the whole batch instead of a
+// round trip per command. Each caller still blocks on its own reply, so code written a request
+// per call gets the throughput of a pipeline by querying through a clone of it. Commands
+// changing the state of the connection, e.g. MULTI or SUBSCRIBE, are rejected. When the
+// connection fails, the commands of the batch fail and the next batch reconnects first
+#[derive(Clone)]
+pub struct AutoPipeline {
+    handle: Arc<Handle>,
+}
+
+impl AutoPipeline {
+    pub fn builder() -> AutoPipelineBuilder {
+        AutoPipelineBuilder { window: DEFAULT_WINDOW, max_batch: DEFAULT_MAX_BATCH }
+    }
+
+    // With a 50µs window and batches of up to 128 commands
+    pub fn new(conn: RedisConnection) -> Self {
+        Self::builder().start(conn)
+    }
+
+    pub fn query<C: TypedCommand + ?Sized>(&self, command: &C) -> Result<C::Output, Resp3Error> {
+        let mut encoded = Vec::new();
+        command.encode_into(&mut encoded);
+        ConnectionState::Normal.check_no_reply(&command_name(&encoded).unwrap_or_default())?;
+
+        let (reply, receiver) = mpsc::channel();
+        {
+            let mut batch = self.handle.shared.batch.lock().unwrap();
+            batch.buffer.extend_from_slice(&encoded);
+            batch.replies.push(reply);
+        }
+        self.handle.shared.queued.notify_one();
+        let reply = receiver.recv().map_err(|_| Resp3Error::Io(io::Error::new(io::ErrorKind::BrokenPipe, "Auto pipeline stopped")))??;
+
+        let reply = match self.handle.shared.protocol {
+            ProtocolVersion::Resp2 => command.normalize_resp2(reply),
+            _ => reply,
+        };
+        match reply {
+            Resp3Value::Error(message) => Err(Resp3Error::Server(ServerError::parse(&message))),
+            reply => command.parse_output(reply),
+        }
+    }
+
+    // The number of pipelines written so far, and of the commands they held
+    pub fn flushes(&self) -> (u64, u64) {
+        (self.handle.shared.batches.load(Ordering::Relaxed), self.handle.shared.commands.load(Ordering::Relaxed))
+    }
+}
+
+fn run(mut conn: RedisConnection, shared: &Shared, options: AutoPipelineBuilder) {
+    let mut broken = false;
+    loop {
+        let (buffer, replies) = {
+            let mut batch = shared.batch.lock().unwrap();
+            while batch.replies.is_empty() && !batch.stopped {
+                batch = shared.queued.wait(batch).unwrap();
+            }
+            if batch.stopped && batch.replies.is_empty() {
+                return;
+            }
+            let deadline = Instant::now() + options.window;
+            while batch.replies.len() < options.max_batch && !batch.stopped {
+                let now = Instant::now();
+                if now >= deadline {
+                    break;
+                }
+                batch = shared.queued.wait_timeout(batch, deadline - now).unwrap().0;
+            }
+            (mem::take(&mut batch.buffer), mem::take(&mut batch.replies))
+        };
+        shared.batches.fetch_add(1, Ordering::Relaxed);
+        shared.commands.fetch_add(replies.len() as u64, Ordering::Relaxed);
+
+        if broken {
+            if let Err(err) = conn.reconnect() {
+                fail(replies, &err);
+                continue;
+            }
+            broken = false;
+        }
+        if let Err(err) = conn.write_pipelined(&buffer) {
+            broken = true;
+            fail(replies, &err);
+            continue;
+        }
+        let mut replies = replies.into_iter();
+        for reply in replies.by_ref() {
+            match conn.read_pipelined() {
+                // A caller that went away is not an error
+                Ok(value) => drop(reply.send(Ok(value))),
+                Err(err) => {
+                    broken = true;
+                    drop(reply.send(Err(copy_error(&err))));
+                    fail(replies.by_ref(), &err);
+                    break;
+                }
+            }
+        }
+    }
+}
+
+fn fail(replies: impl IntoIterator<Item = Reply>, err: &Resp3Error) {
+    for reply in replies {
+        let _ = reply.send(Err(copy_error(err)));
+    }
+}
+
+// Every command of the batch gets the error, which cannot be cloned
+fn copy_error(err: &Resp3Error) -> Resp3Error {
+    match err {
+        Resp3Error::Io(err) => Resp3Error::Io(io::Error::new(err.kind(), err.to_string())),
+        err => Resp3Error::Protocol(err.to_string()),
+    }
+}
@@ -0,0 +1,166 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::time::{Duration, Instant};
+
+use crate::resp3::error::Resp3Error;
+use crate::resp3::utils::command::{Command, TypedCommand};
+use crate::resp3::utils::command_executor::CommandExecutor;
+use crate::resp3::value::Resp3Value;
+
+// Keeps the replies of read-only commands in process, for when client-side tracking is not
+// available. A write through the executor drops the replies involving its keys, writes by
+// other clients are only noticed once the entries expire
+pub struct CachedExecutor {
+    executor: CommandExecutor,
+    capacity: usize,
+    ttl: Duration,
+    // Keyed by the encoded request
+    entries: HashMap<Vec<u8>, Entry>,
+    // Least recently used first
+    usage: BTreeMap<u64, Vec<u8>>,
+    // The cached requests of each key, UPPERCASE as the keys are sent
+    requests_by_key: HashMap<String, HashSet<Vec<u8>>>,
+    clock: u64,
+}
+
+struct Entry {
+    reply: Resp3Value,
+    keys: Vec<String>,
+    expires_at: Instant,
+    last_used: u64,
+}
+
+impl CachedExecutor {
+    pub fn new(executor: CommandExecutor, capacity: usize, ttl: Duration) -> Self {
+        CachedExecutor {
+            executor,
+            capacity,
+            ttl,
+            entries: HashMap::new(),
+            usage: BTreeMap::new(),
+            requests_by_key: HashMap::new(),
+            clock: 0,
+        }
+    }
+
+    pub fn into_inner(self) -> CommandExecutor {
+        self.executor
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn execute<T: Command>(&mut self, command: T) -> Result<Resp3Value, Resp3Error> {
+        self.cached_execute(&command)
+    }
+
+    pub fn query<T: TypedCommand>(&mut self, command: T) -> Result<T::Output, Resp3Error> {
+        let reply = self.cached_execute(&command)?;
+        command.parse_output(reply)
+    }
+
+    // Drops the replies involving the key, e.g. after it was changed by another client
+    pub fn invalidate(&mut self, key: &str) {
+        for request in self.requests_by_key.remove(&key.to_uppercase()).unwrap_or_default() {
+            self.remove(&request);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.usage.clear();
+        self.requests_by_key.clear();
+    }
+
+    fn cached_execute<T: Command>(&mut self, command: &T) -> Result<Resp3Value, Resp3Error> {
+        if !is_cacheable(command) {
+            let reply = self.executor.request(command);
+            self.invalidate_writes(command);
+            return reply;
+        }
+
+        let request = command.format_resp_bytes();
+        if let Some(reply) = self.lookup(&request) {
+            return Ok(reply);
+        }
+        let reply = self.executor.request(command)?;
+        self.insert(request, command.keys().iter().map(|key| key.to_uppercase()).collect(), reply.clone());
+        Ok(reply)
+    }
+
+    // Writes without keys (e.g. FLUSHDB, or scripts declaring none) could touch anything
+    fn invalidate_writes<T: Command>(&mut self, command: &T) {
+        if command.is_readonly() {
+            return;
+        }
+        let keys = command.keys();
+        if keys.is_empty() {
+            self.clear();
+        }
+        for key in keys {
+            self.invalidate(key);
+        }
+    }
+
+    fn lookup(&mut self, request: &[u8]) -> Option<Resp3Value> {
+        let expired = self.entries.get(request)?.expires_at <= Instant::now();
+        if expired {
+            self.remove(request);
+            return None;
+        }
+
+        self.clock += 1;
+        let entry = self.entries.get_mut(request)?;
+        let request = self.usage.remove(&entry.last_used)?;
+        entry.last_used = self.clock;
+        self.usage.insert(self.clock, request);
+        Some(entry.reply.clone())
+    }
+
+    fn insert(&mut self, request: Vec<u8>, keys: Vec<String>, reply: Resp3Value) {
+        if self.capacity == 0 {
+            return;
+        }
+        self.remove(&request);
+        while self.entries.len() >= self.capacity {
+            let oldest = match self.usage.values().next() {
+                Some(oldest) => oldest.clone(),
+                None => break,
+            };
+            self.remove(&oldest);
+        }
+
+        self.clock += 1;
+        for key in &keys {
+            self.requests_by_key.entry(key.clone()).or_default().insert(request.clone());
+        }
+        self.usage.insert(self.clock, request.clone());
+        self.entries.insert(request, Entry { reply, keys, expires_at: Instant::now() + self.ttl, last_used: self.clock });
+    }
+
+    fn remove(&mut self, request: &[u8]) {
+        let entry = match self.entries.remove(request) {
+            Some(entry) => entry,
+            None => return,
+        };
+        self.usage.remove(&entry.last_used);
+        for key in entry.keys {
+            if let Some(requests) = self.requests_by_key.get_mut(&key) {
+                requests.remove(request);
+                if requests.is_empty() {
+                    self.requests_by_key.remove(&key);
+                }
+            }
+        }
+    }
+}
+
+// Deterministic read-only commands on keys. Keyless reads (DBSIZE, INFO, KEYS, ...) have
+// nothing a write could invalidate them by
+fn is_cacheable<T: Command>(command: &T) -> bool {
+    command.is_readonly() && command.is_deterministic() && !command.keys().is_empty()
+}
@@ -0,0 +1,168 @@
+use std::borrow::Cow;
+use std::str;
+
+use crate::resp3::error::Resp3Error;
+use crate::resp3::utils::command::{text_parts, Command, ReplyKind};
+use crate::resp3::utils::value_guard::value_indices;
+use crate::resp3::value::Resp3Value;
+
+// A transformation of the values on their way to Redis and back, e.g. compression or
+// encryption, set with CommandExecutor::with_codec. Keys, fields and scores are left alone
+pub trait ValueCodec: Send + Sync {
+    // The bytes stored in place of the value
+    fn encode<'a>(&self, value: &'a [u8]) -> Result<Cow<'a, [u8]>, Resp3Error>;
+
+    // The value back from the stored bytes. The strings of the replies to the commands reading
+    // values go through it, see reads_values, so the bytes it did not encode, e.g. written
+    // before it was set, reach it as well
+    fn decode<'a>(&self, value: &'a [u8]) -> Result<Cow<'a, [u8]>, Resp3Error>;
+
+    // Like encode, with the key the value is written to as it is sent. For the codecs binding
+    // the values to their key, the others leave it to encode
+    fn encode_for<'a>(&self, _key: Option<&[u8]>, value: &'a [u8]) -> Result<Cow<'a, [u8]>, Resp3Error> {
+        self.encode(value)
+    }
+
+    // Like decode, with the key the value was read from. None when the reply does not tell,
+    // e.g. for the members of SUNION, which come from any of its keys
+    fn decode_for<'a>(&self, _key: Option<&[u8]>, value: &'a [u8]) -> Result<Cow<'a, [u8]>, Resp3Error> {
+        self.decode(value)
+    }
+}
+
+// The commands whose replies are values the codecs may have written, the replies of the others
+// (KEYS, INFO, HKEYS...) are left as they are
+fn reads_values(name: &str) -> bool {
+    matches!(name, "GET" | "GETSET" | "GETDEL" | "GETEX" | "MGET" | "SET" | "HGET" | "HMGET" | "HGETALL" | "HVALS"
+        | "LRANGE" | "LINDEX" | "LPOP" | "RPOP" | "LMOVE" | "RPOPLPUSH" | "SMEMBERS" | "SPOP" | "SRANDMEMBER"
+        | "SINTER" | "SUNION" | "SDIFF")
+}
+
+// The part as it goes on the wire, UPPERCASE unless verbatim like write_part sends it
+fn wire_part<T: Command + ?Sized>(command: &T, parts: &[&[u8]], index: usize) -> Vec<u8> {
+    match (command.is_verbatim(index), str::from_utf8(parts[index])) {
+        (false, Ok(text)) => text.to_uppercase().into_bytes(),
+        _ => parts[index].to_vec(),
+    }
+}
+
+// The strings of the reply decoded, however deep in arrays and maps, the codecs applied in the
+// reverse order of encoding. The values of MGET are read from the key at their position, those
+// of the commands with one key from that key
+pub(crate) fn decode_reply<T: Command + ?Sized>(command: &T, reply: Resp3Value, codecs: &[Box<dyn ValueCodec>]) -> Result<Resp3Value, Resp3Error> {
+    let parts = command.get_binary_parts();
+    let name = parts.first().map(|name| String::from_utf8_lossy(name).to_uppercase()).unwrap_or_default();
+    if !reads_values(&name) {
+        return Ok(reply);
+    }
+    let keys: Vec<Vec<u8>> = command.key_indices().into_iter()
+        .filter(|&index| index < parts.len())
+        .map(|index| wire_part(command, &parts, index))
+        .collect();
+    match reply {
+        Resp3Value::Array(values) if name == "MGET" && values.len() == keys.len() => values.into_iter().zip(&keys)
+            .map(|(value, key)| decode_value(value, Some(key), codecs))
+            .collect::<Result<_, _>>()
+            .map(Resp3Value::Array),
+        reply => decode_value(reply, (keys.len() == 1).then(|| keys[0].as_slice()), codecs),
+    }
+}
+
+fn decode_value(reply: Resp3Value, key: Option<&[u8]>, codecs: &[Box<dyn ValueCodec>]) -> Result<Resp3Value, Resp3Error> {
+    match reply {
+        Resp3Value::BulkString(mut value) => {
+            for codec in codecs.iter().rev() {
+                if let Cow::Owned(decoded) = codec.decode_for(key, &value)? {
+                    value = decoded;
+                }
+            }
+            Ok(Resp3Value::BulkString(value))
+        }
+        Resp3Value::Array(values) => values.into_iter().map(|value| decode_value(value, key, codecs)).collect::<Result<_, _>>().map(Resp3Value::Array),
+        Resp3Value::Map(map) => map.into_iter()
+            .map(|(field, value)| Ok((field, decode_value(value, key, codecs)?)))
+            .collect::<Result<_, Resp3Error>>()
+            .map(Resp3Value::Map),
+        reply => Ok(reply),
+    }
+}
+
+// The command with its values encoded, see value_guard::value_indices for which parts those
+// are. The values are uppercased first unless verbatim, so reading them back gives what the
+// command would have stored without the codecs
+pub(crate) struct Encoded<'a, T: Command + ?Sized> {
+    command: &'a T,
+    parts: Vec<Vec<u8>>,
+    values: Vec<usize>,
+}
+
+impl<'a, T: Command + ?Sized> Encoded<'a, T> {
+    pub(crate) fn new(command: &'a T, codecs: &[Box<dyn ValueCodec>]) -> Result<Self, Resp3Error> {
+        let parts = command.get_binary_parts();
+        let name = parts.first().map(|name| String::from_utf8_lossy(name).to_uppercase()).unwrap_or_default();
+        let values = value_indices(&name, parts.len());
+        let key_indices = command.key_indices();
+        let parts = parts.iter().enumerate().map(|(index, part)| {
+            if !values.contains(&index) {
+                return Ok(part.to_vec());
+            }
+            // The key the value goes to is the last one before it, e.g. in MSET or HSET
+            let key = key_indices.iter().filter(|&&key| key < index).max().map(|&key| wire_part(command, &parts, key));
+            let mut value = wire_part(command, &parts, index);
+            for codec in codecs {
+                if let Cow::Owned(encoded) = codec.encode_for(key.as_deref(), &value)? {
+                    value = encoded;
+                }
+            }
+            Ok(value)
+        }).collect::<Result<_, Resp3Error>>()?;
+        Ok(Encoded { command, parts, values })
+    }
+}
+
+impl<T: Command + ?Sized> Command for Encoded<'_, T> {
+    fn is_verbatim(&self, index: usize) -> bool {
+        self.values.contains(&index) || self.command.is_verbatim(index)
+    }
+
+    // Only the textual parts, the binary ones are sent through get_binary_parts
+    fn get_parts(&self) -> Vec<&str> {
+        text_parts(&self.get_binary_parts())
+    }
+
+    fn get_binary_parts(&self) -> Vec<&[u8]> {
+        self.parts.iter().map(Vec::as_slice).collect()
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        self.command.key_indices()
+    }
+
+    fn pattern_indices(&self) -> Vec<usize> {
+        self.command.pattern_indices()
+    }
+
+    fn pattern_option(&self) -> Option<&str> {
+        self.command.pattern_option()
+    }
+
+    fn is_readonly(&self) -> bool {
+        self.command.is_readonly()
+    }
+
+    fn is_deterministic(&self) -> bool {
+        self.command.is_deterministic()
+    }
+
+    fn is_destructive(&self) -> bool {
+        self.command.is_destructive()
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        self.command.expected_reply()
+    }
+
+    fn normalize_resp2(&self, reply: Resp3Value) -> Resp3Value {
+        self.command.normalize_resp2(reply)
+    }
+}
@@ -1,4 +1,48 @@
+use std::borrow::Cow;
+use std::io::Write;
+use std::str;
+
+use crate::resp3::error::Resp3Error;
 use crate::resp3::utils::redis_connection::RedisConnection;
+use crate::resp3::value::{FromResp3Value, Resp3Value};
+
+// The type of reply a command expects, error replies are accepted for any of them
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReplyKind {
+    Any,
+    Status,
+    Integer,
+    Bulk,
+    Array,
+}
+
+impl ReplyKind {
+    // Nil matches everything but Integer, it is how RESP2 sends a missing string or array
+    pub fn matches(&self, reply: &Resp3Value) -> bool {
+        match (self, reply) {
+            (ReplyKind::Any, _) | (_, Resp3Value::Error(_)) => true,
+            (ReplyKind::Integer, Resp3Value::Integer(_)) => true,
+            (ReplyKind::Integer, _) => false,
+            (_, Resp3Value::Null) => true,
+            (ReplyKind::Status, Resp3Value::SimpleString(_)) => true,
+            (ReplyKind::Bulk, Resp3Value::BulkString(_)) => true,
+            // Where RESP2 sends bulk strings, RESP3 may send doubles, big numbers or verbatim strings
+            (ReplyKind::Bulk, Resp3Value::Double(_) | Resp3Value::BigNumber(_) | Resp3Value::VerbatimString { .. }) => true,
+            // RESP3 sends maps where RESP2 sends flat arrays of keys and values
+            (ReplyKind::Array, Resp3Value::Array(_) | Resp3Value::Map(_)) => true,
+            _ => false,
+        }
+    }
+}
+
+// How commands are written on the wire. Inline is the telnet syntax, one line of space
+// separated arguments, for services that only accept it and for reading the traffic
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EncodingMode {
+    #[default]
+    Resp,
+    Inline,
+}
 
 pub trait Command {
 
@@ -7,16 +51,260 @@ pub trait Command {
         conn.send_command(&formatted_command)
     }
 
+    fn execute(&self, conn: &mut RedisConnection) -> Result<Resp3Value, Resp3Error> {
+        conn.request_command(self)
+    }
+
     fn format_resp_command(&self) -> String {
         let parts: Vec<&str> = self.get_parts();
         let mut resp_command = format!("*{}\r\n", parts.len());
 
-        for part in parts {
-            resp_command.push_str(&format!("${}\r\n{}\r\n", part.len(), part.to_uppercase()));
+        for (index, part) in parts.iter().enumerate() {
+            let part = self.format_part(index, part);
+            resp_command.push_str(&format!("${}\r\n{}\r\n", part.len(), part));
         }
 
         resp_command
     }
 
+    // Binary-safe version of format_resp_command, parts that are not valid UTF-8 are sent as they are
+    fn format_resp_bytes(&self) -> Vec<u8> {
+        let mut resp_command = Vec::new();
+        self.encode_into(&mut resp_command);
+        resp_command
+    }
+
+    // Appends the encoded command to the buffer without building intermediate Strings, so a
+    // reused buffer (like the one RedisConnection keeps) only allocates when it has to grow
+    fn encode_into(&self, buffer: &mut Vec<u8>) {
+        let parts: Vec<&[u8]> = self.get_binary_parts();
+        write_length(buffer, b'*', parts.len());
+
+        for (index, part) in parts.iter().enumerate() {
+            write_part(buffer, part, self.is_verbatim(index));
+        }
+    }
+
+    // The inline form of the command, e.g. SET "A KEY" "LINE\nBREAK"\r\n. Arguments with blanks,
+    // quotes or unprintable bytes are double quoted, with the escapes Redis understands there
+    fn encode_inline_into(&self, buffer: &mut Vec<u8>) {
+        for (index, part) in self.get_binary_parts().into_iter().enumerate() {
+            if index > 0 {
+                buffer.push(b' ');
+            }
+            let part = match str::from_utf8(part) {
+                Ok(text) if !self.is_verbatim(index) => Cow::Owned(text.to_uppercase().into_bytes()),
+                _ => Cow::Borrowed(part),
+            };
+            write_inline_part(buffer, &part);
+        }
+        buffer.extend_from_slice(b"\r\n");
+    }
+
+    fn encode_with(&self, mode: EncodingMode, buffer: &mut Vec<u8>) {
+        match mode {
+            EncodingMode::Resp => self.encode_into(buffer),
+            EncodingMode::Inline => self.encode_inline_into(buffer),
+        }
+    }
+
+    // Every part is sent UPPERCASE, commands can opt out for parts that must be sent verbatim
+    fn is_verbatim(&self, _index: usize) -> bool {
+        false
+    }
+
+    fn format_part(&self, index: usize, part: &str) -> String {
+        if self.is_verbatim(index) {
+            part.to_string()
+        } else {
+            part.to_uppercase()
+        }
+    }
+
     fn get_parts(&self) -> Vec<&str>;
+
+    // Commands carrying binary values (e.g. DUMP payloads) override this to send them,
+    // get_parts then only has the textual parts
+    fn get_binary_parts(&self) -> Vec<&[u8]> {
+        self.get_parts().into_iter().map(str::as_bytes).collect()
+    }
+
+    // Positions of the key arguments in get_binary_parts, e.g. for KeyPrefix to namespace them
+    fn key_indices(&self) -> Vec<usize> {
+        Vec::new()
+    }
+
+    // Positions of the patterns matching keys, e.g. of KEYS, which name no key themselves but
+    // are namespaced by KeyPrefix like the keys are
+    fn pattern_indices(&self) -> Vec<usize> {
+        Vec::new()
+    }
+
+    // The option adding a pattern to a command sent without one, e.g. MATCH for SCAN, so
+    // KeyPrefix can still keep it to its namespace
+    fn pattern_option(&self) -> Option<&str> {
+        None
+    }
+
+    // The key arguments, e.g. to pick the cluster node owning them. Keys that are not valid UTF-8 are left out
+    fn keys(&self) -> Vec<&str> {
+        let parts = self.get_binary_parts();
+        self.key_indices().into_iter()
+            .filter_map(|index| parts.get(index).and_then(|part| str::from_utf8(part).ok()))
+            .collect()
+    }
+
+    // Checked against every reply to detect a connection that got out of step
+    fn expected_reply(&self) -> ReplyKind {
+        ReplyKind::Any
+    }
+
+    // Over RESP2 some replies come flattened, e.g. maps as arrays alternating keys and values.
+    // Commands whose RESP3 reply has another shape turn the RESP2 one into it, so both
+    // protocols give the same Resp3Value
+    fn normalize_resp2(&self, reply: Resp3Value) -> Resp3Value {
+        reply
+    }
+
+    // Commands Redis flags as readonly, which never modify the keyspace and can be served by replicas
+    fn is_readonly(&self) -> bool {
+        false
+    }
+
+    // Whether the reply stays the same until the keys are written, so CachedExecutor may keep
+    // it. Not for random picks (SRANDMEMBER) or what time and reads change (TTL, OBJECT FREQ)
+    fn is_deterministic(&self) -> bool {
+        true
+    }
+
+    // Commands wiping data wholesale, e.g. FLUSHDB, refused unless the connection allows them
+    fn is_destructive(&self) -> bool {
+        false
+    }
+}
+
+// A command whose reply converts into a known Rust type
+pub trait TypedCommand: Command {
+    type Output: FromResp3Value;
+
+    fn query(&self, conn: &mut RedisConnection) -> Result<Self::Output, Resp3Error> {
+        let reply = self.execute(conn)?;
+        self.parse_output(reply)
+    }
+
+    // Commands whose reply needs more than the conversion of the output type can override this
+    fn parse_output(&self, reply: Resp3Value) -> Result<Self::Output, Resp3Error> {
+        Self::Output::from_resp3_value(reply)
+    }
+}
+
+// The textual parts of binary-safe commands, for their get_parts
+pub(crate) fn text_parts<'a>(parts: &[&'a [u8]]) -> Vec<&'a str> {
+    parts.iter().filter_map(|part| str::from_utf8(part).ok()).collect()
+}
+
+// Writes a part as a bulk string, UPPERCASE unless verbatim or not valid UTF-8
+pub(crate) fn write_part(buffer: &mut Vec<u8>, part: &[u8], verbatim: bool) {
+    match str::from_utf8(part) {
+        Ok(text) if !verbatim && !text.is_ascii() => {
+            // Uppercasing may change the length of non-ASCII text, which is rare enough to allocate
+            let text = text.to_uppercase();
+            write_length(buffer, b'$', text.len());
+            buffer.extend_from_slice(text.as_bytes());
+        }
+        Ok(_) if !verbatim => {
+            write_length(buffer, b'$', part.len());
+            buffer.extend(part.iter().map(u8::to_ascii_uppercase));
+        }
+        _ => {
+            write_length(buffer, b'$', part.len());
+            buffer.extend_from_slice(part);
+        }
+    }
+    buffer.extend_from_slice(b"\r\n");
+}
+
+// Writes a "*<length>\r\n" or "$<length>\r\n" header
+pub(crate) fn write_length(buffer: &mut Vec<u8>, prefix: u8, length: usize) {
+    buffer.push(prefix);
+    // Writing into a Vec cannot fail
+    let _ = write!(buffer, "{}", length);
+    buffer.extend_from_slice(b"\r\n");
+}
+
+fn is_plain_inline_byte(byte: u8) -> bool {
+    byte.is_ascii_graphic() && !matches!(byte, b'"' | b'\'' | b'\\')
+}
+
+// Appends an argument of an inline command, double quoted unless it is plain printable ASCII
+pub(crate) fn write_inline_part(buffer: &mut Vec<u8>, part: &[u8]) {
+    if !part.is_empty() && part.iter().all(|&byte| is_plain_inline_byte(byte)) {
+        buffer.extend_from_slice(part);
+        return;
+    }
+    buffer.push(b'"');
+    for &byte in part {
+        match byte {
+            b'"' => buffer.extend_from_slice(b"\\\""),
+            b'\\' => buffer.extend_from_slice(b"\\\\"),
+            b'\n' => buffer.extend_from_slice(b"\\n"),
+            b'\r' => buffer.extend_from_slice(b"\\r"),
+            b'\t' => buffer.extend_from_slice(b"\\t"),
+            b' ' | b'\'' => buffer.push(byte),
+            _ if is_plain_inline_byte(byte) => buffer.push(byte),
+            _ => {
+                let _ = write!(buffer, "\\x{:02x}", byte);
+            }
+        }
+    }
+    buffer.push(b'"');
+}
+
+// Splits the first line of an inline command into its arguments, the way Redis does. None
+// when the quotes are unbalanced
+pub(crate) fn parse_inline(command: &[u8]) -> Option<Vec<Vec<u8>>> {
+    let line = command.split(|&byte| byte == b'\n').next().unwrap_or_default();
+    let line = line.strip_suffix(b"\r").unwrap_or(line);
+    let mut parts = Vec::new();
+    let mut bytes = line.iter().copied().peekable();
+    loop {
+        while bytes.next_if(|byte| byte.is_ascii_whitespace()).is_some() {}
+        let Some(first) = bytes.next() else { return Some(parts) };
+
+        let mut part = Vec::new();
+        match first {
+            b'"' => loop {
+                match bytes.next()? {
+                    b'"' => break,
+                    b'\\' => match bytes.next()? {
+                        b'n' => part.push(b'\n'),
+                        b'r' => part.push(b'\r'),
+                        b't' => part.push(b'\t'),
+                        b'b' => part.push(0x08),
+                        b'a' => part.push(0x07),
+                        b'x' => {
+                            let hex = [bytes.next()?, bytes.next()?];
+                            part.push(u8::from_str_radix(str::from_utf8(&hex).ok()?, 16).ok()?);
+                        }
+                        byte => part.push(byte),
+                    },
+                    byte => part.push(byte),
+                }
+            },
+            b'\'' => loop {
+                match bytes.next()? {
+                    b'\'' => break,
+                    b'\\' if bytes.peek() == Some(&b'\'') => part.push(bytes.next()?),
+                    byte => part.push(byte),
+                }
+            },
+            byte => {
+                part.push(byte);
+                while let Some(byte) = bytes.next_if(|byte| !byte.is_ascii_whitespace()) {
+                    part.push(byte);
+                }
+            }
+        }
+        parts.push(part);
+    }
 }
@@ -1,17 +1,226 @@
-use crate::resp3::utils::command::Command;
+use std::io;
+use std::time::{Duration, Instant};
+
+use crate::resp3::commands::replication::WaitCommand;
+use crate::resp3::error::{Resp3Error, ServerError};
+use crate::resp3::utils::command::{Command, TypedCommand};
+use crate::resp3::utils::codec::{decode_reply, Encoded, ValueCodec};
+use crate::resp3::utils::connection_state::ConnectionState;
+use crate::resp3::utils::journal::{runs_in_scripts, GuardedCommand, Journal, JournalEntry};
+use crate::resp3::utils::lint::Lint;
 use crate::resp3::utils::redis_connection::RedisConnection;
+use crate::resp3::utils::stats::{ExecutorStats, ServerHints};
+use crate::resp3::utils::token::generate_token;
+use crate::resp3::utils::value_guard::{value_indices, ValueGuard};
+use crate::resp3::value::Resp3Value;
 
 pub struct CommandExecutor {
     conn: RedisConnection,
+    stats: ExecutorStats,
+    hints: ServerHints,
+    journal: Option<Journal>,
+    value_guard: Option<ValueGuard>,
+    lint: Option<Lint>,
+    // Applied to the values in this order on writes, in the reverse order on reads
+    codecs: Vec<Box<dyn ValueCodec>>,
+    // The last request failed with an I/O error, the next one reconnects first
+    offline: bool,
 }
 
 impl CommandExecutor {
     pub fn new(address: &str) -> Self {
         let conn = RedisConnection::new(address);
-        CommandExecutor { conn }
+        Self::from_connection(conn)
+    }
+
+    // For a connection set up with RedisConnectionBuilder, e.g. over RESP3 or with TLS
+    pub fn from_connection(conn: RedisConnection) -> Self {
+        CommandExecutor { conn, stats: ExecutorStats::new(), hints: ServerHints::new(), journal: None, value_guard: None, lint: None, codecs: Vec::new(), offline: false }
+    }
+
+    // Offline mode: the writes sent with query, or through the wrappers, that fail because the
+    // server is unreachable are kept in the journal and return Resp3Error::Journaled. The next
+    // request reconnects and replays them first, in order. Writes are the commands not flagged
+    // read-only, except those Lua scripts cannot run, e.g. MULTI, EVAL or CONFIG SET, which are
+    // sent as they are. Once the journal is full, the writes fail with the I/O error as usual
+    pub fn with_journal(mut self, journal: Journal) -> Self {
+        self.journal = Some(journal);
+        self
+    }
+
+    // Checks the size of the values written with query or through the wrappers before sending
+    // them, counting the oversized ones in the stats. The sizes are recorded in the stats either way
+    pub fn with_value_guard(mut self, guard: ValueGuard) -> Self {
+        self.value_guard = Some(guard);
+        self
+    }
+
+    // Warns about or refuses the dangerous and deprecated commands sent with query or through
+    // the wrappers, before anything is sent
+    pub fn with_lint(mut self, lint: Lint) -> Self {
+        self.lint = Some(lint);
+        self
+    }
+
+    // Encodes the values written with query or through the wrappers, and decodes every string
+    // read. Codecs added later apply to the output of the earlier ones, e.g. a Compression then
+    // an AesGcmCodec, as encrypted bytes do not compress. The value guard and the stats see
+    // the encoded sizes
+    pub fn with_codec(mut self, codec: impl ValueCodec + 'static) -> Self {
+        self.codecs.push(Box::new(codec));
+        self
+    }
+
+    pub fn journal(&self) -> Option<&Journal> {
+        self.journal.as_ref()
+    }
+
+    // Sends the journaled writes, returns how many ran, the ones that had already run before
+    // the link dropped excluded. Stops at the first I/O error, leaving the rest in the journal.
+    // The writes refused by the server are dropped, as they would have been had they run live
+    pub fn replay(&mut self) -> Result<u64, Resp3Error> {
+        let Some(journal) = self.journal.as_mut() else { return Ok(0) };
+        let mut replayed = 0;
+        let mut result = Ok(());
+        while let Some(entry) = journal.front() {
+            match GuardedCommand::new(entry, journal).execute(&mut self.conn) {
+                Ok(reply) => replayed += GuardedCommand::unwrap_reply(reply)?.is_some() as u64,
+                Err(Resp3Error::Io(err)) => {
+                    self.offline = true;
+                    result = Err(Resp3Error::Io(err));
+                    break;
+                }
+                Err(err) => log::warn!("Journaled write {} failed on replay: {}", entry.id, err),
+            }
+            journal.pop_front();
+        }
+        if replayed > 0 || result.is_ok() {
+            journal.rewrite()?;
+        }
+        result.map(|_| replayed)
+    }
+
+    // The counters of every command sent through the executor or its wrappers, cache hits
+    // of a CachedExecutor excluded. Clone it to read or reset it from another thread
+    pub fn stats(&self) -> &ExecutorStats {
+        &self.stats
+    }
+
+    // The RESP3 attributes of the replies to the commands sent through the executor, e.g. the
+    // keys the server reports as hot. Only servers spoken to over RESP3 send them
+    pub fn server_hints(&self) -> &ServerHints {
+        &self.hints
+    }
+
+    // For the wrappers adding behaviour around the commands, e.g. KeyPrefix. Sends the
+    // command like Command::execute, counting it in the stats
+    pub(crate) fn request<T: Command + ?Sized>(&mut self, command: &T) -> Result<Resp3Value, Resp3Error> {
+        if let Some(lint) = &self.lint {
+            lint.check(command)?;
+        }
+        if self.codecs.is_empty() {
+            return self.send(command);
+        }
+        let encoded = Encoded::new(command, &self.codecs)?;
+        let reply = self.send(&encoded)?;
+        decode_reply(command, reply, &self.codecs)
+    }
+
+    fn send<T: Command + ?Sized>(&mut self, command: &T) -> Result<Resp3Value, Resp3Error> {
+        self.check_values(command)?;
+        if self.offline && self.conn.reconnect().is_ok() {
+            self.offline = false;
+        }
+        if !self.is_journaled(command) {
+            let reply = self.measure(command, |conn| command.execute(conn), |reply| reply.as_ref().err().map(|err| err.kind().to_string()));
+            self.offline |= matches!(reply, Err(Resp3Error::Io(_)));
+            return reply;
+        }
+
+        let entry = JournalEntry::new(generate_token(), command);
+        // The earlier writes go first, this one waits behind them if they cannot be sent
+        let pending = self.journal.as_ref().is_some_and(|journal| !journal.is_empty());
+        let reply = match pending && !self.offline {
+            true => self.replay().and_then(|_| self.send_guarded(command, &entry)),
+            false if self.offline => Err(Resp3Error::Io(io::Error::new(io::ErrorKind::NotConnected, "Redis server unreachable"))),
+            false => self.send_guarded(command, &entry),
+        };
+        match reply {
+            Err(Resp3Error::Io(err)) => {
+                self.offline = true;
+                let journal = self.journal.as_mut().unwrap();
+                if journal.push(entry.clone())? {
+                    Err(Resp3Error::Journaled(entry.id))
+                } else {
+                    Err(Resp3Error::Io(err))
+                }
+            }
+            reply => reply,
+        }
+    }
+
+    // Rejected values are counted as oversized, not in the sizes, as they are never sent
+    fn check_values<T: Command + ?Sized>(&self, command: &T) -> Result<(), Resp3Error> {
+        let parts = command.get_binary_parts();
+        let name = parts.first().map(|name| String::from_utf8_lossy(name).to_uppercase()).unwrap_or_default();
+        let indices = value_indices(&name, parts.len());
+        if indices.is_empty() {
+            return Ok(());
+        }
+        let oversized = match &self.value_guard {
+            Some(guard) => guard.check(command).inspect_err(|_| self.stats.record_values([], true))?,
+            None => false,
+        };
+        self.stats.record_values(indices.into_iter().map(|index| parts[index].len()), oversized);
+        Ok(())
+    }
+
+    fn is_journaled<T: Command + ?Sized>(&self, command: &T) -> bool {
+        let name = command.get_binary_parts().first().map(|name| String::from_utf8_lossy(name).to_uppercase()).unwrap_or_default();
+        self.journal.is_some() && !command.is_readonly() && self.conn.state() == ConnectionState::Normal
+            && ConnectionState::Normal.check_no_reply(&name).is_ok() && runs_in_scripts(&name)
+    }
+
+    // Counted in the stats under the name of the command, not EVAL
+    fn send_guarded<T: Command + ?Sized>(&mut self, command: &T, entry: &JournalEntry) -> Result<Resp3Value, Resp3Error> {
+        let journal = self.journal.as_ref().unwrap();
+        let guarded = GuardedCommand::new(entry, journal);
+        let reply = self.measure(command, |conn| guarded.execute(conn), |reply| reply.as_ref().err().map(|err| err.kind().to_string()))?;
+        // The identifier is new, so the write always runs
+        Ok(GuardedCommand::unwrap_reply(reply)?.unwrap_or(Resp3Value::Null))
     }
 
     pub fn execute<T: Command>(&mut self, command: T) -> String {
-        command.process_command(&mut self.conn)
+        self.measure(&command, |conn| command.process_command(conn), |reply| {
+            let error = reply.strip_prefix('-')?;
+            Some(Resp3Error::Server(ServerError::parse(error.trim_end())).kind().to_string())
+        })
+    }
+
+    pub fn query<T: TypedCommand>(&mut self, command: T) -> Result<T::Output, Resp3Error> {
+        let reply = self.request(&command)?;
+        command.parse_output(reply)
+    }
+
+    // Sends the write followed by WAIT, returning its reply together with the number of
+    // replicas that acknowledged it within the timeout
+    pub fn write_with_ack<T: TypedCommand>(&mut self, command: T, num_replicas: u64, timeout: Duration) -> Result<(T::Output, u64), Resp3Error> {
+        let output = command.parse_output(self.request(&command)?)?;
+        let wait = WaitCommand::new(num_replicas, timeout);
+        let acknowledged = wait.parse_output(self.request(&wait)?)?;
+        Ok((output, acknowledged))
+    }
+
+    fn measure<T: Command + ?Sized, R>(&mut self, command: &T, send: impl FnOnce(&mut RedisConnection) -> R, error_kind: impl FnOnce(&R) -> Option<String>) -> R {
+        let name = command.get_binary_parts().first().map(|name| String::from_utf8_lossy(name).to_uppercase()).unwrap_or_default();
+        let (written, read) = (self.conn.bytes_written(), self.conn.bytes_read());
+        let started = Instant::now();
+        let reply = send(&mut self.conn);
+        let latency = started.elapsed();
+        self.stats.record(&name, latency, self.conn.bytes_written() - written, self.conn.bytes_read() - read, error_kind(&reply).as_deref());
+        if let Some(attributes) = self.conn.take_attributes() {
+            self.hints.record(&name, &attributes);
+        }
+        reply
     }
 }
@@ -0,0 +1,67 @@
+use crate::resp3::commands::client::{ClientReplyCommand, ClientReplyMode};
+use crate::resp3::error::Resp3Error;
+use crate::resp3::utils::command::Command;
+use crate::resp3::utils::connection_state::{command_name, ConnectionState};
+use crate::resp3::utils::redis_connection::RedisConnection;
+use crate::resp3::value::Resp3Value;
+
+// Commands queued for high-throughput ingestion, flushed in one write between CLIENT REPLY OFF
+// and ON. Their replies, errors included, are never sent; the reply to ON tells that the
+// server went through all of them
+pub struct CommandQueue {
+    buffer: Vec<u8>,
+    len: usize,
+}
+
+impl CommandQueue {
+    pub fn new() -> Self {
+        CommandQueue { buffer: Vec::new(), len: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn push<C: Command + ?Sized>(&mut self, command: &C) -> Result<(), Resp3Error> {
+        let start = self.buffer.len();
+        command.encode_into(&mut self.buffer);
+        let name = command_name(&self.buffer[start..]).unwrap_or_default();
+        if let Err(err) = ConnectionState::Normal.check_no_reply(&name) {
+            self.buffer.truncate(start);
+            return Err(err);
+        }
+        self.len += 1;
+        Ok(())
+    }
+
+    // Sends the queued commands and waits for the server to go through them, returning how many
+    pub fn flush(&mut self, conn: &mut RedisConnection) -> Result<usize, Resp3Error> {
+        if self.is_empty() {
+            return Ok(0);
+        }
+        // Inside MULTI the CLIENT REPLY commands would be queued instead of run
+        if conn.state() != ConnectionState::Normal {
+            return Err(Resp3Error::InvalidState(format!("Replies cannot be turned off while the connection is {:?}", conn.state())));
+        }
+
+        let mut request = ClientReplyCommand::new(ClientReplyMode::Off).format_resp_bytes();
+        request.append(&mut self.buffer);
+        ClientReplyCommand::new(ClientReplyMode::On).encode_into(&mut request);
+        let count = std::mem::take(&mut self.len);
+
+        match conn.request(&request)? {
+            Resp3Value::SimpleString(_) => Ok(count),
+            reply => Err(Resp3Error::ProtocolDesync(format!("Expected the reply to CLIENT REPLY ON, got {:?}", reply))),
+        }
+    }
+}
+
+impl Default for CommandQueue {
+    fn default() -> Self {
+        Self::new()
+    }
+}
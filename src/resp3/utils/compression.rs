@@ -0,0 +1,92 @@
+use std::borrow::Cow;
+
+use crate::resp3::error::Resp3Error;
+use crate::resp3::utils::codec::ValueCodec;
+
+// The first bytes of a compressed value. 0xFF never starts UTF-8 text, so only binary values
+// written without compression could be mistaken for one
+const MAGIC: [u8; 4] = [0xFF, b'R', b'3', b'Z'];
+// The magic, the algorithm and the length of the value before compression, a u32 little endian
+const HEADER_LENGTH: usize = MAGIC.len() + 1 + 4;
+const LZ4: u8 = 1;
+
+// A byte of LZ4 input never stands for more than 255 bytes of output
+const MAX_RATIO: usize = 255;
+
+// Compresses the values written above a size, e.g. Compression::lz4(1024) set with
+// CommandExecutor::with_codec. The values read back are decompressed whatever the
+// threshold, and the ones written without it pass through, so it can be turned on over an
+// existing keyspace. Values that do not shrink are written as they are
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Compression {
+    threshold: usize,
+}
+
+impl Compression {
+    // The LZ4 block format, fast enough to be worth it on values of a few hundred bytes
+    pub fn lz4(threshold: usize) -> Self {
+        Compression { threshold }
+    }
+
+    pub fn threshold(&self) -> usize {
+        self.threshold
+    }
+
+    pub fn compress<'a>(&self, value: &'a [u8]) -> Cow<'a, [u8]> {
+        if value.len() < self.threshold || value.len() > u32::MAX as usize {
+            return Cow::Borrowed(value);
+        }
+        let block = lz4_flex::block::compress(value);
+        if HEADER_LENGTH + block.len() >= value.len() {
+            return Cow::Borrowed(value);
+        }
+        let mut compressed = Vec::with_capacity(HEADER_LENGTH + block.len());
+        compressed.extend_from_slice(&MAGIC);
+        compressed.push(LZ4);
+        compressed.extend_from_slice(&(value.len() as u32).to_le_bytes());
+        compressed.extend_from_slice(&block);
+        Cow::Owned(compressed)
+    }
+
+    // Values without the header are returned as they are
+    pub fn decompress(value: &[u8]) -> Result<Cow<'_, [u8]>, Resp3Error> {
+        if !is_compressed(value) {
+            return Ok(Cow::Borrowed(value));
+        }
+        let length = u32::from_le_bytes(value[MAGIC.len() + 1..HEADER_LENGTH].try_into().unwrap()) as usize;
+        match value[MAGIC.len()] {
+            LZ4 => lz4_decompress(&value[HEADER_LENGTH..], length).map(Cow::Owned),
+            algorithm => Err(Resp3Error::UnexpectedReply(format!("Unknown compression algorithm {}", algorithm))),
+        }
+    }
+}
+
+impl ValueCodec for Compression {
+    fn encode<'a>(&self, value: &'a [u8]) -> Result<Cow<'a, [u8]>, Resp3Error> {
+        Ok(self.compress(value))
+    }
+
+    fn decode<'a>(&self, value: &'a [u8]) -> Result<Cow<'a, [u8]>, Resp3Error> {
+        Compression::decompress(value)
+    }
+}
+
+fn is_compressed(value: &[u8]) -> bool {
+    value.len() >= HEADER_LENGTH && value.starts_with(&MAGIC)
+}
+
+// The length comes from the header, a corrupt one must not make it allocate gigabytes
+fn lz4_decompress(input: &[u8], length: usize) -> Result<Vec<u8>, Resp3Error> {
+    if length > input.len().saturating_mul(MAX_RATIO) {
+        return Err(corrupt());
+    }
+    let mut output = vec![0; length];
+    match lz4_flex::block::decompress_into(input, &mut output) {
+        Ok(written) if written == length => Ok(output),
+        _ => Err(corrupt()),
+    }
+}
+
+fn corrupt() -> Resp3Error {
+    Resp3Error::UnexpectedReply("Corrupt LZ4 value".to_string())
+}
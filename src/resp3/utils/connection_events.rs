@@ -0,0 +1,65 @@
+use std::fmt;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionEventKind {
+    // The stream is open and set up, on connect and after each reconnect
+    Connected,
+    // The stream was closed, by close or by the server or the network, or a reconnect failed
+    Disconnected,
+    // A new stream is about to be opened
+    Reconnecting,
+    // The connection entered the subscribed state
+    Subscribed,
+    // The connection left the subscribed state, e.g. after the last UNSUBSCRIBE or a reconnect
+    Unsubscribed,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConnectionEvent {
+    pub kind: ConnectionEventKind,
+    // The address of the endpoint as configured
+    pub endpoint: String,
+    pub at: SystemTime,
+    // The error behind a Disconnected or the Reconnecting that follows it, None for the
+    // events the application asked for, e.g. close or a reconnect after a timeout
+    pub cause: Option<String>,
+}
+
+// Receives the lifecycle events of the connections opened by a builder, e.g. to export their
+// health to the application's own monitoring. Set with RedisConnectionBuilder::events, the
+// connections of a pool share the listener of its builder. It runs on the thread using the
+// connection, in the middle of the request that caused the event, so it should not block
+pub trait ConnectionEvents: Send + Sync {
+    fn on_event(&self, event: &ConnectionEvent);
+}
+
+impl<F> ConnectionEvents for F
+where
+    F: Fn(&ConnectionEvent) + Send + Sync,
+{
+    fn on_event(&self, event: &ConnectionEvent) {
+        self(event)
+    }
+}
+
+// Shared by the builder and every connection it opens
+#[derive(Clone)]
+pub(crate) struct EventListener(Arc<dyn ConnectionEvents>);
+
+impl EventListener {
+    pub(crate) fn new(listener: impl ConnectionEvents + 'static) -> Self {
+        EventListener(Arc::new(listener))
+    }
+
+    pub(crate) fn emit(&self, kind: ConnectionEventKind, endpoint: &str, cause: Option<String>) {
+        self.0.on_event(&ConnectionEvent { kind, endpoint: endpoint.to_string(), at: SystemTime::now(), cause });
+    }
+}
+
+impl fmt::Debug for EventListener {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("EventListener")
+    }
+}
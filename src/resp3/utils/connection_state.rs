@@ -0,0 +1,103 @@
+use crate::resp3::error::Resp3Error;
+use crate::resp3::utils::command::parse_inline;
+use crate::resp3::value::Resp3Value;
+
+// Commands allowed once a RESP2 connection subscribed to a channel or pattern
+const SUBSCRIBED_COMMANDS: [&str; 9] = [
+    "SUBSCRIBE", "PSUBSCRIBE", "SSUBSCRIBE", "UNSUBSCRIBE", "PUNSUBSCRIBE", "SUNSUBSCRIBE", "PING", "QUIT", "RESET",
+];
+
+// Monitoring connections only receive the feed, they can just be reset or closed
+const MONITORING_COMMANDS: [&str; 2] = ["QUIT", "RESET"];
+
+// Commands that would corrupt the stream or cannot be nested inside MULTI
+const TRANSACTION_FORBIDDEN_COMMANDS: [&str; 6] = ["MULTI", "WATCH", "SUBSCRIBE", "PSUBSCRIBE", "SSUBSCRIBE", "MONITOR"];
+
+// Commands whose reply changes the state or the replies that follow, they cannot be sent without reading it
+const NO_REPLY_FORBIDDEN_COMMANDS: [&str; 13] = [
+    "SUBSCRIBE", "PSUBSCRIBE", "SSUBSCRIBE", "UNSUBSCRIBE", "PUNSUBSCRIBE", "SUNSUBSCRIBE",
+    "MONITOR", "MULTI", "EXEC", "DISCARD", "RESET", "HELLO", "CLIENT",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionState {
+    Normal,
+    // Subscribed to channels or patterns, the server pushes messages at any time
+    Subscribed,
+    // MONITOR was sent, the server streams every command it processes
+    Monitoring,
+    // Between MULTI and EXEC or DISCARD, commands are queued
+    Transaction,
+}
+
+impl ConnectionState {
+    // Rejects the commands that are not legal in this state before they are sent
+    pub fn check(&self, command: &str) -> Result<(), Resp3Error> {
+        let allowed = match self {
+            ConnectionState::Normal => true,
+            ConnectionState::Subscribed => SUBSCRIBED_COMMANDS.contains(&command),
+            ConnectionState::Monitoring => MONITORING_COMMANDS.contains(&command),
+            ConnectionState::Transaction => !TRANSACTION_FORBIDDEN_COMMANDS.contains(&command),
+        };
+        if allowed {
+            Ok(())
+        } else {
+            Err(Resp3Error::InvalidState(format!("{} is not allowed while the connection is {:?}", command, self)))
+        }
+    }
+
+    // Rejects the commands that cannot be sent with CLIENT REPLY OFF or SKIP, which only a
+    // Normal connection can do
+    pub fn check_no_reply(&self, command: &str) -> Result<(), Resp3Error> {
+        if *self != ConnectionState::Normal {
+            return Err(Resp3Error::InvalidState(format!("Replies cannot be turned off while the connection is {:?}", self)));
+        }
+        let name = command.split(' ').next().unwrap_or_default();
+        if NO_REPLY_FORBIDDEN_COMMANDS.contains(&name) {
+            return Err(Resp3Error::InvalidState(format!("{} cannot be sent without reading its reply", name)));
+        }
+        Ok(())
+    }
+
+    // The state after the server replied to the command
+    pub fn after_reply(&self, command: &str, reply: &Resp3Value) -> ConnectionState {
+        match (command, reply) {
+            ("RESET", _) => ConnectionState::Normal,
+            // EXEC and DISCARD end the transaction even when it was aborted
+            ("EXEC", _) | ("DISCARD", _) if *self == ConnectionState::Transaction => ConnectionState::Normal,
+            (_, Resp3Value::Error(_)) => *self,
+            ("MULTI", _) => ConnectionState::Transaction,
+            ("MONITOR", _) => ConnectionState::Monitoring,
+            ("SUBSCRIBE", _) | ("PSUBSCRIBE", _) | ("SSUBSCRIBE", _) => ConnectionState::Subscribed,
+            _ => self.after_message(reply),
+        }
+    }
+
+    // The state after a reply or push received without sending anything, e.g. the
+    // confirmations of the remaining channels of an UNSUBSCRIBE
+    pub fn after_message(&self, message: &Resp3Value) -> ConnectionState {
+        if *self != ConnectionState::Subscribed {
+            return *self;
+        }
+        match message.as_array() {
+            Some([kind, _, Resp3Value::Integer(0)]) if matches!(kind.as_str(), Some("unsubscribe" | "punsubscribe" | "sunsubscribe")) => {
+                ConnectionState::Normal
+            }
+            _ => *self,
+        }
+    }
+}
+
+// The uppercase name of an encoded command, i.e. its first bulk string, or first word inline
+pub fn command_name(command: &[u8]) -> Option<String> {
+    let Some(rest) = command.strip_prefix(b"*") else {
+        let name = parse_inline(command)?.into_iter().next()?;
+        return Some(String::from_utf8_lossy(&name).to_uppercase());
+    };
+    let rest = &rest[rest.windows(2).position(|window| window == b"\r\n")? + 2..];
+    let rest = rest.strip_prefix(b"$")?;
+    let length_end = rest.windows(2).position(|window| window == b"\r\n")?;
+    let length: usize = std::str::from_utf8(&rest[..length_end]).ok()?.parse().ok()?;
+    let name = rest.get(length_end + 2..length_end + 2 + length)?;
+    Some(String::from_utf8_lossy(name).to_uppercase())
+}
@@ -0,0 +1,122 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+use openssl::rand::rand_bytes;
+use openssl::symm::{decrypt_aead, encrypt_aead, Cipher};
+
+use crate::resp3::error::Resp3Error;
+use crate::resp3::utils::codec::ValueCodec;
+
+// The first bytes of an encrypted value, then the id of the key. 0xFF never starts UTF-8 text
+const MAGIC: [u8; 4] = [0xFF, b'R', b'3', b'E'];
+const NONCE_LENGTH: usize = 12;
+const TAG_LENGTH: usize = 16;
+const HEADER_LENGTH: usize = MAGIC.len() + 1 + NONCE_LENGTH;
+
+// Encrypts the values with AES-256-GCM under a data key, e.g. one unwrapped from a KMS at
+// startup, the key itself never leaving the process. Each value gets a random nonce and is
+// authenticated together with the id of its key, so tampered values fail to decode instead of
+// reading as garbage. Keys are rotated by giving the new one an id and keeping the previous
+// ones with decrypt_with until the values they wrote are rewritten or expire. Values without
+// the header fail as well, unless allow_plaintext lets through the ones written before
+// encryption was turned on
+pub struct AesGcmCodec {
+    key_id: u8,
+    keys: HashMap<u8, [u8; 32]>,
+    allow_plaintext: bool,
+    bind_to_key: bool,
+}
+
+impl AesGcmCodec {
+    // The key encrypting the values written, which decrypts them as well
+    pub fn new(key_id: u8, key: [u8; 32]) -> Self {
+        AesGcmCodec { key_id, keys: HashMap::from([(key_id, key)]), allow_plaintext: false, bind_to_key: false }
+    }
+
+    // A previous key, for the values written before the rotation
+    pub fn decrypt_with(mut self, key_id: u8, key: [u8; 32]) -> Self {
+        self.keys.entry(key_id).or_insert(key);
+        self
+    }
+
+    // Reads the values without the header as they are, while the ones stored in clear are
+    // rewritten. Whoever can write to Redis can then replace an encrypted value with any other
+    pub fn allow_plaintext(mut self) -> Self {
+        self.allow_plaintext = true;
+        self
+    }
+
+    // Authenticates each value together with the Redis key it is written to, so a value copied
+    // to another key fails to decode. That includes RENAME, COPY and the moves between lists,
+    // and the values read without knowing their key, e.g. the members of SUNION or the replies
+    // of scripts
+    pub fn bind_to_key(mut self) -> Self {
+        self.bind_to_key = true;
+        self
+    }
+
+    pub fn key_id(&self) -> u8 {
+        self.key_id
+    }
+
+    // The id of the key an encrypted value was written with, None for the other values
+    pub fn encrypted_with(value: &[u8]) -> Option<u8> {
+        match value.len() >= HEADER_LENGTH + TAG_LENGTH && value.starts_with(&MAGIC) {
+            true => Some(value[MAGIC.len()]),
+            false => None,
+        }
+    }
+
+    // The header up to the key id, then the Redis key when the values are bound to it
+    fn associated_data(&self, header: &[u8], key: Option<&[u8]>) -> Result<Vec<u8>, Resp3Error> {
+        let mut data = header[..MAGIC.len() + 1].to_vec();
+        if self.bind_to_key {
+            let key = key.ok_or_else(|| Resp3Error::InvalidArgument("Values bound to their key need one, this command does not tell it".to_string()))?;
+            data.extend_from_slice(key);
+        }
+        Ok(data)
+    }
+}
+
+impl ValueCodec for AesGcmCodec {
+    fn encode<'a>(&self, value: &'a [u8]) -> Result<Cow<'a, [u8]>, Resp3Error> {
+        self.encode_for(None, value)
+    }
+
+    fn decode<'a>(&self, value: &'a [u8]) -> Result<Cow<'a, [u8]>, Resp3Error> {
+        self.decode_for(None, value)
+    }
+
+    fn encode_for<'a>(&self, key: Option<&[u8]>, value: &'a [u8]) -> Result<Cow<'a, [u8]>, Resp3Error> {
+        let mut nonce = [0; NONCE_LENGTH];
+        rand_bytes(&mut nonce).map_err(|err| Resp3Error::InvalidState(format!("No random nonce: {}", err)))?;
+        let mut encrypted = Vec::with_capacity(HEADER_LENGTH + value.len() + TAG_LENGTH);
+        encrypted.extend_from_slice(&MAGIC);
+        encrypted.push(self.key_id);
+        encrypted.extend_from_slice(&nonce);
+        let aad = self.associated_data(&encrypted, key)?;
+        let mut tag = [0; TAG_LENGTH];
+        let ciphertext = encrypt_aead(Cipher::aes_256_gcm(), &self.keys[&self.key_id], Some(&nonce), &aad, value, &mut tag)
+            .map_err(|err| Resp3Error::InvalidArgument(format!("Could not encrypt the value: {}", err)))?;
+        encrypted.extend_from_slice(&ciphertext);
+        encrypted.extend_from_slice(&tag);
+        Ok(Cow::Owned(encrypted))
+    }
+
+    fn decode_for<'a>(&self, key: Option<&[u8]>, value: &'a [u8]) -> Result<Cow<'a, [u8]>, Resp3Error> {
+        let Some(key_id) = AesGcmCodec::encrypted_with(value) else {
+            return match self.allow_plaintext {
+                true => Ok(Cow::Borrowed(value)),
+                false => Err(Resp3Error::UnexpectedReply("Value is not encrypted, see AesGcmCodec::allow_plaintext".to_string())),
+            };
+        };
+        let data_key = self.keys.get(&key_id)
+            .ok_or_else(|| Resp3Error::UnexpectedReply(format!("Value encrypted with the unknown key {}", key_id)))?;
+        let (header, rest) = value.split_at(HEADER_LENGTH);
+        let (ciphertext, tag) = rest.split_at(rest.len() - TAG_LENGTH);
+        let aad = self.associated_data(header, key)?;
+        decrypt_aead(Cipher::aes_256_gcm(), data_key, Some(&header[MAGIC.len() + 1..]), &aad, ciphertext, tag)
+            .map(Cow::Owned)
+            .map_err(|_| Resp3Error::UnexpectedReply(format!("Value encrypted with key {} failed authentication", key_id)))
+    }
+}
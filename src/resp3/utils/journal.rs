@@ -0,0 +1,260 @@
+use std::collections::VecDeque;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::resp3::error::Resp3Error;
+use crate::resp3::parser::parse_frame;
+use crate::resp3::utils::command::Command;
+use crate::resp3::value::Resp3Value;
+
+// Runs the command in ARGV[2..] unless the idempotency marker KEYS[1] is already set, the keys
+// of the command follow the marker. Returns {1, reply} when it ran, {0} when it had already run
+const GUARD_SCRIPT: &str = "\
+if redis.call('set', KEYS[1], '1', 'NX', 'PX', ARGV[1]) then
+    return {1, redis.call(unpack(ARGV, 2))}
+end
+return {0}";
+
+// The commands Redis flags noscript, which a Lua script cannot run, so they are never journaled
+const NOSCRIPT_COMMANDS: [&str; 45] = [
+    "ACL", "AUTH", "BGREWRITEAOF", "BGSAVE", "CLIENT", "CLUSTER", "CONFIG", "DEBUG", "DISCARD", "EVAL", "EVALSHA",
+    "EVAL_RO", "EVALSHA_RO", "EXEC", "FAILOVER", "FCALL", "FCALL_RO", "FUNCTION", "HELLO", "LATENCY", "MODULE",
+    "MONITOR", "MULTI", "PSUBSCRIBE", "PSYNC", "PUNSUBSCRIBE", "QUIT", "REPLCONF", "REPLICAOF", "RESET", "ROLE",
+    "SAVE", "SCRIPT", "SHUTDOWN", "SLAVEOF", "SLOWLOG", "SSUBSCRIBE", "SUBSCRIBE", "SUNSUBSCRIBE", "SYNC",
+    "UNSUBSCRIBE", "UNWATCH", "WAIT", "WAITAOF", "WATCH",
+];
+
+pub(crate) fn runs_in_scripts(name: &str) -> bool {
+    !NOSCRIPT_COMMANDS.contains(&name)
+}
+
+// A write kept while the server was unreachable, as it would have been sent
+#[derive(Debug, Clone, PartialEq)]
+pub struct JournalEntry {
+    // The idempotency key, the marker set when the write runs is <marker_prefix><id>
+    pub id: String,
+    pub parts: Vec<Vec<u8>>,
+    // Positions of the keys in parts
+    pub keys: Vec<usize>,
+    // Checked again on replay, the connection may not allow it
+    pub destructive: bool,
+}
+
+impl JournalEntry {
+    pub(crate) fn new<C: Command + ?Sized>(id: String, command: &C) -> Self {
+        let mut encoded = Vec::new();
+        command.encode_into(&mut encoded);
+        // Parsing the encoded command back gives the parts uppercased as they are sent
+        let parts = match parse_frame(&encoded) {
+            Ok(Some((Resp3Value::Array(parts), _))) => parts.into_iter().filter_map(|part| match part {
+                Resp3Value::BulkString(bytes) => Some(bytes),
+                _ => None,
+            }).collect(),
+            _ => Vec::new(),
+        };
+        JournalEntry { id, parts, keys: command.key_indices(), destructive: command.is_destructive() }
+    }
+
+    // An entry is stored as a RESP array of its id, its key positions separated by commas, then
+    // its parts. The positions of a destructive command start with a !
+    fn encode_into(&self, buffer: &mut Vec<u8>) {
+        let mut keys = self.keys.iter().map(usize::to_string).collect::<Vec<_>>().join(",");
+        if self.destructive {
+            keys.insert(0, '!');
+        }
+        let mut fields = vec![Resp3Value::BulkString(self.id.clone().into_bytes()), Resp3Value::BulkString(keys.into_bytes())];
+        fields.extend(self.parts.iter().cloned().map(Resp3Value::BulkString));
+        buffer.extend_from_slice(&Resp3Value::Array(fields).to_bytes());
+    }
+
+    fn decode(value: Resp3Value) -> Option<Self> {
+        let Resp3Value::Array(fields) = value else { return None };
+        let mut fields = fields.into_iter().map(|field| match field {
+            Resp3Value::BulkString(bytes) => Some(bytes),
+            _ => None,
+        });
+        let id = String::from_utf8(fields.next()??).ok()?;
+        let keys = String::from_utf8(fields.next()??).ok()?;
+        let (destructive, keys) = match keys.strip_prefix('!') {
+            Some(keys) => (true, keys),
+            None => (false, keys.as_str()),
+        };
+        let keys = keys.split(',').filter(|key| !key.is_empty()).map(str::parse).collect::<Result<_, _>>().ok()?;
+        let parts = fields.collect::<Option<Vec<_>>>()?;
+        Some(JournalEntry { id, parts, keys, destructive })
+    }
+}
+
+// The guarded form of an entry, EVAL of GUARD_SCRIPT with the command as its arguments
+pub(crate) struct GuardedCommand<'a> {
+    entry: &'a JournalEntry,
+    marker: String,
+    num_keys: String,
+    ttl: String,
+}
+
+impl<'a> GuardedCommand<'a> {
+    pub(crate) fn new(entry: &'a JournalEntry, journal: &Journal) -> Self {
+        GuardedCommand {
+            entry,
+            marker: format!("{}{}", journal.marker_prefix, entry.id),
+            num_keys: (entry.keys.len() + 1).to_string(),
+            ttl: journal.marker_ttl.as_millis().max(1).to_string(),
+        }
+    }
+
+    // The reply of the command, None when it had already run
+    pub(crate) fn unwrap_reply(reply: Resp3Value) -> Result<Option<Resp3Value>, Resp3Error> {
+        match reply {
+            Resp3Value::Array(mut values) if values.first() == Some(&Resp3Value::Integer(1)) => {
+                Ok(Some(if values.len() > 1 { values.swap_remove(1) } else { Resp3Value::Null }))
+            }
+            Resp3Value::Array(values) if values.first() == Some(&Resp3Value::Integer(0)) => Ok(None),
+            reply => Err(Resp3Error::UnexpectedReply(format!("Expected the reply of a journaled write, got {:?}", reply))),
+        }
+    }
+}
+
+impl Command for GuardedCommand<'_> {
+    // Everything after EVAL is already in its final form
+    fn is_verbatim(&self, index: usize) -> bool {
+        index > 0
+    }
+
+    // The command is only sent through get_binary_parts
+    fn get_parts(&self) -> Vec<&str> {
+        vec!["EVAL", GUARD_SCRIPT, &self.num_keys, &self.marker]
+    }
+
+    fn get_binary_parts(&self) -> Vec<&[u8]> {
+        let mut parts: Vec<&[u8]> = vec![b"EVAL", GUARD_SCRIPT.as_bytes(), self.num_keys.as_bytes(), self.marker.as_bytes()];
+        parts.extend(self.entry.keys.iter().filter_map(|&index| self.entry.parts.get(index)).map(Vec::as_slice));
+        parts.push(self.ttl.as_bytes());
+        parts.extend(self.entry.parts.iter().map(Vec::as_slice));
+        parts
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        (3..4 + self.entry.keys.len()).collect()
+    }
+
+    fn is_destructive(&self) -> bool {
+        self.entry.destructive
+    }
+}
+
+// The writes an executor could not send, replayed in order once the server is reachable again.
+// Every write of an executor with a journal runs through a Lua script setting an idempotency
+// marker, so a write that reached the server before the link dropped does not run twice when
+// it is replayed. That is one marker key per write, online ones included, kept for the marker
+// TTL: budget the memory for a day of writes by default, or shorten it. The commands scripts
+// cannot run (EVAL, FCALL, CONFIG SET, BGSAVE...) are sent as they are and never journaled.
+// Kept in memory, or in a file that survives restarts
+pub struct Journal {
+    entries: VecDeque<JournalEntry>,
+    path: Option<PathBuf>,
+    max_entries: usize,
+    marker_prefix: String,
+    marker_ttl: Duration,
+}
+
+impl Journal {
+    pub fn in_memory(max_entries: usize) -> Self {
+        Journal { entries: VecDeque::new(), path: None, max_entries, marker_prefix: "journal:".to_string(), marker_ttl: Duration::from_secs(24 * 60 * 60) }
+    }
+
+    // Loads the entries already in the file, which is created when missing. An entry cut short,
+    // e.g. by a crash while it was written, is dropped
+    pub fn open<P: AsRef<Path>>(path: P, max_entries: usize) -> Result<Self, Resp3Error> {
+        let path = path.as_ref().to_path_buf();
+        let contents = match fs::read(&path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(err) => return Err(Resp3Error::Io(err)),
+        };
+        let mut journal = Journal { path: Some(path), ..Self::in_memory(max_entries) };
+        let mut offset = 0;
+        while let Ok(Some((value, length))) = parse_frame(&contents[offset..]) {
+            offset += length;
+            match JournalEntry::decode(value) {
+                Some(entry) => journal.entries.push_back(entry),
+                None => return Err(Resp3Error::Protocol(format!("Invalid journal entry at byte {}", offset - length))),
+            }
+        }
+        if offset < contents.len() {
+            journal.rewrite()?;
+        }
+        Ok(journal)
+    }
+
+    // The markers are <prefix><id>, "journal:" by default
+    pub fn marker_prefix(mut self, prefix: &str) -> Self {
+        self.marker_prefix = prefix.to_string();
+        self
+    }
+
+    // How long the markers stay, a write replayed later than that may run twice
+    pub fn marker_ttl(mut self, ttl: Duration) -> Self {
+        self.marker_ttl = ttl;
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.entries.len() >= self.max_entries
+    }
+
+    // Oldest first
+    pub fn entries(&self) -> impl Iterator<Item = &JournalEntry> {
+        self.entries.iter()
+    }
+
+    // Returns false when the journal is full
+    pub(crate) fn push(&mut self, entry: JournalEntry) -> Result<bool, Resp3Error> {
+        if self.is_full() {
+            return Ok(false);
+        }
+        if let Some(path) = &self.path {
+            let mut buffer = Vec::new();
+            entry.encode_into(&mut buffer);
+            let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+            file.write_all(&buffer)?;
+            file.sync_data()?;
+        }
+        self.entries.push_back(entry);
+        Ok(true)
+    }
+
+    pub(crate) fn front(&self) -> Option<&JournalEntry> {
+        self.entries.front()
+    }
+
+    pub(crate) fn pop_front(&mut self) {
+        self.entries.pop_front();
+    }
+
+    // Writes the remaining entries to the file, after some were replayed
+    pub(crate) fn rewrite(&self) -> Result<(), Resp3Error> {
+        let Some(path) = &self.path else { return Ok(()) };
+        let mut buffer = Vec::new();
+        for entry in &self.entries {
+            entry.encode_into(&mut buffer);
+        }
+        // Written next to the journal then renamed, so a crash leaves either version whole
+        let temporary = path.with_extension("tmp");
+        let mut file = File::create(&temporary)?;
+        file.write_all(&buffer)?;
+        file.sync_data()?;
+        fs::rename(&temporary, path)?;
+        Ok(())
+    }
+}
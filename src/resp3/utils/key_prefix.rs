@@ -0,0 +1,151 @@
+use crate::resp3::error::Resp3Error;
+use crate::resp3::utils::command::{text_parts, Command, ReplyKind, TypedCommand};
+use crate::resp3::utils::command_executor::CommandExecutor;
+use crate::resp3::value::Resp3Value;
+
+// Namespaces every key the commands send, so several tenants can share one database
+// without seeing each other's keys. The keys returned by KEYS and SCAN lose the prefix again.
+// The destructive commands (FLUSHDB, FLUSHALL) are refused whatever the connection allows,
+// they would wipe the keys of every tenant
+pub struct KeyPrefix {
+    executor: CommandExecutor,
+    prefix: String,
+}
+
+impl KeyPrefix {
+    pub fn new(executor: CommandExecutor, prefix: &str) -> Self {
+        KeyPrefix { executor, prefix: prefix.to_string() }
+    }
+
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    pub fn into_inner(self) -> CommandExecutor {
+        self.executor
+    }
+
+    pub fn execute<T: Command>(&mut self, command: T) -> Result<Resp3Value, Resp3Error> {
+        check_scoped(&command)?;
+        let prefixed = Prefixed::new(&command, &self.prefix);
+        let reply = self.executor.request(&prefixed)?;
+        Ok(self.strip_reply(&command, reply))
+    }
+
+    pub fn query<T: TypedCommand>(&mut self, command: T) -> Result<T::Output, Resp3Error> {
+        check_scoped(&command)?;
+        let prefixed = Prefixed::new(&command, &self.prefix);
+        let reply = self.executor.request(&prefixed)?;
+        let reply = self.strip_reply(&command, reply);
+        command.parse_output(reply)
+    }
+
+    fn strip_reply<T: Command>(&self, command: &T, reply: Resp3Value) -> Resp3Value {
+        let name = command.get_parts().first().map(|name| name.to_uppercase()).unwrap_or_default();
+        match (name.as_str(), reply) {
+            ("KEYS", reply) => self.strip_keys(reply),
+            // A cursor followed by the keys
+            ("SCAN", Resp3Value::Array(mut values)) if values.len() == 2 => {
+                let keys = values.pop().unwrap();
+                values.push(self.strip_keys(keys));
+                Resp3Value::Array(values)
+            }
+            (_, reply) => reply,
+        }
+    }
+
+    fn strip_keys(&self, keys: Resp3Value) -> Resp3Value {
+        // The keys were sent UPPERCASE, so that is how Redis returns the prefix
+        let prefix = self.prefix.to_uppercase();
+        match keys {
+            Resp3Value::Array(keys) => Resp3Value::Array(keys.into_iter().map(|key| match key {
+                Resp3Value::BulkString(key) if key.starts_with(prefix.as_bytes()) => {
+                    Resp3Value::BulkString(key[prefix.len()..].to_vec())
+                }
+                key => key,
+            }).collect()),
+            keys => keys,
+        }
+    }
+}
+
+fn check_scoped<T: Command>(command: &T) -> Result<(), Resp3Error> {
+    if command.is_destructive() {
+        let name = command.get_parts().first().map(|name| name.to_uppercase()).unwrap_or_default();
+        return Err(Resp3Error::InvalidArgument(format!("{} is destructive, it cannot be scoped to a key prefix", name)));
+    }
+    Ok(())
+}
+
+// The command with the prefix in front of each of its keys and key patterns, and a pattern
+// matching the whole namespace when it takes one but was given none. Everything else is left
+// to the command
+struct Prefixed<'a, T: Command> {
+    command: &'a T,
+    parts: Vec<Vec<u8>>,
+    patterns: Vec<usize>,
+}
+
+impl<'a, T: Command> Prefixed<'a, T> {
+    fn new(command: &'a T, prefix: &str) -> Self {
+        let key_indices = command.key_indices();
+        let mut patterns = command.pattern_indices();
+        let mut parts: Vec<Vec<u8>> = command.get_binary_parts().into_iter().enumerate().map(|(index, part)| {
+            if key_indices.contains(&index) || patterns.contains(&index) {
+                [prefix.as_bytes(), part].concat()
+            } else {
+                part.to_vec()
+            }
+        }).collect();
+        if let Some(option) = command.pattern_option() {
+            parts.push(option.as_bytes().to_vec());
+            patterns.push(parts.len());
+            parts.push(format!("{}*", prefix).into_bytes());
+        }
+        Prefixed { command, parts, patterns }
+    }
+}
+
+impl<T: Command> Command for Prefixed<'_, T> {
+    fn is_verbatim(&self, index: usize) -> bool {
+        self.command.is_verbatim(index)
+    }
+
+    // Only the textual parts, the binary ones are sent through get_binary_parts
+    fn get_parts(&self) -> Vec<&str> {
+        text_parts(&self.get_binary_parts())
+    }
+
+    fn get_binary_parts(&self) -> Vec<&[u8]> {
+        self.parts.iter().map(Vec::as_slice).collect()
+    }
+
+    fn key_indices(&self) -> Vec<usize> {
+        self.command.key_indices()
+    }
+
+    // The pattern added by new is one of them, the command needs no other
+    fn pattern_indices(&self) -> Vec<usize> {
+        self.patterns.clone()
+    }
+
+    fn is_readonly(&self) -> bool {
+        self.command.is_readonly()
+    }
+
+    fn is_deterministic(&self) -> bool {
+        self.command.is_deterministic()
+    }
+
+    fn is_destructive(&self) -> bool {
+        self.command.is_destructive()
+    }
+
+    fn expected_reply(&self) -> ReplyKind {
+        self.command.expected_reply()
+    }
+
+    fn normalize_resp2(&self, reply: Resp3Value) -> Resp3Value {
+        self.command.normalize_resp2(reply)
+    }
+}
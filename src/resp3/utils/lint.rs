@@ -0,0 +1,116 @@
+use std::collections::HashMap;
+
+use crate::resp3::error::Resp3Error;
+use crate::resp3::utils::command::Command;
+
+// What happens to a command the lint flags
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Allow,
+    // Logged at warn level and sent all the same
+    Warn,
+    // Fails with Resp3Error::InvalidArgument before being sent
+    Deny,
+}
+
+// Why a command is flagged
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LintKind {
+    // Blocks the server or walks the whole keyspace, fine in a test, not on a busy production server
+    Dangerous,
+    // Superseded by another command or option that Redis recommends instead
+    Deprecated,
+}
+
+// The commands flagged out of the box, with what to use instead
+const RULES: [(&str, LintKind, &str); 17] = [
+    ("KEYS", LintKind::Dangerous, "it blocks the server while walking every key, use SCAN"),
+    ("MONITOR", LintKind::Dangerous, "it halves the throughput of the server, use it for debugging only"),
+    ("DEBUG", LintKind::Dangerous, "it can crash or block the server"),
+    ("SAVE", LintKind::Dangerous, "it blocks the server until the dump is written, use BGSAVE"),
+    ("FLUSHALL", LintKind::Dangerous, "it deletes every key of every database, pass ASYNC at least"),
+    ("FLUSHDB", LintKind::Dangerous, "it deletes every key of the database, pass ASYNC at least"),
+    ("GETSET", LintKind::Deprecated, "use SET with the GET option"),
+    ("SETNX", LintKind::Deprecated, "use SET with the NX option"),
+    ("SETEX", LintKind::Deprecated, "use SET with the EX option"),
+    ("PSETEX", LintKind::Deprecated, "use SET with the PX option"),
+    ("HMSET", LintKind::Deprecated, "use HSET, which takes several fields"),
+    ("RPOPLPUSH", LintKind::Deprecated, "use LMOVE"),
+    ("BRPOPLPUSH", LintKind::Deprecated, "use BLMOVE"),
+    ("GEORADIUS", LintKind::Deprecated, "use GEOSEARCH"),
+    ("GEORADIUSBYMEMBER", LintKind::Deprecated, "use GEOSEARCH"),
+    ("ZRANGEBYSCORE", LintKind::Deprecated, "use ZRANGE with the BYSCORE option"),
+    ("SUBSTR", LintKind::Deprecated, "use GETRANGE"),
+];
+
+// Flags the commands that are dangerous or deprecated before the executor sends them, e.g.
+// Lint::new().dangerous(Severity::Deny).allow("FLUSHDB") set with CommandExecutor::with_lint.
+// A guard rail for large codebases, where a KEYS in a hot path is easier to catch in review
+// tooling than in production
+#[derive(Debug, Clone)]
+pub struct Lint {
+    dangerous: Severity,
+    deprecated: Severity,
+    // By command name UPPERCASE, over the severity of its kind
+    overrides: HashMap<String, Severity>,
+}
+
+impl Lint {
+    // Warns about both kinds
+    pub fn new() -> Self {
+        Lint { dangerous: Severity::Warn, deprecated: Severity::Warn, overrides: HashMap::new() }
+    }
+
+    pub fn dangerous(mut self, severity: Severity) -> Self {
+        self.dangerous = severity;
+        self
+    }
+
+    pub fn deprecated(mut self, severity: Severity) -> Self {
+        self.deprecated = severity;
+        self
+    }
+
+    // The severity of one command, flagged or not, e.g. to deny SMEMBERS on a service with large sets
+    pub fn command(mut self, name: &str, severity: Severity) -> Self {
+        self.overrides.insert(name.to_uppercase(), severity);
+        self
+    }
+
+    // Lets a flagged command through silently
+    pub fn allow(self, name: &str) -> Self {
+        self.command(name, Severity::Allow)
+    }
+
+    // The severity of the command with the reason, None when it is not flagged or allowed
+    pub fn lint(&self, name: &str) -> Option<(Severity, &'static str)> {
+        let name = name.to_uppercase();
+        let rule = RULES.iter().find(|(rule, _, _)| *rule == name);
+        let severity = match (self.overrides.get(&name), rule) {
+            (Some(severity), _) => *severity,
+            (None, Some((_, LintKind::Dangerous, _))) => self.dangerous,
+            (None, Some((_, LintKind::Deprecated, _))) => self.deprecated,
+            (None, None) => return None,
+        };
+        let reason = rule.map_or("it is flagged by the lint", |(_, _, reason)| reason);
+        (severity != Severity::Allow).then_some((severity, reason))
+    }
+
+    pub fn check<C: Command + ?Sized>(&self, command: &C) -> Result<(), Resp3Error> {
+        let name = command.get_binary_parts().first().map(|name| String::from_utf8_lossy(name).to_uppercase()).unwrap_or_default();
+        match self.lint(&name) {
+            Some((Severity::Deny, reason)) => Err(Resp3Error::InvalidArgument(format!("{} is denied, {}", name, reason))),
+            Some((_, reason)) => {
+                log::warn!("{} sent, {}", name, reason);
+                Ok(())
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+impl Default for Lint {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,89 @@
+use std::sync::Mutex;
+
+use crate::resp3::error::Resp3Error;
+use crate::resp3::utils::command::{write_length, write_part};
+use crate::resp3::utils::redis_connection::RedisConnection;
+use crate::resp3::value::{FromResp3Value, Resp3Value};
+
+// Buffers kept for reuse, enough for the threads sharing a command
+const POOLED_BUFFERS: usize = 16;
+// Larger buffers are dropped after use rather than kept around
+const MAX_POOLED_CAPACITY: usize = 64 * 1024;
+
+// A command shape encoded once, for the hot paths sending the same command millions of times
+// with other keys and values, e.g. PreparedCommand::new("SET").argument().verbatim_argument().token("EX").token("60").
+// The name and the option tokens are encoded when it is built, each call only writes its
+// arguments between them, into a buffer taken from the pool of the command
+pub struct PreparedCommand {
+    // The encoded tokens before each argument, then the ones after the last argument
+    segments: Vec<Vec<u8>>,
+    verbatim: Vec<bool>,
+    parts: usize,
+    buffers: Mutex<Vec<Vec<u8>>>,
+}
+
+impl PreparedCommand {
+    pub fn new(name: &str) -> Self {
+        PreparedCommand { segments: vec![Vec::new()], verbatim: Vec::new(), parts: 0, buffers: Mutex::new(Vec::new()) }.token(name)
+    }
+
+    // A fixed part, sent UPPERCASE like the parts of the other commands
+    pub fn token(mut self, token: &str) -> Self {
+        write_part(self.segments.last_mut().unwrap(), token.as_bytes(), false);
+        self.parts += 1;
+        self
+    }
+
+    // A part given on each call, sent UPPERCASE, e.g. a key
+    pub fn argument(self) -> Self {
+        self.slot(false)
+    }
+
+    // A part given on each call and sent as it is, e.g. a payload
+    pub fn verbatim_argument(self) -> Self {
+        self.slot(true)
+    }
+
+    pub fn arguments(&self) -> usize {
+        self.verbatim.len()
+    }
+
+    // Appends the command with these arguments, one for each argument() in order
+    pub fn encode_into(&self, buffer: &mut Vec<u8>, arguments: &[&[u8]]) -> Result<(), Resp3Error> {
+        if arguments.len() != self.verbatim.len() {
+            return Err(Resp3Error::InvalidArgument(format!("Prepared command takes {} arguments, got {}", self.verbatim.len(), arguments.len())));
+        }
+        write_length(buffer, b'*', self.parts);
+        for ((segment, argument), verbatim) in self.segments.iter().zip(arguments).zip(&self.verbatim) {
+            buffer.extend_from_slice(segment);
+            write_part(buffer, argument, *verbatim);
+        }
+        buffer.extend_from_slice(self.segments.last().unwrap());
+        Ok(())
+    }
+
+    // Error replies are returned as Resp3Error::Server, like RedisConnection::request does
+    pub fn request(&self, conn: &mut RedisConnection, arguments: &[&[u8]]) -> Result<Resp3Value, Resp3Error> {
+        let mut buffer = self.buffers.lock().unwrap().pop().unwrap_or_default();
+        buffer.clear();
+        let reply = self.encode_into(&mut buffer, arguments).and_then(|_| conn.request(&buffer));
+        if buffer.capacity() <= MAX_POOLED_CAPACITY {
+            let mut buffers = self.buffers.lock().unwrap();
+            if buffers.len() < POOLED_BUFFERS {
+                buffers.push(buffer);
+            }
+        }
+        reply
+    }
+
+    pub fn query<T: FromResp3Value>(&self, conn: &mut RedisConnection, arguments: &[&[u8]]) -> Result<T, Resp3Error> {
+        T::from_resp3_value(self.request(conn, arguments)?)
+    }
+
+    fn slot(mut self, verbatim: bool) -> Self {
+        self.verbatim.push(verbatim);
+        self.segments.push(Vec::new());
+        self.parts += 1;
+        self
+    }
+}
@@ -0,0 +1,181 @@
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{IpAddr, TcpStream};
+
+const SOCKS_VERSION: u8 = 5;
+const SOCKS_NO_AUTHENTICATION: u8 = 0;
+const SOCKS_USERNAME_PASSWORD: u8 = 2;
+const SOCKS_CONNECT: u8 = 1;
+const SOCKS_IPV4: u8 = 1;
+const SOCKS_DOMAIN_NAME: u8 = 3;
+const SOCKS_IPV6: u8 = 4;
+
+// A proxy the connection goes through, e.g. a bastion host in front of the Redis servers.
+// The Redis address is sent to the proxy as given, so hostnames are resolved on its side
+#[derive(Debug, Clone, PartialEq)]
+pub enum Proxy {
+    Socks5 { address: String, credentials: Option<ProxyCredentials> },
+    HttpConnect { address: String, credentials: Option<ProxyCredentials> },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProxyCredentials {
+    pub username: String,
+    pub password: String,
+}
+
+impl Proxy {
+    pub fn socks5(address: &str) -> Self {
+        Proxy::Socks5 { address: address.to_string(), credentials: None }
+    }
+
+    pub fn http_connect(address: &str) -> Self {
+        Proxy::HttpConnect { address: address.to_string(), credentials: None }
+    }
+
+    // SOCKS5 username/password authentication, or HTTP basic authentication
+    pub fn credentials(self, username: &str, password: &str) -> Self {
+        let credentials = Some(ProxyCredentials { username: username.to_string(), password: password.to_string() });
+        match self {
+            Proxy::Socks5 { address, .. } => Proxy::Socks5 { address, credentials },
+            Proxy::HttpConnect { address, .. } => Proxy::HttpConnect { address, credentials },
+        }
+    }
+
+    pub fn address(&self) -> &str {
+        match self {
+            Proxy::Socks5 { address, .. } | Proxy::HttpConnect { address, .. } => address,
+        }
+    }
+
+    // Asks the proxy, already connected on the stream, to open a tunnel to the target
+    pub(crate) fn handshake(&self, stream: &mut TcpStream, target: &str) -> io::Result<()> {
+        match self {
+            Proxy::Socks5 { credentials, .. } => socks5_handshake(stream, target, credentials.as_ref()),
+            Proxy::HttpConnect { credentials, .. } => http_connect_handshake(stream, target, credentials.as_ref()),
+        }
+    }
+}
+
+fn proxy_error(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::ConnectionRefused, message)
+}
+
+// Splits "host:port", IPv6 hosts are written in brackets ("[::1]:6379")
+pub(crate) fn split_host_port(target: &str) -> io::Result<(&str, u16)> {
+    let invalid = || io::Error::new(io::ErrorKind::InvalidInput, format!("Invalid address {}", target));
+    let (host, port) = target.rsplit_once(':').ok_or_else(invalid)?;
+    let host = host.strip_prefix('[').and_then(|host| host.strip_suffix(']')).unwrap_or(host);
+    Ok((host, port.parse().map_err(|_| invalid())?))
+}
+
+// RFC 1928, with the username/password authentication of RFC 1929
+fn socks5_handshake(stream: &mut TcpStream, target: &str, credentials: Option<&ProxyCredentials>) -> io::Result<()> {
+    let method = if credentials.is_some() { SOCKS_USERNAME_PASSWORD } else { SOCKS_NO_AUTHENTICATION };
+    stream.write_all(&[SOCKS_VERSION, 1, method])?;
+    let mut reply = [0; 2];
+    stream.read_exact(&mut reply)?;
+    if reply != [SOCKS_VERSION, method] {
+        return Err(proxy_error("SOCKS5 proxy refused the authentication method".to_string()));
+    }
+
+    if let Some(credentials) = credentials {
+        let mut request = vec![1];
+        for field in [&credentials.username, &credentials.password] {
+            let length = u8::try_from(field.len()).map_err(|_| proxy_error("SOCKS5 credentials longer than 255 bytes".to_string()))?;
+            request.push(length);
+            request.extend_from_slice(field.as_bytes());
+        }
+        stream.write_all(&request)?;
+        stream.read_exact(&mut reply)?;
+        if reply[1] != 0 {
+            return Err(proxy_error("SOCKS5 proxy rejected the credentials".to_string()));
+        }
+    }
+
+    let (host, port) = split_host_port(target)?;
+    let mut request = vec![SOCKS_VERSION, SOCKS_CONNECT, 0];
+    match host.parse::<IpAddr>() {
+        Ok(IpAddr::V4(ip)) => {
+            request.push(SOCKS_IPV4);
+            request.extend_from_slice(&ip.octets());
+        }
+        Ok(IpAddr::V6(ip)) => {
+            request.push(SOCKS_IPV6);
+            request.extend_from_slice(&ip.octets());
+        }
+        Err(_) => {
+            let length = u8::try_from(host.len()).map_err(|_| proxy_error(format!("Hostname {} longer than 255 bytes", host)))?;
+            request.push(SOCKS_DOMAIN_NAME);
+            request.push(length);
+            request.extend_from_slice(host.as_bytes());
+        }
+    }
+    request.extend_from_slice(&port.to_be_bytes());
+    stream.write_all(&request)?;
+
+    let mut reply = [0; 4];
+    stream.read_exact(&mut reply)?;
+    if reply[1] != 0 {
+        return Err(proxy_error(format!("SOCKS5 proxy could not connect to {} (reply code {})", target, reply[1])));
+    }
+    // The address the proxy bound, of no use here but it has to be read past
+    let bound_length = match reply[3] {
+        SOCKS_IPV4 => 4,
+        SOCKS_IPV6 => 16,
+        SOCKS_DOMAIN_NAME => {
+            let mut length = [0; 1];
+            stream.read_exact(&mut length)?;
+            length[0] as usize
+        }
+        address_type => return Err(proxy_error(format!("SOCKS5 proxy sent an unknown address type {}", address_type))),
+    };
+    let mut bound = vec![0; bound_length + 2];
+    stream.read_exact(&mut bound)?;
+    Ok(())
+}
+
+fn http_connect_handshake(stream: &mut TcpStream, target: &str, credentials: Option<&ProxyCredentials>) -> io::Result<()> {
+    let mut request = format!("CONNECT {} HTTP/1.1\r\nHost: {}\r\n", target, target);
+    if let Some(credentials) = credentials {
+        let token = base64(format!("{}:{}", credentials.username, credentials.password).as_bytes());
+        request.push_str(&format!("Proxy-Authorization: Basic {}\r\n", token));
+    }
+    request.push_str("\r\n");
+    stream.write_all(request.as_bytes())?;
+
+    // Read byte by byte, what follows the headers already belongs to Redis
+    let mut reader = BufReader::with_capacity(1, stream);
+    let mut status_line = String::new();
+    reader.read_line(&mut status_line)?;
+    let status = status_line.split(' ').nth(1).unwrap_or_default();
+    if !status.starts_with('2') {
+        return Err(proxy_error(format!("HTTP proxy could not connect to {}: {}", target, status_line.trim_end())));
+    }
+    let mut header = String::new();
+    loop {
+        header.clear();
+        if reader.read_line(&mut header)? == 0 {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "HTTP proxy closed the connection"));
+        }
+        if header == "\r\n" || header == "\n" {
+            return Ok(());
+        }
+    }
+}
+
+fn base64(input: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut output = String::new();
+    for chunk in input.chunks(3) {
+        let bytes = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let bits = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+        for index in 0..4 {
+            if index <= chunk.len() {
+                output.push(ALPHABET[(bits >> (18 - 6 * index) & 0x3f) as usize] as char);
+            } else {
+                output.push('=');
+            }
+        }
+    }
+    output
+}
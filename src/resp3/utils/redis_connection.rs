@@ -1,35 +1,913 @@
-use std::io::{Read, Write};
-use std::net::{Shutdown, TcpStream};
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::hash::BuildHasher;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs};
 use std::str;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+#[cfg(feature="otel")]
+use opentelemetry::global::BoxedTracer;
+
+use crate::resp3::commands::client::{ClientFlag, ClientFlagCommand, ClientReplyCommand, ClientReplyMode, HelloCommand, QuitCommand};
+use crate::resp3::commands::replication::{RoleCommand, ServerRole};
+use crate::resp3::commands::server::CommandInfoCommand;
+use crate::resp3::error::{Resp3Error, ServerError};
+#[cfg(feature="otel")]
+use crate::resp3::otel;
+use crate::resp3::parser::{parse_frame_with_attributes, FrameScanner};
+use crate::resp3::utils::command::{Command, EncodingMode, TypedCommand};
+use crate::resp3::utils::connection_events::{ConnectionEventKind, ConnectionEvents, EventListener};
+use crate::resp3::utils::connection_state::{command_name, ConnectionState};
+use crate::resp3::utils::proxy::Proxy;
+#[cfg(feature="tls")]
+use crate::resp3::utils::tls::TlsOptions;
+use crate::resp3::utils::transport::{Connector, Transport, TransportWrapper};
+use crate::resp3::utils::validation::Validation;
+use crate::resp3::value::{Resp3Map, Resp3Value};
+#[cfg(feature="wire-debug")]
+use crate::resp3::wire_debug;
+
+// Same default as std's BufReader and BufWriter
+const DEFAULT_BUFFER_SIZE: usize = 8 * 1024;
+
+// How long close, and so Drop, waits for the reply to QUIT at most, whatever the timeout
+const QUIT_TIMEOUT: Duration = Duration::from_secs(1);
+
+type Reader = BufReader<Box<dyn Transport>>;
+type Writer = BufWriter<Box<dyn Transport>>;
 
 // RedisConnection manages the actual TCP connection to Redis
 pub struct RedisConnection {
-    stream: TcpStream,
+    // Both halves wrap the same stream, so small replies and commands take a single syscall
+    reader: Reader,
+    writer: Writer,
+    // Bytes read from the stream that are not part of a returned reply yet
+    buffer: Vec<u8>,
+    // How far into buffer the reply being read is known to go, reset with it
+    scanner: FrameScanner,
+    // Reused for every command sent with request_command, so encoding does not allocate
+    write_buffer: Vec<u8>,
+    state: ConnectionState,
+    // Kept to reconnect with the same options
+    options: RedisConnectionBuilder,
+    endpoints: Vec<EndpointHealth>,
+    // Index in endpoints of the one the stream is connected to
+    current_endpoint: usize,
+    // Since connect, over every reconnect
+    bytes_written: u64,
+    bytes_read: u64,
+    // Set by close, so dropping the connection does not close it again
+    closed: bool,
+    // Why the stream was lost, until the next reconnect, so Disconnected is sent once
+    disconnect_cause: Option<String>,
+    // The one negotiated, Resp2 or Resp3
+    protocol: ProtocolVersion,
+    // The answers of supports, forgotten on reconnect as the server may have changed
+    supported_commands: HashMap<String, bool>,
+    // The RESP3 attributes sent before the last reply read
+    attributes: Option<Resp3Map>,
+}
+
+// Outcome of the connection attempts to one of the endpoints
+#[derive(Debug, Clone, PartialEq)]
+pub struct EndpointHealth {
+    pub address: String,
+    // False when the last attempt failed, endpoints not tried yet count as healthy
+    pub healthy: bool,
+    pub consecutive_failures: u32,
+    pub last_error: Option<String>,
+}
+
+impl EndpointHealth {
+    fn new(address: &str) -> Self {
+        EndpointHealth { address: address.to_string(), healthy: true, consecutive_failures: 0, last_error: None }
+    }
 }
 
 impl RedisConnection {
     pub fn new(address: &str) -> Self {
-        let stream = TcpStream::connect(address).expect("Could not connect to Redis server");
-        RedisConnection { stream }
+        Self::builder(address).connect().expect("Could not connect to Redis server")
     }
 
-    pub fn send_command(&mut self, command: &str) -> String {
-        self.stream.write_all(command.as_bytes()).expect("Failed to write to Redis server");
-        self.stream.flush().expect("Failed to flush the stream");
+    pub fn builder(address: &str) -> RedisConnectionBuilder {
+        RedisConnectionBuilder::new(address)
+    }
 
-        let mut buffer = [0; 512];
-        let bytes_read = self.stream.read(&mut buffer).expect("Failed to read from Redis server");
-        let response = str::from_utf8(&buffer[..bytes_read]).expect("Failed to parse Redis response");
+    pub fn send_command(&mut self, command: &str) -> String {
+        let (_, length) = self.exchange(command.as_bytes()).expect("Failed to send command to Redis server");
+        let frame: Vec<u8> = self.buffer.drain(..length).collect();
+        let response = str::from_utf8(&frame).expect("Failed to parse Redis response");
         response.to_string()
     }
-    pub fn close(&mut self) {
-        self.stream.shutdown(Shutdown::Both).expect("shutdown call failed");
+
+    // Sends the command and parses the reply, error replies are returned as Resp3Error::Server
+    pub fn request(&mut self, command: &[u8]) -> Result<Resp3Value, Resp3Error> {
+        let (value, length) = self.exchange(command)?;
+        self.buffer.drain(..length);
+
+        match value {
+            Resp3Value::Error(message) => Err(Resp3Error::Server(ServerError::parse(&message))),
+            value => Ok(value),
+        }
+    }
+
+    // Encodes the command into the connection's write buffer and sends it like request
+    pub fn request_command<C: Command + ?Sized>(&mut self, command: &C) -> Result<Resp3Value, Resp3Error> {
+        self.validate(command)?;
+        let mut write_buffer = std::mem::take(&mut self.write_buffer);
+        write_buffer.clear();
+        command.encode_with(self.options.encoding, &mut write_buffer);
+        let reply = self.request(&write_buffer);
+        self.write_buffer = write_buffer;
+
+        let reply = match self.protocol {
+            ProtocolVersion::Resp2 => command.normalize_resp2(reply?),
+            _ => reply?,
+        };
+        let expected = command.expected_reply();
+        if !expected.matches(&reply) {
+            // Best effort, a failed reconnect shows up on the next request
+            self.disconnect_cause = Some("protocol desync".to_string());
+            let _ = self.reconnect();
+            return Err(Resp3Error::ProtocolDesync(format!("Expected {:?} reply, got {:?}", expected, reply)));
+        }
+        Ok(reply)
+    }
+
+    // For the commands the server may answer by closing the connection, e.g. SHUTDOWN. None
+    // when it did, the connection is then closed as after close. Replies, error ones included,
+    // come back as with request_command
+    pub fn request_closing<C: Command + ?Sized>(&mut self, command: &C) -> Result<Option<Resp3Value>, Resp3Error> {
+        match self.request_command(command) {
+            Err(Resp3Error::Io(err)) if is_closed(&err) => {
+                self.closed = true;
+                Ok(None)
+            }
+            result => result.map(Some),
+        }
+    }
+
+    // Sends the already encoded commands in one write and reads one reply for each, error
+    // replies included. Only for commands that do not change the state of the connection
+    pub fn request_pipelined(&mut self, commands: &[u8], count: usize) -> Result<Vec<Resp3Value>, Resp3Error> {
+        self.write_pipelined(commands)?;
+        (0..count).map(|_| self.read_pipelined()).collect()
+    }
+
+    // The halves of request_pipelined, for the pipelines reading the replies of earlier
+    // commands while sending more
+    pub(crate) fn write_pipelined(&mut self, commands: &[u8]) -> Result<(), Resp3Error> {
+        if self.state != ConnectionState::Normal {
+            return Err(Resp3Error::InvalidState(format!("Cannot pipeline while the connection is {:?}", self.state)));
+        }
+        self.write_only(commands)
+    }
+
+    pub(crate) fn read_pipelined(&mut self) -> Result<Resp3Value, Resp3Error> {
+        let (value, length) = self.fill_until_frame()?;
+        #[cfg(feature="wire-debug")]
+        wire_debug::log_reply(&self.buffer[..length], false);
+        self.buffer.drain(..length);
+        Ok(value)
+    }
+
+    // Fire and forget, the command goes after CLIENT REPLY SKIP so the server sends no reply,
+    // not even an error. Later requests get their own replies as usual
+    pub fn send_no_reply<C: Command + ?Sized>(&mut self, command: &C) -> Result<(), Resp3Error> {
+        self.validate(command)?;
+        let mut write_buffer = std::mem::take(&mut self.write_buffer);
+        write_buffer.clear();
+        ClientReplyCommand::new(ClientReplyMode::Skip).encode_with(self.options.encoding, &mut write_buffer);
+        let skip_length = write_buffer.len();
+        command.encode_with(self.options.encoding, &mut write_buffer);
+
+        let name = command_name(&write_buffer[skip_length..]).unwrap_or_default();
+        let result = self.state.check_no_reply(&name).and_then(|_| self.write_only(&write_buffer));
+        self.write_buffer = write_buffer;
+        result
+    }
+
+    // Replaces the stream with a new one, dropping any unread reply and the subscriptions,
+    // MONITOR or MULTI of the previous one
+    pub fn reconnect(&mut self) -> Result<(), Resp3Error> {
+        self.emit(ConnectionEventKind::Reconnecting, self.disconnect_cause.clone());
+        let (reader, writer, endpoint) = match self.options.open(&mut self.endpoints) {
+            Ok(streams) => streams,
+            Err(err) => {
+                self.disconnect_cause = Some(err.to_string());
+                self.emit(ConnectionEventKind::Disconnected, Some(err.to_string()));
+                return Err(err);
+            }
+        };
+        self.reader = reader;
+        self.writer = writer;
+        self.current_endpoint = endpoint;
+        self.buffer.clear();
+        self.scanner = FrameScanner::default();
+        self.set_state(ConnectionState::Normal);
+        self.closed = false;
+        self.disconnect_cause = None;
+        self.supported_commands.clear();
+        self.set_up()?;
+        self.emit(ConnectionEventKind::Connected, None);
+        Ok(())
+    }
+
+    // Reads a reply without sending anything, e.g. the messages of a subscribed connection
+    pub fn read_response(&mut self) -> Result<Resp3Value, Resp3Error> {
+        let (value, length) = self.fill_until_frame()?;
+        #[cfg(feature="wire-debug")]
+        wire_debug::log_reply(&self.buffer[..length], false);
+        self.buffer.drain(..length);
+        self.set_state(self.state.after_message(&value));
+        Ok(value)
+    }
+
+    // Reads the next reply or push message, true for the pushes, which RESP3 sends with '>' in
+    // place of '*'. The state is left alone, the caller tells which is which. A message that does
+    // not parse is skipped and its error returned in its place, the stream stays in step
+    #[cfg(feature="tokio")]
+    pub(crate) fn read_message(&mut self) -> Result<(Result<Resp3Value, Resp3Error>, bool), Resp3Error> {
+        let result = self.next_frame();
+        let (value, length) = self.observe(result)?;
+        let push = self.buffer.first() == Some(&b'>');
+        #[cfg(feature="wire-debug")]
+        wire_debug::log_reply(&self.buffer[..length], false);
+        self.buffer.drain(..length);
+        Ok((value, push))
+    }
+
+    // Another handle of the stream, for writing while a thread blocks reading this connection
+    #[cfg(feature="tokio")]
+    pub(crate) fn try_clone_transport(&self) -> io::Result<Box<dyn Transport>> {
+        self.writer.get_ref().try_clone()
+    }
+
+    // For the connections of admin tools, see RedisConnectionBuilder::allow_destructive
+    pub fn allow_destructive(&mut self, allowed: bool) {
+        self.options.allow_destructive = allowed;
+    }
+
+    // Replaces the timeout of RedisConnectionBuilder::timeout on the open stream and for the
+    // reconnects, e.g. lengthened around a blocking command
+    pub fn set_timeout(&mut self, timeout: Option<Duration>) -> Result<(), Resp3Error> {
+        self.writer.get_ref().set_timeout(timeout)?;
+        self.options.timeout = timeout;
+        Ok(())
+    }
+
+    // The RESP3 attributes the server sent before the last reply read, None when there were
+    // none. Servers send them for some replies only, e.g. key-popularity hints, and never over RESP2
+    pub fn last_attributes(&self) -> Option<&Resp3Map> {
+        self.attributes.as_ref()
+    }
+
+    // Takes them, so that they are not counted again for a request failing before its reply
+    pub(crate) fn take_attributes(&mut self) -> Option<Resp3Map> {
+        self.attributes.take()
+    }
+
+    pub fn state(&self) -> ConnectionState {
+        self.state
+    }
+
+    // The protocol the server speaks on this connection, Resp2 or Resp3
+    pub fn protocol(&self) -> ProtocolVersion {
+        self.protocol
+    }
+
+    // Whether the server knows the command, to feature-detect it, e.g. conn.supports("GETEX")
+    // to fall back to GET and EXPIRE on a server older than 6.2. Subcommands are written
+    // "CLIENT NO-TOUCH" and need Redis 7.0+. Asked once per command with COMMAND INFO
+    pub fn supports(&mut self, command: &str) -> Result<bool, Resp3Error> {
+        let name = command.split_whitespace().collect::<Vec<_>>().join("|").to_lowercase();
+        if let Some(&supported) = self.supported_commands.get(&name) {
+            return Ok(supported);
+        }
+        let supported = CommandInfoCommand::new([name.as_str()]).query(self)?.into_iter().next().flatten().is_some();
+        self.supported_commands.insert(name, supported);
+        Ok(supported)
+    }
+
+    // Fails with UnsupportedCommand when the server does not know the command, like supports
+    pub fn require(&mut self, command: &str) -> Result<(), Resp3Error> {
+        match self.supports(command)? {
+            true => Ok(()),
+            false => Err(Resp3Error::UnsupportedCommand(command.to_uppercase())),
+        }
+    }
+
+    // The address the connection is currently connected to, as configured
+    pub fn current_endpoint(&self) -> &str {
+        &self.endpoints[self.current_endpoint].address
+    }
+
+    // The IP address the current endpoint resolved to, the one of the proxy when there is one
+    pub fn peer_address(&self) -> Result<SocketAddr, Resp3Error> {
+        Ok(self.writer.get_ref().peer_addr()?)
+    }
+
+    // One entry per configured endpoint, in the order they were added
+    pub fn endpoint_health(&self) -> &[EndpointHealth] {
+        &self.endpoints
+    }
+
+    // Every byte written to the stream, requests sent as raw bytes included
+    pub fn bytes_written(&self) -> u64 {
+        self.bytes_written
+    }
+
+    pub fn bytes_read(&self) -> u64 {
+        self.bytes_read
+    }
+
+    // Sends QUIT and waits for its reply before shutting the stream down, so the server has
+    // processed every command sent before, the fire and forget ones included. The wait is
+    // capped at a second, so that dropping a connection to a stuck server does not hang. A
+    // connection the server or an earlier close already closed is not an error. Later requests
+    // fail until reconnect
+    pub fn close(&mut self) -> Result<(), Resp3Error> {
+        if self.closed {
+            return Ok(());
+        }
+        self.closed = true;
+        // Shut down even when QUIT failed, e.g. on a timeout
+        let quit = self.quit();
+        let shutdown = self.writer.get_ref().shutdown().map_err(Resp3Error::Io);
+        if self.disconnect_cause.is_none() {
+            self.disconnect_cause = Some("closed".to_string());
+            self.emit(ConnectionEventKind::Disconnected, None);
+        }
+        for result in [quit, shutdown] {
+            match result {
+                Err(Resp3Error::Io(err)) if is_closed(&err) => {}
+                result => result?,
+            }
+        }
+        Ok(())
+    }
+
+    fn quit(&mut self) -> Result<(), Resp3Error> {
+        let mut write_buffer = std::mem::take(&mut self.write_buffer);
+        write_buffer.clear();
+        QuitCommand.encode_into(&mut write_buffer);
+        let result = self.write_only(&write_buffer);
+        self.write_buffer = write_buffer;
+        result?;
+
+        // Subscribed and monitoring connections may get messages before the reply
+        let wait = self.options.timeout.map_or(QUIT_TIMEOUT, |timeout| timeout.min(QUIT_TIMEOUT));
+        let deadline = Instant::now() + wait;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Err(Resp3Error::Io(io::Error::new(io::ErrorKind::TimedOut, "no reply to QUIT in time")));
+            }
+            self.writer.get_ref().set_timeout(Some(remaining))?;
+            let (value, length) = self.fill_until_frame()?;
+            #[cfg(feature="wire-debug")]
+            wire_debug::log_reply(&self.buffer[..length], false);
+            self.buffer.drain(..length);
+            match value {
+                Resp3Value::SimpleString(reply) if reply == "OK" => return Ok(()),
+                // e.g. a proxy not knowing QUIT, the stream is shut down all the same
+                Resp3Value::Error(_) => return Ok(()),
+                _ => {}
+            }
+        }
+    }
+
+    // Runs on every new stream, before any other command
+    fn set_up(&mut self) -> Result<(), Resp3Error> {
+        self.negotiate()?;
+        let flags = [(ClientFlag::NoEvict, self.options.no_evict), (ClientFlag::NoTouch, self.options.no_touch)];
+        for (flag, _) in flags.into_iter().filter(|(_, enabled)| *enabled) {
+            self.request(&ClientFlagCommand::new(flag, true).format_resp_bytes())?;
+        }
+        if let Some(expected) = self.options.verify_role {
+            self.verify_role(expected)?;
+        }
+        Ok(())
+    }
+
+    // Marks the endpoint unhealthy on a mismatch, so endpoint_health shows which one was demoted
+    fn verify_role(&mut self, expected: ServerRole) -> Result<(), Resp3Error> {
+        let actual = RoleCommand.parse_output(self.request(&RoleCommand.format_resp_bytes())?)?.server_role();
+        if actual == expected {
+            return Ok(());
+        }
+        let err = Resp3Error::WrongRole { endpoint: self.current_endpoint().to_string(), expected: expected.to_string(), actual: actual.to_string() };
+        let endpoint = &mut self.endpoints[self.current_endpoint];
+        endpoint.healthy = false;
+        endpoint.consecutive_failures += 1;
+        endpoint.last_error = Some(err.to_string());
+        Err(err)
+    }
+
+    // Sends HELLO 3 unless RESP2 was asked for. In Auto mode a failure, an error reply or the
+    // connection closed by a proxy like Twemproxy, leaves the connection in RESP2, on a new
+    // stream in the latter case
+    fn negotiate(&mut self) -> Result<(), Resp3Error> {
+        self.protocol = ProtocolVersion::Resp2;
+        if self.options.protocol == ProtocolVersion::Resp2 {
+            return Ok(());
+        }
+        // Not request_command, a desync would reconnect and negotiate again
+        let hello = HelloCommand::new(3).format_resp_bytes();
+        match self.request(&hello) {
+            Ok(_) => self.protocol = ProtocolVersion::Resp3,
+            Err(err) if self.options.protocol == ProtocolVersion::Auto => {
+                log::debug!("HELLO 3 failed on {}, using RESP2: {}", self.current_endpoint(), err);
+                if let Resp3Error::Io(_) = err {
+                    let (reader, writer, endpoint) = self.options.open(&mut self.endpoints)?;
+                    self.reader = reader;
+                    self.writer = writer;
+                    self.current_endpoint = endpoint;
+                    self.buffer.clear();
+                    self.scanner = FrameScanner::default();
+                    self.disconnect_cause = None;
+                }
+            }
+            Err(err) => return Err(err),
+        }
+        Ok(())
+    }
+
+    fn validate<C: Command + ?Sized>(&self, command: &C) -> Result<(), Resp3Error> {
+        if command.is_destructive() && !self.options.allow_destructive {
+            let name = command.get_parts().first().map(|name| name.to_uppercase()).unwrap_or_default();
+            return Err(Resp3Error::InvalidArgument(format!("{} is destructive, see RedisConnectionBuilder::allow_destructive", name)));
+        }
+        match &self.options.validation {
+            Some(validation) => validation.check(command),
+            None => Ok(()),
+        }
+    }
+
+    // Sends the command if the state allows it and waits for the reply, which is left in the buffer
+    fn exchange(&mut self, command: &[u8]) -> Result<(Resp3Value, usize), Resp3Error> {
+        let name = command_name(command).unwrap_or_default();
+        self.state.check(&name)?;
+
+        #[cfg(feature="otel")]
+        let mut span = otel::start_span(self.options.tracer.as_deref(), &name, self.current_endpoint());
+        let result = self.send_and_read(&name, command);
+        #[cfg(feature="otel")]
+        otel::end_span(&mut span, result.as_ref().map(|(value, _)| value));
+        result
+    }
+
+    fn send_and_read(&mut self, name: &str, command: &[u8]) -> Result<(Resp3Value, usize), Resp3Error> {
+
+        #[cfg(feature="wire-debug")]
+        let redacted = wire_debug::log_request(command);
+        self.write_stream(command)?;
+
+        let (value, length) = self.fill_until_frame()?;
+        #[cfg(feature="wire-debug")]
+        wire_debug::log_reply(&self.buffer[..length], redacted);
+        self.set_state(self.state.after_reply(name, &value));
+        Ok((value, length))
+    }
+
+    fn write_only(&mut self, command: &[u8]) -> Result<(), Resp3Error> {
+        #[cfg(feature="wire-debug")]
+        wire_debug::log_request(command);
+        self.write_stream(command)
+    }
+
+    fn write_stream(&mut self, command: &[u8]) -> Result<(), Resp3Error> {
+        let result = self.writer.write_all(command).and_then(|_| self.writer.flush());
+        if result.is_ok() {
+            self.bytes_written += command.len() as u64;
+        }
+        self.observe(result.map_err(Resp3Error::Io))
+    }
+
+    fn fill_until_frame(&mut self) -> Result<(Resp3Value, usize), Resp3Error> {
+        let result = self.read_frame();
+        self.observe(result)
+    }
+
+    fn read_frame(&mut self) -> Result<(Resp3Value, usize), Resp3Error> {
+        let (value, length) = self.next_frame()?;
+        Ok((value?, length))
+    }
+
+    // The outer error leaves the stream unusable. The inner one is for a frame whose bounds are
+    // known but whose content does not parse, e.g. a simple string that is not UTF-8, and which
+    // can be skipped with its length
+    fn next_frame(&mut self) -> Result<(Result<Resp3Value, Resp3Error>, usize), Resp3Error> {
+        loop {
+            // Parsed only once whole, so a reply read in many pieces is not parsed again for each
+            if self.scanner.is_complete(&self.buffer) {
+                let length = self.scanner.frame_length();
+                self.scanner = FrameScanner::default();
+                match (parse_frame_with_attributes(&self.buffer), length) {
+                    (Ok(Some((value, attributes, length))), _) => {
+                        self.attributes = attributes;
+                        return Ok((Ok(value), length));
+                    }
+                    (Ok(None), _) => {}
+                    (Err(err), Some(length)) => return Ok((Err(err), length)),
+                    (Err(err), None) => return Err(err),
+                }
+            }
+
+            let chunk = self.reader.fill_buf()?;
+            if chunk.is_empty() {
+                return Err(Resp3Error::Io(io::Error::new(io::ErrorKind::UnexpectedEof, "Connection closed by Redis server")));
+            }
+            let bytes_read = chunk.len();
+            self.buffer.extend_from_slice(chunk);
+            self.reader.consume(bytes_read);
+            self.bytes_read += bytes_read as u64;
+        }
+    }
+
+    // Sends Disconnected the first time the stream turns out to be closed
+    fn observe<T>(&mut self, result: Result<T, Resp3Error>) -> Result<T, Resp3Error> {
+        if let Err(Resp3Error::Io(err)) = &result {
+            if is_closed(err) && self.disconnect_cause.is_none() {
+                self.disconnect_cause = Some(err.to_string());
+                self.emit(ConnectionEventKind::Disconnected, Some(err.to_string()));
+            }
+        }
+        result
+    }
+
+    fn set_state(&mut self, state: ConnectionState) {
+        let subscribed = (self.state == ConnectionState::Subscribed, state == ConnectionState::Subscribed);
+        self.state = state;
+        match subscribed {
+            (false, true) => self.emit(ConnectionEventKind::Subscribed, None),
+            (true, false) => self.emit(ConnectionEventKind::Unsubscribed, None),
+            _ => {}
+        }
+    }
+
+    fn emit(&self, kind: ConnectionEventKind, cause: Option<String>) {
+        if let Some(listener) = &self.options.events {
+            listener.emit(kind, self.current_endpoint(), cause);
+        }
     }
 }
 
+// The protocol of the replies. Connections start in RESP2 and switch with HELLO 3, which
+// Redis 6+ knows but some proxies do not (e.g. Twemproxy, older Envoy filters)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProtocolVersion {
+    // No HELLO, replies are RESP2 and normalized by the commands
+    Resp2,
+    // Connecting fails when the server refuses HELLO 3
+    Resp3,
+    // RESP3 when the server takes HELLO 3, RESP2 otherwise
+    Auto,
+}
+
+// Which address family to try first when a hostname resolves to both
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IpPreference {
+    // In the order the resolver returns them
+    Any,
+    V4,
+    V6,
+}
+
+// Connection options, e.g. RedisConnection::builder("127.0.0.1:6379").read_buffer_size(64 * 1024).connect()
+#[derive(Debug, Clone)]
+pub struct RedisConnectionBuilder {
+    addresses: Vec<String>,
+    randomize: bool,
+    read_buffer_size: usize,
+    write_buffer_size: usize,
+    timeout: Option<Duration>,
+    ip_preference: IpPreference,
+    proxy: Option<Proxy>,
+    connector: Option<Arc<dyn Connector>>,
+    transport_wrapper: Option<TransportWrapper>,
+    events: Option<EventListener>,
+    encoding: EncodingMode,
+    protocol: ProtocolVersion,
+    no_evict: bool,
+    no_touch: bool,
+    validation: Option<Validation>,
+    allow_destructive: bool,
+    verify_role: Option<ServerRole>,
+    #[cfg(feature="tls")]
+    tls: Option<TlsOptions>,
+    #[cfg(feature="otel")]
+    tracer: Option<Arc<BoxedTracer>>,
+}
+
+impl RedisConnectionBuilder {
+    pub fn new(address: &str) -> Self {
+        RedisConnectionBuilder {
+            addresses: vec![address.to_string()],
+            randomize: false,
+            read_buffer_size: DEFAULT_BUFFER_SIZE,
+            write_buffer_size: DEFAULT_BUFFER_SIZE,
+            timeout: None,
+            ip_preference: IpPreference::Any,
+            proxy: None,
+            connector: None,
+            transport_wrapper: None,
+            events: None,
+            encoding: EncodingMode::Resp,
+            protocol: ProtocolVersion::Resp2,
+            no_evict: false,
+            no_touch: false,
+            validation: None,
+            allow_destructive: false,
+            verify_role: None,
+            #[cfg(feature="tls")]
+            tls: None,
+            #[cfg(feature="otel")]
+            tracer: None,
+        }
+    }
+
+    // A fallback tried when the previous endpoints cannot be reached, on connect and on every
+    // reconnect. The endpoints are not kept in sync, so this suits replicas promoted by hand
+    // or a proxy pair rather than replacing Sentinel
+    pub fn endpoint(mut self, address: &str) -> Self {
+        self.addresses.push(address.to_string());
+        self
+    }
+
+    // Tries the endpoints in a random order on every attempt instead of the order they were
+    // added, to spread the connections over them
+    pub fn randomize(mut self, randomize: bool) -> Self {
+        self.randomize = randomize;
+        self
+    }
+
+    // The hostnames are resolved again on every connect and reconnect, so a Redis behind a
+    // DNS name (e.g. a Kubernetes service) is found at its new IP after a failover. The other
+    // family is still tried when none of the preferred addresses accept
+    pub fn prefer_ip(mut self, preference: IpPreference) -> Self {
+        self.ip_preference = preference;
+        self
+    }
+
+    // Connects to every endpoint through the proxy, the timeout and IP preference then apply
+    // to the connection to the proxy
+    pub fn proxy(mut self, proxy: Proxy) -> Self {
+        self.proxy = Some(proxy);
+        self
+    }
+
+    // Encrypts every connection, the proxy handshake if any goes first in clear
+    #[cfg(feature="tls")]
+    pub fn tls(mut self, options: TlsOptions) -> Self {
+        self.tls = Some(options);
+        self
+    }
+
+    // The tracer of the command spans, in place of the global one, e.g.
+    // BoxedTracer::new(Box::new(provider.tracer("orders")))
+    #[cfg(feature="otel")]
+    pub fn tracer(mut self, tracer: BoxedTracer) -> Self {
+        self.tracer = Some(Arc::new(tracer));
+        self
+    }
+
+    // Opens the streams in place of TCP, e.g. UnixConnector, the endpoints being whatever
+    // addresses the connector understands
+    pub fn connector(mut self, connector: impl Connector + 'static) -> Self {
+        self.connector = Some(Arc::new(connector));
+        self
+    }
+
+    // Puts a layer between the connection and the stream, e.g. a ChaosTransport for
+    // resilience tests or one counting the bytes sent
+    pub fn transport_wrapper(mut self, wrapper: TransportWrapper) -> Self {
+        self.transport_wrapper = Some(wrapper);
+        self
+    }
+
+    // Receives the Connected, Disconnected, Reconnecting, Subscribed and Unsubscribed events of
+    // every connection opened with these options
+    pub fn events(mut self, listener: impl ConnectionEvents + 'static) -> Self {
+        self.events = Some(EventListener::new(listener));
+        self
+    }
+
+    // How request_command and send_no_reply write the commands, request and request_pipelined
+    // send their bytes as given. Inline commands cannot be longer than 64 KB on Redis
+    pub fn encoding(mut self, mode: EncodingMode) -> Self {
+        self.encoding = mode;
+        self
+    }
+
+    // Negotiated again on every reconnect, so a failover to a server that speaks RESP3 (or a
+    // proxy that does not) is picked up in Auto mode
+    pub fn protocol(mut self, version: ProtocolVersion) -> Self {
+        self.protocol = version;
+        self
+    }
+
+    // CLIENT NO-EVICT ON after connecting, so maxmemory-clients never drops the connection,
+    // e.g. for admin tools. Connecting fails on servers older than Redis 7.0
+    pub fn no_evict(mut self, enabled: bool) -> Self {
+        self.no_evict = enabled;
+        self
+    }
+
+    // CLIENT NO-TOUCH ON after connecting, so reads leave the LRU and LFU of the keys alone,
+    // e.g. for scans that should not change what gets evicted. Needs Redis 7.2
+    pub fn no_touch(mut self, enabled: bool) -> Self {
+        self.no_touch = enabled;
+        self
+    }
+
+    // Checks the commands of request_command and send_no_reply before sending them, the
+    // commands are sent as they are by default
+    pub fn validation(mut self, validation: Validation) -> Self {
+        self.validation = Some(validation);
+        self
+    }
+
+    // Lets the commands that wipe data wholesale through, e.g. FLUSHDB and FLUSHALL, which
+    // fail with Resp3Error::InvalidArgument before being sent otherwise
+    pub fn allow_destructive(mut self, allowed: bool) -> Self {
+        self.allow_destructive = allowed;
+        self
+    }
+
+    // Sends ROLE after connecting and on every reconnect, failing with Resp3Error::WrongRole when
+    // the server is of another role, e.g. a master demoted by a failover that a DNS name or the
+    // endpoints still point to, so writes never go to a stale master
+    pub fn verify_role(mut self, role: ServerRole) -> Self {
+        self.verify_role = Some(role);
+        self
+    }
+
+    // Bounds connecting and every read and write, by default they block for as long as it takes.
+    // Blocking commands (e.g. BLMPOP) need a longer timeout than the one they send. After a
+    // timeout the reply may still arrive later, so the connection should be dropped
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    // Larger buffers help with big replies (e.g. LRANGE over long lists), at the cost of memory per connection
+    pub fn read_buffer_size(mut self, size: usize) -> Self {
+        self.read_buffer_size = size;
+        self
+    }
+
+    // Commands larger than the buffer are written straight to the stream
+    pub fn write_buffer_size(mut self, size: usize) -> Self {
+        self.write_buffer_size = size;
+        self
+    }
+
+    // The same options for another server, e.g. the other nodes of a cluster
+    pub(crate) fn for_address(&self, address: &str) -> Self {
+        RedisConnectionBuilder { addresses: vec![address.to_string()], ..self.clone() }
+    }
+
+    pub(crate) fn address(&self) -> &str {
+        &self.addresses[0]
+    }
+
+    pub fn connect(self) -> Result<RedisConnection, Resp3Error> {
+        let mut endpoints: Vec<EndpointHealth> = self.addresses.iter().map(|address| EndpointHealth::new(address)).collect();
+        let (reader, writer, current_endpoint) = self.open(&mut endpoints)?;
+        let mut conn = RedisConnection {
+            reader,
+            writer,
+            buffer: Vec::new(),
+            scanner: FrameScanner::default(),
+            write_buffer: Vec::new(),
+            state: ConnectionState::Normal,
+            options: self,
+            endpoints,
+            current_endpoint,
+            bytes_written: 0,
+            bytes_read: 0,
+            closed: false,
+            disconnect_cause: None,
+            protocol: ProtocolVersion::Resp2,
+            supported_commands: HashMap::new(),
+            attributes: None,
+        };
+        conn.set_up()?;
+        conn.emit(ConnectionEventKind::Connected, None);
+        Ok(conn)
+    }
+
+    // Connects to the first endpoint that accepts, recording the outcome of every attempt,
+    // and returns the streams with the index of that endpoint
+    fn open(&self, endpoints: &mut [EndpointHealth]) -> Result<(Reader, Writer, usize), Resp3Error> {
+        let mut last_error = None;
+        for index in self.attempt_order() {
+            let endpoint = &mut endpoints[index];
+            match self.open_endpoint(&endpoint.address) {
+                Ok((reader, writer)) => {
+                    endpoint.healthy = true;
+                    endpoint.consecutive_failures = 0;
+                    endpoint.last_error = None;
+                    return Ok((reader, writer, index));
+                }
+                Err(err) => {
+                    endpoint.healthy = false;
+                    endpoint.consecutive_failures += 1;
+                    endpoint.last_error = Some(err.to_string());
+                    last_error = Some(err);
+                }
+            }
+        }
+        Err(last_error.unwrap_or_else(|| Resp3Error::InvalidState("No endpoint to connect to".to_string())))
+    }
+
+    fn attempt_order(&self) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..self.addresses.len()).collect();
+        if self.randomize {
+            // std has no random numbers, but every RandomState is seeded differently
+            let state = RandomState::new();
+            order.sort_by_cached_key(|index| state.hash_one(index));
+        }
+        order
+    }
+
+    fn open_endpoint(&self, address: &str) -> Result<(Reader, Writer), Resp3Error> {
+        let mut transport = match &self.connector {
+            Some(connector) => {
+                let transport = connector.connect(address, self.timeout)?;
+                transport.set_timeout(self.timeout)?;
+                transport
+            }
+            None => self.open_tcp(address)?,
+        };
+        if let Some(wrapper) = &self.transport_wrapper {
+            transport = wrapper.wrap(transport);
+        }
+        let reader = BufReader::with_capacity(self.read_buffer_size, transport.try_clone()?);
+        let writer = BufWriter::with_capacity(self.write_buffer_size, transport);
+        Ok((reader, writer))
+    }
+
+    fn open_tcp(&self, address: &str) -> Result<Box<dyn Transport>, Resp3Error> {
+        let stream = match &self.proxy {
+            Some(proxy) => {
+                let mut stream = connect_resolved(proxy.address(), self.ip_preference, self.timeout)?;
+                stream.set_read_timeout(self.timeout)?;
+                stream.set_write_timeout(self.timeout)?;
+                proxy.handshake(&mut stream, address)?;
+                stream
+            }
+            None => connect_resolved(address, self.ip_preference, self.timeout)?,
+        };
+        stream.set_read_timeout(self.timeout)?;
+        stream.set_write_timeout(self.timeout)?;
+        #[cfg(feature="tls")]
+        if let Some(tls) = &self.tls {
+            return Ok(tls.connect(address, stream)?);
+        }
+        Ok(Box::new(stream))
+    }
+}
+
+// Resolves the address, never cached, and tries each IP in turn
+fn connect_resolved(address: &str, preference: IpPreference, timeout: Option<Duration>) -> io::Result<TcpStream> {
+    let mut socket_addresses: Vec<SocketAddr> = address.to_socket_addrs()?.collect();
+    match preference {
+        IpPreference::Any => {}
+        // Stable, so the resolver's order is kept within each family
+        IpPreference::V4 => socket_addresses.sort_by_key(|socket_address| !socket_address.is_ipv4()),
+        IpPreference::V6 => socket_addresses.sort_by_key(|socket_address| !socket_address.is_ipv6()),
+    }
+
+    let mut last_error = io::Error::new(io::ErrorKind::InvalidInput, "Address resolved to nothing");
+    for socket_address in socket_addresses {
+        let stream = match timeout {
+            Some(timeout) => TcpStream::connect_timeout(&socket_address, timeout),
+            None => TcpStream::connect(socket_address),
+        };
+        match stream {
+            Ok(stream) => return Ok(stream),
+            Err(err) => last_error = err,
+        }
+    }
+    Err(last_error)
+}
+
+// The errors of a stream that is already closed, by the server or the network
+fn is_closed(err: &io::Error) -> bool {
+    matches!(
+        err.kind(),
+        io::ErrorKind::NotConnected | io::ErrorKind::BrokenPipe | io::ErrorKind::ConnectionReset | io::ErrorKind::ConnectionAborted | io::ErrorKind::UnexpectedEof
+    )
+}
+
 impl Drop for RedisConnection {
     fn drop(&mut self) {
-        println!("Dropping RedisConnection...");
-        self.close();
+        if let Err(err) = self.close() {
+            log::debug!("Could not close the connection to {} gracefully: {}", self.current_endpoint(), err);
+        }
     }
-}
\ No newline at end of file
+}
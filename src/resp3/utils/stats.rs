@@ -0,0 +1,238 @@
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::resp3::value::{Resp3Map, Resp3Value};
+
+// Counters of the commands an executor sent. Clones share the counters, so another thread
+// can read or reset them while the executor is in use
+#[derive(Debug, Clone, Default)]
+pub struct ExecutorStats {
+    inner: Arc<Mutex<StatsSnapshot>>,
+}
+
+impl ExecutorStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // A copy of the counters as they are now
+    pub fn snapshot(&self) -> StatsSnapshot {
+        self.inner.lock().unwrap().clone()
+    }
+
+    pub fn reset(&self) {
+        *self.inner.lock().unwrap() = StatsSnapshot::default();
+    }
+
+    // The error kind is the one of Resp3Error::kind, None when the command succeeded
+    pub(crate) fn record(&self, name: &str, latency: Duration, bytes_sent: u64, bytes_received: u64, error: Option<&str>) {
+        let mut stats = self.inner.lock().unwrap();
+        let command = stats.commands.entry(name.to_string()).or_default();
+        command.count += 1;
+        command.total_latency += latency;
+        stats.bytes_sent += bytes_sent;
+        stats.bytes_received += bytes_received;
+        if let Some(kind) = error {
+            stats.commands.get_mut(name).unwrap().errors += 1;
+            *stats.errors.entry(kind.to_string()).or_default() += 1;
+        }
+    }
+
+    // The sizes of the values a command wrote, see value_guard::value_indices
+    pub(crate) fn record_values(&self, sizes: impl IntoIterator<Item = usize>, oversized: bool) {
+        let mut stats = self.inner.lock().unwrap();
+        for size in sizes {
+            stats.value_sizes.record(size as u64);
+        }
+        stats.oversized_values += oversized as u64;
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StatsSnapshot {
+    // By command name, UPPERCASE
+    pub commands: BTreeMap<String, CommandStats>,
+    // By kind, e.g. "WRONGTYPE" or "IO"
+    pub errors: BTreeMap<String, u64>,
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+    // The sizes of the values written by SET, HSET, LPUSH and the like
+    pub value_sizes: SizeHistogram,
+    // The commands with a value over the limit of the ValueGuard, sent or not
+    pub oversized_values: u64,
+}
+
+impl StatsSnapshot {
+    pub fn total_commands(&self) -> u64 {
+        self.commands.values().map(|command| command.count).sum()
+    }
+
+    pub fn total_errors(&self) -> u64 {
+        self.errors.values().sum()
+    }
+
+    // Over every command, zero when none was sent
+    pub fn average_latency(&self) -> Duration {
+        let total: Duration = self.commands.values().map(|command| command.total_latency).sum();
+        average(total, self.total_commands())
+    }
+
+    pub fn command(&self, name: &str) -> Option<&CommandStats> {
+        self.commands.get(&name.to_uppercase())
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CommandStats {
+    pub count: u64,
+    pub errors: u64,
+    // From sending the command to having read its reply
+    pub total_latency: Duration,
+}
+
+impl CommandStats {
+    pub fn average_latency(&self) -> Duration {
+        average(self.total_latency, self.count)
+    }
+}
+
+// Counts of sizes in power of two buckets: 0, 1, 2-3, 4-7 and so on, precise enough to tell
+// kilobytes from megabytes in constant memory
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SizeHistogram {
+    // Bucket i holds the sizes below 2^i, from 2^(i-1) up
+    counts: Vec<u64>,
+    total: u64,
+    max: u64,
+}
+
+impl SizeHistogram {
+    pub fn record(&mut self, size: u64) {
+        let bucket = (u64::BITS - size.leading_zeros()) as usize;
+        if self.counts.len() <= bucket {
+            self.counts.resize(bucket + 1, 0);
+        }
+        self.counts[bucket] += 1;
+        self.total += size;
+        self.max = self.max.max(size);
+    }
+
+    pub fn count(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    pub fn total_bytes(&self) -> u64 {
+        self.total
+    }
+
+    pub fn max(&self) -> u64 {
+        self.max
+    }
+
+    // The non-empty buckets as (largest size of the bucket, count), smallest first
+    pub fn buckets(&self) -> Vec<(u64, u64)> {
+        self.counts.iter().enumerate()
+            .filter(|(_, count)| **count > 0)
+            .map(|(bucket, count)| (upper_bound(bucket), *count))
+            .collect()
+    }
+
+    // The largest size of the bucket the quantile falls in, e.g. 0.99 for the p99, so an upper
+    // bound at most twice the actual one. Zero when nothing was recorded
+    pub fn quantile(&self, quantile: f64) -> u64 {
+        let rank = (quantile.clamp(0.0, 1.0) * self.count() as f64).ceil().max(1.0) as u64;
+        let mut seen = 0;
+        for (bucket, count) in self.counts.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                return upper_bound(bucket).min(self.max);
+            }
+        }
+        0
+    }
+}
+
+fn upper_bound(bucket: usize) -> u64 {
+    match bucket {
+        0 => 0,
+        64 => u64::MAX,
+        bucket => (1 << bucket) - 1,
+    }
+}
+
+fn average(total: Duration, count: u64) -> Duration {
+    match u32::try_from(count) {
+        Ok(0) => Duration::ZERO,
+        Ok(count) => total / count,
+        Err(_) => Duration::from_secs_f64(total.as_secs_f64() / count as f64),
+    }
+}
+
+// The hints servers send as RESP3 attributes before some replies, gathered over the commands of
+// an executor. Clones share them, like ExecutorStats
+#[derive(Debug, Clone, Default)]
+pub struct ServerHints {
+    inner: Arc<Mutex<HintsSnapshot>>,
+}
+
+impl ServerHints {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn snapshot(&self) -> HintsSnapshot {
+        self.inner.lock().unwrap().clone()
+    }
+
+    pub fn reset(&self) {
+        *self.inner.lock().unwrap() = HintsSnapshot::default();
+    }
+
+    // The attributes sent before the reply of the command
+    pub(crate) fn record(&self, name: &str, attributes: &Resp3Map) {
+        let mut hints = self.inner.lock().unwrap();
+        *hints.commands.entry(name.to_string()).or_default() += 1;
+        for (attribute, value) in attributes.iter() {
+            let Some(attribute) = attribute.as_str() else { continue };
+            match (attribute, value) {
+                ("key-popularity", Resp3Value::Map(keys)) => {
+                    for (key, popularity) in keys.iter() {
+                        if let (Some(key), Some(popularity)) = (key.as_str(), popularity.as_f64()) {
+                            hints.key_popularity.insert(key.to_string(), popularity);
+                        }
+                    }
+                }
+                ("replication-offset", value) if value.as_i64().is_some() => {
+                    hints.replication_offset = hints.replication_offset.max(value.as_i64());
+                }
+                (attribute, value) => {
+                    hints.other.insert(attribute.to_string(), value.clone());
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HintsSnapshot {
+    // The last popularity reported for each key, from key-popularity attributes. Higher is hotter,
+    // the scale is the server's
+    pub key_popularity: BTreeMap<String, f64>,
+    // The highest offset of replication-offset attributes, e.g. to wait for a replica to catch up
+    pub replication_offset: Option<i64>,
+    // How many replies of each command came with attributes, by name UPPERCASE
+    pub commands: BTreeMap<String, u64>,
+    // The last value of every other attribute, by name
+    pub other: BTreeMap<String, Resp3Value>,
+}
+
+impl HintsSnapshot {
+    // The count most popular keys, hottest first
+    pub fn hot_keys(&self, count: usize) -> Vec<(&str, f64)> {
+        let mut keys: Vec<(&str, f64)> = self.key_popularity.iter().map(|(key, popularity)| (key.as_str(), *popularity)).collect();
+        keys.sort_by(|a, b| b.1.total_cmp(&a.1));
+        keys.truncate(count);
+        keys
+    }
+}
@@ -0,0 +1,117 @@
+use crate::resp3::error::{Resp3Error, ServerError};
+use crate::resp3::utils::command::Command;
+use crate::resp3::utils::connection_state::{command_name, ConnectionState};
+use crate::resp3::utils::redis_connection::RedisConnection;
+use crate::resp3::value::Resp3Value;
+
+const DEFAULT_WINDOW: usize = 1000;
+
+// A pipeline for bulk loads that never has more than the window of commands waiting for
+// their replies. Commands are written in batches of half the window, reading the replies
+// of the earlier ones when the window is full, so neither side buffers more than a window
+// however many commands go through. Only the error replies are kept, e.g.
+// let mut pipeline = StreamingPipeline::new(&mut conn).window(500);
+// for ... { pipeline.push(&SetCommand::new(key, value))?; }
+// let summary = pipeline.finish()?;
+pub struct StreamingPipeline<'a> {
+    conn: &'a mut RedisConnection,
+    window: usize,
+    // Encoded commands not written yet
+    buffer: Vec<u8>,
+    unsent: usize,
+    // Written, their replies not read yet
+    in_flight: usize,
+    summary: StreamingSummary,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StreamingSummary {
+    // Number of replies read, one per command
+    pub replies: u64,
+    // By index of the command in the order it was pushed, from 0
+    pub errors: Vec<(u64, ServerError)>,
+}
+
+impl<'a> StreamingPipeline<'a> {
+    pub fn new(conn: &'a mut RedisConnection) -> Self {
+        StreamingPipeline { conn, window: DEFAULT_WINDOW, buffer: Vec::new(), unsent: 0, in_flight: 0, summary: StreamingSummary::default() }
+    }
+
+    // The most commands sent and not answered yet, at least 1
+    pub fn window(mut self, window: usize) -> Self {
+        self.window = window.max(1);
+        self
+    }
+
+    // Commands written and waiting for their replies
+    pub fn in_flight(&self) -> usize {
+        self.in_flight
+    }
+
+    // Queues the command, writing the batch when it is full. Commands changing the state of
+    // the connection, e.g. MULTI or SUBSCRIBE, are rejected before anything is sent
+    pub fn push<C: Command + ?Sized>(&mut self, command: &C) -> Result<(), Resp3Error> {
+        let start = self.buffer.len();
+        command.encode_into(&mut self.buffer);
+        let name = command_name(&self.buffer[start..]).unwrap_or_default();
+        if let Err(err) = ConnectionState::Normal.check_no_reply(&name) {
+            self.buffer.truncate(start);
+            return Err(err);
+        }
+        self.unsent += 1;
+        if self.unsent >= self.batch_size() {
+            self.send_batch()?;
+        }
+        Ok(())
+    }
+
+    // Writes what is left and reads every remaining reply
+    pub fn finish(mut self) -> Result<StreamingSummary, Resp3Error> {
+        self.send_batch()?;
+        while self.in_flight > 0 {
+            self.read_reply()?;
+        }
+        Ok(std::mem::take(&mut self.summary))
+    }
+
+    fn batch_size(&self) -> usize {
+        self.window.div_ceil(2)
+    }
+
+    fn send_batch(&mut self) -> Result<(), Resp3Error> {
+        if self.unsent == 0 {
+            return Ok(());
+        }
+        while self.in_flight + self.unsent > self.window {
+            self.read_reply()?;
+        }
+        self.conn.write_pipelined(&self.buffer)?;
+        self.buffer.clear();
+        self.in_flight += std::mem::take(&mut self.unsent);
+        Ok(())
+    }
+
+    fn read_reply(&mut self) -> Result<(), Resp3Error> {
+        let reply = self.conn.read_pipelined()?;
+        self.in_flight -= 1;
+        if let Resp3Value::Error(message) = reply {
+            self.summary.errors.push((self.summary.replies, ServerError::parse(&message)));
+        }
+        self.summary.replies += 1;
+        Ok(())
+    }
+}
+
+// Dropped before finish, e.g. after an error, the commands not written are discarded and the
+// replies still to come are read, so they do not end up as the replies of later requests
+impl Drop for StreamingPipeline<'_> {
+    fn drop(&mut self) {
+        while self.in_flight > 0 {
+            if self.read_reply().is_err() {
+                // Best effort, a failed reconnect shows up on the next request
+                let _ = self.conn.reconnect();
+                return;
+            }
+        }
+    }
+}
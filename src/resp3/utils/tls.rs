@@ -0,0 +1,114 @@
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use native_tls::{Certificate, TlsConnector, TlsStream};
+
+use crate::resp3::utils::proxy::split_host_port;
+use crate::resp3::utils::transport::Transport;
+
+// TLS on top of the TCP stream (or of the proxy tunnel), e.g.
+// RedisConnection::builder("redis.example.com:6380").tls(TlsOptions::new()).connect()
+#[derive(Debug, Clone, Default)]
+pub struct TlsOptions {
+    ca_certificates: Vec<Vec<u8>>,
+    accept_invalid_certificates: bool,
+    domain: Option<String>,
+}
+
+impl TlsOptions {
+    // Verifies the server against the system roots and the host of the endpoint address
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Trusts one more CA, PEM encoded, e.g. the one of a self-managed Redis
+    pub fn ca_certificate(mut self, pem: &[u8]) -> Self {
+        self.ca_certificates.push(pem.to_vec());
+        self
+    }
+
+    // Skips the certificate and hostname checks, only for tests against self-signed servers
+    pub fn accept_invalid_certificates(mut self, accept: bool) -> Self {
+        self.accept_invalid_certificates = accept;
+        self
+    }
+
+    // The name to verify the certificate against, when the address is an IP or an alias
+    pub fn domain(mut self, domain: &str) -> Self {
+        self.domain = Some(domain.to_string());
+        self
+    }
+
+    pub(crate) fn connect(&self, address: &str, stream: TcpStream) -> io::Result<Box<dyn Transport>> {
+        let mut builder = TlsConnector::builder();
+        for pem in &self.ca_certificates {
+            builder.add_root_certificate(Certificate::from_pem(pem).map_err(tls_error)?);
+        }
+        builder.danger_accept_invalid_certs(self.accept_invalid_certificates);
+        builder.danger_accept_invalid_hostnames(self.accept_invalid_certificates);
+        let connector = builder.build().map_err(tls_error)?;
+
+        let domain = match &self.domain {
+            Some(domain) => domain.as_str(),
+            None => split_host_port(address)?.0,
+        };
+        let peer_addr = stream.peer_addr()?;
+        let stream = connector.connect(domain, stream).map_err(|err| match err {
+            native_tls::HandshakeError::Failure(err) => tls_error(err),
+            native_tls::HandshakeError::WouldBlock(_) => io::Error::new(io::ErrorKind::TimedOut, "TLS handshake timed out"),
+        })?;
+        Ok(Box::new(TlsTransport { stream: Arc::new(Mutex::new(stream)), peer_addr }))
+    }
+}
+
+fn tls_error(err: native_tls::Error) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("TLS error: {}", err))
+}
+
+// A TLS session cannot be split like a TcpStream, so both halves share it. The connection
+// never reads and writes at the same time, the lock is never contended
+struct TlsTransport {
+    stream: Arc<Mutex<TlsStream<TcpStream>>>,
+    peer_addr: SocketAddr,
+}
+
+impl Read for TlsTransport {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        self.stream.lock().unwrap().read(buffer)
+    }
+}
+
+impl Write for TlsTransport {
+    fn write(&mut self, buffer: &[u8]) -> io::Result<usize> {
+        self.stream.lock().unwrap().write(buffer)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.stream.lock().unwrap().flush()
+    }
+}
+
+impl Transport for TlsTransport {
+    fn try_clone(&self) -> io::Result<Box<dyn Transport>> {
+        Ok(Box::new(TlsTransport { stream: self.stream.clone(), peer_addr: self.peer_addr }))
+    }
+
+    // Sends close_notify before closing the socket
+    fn shutdown(&self) -> io::Result<()> {
+        let mut stream = self.stream.lock().unwrap();
+        let _ = stream.shutdown();
+        stream.get_ref().shutdown(std::net::Shutdown::Both)
+    }
+
+    fn peer_addr(&self) -> io::Result<SocketAddr> {
+        Ok(self.peer_addr)
+    }
+
+    fn set_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        let stream = self.stream.lock().unwrap();
+        stream.get_ref().set_read_timeout(timeout)?;
+        stream.get_ref().set_write_timeout(timeout)
+    }
+}
@@ -0,0 +1,110 @@
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::net::{Shutdown, SocketAddr, TcpStream};
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
+use std::sync::Arc;
+use std::time::Duration;
+
+// The byte stream a RedisConnection talks over. The connection reads and writes through two
+// handles of the same stream, so try_clone has to return one sharing it
+pub trait Transport: Read + Write + Send {
+    fn try_clone(&self) -> io::Result<Box<dyn Transport>>;
+
+    fn shutdown(&self) -> io::Result<()>;
+
+    fn peer_addr(&self) -> io::Result<SocketAddr>;
+
+    // Bounds the reads and the writes of both handles, None blocks for as long as it takes.
+    // Streams that cannot time out, e.g. in-memory pipes, keep this default ignoring it
+    fn set_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        let _ = timeout;
+        Ok(())
+    }
+}
+
+impl Transport for TcpStream {
+    fn try_clone(&self) -> io::Result<Box<dyn Transport>> {
+        Ok(Box::new(TcpStream::try_clone(self)?))
+    }
+
+    fn shutdown(&self) -> io::Result<()> {
+        TcpStream::shutdown(self, Shutdown::Both)
+    }
+
+    fn peer_addr(&self) -> io::Result<SocketAddr> {
+        TcpStream::peer_addr(self)
+    }
+
+    fn set_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        self.set_read_timeout(timeout)?;
+        self.set_write_timeout(timeout)
+    }
+}
+
+#[cfg(unix)]
+impl Transport for UnixStream {
+    fn try_clone(&self) -> io::Result<Box<dyn Transport>> {
+        Ok(Box::new(UnixStream::try_clone(self)?))
+    }
+
+    fn shutdown(&self) -> io::Result<()> {
+        UnixStream::shutdown(self, Shutdown::Both)
+    }
+
+    // Unix sockets have a path, not an IP address
+    fn peer_addr(&self) -> io::Result<SocketAddr> {
+        Err(io::Error::new(io::ErrorKind::Unsupported, "Unix sockets have no IP address"))
+    }
+
+    fn set_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        self.set_read_timeout(timeout)?;
+        self.set_write_timeout(timeout)
+    }
+}
+
+// Opens the streams of a connection in place of TCP, e.g. a WebSocket tunnel or an in-memory
+// pipe for tests, with the address of the endpoint on connect and on every reconnect. Set with
+// RedisConnectionBuilder::connector, the proxy and TLS options only apply to TCP
+pub trait Connector: fmt::Debug + Send + Sync {
+    fn connect(&self, address: &str, timeout: Option<Duration>) -> io::Result<Box<dyn Transport>>;
+}
+
+// The endpoints are socket paths, e.g. RedisConnection::builder("/run/redis/redis.sock").connector(UnixConnector).
+// Connecting to a local socket does not time out, the reads and writes do
+#[cfg(unix)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UnixConnector;
+
+#[cfg(unix)]
+impl Connector for UnixConnector {
+    fn connect(&self, address: &str, _timeout: Option<Duration>) -> io::Result<Box<dyn Transport>> {
+        Ok(Box::new(UnixStream::connect(address)?))
+    }
+}
+
+type WrapFn = dyn Fn(Box<dyn Transport>) -> Box<dyn Transport> + Send + Sync;
+
+// Wraps every stream the connection opens, on connect and on reconnect, once the proxy
+// handshake is done
+#[derive(Clone)]
+pub struct TransportWrapper(Arc<WrapFn>);
+
+impl TransportWrapper {
+    pub fn new<F>(wrap: F) -> Self
+    where
+        F: Fn(Box<dyn Transport>) -> Box<dyn Transport> + Send + Sync + 'static,
+    {
+        TransportWrapper(Arc::new(wrap))
+    }
+
+    pub(crate) fn wrap(&self, transport: Box<dyn Transport>) -> Box<dyn Transport> {
+        (self.0)(transport)
+    }
+}
+
+impl fmt::Debug for TransportWrapper {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("TransportWrapper")
+    }
+}
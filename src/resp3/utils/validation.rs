@@ -0,0 +1,143 @@
+use std::str;
+
+use crate::resp3::error::Resp3Error;
+use crate::resp3::utils::command::Command;
+
+// The proto-max-bulk-len default of Redis, longer arguments are refused with a protocol error
+pub const DEFAULT_MAX_BULK_SIZE: usize = 512 * 1024 * 1024;
+
+// Which arguments must be valid UTF-8, Redis itself takes any bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Utf8Policy {
+    #[default]
+    Any,
+    Keys,
+    All,
+}
+
+// Checks run on the commands before they are sent, so mistakes come back as descriptive
+// Resp3Error::InvalidArgument instead of server errors, or silent surprises like an empty key.
+// Enabled with RedisConnectionBuilder::validation, e.g. Validation::new().utf8(Utf8Policy::Keys)
+#[derive(Debug, Clone)]
+pub struct Validation {
+    reject_empty_keys: bool,
+    max_bulk_size: usize,
+    check_expirations: bool,
+    utf8: Utf8Policy,
+}
+
+impl Validation {
+    // Every check on, with the limits of a default Redis configuration
+    pub fn new() -> Self {
+        Validation { reject_empty_keys: true, max_bulk_size: DEFAULT_MAX_BULK_SIZE, check_expirations: true, utf8: Utf8Policy::Any }
+    }
+
+    // Redis takes "" as a key, it is rarely meant
+    pub fn reject_empty_keys(mut self, reject: bool) -> Self {
+        self.reject_empty_keys = reject;
+        self
+    }
+
+    // Should match the proto-max-bulk-len of the server
+    pub fn max_bulk_size(mut self, size: usize) -> Self {
+        self.max_bulk_size = size;
+        self
+    }
+
+    // The expiration arguments of EXPIRE, PEXPIRE, EXPIREAT, PEXPIREAT, SETEX, PSETEX, and
+    // of the EX, PX, EXAT and PXAT options of SET and GETEX
+    pub fn check_expirations(mut self, check: bool) -> Self {
+        self.check_expirations = check;
+        self
+    }
+
+    pub fn utf8(mut self, policy: Utf8Policy) -> Self {
+        self.utf8 = policy;
+        self
+    }
+
+    pub fn check<C: Command + ?Sized>(&self, command: &C) -> Result<(), Resp3Error> {
+        let parts = command.get_binary_parts();
+        let name = parts.first().map(|name| String::from_utf8_lossy(name).to_uppercase()).unwrap_or_default();
+        let invalid = |message: String| Err(Resp3Error::InvalidArgument(format!("{} {}", name, message)));
+        let keys = command.key_indices();
+
+        for (index, part) in parts.iter().enumerate() {
+            if part.len() > self.max_bulk_size {
+                return invalid(format!("argument {} is {} bytes long, over the limit of {}", index, part.len(), self.max_bulk_size));
+            }
+            let is_key = keys.contains(&index);
+            if is_key && part.is_empty() && self.reject_empty_keys {
+                return invalid(format!("key at argument {} is empty", index));
+            }
+            let must_be_text = match self.utf8 {
+                Utf8Policy::Any => false,
+                Utf8Policy::Keys => is_key,
+                Utf8Policy::All => true,
+            };
+            if must_be_text && str::from_utf8(part).is_err() {
+                return invalid(format!("{} at argument {} is not valid UTF-8", if is_key { "key" } else { "value" }, index));
+            }
+        }
+
+        if self.check_expirations {
+            if let Err(message) = check_expiration(&name, &parts) {
+                return invalid(message);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for Validation {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Unit {
+    Seconds,
+    Milliseconds,
+}
+
+// Redis keeps expirations as milliseconds in an i64, and SET, SETEX and GETEX refuse the ones
+// that are not positive. EXPIRE and the like take negative ones, which delete the key
+fn check_expiration(name: &str, parts: &[&[u8]]) -> Result<(), String> {
+    match name {
+        "EXPIRE" | "EXPIREAT" => expiration(parts.get(2), "the expiration", Unit::Seconds, false),
+        "PEXPIRE" | "PEXPIREAT" => expiration(parts.get(2), "the expiration", Unit::Milliseconds, false),
+        "SETEX" => expiration(parts.get(2), "the expiration", Unit::Seconds, true),
+        "PSETEX" => expiration(parts.get(2), "the expiration", Unit::Milliseconds, true),
+        // The options come after the value of SET, which may well be "EX"
+        "SET" => expiration_options(parts.get(3..).unwrap_or_default()),
+        "GETEX" => expiration_options(parts.get(2..).unwrap_or_default()),
+        _ => Ok(()),
+    }
+}
+
+fn expiration_options(options: &[&[u8]]) -> Result<(), String> {
+    for (index, option) in options.iter().enumerate() {
+        let option = String::from_utf8_lossy(option).to_uppercase();
+        let unit = match option.as_str() {
+            "EX" | "EXAT" => Unit::Seconds,
+            "PX" | "PXAT" => Unit::Milliseconds,
+            _ => continue,
+        };
+        expiration(options.get(index + 1), &option, unit, true)?;
+    }
+    Ok(())
+}
+
+fn expiration(argument: Option<&&[u8]>, what: &str, unit: Unit, positive: bool) -> Result<(), String> {
+    let Some(argument) = argument else { return Err(format!("is missing {}", what)) };
+    let text = String::from_utf8_lossy(argument);
+    let Ok(value) = text.parse::<i64>() else { return Err(format!("needs an integer for {}, got '{}'", what, text)) };
+    if positive && value <= 0 {
+        return Err(format!("needs a positive {}, got {}", what, value));
+    }
+    if matches!(unit, Unit::Seconds) && value.checked_mul(1000).is_none() {
+        return Err(format!("has {} {} out of range, it must fit in milliseconds", what, value));
+    }
+    Ok(())
+}
@@ -0,0 +1,66 @@
+use crate::resp3::error::Resp3Error;
+use crate::resp3::utils::command::Command;
+
+// What happens to a value over the limit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OversizeAction {
+    // Logged at warn level and sent all the same
+    Warn,
+    // Fails with Resp3Error::InvalidArgument before being sent
+    Reject,
+}
+
+// A limit on the size of the values written, e.g. ValueGuard::reject(1024 * 1024), set with
+// CommandExecutor::with_value_guard. Multi-megabyte values block Redis while it copies them and
+// every client queued behind, so they are better caught in the client than in the latency graphs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ValueGuard {
+    max_size: usize,
+    action: OversizeAction,
+}
+
+impl ValueGuard {
+    pub fn warn(max_size: usize) -> Self {
+        ValueGuard { max_size, action: OversizeAction::Warn }
+    }
+
+    pub fn reject(max_size: usize) -> Self {
+        ValueGuard { max_size, action: OversizeAction::Reject }
+    }
+
+    pub fn max_size(&self) -> usize {
+        self.max_size
+    }
+
+    pub fn action(&self) -> OversizeAction {
+        self.action
+    }
+
+    // Returns whether a value was over the limit, the error only when rejecting
+    pub fn check<C: Command + ?Sized>(&self, command: &C) -> Result<bool, Resp3Error> {
+        let parts = command.get_binary_parts();
+        let name = parts.first().map(|name| String::from_utf8_lossy(name).to_uppercase()).unwrap_or_default();
+        let Some((index, size)) = value_indices(&name, parts.len()).into_iter()
+            .map(|index| (index, parts[index].len()))
+            .find(|(_, size)| *size > self.max_size) else { return Ok(false) };
+        let message = format!("{} value at argument {} is {} bytes long, over the limit of {}", name, index, size, self.max_size);
+        match self.action {
+            OversizeAction::Warn => log::warn!("{}", message),
+            OversizeAction::Reject => return Err(Resp3Error::InvalidArgument(message)),
+        }
+        Ok(true)
+    }
+}
+
+// The indices of the values written by the commands storing them, the keys, fields, scores
+// and options excluded. Empty for the other commands
+pub fn value_indices(name: &str, parts: usize) -> Vec<usize> {
+    match name {
+        "SET" | "SETNX" | "GETSET" | "APPEND" => (2..parts.min(3)).collect(),
+        "SETEX" | "PSETEX" | "SETRANGE" | "HSETNX" | "LSET" => (3..parts.min(4)).collect(),
+        "MSET" | "MSETNX" => (2..parts).step_by(2).collect(),
+        "HSET" | "HMSET" => (3..parts).step_by(2).collect(),
+        "LPUSH" | "RPUSH" | "LPUSHX" | "RPUSHX" | "SADD" => (2..parts).collect(),
+        _ => Vec::new(),
+    }
+}
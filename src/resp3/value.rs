@@ -0,0 +1,390 @@
+// src/resp3/value.rs
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::resp3::error::Resp3Error;
+use crate::resp3::protocol::{ARRAY_PREFIX, BIG_NUMBER_PREFIX, BULK_STRING_PREFIX, CRLF, DOUBLE_PREFIX, ERROR_PREFIX, INTEGER_PREFIX, MAP_PREFIX, SIMPLE_STRING_PREFIX, VERBATIM_STRING_PREFIX};
+
+// A reply received from the Redis server
+#[derive(Debug, Clone, PartialEq)]
+pub enum Resp3Value {
+    SimpleString(String),
+    Error(String),
+    Integer(i64),
+    BulkString(Vec<u8>),
+    Array(Vec<Resp3Value>),
+    Null,
+    // RESP3 doubles, inf, -inf and nan included
+    Double(f64),
+    // RESP3 big numbers, kept as their digits (with a leading "-" when negative) so nothing is
+    // lost. The num-bigint feature decodes them into num_bigint::BigInt
+    BigNumber(String),
+    // RESP3 verbatim strings, e.g. the output of INFO or LOLWUT, with their format: "txt" for
+    // plain text, "mkd" for markdown
+    VerbatimString { format: String, text: String },
+    // RESP3 maps, e.g. CONFIG GET or XINFO replies, in the order the server sent them
+    Map(Resp3Map),
+}
+
+impl Resp3Value {
+    pub fn is_null(&self) -> bool {
+        matches!(self, Resp3Value::Null)
+    }
+
+    // Simple strings, bulk strings holding valid UTF-8, and the text of verbatim strings
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Resp3Value::SimpleString(value) | Resp3Value::VerbatimString { text: value, .. } => Some(value),
+            Resp3Value::BulkString(value) => std::str::from_utf8(value).ok(),
+            _ => None,
+        }
+    }
+
+    // Integers, and strings holding an integer (e.g. numbers returned by Lua scripts or GETs).
+    // Big numbers too when they fit
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Resp3Value::Integer(value) => Some(*value),
+            Resp3Value::BigNumber(digits) => digits.parse().ok(),
+            _ => self.as_str().and_then(|value| value.parse().ok()),
+        }
+    }
+
+    // Doubles, integers, and strings holding a number (e.g. INCRBYFLOAT over RESP2)
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Resp3Value::Double(value) => Some(*value),
+            Resp3Value::Integer(value) => Some(*value as f64),
+            Resp3Value::BigNumber(digits) => digits.parse().ok(),
+            _ => self.as_str().and_then(|value| value.parse().ok()),
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Resp3Value]> {
+        match self {
+            Resp3Value::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+
+    pub fn as_map(&self) -> Option<&Resp3Map> {
+        match self {
+            Resp3Value::Map(map) => Some(map),
+            _ => None,
+        }
+    }
+
+    // The RESP2 encoding of the reply, as a server would send it. Null goes as the null bulk
+    // string, doubles, big numbers, verbatim strings and maps, which RESP2 lacks, in their RESP3 form
+    pub fn encode_into(&self, output: &mut Vec<u8>) {
+        match self {
+            Resp3Value::SimpleString(string) => output.extend_from_slice(format!("{}{}{}", SIMPLE_STRING_PREFIX, string, CRLF).as_bytes()),
+            Resp3Value::Error(message) => output.extend_from_slice(format!("{}{}{}", ERROR_PREFIX, message, CRLF).as_bytes()),
+            Resp3Value::Integer(integer) => output.extend_from_slice(format!("{}{}{}", INTEGER_PREFIX, integer, CRLF).as_bytes()),
+            Resp3Value::BulkString(bytes) => {
+                output.extend_from_slice(format!("{}{}{}", BULK_STRING_PREFIX, bytes.len(), CRLF).as_bytes());
+                output.extend_from_slice(bytes);
+                output.extend_from_slice(CRLF.as_bytes());
+            }
+            Resp3Value::Array(values) => {
+                output.extend_from_slice(format!("{}{}{}", ARRAY_PREFIX, values.len(), CRLF).as_bytes());
+                for value in values {
+                    value.encode_into(output);
+                }
+            }
+            Resp3Value::Null => output.extend_from_slice(format!("{}-1{}", BULK_STRING_PREFIX, CRLF).as_bytes()),
+            Resp3Value::Double(double) => output.extend_from_slice(format!("{}{}{}", DOUBLE_PREFIX, format_double(*double), CRLF).as_bytes()),
+            Resp3Value::BigNumber(digits) => output.extend_from_slice(format!("{}{}{}", BIG_NUMBER_PREFIX, digits, CRLF).as_bytes()),
+            Resp3Value::VerbatimString { format, text } => {
+                output.extend_from_slice(format!("{}{}{}{}:{}{}", VERBATIM_STRING_PREFIX, format.len() + 1 + text.len(), CRLF, format, text, CRLF).as_bytes());
+            }
+            Resp3Value::Map(map) => {
+                output.extend_from_slice(format!("{}{}{}", MAP_PREFIX, map.len(), CRLF).as_bytes());
+                for (key, value) in map.iter() {
+                    key.encode_into(output);
+                    value.encode_into(output);
+                }
+            }
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut output = Vec::new();
+        self.encode_into(&mut output);
+        output
+    }
+}
+
+// The entries of a map reply, in their order. Keys are usually strings, the getters look them
+// up by their text, e.g. map.get_i64("proto")
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Resp3Map {
+    entries: Vec<(Resp3Value, Resp3Value)>,
+}
+
+impl Resp3Map {
+    pub fn new() -> Self {
+        Resp3Map::default()
+    }
+
+    // Appends the entry, maps are small enough that keys are not checked for duplicates
+    pub fn insert(&mut self, key: Resp3Value, value: Resp3Value) {
+        self.entries.push((key, value));
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&Resp3Value, &Resp3Value)> {
+        self.entries.iter().map(|(key, value)| (key, value))
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &Resp3Value> {
+        self.entries.iter().map(|(key, _)| key)
+    }
+
+    // The value of the first entry whose key has this text
+    pub fn get(&self, key: &str) -> Option<&Resp3Value> {
+        self.entries.iter().find(|(entry, _)| entry.as_str() == Some(key)).map(|(_, value)| value)
+    }
+
+    pub fn get_str(&self, key: &str) -> Option<&str> {
+        self.get(key).and_then(Resp3Value::as_str)
+    }
+
+    pub fn get_i64(&self, key: &str) -> Option<i64> {
+        self.get(key).and_then(Resp3Value::as_i64)
+    }
+
+    pub fn get_f64(&self, key: &str) -> Option<f64> {
+        self.get(key).and_then(Resp3Value::as_f64)
+    }
+
+    // Decodes the value into any type with a conversion, e.g. map.get_as::<Vec<String>>("flags")
+    pub fn get_as<T: FromResp3Value>(&self, key: &str) -> Result<Option<T>, Resp3Error> {
+        self.get(key).cloned().map(T::from_resp3_value).transpose()
+    }
+}
+
+impl IntoIterator for Resp3Map {
+    type Item = (Resp3Value, Resp3Value);
+    type IntoIter = std::vec::IntoIter<(Resp3Value, Resp3Value)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+impl FromIterator<(Resp3Value, Resp3Value)> for Resp3Map {
+    fn from_iter<I: IntoIterator<Item = (Resp3Value, Resp3Value)>>(entries: I) -> Self {
+        Resp3Map { entries: entries.into_iter().collect() }
+    }
+}
+
+// A double the way RESP3 writes it, the shortest digits that read back the same value
+pub(crate) fn format_double(double: f64) -> String {
+    match double {
+        _ if double.is_nan() => "nan".to_string(),
+        f64::INFINITY => "inf".to_string(),
+        f64::NEG_INFINITY => "-inf".to_string(),
+        _ => double.to_string(),
+    }
+}
+
+// Conversion of a reply into a Rust type, implemented by the typed results of the commands
+pub trait FromResp3Value: Sized {
+    fn from_resp3_value(value: Resp3Value) -> Result<Self, Resp3Error>;
+}
+
+pub(crate) fn unexpected<T>(expected: &str, value: &Resp3Value) -> Result<T, Resp3Error> {
+    Err(Resp3Error::UnexpectedReply(format!("Expected {}, got {:?}", expected, value)))
+}
+
+// The map a RESP3 server sends in place of the flat array of keys and values of RESP2
+pub(crate) fn flat_to_map(value: Resp3Value) -> Resp3Value {
+    match value {
+        Resp3Value::Array(values) if values.len() % 2 == 0 => {
+            let mut values = values.into_iter();
+            let mut map = Resp3Map::new();
+            while let (Some(key), Some(value)) = (values.next(), values.next()) {
+                map.insert(key, value);
+            }
+            Resp3Value::Map(map)
+        }
+        value => value,
+    }
+}
+
+// Decodes a map, or a flat array alternating keys and values as maps are sent over RESP2
+pub(crate) fn pairs<K: FromResp3Value, V: FromResp3Value>(value: Resp3Value) -> Result<Vec<(K, V)>, Resp3Error> {
+    let values = match value {
+        Resp3Value::Map(map) => {
+            return map.into_iter().map(|(key, value)| Ok((K::from_resp3_value(key)?, V::from_resp3_value(value)?))).collect();
+        }
+        Resp3Value::Array(values) if values.len() % 2 == 0 => values,
+        value => return unexpected("an array of key and value pairs", &value),
+    };
+    let mut values = values.into_iter();
+    let mut pairs = Vec::new();
+    while let (Some(key), Some(value)) = (values.next(), values.next()) {
+        pairs.push((K::from_resp3_value(key)?, V::from_resp3_value(value)?));
+    }
+    Ok(pairs)
+}
+
+// Decodes the pairs RESP3 sends as an array of [key, value] arrays and RESP2 as a flat array
+// alternating them, e.g. the members and scores of ZRANGE WITHSCORES
+pub(crate) fn nested_pairs<K: FromResp3Value, V: FromResp3Value>(value: Resp3Value) -> Result<Vec<(K, V)>, Resp3Error> {
+    match value.as_array() {
+        Some([Resp3Value::Array(_), ..]) => Vec::from_resp3_value(value),
+        _ => pairs(value),
+    }
+}
+
+impl FromResp3Value for Resp3Value {
+    fn from_resp3_value(value: Resp3Value) -> Result<Self, Resp3Error> {
+        Ok(value)
+    }
+}
+
+impl FromResp3Value for i64 {
+    fn from_resp3_value(value: Resp3Value) -> Result<Self, Resp3Error> {
+        match value.as_i64() {
+            Some(integer) => Ok(integer),
+            None => unexpected("an integer", &value),
+        }
+    }
+}
+
+impl FromResp3Value for u64 {
+    fn from_resp3_value(value: Resp3Value) -> Result<Self, Resp3Error> {
+        match value.as_i64().and_then(|integer| u64::try_from(integer).ok()) {
+            Some(integer) => Ok(integer),
+            None => unexpected("a non-negative integer", &value),
+        }
+    }
+}
+
+impl FromResp3Value for f64 {
+    fn from_resp3_value(value: Resp3Value) -> Result<Self, Resp3Error> {
+        match value.as_f64() {
+            Some(number) => Ok(number),
+            None => unexpected("a number", &value),
+        }
+    }
+}
+
+// A Unix time in seconds, e.g. the reply of LASTSAVE. Commands replying in milliseconds convert it themselves
+impl FromResp3Value for SystemTime {
+    fn from_resp3_value(value: Resp3Value) -> Result<Self, Resp3Error> {
+        match value.as_i64().and_then(|seconds| u64::try_from(seconds).ok()) {
+            Some(seconds) => Ok(UNIX_EPOCH + Duration::from_secs(seconds)),
+            None => unexpected("a Unix time", &value),
+        }
+    }
+}
+
+// Integers and strings holding one are accepted as well, as RESP2 servers send big numbers
+#[cfg(feature="num-bigint")]
+impl FromResp3Value for num_bigint::BigInt {
+    fn from_resp3_value(value: Resp3Value) -> Result<Self, Resp3Error> {
+        let number = match &value {
+            Resp3Value::Integer(integer) => Some(num_bigint::BigInt::from(*integer)),
+            Resp3Value::BigNumber(digits) => digits.parse().ok(),
+            _ => value.as_str().and_then(|digits| digits.parse().ok()),
+        };
+        match number {
+            Some(number) => Ok(number),
+            None => unexpected("a big number", &value),
+        }
+    }
+}
+
+impl FromResp3Value for bool {
+    fn from_resp3_value(value: Resp3Value) -> Result<Self, Resp3Error> {
+        match &value {
+            Resp3Value::Integer(integer) => Ok(*integer != 0),
+            Resp3Value::SimpleString(status) if status == "OK" => Ok(true),
+            Resp3Value::Null => Ok(false),
+            _ => unexpected("a boolean", &value),
+        }
+    }
+}
+
+impl FromResp3Value for String {
+    fn from_resp3_value(value: Resp3Value) -> Result<Self, Resp3Error> {
+        match value {
+            Resp3Value::SimpleString(string) => Ok(string),
+            Resp3Value::BulkString(bytes) => String::from_utf8(bytes)
+                .map_err(|_| Resp3Error::UnexpectedReply("Expected a UTF-8 string".to_string())),
+            Resp3Value::Integer(integer) => Ok(integer.to_string()),
+            Resp3Value::BigNumber(digits) => Ok(digits),
+            Resp3Value::VerbatimString { text, .. } => Ok(text),
+            value => unexpected("a string", &value),
+        }
+    }
+}
+
+// Binary-safe counterpart of String, for payloads that may not be valid UTF-8
+impl FromResp3Value for Vec<u8> {
+    fn from_resp3_value(value: Resp3Value) -> Result<Self, Resp3Error> {
+        match value {
+            Resp3Value::BulkString(bytes) => Ok(bytes),
+            Resp3Value::SimpleString(string) | Resp3Value::VerbatimString { text: string, .. } => Ok(string.into_bytes()),
+            value => unexpected("a string", &value),
+        }
+    }
+}
+
+impl<T: FromResp3Value> FromResp3Value for Option<T> {
+    fn from_resp3_value(value: Resp3Value) -> Result<Self, Resp3Error> {
+        match value {
+            Resp3Value::Null => Ok(None),
+            value => T::from_resp3_value(value).map(Some),
+        }
+    }
+}
+
+impl<T: FromResp3Value> FromResp3Value for Vec<T> {
+    fn from_resp3_value(value: Resp3Value) -> Result<Self, Resp3Error> {
+        match value {
+            Resp3Value::Array(values) => values.into_iter().map(T::from_resp3_value).collect(),
+            Resp3Value::Null => Ok(Vec::new()),
+            value => unexpected("an array", &value),
+        }
+    }
+}
+
+impl<A: FromResp3Value, B: FromResp3Value> FromResp3Value for (A, B) {
+    fn from_resp3_value(value: Resp3Value) -> Result<Self, Resp3Error> {
+        match value {
+            Resp3Value::Array(values) if values.len() == 2 => {
+                let mut values = values.into_iter();
+                let first = A::from_resp3_value(values.next().unwrap())?;
+                let second = B::from_resp3_value(values.next().unwrap())?;
+                Ok((first, second))
+            }
+            value => unexpected("an array of two elements", &value),
+        }
+    }
+}
+
+// Keeps the order of the entries, which a HashMap loses
+impl FromResp3Value for Resp3Map {
+    fn from_resp3_value(value: Resp3Value) -> Result<Self, Resp3Error> {
+        Ok(pairs(value)?.into_iter().collect())
+    }
+}
+
+impl<K: FromResp3Value + Eq + Hash, V: FromResp3Value> FromResp3Value for HashMap<K, V> {
+    fn from_resp3_value(value: Resp3Value) -> Result<Self, Resp3Error> {
+        Ok(pairs(value)?.into_iter().collect())
+    }
+}
@@ -0,0 +1,131 @@
+// src/resp3/wire_debug.rs
+
+use std::sync::RwLock;
+
+use crate::resp3::parser::parse_frame;
+use crate::resp3::utils::command::{parse_inline, write_inline_part};
+use crate::resp3::value::Resp3Value;
+
+// The log target, e.g. RUST_LOG=resp3string::wire=debug with env_logger
+pub const TARGET: &str = "resp3string::wire";
+
+// Commands whose arguments always carry credentials
+const CREDENTIAL_COMMANDS: [&str; 2] = ["AUTH", "HELLO"];
+
+// Past this many bytes the frames are cut, values can be megabytes long
+const MAX_LOGGED_BYTES: usize = 1024;
+
+const REDACTED: &[u8] = b"<redacted>";
+
+static REDACTED_COMMANDS: RwLock<Vec<String>> = RwLock::new(Vec::new());
+static REDACTED_KEYS: RwLock<Vec<String>> = RwLock::new(Vec::new());
+
+// Hides the arguments of the command, and its reply, on top of AUTH and HELLO
+pub fn redact_command(name: &str) {
+    REDACTED_COMMANDS.write().unwrap().push(name.to_uppercase());
+}
+
+// Hides everything after the key in the commands using it, and their replies. A trailing "*"
+// matches by prefix, e.g. "session:*"
+pub fn redact_key(pattern: &str) {
+    REDACTED_KEYS.write().unwrap().push(pattern.to_uppercase());
+}
+
+// Sent frames are logged with ">>" and received ones with "<<", as hex next to the printable characters
+pub(crate) fn log_request(command: &[u8]) -> bool {
+    let (description, redacted) = describe_request(command);
+    log::debug!(target: TARGET, ">> {} bytes\n{}", command.len(), description);
+    redacted
+}
+
+pub(crate) fn log_reply(reply: &[u8], redacted: bool) {
+    log::debug!(target: TARGET, "<< {} bytes\n{}", reply.len(), describe_reply(reply, redacted));
+}
+
+// The dump of the command, with the sensitive arguments replaced, and whether any was
+pub fn describe_request(command: &[u8]) -> (String, bool) {
+    let inline = command.first() != Some(&b'*');
+    let parts: Vec<Vec<u8>> = if inline {
+        match parse_inline(command) {
+            Some(parts) => parts,
+            None => return (hex_dump(command), false),
+        }
+    } else {
+        match parse_frame(command) {
+            Ok(Some((Resp3Value::Array(parts), _))) => parts.into_iter().filter_map(|part| match part {
+                Resp3Value::BulkString(part) => Some(part),
+                _ => None,
+            }).collect(),
+            _ => return (hex_dump(command), false),
+        }
+    };
+
+    match redaction_start(&parts) {
+        Some(start) if inline => {
+            let mut redacted = Vec::new();
+            for (index, part) in parts.iter().enumerate() {
+                if index > 0 {
+                    redacted.push(b' ');
+                }
+                write_inline_part(&mut redacted, if index >= start { REDACTED } else { part.as_slice() });
+            }
+            redacted.extend_from_slice(b"\r\n");
+            (hex_dump(&redacted), true)
+        }
+        Some(start) => {
+            let mut redacted = format!("*{}\r\n", parts.len()).into_bytes();
+            for (index, part) in parts.iter().enumerate() {
+                let part = if index >= start { REDACTED } else { part.as_slice() };
+                redacted.extend_from_slice(format!("${}\r\n", part.len()).as_bytes());
+                redacted.extend_from_slice(part);
+                redacted.extend_from_slice(b"\r\n");
+            }
+            (hex_dump(&redacted), true)
+        }
+        None => (hex_dump(command), false),
+    }
+}
+
+pub fn describe_reply(reply: &[u8], redacted: bool) -> String {
+    if redacted {
+        String::from_utf8_lossy(REDACTED).to_string()
+    } else {
+        hex_dump(reply)
+    }
+}
+
+// Index of the first part to hide, the parts are compared UPPERCASE as they are sent
+fn redaction_start(parts: &[Vec<u8>]) -> Option<usize> {
+    let name = String::from_utf8_lossy(parts.first()?).to_uppercase();
+    if CREDENTIAL_COMMANDS.contains(&name.as_str()) || REDACTED_COMMANDS.read().unwrap().contains(&name) {
+        return Some(1);
+    }
+    // The rules after the user name may set passwords
+    if name == "ACL" && parts.get(1).is_some_and(|subcommand| subcommand.eq_ignore_ascii_case(b"SETUSER")) {
+        return Some(3);
+    }
+
+    let keys = REDACTED_KEYS.read().unwrap();
+    parts.iter().skip(1).position(|part| {
+        let part = String::from_utf8_lossy(part).to_uppercase();
+        keys.iter().any(|pattern| match pattern.strip_suffix('*') {
+            Some(prefix) => part.starts_with(prefix),
+            None => part == *pattern,
+        })
+    }).map(|position| position + 2)
+}
+
+// Lines of 16 bytes as hex, padded, then as characters with "." for the unprintable ones
+fn hex_dump(bytes: &[u8]) -> String {
+    let mut lines: Vec<String> = bytes[..bytes.len().min(MAX_LOGGED_BYTES)].chunks(16).map(|chunk| {
+        let hex: Vec<String> = chunk.iter().map(|byte| format!("{:02x}", byte)).collect();
+        let printable: String = chunk.iter()
+            .map(|&byte| if byte.is_ascii_graphic() || byte == b' ' { byte as char } else { '.' })
+            .collect();
+        format!("{:<47}  {}", hex.join(" "), printable)
+    }).collect();
+    if bytes.len() > MAX_LOGGED_BYTES {
+        lines.push(format!("... {} more bytes", bytes.len() - MAX_LOGGED_BYTES));
+    }
+    lines.join("\n")
+}
@@ -26,3 +26,54 @@ response = get_command.execute(conn)
 
 # Print the response from Redis
 print(f"Response from Redis: {response}")
+
+# Failures are raised as resp3string exceptions, e.g. a timeout while the server blocks
+from resp3string import Resp3Error, TimeoutError
+
+# Connect with a timeout, in seconds, for connecting and for every read and write
+conn = PyRedisConnection("127.0.0.1:6379", timeout=0.5)
+
+try:
+    value = PyGetCommand("mykey").query(conn)
+    print(f"Value from Redis: {value}")
+    # None rather than the raw nil reply for a key that does not exist
+    missing = PyGetCommand("missingkey").query(conn)
+    print(f"Missing key is None: {missing is None}")
+except TimeoutError:
+    print("Redis did not answer in time")
+except Resp3Error as err:
+    print(f"Redis request failed: {err}")
+
+# Pipelines send their commands in one write, the replies come back as a list
+from resp3string import PyPipeline, PyTransaction, ResponseError, WatchError
+
+replies = PyPipeline().set("counter", "1").execute_command("INCRBY", "counter", 41).get("counter").execute(conn)
+print(f"Pipeline replies: {replies}")
+
+# Error replies are raised, or returned in the list with raise_on_error=False
+replies = PyPipeline().execute_command("INCR", "mykey").get("counter").execute(conn, raise_on_error=False)
+print(f"Failed command: {replies[0]!r}")
+
+# Transactions run atomically, and fail with WatchError when a watched key changed meanwhile
+tx = PyTransaction()
+tx.watch(conn, "counter")
+current = int(PyGetCommand("counter").query(conn))
+try:
+    replies = tx.set("counter", str(current * 2)).get("counter").execute(conn)
+    print(f"Transaction replies: {replies}")
+except WatchError:
+    print("counter changed, retry the transaction")
+
+# asyncio applications await the commands on one connection shared by their tasks, the event
+# loop keeps running meanwhile. Needs the module built with the python-asyncio feature
+import asyncio
+from resp3string import AsyncPyRedisConnection
+
+async def main():
+    conn = await AsyncPyRedisConnection.connect("127.0.0.1:6379", timeout=0.5)
+    await conn.set("mykey", "myvalue")
+    values = await asyncio.gather(conn.get("mykey"), conn.execute("INCRBY", "counter", 1), conn.get("missingkey"))
+    print(f"Awaited values: {values}")
+    conn.close()
+
+asyncio.run(main())
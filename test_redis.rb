@@ -0,0 +1,37 @@
+# Build with `cargo build --release --features ruby` and copy the library as resp3string.so
+require_relative "resp3string"
+
+# Create a connection to Redis, with a timeout in seconds for connecting and every read and write
+conn = Resp3string::Connection.new("127.0.0.1:6379", 0.5)
+
+# Commands are sent with their arguments UPPERCASE like the Rust commands, the replies come back as Ruby objects
+puts "Response from Redis: #{conn.call("SET", "mykey", "myvalue")}"
+puts "Value from Redis: #{conn.call(:get, "mykey")}"
+
+# Failures are raised as Resp3string exceptions
+begin
+  conn.call("INCR", "mykey")
+rescue Resp3string::CommandError => err
+  puts "Redis refused the command: #{err.message}"
+rescue Resp3string::TimeoutError
+  puts "Redis did not answer in time"
+end
+
+# Pipelines send their commands in one write, the replies come back as an array
+replies = Resp3string::Pipeline.new.set("counter", "1").call("INCRBY", "counter", 41).get("counter").execute(conn)
+puts "Pipeline replies: #{replies}"
+
+# Error replies are raised, or returned in the array with raise_on_error false
+replies = Resp3string::Pipeline.new.call("INCR", "mykey").get("counter").execute(conn, false)
+puts "Failed command: #{replies[0].inspect}"
+
+# Subscribers use a connection of their own, next_message returns nil after the timeout
+subscriber = Resp3string::Subscriber.new("127.0.0.1:6379", 1.0)
+subscriber.subscribe("news")
+conn.call("PUBLISH", "news", "hello")
+# The channel and the payload arrive UPPERCASE, like every argument is sent
+kind, channel, payload = subscriber.next_message
+raise "The message did not arrive, got #{[kind, channel, payload].inspect}" unless [kind, channel, payload] == ["message", "NEWS", "HELLO"]
+puts "#{kind} on #{channel}: #{payload}"
+
+conn.close